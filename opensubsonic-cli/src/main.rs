@@ -31,6 +31,10 @@ struct Args {
     #[clap(long, env = "OPENSUBSONIC_PASSWORD")]
     password: Option<String>,
 
+    /// The api key to use instead of username and password
+    #[clap(long, env = "OPENSUBSONIC_API_KEY")]
+    api_key: Option<String>,
+
     /// The format to use
     #[clap(long, default_value = "json", env = "OPENSUBSONIC_FORMAT")]
     format: String,
@@ -121,7 +125,7 @@ enum Command {
 struct RequestContext {
     server: String,
     version: Version,
-    username: String,
+    username: Option<String>,
     authentication: Authentication,
     client: String,
     format: String,
@@ -153,11 +157,18 @@ impl RequestContext {
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let args = Args::parse();
 
+    let (username, authentication) = match args.api_key {
+        Some(api_key) => (None, Authentication::ApiKey(api_key)),
+        None => (
+            Some(args.username.unwrap_or_default()),
+            Authentication::Password(args.password.unwrap_or_default()),
+        ),
+    };
     let ctx = RequestContext {
         server: args.server,
         version: Version::V1_16_1,
-        username: args.username.unwrap_or_default(),
-        authentication: Authentication::Password(args.password.unwrap_or_default()),
+        username,
+        authentication,
         client: args.client,
         format: args.format,
     };
@@ -57,7 +57,8 @@ pub struct SetRating {
     /// A string which uniquely identifies the file (song) or folder (album/artist) to rate.
     pub id: String,
     /// The rating between 1 and 5 (inclusive), or 0 to remove the rating.
-    pub rating: UserRating,
+    /// This is not a [`UserRating`] because it can be 0.
+    pub rating: u32,
 }
 
 /// Registers the local playback of one or more media files.
@@ -186,18 +187,21 @@ mod tests {
     fn test_set_rating() {
         let request = SetRating {
             id: "1".to_string(),
-            rating: UserRating::new(5).unwrap(),
+            rating: 5,
         };
         let encoded = test_request_encode(&request);
         assert_eq!(encoded, "id=1&rating=5");
 
         let request = SetRating {
             id: "1".to_string(),
-            rating: UserRating::new(2).unwrap(),
+            rating: 2,
         };
         let encoded = test_request_encode(&request);
         assert_eq!(encoded, "id=1&rating=2");
 
+        let request = SetRating::from_query("id=1&rating=0").unwrap();
+        assert_eq!(request.rating, 0);
+
         let encoded = "id=1";
         SetRating::from_query(encoded).unwrap_err();
     }
@@ -11,7 +11,7 @@
 //!     };
 //!
 //!     let request = Request {
-//!         username: "admin".into(),
+//!         username: Some("admin".into()),
 //!         authentication: Authentication::Password("admin".into()),
 //!         version: Version::LATEST,
 //!         client: "Rust Example".into(),
@@ -37,7 +37,7 @@
 //!     let query = "u=admin&p=admin&v=1.16.1&c=Rust%20Example&f=json&id=123";
 //!     let request: Request<GetSong> = Request::from_query(query).unwrap();
 //!     let expected = Request {
-//!         username: "admin".into(),
+//!         username: Some("admin".into()),
 //!         authentication: Authentication::Password("admin".into()),
 //!         version: Version::LATEST,
 //!         client: "Rust Example".into(),
@@ -138,13 +138,19 @@ pub trait SubsonicRequest:
 #[derive(Debug, Clone, PartialEq)]
 pub enum Authentication {
     Password(String),
-    Token { token: String, salt: String },
+    Token {
+        token: String,
+        salt: String,
+    },
+    /// OpenSubsonic API key extension.
+    /// Requests using an api key must not include the username.
+    ApiKey(String),
 }
 
 #[derive(Debug, Clone, PartialEq, ToQuery, FromQuery)]
 pub struct Request<R: SubsonicRequest> {
     #[query(rename = "u")]
-    pub username: String,
+    pub username: Option<String>,
     #[query(flatten)]
     pub authentication: Authentication,
     #[query(rename = "v")]
@@ -170,6 +176,7 @@ impl ToQuery for Authentication {
                 builder.emit_key_value("t", token);
                 builder.emit_key_value("s", salt);
             }
+            Authentication::ApiKey(key) => builder.emit_key_value("apiKey", key),
         }
     }
 }
@@ -180,6 +187,7 @@ const _: () = {
         password: Option<String>,
         token: Option<String>,
         salt: Option<String>,
+        api_key: Option<String>,
     }
 
     impl QueryAccumulator for AuthenticationAccum {
@@ -203,6 +211,10 @@ const _: () = {
                     self.salt = Some(s.to_string());
                     Ok(crate::query::ConsumeStatus::Consumed)
                 }
+                ("apiKey", Some(k)) => {
+                    self.api_key = Some(k.to_string());
+                    Ok(crate::query::ConsumeStatus::Consumed)
+                }
                 (_, v) => Ok(crate::query::ConsumeStatus::Ignored(QueryPair {
                     key,
                     value: v,
@@ -219,7 +231,22 @@ const _: () = {
                 self.salt = None;
             }
 
-            if let (Some(token), Some(salt)) = (self.token, self.salt) {
+            if self.api_key.as_ref().map(|k| k.is_empty()).unwrap_or(false) {
+                self.api_key = None;
+            }
+
+            if let Some(api_key) = self.api_key {
+                if self.password.is_some() || self.token.is_some() {
+                    return Err(crate::query::QueryParseError::invalid_value(
+                        "apiKey",
+                        QueryValueParseError::new(
+                            query::QueryValueParseErrorKind::Other,
+                            "apiKey cannot be used with p/t/s",
+                        ),
+                    ));
+                }
+                Ok(Authentication::ApiKey(api_key))
+            } else if let (Some(token), Some(salt)) = (self.token, self.salt) {
                 Ok(Authentication::Token { token, salt })
             } else if let Some(password) = self.password {
                 Ok(Authentication::Password(password))
@@ -228,7 +255,7 @@ const _: () = {
                     "p/t/s",
                     QueryValueParseError::new(
                         query::QueryValueParseErrorKind::Missing,
-                        "one of p, t, s must be present",
+                        "one of p, t, s or apiKey must be present",
                     ),
                 ))
             }
@@ -263,7 +290,7 @@ mod tests {
     #[test]
     fn test_ping_request() {
         let req = Request {
-            username: "user".to_string(),
+            username: Some("user".to_string()),
             authentication: Authentication::Password("password".to_string()),
             version: Version::new(1, 16, 1),
             client: "test".to_string(),
@@ -283,4 +310,23 @@ mod tests {
             Authentication::Password("test12345".into())
         );
     }
+
+    #[test]
+    fn authentication_with_api_key() {
+        let query = "apiKey=key1234&v=1.16.1&c=app&f=json";
+        let ping = Request::<system::Ping>::from_query(query).unwrap();
+        assert_eq!(ping.username, None);
+        assert_eq!(
+            ping.authentication,
+            Authentication::ApiKey("key1234".into())
+        );
+        assert_eq!(test_request_encode(&ping), query);
+    }
+
+    #[test]
+    fn authentication_with_api_key_and_password() {
+        let query = "apiKey=key1234&p=test12345&v=1.16.1&c=app";
+        let ping = Request::<system::Ping>::from_query(query);
+        assert!(ping.is_err());
+    }
 }
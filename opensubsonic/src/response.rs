@@ -354,6 +354,12 @@ pub struct AlbumID3 {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub user_rating: Option<UserRating>,
     pub record_labels: Vec<RecordLabel>,
+    /// All the album artists.
+    #[serde(default)]
+    pub artists: Vec<ArtistID3>,
+    /// The album artists as a single string.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub display_artist: Option<String>,
 }
 
 #[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
@@ -483,6 +489,26 @@ pub struct Child {
     pub original_width: Option<u32>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub original_height: Option<u32>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub artists: Vec<ArtistID3>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub display_artist: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub album_artists: Vec<ArtistID3>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub display_album_artist: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub contributors: Vec<Contributor>,
+}
+
+/// An artist that contributed to a song in a role other than performing it.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Contributor {
+    pub role: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sub_role: Option<String>,
+    pub artist: ArtistID3,
 }
 
 #[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
@@ -552,6 +578,8 @@ pub struct Playlist {
     pub cover_art: Option<String>,
     #[serde(skip_serializing_if = "Vec::is_empty")]
     pub allowed_user: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub readonly: Option<bool>,
 }
 
 #[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
@@ -699,20 +727,23 @@ pub struct Bookmarks {
 #[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Bookmark {
-    pub position: u64,
+    pub position: Milliseconds,
     pub username: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub comment: Option<String>,
     pub created: DateTime,
     pub changed: DateTime,
-    pub entry: Vec<Child>,
+    pub entry: Child,
 }
 
 #[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct PlayQueue {
     /// ID of the currently playing song
-    pub current: Option<u64>, // TODO: u64?
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub current: Option<String>,
     /// Position of the currently playing track
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub position: Option<Milliseconds>,
     pub username: String,
     pub changed: DateTime,
@@ -938,6 +969,9 @@ pub enum ErrorCode {
     IncompatibleServer = 30,
     WrongUsernameOrPassword = 40,
     TokenAuthenticationNotSupported = 41,
+    ProvidedAuthenticationMechanismNotSupported = 42,
+    MultipleConflictingAuthenticationMechanisms = 43,
+    InvalidApiKey = 44,
     UserNotAuthorizedForTheGivenOperation = 50,
     TrialExpired = 60,
     DataNotFound = 70,
@@ -953,6 +987,9 @@ impl From<u32> for ErrorCode {
             30 => ErrorCode::IncompatibleServer,
             40 => ErrorCode::WrongUsernameOrPassword,
             41 => ErrorCode::TokenAuthenticationNotSupported,
+            42 => ErrorCode::ProvidedAuthenticationMechanismNotSupported,
+            43 => ErrorCode::MultipleConflictingAuthenticationMechanisms,
+            44 => ErrorCode::InvalidApiKey,
             50 => ErrorCode::UserNotAuthorizedForTheGivenOperation,
             60 => ErrorCode::TrialExpired,
             70 => ErrorCode::DataNotFound,
@@ -970,6 +1007,9 @@ impl From<ErrorCode> for u32 {
             ErrorCode::IncompatibleServer => 30,
             ErrorCode::WrongUsernameOrPassword => 40,
             ErrorCode::TokenAuthenticationNotSupported => 41,
+            ErrorCode::ProvidedAuthenticationMechanismNotSupported => 42,
+            ErrorCode::MultipleConflictingAuthenticationMechanisms => 43,
+            ErrorCode::InvalidApiKey => 44,
             ErrorCode::UserNotAuthorizedForTheGivenOperation => 50,
             ErrorCode::TrialExpired => 60,
             ErrorCode::DataNotFound => 70,
@@ -1030,8 +1070,8 @@ impl XmlSerialize for ResponseBody {
             ResponseBody::JukeboxPlaylist(_) => todo!(),
             ResponseBody::JukeboxControlResponse(_) => todo!(),
             ResponseBody::License(v) => XmlSerialize::serialize(v, xml),
-            ResponseBody::Users(_) => todo!(),
-            ResponseBody::User(_) => todo!(),
+            ResponseBody::Users(v) => XmlSerialize::serialize(v, xml),
+            ResponseBody::User(v) => XmlSerialize::serialize(v, xml),
             ResponseBody::ChatMessages(_) => todo!(),
             ResponseBody::AlbumList(v) => XmlSerialize::serialize(v, xml),
             ResponseBody::AlbumList2(v) => XmlSerialize::serialize(v, xml),
@@ -1041,16 +1081,16 @@ impl XmlSerialize for ResponseBody {
             ResponseBody::Podcasts(_) => todo!(),
             ResponseBody::NewestPodcasts(_) => todo!(),
             ResponseBody::InternetRadioStations(_) => todo!(),
-            ResponseBody::Bookmarks(_) => todo!(),
-            ResponseBody::PlayQueue(_) => todo!(),
+            ResponseBody::Bookmarks(v) => XmlSerialize::serialize(v, xml),
+            ResponseBody::PlayQueue(v) => XmlSerialize::serialize(v, xml),
             ResponseBody::Shares(_) => todo!(),
             ResponseBody::Starred(v) => XmlSerialize::serialize(v, xml),
             ResponseBody::Starred2(v) => XmlSerialize::serialize(v, xml),
             ResponseBody::AlbumInfo(v) => XmlSerialize::serialize(v, xml),
             ResponseBody::ArtistInfo(v) => XmlSerialize::serialize(v, xml),
             ResponseBody::ArtistInfo2(v) => XmlSerialize::serialize(v, xml),
-            ResponseBody::SimilarSongs(_) => todo!(),
-            ResponseBody::SimilarSongs2(_) => todo!(),
+            ResponseBody::SimilarSongs(v) => XmlSerialize::serialize(v, xml),
+            ResponseBody::SimilarSongs2(v) => XmlSerialize::serialize(v, xml),
             ResponseBody::TopSongs(v) => XmlSerialize::serialize(v, xml),
             ResponseBody::ScanStatus(_) => todo!(),
             ResponseBody::Error(v) => XmlSerialize::serialize(v, xml),
@@ -1235,6 +1275,13 @@ impl AlbumID3 {
         xml::attr_opt(xml, "year", &self.year);
         xml::attr_opt(xml, "genre", &self.genre);
         xml::attr_opt(xml, "userRating", &self.user_rating);
+        xml::attr_opt(xml, "displayArtist", &self.display_artist);
+    }
+
+    fn serialize_children(&self, xml: &mut xml::Xml) {
+        for artist in &self.artists {
+            artist.serialize_as(xml, "artists");
+        }
     }
 }
 
@@ -1242,7 +1289,13 @@ impl XmlSerialize for AlbumID3 {
     fn serialize(&self, xml: &mut xml::Xml) {
         xml::elem_begin_open(xml, "album");
         self.serialize_attributes(xml);
-        xml::elem_begin_close_end(xml);
+        if self.artists.is_empty() {
+            xml::elem_begin_close_end(xml);
+        } else {
+            xml::elem_begin_close(xml);
+            self.serialize_children(xml);
+            xml::elem_end(xml);
+        }
     }
 }
 
@@ -1251,6 +1304,7 @@ impl XmlSerialize for AlbumWithSongsID3 {
         xml::elem_begin_open(xml, "album");
         self.album.serialize_attributes(xml);
         xml::elem_begin_close(xml);
+        self.album.serialize_children(xml);
         for song in &self.song {
             song.serialize_as(xml, "song");
         }
@@ -1310,7 +1364,20 @@ impl Child {
         xml::attr_opt(xml, "bookmarkPosition", &self.bookmark_position);
         xml::attr_opt(xml, "originalWidth", &self.original_width);
         xml::attr_opt(xml, "originalHeight", &self.original_height);
-        xml::elem_begin_close_end(xml);
+        xml::attr_opt(xml, "displayArtist", &self.display_artist);
+        xml::attr_opt(xml, "displayAlbumArtist", &self.display_album_artist);
+        if self.artists.is_empty() && self.album_artists.is_empty() {
+            xml::elem_begin_close_end(xml);
+        } else {
+            xml::elem_begin_close(xml);
+            for artist in &self.artists {
+                artist.serialize_as(xml, "artists");
+            }
+            for artist in &self.album_artists {
+                artist.serialize_as(xml, "albumArtists");
+            }
+            xml::elem_end(xml);
+        }
     }
 }
 
@@ -1389,6 +1456,7 @@ impl Playlist {
         xml::attr(xml, "created", &self.created);
         xml::attr(xml, "changed", &self.changed);
         xml::attr_opt(xml, "coverArt", &self.cover_art);
+        xml::attr_opt(xml, "readonly", &self.readonly);
     }
 
     fn serialize_allowed_users(&self, xml: &mut xml::Xml) {
@@ -1610,6 +1678,70 @@ impl XmlSerialize for Starred2 {
     }
 }
 
+impl XmlSerialize for Users {
+    fn serialize(&self, xml: &mut xml::Xml) {
+        xml::elem_begin_open(xml, "users");
+        xml::elem_begin_close(xml);
+        for user in &self.user {
+            XmlSerialize::serialize(user, xml);
+        }
+        xml::elem_end(xml);
+    }
+}
+
+impl XmlSerialize for User {
+    fn serialize(&self, xml: &mut xml::Xml) {
+        xml::elem_begin_open(xml, "user");
+        xml::attr(xml, "username", &self.username);
+        xml::attr_opt(xml, "email", &self.email);
+        xml::attr(xml, "scrobblingEnabled", &self.scrobbling_enabled);
+        xml::attr_opt(xml, "maxBitRate", &self.max_bit_rate);
+        xml::attr(xml, "adminRole", &self.admin_role);
+        xml::attr(xml, "settingsRole", &self.settings_role);
+        xml::attr(xml, "downloadRole", &self.download_role);
+        xml::attr(xml, "uploadRole", &self.upload_role);
+        xml::attr(xml, "playlistRole", &self.playlist_role);
+        xml::attr(xml, "coverArtRole", &self.cover_art_role);
+        xml::attr(xml, "commentRole", &self.comment_role);
+        xml::attr(xml, "podcastRole", &self.podcast_role);
+        xml::attr(xml, "streamRole", &self.stream_role);
+        xml::attr(xml, "jukeboxRole", &self.jukebox_role);
+        xml::attr(xml, "shareRole", &self.share_role);
+        xml::attr(xml, "videoConversionRole", &self.video_conversion_role);
+        xml::attr_opt(xml, "avatarLastChanged", &self.avatar_last_changed);
+        xml::elem_begin_close(xml);
+        for folder in &self.folder {
+            xml::elem_begin_open(xml, "folder");
+            xml::elem_begin_close(xml);
+            xml::body_display(xml, folder);
+            xml::elem_end(xml);
+        }
+        xml::elem_end(xml);
+    }
+}
+
+impl XmlSerialize for SimilarSongs {
+    fn serialize(&self, xml: &mut xml::Xml) {
+        xml::elem_begin_open(xml, "similarSongs");
+        xml::elem_begin_close(xml);
+        for song in &self.song {
+            song.serialize_as(xml, "song");
+        }
+        xml::elem_end(xml);
+    }
+}
+
+impl XmlSerialize for SimilarSongs2 {
+    fn serialize(&self, xml: &mut xml::Xml) {
+        xml::elem_begin_open(xml, "similarSongs2");
+        xml::elem_begin_close(xml);
+        for song in &self.song {
+            song.serialize_as(xml, "song");
+        }
+        xml::elem_end(xml);
+    }
+}
+
 impl XmlSerialize for TopSongs {
     fn serialize(&self, xml: &mut xml::Xml) {
         xml::elem_begin_open(xml, "topSongs");
@@ -1621,6 +1753,47 @@ impl XmlSerialize for TopSongs {
     }
 }
 
+impl XmlSerialize for Bookmarks {
+    fn serialize(&self, xml: &mut xml::Xml) {
+        xml::elem_begin_open(xml, "bookmarks");
+        xml::elem_begin_close(xml);
+        for bookmark in &self.bookmark {
+            XmlSerialize::serialize(bookmark, xml);
+        }
+        xml::elem_end(xml);
+    }
+}
+
+impl XmlSerialize for Bookmark {
+    fn serialize(&self, xml: &mut xml::Xml) {
+        xml::elem_begin_open(xml, "bookmark");
+        xml::attr(xml, "position", &self.position);
+        xml::attr(xml, "username", &self.username);
+        xml::attr_opt(xml, "comment", &self.comment);
+        xml::attr(xml, "created", &self.created);
+        xml::attr(xml, "changed", &self.changed);
+        xml::elem_begin_close(xml);
+        self.entry.serialize_as(xml, "entry");
+        xml::elem_end(xml);
+    }
+}
+
+impl XmlSerialize for PlayQueue {
+    fn serialize(&self, xml: &mut xml::Xml) {
+        xml::elem_begin_open(xml, "playQueue");
+        xml::attr_opt(xml, "current", &self.current);
+        xml::attr_opt(xml, "position", &self.position);
+        xml::attr(xml, "username", &self.username);
+        xml::attr(xml, "changed", &self.changed);
+        xml::attr(xml, "changedBy", &self.changed_by);
+        xml::elem_begin_close(xml);
+        for entry in &self.entry {
+            entry.serialize_as(xml, "entry");
+        }
+        xml::elem_end(xml);
+    }
+}
+
 impl XmlSerialize for Error {
     fn serialize(&self, xml: &mut xml::Xml) {
         xml::elem_begin_open(xml, "error");
@@ -1666,4 +1839,59 @@ mod test {
             ]
         }));
     }
+
+    #[test]
+    fn test_xml_album_artists() {
+        insta::assert_snapshot!(xml::serialize(&AlbumID3 {
+            id: "al-1".to_string(),
+            name: "album".to_string(),
+            artist: Some("artist 1".to_string()),
+            artist_id: Some("ar-1".to_string()),
+            song_count: 3,
+            artists: vec![
+                ArtistID3 {
+                    id: "ar-1".to_string(),
+                    name: "artist 1".to_string(),
+                    ..Default::default()
+                },
+                ArtistID3 {
+                    id: "ar-2".to_string(),
+                    name: "artist 2".to_string(),
+                    ..Default::default()
+                }
+            ],
+            display_artist: Some("artist 1, artist 2".to_string()),
+            ..Default::default()
+        }));
+    }
+
+    #[test]
+    fn test_xml_similar_songs() {
+        let song = Child {
+            id: "tr-1".to_string(),
+            title: "track".to_string(),
+            album: Some("album".to_string()),
+            artist: Some("artist".to_string()),
+            ..Default::default()
+        };
+        insta::assert_snapshot!(xml::serialize(&SimilarSongs {
+            song: vec![song.clone()],
+        }));
+        insta::assert_snapshot!(xml::serialize(&SimilarSongs2 { song: vec![song] }));
+    }
+
+    #[test]
+    fn test_xml_user() {
+        insta::assert_snapshot!(xml::serialize(&User {
+            username: "user".to_string(),
+            email: Some("user@example.com".to_string()),
+            scrobbling_enabled: true,
+            admin_role: false,
+            download_role: true,
+            playlist_role: true,
+            stream_role: true,
+            folder: vec![1, 3],
+            ..Default::default()
+        }));
+    }
 }
@@ -57,15 +57,25 @@ enum Command {
     Track(TrackArgs),
     Playlist(PlaylistArgs),
     Favorite(FavoriteArgs),
+    Rating(RatingArgs),
+    Queue(QueueArgs),
+    Bookmark(BookmarkArgs),
     Scrobble(ScrobbleArgs),
+    Stats(StatsArgs),
+    Recommend(RecommendArgs),
+    Radio(RadioArgs),
     Sync(SyncArgs),
     Pin(PinArgs),
     Search(SearchArgs),
+    Watch(WatchArgs),
+    Library(LibraryArgs),
+    Webhook(WebhookArgs),
     Subscription(SubscriptionArgs),
     Metadata(MetadataArgs),
     Admin(AdminArgs),
     Import(ImportArgs),
     Server(ServerArgs),
+    Restore(RestoreArgs),
 }
 
 // Types used to generate json
@@ -74,11 +84,18 @@ struct User {
     id: String,
     username: String,
     avatar: Option<String>,
+    admin: bool,
+    roles: Vec<String>,
 }
 
 impl std::fmt::Display for User {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}\t{}", self.id, self.username)
+        let roles = if self.admin {
+            "admin".to_string()
+        } else {
+            self.roles.join(",")
+        };
+        write!(f, "{}\t{}\t{}", self.id, self.username, roles)
     }
 }
 
@@ -88,6 +105,79 @@ impl From<sonar_grpc::User> for User {
             id: value.user_id,
             username: value.username,
             avatar: value.avatar_id,
+            admin: value.admin,
+            roles: value.roles,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct UserSession {
+    id: String,
+    user: String,
+    device: Option<String>,
+    created_at: u64,
+    last_used_at: u64,
+    expires_at: Option<u64>,
+}
+
+impl std::fmt::Display for UserSession {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}\t{}\t{}",
+            self.id,
+            self.user,
+            self.device.as_deref().unwrap_or_default()
+        )
+    }
+}
+
+impl From<sonar_grpc::UserSession> for UserSession {
+    fn from(value: sonar_grpc::UserSession) -> Self {
+        Self {
+            id: value.session_id,
+            user: value.user_id,
+            device: value.device,
+            created_at: value.created_at.unwrap().seconds as u64,
+            last_used_at: value.last_used_at.unwrap().seconds as u64,
+            expires_at: value.expires_at.map(|t| t.seconds as u64),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct UserApiKey {
+    id: String,
+    user: String,
+    name: String,
+    scope: Option<String>,
+    created_at: u64,
+    last_used_at: Option<u64>,
+}
+
+impl std::fmt::Display for UserApiKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}\t{}\t{}\t{}",
+            self.id,
+            self.user,
+            self.name,
+            self.scope.as_deref().unwrap_or_default()
+        )
+    }
+}
+
+impl From<sonar_grpc::UserApiKey> for UserApiKey {
+    fn from(value: sonar_grpc::UserApiKey) -> Self {
+        Self {
+            id: value.api_key_id,
+            user: value.user_id,
+            name: value.name,
+            scope: value.scope,
+            created_at: value.created_at.unwrap().seconds as u64,
+            last_used_at: value.last_used_at.map(|t| t.seconds as u64),
         }
     }
 }
@@ -131,6 +221,7 @@ struct Album {
     duration: Option<u32>,
     listen_count: u32,
     artist: String,
+    artists: Vec<String>,
     coverart: Option<String>,
     genres: Genres,
     properties: Properties,
@@ -151,6 +242,7 @@ impl From<sonar_grpc::Album> for Album {
             duration: value.duration.map(|x| x.seconds as u32),
             listen_count: value.listen_count,
             artist: value.artist_id,
+            artists: value.artist_ids,
             coverart: value.coverart_id,
             genres: genres_from_pb(value.genres),
             properties: properties_from_pb(value.properties),
@@ -164,11 +256,27 @@ struct Track {
     name: String,
     album: String,
     artist: String,
+    artists: Vec<ArtistCredit>,
     duration: Option<u32>,
     listen_count: u32,
     properties: Properties,
 }
 
+#[derive(Debug, Serialize)]
+struct ArtistCredit {
+    artist: String,
+    role: String,
+}
+
+impl From<sonar_grpc::ArtistCredit> for ArtistCredit {
+    fn from(value: sonar_grpc::ArtistCredit) -> Self {
+        Self {
+            artist: value.artist_id,
+            role: value.role,
+        }
+    }
+}
+
 impl std::fmt::Display for Track {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{}\t{}", self.id, self.name)
@@ -182,6 +290,7 @@ impl From<sonar_grpc::Track> for Track {
             name: value.name,
             album: value.album_id,
             artist: value.artist_id,
+            artists: value.artists.into_iter().map(ArtistCredit::from).collect(),
             duration: value.duration.map(|x| x.seconds as u32),
             listen_count: value.listen_count,
             properties: properties_from_pb(value.properties),
@@ -232,6 +341,7 @@ struct Playlist {
     name: String,
     track_count: u32,
     duration: Option<u32>,
+    smart: bool,
     properties: Properties,
 }
 
@@ -248,11 +358,50 @@ impl From<sonar_grpc::Playlist> for Playlist {
             name: value.name,
             track_count: value.track_count,
             duration: value.duration.map(|x| x.seconds as u32),
+            smart: value.smart,
             properties: properties_from_pb(value.properties),
         }
     }
 }
 
+#[derive(Debug, Serialize)]
+struct SmartPlaylist {
+    id: String,
+    rules: Vec<String>,
+    match_any: bool,
+    sort: String,
+    descending: bool,
+    limit: Option<u32>,
+    refreshed_at: Option<u64>,
+}
+
+impl std::fmt::Display for SmartPlaylist {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}\t{}\t{}",
+            self.id,
+            self.sort,
+            self.rules.join(if self.match_any { " | " } else { " & " })
+        )
+    }
+}
+
+impl From<sonar_grpc::SmartPlaylist> for SmartPlaylist {
+    fn from(value: sonar_grpc::SmartPlaylist) -> Self {
+        let rules = value.rules.unwrap_or_default();
+        Self {
+            id: value.playlist_id,
+            rules: rules.rules,
+            match_any: rules.match_any,
+            sort: rules.sort,
+            descending: rules.descending,
+            limit: rules.limit,
+            refreshed_at: value.refreshed_at.map(|t| t.seconds as u64),
+        }
+    }
+}
+
 #[derive(Debug, Serialize)]
 struct Scrobble {
     id: String,
@@ -285,117 +434,565 @@ impl From<sonar_grpc::Scrobble> for Scrobble {
 }
 
 #[derive(Debug, Serialize)]
-enum SearchResult {
-    Artist(Artist),
-    Album(Album),
-    Track(Track),
-    Playlist(Playlist),
+struct ScrobbleImportEntry {
+    artist: String,
+    album: Option<String>,
+    title: String,
+    listen_at: u64,
 }
 
-impl std::fmt::Display for SearchResult {
+impl std::fmt::Display for ScrobbleImportEntry {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            SearchResult::Artist(artist) => write!(f, "{}\t{}", artist.id, artist.name),
-            SearchResult::Album(album) => write!(f, "{}\t{}", album.id, album.name),
-            SearchResult::Track(track) => write!(f, "{}\t{}", track.id, track.name),
-            SearchResult::Playlist(playlist) => write!(f, "{}\t{}", playlist.id, playlist.name),
+        write!(
+            f,
+            "{}\t{}\t{}\t{}",
+            self.listen_at,
+            self.artist,
+            self.album.as_deref().unwrap_or_default(),
+            self.title
+        )
+    }
+}
+
+impl From<sonar_grpc::ScrobbleImportEntry> for ScrobbleImportEntry {
+    fn from(value: sonar_grpc::ScrobbleImportEntry) -> Self {
+        Self {
+            artist: value.artist,
+            album: value.album,
+            title: value.title,
+            listen_at: value.listen_at.unwrap().seconds as u64,
         }
     }
 }
 
 #[derive(Debug, Serialize)]
-struct SearchResults(Vec<SearchResult>);
+struct StatsEntry {
+    item: String,
+    listen_count: u64,
+    listen_time: u64,
+}
 
-impl std::fmt::Display for SearchResults {
+impl From<sonar_grpc::StatsEntry> for StatsEntry {
+    fn from(value: sonar_grpc::StatsEntry) -> Self {
+        Self {
+            item: value.item,
+            listen_count: value.listen_count,
+            listen_time: value.listen_time.unwrap_or_default().seconds as u64,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct Stats {
+    listen_count: u64,
+    listen_time: u64,
+    top_artists: Vec<StatsEntry>,
+    top_albums: Vec<StatsEntry>,
+    top_tracks: Vec<StatsEntry>,
+    top_genres: Vec<StatsEntry>,
+    hour_histogram: Vec<u64>,
+    weekday_histogram: Vec<u64>,
+    current_streak: u32,
+    longest_streak: u32,
+}
+
+impl std::fmt::Display for Stats {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        for result in &self.0 {
-            writeln!(f, "{}", result)?;
+        const WEEKDAYS: [&str; 7] = ["mon", "tue", "wed", "thu", "fri", "sat", "sun"];
+
+        writeln!(f, "listens\t{}", self.listen_count)?;
+        writeln!(f, "listen time\t{}s", self.listen_time)?;
+        writeln!(f, "current streak\t{} days", self.current_streak)?;
+        writeln!(f, "longest streak\t{} days", self.longest_streak)?;
+        for (title, entries) in [
+            ("top artists", &self.top_artists),
+            ("top albums", &self.top_albums),
+            ("top tracks", &self.top_tracks),
+            ("top genres", &self.top_genres),
+        ] {
+            writeln!(f, "{}", title)?;
+            for entry in entries {
+                writeln!(f, "\t{}\t{}", entry.item, entry.listen_count)?;
+            }
+        }
+        writeln!(f, "hours")?;
+        for (hour, count) in self.hour_histogram.iter().enumerate() {
+            writeln!(f, "\t{:02}\t{}", hour, count)?;
+        }
+        write!(f, "weekdays")?;
+        for (weekday, count) in WEEKDAYS.iter().zip(self.weekday_histogram.iter()) {
+            write!(f, "\n\t{}\t{}", weekday, count)?;
         }
         Ok(())
     }
 }
 
+impl From<sonar_grpc::Stats> for Stats {
+    fn from(value: sonar_grpc::Stats) -> Self {
+        Self {
+            listen_count: value.listen_count,
+            listen_time: value.listen_time.unwrap_or_default().seconds as u64,
+            top_artists: value.top_artists.into_iter().map(Into::into).collect(),
+            top_albums: value.top_albums.into_iter().map(Into::into).collect(),
+            top_tracks: value.top_tracks.into_iter().map(Into::into).collect(),
+            top_genres: value.top_genres.into_iter().map(Into::into).collect(),
+            hour_histogram: value.hour_histogram,
+            weekday_histogram: value.weekday_histogram,
+            current_streak: value.current_streak,
+            longest_streak: value.longest_streak,
+        }
+    }
+}
+
 #[derive(Debug, Serialize)]
-struct Subscription {
-    id: String,
-    user: String,
-    created_at: u64,
-    last_submitted: Option<u64>,
-    interval: Option<u64>,
-    description: String,
-    artist: String,
-    album: String,
+struct Rating {
+    item: String,
+    rating: u32,
+    rated_at: u64,
+}
+
+impl std::fmt::Display for Rating {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}\t{}", self.item, self.rating)
+    }
+}
+
+impl From<sonar_grpc::Rating> for Rating {
+    fn from(value: sonar_grpc::Rating) -> Self {
+        Self {
+            item: value.item_id,
+            rating: value.rating,
+            rated_at: value.rated_at.map(|t| t.seconds as u64).unwrap_or_default(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct AverageRating {
+    item: String,
+    average: f64,
+    count: u32,
+}
+
+impl std::fmt::Display for AverageRating {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}\t{:.2}\t{}", self.item, self.average, self.count)
+    }
+}
+
+impl From<sonar_grpc::AverageRating> for AverageRating {
+    fn from(value: sonar_grpc::AverageRating) -> Self {
+        Self {
+            item: value.item_id,
+            average: value.average,
+            count: value.count,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct PlayQueue {
+    tracks: Vec<String>,
+    current: Option<u32>,
+    position_ms: u64,
+    changed_by: String,
+    changed_at: u64,
+}
+
+impl std::fmt::Display for PlayQueue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}\t{}", self.position_ms, self.changed_by)?;
+        for (index, track) in self.tracks.iter().enumerate() {
+            let marker = if self.current == Some(index as u32) {
+                "*"
+            } else {
+                " "
+            };
+            write!(f, "\n{}{}\t{}", marker, index, track)?;
+        }
+        Ok(())
+    }
+}
+
+impl From<sonar_grpc::PlayQueue> for PlayQueue {
+    fn from(value: sonar_grpc::PlayQueue) -> Self {
+        Self {
+            tracks: value.track_ids,
+            current: value.current,
+            position_ms: value.position_ms,
+            changed_by: value.changed_by,
+            changed_at: value
+                .changed_at
+                .map(|t| t.seconds as u64)
+                .unwrap_or_default(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct Bookmark {
     track: String,
-    playlist: String,
-    external_id: String,
-    media_type: String,
+    position_ms: u64,
+    comment: Option<String>,
+    created_at: u64,
+    updated_at: u64,
 }
 
-impl From<sonar_grpc::Subscription> for Subscription {
-    fn from(value: sonar_grpc::Subscription) -> Self {
+impl std::fmt::Display for Bookmark {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}\t{}\t{}",
+            self.track,
+            self.position_ms,
+            self.comment.as_deref().unwrap_or_default()
+        )
+    }
+}
+
+impl From<sonar_grpc::Bookmark> for Bookmark {
+    fn from(value: sonar_grpc::Bookmark) -> Self {
+        Self {
+            track: value.track_id,
+            position_ms: value.position_ms,
+            comment: value.comment,
+            created_at: value
+                .created_at
+                .map(|t| t.seconds as u64)
+                .unwrap_or_default(),
+            updated_at: value
+                .updated_at
+                .map(|t| t.seconds as u64)
+                .unwrap_or_default(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct Event {
+    sequence: u64,
+    kind: String,
+    id: String,
+    user: Option<String>,
+    timestamp: u64,
+}
+
+impl std::fmt::Display for Event {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}\t{}\t{}", self.sequence, self.kind, self.id)
+    }
+}
+
+impl From<sonar_grpc::Event> for Event {
+    fn from(value: sonar_grpc::Event) -> Self {
         Self {
+            sequence: value.sequence,
+            kind: value.kind,
             id: value.id,
             user: value.user_id,
-            created_at: value.created_at.unwrap().seconds as u64,
-            last_submitted: value.last_submitted.map(|t| t.seconds as u64),
-            interval: value.interval.map(|d| d.seconds as u64),
-            description: value.description,
-            artist: value.artist,
-            album: value.album,
-            track: value.track,
-            playlist: value.playlist,
-            external_id: value.external_id,
-            media_type: value.media_type,
+            timestamp: value
+                .timestamp
+                .map(|t| t.seconds as u64)
+                .unwrap_or_default(),
         }
     }
 }
 
-impl std::fmt::Display for Subscription {
+#[derive(Debug, Serialize)]
+struct Radio {
+    id: String,
+    user: String,
+    seed: String,
+    track_count: u32,
+    created_at: u64,
+    updated_at: u64,
+}
+
+impl std::fmt::Display for Radio {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}\t{}\t{}", self.id, self.user, self.external_id)
+        write!(f, "{}\t{}\t{}", self.id, self.seed, self.track_count)
     }
 }
 
-fn main() -> Result<()> {
-    tokio::runtime::Builder::new_multi_thread()
-        .worker_threads(16)
-        .thread_stack_size(8 * 1024 * 1024)
-        .enable_all()
-        .build()
-        .unwrap()
-        .block_on(async_main())
+impl From<sonar_grpc::Radio> for Radio {
+    fn from(value: sonar_grpc::Radio) -> Self {
+        Self {
+            id: value.id,
+            user: value.user_id,
+            seed: value.seed,
+            track_count: value.track_count,
+            created_at: value
+                .created_at
+                .map(|t| t.seconds as u64)
+                .unwrap_or_default(),
+            updated_at: value
+                .updated_at
+                .map(|t| t.seconds as u64)
+                .unwrap_or_default(),
+        }
+    }
 }
 
-async fn async_main() -> Result<()> {
-    color_eyre::install()?;
+#[derive(Debug, Serialize)]
+struct Library {
+    id: String,
+    name: String,
+    created_at: u64,
+}
 
-    let args = Args::parse();
+impl std::fmt::Display for Library {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}\t{}", self.id, self.name)
+    }
+}
 
-    if let Command::Server(ref args) = args.command
-        && args.jaeger_exporter_endpoint.is_some()
-    {
-        let mut tracer = opentelemetry_jaeger::new_agent_pipeline();
-        if let Some(ref endpoint) = args.jaeger_exporter_endpoint {
-            tracer = tracer.with_endpoint(endpoint).with_auto_split_batch(true);
+impl From<sonar_grpc::Library> for Library {
+    fn from(value: sonar_grpc::Library) -> Self {
+        Self {
+            id: value.id,
+            name: value.name,
+            created_at: value
+                .created_at
+                .map(|t| t.seconds as u64)
+                .unwrap_or_default(),
         }
-        let tracer = tracer.with_service_name("sonar").install_simple()?;
-
-        let opentelemetry = tracing_opentelemetry::layer().with_tracer(tracer);
-        tracing_subscriber::registry()
-            .with(opentelemetry)
-            .with(
-                tracing_subscriber::fmt::layer()
-                    .with_filter(tracing_subscriber::EnvFilter::from_default_env()),
-            )
-            .try_init()?;
-    } else {
-        tracing_subscriber::fmt::init();
     }
+}
 
-    SERVER_ENDPOINT.set(args.server.clone()).unwrap();
-    OUTPUT_JSON.set(args.json).unwrap();
+#[derive(Debug, Serialize)]
+struct Webhook {
+    id: String,
+    url: String,
+    kinds: Vec<String>,
+    secret: String,
+    enabled: bool,
+    created_at: u64,
+}
 
-    match args.command {
+impl std::fmt::Display for Webhook {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let kinds = if self.kinds.is_empty() {
+            "all".to_string()
+        } else {
+            self.kinds.join(",")
+        };
+        write!(f, "{}\t{}\t{}\t{}", self.id, self.url, kinds, self.enabled)
+    }
+}
+
+impl From<sonar_grpc::Webhook> for Webhook {
+    fn from(value: sonar_grpc::Webhook) -> Self {
+        Self {
+            id: value.id,
+            url: value.url,
+            kinds: value.kinds,
+            secret: value.secret,
+            enabled: value.enabled,
+            created_at: value
+                .created_at
+                .map(|t| t.seconds as u64)
+                .unwrap_or_default(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct WebhookDelivery {
+    id: u64,
+    webhook: String,
+    event_sequence: u64,
+    event_kind: String,
+    payload: String,
+    status: String,
+    attempts: u32,
+    response_status: Option<u32>,
+    error: Option<String>,
+    next_attempt_at: u64,
+    created_at: u64,
+    updated_at: u64,
+}
+
+impl std::fmt::Display for WebhookDelivery {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}\t{}\t{}\t{}\t{}",
+            self.id, self.event_kind, self.status, self.attempts, self.updated_at
+        )?;
+        if let Some(error) = &self.error {
+            write!(f, "\t{}", error)?;
+        }
+        Ok(())
+    }
+}
+
+impl From<sonar_grpc::WebhookDelivery> for WebhookDelivery {
+    fn from(value: sonar_grpc::WebhookDelivery) -> Self {
+        Self {
+            id: value.id,
+            webhook: value.webhook_id,
+            event_sequence: value.event_sequence,
+            event_kind: value.event_kind,
+            payload: value.payload,
+            status: value.status,
+            attempts: value.attempts,
+            response_status: value.response_status,
+            error: value.error,
+            next_attempt_at: value
+                .next_attempt_at
+                .map(|t| t.seconds as u64)
+                .unwrap_or_default(),
+            created_at: value
+                .created_at
+                .map(|t| t.seconds as u64)
+                .unwrap_or_default(),
+            updated_at: value
+                .updated_at
+                .map(|t| t.seconds as u64)
+                .unwrap_or_default(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct FsckIssue {
+    key: String,
+    kind: String,
+    repaired: bool,
+}
+
+impl std::fmt::Display for FsckIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}\t{}", self.kind, self.key)?;
+        if self.repaired {
+            write!(f, "\trepaired")?;
+        }
+        Ok(())
+    }
+}
+
+impl From<sonar_grpc::BlobFsckIssue> for FsckIssue {
+    fn from(value: sonar_grpc::BlobFsckIssue) -> Self {
+        Self {
+            key: value.key,
+            kind: value.kind,
+            repaired: value.repaired,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+enum SearchResult {
+    Artist(Artist),
+    Album(Album),
+    Track(Track),
+    Playlist(Playlist),
+}
+
+impl std::fmt::Display for SearchResult {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SearchResult::Artist(artist) => write!(f, "{}\t{}", artist.id, artist.name),
+            SearchResult::Album(album) => write!(f, "{}\t{}", album.id, album.name),
+            SearchResult::Track(track) => write!(f, "{}\t{}", track.id, track.name),
+            SearchResult::Playlist(playlist) => write!(f, "{}\t{}", playlist.id, playlist.name),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct SearchResults(Vec<SearchResult>);
+
+impl std::fmt::Display for SearchResults {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for result in &self.0 {
+            writeln!(f, "{}", result)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct Subscription {
+    id: String,
+    user: String,
+    created_at: u64,
+    last_submitted: Option<u64>,
+    interval: Option<u64>,
+    description: String,
+    artist: String,
+    album: String,
+    track: String,
+    playlist: String,
+    external_id: String,
+    media_type: String,
+}
+
+impl From<sonar_grpc::Subscription> for Subscription {
+    fn from(value: sonar_grpc::Subscription) -> Self {
+        Self {
+            id: value.id,
+            user: value.user_id,
+            created_at: value.created_at.unwrap().seconds as u64,
+            last_submitted: value.last_submitted.map(|t| t.seconds as u64),
+            interval: value.interval.map(|d| d.seconds as u64),
+            description: value.description,
+            artist: value.artist,
+            album: value.album,
+            track: value.track,
+            playlist: value.playlist,
+            external_id: value.external_id,
+            media_type: value.media_type,
+        }
+    }
+}
+
+impl std::fmt::Display for Subscription {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}\t{}\t{}", self.id, self.user, self.external_id)
+    }
+}
+
+fn main() -> Result<()> {
+    tokio::runtime::Builder::new_multi_thread()
+        .worker_threads(16)
+        .thread_stack_size(8 * 1024 * 1024)
+        .enable_all()
+        .build()
+        .unwrap()
+        .block_on(async_main())
+}
+
+async fn async_main() -> Result<()> {
+    color_eyre::install()?;
+
+    let args = Args::parse();
+
+    if let Command::Server(ref args) = args.command
+        && args.jaeger_exporter_endpoint.is_some()
+    {
+        let mut tracer = opentelemetry_jaeger::new_agent_pipeline();
+        if let Some(ref endpoint) = args.jaeger_exporter_endpoint {
+            tracer = tracer.with_endpoint(endpoint).with_auto_split_batch(true);
+        }
+        let tracer = tracer.with_service_name("sonar").install_simple()?;
+
+        let opentelemetry = tracing_opentelemetry::layer().with_tracer(tracer);
+        tracing_subscriber::registry()
+            .with(opentelemetry)
+            .with(
+                tracing_subscriber::fmt::layer()
+                    .with_filter(tracing_subscriber::EnvFilter::from_default_env()),
+            )
+            .try_init()?;
+    } else {
+        tracing_subscriber::fmt::init();
+    }
+
+    SERVER_ENDPOINT.set(args.server.clone()).unwrap();
+    OUTPUT_JSON.set(args.json).unwrap();
+
+    match args.command {
         Command::Login(args) => cmd_login(args).await?,
         Command::Logout(args) => cmd_logout(args).await?,
         Command::Artist(cargs) => match cargs.command {
@@ -428,6 +1025,22 @@ async fn async_main() -> Result<()> {
             FavoriteCommand::Add(cargs) => cmd_favorite_add(cargs).await?,
             FavoriteCommand::Remove(cargs) => cmd_favorite_remove(cargs).await?,
         },
+        Command::Rating(cargs) => match cargs.command {
+            RatingCommand::List(cargs) => cmd_rating_list(cargs).await?,
+            RatingCommand::Set(cargs) => cmd_rating_set(cargs).await?,
+            RatingCommand::Remove(cargs) => cmd_rating_remove(cargs).await?,
+            RatingCommand::Average(cargs) => cmd_rating_average(cargs).await?,
+        },
+        Command::Queue(cargs) => match cargs.command {
+            QueueCommand::Get(cargs) => cmd_queue_get(cargs).await?,
+            QueueCommand::Save(cargs) => cmd_queue_save(cargs).await?,
+            QueueCommand::Clear(cargs) => cmd_queue_clear(cargs).await?,
+        },
+        Command::Bookmark(cargs) => match cargs.command {
+            BookmarkCommand::List(cargs) => cmd_bookmark_list(cargs).await?,
+            BookmarkCommand::Create(cargs) => cmd_bookmark_create(cargs).await?,
+            BookmarkCommand::Delete(cargs) => cmd_bookmark_delete(cargs).await?,
+        },
         Command::Playlist(cargs) => match cargs.command {
             PlaylistCommand::List(cargs) => cmd_playlist_list(cargs).await?,
             PlaylistCommand::Create(cargs) => cmd_playlist_create(cargs).await?,
@@ -436,11 +1049,30 @@ async fn async_main() -> Result<()> {
             PlaylistCommand::Delete(cargs) => cmd_playlist_delete(cargs).await?,
             PlaylistCommand::Add(cargs) => cmd_playlist_add(cargs).await?,
             PlaylistCommand::Remove(cargs) => cmd_playlist_remove(cargs).await?,
+            PlaylistCommand::Move(cargs) => cmd_playlist_move(cargs).await?,
+            PlaylistCommand::SmartCreate(cargs) => cmd_playlist_smart_create(cargs).await?,
+            PlaylistCommand::SmartUpdate(cargs) => cmd_playlist_smart_update(cargs).await?,
+            PlaylistCommand::SmartShow(cargs) => cmd_playlist_smart_show(cargs).await?,
+            PlaylistCommand::SmartRefresh(cargs) => cmd_playlist_smart_refresh(cargs).await?,
         },
         Command::Scrobble(cargs) => match cargs.command {
             ScrobbleCommand::List(cargs) => cmd_scrobble_list(cargs).await?,
             ScrobbleCommand::Create(cargs) => cmd_scrobble_create(cargs).await?,
             ScrobbleCommand::Delete(cargs) => cmd_scrobble_delete(cargs).await?,
+            ScrobbleCommand::Import(cargs) => cmd_scrobble_import(cargs).await?,
+        },
+        Command::Stats(cargs) => cmd_stats(cargs).await?,
+        Command::Recommend(cargs) => match cargs.command {
+            RecommendCommand::Tracks(cargs) => cmd_recommend_tracks(cargs).await?,
+            RecommendCommand::Artists(cargs) => cmd_recommend_artists(cargs).await?,
+            RecommendCommand::DailyMix(cargs) => cmd_recommend_daily_mix(cargs).await?,
+        },
+        Command::Radio(cargs) => match cargs.command {
+            RadioCommand::Get(cargs) => cmd_radio_get(cargs).await?,
+            RadioCommand::Create(cargs) => cmd_radio_create(cargs).await?,
+            RadioCommand::Delete(cargs) => cmd_radio_delete(cargs).await?,
+            RadioCommand::Next(cargs) => cmd_radio_next(cargs).await?,
+            RadioCommand::Stream(cargs) => cmd_radio_stream(cargs).await?,
         },
         Command::Sync(cargs) => cmd_sync(cargs).await?,
         Command::Pin(cargs) => match cargs.command {
@@ -449,6 +1081,24 @@ async fn async_main() -> Result<()> {
             PinCommand::Unset(cargs) => cmd_pin_unset(cargs).await?,
         },
         Command::Search(cargs) => cmd_search(cargs).await?,
+        Command::Watch(cargs) => cmd_watch(cargs).await?,
+        Command::Library(cargs) => match cargs.command {
+            LibraryCommand::List => cmd_library_list().await?,
+            LibraryCommand::Create(cargs) => cmd_library_create(cargs).await?,
+            LibraryCommand::Update(cargs) => cmd_library_update(cargs).await?,
+            LibraryCommand::Delete(cargs) => cmd_library_delete(cargs).await?,
+            LibraryCommand::Users(cargs) => cmd_library_users(cargs).await?,
+            LibraryCommand::Grant(cargs) => cmd_library_grant(cargs).await?,
+            LibraryCommand::Revoke(cargs) => cmd_library_revoke(cargs).await?,
+        },
+        Command::Webhook(cargs) => match cargs.command {
+            WebhookCommand::List => cmd_webhook_list().await?,
+            WebhookCommand::Get(cargs) => cmd_webhook_get(cargs).await?,
+            WebhookCommand::Create(cargs) => cmd_webhook_create(cargs).await?,
+            WebhookCommand::Update(cargs) => cmd_webhook_update(cargs).await?,
+            WebhookCommand::Delete(cargs) => cmd_webhook_delete(cargs).await?,
+            WebhookCommand::Deliveries(cargs) => cmd_webhook_deliveries(cargs).await?,
+        },
         Command::Subscription(cargs) => match cargs.command {
             SubscriptionCommand::List(cargs) => cmd_subscription_list(cargs).await?,
             SubscriptionCommand::Create(cargs) => cmd_subscription_create(cargs).await?,
@@ -465,6 +1115,28 @@ async fn async_main() -> Result<()> {
                 AdminUserCommand::Create(cargs) => cmd_admin_user_create(cargs).await?,
                 AdminUserCommand::Update(cargs) => cmd_admin_user_update(cargs).await?,
                 AdminUserCommand::Delete(cargs) => cmd_admin_user_delete(cargs).await?,
+                AdminUserCommand::Session(cargs) => match cargs.command {
+                    AdminUserSessionCommand::List(cargs) => {
+                        cmd_admin_user_session_list(cargs).await?
+                    }
+                    AdminUserSessionCommand::Revoke(cargs) => {
+                        cmd_admin_user_session_revoke(cargs).await?
+                    }
+                },
+                AdminUserCommand::SubsonicPassword(cargs) => {
+                    cmd_admin_user_subsonic_password(cargs).await?
+                }
+                AdminUserCommand::ApiKey(cargs) => match cargs.command {
+                    AdminUserApiKeyCommand::List(cargs) => {
+                        cmd_admin_user_api_key_list(cargs).await?
+                    }
+                    AdminUserApiKeyCommand::Create(cargs) => {
+                        cmd_admin_user_api_key_create(cargs).await?
+                    }
+                    AdminUserApiKeyCommand::Revoke(cargs) => {
+                        cmd_admin_user_api_key_revoke(cargs).await?
+                    }
+                },
             },
             AdminCommand::Playlist(cargs) => match cargs.command {
                 AdminPlaylistCommand::List(cargs) => cmd_admin_playlist_list(cargs).await?,
@@ -473,9 +1145,14 @@ async fn async_main() -> Result<()> {
                 AdminPlaylistCommand::Delete(cargs) => cmd_admin_playlist_delete(cargs).await?,
             },
             AdminCommand::MetadataPreview(cargs) => cmd_admin_metadata_preview(cargs).await?,
+            AdminCommand::Fsck(cargs) => cmd_admin_fsck(cargs).await?,
+            AdminCommand::Gc(cargs) => cmd_admin_gc(cargs).await?,
+            AdminCommand::Sweep(cargs) => cmd_admin_sweep(cargs).await?,
+            AdminCommand::Backup(cargs) => cmd_admin_backup(cargs).await?,
         },
         Command::Import(cargs) => cmd_import(cargs).await?,
         Command::Server(cargs) => cmd_server(cargs).await?,
+        Command::Restore(cargs) => cmd_restore(cargs).await?,
     }
 
     Ok(())
@@ -494,6 +1171,14 @@ struct LoginArgs {
     username: String,
 
     password: String,
+
+    /// label used to identify this session
+    #[clap(long, default_value = "sonar-cli")]
+    device: String,
+
+    /// number of seconds until the session expires
+    #[clap(long)]
+    expires_in: Option<u32>,
 }
 
 async fn cmd_login(args: LoginArgs) -> Result<()> {
@@ -502,6 +1187,11 @@ async fn cmd_login(args: LoginArgs) -> Result<()> {
         .user_login(sonar_grpc::UserLoginRequest {
             username: args.username,
             password: args.password,
+            device: Some(args.device),
+            expires_in: args.expires_in.map(|s| Duration {
+                seconds: s as i64,
+                nanos: 0,
+            }),
         })
         .await?;
     let response = response.into_inner();
@@ -546,6 +1236,10 @@ enum ArtistCommand {
 struct ArtistListArgs {
     #[clap(flatten)]
     params: ListParams,
+
+    /// only list artists from this library
+    #[clap(long)]
+    library: Option<sonar::LibraryId>,
 }
 
 async fn cmd_artist_list(args: ArtistListArgs) -> Result<()> {
@@ -554,6 +1248,7 @@ async fn cmd_artist_list(args: ArtistListArgs) -> Result<()> {
         .artist_list(sonar_grpc::ArtistListRequest {
             offset: args.params.offset,
             count: args.params.limit,
+            library_id: args.library.map(|id| id.to_string()),
         })
         .await?;
     let artists = response
@@ -575,6 +1270,10 @@ struct ArtistCreateArgs {
 
     #[clap(long, default_value = "")]
     genres: sonar::Genres,
+
+    /// library to create the artist in, the default library if not given
+    #[clap(long)]
+    library: Option<sonar::LibraryId>,
 }
 
 async fn cmd_artist_create(args: ArtistCreateArgs) -> Result<()> {
@@ -588,6 +1287,7 @@ async fn cmd_artist_create(args: ArtistCreateArgs) -> Result<()> {
         .artist_create(sonar_grpc::ArtistCreateRequest {
             name: args.name,
             coverart_id: image_id.map(|x| x.to_string()),
+            library_id: args.library.map(|id| id.to_string()),
             ..Default::default()
         })
         .await?;
@@ -712,6 +1412,10 @@ enum AlbumCommand {
 struct AlbumListArgs {
     #[clap(flatten)]
     params: ListParams,
+
+    /// only list albums from this library
+    #[clap(long)]
+    library: Option<sonar::LibraryId>,
 }
 
 async fn cmd_album_list(args: AlbumListArgs) -> Result<()> {
@@ -720,6 +1424,7 @@ async fn cmd_album_list(args: AlbumListArgs) -> Result<()> {
         .album_list(sonar_grpc::AlbumListRequest {
             offset: args.params.offset,
             count: args.params.limit,
+            library_id: args.library.map(|id| id.to_string()),
         })
         .await?;
     let albums = response
@@ -738,6 +1443,10 @@ struct AlbumCreateArgs {
 
     name: String,
 
+    /// other album artists
+    #[clap(long)]
+    artist: Vec<String>,
+
     #[clap(long)]
     cover_art: Option<PathBuf>,
 }
@@ -753,6 +1462,7 @@ async fn cmd_album_create(args: AlbumCreateArgs) -> Result<()> {
         .album_create(sonar_grpc::AlbumCreateRequest {
             name: args.name,
             artist_id: args.artist_id.to_string(),
+            artist_ids: args.artist,
             coverart_id: image_id.map(|x| x.to_string()),
             ..Default::default()
         })
@@ -769,6 +1479,10 @@ struct AlbumUpdateArgs {
     #[clap(long)]
     name: Option<String>,
 
+    /// replaces every album artist
+    #[clap(long)]
+    artist: Vec<String>,
+
     #[clap(long)]
     cover_art: Option<PathBuf>,
 }
@@ -783,6 +1497,7 @@ async fn cmd_album_update(args: AlbumUpdateArgs) -> Result<()> {
         .album_update(sonar_grpc::AlbumUpdateRequest {
             album_id: args.id.to_string(),
             name: args.name,
+            artist_ids: args.artist,
             coverart_id: image_id.map(|x| x.to_string()),
             ..Default::default()
         })
@@ -878,12 +1593,21 @@ enum TrackCommand {
 struct TrackListArgs {
     #[clap(flatten)]
     params: ListParams,
+
+    /// only list tracks from this library
+    #[clap(long)]
+    library: Option<sonar::LibraryId>,
 }
 
 async fn cmd_track_list(args: TrackListArgs) -> Result<()> {
     let mut client = create_client().await?;
-    let tracks =
-        sonar_grpc::ext::track_list(&mut client, args.params.offset, args.params.limit).await?;
+    let tracks = sonar_grpc::ext::track_list(
+        &mut client,
+        args.params.offset,
+        args.params.limit,
+        args.library.map(|id| id.to_string()),
+    )
+    .await?;
     let tracks = tracks.into_iter().map(Track::from).collect::<Vec<_>>();
     stdout_values(&tracks)?;
     Ok(())
@@ -895,6 +1619,9 @@ struct TrackCreateArgs {
 
     name: String,
 
+    #[clap(flatten)]
+    credits: TrackCreditArgs,
+
     #[clap(long)]
     cover_art: Option<PathBuf>,
 
@@ -914,6 +1641,7 @@ async fn cmd_track_create(args: TrackCreateArgs) -> Result<()> {
             name: args.name,
             album_id: args.album_id.to_string(),
             coverart_id: image_id.map(|x| x.to_string()),
+            artists: args.credits.into_pb(),
             ..Default::default()
         })
         .await?;
@@ -934,6 +1662,42 @@ struct TrackUpdateArgs {
 
     #[clap(long)]
     cover_art: Option<PathBuf>,
+
+    #[clap(flatten)]
+    credits: TrackCreditArgs,
+
+    /// credit the track to the album artists
+    #[clap(long, conflicts_with_all = ["artist", "featured", "remixer"])]
+    reset_artists: bool,
+}
+
+#[derive(Debug, Parser)]
+struct TrackCreditArgs {
+    /// primary artists, the album artists are used if there are none
+    #[clap(long)]
+    artist: Vec<String>,
+
+    #[clap(long)]
+    featured: Vec<String>,
+
+    #[clap(long)]
+    remixer: Vec<String>,
+}
+
+impl TrackCreditArgs {
+    fn into_pb(self) -> Vec<sonar_grpc::ArtistCredit> {
+        let credits = |ids: Vec<String>, role: sonar::ArtistRole| {
+            ids.into_iter()
+                .map(move |artist_id| sonar_grpc::ArtistCredit {
+                    artist_id,
+                    role: role.to_string(),
+                })
+        };
+        credits(self.artist, sonar::ArtistRole::Primary)
+            .chain(credits(self.featured, sonar::ArtistRole::Featured))
+            .chain(credits(self.remixer, sonar::ArtistRole::Remixer))
+            .collect()
+    }
 }
 
 async fn cmd_track_update(args: TrackUpdateArgs) -> Result<()> {
@@ -948,6 +1712,8 @@ async fn cmd_track_update(args: TrackUpdateArgs) -> Result<()> {
             name: args.name,
             album_id: args.album_id.map(|x| x.to_string()),
             coverart_id: image_id.map(|x| x.to_string()),
+            artists: args.credits.into_pb(),
+            reset_artists: args.reset_artists,
             ..Default::default()
         })
         .await?;
@@ -1040,6 +1806,14 @@ enum PlaylistCommand {
     Delete(PlaylistDeleteArgs),
     Add(PlaylistAddArgs),
     Remove(PlaylistRemoveArgs),
+    Move(PlaylistMoveArgs),
+    /// create a playlist whose tracks are generated from rules
+    SmartCreate(PlaylistSmartCreateArgs),
+    /// replace the rules of a smart playlist
+    SmartUpdate(PlaylistSmartUpdateArgs),
+    SmartShow(PlaylistSmartShowArgs),
+    /// regenerate the tracks of a smart playlist
+    SmartRefresh(PlaylistSmartRefreshArgs),
 }
 
 #[derive(Debug, Parser)]
@@ -1164,6 +1938,10 @@ struct PlaylistAddArgs {
     playlist_id: sonar::PlaylistId,
 
     track_ids: Vec<sonar::TrackId>,
+
+    /// insert the tracks at this position instead of appending them
+    #[clap(long)]
+    position: Option<u32>,
 }
 
 async fn cmd_playlist_add(args: PlaylistAddArgs) -> Result<()> {
@@ -1172,6 +1950,7 @@ async fn cmd_playlist_add(args: PlaylistAddArgs) -> Result<()> {
         .playlist_track_insert(sonar_grpc::PlaylistTrackInsertRequest {
             playlist_id: args.playlist_id.to_string(),
             track_ids: args.track_ids.iter().map(|x| x.to_string()).collect(),
+            position: args.position,
         })
         .await?;
     Ok(())
@@ -1182,6 +1961,10 @@ struct PlaylistRemoveArgs {
     playlist_id: sonar::PlaylistId,
 
     track_ids: Vec<sonar::TrackId>,
+
+    /// remove the track at this position, can be used multiple times
+    #[clap(long = "position")]
+    positions: Vec<u32>,
 }
 
 async fn cmd_playlist_remove(args: PlaylistRemoveArgs) -> Result<()> {
@@ -1190,53 +1973,197 @@ async fn cmd_playlist_remove(args: PlaylistRemoveArgs) -> Result<()> {
         .playlist_track_remove(sonar_grpc::PlaylistTrackRemoveRequest {
             playlist_id: args.playlist_id.to_string(),
             track_ids: args.track_ids.iter().map(|x| x.to_string()).collect(),
+            positions: args.positions,
         })
         .await?;
     Ok(())
 }
 
 #[derive(Debug, Parser)]
-struct FavoriteArgs {
-    #[clap(subcommand)]
-    command: FavoriteCommand,
+struct PlaylistMoveArgs {
+    playlist_id: sonar::PlaylistId,
+
+    /// position of the first track to move
+    from: u32,
+
+    /// position the first moved track will end up at
+    to: u32,
+
+    /// number of tracks to move
+    #[clap(long, default_value = "1")]
+    count: u32,
 }
 
-#[derive(Debug, Parser)]
-enum FavoriteCommand {
-    List(FavoriteListArgs),
-    Add(FavoriteAddArgs),
-    Remove(FavoriteRemoveArgs),
+async fn cmd_playlist_move(args: PlaylistMoveArgs) -> Result<()> {
+    let mut client = create_client().await?;
+    client
+        .playlist_track_move(sonar_grpc::PlaylistTrackMoveRequest {
+            playlist_id: args.playlist_id.to_string(),
+            from: args.from,
+            count: args.count,
+            to: args.to,
+        })
+        .await?;
+    Ok(())
 }
 
 #[derive(Debug, Parser)]
-struct FavoriteListArgs {}
+struct PlaylistSmartRulesArgs {
+    /// rule the tracks must match, for example genre:rock, artist:<id>, favorite, not-favorite,
+    /// min-listens:5, max-listens:0, played-within:7d, not-played-within:30d, added-within:2w,
+    /// min-rating:4 or property:key=value
+    #[clap(long = "rule")]
+    rules: Vec<sonar::SmartPlaylistRule>,
+
+    /// include the tracks that match any rule instead of all of them
+    #[clap(long)]
+    match_any: bool,
 
-async fn cmd_favorite_list(_args: FavoriteListArgs) -> Result<()> {
-    let mut client = create_client().await?;
-    let (user_id, _) = auth_read().await?;
-    let response = client
-        .favorite_list(sonar_grpc::FavoriteListRequest { user_id })
-        .await?;
-    let favorites = response.into_inner().favorites;
+    /// random, name, listens, last-played, added or rating
+    #[clap(long, default_value = "random")]
+    sort: sonar::SmartPlaylistSort,
 
-    for favorite in favorites {
-        println!("{}", favorite.item_id);
-    }
+    #[clap(long)]
+    descending: bool,
 
-    Ok(())
+    /// maximum number of tracks
+    #[clap(long)]
+    limit: Option<u32>,
+}
+
+impl From<PlaylistSmartRulesArgs> for sonar_grpc::SmartPlaylistRules {
+    fn from(value: PlaylistSmartRulesArgs) -> Self {
+        Self {
+            rules: value.rules.iter().map(ToString::to_string).collect(),
+            match_any: value.match_any,
+            sort: value.sort.to_string(),
+            descending: value.descending,
+            limit: value.limit,
+        }
+    }
 }
 
 #[derive(Debug, Parser)]
-struct FavoriteAddArgs {
-    items_ids: Vec<sonar::SonarId>,
+struct PlaylistSmartCreateArgs {
+    name: String,
+
+    #[clap(flatten)]
+    rules: PlaylistSmartRulesArgs,
 }
 
-async fn cmd_favorite_add(args: FavoriteAddArgs) -> Result<()> {
+async fn cmd_playlist_smart_create(args: PlaylistSmartCreateArgs) -> Result<()> {
     let mut client = create_client().await?;
     let (user_id, _) = auth_read().await?;
-    for item_id in args.items_ids {
-        client
-            .favorite_add(sonar_grpc::FavoriteAddRequest {
+    let response = client
+        .smart_playlist_create(sonar_grpc::SmartPlaylistCreateRequest {
+            name: args.name,
+            owner_id: user_id.to_string(),
+            rules: Some(args.rules.into()),
+            ..Default::default()
+        })
+        .await?;
+    let playlist = Playlist::from(response.into_inner());
+    stdout_value(playlist)?;
+    Ok(())
+}
+
+#[derive(Debug, Parser)]
+struct PlaylistSmartUpdateArgs {
+    id: sonar::PlaylistId,
+
+    #[clap(flatten)]
+    rules: PlaylistSmartRulesArgs,
+}
+
+async fn cmd_playlist_smart_update(args: PlaylistSmartUpdateArgs) -> Result<()> {
+    let mut client = create_client().await?;
+    let response = client
+        .smart_playlist_update(sonar_grpc::SmartPlaylistUpdateRequest {
+            playlist_id: args.id.to_string(),
+            rules: Some(args.rules.into()),
+        })
+        .await?;
+    let playlist = Playlist::from(response.into_inner());
+    stdout_value(playlist)?;
+    Ok(())
+}
+
+#[derive(Debug, Parser)]
+struct PlaylistSmartShowArgs {
+    id: sonar::PlaylistId,
+}
+
+async fn cmd_playlist_smart_show(args: PlaylistSmartShowArgs) -> Result<()> {
+    let mut client = create_client().await?;
+    let response = client
+        .smart_playlist_get(sonar_grpc::SmartPlaylistGetRequest {
+            playlist_id: args.id.to_string(),
+        })
+        .await?;
+    let smart_playlist = SmartPlaylist::from(response.into_inner());
+    stdout_value(smart_playlist)?;
+    Ok(())
+}
+
+#[derive(Debug, Parser)]
+struct PlaylistSmartRefreshArgs {
+    id: sonar::PlaylistId,
+}
+
+async fn cmd_playlist_smart_refresh(args: PlaylistSmartRefreshArgs) -> Result<()> {
+    let mut client = create_client().await?;
+    let response = client
+        .smart_playlist_refresh(sonar_grpc::SmartPlaylistRefreshRequest {
+            playlist_id: args.id.to_string(),
+        })
+        .await?;
+    let playlist = Playlist::from(response.into_inner());
+    stdout_value(playlist)?;
+    Ok(())
+}
+
+#[derive(Debug, Parser)]
+struct FavoriteArgs {
+    #[clap(subcommand)]
+    command: FavoriteCommand,
+}
+
+#[derive(Debug, Parser)]
+enum FavoriteCommand {
+    List(FavoriteListArgs),
+    Add(FavoriteAddArgs),
+    Remove(FavoriteRemoveArgs),
+}
+
+#[derive(Debug, Parser)]
+struct FavoriteListArgs {}
+
+async fn cmd_favorite_list(_args: FavoriteListArgs) -> Result<()> {
+    let mut client = create_client().await?;
+    let (user_id, _) = auth_read().await?;
+    let response = client
+        .favorite_list(sonar_grpc::FavoriteListRequest { user_id })
+        .await?;
+    let favorites = response.into_inner().favorites;
+
+    for favorite in favorites {
+        println!("{}", favorite.item_id);
+    }
+
+    Ok(())
+}
+
+#[derive(Debug, Parser)]
+struct FavoriteAddArgs {
+    items_ids: Vec<sonar::SonarId>,
+}
+
+async fn cmd_favorite_add(args: FavoriteAddArgs) -> Result<()> {
+    let mut client = create_client().await?;
+    let (user_id, _) = auth_read().await?;
+    for item_id in args.items_ids {
+        client
+            .favorite_add(sonar_grpc::FavoriteAddRequest {
                 user_id: user_id.clone(),
                 item_id: item_id.to_string(),
             })
@@ -1264,6 +2191,243 @@ async fn cmd_favorite_remove(args: FavoriteRemoveArgs) -> Result<()> {
     Ok(())
 }
 
+#[derive(Debug, Parser)]
+struct RatingArgs {
+    #[clap(subcommand)]
+    command: RatingCommand,
+}
+
+#[derive(Debug, Parser)]
+enum RatingCommand {
+    List(RatingListArgs),
+    Set(RatingSetArgs),
+    Remove(RatingRemoveArgs),
+    Average(RatingAverageArgs),
+}
+
+#[derive(Debug, Parser)]
+struct RatingListArgs {
+    /// only list the ratings of these items.
+    items_ids: Vec<sonar::SonarId>,
+}
+
+async fn cmd_rating_list(args: RatingListArgs) -> Result<()> {
+    let mut client = create_client().await?;
+    let (user_id, _) = auth_read().await?;
+    let response = client
+        .rating_list(sonar_grpc::RatingListRequest {
+            user_id,
+            item_ids: args.items_ids.iter().map(ToString::to_string).collect(),
+        })
+        .await?;
+    let ratings = response
+        .into_inner()
+        .ratings
+        .into_iter()
+        .map(Rating::from)
+        .collect::<Vec<_>>();
+    stdout_values(&ratings)
+}
+
+#[derive(Debug, Parser)]
+struct RatingSetArgs {
+    item_id: sonar::SonarId,
+    /// rating between 1 and 5.
+    rating: sonar::Rating,
+}
+
+async fn cmd_rating_set(args: RatingSetArgs) -> Result<()> {
+    let mut client = create_client().await?;
+    let (user_id, _) = auth_read().await?;
+    client
+        .rating_set(sonar_grpc::RatingSetRequest {
+            user_id,
+            item_id: args.item_id.to_string(),
+            rating: args.rating.value(),
+        })
+        .await?;
+    Ok(())
+}
+
+#[derive(Debug, Parser)]
+struct RatingRemoveArgs {
+    items_ids: Vec<sonar::SonarId>,
+}
+
+async fn cmd_rating_remove(args: RatingRemoveArgs) -> Result<()> {
+    let mut client = create_client().await?;
+    let (user_id, _) = auth_read().await?;
+    for item_id in args.items_ids {
+        client
+            .rating_remove(sonar_grpc::RatingRemoveRequest {
+                user_id: user_id.clone(),
+                item_id: item_id.to_string(),
+            })
+            .await?;
+    }
+    Ok(())
+}
+
+#[derive(Debug, Parser)]
+struct RatingAverageArgs {
+    items_ids: Vec<sonar::SonarId>,
+}
+
+async fn cmd_rating_average(args: RatingAverageArgs) -> Result<()> {
+    let mut client = create_client().await?;
+    let response = client
+        .rating_average_list(sonar_grpc::RatingAverageListRequest {
+            item_ids: args.items_ids.iter().map(ToString::to_string).collect(),
+        })
+        .await?;
+    let ratings = response
+        .into_inner()
+        .ratings
+        .into_iter()
+        .map(AverageRating::from)
+        .collect::<Vec<_>>();
+    stdout_values(&ratings)
+}
+
+#[derive(Debug, Parser)]
+struct QueueArgs {
+    #[clap(subcommand)]
+    command: QueueCommand,
+}
+
+#[derive(Debug, Parser)]
+enum QueueCommand {
+    Get(QueueGetArgs),
+    Save(QueueSaveArgs),
+    Clear(QueueClearArgs),
+}
+
+#[derive(Debug, Parser)]
+struct QueueGetArgs {}
+
+async fn cmd_queue_get(_args: QueueGetArgs) -> Result<()> {
+    let mut client = create_client().await?;
+    let (user_id, _) = auth_read().await?;
+    let response = client
+        .play_queue_get(sonar_grpc::PlayQueueGetRequest { user_id })
+        .await?;
+    stdout_value(PlayQueue::from(response.into_inner()))
+}
+
+#[derive(Debug, Parser)]
+struct QueueSaveArgs {
+    /// index of the current track.
+    #[clap(long)]
+    current: Option<u32>,
+    /// position within the current track, in milliseconds.
+    #[clap(long, default_value = "0")]
+    position: u64,
+    /// name of the client saving the queue.
+    #[clap(long, default_value = "sonar-cli")]
+    client: String,
+    track_ids: Vec<sonar::TrackId>,
+}
+
+async fn cmd_queue_save(args: QueueSaveArgs) -> Result<()> {
+    let mut client = create_client().await?;
+    let (user_id, _) = auth_read().await?;
+    let response = client
+        .play_queue_save(sonar_grpc::PlayQueueSaveRequest {
+            user_id,
+            track_ids: args.track_ids.iter().map(ToString::to_string).collect(),
+            current: args.current,
+            position_ms: args.position,
+            changed_by: args.client,
+        })
+        .await?;
+    stdout_value(PlayQueue::from(response.into_inner()))
+}
+
+#[derive(Debug, Parser)]
+struct QueueClearArgs {}
+
+async fn cmd_queue_clear(_args: QueueClearArgs) -> Result<()> {
+    let mut client = create_client().await?;
+    let (user_id, _) = auth_read().await?;
+    client
+        .play_queue_clear(sonar_grpc::PlayQueueClearRequest { user_id })
+        .await?;
+    Ok(())
+}
+
+#[derive(Debug, Parser)]
+struct BookmarkArgs {
+    #[clap(subcommand)]
+    command: BookmarkCommand,
+}
+
+#[derive(Debug, Parser)]
+enum BookmarkCommand {
+    List(BookmarkListArgs),
+    Create(BookmarkCreateArgs),
+    Delete(BookmarkDeleteArgs),
+}
+
+#[derive(Debug, Parser)]
+struct BookmarkListArgs {}
+
+async fn cmd_bookmark_list(_args: BookmarkListArgs) -> Result<()> {
+    let mut client = create_client().await?;
+    let (user_id, _) = auth_read().await?;
+    let response = client
+        .bookmark_list(sonar_grpc::BookmarkListRequest { user_id })
+        .await?;
+    let bookmarks = response
+        .into_inner()
+        .bookmarks
+        .into_iter()
+        .map(Bookmark::from)
+        .collect::<Vec<_>>();
+    stdout_values(&bookmarks)
+}
+
+#[derive(Debug, Parser)]
+struct BookmarkCreateArgs {
+    track_id: sonar::TrackId,
+    /// position within the track, in milliseconds.
+    position: u64,
+    #[clap(long)]
+    comment: Option<String>,
+}
+
+async fn cmd_bookmark_create(args: BookmarkCreateArgs) -> Result<()> {
+    let mut client = create_client().await?;
+    let (user_id, _) = auth_read().await?;
+    let response = client
+        .bookmark_create(sonar_grpc::BookmarkCreateRequest {
+            user_id,
+            track_id: args.track_id.to_string(),
+            position_ms: args.position,
+            comment: args.comment,
+        })
+        .await?;
+    stdout_value(Bookmark::from(response.into_inner()))
+}
+
+#[derive(Debug, Parser)]
+struct BookmarkDeleteArgs {
+    track_ids: Vec<sonar::TrackId>,
+}
+
+async fn cmd_bookmark_delete(args: BookmarkDeleteArgs) -> Result<()> {
+    let mut client = create_client().await?;
+    let (user_id, _) = auth_read().await?;
+    for track_id in args.track_ids {
+        client
+            .bookmark_delete(sonar_grpc::BookmarkDeleteRequest {
+                user_id: user_id.clone(),
+                track_id: track_id.to_string(),
+            })
+            .await?;
+    }
+    Ok(())
+}
+
 #[derive(Debug, Parser)]
 struct ScrobbleArgs {
     #[clap(subcommand)]
@@ -1275,6 +2439,7 @@ enum ScrobbleCommand {
     List(ScrobbleListArgs),
     Create(ScrobbleCreateArgs),
     Delete(ScrobbleDeleteArgs),
+    Import(ScrobbleImportArgs),
 }
 
 #[derive(Debug, Parser)]
@@ -1283,72 +2448,453 @@ struct ScrobbleListArgs {
     params: ListParams,
 }
 
-async fn cmd_scrobble_list(args: ScrobbleListArgs) -> Result<()> {
+async fn cmd_scrobble_list(args: ScrobbleListArgs) -> Result<()> {
+    let mut client = create_client().await?;
+    let response = client
+        .scrobble_list(sonar_grpc::ScrobbleListRequest {
+            offset: args.params.offset,
+            count: args.params.limit,
+        })
+        .await?;
+    let scrobbles = response
+        .into_inner()
+        .scrobbles
+        .into_iter()
+        .map(Scrobble::from)
+        .collect::<Vec<_>>();
+    stdout_values(&scrobbles)?;
+    Ok(())
+}
+
+#[derive(Debug, Parser)]
+struct ScrobbleCreateArgs {
+    user_id: sonar::UserId,
+
+    track_id: sonar::TrackId,
+
+    listen_at: u64,
+
+    listen_duration: u32,
+
+    listen_device: String,
+}
+
+async fn cmd_scrobble_create(args: ScrobbleCreateArgs) -> Result<()> {
+    let mut client = create_client().await?;
+    let response = client
+        .scrobble_create(sonar_grpc::ScrobbleCreateRequest {
+            user_id: args.user_id.to_string(),
+            track_id: args.track_id.to_string(),
+            listen_at: Some(prost_types::Timestamp {
+                seconds: args.listen_at as i64,
+                nanos: 0,
+            }),
+            listen_duration: Some(prost_types::Duration {
+                seconds: args.listen_duration as i64,
+                nanos: 0,
+            }),
+            listen_device: args.listen_device,
+            ..Default::default()
+        })
+        .await?;
+    let scrobble = Scrobble::from(response.into_inner());
+    stdout_value(scrobble)?;
+    Ok(())
+}
+
+#[derive(Debug, Parser)]
+struct ScrobbleDeleteArgs {
+    id: sonar::ScrobbleId,
+}
+
+async fn cmd_scrobble_delete(args: ScrobbleDeleteArgs) -> Result<()> {
+    let mut client = create_client().await?;
+    client
+        .scrobble_delete(sonar_grpc::ScrobbleDeleteRequest {
+            scrobble_id: args.id.to_string(),
+        })
+        .await?;
+    Ok(())
+}
+
+#[derive(Debug, Parser)]
+struct ScrobbleImportArgs {
+    /// format of the export file: listenbrainz, lastfm-json or lastfm-csv
+    #[clap(long)]
+    format: sonar::ScrobbleImportFormat,
+
+    /// identifier of the scrobbler the listens were exported from.
+    /// defaults to listenbrainz or lastfm depending on the format.
+    #[clap(long)]
+    scrobbler: Option<String>,
+
+    #[clap(long, default_value = "import")]
+    device: String,
+
+    /// user to import the scrobbles for, defaults to the logged in user
+    #[clap(long)]
+    user: Option<sonar::UserId>,
+
+    path: PathBuf,
+}
+
+async fn cmd_scrobble_import(args: ScrobbleImportArgs) -> Result<()> {
+    const BATCH_SIZE: usize = 1000;
+
+    let mut client = create_client().await?;
+    let user_id = match args.user {
+        Some(user_id) => user_id.to_string(),
+        None => auth_read().await?.0,
+    };
+    let scrobbler = args.scrobbler.unwrap_or_else(|| match args.format {
+        sonar::ScrobbleImportFormat::ListenBrainz => "listenbrainz".to_string(),
+        sonar::ScrobbleImportFormat::LastFmJson | sonar::ScrobbleImportFormat::LastFmCsv => {
+            "lastfm".to_string()
+        }
+    });
+    let content = tokio::fs::read_to_string(&args.path)
+        .await
+        .context("reading scrobble export")?;
+    let entries = args.format.parse(&content)?;
+
+    let mut imported = 0;
+    let mut duplicates = 0;
+    let mut unmatched = Vec::new();
+    for batch in entries.chunks(BATCH_SIZE) {
+        let entries = batch
+            .iter()
+            .map(|entry| sonar_grpc::ScrobbleImportEntry {
+                artist: entry.artist.clone(),
+                album: entry.album.clone(),
+                title: entry.title.clone(),
+                duration: entry.duration.map(|d| Duration {
+                    seconds: d.as_secs() as i64,
+                    nanos: d.subsec_nanos() as i32,
+                }),
+                recording_mbid: entry.recording_mbid.clone(),
+                listen_at: Some(prost_types::Timestamp {
+                    seconds: entry.listen_at.seconds() as i64,
+                    nanos: 0,
+                }),
+            })
+            .collect();
+        let response = client
+            .scrobble_import(sonar_grpc::ScrobbleImportRequest {
+                user_id: user_id.clone(),
+                scrobbler: Some(scrobbler.clone()),
+                listen_device: args.device.clone(),
+                entries,
+            })
+            .await?
+            .into_inner();
+        imported += response.imported;
+        duplicates += response.duplicates;
+        unmatched.extend(
+            response
+                .unmatched
+                .into_iter()
+                .map(ScrobbleImportEntry::from),
+        );
+    }
+
+    eprintln!(
+        "imported {} scrobbles, {} duplicates, {} unmatched",
+        imported,
+        duplicates,
+        unmatched.len()
+    );
+    stdout_values(&unmatched)?;
+    Ok(())
+}
+
+#[derive(Debug, Parser)]
+struct StatsArgs {
+    /// period ending now to compute the stats for: week, month, year or all-time
+    #[clap(long, default_value = "all-time")]
+    period: sonar::StatsPeriod,
+
+    /// unix timestamp of the start of the range, overrides the period
+    #[clap(long)]
+    since: Option<u64>,
+
+    /// unix timestamp of the end of the range
+    #[clap(long)]
+    until: Option<u64>,
+
+    /// maximum number of entries in each top list
+    #[clap(long)]
+    limit: Option<u32>,
+
+    /// user to compute the stats for, defaults to the logged in user
+    #[clap(long)]
+    user: Option<sonar::UserId>,
+}
+
+async fn cmd_stats(args: StatsArgs) -> Result<()> {
+    let mut client = create_client().await?;
+    let user_id = match args.user {
+        Some(user_id) => user_id.to_string(),
+        None => auth_read().await?.0,
+    };
+    let since = args
+        .since
+        .map(sonar::Timestamp::from_seconds)
+        .or_else(|| args.period.since(sonar::Timestamp::now()));
+    let utc_offset = sonar::chrono::Local::now().offset().local_minus_utc();
+    let response = client
+        .stats_get(sonar_grpc::StatsGetRequest {
+            user_id,
+            since: since.map(|t| prost_types::Timestamp {
+                seconds: t.seconds() as i64,
+                nanos: 0,
+            }),
+            until: args.until.map(|t| prost_types::Timestamp {
+                seconds: t as i64,
+                nanos: 0,
+            }),
+            limit: args.limit,
+            utc_offset,
+        })
+        .await?;
+    stdout_value(Stats::from(response.into_inner()))?;
+    Ok(())
+}
+
+#[derive(Debug, Parser)]
+struct RecommendArgs {
+    #[clap(subcommand)]
+    command: RecommendCommand,
+}
+
+#[derive(Debug, Parser)]
+enum RecommendCommand {
+    Tracks(RecommendTracksArgs),
+    Artists(RecommendArtistsArgs),
+    DailyMix(RecommendDailyMixArgs),
+}
+
+#[derive(Debug, Parser)]
+struct RecommendTracksArgs {
+    /// maximum number of tracks
+    #[clap(long)]
+    limit: Option<u32>,
+
+    /// track, album or artist to find similar tracks for
+    id: sonar::SonarId,
+}
+
+async fn cmd_recommend_tracks(args: RecommendTracksArgs) -> Result<()> {
+    let mut client = create_client().await?;
+    let response = client
+        .similar_track_list(sonar_grpc::SimilarTrackListRequest {
+            id: args.id.to_string(),
+            limit: args.limit,
+        })
+        .await?;
+    let tracks = response
+        .into_inner()
+        .tracks
+        .into_iter()
+        .map(Track::from)
+        .collect::<Vec<_>>();
+    stdout_values(&tracks)?;
+    Ok(())
+}
+
+#[derive(Debug, Parser)]
+struct RecommendArtistsArgs {
+    /// maximum number of artists
+    #[clap(long)]
+    limit: Option<u32>,
+
+    artist: sonar::ArtistId,
+}
+
+async fn cmd_recommend_artists(args: RecommendArtistsArgs) -> Result<()> {
+    let mut client = create_client().await?;
+    let response = client
+        .similar_artist_list(sonar_grpc::SimilarArtistListRequest {
+            artist_id: args.artist.to_string(),
+            limit: args.limit,
+        })
+        .await?;
+    let artists = response
+        .into_inner()
+        .artists
+        .into_iter()
+        .map(Artist::from)
+        .collect::<Vec<_>>();
+    stdout_values(&artists)?;
+    Ok(())
+}
+
+#[derive(Debug, Parser)]
+struct RecommendDailyMixArgs {
+    /// maximum number of mixes to generate
+    #[clap(long)]
+    mixes: Option<u32>,
+
+    /// maximum number of tracks in each mix
+    #[clap(long)]
+    tracks: Option<u32>,
+
+    /// user to regenerate the daily mixes for, defaults to the logged in user
+    #[clap(long)]
+    user: Option<sonar::UserId>,
+}
+
+async fn cmd_recommend_daily_mix(args: RecommendDailyMixArgs) -> Result<()> {
+    let mut client = create_client().await?;
+    let user_id = match args.user {
+        Some(user_id) => user_id.to_string(),
+        None => auth_read().await?.0,
+    };
+    let response = client
+        .daily_mix_refresh(sonar_grpc::DailyMixRefreshRequest {
+            user_id,
+            mixes: args.mixes,
+            tracks: args.tracks,
+        })
+        .await?;
+    let playlists = response
+        .into_inner()
+        .playlists
+        .into_iter()
+        .map(Playlist::from)
+        .collect::<Vec<_>>();
+    stdout_values(&playlists)?;
+    Ok(())
+}
+
+#[derive(Debug, Parser)]
+struct RadioArgs {
+    #[clap(subcommand)]
+    command: RadioCommand,
+}
+
+#[derive(Debug, Parser)]
+enum RadioCommand {
+    Get(RadioGetArgs),
+    Create(RadioCreateArgs),
+    Delete(RadioDeleteArgs),
+    Next(RadioNextArgs),
+    Stream(RadioStreamArgs),
+}
+
+#[derive(Debug, Parser)]
+struct RadioGetArgs {
+    radio: sonar::RadioId,
+}
+
+async fn cmd_radio_get(args: RadioGetArgs) -> Result<()> {
+    let mut client = create_client().await?;
+    let response = client
+        .radio_get(sonar_grpc::RadioGetRequest {
+            radio_id: args.radio.to_string(),
+        })
+        .await?;
+    stdout_value(Radio::from(response.into_inner()))?;
+    Ok(())
+}
+
+#[derive(Debug, Parser)]
+struct RadioCreateArgs {
+    /// user to create the radio for, defaults to the logged in user
+    #[clap(long)]
+    user: Option<sonar::UserId>,
+
+    /// track, album or artist id or genre:<name>
+    seed: sonar::RadioSeed,
+}
+
+async fn cmd_radio_create(args: RadioCreateArgs) -> Result<()> {
     let mut client = create_client().await?;
+    let user_id = match args.user {
+        Some(user_id) => user_id.to_string(),
+        None => auth_read().await?.0,
+    };
     let response = client
-        .scrobble_list(sonar_grpc::ScrobbleListRequest {
-            offset: args.params.offset,
-            count: args.params.limit,
+        .radio_create(sonar_grpc::RadioCreateRequest {
+            user_id,
+            seed: args.seed.to_string(),
         })
         .await?;
-    let scrobbles = response
-        .into_inner()
-        .scrobbles
-        .into_iter()
-        .map(Scrobble::from)
-        .collect::<Vec<_>>();
-    stdout_values(&scrobbles)?;
+    stdout_value(Radio::from(response.into_inner()))?;
     Ok(())
 }
 
 #[derive(Debug, Parser)]
-struct ScrobbleCreateArgs {
-    user_id: sonar::UserId,
-
-    track_id: sonar::TrackId,
+struct RadioDeleteArgs {
+    radio: sonar::RadioId,
+}
 
-    listen_at: u64,
+async fn cmd_radio_delete(args: RadioDeleteArgs) -> Result<()> {
+    let mut client = create_client().await?;
+    client
+        .radio_delete(sonar_grpc::RadioDeleteRequest {
+            radio_id: args.radio.to_string(),
+        })
+        .await?;
+    Ok(())
+}
 
-    listen_duration: u32,
+#[derive(Debug, Parser)]
+struct RadioNextArgs {
+    /// number of tracks
+    #[clap(long)]
+    count: Option<u32>,
 
-    listen_device: String,
+    radio: sonar::RadioId,
 }
 
-async fn cmd_scrobble_create(args: ScrobbleCreateArgs) -> Result<()> {
+async fn cmd_radio_next(args: RadioNextArgs) -> Result<()> {
     let mut client = create_client().await?;
     let response = client
-        .scrobble_create(sonar_grpc::ScrobbleCreateRequest {
-            user_id: args.user_id.to_string(),
-            track_id: args.track_id.to_string(),
-            listen_at: Some(prost_types::Timestamp {
-                seconds: args.listen_at as i64,
-                nanos: 0,
-            }),
-            listen_duration: Some(prost_types::Duration {
-                seconds: args.listen_duration as i64,
-                nanos: 0,
-            }),
-            listen_device: args.listen_device,
-            ..Default::default()
+        .radio_next(sonar_grpc::RadioNextRequest {
+            radio_id: args.radio.to_string(),
+            count: args.count,
         })
         .await?;
-    let scrobble = Scrobble::from(response.into_inner());
-    stdout_value(scrobble)?;
+    let tracks = response
+        .into_inner()
+        .tracks
+        .into_iter()
+        .map(Track::from)
+        .collect::<Vec<_>>();
+    stdout_values(&tracks)?;
     Ok(())
 }
 
 #[derive(Debug, Parser)]
-struct ScrobbleDeleteArgs {
-    id: sonar::ScrobbleId,
+struct RadioStreamArgs {
+    /// number of tracks generated at a time
+    #[clap(long)]
+    batch_size: Option<u32>,
+
+    /// stop after this many tracks
+    #[clap(long)]
+    limit: Option<usize>,
+
+    radio: sonar::RadioId,
 }
 
-async fn cmd_scrobble_delete(args: ScrobbleDeleteArgs) -> Result<()> {
+async fn cmd_radio_stream(args: RadioStreamArgs) -> Result<()> {
     let mut client = create_client().await?;
-    client
-        .scrobble_delete(sonar_grpc::ScrobbleDeleteRequest {
-            scrobble_id: args.id.to_string(),
+    let response = client
+        .radio_stream(sonar_grpc::RadioStreamRequest {
+            radio_id: args.radio.to_string(),
+            batch_size: args.batch_size,
         })
         .await?;
+    let mut stream = response.into_inner();
+    let mut count = 0;
+    while let Some(track) = stream.next().await {
+        if args.limit.is_some_and(|limit| count >= limit) {
+            break;
+        }
+        stdout_value(Track::from(track?))?;
+        count += 1;
+    }
     Ok(())
 }
 
@@ -1735,77 +3281,404 @@ async fn cmd_pin_unset(args: PinUnsetArgs) -> Result<()> {
 
 #[derive(Debug, Parser)]
 struct SearchArgs {
+    /// words to search for, optionally with filters like `artist:name`, `genre:rock`,
+    /// `year:1990..1999`, `favorite:true`, `duration:>300` or `prop:key=value`.
+    /// prefix a clause with `-` to exclude its matches.
     query: String,
 
     #[clap(long, default_value = "50")]
     limit: u32,
 
     #[clap(long)]
-    artist: bool,
+    artist: bool,
+
+    #[clap(long)]
+    album: bool,
+
+    #[clap(long)]
+    track: bool,
+
+    #[clap(long)]
+    playlist: bool,
+
+    /// only return media from this library
+    #[clap(long)]
+    library: Option<sonar::LibraryId>,
+}
+
+async fn cmd_search(args: SearchArgs) -> Result<()> {
+    let mut client = create_client().await?;
+    let (user_id, _) = auth_read().await?;
+
+    let request = sonar_grpc::SearchRequest {
+        user_id,
+        query: args.query,
+        limit: Some(args.limit),
+        flags: if args.artist || args.album || args.track || args.playlist {
+            let mut flags = 0;
+            if args.artist {
+                flags |= sonar_grpc::search_request::Flags::FlagArtist as u32;
+            }
+            if args.album {
+                flags |= sonar_grpc::search_request::Flags::FlagAlbum as u32;
+            }
+            if args.track {
+                flags |= sonar_grpc::search_request::Flags::FlagTrack as u32;
+            }
+            if args.playlist {
+                flags |= sonar_grpc::search_request::Flags::FlagPlaylist as u32;
+            }
+            Some(flags)
+        } else {
+            None
+        },
+        library_id: args.library.map(|id| id.to_string()),
+    };
+
+    let response = client.search(request).await?;
+    let response = response.into_inner();
+    let mut results = vec![];
+    for result in response.results {
+        results.push(match result.result.unwrap() {
+            sonar_grpc::search_result::Result::Artist(artist) => {
+                SearchResult::Artist(Artist::from(artist))
+            }
+            sonar_grpc::search_result::Result::Album(album) => {
+                SearchResult::Album(Album::from(album))
+            }
+            sonar_grpc::search_result::Result::Track(track) => {
+                SearchResult::Track(Track::from(track))
+            }
+            sonar_grpc::search_result::Result::Playlist(playlist) => {
+                SearchResult::Playlist(Playlist::from(playlist))
+            }
+        });
+    }
+
+    let results = SearchResults(results);
+    stdout_value(results)?;
+
+    Ok(())
+}
+
+#[derive(Debug, Parser)]
+struct WatchArgs {
+    /// only receive events of this kind, like `track-created` or `import-finished`
+    #[clap(long)]
+    kind: Vec<sonar::EventKind>,
+
+    /// only receive events about this id
+    #[clap(long)]
+    id: Vec<sonar::SonarId>,
+
+    /// resume after this sequence number
+    #[clap(long)]
+    since: Option<u64>,
+}
+
+async fn cmd_watch(args: WatchArgs) -> Result<()> {
+    let mut client = create_client().await?;
+    let response = client
+        .watch(sonar_grpc::WatchRequest {
+            kinds: args.kind.iter().map(ToString::to_string).collect(),
+            ids: args.id.iter().map(ToString::to_string).collect(),
+            since: args.since,
+        })
+        .await?;
+    let mut stream = response.into_inner();
+    while let Some(event) = stream.next().await {
+        stdout_value(Event::from(event?))?;
+    }
+    Ok(())
+}
+
+#[derive(Debug, Parser)]
+struct LibraryArgs {
+    #[clap(subcommand)]
+    command: LibraryCommand,
+}
+
+#[derive(Debug, Parser)]
+enum LibraryCommand {
+    /// list the libraries you have access to
+    List,
+    Create(LibraryCreateArgs),
+    Update(LibraryUpdateArgs),
+    /// delete an empty library
+    Delete(LibraryDeleteArgs),
+    /// list the users with access to a library
+    Users(LibraryUsersArgs),
+    /// give a user access to a library
+    Grant(LibraryGrantArgs),
+    /// remove a user's access to a library
+    Revoke(LibraryRevokeArgs),
+}
+
+async fn cmd_library_list() -> Result<()> {
+    let mut client = create_client().await?;
+    let response = client
+        .library_list(sonar_grpc::LibraryListRequest {})
+        .await?;
+    let libraries = response
+        .into_inner()
+        .libraries
+        .into_iter()
+        .map(Library::from)
+        .collect::<Vec<_>>();
+    stdout_values(&libraries)?;
+    Ok(())
+}
+
+#[derive(Debug, Parser)]
+struct LibraryCreateArgs {
+    name: String,
+}
+
+async fn cmd_library_create(args: LibraryCreateArgs) -> Result<()> {
+    let mut client = create_client().await?;
+    let response = client
+        .library_create(sonar_grpc::LibraryCreateRequest { name: args.name })
+        .await?;
+    stdout_value(Library::from(response.into_inner()))?;
+    Ok(())
+}
+
+#[derive(Debug, Parser)]
+struct LibraryUpdateArgs {
+    #[clap(long)]
+    name: Option<String>,
+
+    library: sonar::LibraryId,
+}
+
+async fn cmd_library_update(args: LibraryUpdateArgs) -> Result<()> {
+    let mut client = create_client().await?;
+    let response = client
+        .library_update(sonar_grpc::LibraryUpdateRequest {
+            library_id: args.library.to_string(),
+            name: args.name,
+        })
+        .await?;
+    stdout_value(Library::from(response.into_inner()))?;
+    Ok(())
+}
+
+#[derive(Debug, Parser)]
+struct LibraryDeleteArgs {
+    library: sonar::LibraryId,
+}
+
+async fn cmd_library_delete(args: LibraryDeleteArgs) -> Result<()> {
+    let mut client = create_client().await?;
+    client
+        .library_delete(sonar_grpc::LibraryDeleteRequest {
+            library_id: args.library.to_string(),
+        })
+        .await?;
+    Ok(())
+}
+
+#[derive(Debug, Parser)]
+struct LibraryUsersArgs {
+    library: sonar::LibraryId,
+}
+
+async fn cmd_library_users(args: LibraryUsersArgs) -> Result<()> {
+    let mut client = create_client().await?;
+    let response = client
+        .library_user_list(sonar_grpc::LibraryUserListRequest {
+            library_id: args.library.to_string(),
+        })
+        .await?;
+    stdout_values(&response.into_inner().user_ids)?;
+    Ok(())
+}
+
+#[derive(Debug, Parser)]
+struct LibraryGrantArgs {
+    library: sonar::LibraryId,
+    user: sonar::UserId,
+}
+
+async fn cmd_library_grant(args: LibraryGrantArgs) -> Result<()> {
+    let mut client = create_client().await?;
+    client
+        .library_grant(sonar_grpc::LibraryGrantRequest {
+            library_id: args.library.to_string(),
+            user_id: args.user.to_string(),
+        })
+        .await?;
+    Ok(())
+}
+
+#[derive(Debug, Parser)]
+struct LibraryRevokeArgs {
+    library: sonar::LibraryId,
+    user: sonar::UserId,
+}
+
+async fn cmd_library_revoke(args: LibraryRevokeArgs) -> Result<()> {
+    let mut client = create_client().await?;
+    client
+        .library_revoke(sonar_grpc::LibraryRevokeRequest {
+            library_id: args.library.to_string(),
+            user_id: args.user.to_string(),
+        })
+        .await?;
+    Ok(())
+}
+
+#[derive(Debug, Parser)]
+struct WebhookArgs {
+    #[clap(subcommand)]
+    command: WebhookCommand,
+}
+
+#[derive(Debug, Parser)]
+enum WebhookCommand {
+    List,
+    Get(WebhookGetArgs),
+    Create(WebhookCreateArgs),
+    Update(WebhookUpdateArgs),
+    Delete(WebhookDeleteArgs),
+    /// list the most recent deliveries of a webhook
+    Deliveries(WebhookDeliveriesArgs),
+}
+
+async fn cmd_webhook_list() -> Result<()> {
+    let mut client = create_client().await?;
+    let response = client
+        .webhook_list(sonar_grpc::WebhookListRequest {})
+        .await?;
+    let webhooks = response
+        .into_inner()
+        .webhooks
+        .into_iter()
+        .map(Webhook::from)
+        .collect::<Vec<_>>();
+    stdout_values(&webhooks)?;
+    Ok(())
+}
+
+#[derive(Debug, Parser)]
+struct WebhookGetArgs {
+    webhook: sonar::WebhookId,
+}
+
+async fn cmd_webhook_get(args: WebhookGetArgs) -> Result<()> {
+    let mut client = create_client().await?;
+    let response = client
+        .webhook_get(sonar_grpc::WebhookGetRequest {
+            webhook_id: args.webhook.to_string(),
+        })
+        .await?;
+    stdout_value(Webhook::from(response.into_inner()))?;
+    Ok(())
+}
+
+#[derive(Debug, Parser)]
+struct WebhookCreateArgs {
+    /// only deliver events of this kind, all events are delivered if none is given
+    #[clap(long)]
+    kind: Vec<sonar::EventKind>,
+
+    /// key used to sign the payloads, a random one is generated if not given
+    #[clap(long)]
+    secret: Option<String>,
+
+    url: String,
+}
+
+async fn cmd_webhook_create(args: WebhookCreateArgs) -> Result<()> {
+    let mut client = create_client().await?;
+    let response = client
+        .webhook_create(sonar_grpc::WebhookCreateRequest {
+            url: args.url,
+            kinds: args.kind.iter().map(ToString::to_string).collect(),
+            secret: args.secret,
+        })
+        .await?;
+    stdout_value(Webhook::from(response.into_inner()))?;
+    Ok(())
+}
+
+#[derive(Debug, Parser)]
+struct WebhookUpdateArgs {
+    #[clap(long)]
+    url: Option<String>,
 
+    /// replace the delivered event kinds
     #[clap(long)]
-    album: bool,
+    kind: Vec<sonar::EventKind>,
+
+    /// deliver events of every kind
+    #[clap(long, conflicts_with = "kind")]
+    all_kinds: bool,
 
     #[clap(long)]
-    track: bool,
+    secret: Option<String>,
 
     #[clap(long)]
-    playlist: bool,
+    enabled: Option<bool>,
+
+    webhook: sonar::WebhookId,
 }
 
-async fn cmd_search(args: SearchArgs) -> Result<()> {
+async fn cmd_webhook_update(args: WebhookUpdateArgs) -> Result<()> {
     let mut client = create_client().await?;
-    let (user_id, _) = auth_read().await?;
+    let response = client
+        .webhook_update(sonar_grpc::WebhookUpdateRequest {
+            webhook_id: args.webhook.to_string(),
+            url: args.url,
+            kinds: args.kind.iter().map(ToString::to_string).collect(),
+            all_kinds: args.all_kinds,
+            secret: args.secret,
+            enabled: args.enabled,
+        })
+        .await?;
+    stdout_value(Webhook::from(response.into_inner()))?;
+    Ok(())
+}
 
-    let request = sonar_grpc::SearchRequest {
-        user_id,
-        query: args.query,
-        limit: Some(args.limit),
-        flags: Some(
-            if args.artist || args.album || args.track || args.playlist {
-                let mut flags = 0;
-                if args.artist {
-                    flags |= sonar_grpc::search_request::Flags::FlagArtist as u32;
-                }
-                if args.album {
-                    flags |= sonar_grpc::search_request::Flags::FlagAlbum as u32;
-                }
-                if args.track {
-                    flags |= sonar_grpc::search_request::Flags::FlagTrack as u32;
-                }
-                if args.playlist {
-                    flags |= sonar_grpc::search_request::Flags::FlagPlaylist as u32;
-                }
-                flags
-            } else {
-                0
-            },
-        ),
-    };
+#[derive(Debug, Parser)]
+struct WebhookDeleteArgs {
+    webhook: sonar::WebhookId,
+}
 
-    let response = client.search(request).await?;
-    let response = response.into_inner();
-    let mut results = vec![];
-    for result in response.results {
-        results.push(match result.result.unwrap() {
-            sonar_grpc::search_result::Result::Artist(artist) => {
-                SearchResult::Artist(Artist::from(artist))
-            }
-            sonar_grpc::search_result::Result::Album(album) => {
-                SearchResult::Album(Album::from(album))
-            }
-            sonar_grpc::search_result::Result::Track(track) => {
-                SearchResult::Track(Track::from(track))
-            }
-            sonar_grpc::search_result::Result::Playlist(playlist) => {
-                SearchResult::Playlist(Playlist::from(playlist))
-            }
-        });
-    }
+async fn cmd_webhook_delete(args: WebhookDeleteArgs) -> Result<()> {
+    let mut client = create_client().await?;
+    client
+        .webhook_delete(sonar_grpc::WebhookDeleteRequest {
+            webhook_id: args.webhook.to_string(),
+        })
+        .await?;
+    Ok(())
+}
 
-    let results = SearchResults(results);
-    stdout_value(results)?;
+#[derive(Debug, Parser)]
+struct WebhookDeliveriesArgs {
+    #[clap(flatten)]
+    params: ListParams,
+
+    webhook: sonar::WebhookId,
+}
 
+async fn cmd_webhook_deliveries(args: WebhookDeliveriesArgs) -> Result<()> {
+    let mut client = create_client().await?;
+    let response = client
+        .webhook_delivery_list(sonar_grpc::WebhookDeliveryListRequest {
+            webhook_id: args.webhook.to_string(),
+            offset: args.params.offset,
+            count: args.params.limit,
+        })
+        .await?;
+    let deliveries = response
+        .into_inner()
+        .deliveries
+        .into_iter()
+        .map(WebhookDelivery::from)
+        .collect::<Vec<_>>();
+    stdout_values(&deliveries)?;
     Ok(())
 }
 
@@ -1863,6 +3736,9 @@ struct SubscriptionCreateArgs {
     external_id: Option<String>,
     #[clap(long)]
     media_type: Option<String>,
+    /// library the downloaded media is added to
+    #[clap(long)]
+    library: Option<sonar::LibraryId>,
 }
 
 async fn cmd_subscription_create(args: SubscriptionCreateArgs) -> Result<()> {
@@ -1882,6 +3758,7 @@ async fn cmd_subscription_create(args: SubscriptionCreateArgs) -> Result<()> {
             playlist: args.playlist,
             external_id: args.external_id,
             media_type: args.media_type,
+            library_id: args.library.map(|id| id.to_string()),
         })
         .await?;
     Ok(())
@@ -2027,6 +3904,10 @@ enum AdminCommand {
     User(AdminUserArgs),
     Playlist(AdminPlaylistArgs),
     MetadataPreview(AdminMetadataPreviewArgs),
+    Fsck(AdminFsckArgs),
+    Gc(AdminGcArgs),
+    Sweep(AdminSweepArgs),
+    Backup(AdminBackupArgs),
 }
 
 #[derive(Debug, Parser)]
@@ -2041,6 +3922,9 @@ enum AdminUserCommand {
     Create(AdminUserCreateArgs),
     Update(AdminUserUpdateArgs),
     Delete(AdminUserDeleteArgs),
+    Session(AdminUserSessionArgs),
+    ApiKey(AdminUserApiKeyArgs),
+    SubsonicPassword(AdminUserSubsonicPasswordArgs),
 }
 
 #[derive(Debug, Parser)]
@@ -2074,6 +3958,11 @@ struct AdminUserCreateArgs {
 
     #[clap(long)]
     admin: bool,
+
+    /// role of the user, like `download` or `edit-metadata`, the default roles are used if none
+    /// is given
+    #[clap(long)]
+    role: Vec<sonar::UserRole>,
 }
 
 async fn cmd_admin_user_create(args: AdminUserCreateArgs) -> Result<()> {
@@ -2097,6 +3986,7 @@ async fn cmd_admin_user_create(args: AdminUserCreateArgs) -> Result<()> {
             password: args.password,
             avatar_id: image_id,
             admin: Some(args.admin),
+            roles: args.role.iter().map(ToString::to_string).collect(),
         })
         .await?;
     println!("{:?}", response.into_inner());
@@ -2116,6 +4006,14 @@ struct AdminUserUpdateArgs {
 
     #[clap(long)]
     admin: Option<bool>,
+
+    /// replace the roles of the user
+    #[clap(long)]
+    role: Vec<sonar::UserRole>,
+
+    /// remove every role of the user
+    #[clap(long, conflicts_with = "role")]
+    clear_roles: bool,
 }
 
 async fn cmd_admin_user_update(args: AdminUserUpdateArgs) -> Result<()> {
@@ -2130,6 +4028,8 @@ async fn cmd_admin_user_update(args: AdminUserUpdateArgs) -> Result<()> {
             password: args.password,
             avatar_id: avatar_id.map(|x| x.to_string()),
             admin: args.admin,
+            roles: args.role.iter().map(ToString::to_string).collect(),
+            clear_roles: args.clear_roles,
         })
         .await?;
     Ok(())
@@ -2151,6 +4051,154 @@ async fn cmd_admin_user_delete(args: AdminUserDeleteArgs) -> Result<()> {
     Ok(())
 }
 
+#[derive(Debug, Parser)]
+struct AdminUserSessionArgs {
+    #[clap(subcommand)]
+    command: AdminUserSessionCommand,
+}
+
+#[derive(Debug, Parser)]
+enum AdminUserSessionCommand {
+    List(AdminUserSessionListArgs),
+    Revoke(AdminUserSessionRevokeArgs),
+}
+
+#[derive(Debug, Parser)]
+struct AdminUserSessionListArgs {
+    userid: sonar::UserId,
+}
+
+async fn cmd_admin_user_session_list(args: AdminUserSessionListArgs) -> Result<()> {
+    let mut client = create_client().await?;
+    let response = client
+        .user_session_list(sonar_grpc::UserSessionListRequest {
+            user_id: args.userid.to_string(),
+        })
+        .await?;
+    let sessions = response
+        .into_inner()
+        .sessions
+        .into_iter()
+        .map(UserSession::from)
+        .collect::<Vec<_>>();
+    stdout_values(&sessions)?;
+    Ok(())
+}
+
+#[derive(Debug, Parser)]
+struct AdminUserSessionRevokeArgs {
+    id: sonar::UserSessionId,
+}
+
+async fn cmd_admin_user_session_revoke(args: AdminUserSessionRevokeArgs) -> Result<()> {
+    let mut client = create_client().await?;
+    client
+        .user_session_revoke(sonar_grpc::UserSessionRevokeRequest {
+            session_id: args.id.to_string(),
+        })
+        .await?;
+    Ok(())
+}
+
+/// set the password used for opensubsonic token authentication
+#[derive(Debug, Parser)]
+struct AdminUserSubsonicPasswordArgs {
+    userid: sonar::UserId,
+
+    #[clap(required_unless_present = "disable")]
+    password: Option<String>,
+
+    /// disable opensubsonic token authentication for the user
+    #[clap(long, conflicts_with = "password")]
+    disable: bool,
+}
+
+async fn cmd_admin_user_subsonic_password(args: AdminUserSubsonicPasswordArgs) -> Result<()> {
+    let mut client = create_client().await?;
+    client
+        .user_subsonic_password_set(sonar_grpc::UserSubsonicPasswordSetRequest {
+            user_id: args.userid.to_string(),
+            password: args.password,
+        })
+        .await?;
+    Ok(())
+}
+
+#[derive(Debug, Parser)]
+struct AdminUserApiKeyArgs {
+    #[clap(subcommand)]
+    command: AdminUserApiKeyCommand,
+}
+
+#[derive(Debug, Parser)]
+enum AdminUserApiKeyCommand {
+    List(AdminUserApiKeyListArgs),
+    Create(AdminUserApiKeyCreateArgs),
+    Revoke(AdminUserApiKeyRevokeArgs),
+}
+
+#[derive(Debug, Parser)]
+struct AdminUserApiKeyListArgs {
+    userid: sonar::UserId,
+}
+
+async fn cmd_admin_user_api_key_list(args: AdminUserApiKeyListArgs) -> Result<()> {
+    let mut client = create_client().await?;
+    let response = client
+        .user_api_key_list(sonar_grpc::UserApiKeyListRequest {
+            user_id: args.userid.to_string(),
+        })
+        .await?;
+    let api_keys = response
+        .into_inner()
+        .api_keys
+        .into_iter()
+        .map(UserApiKey::from)
+        .collect::<Vec<_>>();
+    stdout_values(&api_keys)?;
+    Ok(())
+}
+
+#[derive(Debug, Parser)]
+struct AdminUserApiKeyCreateArgs {
+    userid: sonar::UserId,
+
+    name: String,
+
+    /// one of read-only, streaming or admin
+    #[clap(long)]
+    scope: Option<sonar::ApiKeyScope>,
+}
+
+async fn cmd_admin_user_api_key_create(args: AdminUserApiKeyCreateArgs) -> Result<()> {
+    let mut client = create_client().await?;
+    let response = client
+        .user_api_key_create(sonar_grpc::UserApiKeyCreateRequest {
+            user_id: args.userid.to_string(),
+            name: args.name,
+            scope: args.scope.map(|s| s.to_string()),
+        })
+        .await?;
+    // the key can't be retrieved after this
+    println!("{}", response.into_inner().key);
+    Ok(())
+}
+
+#[derive(Debug, Parser)]
+struct AdminUserApiKeyRevokeArgs {
+    id: sonar::ApiKeyId,
+}
+
+async fn cmd_admin_user_api_key_revoke(args: AdminUserApiKeyRevokeArgs) -> Result<()> {
+    let mut client = create_client().await?;
+    client
+        .user_api_key_revoke(sonar_grpc::UserApiKeyRevokeRequest {
+            api_key_id: args.id.to_string(),
+        })
+        .await?;
+    Ok(())
+}
+
 #[derive(Debug, Parser)]
 struct AdminPlaylistArgs {
     #[clap(subcommand)]
@@ -2259,6 +4307,125 @@ async fn cmd_admin_metadata_preview(args: AdminMetadataPreviewArgs) -> Result<()
     Ok(())
 }
 
+#[derive(Debug, Parser)]
+struct AdminFsckArgs {
+    /// what to do with files that fail verification: none, quarantine or delete.
+    #[clap(long, default_value = "none")]
+    repair: sonar::FsckRepair,
+}
+
+async fn cmd_admin_fsck(args: AdminFsckArgs) -> Result<()> {
+    let mut client = create_client().await?;
+    let response = client
+        .blob_fsck(sonar_grpc::BlobFsckRequest {
+            repair: Some(args.repair.to_string()),
+        })
+        .await?;
+
+    let mut stream = response.into_inner();
+    let mut checked = 0;
+    let mut issues = 0;
+    while let Some(message) = stream.next().await {
+        let message = message?;
+        checked = message.checked;
+        eprint!("\rchecked {}/{}", message.checked, message.total);
+        if let Some(issue) = message.issue {
+            eprintln!();
+            issues += 1;
+            stdout_value(FsckIssue::from(issue))?;
+        }
+    }
+    eprintln!();
+    eprintln!("checked {} blobs, found {} issues", checked, issues);
+    Ok(())
+}
+
+#[derive(Debug, Parser)]
+struct AdminGcArgs {
+    /// only list the items that would be deleted
+    #[clap(long)]
+    dry_run: bool,
+
+    /// number of seconds since an item was created before it can be deleted
+    #[clap(long)]
+    min_age: Option<u64>,
+}
+
+async fn cmd_admin_gc(args: AdminGcArgs) -> Result<()> {
+    let mut client = create_client().await?;
+    let response = client
+        .garbage_collect(sonar_grpc::GarbageCollectRequest {
+            min_age: args.min_age.map(|s| Duration {
+                seconds: s as i64,
+                nanos: 0,
+            }),
+            dry_run: args.dry_run,
+        })
+        .await?;
+    for item_id in response.into_inner().item_ids {
+        println!("{}", item_id);
+    }
+    Ok(())
+}
+
+#[derive(Debug, Parser)]
+struct AdminSweepArgs {
+    /// only list the images that would be deleted
+    #[clap(long)]
+    dry_run: bool,
+
+    /// number of seconds since an image was created before it can be deleted
+    #[clap(long)]
+    min_age: Option<u64>,
+}
+
+async fn cmd_admin_sweep(args: AdminSweepArgs) -> Result<()> {
+    let mut client = create_client().await?;
+    let response = client
+        .orphan_sweep(sonar_grpc::OrphanSweepRequest {
+            min_age: args.min_age.map(|s| Duration {
+                seconds: s as i64,
+                nanos: 0,
+            }),
+            dry_run: args.dry_run,
+        })
+        .await?
+        .into_inner();
+    for image_id in response.image_ids {
+        println!("{}", image_id);
+    }
+    eprintln!(
+        "removed {} blobs, reclaimed {} bytes",
+        response.blob_count, response.reclaimed_bytes
+    );
+    Ok(())
+}
+
+#[derive(Debug, Parser)]
+struct AdminBackupArgs {
+    /// directory on the server to write the backup to.
+    /// blobs already present in the directory from a previous backup are not copied again.
+    path: PathBuf,
+}
+
+async fn cmd_admin_backup(args: AdminBackupArgs) -> Result<()> {
+    let mut client = create_client().await?;
+    let response = client
+        .backup(sonar_grpc::BackupRequest {
+            path: args.path.display().to_string(),
+        })
+        .await?
+        .into_inner();
+    eprintln!(
+        "backed up {} blobs, copied {} blobs ({} bytes)",
+        response.blob_count, response.blobs_copied, response.bytes_copied
+    );
+    for key in response.blobs_skipped {
+        eprintln!("skipped blob {key}, it was deleted during the backup");
+    }
+    Ok(())
+}
+
 #[derive(Debug, Parser)]
 struct ServerArgs {
     #[clap(long, default_value = "0.0.0.0:3000", env = "SONAR_ADDRESS")]
@@ -2305,6 +4472,45 @@ struct ServerArgs {
     meilisearch_endpoint: Option<String>,
     #[clap(long, env = "SONAR_MEILISEARCH_KEY")]
     meilisearch_key: Option<String>,
+
+    /// use an embedded tantivy search index, stored in the data directory
+    #[clap(long, env = "SONAR_SEARCH_TANTIVY")]
+    search_tantivy: bool,
+
+    /// number of seconds between garbage collection and orphan sweep runs, disabled if not set
+    #[clap(long, env = "SONAR_GC_INTERVAL")]
+    gc_interval: Option<u64>,
+
+    /// number of seconds since an item was created before it can be garbage collected
+    #[clap(long, env = "SONAR_GC_MIN_AGE")]
+    gc_min_age: Option<u64>,
+}
+
+#[derive(Debug, Parser)]
+struct RestoreArgs {
+    /// directory of the backup to restore
+    archive: PathBuf,
+
+    /// data directory to restore into, must not contain a database
+    #[clap(long, default_value = ".", env = "SONAR_DATA_DIR")]
+    data_dir: PathBuf,
+}
+
+async fn cmd_restore(args: RestoreArgs) -> Result<()> {
+    let database_path = args.data_dir.join("sonar.db");
+    let storage_backend = sonar::StorageBackend::Filesystem {
+        path: args.data_dir.join("storage"),
+    };
+    let report = sonar::backup_restore(&args.archive, &database_path, storage_backend)
+        .await
+        .context("restoring backup")?;
+    eprintln!(
+        "restored {} blobs ({} bytes) into {}",
+        report.blobs,
+        report.bytes,
+        args.data_dir.display()
+    );
+    Ok(())
 }
 
 #[derive(Debug, Parser)]
@@ -2315,6 +4521,9 @@ struct ImportArgs {
     /// the id of the album to upload to
     #[clap(long)]
     album: Option<String>,
+    /// the id of the library to import new artists to
+    #[clap(long)]
+    library: Option<sonar::LibraryId>,
     paths: Vec<PathBuf>,
 }
 
@@ -2336,6 +4545,7 @@ async fn cmd_import(args: ImportArgs) -> Result<()> {
         let permit = semaphore.clone().acquire_owned().await;
         let artist = args.artist.clone();
         let album = args.album.clone();
+        let library = args.library.map(|id| id.to_string());
         let handle = tokio::spawn(async move {
             tracing::info!("importing {}", filepath.display());
             let _permit = permit;
@@ -2347,6 +4557,7 @@ async fn cmd_import(args: ImportArgs) -> Result<()> {
                     filepath: Some(filepath.display().to_string()),
                     artist_id: artist.clone(),
                     album_id: album.clone(),
+                    library_id: library.clone(),
                 });
             let response = client.import(stream).await?;
             let track = response.into_inner();
@@ -2393,6 +4604,10 @@ async fn cmd_server(args: ServerArgs) -> Result<()> {
     let search_backend =
         if let (Some(url), Some(key)) = (args.meilisearch_endpoint, args.meilisearch_key) {
             sonar::SearchBackend::Meilisearch { endpoint: url, key }
+        } else if args.search_tantivy {
+            sonar::SearchBackend::Tantivy {
+                path: data_dir.join("search"),
+            }
         } else {
             sonar::SearchBackend::BuiltIn
         };
@@ -2413,6 +4628,14 @@ async fn cmd_server(args: ServerArgs) -> Result<()> {
             .context("registering listenbrainz scrobbler")?;
     }
 
+    if let Some(interval) = args.gc_interval {
+        let mut params = sonar::GarbageCollectParams::default();
+        if let Some(min_age) = args.gc_min_age {
+            params.min_age = std::time::Duration::from_secs(min_age);
+        }
+        config.enable_garbage_collection(std::time::Duration::from_secs(interval), params);
+    }
+
     config
         .register_external_service(2, "musicbrainz", MusicBrainzService::default())
         .context("registering musicbrainz external service")?;
@@ -2467,6 +4690,7 @@ async fn cmd_server(args: ServerArgs) -> Result<()> {
                     password: default_password,
                     avatar: Default::default(),
                     admin: true,
+                    roles: Default::default(),
                 },
             )
             .await?;
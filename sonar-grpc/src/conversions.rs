@@ -6,6 +6,82 @@ impl From<sonar::User> for User {
             user_id: value.id.to_string(),
             username: From::from(value.username),
             avatar_id: value.avatar.map(|id| id.to_string()),
+            admin: value.admin,
+            roles: value.roles.iter().map(ToString::to_string).collect(),
+        }
+    }
+}
+
+fn parse_user_roles(roles: Vec<String>) -> Result<Vec<sonar::UserRole>, tonic::Status> {
+    roles
+        .iter()
+        .map(|role| role.parse::<sonar::UserRole>().m())
+        .collect()
+}
+
+impl TryFrom<UserCreateRequest> for sonar::UserCreate {
+    type Error = tonic::Status;
+
+    fn try_from(value: UserCreateRequest) -> Result<Self, Self::Error> {
+        let roles = if value.roles.is_empty() {
+            sonar::UserRole::DEFAULT.to_vec()
+        } else {
+            parse_user_roles(value.roles)?
+        };
+        Ok(Self {
+            username: value.username.parse::<sonar::Username>().m()?,
+            password: value.password,
+            avatar: parse_imageid_opt(value.avatar_id)?,
+            admin: value.admin.unwrap_or(false),
+            roles,
+        })
+    }
+}
+
+impl TryFrom<UserUpdateRequest> for (sonar::UserId, sonar::UserUpdate) {
+    type Error = tonic::Status;
+
+    fn try_from(value: UserUpdateRequest) -> Result<Self, Self::Error> {
+        let user_id = parse_userid(value.user_id)?;
+        let roles = if value.clear_roles {
+            sonar::ValueUpdate::Unset
+        } else if value.roles.is_empty() {
+            sonar::ValueUpdate::Unchanged
+        } else {
+            sonar::ValueUpdate::Set(parse_user_roles(value.roles)?)
+        };
+        let update = sonar::UserUpdate {
+            password: sonar::ValueUpdate::from_option_unchanged(value.password),
+            avatar: sonar::ValueUpdate::from_option_unchanged(parse_imageid_opt(value.avatar_id)?),
+            admin: sonar::ValueUpdate::from_option_unchanged(value.admin),
+            roles,
+        };
+        Ok((user_id, update))
+    }
+}
+
+impl From<sonar::UserSession> for UserSession {
+    fn from(value: sonar::UserSession) -> Self {
+        Self {
+            session_id: value.id.to_string(),
+            user_id: value.user.to_string(),
+            device: value.device,
+            created_at: Some(convert_timestamp_to_pb(value.created_at)),
+            last_used_at: Some(convert_timestamp_to_pb(value.last_used_at)),
+            expires_at: value.expires_at.map(convert_timestamp_to_pb),
+        }
+    }
+}
+
+impl From<sonar::ApiKey> for UserApiKey {
+    fn from(value: sonar::ApiKey) -> Self {
+        Self {
+            api_key_id: value.id.to_string(),
+            user_id: value.user.to_string(),
+            name: value.name,
+            scope: value.scope.map(|s| s.to_string()),
+            created_at: Some(convert_timestamp_to_pb(value.created_at)),
+            last_used_at: value.last_used_at.map(convert_timestamp_to_pb),
         }
     }
 }
@@ -20,6 +96,7 @@ impl From<sonar::Artist> for Artist {
             coverart_id: value.cover_art.map(|id| id.to_string()),
             genres: convert_genres_to_pb(value.genres),
             properties: convert_properties_to_pb(value.properties),
+            library_id: value.library.to_string(),
         }
     }
 }
@@ -52,6 +129,8 @@ impl From<sonar::Album> for Album {
             coverart_id: value.cover_art.map(|id| id.to_string()),
             genres: convert_genres_to_pb(value.genres),
             properties: convert_properties_to_pb(value.properties),
+            artist_ids: value.artists.into_iter().map(|id| id.to_string()).collect(),
+            library_id: value.library.to_string(),
         }
     }
 }
@@ -63,6 +142,7 @@ impl TryFrom<AlbumCreateRequest> for sonar::AlbumCreate {
         Ok(sonar::AlbumCreate {
             name: value.name,
             artist: parse_artistid(value.artist_id)?,
+            artists: parse_artistids(value.artist_ids)?,
             cover_art: parse_imageid_opt(value.coverart_id)?,
             genres: convert_genres_from_pb(value.genres)?,
             properties: convert_properties_from_pb(value.properties)?,
@@ -75,10 +155,21 @@ impl TryFrom<AlbumUpdateRequest> for (String, sonar::AlbumUpdate) {
 
     fn try_from(value: AlbumUpdateRequest) -> Result<Self, Self::Error> {
         let artist_id = parse_artistid_opt(value.artist_id)?;
+        let artist_ids = parse_artistids(value.artist_ids)?;
+        if artist_id.is_some() && !artist_ids.is_empty() {
+            return Err(tonic::Status::invalid_argument(
+                "artist_id and artist_ids cannot be set together",
+            ));
+        }
         let cover_art = parse_imageid_opt(value.coverart_id)?;
         let update = sonar::AlbumUpdate {
             name: sonar::ValueUpdate::from_option_unchanged(value.name),
             artist: sonar::ValueUpdate::from_option_unchanged(artist_id),
+            artists: if artist_ids.is_empty() {
+                sonar::ValueUpdate::Unchanged
+            } else {
+                sonar::ValueUpdate::Set(artist_ids)
+            },
             cover_art: sonar::ValueUpdate::from_option_unchanged(cover_art),
             genres: convert_genre_updates_from_pb(value.genres)?,
             properties: convert_property_updates_from_pb(value.properties)?,
@@ -98,10 +189,32 @@ impl From<sonar::Track> for Track {
             listen_count: value.listen_count,
             cover_art_id: value.cover_art.map(|id| id.to_string()),
             properties: convert_properties_to_pb(value.properties),
+            artists: value.artists.into_iter().map(From::from).collect(),
+            library_id: value.library.to_string(),
         }
     }
 }
 
+impl From<sonar::ArtistCredit> for ArtistCredit {
+    fn from(value: sonar::ArtistCredit) -> Self {
+        Self {
+            artist_id: value.artist.to_string(),
+            role: value.role.to_string(),
+        }
+    }
+}
+
+impl TryFrom<ArtistCredit> for sonar::ArtistCredit {
+    type Error = tonic::Status;
+
+    fn try_from(value: ArtistCredit) -> Result<Self, Self::Error> {
+        Ok(Self {
+            artist: parse_artistid(value.artist_id)?,
+            role: value.role.parse::<sonar::ArtistRole>().m()?,
+        })
+    }
+}
+
 impl TryFrom<TrackCreateRequest> for sonar::TrackCreate {
     type Error = tonic::Status;
 
@@ -111,6 +224,7 @@ impl TryFrom<TrackCreateRequest> for sonar::TrackCreate {
         Ok(Self {
             name: value.name,
             album: album_id,
+            artists: convert_artist_credits_from_pb(value.artists)?,
             cover_art,
             lyrics: Default::default(),
             audio: Default::default(),
@@ -125,9 +239,17 @@ impl TryFrom<TrackUpdateRequest> for (String, sonar::TrackUpdate) {
     fn try_from(value: TrackUpdateRequest) -> Result<Self, Self::Error> {
         let album_id = parse_albumid_opt(value.album_id)?;
         let cover_art = parse_imageid_opt(value.coverart_id)?;
+        let artists = convert_artist_credits_from_pb(value.artists)?;
         let update = sonar::TrackUpdate {
             name: sonar::ValueUpdate::from_option_unchanged(value.name),
             album: sonar::ValueUpdate::from_option_unchanged(album_id),
+            artists: if value.reset_artists {
+                sonar::ValueUpdate::Unset
+            } else if artists.is_empty() {
+                sonar::ValueUpdate::Unchanged
+            } else {
+                sonar::ValueUpdate::Set(artists)
+            },
             cover_art: sonar::ValueUpdate::from_option_unchanged(cover_art),
             lyrics: Default::default(),
             properties: convert_property_updates_from_pb(value.properties)?,
@@ -163,6 +285,17 @@ impl From<sonar::Playlist> for Playlist {
             duration: None,
             coverart_id: None,
             properties: convert_properties_to_pb(value.properties),
+            smart: value.smart,
+        }
+    }
+}
+
+impl From<sonar::PlaylistTrack> for PlaylistTrack {
+    fn from(value: sonar::PlaylistTrack) -> Self {
+        Self {
+            position: value.position,
+            track_id: value.track.to_string(),
+            inserted_at: Some(convert_timestamp_to_pb(value.inserted_at)),
         }
     }
 }
@@ -188,6 +321,72 @@ impl TryFrom<PlaylistCreateRequest> for sonar::PlaylistCreate {
     }
 }
 
+impl From<sonar::SmartPlaylistRules> for SmartPlaylistRules {
+    fn from(value: sonar::SmartPlaylistRules) -> Self {
+        Self {
+            rules: value.rules.iter().map(ToString::to_string).collect(),
+            match_any: value.match_any,
+            sort: value.sort.to_string(),
+            descending: value.descending,
+            limit: value.limit,
+        }
+    }
+}
+
+impl TryFrom<SmartPlaylistRules> for sonar::SmartPlaylistRules {
+    type Error = tonic::Status;
+
+    fn try_from(value: SmartPlaylistRules) -> Result<Self, Self::Error> {
+        let rules = value
+            .rules
+            .iter()
+            .map(|rule| rule.parse::<sonar::SmartPlaylistRule>().m())
+            .collect::<Result<Vec<_>, _>>()?;
+        let sort = if value.sort.is_empty() {
+            Default::default()
+        } else {
+            value.sort.parse::<sonar::SmartPlaylistSort>().m()?
+        };
+        Ok(Self {
+            rules,
+            match_any: value.match_any,
+            sort,
+            descending: value.descending,
+            limit: value.limit,
+        })
+    }
+}
+
+impl From<sonar::SmartPlaylist> for SmartPlaylist {
+    fn from(value: sonar::SmartPlaylist) -> Self {
+        Self {
+            playlist_id: value.playlist.to_string(),
+            rules: Some(value.rules.into()),
+            refreshed_at: value.refreshed_at.map(convert_timestamp_to_pb),
+        }
+    }
+}
+
+impl TryFrom<SmartPlaylistCreateRequest> for sonar::SmartPlaylistCreate {
+    type Error = tonic::Status;
+
+    fn try_from(value: SmartPlaylistCreateRequest) -> Result<Self, Self::Error> {
+        let owner = value.owner_id.parse::<sonar::UserId>().m()?;
+        let properties = convert_properties_from_pb(value.properties)?;
+        let rules = value
+            .rules
+            .map(TryFrom::try_from)
+            .transpose()?
+            .unwrap_or_default();
+        Ok(Self {
+            name: value.name,
+            owner,
+            rules,
+            properties,
+        })
+    }
+}
+
 impl TryFrom<PlaylistUpdateRequest> for (sonar::PlaylistId, sonar::PlaylistUpdate) {
     type Error = tonic::Status;
 
@@ -202,6 +401,100 @@ impl TryFrom<PlaylistUpdateRequest> for (sonar::PlaylistId, sonar::PlaylistUpdat
     }
 }
 
+impl From<sonar::UserRating> for Rating {
+    fn from(value: sonar::UserRating) -> Self {
+        Self {
+            item_id: value.id.to_string(),
+            rating: value.rating.value(),
+            rated_at: Some(convert_timestamp_to_pb(value.rated_at)),
+        }
+    }
+}
+
+impl From<sonar::AverageRating> for AverageRating {
+    fn from(value: sonar::AverageRating) -> Self {
+        Self {
+            item_id: value.id.to_string(),
+            average: value.average,
+            count: value.count,
+        }
+    }
+}
+
+impl From<sonar::PlayQueue> for PlayQueue {
+    fn from(value: sonar::PlayQueue) -> Self {
+        Self {
+            user_id: value.user.to_string(),
+            track_ids: value.tracks.into_iter().map(|id| id.to_string()).collect(),
+            current: value.current,
+            position_ms: value.position.as_millis() as u64,
+            changed_by: value.changed_by,
+            changed_at: Some(convert_timestamp_to_pb(value.changed_at)),
+        }
+    }
+}
+
+impl TryFrom<PlayQueueSaveRequest> for (sonar::UserId, sonar::PlayQueueSave) {
+    type Error = tonic::Status;
+
+    fn try_from(value: PlayQueueSaveRequest) -> Result<Self, Self::Error> {
+        let user_id = parse_userid(value.user_id)?;
+        let save = sonar::PlayQueueSave {
+            tracks: parse_trackids(value.track_ids)?,
+            current: value.current,
+            position: std::time::Duration::from_millis(value.position_ms),
+            changed_by: value.changed_by,
+        };
+        Ok((user_id, save))
+    }
+}
+
+impl From<sonar::Bookmark> for Bookmark {
+    fn from(value: sonar::Bookmark) -> Self {
+        Self {
+            user_id: value.user.to_string(),
+            track_id: value.track.to_string(),
+            position_ms: value.position.as_millis() as u64,
+            comment: value.comment,
+            created_at: Some(convert_timestamp_to_pb(value.created_at)),
+            updated_at: Some(convert_timestamp_to_pb(value.updated_at)),
+        }
+    }
+}
+
+impl TryFrom<BookmarkCreateRequest> for sonar::BookmarkCreate {
+    type Error = tonic::Status;
+
+    fn try_from(value: BookmarkCreateRequest) -> Result<Self, Self::Error> {
+        Ok(Self {
+            user: parse_userid(value.user_id)?,
+            track: parse_trackid(value.track_id)?,
+            position: std::time::Duration::from_millis(value.position_ms),
+            comment: value.comment,
+        })
+    }
+}
+
+impl From<sonar::FsckIssue> for BlobFsckIssue {
+    fn from(value: sonar::FsckIssue) -> Self {
+        Self {
+            key: value.key,
+            kind: value.kind.to_string(),
+            repaired: value.repaired,
+        }
+    }
+}
+
+impl From<sonar::FsckProgress> for BlobFsckResponse {
+    fn from(value: sonar::FsckProgress) -> Self {
+        Self {
+            checked: value.checked,
+            total: value.total,
+            issue: value.issue.map(Into::into),
+        }
+    }
+}
+
 impl From<sonar::Scrobble> for Scrobble {
     fn from(value: sonar::Scrobble) -> Self {
         Self {
@@ -244,6 +537,268 @@ impl TryFrom<ScrobbleCreateRequest> for sonar::ScrobbleCreate {
     }
 }
 
+impl From<sonar::ScrobbleImportEntry> for ScrobbleImportEntry {
+    fn from(value: sonar::ScrobbleImportEntry) -> Self {
+        Self {
+            artist: value.artist,
+            album: value.album,
+            title: value.title,
+            duration: value
+                .duration
+                .map(|d| TryFrom::try_from(d).expect("failed to convert duration")),
+            recording_mbid: value.recording_mbid,
+            listen_at: Some(convert_timestamp_to_pb(value.listen_at)),
+        }
+    }
+}
+
+impl TryFrom<ScrobbleImportEntry> for sonar::ScrobbleImportEntry {
+    type Error = tonic::Status;
+
+    fn try_from(value: ScrobbleImportEntry) -> Result<Self, Self::Error> {
+        let listen_at = value
+            .listen_at
+            .ok_or_else(|| tonic::Status::invalid_argument("listen_at is required"))?;
+        Ok(Self {
+            artist: value.artist,
+            album: value.album,
+            title: value.title,
+            duration: value
+                .duration
+                .map(|d| std::time::Duration::new(d.seconds as u64, d.nanos as u32)),
+            recording_mbid: value.recording_mbid,
+            listen_at: convert_timestamp_from_pb(listen_at),
+        })
+    }
+}
+
+impl TryFrom<ScrobbleImportRequest> for sonar::ScrobbleImport {
+    type Error = tonic::Status;
+
+    fn try_from(value: ScrobbleImportRequest) -> Result<Self, Self::Error> {
+        let user = parse_userid(value.user_id)?;
+        let entries = value
+            .entries
+            .into_iter()
+            .map(TryFrom::try_from)
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Self {
+            user,
+            scrobbler: value.scrobbler,
+            listen_device: value.listen_device,
+            entries,
+        })
+    }
+}
+
+impl From<sonar::ScrobbleImportReport> for ScrobbleImportResponse {
+    fn from(value: sonar::ScrobbleImportReport) -> Self {
+        Self {
+            imported: value.imported,
+            duplicates: value.duplicates,
+            unmatched: value.unmatched.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl TryFrom<StatsGetRequest> for sonar::StatsParams {
+    type Error = tonic::Status;
+
+    fn try_from(value: StatsGetRequest) -> Result<Self, Self::Error> {
+        let user = parse_userid(value.user_id)?;
+        Ok(Self {
+            user,
+            since: value.since.map(convert_timestamp_from_pb),
+            until: value.until.map(convert_timestamp_from_pb),
+            limit: value.limit.unwrap_or(sonar::StatsParams::DEFAULT_LIMIT),
+            utc_offset: value.utc_offset,
+        })
+    }
+}
+
+impl<T: std::fmt::Display> From<sonar::StatsEntry<T>> for StatsEntry {
+    fn from(value: sonar::StatsEntry<T>) -> Self {
+        Self {
+            item: value.item.to_string(),
+            listen_count: value.listen_count,
+            listen_time: Some(
+                TryFrom::try_from(value.listen_time).expect("failed to convert duration"),
+            ),
+        }
+    }
+}
+
+impl From<sonar::Stats> for Stats {
+    fn from(value: sonar::Stats) -> Self {
+        Self {
+            listen_count: value.listen_count,
+            listen_time: Some(
+                TryFrom::try_from(value.listen_time).expect("failed to convert duration"),
+            ),
+            top_artists: value.top_artists.into_iter().map(Into::into).collect(),
+            top_albums: value.top_albums.into_iter().map(Into::into).collect(),
+            top_tracks: value.top_tracks.into_iter().map(Into::into).collect(),
+            top_genres: value.top_genres.into_iter().map(Into::into).collect(),
+            hour_histogram: value.hour_histogram.to_vec(),
+            weekday_histogram: value.weekday_histogram.to_vec(),
+            current_streak: value.current_streak,
+            longest_streak: value.longest_streak,
+        }
+    }
+}
+
+impl TryFrom<DailyMixRefreshRequest> for sonar::DailyMixParams {
+    type Error = tonic::Status;
+
+    fn try_from(value: DailyMixRefreshRequest) -> Result<Self, Self::Error> {
+        let user = parse_userid(value.user_id)?;
+        Ok(Self {
+            user,
+            mixes: value.mixes.unwrap_or(sonar::DailyMixParams::DEFAULT_MIXES),
+            tracks: value
+                .tracks
+                .unwrap_or(sonar::DailyMixParams::DEFAULT_TRACKS),
+        })
+    }
+}
+
+impl From<sonar::Event> for Event {
+    fn from(value: sonar::Event) -> Self {
+        Self {
+            sequence: value.sequence,
+            kind: value.kind.to_string(),
+            id: value.id.to_string(),
+            user_id: value.user.map(|user| user.to_string()),
+            timestamp: Some(convert_timestamp_to_pb(value.timestamp)),
+        }
+    }
+}
+
+impl TryFrom<WatchRequest> for sonar::EventFilter {
+    type Error = tonic::Status;
+
+    fn try_from(value: WatchRequest) -> Result<Self, Self::Error> {
+        let kinds = parse_event_kinds(value.kinds)?;
+        let ids = value
+            .ids
+            .iter()
+            .map(|id| id.parse::<sonar::SonarId>().m())
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Self {
+            kinds,
+            ids,
+            user: None,
+            since: value.since,
+        })
+    }
+}
+
+fn parse_event_kinds(kinds: Vec<String>) -> Result<Vec<sonar::EventKind>, tonic::Status> {
+    kinds
+        .iter()
+        .map(|kind| kind.parse::<sonar::EventKind>().m())
+        .collect()
+}
+
+impl From<sonar::Library> for Library {
+    fn from(value: sonar::Library) -> Self {
+        Self {
+            id: value.id.to_string(),
+            name: value.name,
+            created_at: Some(convert_timestamp_to_pb(value.created_at)),
+        }
+    }
+}
+
+impl From<sonar::Webhook> for Webhook {
+    fn from(value: sonar::Webhook) -> Self {
+        Self {
+            id: value.id.to_string(),
+            url: value.url,
+            kinds: value.kinds.iter().map(ToString::to_string).collect(),
+            secret: value.secret,
+            enabled: value.enabled,
+            created_at: Some(convert_timestamp_to_pb(value.created_at)),
+        }
+    }
+}
+
+impl TryFrom<WebhookCreateRequest> for sonar::WebhookCreate {
+    type Error = tonic::Status;
+
+    fn try_from(value: WebhookCreateRequest) -> Result<Self, Self::Error> {
+        Ok(Self {
+            url: value.url,
+            kinds: parse_event_kinds(value.kinds)?,
+            secret: value.secret,
+        })
+    }
+}
+
+impl TryFrom<WebhookUpdateRequest> for (sonar::WebhookId, sonar::WebhookUpdate) {
+    type Error = tonic::Status;
+
+    fn try_from(value: WebhookUpdateRequest) -> Result<Self, Self::Error> {
+        let webhook_id = value.webhook_id.parse::<sonar::WebhookId>().m()?;
+        let kinds = if value.all_kinds {
+            sonar::ValueUpdate::Unset
+        } else if value.kinds.is_empty() {
+            sonar::ValueUpdate::Unchanged
+        } else {
+            sonar::ValueUpdate::Set(parse_event_kinds(value.kinds)?)
+        };
+        let update = sonar::WebhookUpdate {
+            url: sonar::ValueUpdate::from_option_unchanged(value.url),
+            kinds,
+            secret: sonar::ValueUpdate::from_option_unchanged(value.secret),
+            enabled: sonar::ValueUpdate::from_option_unchanged(value.enabled),
+        };
+        Ok((webhook_id, update))
+    }
+}
+
+impl From<sonar::WebhookDelivery> for WebhookDelivery {
+    fn from(value: sonar::WebhookDelivery) -> Self {
+        Self {
+            id: value.id,
+            webhook_id: value.webhook.to_string(),
+            event_sequence: value.event_sequence,
+            event_kind: value.event_kind.to_string(),
+            payload: value.payload,
+            status: value.status.to_string(),
+            attempts: value.attempts,
+            response_status: value.response_status.map(u32::from),
+            error: value.error,
+            next_attempt_at: Some(convert_timestamp_to_pb(value.next_attempt_at)),
+            created_at: Some(convert_timestamp_to_pb(value.created_at)),
+            updated_at: Some(convert_timestamp_to_pb(value.updated_at)),
+        }
+    }
+}
+
+impl From<sonar::Radio> for Radio {
+    fn from(value: sonar::Radio) -> Self {
+        Self {
+            id: value.id.to_string(),
+            user_id: value.user.to_string(),
+            seed: value.seed.to_string(),
+            track_count: value.track_count,
+            created_at: Some(convert_timestamp_to_pb(value.created_at)),
+            updated_at: Some(convert_timestamp_to_pb(value.updated_at)),
+        }
+    }
+}
+
+impl TryFrom<RadioCreateRequest> for sonar::RadioCreate {
+    type Error = tonic::Status;
+
+    fn try_from(value: RadioCreateRequest) -> Result<Self, Self::Error> {
+        let user = parse_userid(value.user_id)?;
+        let seed = value.seed.parse::<sonar::RadioSeed>().m()?;
+        Ok(Self { user, seed })
+    }
+}
+
 impl From<sonar::SearchResult> for SearchResult {
     fn from(value: sonar::SearchResult) -> Self {
         match value {
@@ -291,6 +846,7 @@ impl TryFrom<SearchRequest> for (sonar::UserId, sonar::SearchQuery) {
             query: value.query,
             limit: value.limit,
             flags,
+            library: parse_libraryid_opt(value.library_id)?,
         };
         Ok((user_id, query))
     }
@@ -311,6 +867,7 @@ impl From<sonar::Subscription> for Subscription {
             playlist: value.playlist.unwrap_or_default(),
             external_id: value.external_id.unwrap_or_default(),
             media_type: value.media_type.map(|v| v.to_string()).unwrap_or_default(),
+            library_id: value.library.map(|id| id.to_string()),
         }
     }
 }
@@ -438,6 +995,16 @@ pub fn parse_artistid_opt(id: Option<String>) -> Result<Option<sonar::ArtistId>,
     id.map(|id| id.parse::<sonar::ArtistId>().m()).transpose()
 }
 
+pub fn parse_artistids(ids: Vec<String>) -> Result<Vec<sonar::ArtistId>, tonic::Status> {
+    ids.into_iter().map(parse_artistid).collect()
+}
+
+pub fn convert_artist_credits_from_pb(
+    credits: Vec<ArtistCredit>,
+) -> Result<Vec<sonar::ArtistCredit>, tonic::Status> {
+    credits.into_iter().map(TryFrom::try_from).collect()
+}
+
 pub fn parse_albumid(id: String) -> Result<sonar::AlbumId, tonic::Status> {
     id.parse::<sonar::AlbumId>().m()
 }
@@ -458,10 +1025,26 @@ pub fn parse_trackid(id: String) -> Result<sonar::TrackId, tonic::Status> {
     id.parse::<sonar::TrackId>().m()
 }
 
+pub fn parse_trackids(ids: Vec<String>) -> Result<Vec<sonar::TrackId>, tonic::Status> {
+    ids.into_iter().map(parse_trackid).collect()
+}
+
+pub fn parse_libraryid(id: String) -> Result<sonar::LibraryId, tonic::Status> {
+    id.parse::<sonar::LibraryId>().m()
+}
+
+pub fn parse_libraryid_opt(id: Option<String>) -> Result<Option<sonar::LibraryId>, tonic::Status> {
+    id.map(|id| id.parse::<sonar::LibraryId>().m()).transpose()
+}
+
 pub fn parse_sonarid(id: String) -> Result<sonar::SonarId, tonic::Status> {
     id.parse::<sonar::SonarId>().m()
 }
 
+pub fn parse_sonarids(ids: Vec<String>) -> Result<Vec<sonar::SonarId>, tonic::Status> {
+    ids.into_iter().map(parse_sonarid).collect()
+}
+
 pub fn metadata_mask_from_fields(
     fields: Vec<String>,
 ) -> Result<sonar::MetadataFetchMask, tonic::Status> {
@@ -483,3 +1066,58 @@ pub fn metadata_mask_from_fields(
 
     Ok(mask)
 }
+
+impl From<GarbageCollectRequest> for sonar::GarbageCollectParams {
+    fn from(value: GarbageCollectRequest) -> Self {
+        let mut params = sonar::GarbageCollectParams {
+            dry_run: value.dry_run,
+            ..Default::default()
+        };
+        if let Some(min_age) = value.min_age {
+            params.min_age = std::time::Duration::new(min_age.seconds as u64, min_age.nanos as u32);
+        }
+        params
+    }
+}
+
+impl From<sonar::GarbageCollectReport> for GarbageCollectResponse {
+    fn from(value: sonar::GarbageCollectReport) -> Self {
+        Self {
+            item_ids: value.items.into_iter().map(|id| id.to_string()).collect(),
+        }
+    }
+}
+
+impl From<OrphanSweepRequest> for sonar::SweepParams {
+    fn from(value: OrphanSweepRequest) -> Self {
+        let mut params = sonar::SweepParams {
+            dry_run: value.dry_run,
+            ..Default::default()
+        };
+        if let Some(min_age) = value.min_age {
+            params.min_age = std::time::Duration::new(min_age.seconds as u64, min_age.nanos as u32);
+        }
+        params
+    }
+}
+
+impl From<sonar::SweepReport> for OrphanSweepResponse {
+    fn from(value: sonar::SweepReport) -> Self {
+        Self {
+            image_ids: value.images.into_iter().map(|id| id.to_string()).collect(),
+            blob_count: value.blobs,
+            reclaimed_bytes: value.reclaimed_bytes,
+        }
+    }
+}
+
+impl From<sonar::BackupReport> for BackupResponse {
+    fn from(value: sonar::BackupReport) -> Self {
+        Self {
+            blob_count: value.blobs,
+            blobs_copied: value.blobs_copied,
+            bytes_copied: value.bytes_copied,
+            blobs_skipped: value.blobs_skipped,
+        }
+    }
+}
@@ -11,6 +11,7 @@ pub async fn artist_list_all(client: &mut Client) -> Result<Vec<Artist>> {
             .artist_list(super::ArtistListRequest {
                 offset: Some(offset),
                 count: Some(limit),
+                library_id: None,
             })
             .await?;
 
@@ -37,6 +38,7 @@ pub async fn album_list_all(client: &mut Client) -> Result<Vec<Album>> {
             .album_list(super::AlbumListRequest {
                 offset: Some(offset),
                 count: Some(limit),
+                library_id: None,
             })
             .await?;
 
@@ -57,6 +59,7 @@ pub async fn track_list(
     client: &mut Client,
     offset: Option<u32>,
     limit: Option<u32>,
+    library_id: Option<String>,
 ) -> Result<Vec<Track>> {
     let mut offset = offset.unwrap_or(0);
     let mut limit = limit.unwrap_or(u32::MAX);
@@ -68,6 +71,7 @@ pub async fn track_list(
             .track_list(super::TrackListRequest {
                 offset: Some(offset),
                 count: Some(limit_per_req.min(limit)),
+                library_id: library_id.clone(),
             })
             .await?;
 
@@ -86,5 +90,5 @@ pub async fn track_list(
 }
 
 pub async fn track_list_all(client: &mut Client) -> Result<Vec<Track>> {
-    track_list(client, None, None).await
+    track_list(client, None, None, None).await
 }
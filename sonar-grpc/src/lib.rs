@@ -7,13 +7,20 @@ use result_ext::*;
 
 mod conversions;
 use conversions::*;
-use tokio_stream::StreamExt;
+use tokio_stream::{
+    wrappers::{ReceiverStream, UnboundedReceiverStream},
+    StreamExt,
+};
 use tonic::{service::interceptor::InterceptedService, transport::Endpoint};
 
 pub mod ext;
 
 tonic::include_proto!("sonar");
 
+const DEFAULT_SIMILAR_LIMIT: u32 = 20;
+const DEFAULT_RADIO_COUNT: u32 = 20;
+const WATCH_CHANNEL_CAPACITY: usize = 64;
+
 pub type Client = sonar_service_client::SonarServiceClient<
     InterceptedService<tonic::transport::Channel, AuthInterceptor>,
 >;
@@ -49,78 +56,204 @@ impl Server {
     async fn require_user_mt(
         &self,
         metadata: &tonic::metadata::MetadataMap,
+        access: sonar::ApiKeyAccess,
     ) -> Result<sonar::User, tonic::Status> {
-        let token = sonar::UserToken::from_str(
-            metadata
-                .get("authorization")
-                .map(|v| v.to_str().unwrap_or_default())
-                .unwrap_or_default(),
-        )
-        .m()?;
-        let user_id = sonar::user_validate_token(&self.context, &token)
-            .await
-            .m()?;
-        let user = sonar::user_get(&self.context, user_id).await.m()?;
-        Ok(user)
+        self.require_user_for(metadata, access).await
     }
 
     async fn require_user<T>(
         &self,
         request: &tonic::Request<T>,
+        access: sonar::ApiKeyAccess,
     ) -> Result<sonar::User, tonic::Status> {
-        self.require_user_mt(request.metadata()).await
+        self.require_user_for(request.metadata(), access).await
     }
 
-    async fn require_admin_mt(
+    /// Authenticates the request using either a session token or an api key.
+    /// Api keys must allow the `access` required by the operation.
+    async fn require_user_for(
         &self,
         metadata: &tonic::metadata::MetadataMap,
+        access: sonar::ApiKeyAccess,
+    ) -> Result<sonar::User, tonic::Status> {
+        let credential = metadata
+            .get("authorization")
+            .map(|v| v.to_str().unwrap_or_default())
+            .unwrap_or_default();
+
+        if let Ok(token) = sonar::UserToken::from_str(credential) {
+            let user_id = sonar::user_validate_token(&self.context, &token)
+                .await
+                .m()?;
+            let user = sonar::user_get(&self.context, user_id).await.m()?;
+            return Ok(user);
+        }
+
+        let api_key = sonar::user_api_key_validate(&self.context, credential)
+            .await
+            .m()?;
+        if !api_key.allows(access) {
+            return Err(tonic::Status::permission_denied(
+                "api key scope does not allow this operation",
+            ));
+        }
+        let mut user = sonar::user_get(&self.context, api_key.user).await.m()?;
+        if user.admin && !api_key.allows(sonar::ApiKeyAccess::Admin) {
+            // keys without admin access only get the roles stored for the user
+            user.admin = false;
+        }
+        Ok(user)
+    }
+
+    async fn require_admin<T>(
+        &self,
+        request: &tonic::Request<T>,
     ) -> Result<sonar::User, tonic::Status> {
-        let user = self.require_user_mt(metadata).await?;
+        let user = self
+            .require_user(request, sonar::ApiKeyAccess::Admin)
+            .await?;
         if !user.admin {
             return Err(tonic::Status::permission_denied("not an admin"));
         }
         Ok(user)
     }
 
-    async fn require_admin<T>(
+    async fn require_role_mt(
+        &self,
+        metadata: &tonic::metadata::MetadataMap,
+        access: sonar::ApiKeyAccess,
+        role: sonar::UserRole,
+    ) -> Result<sonar::User, tonic::Status> {
+        let user = self.require_user_mt(metadata, access).await?;
+        check_role(&user, role)?;
+        Ok(user)
+    }
+
+    async fn require_role<T>(
         &self,
         request: &tonic::Request<T>,
+        access: sonar::ApiKeyAccess,
+        role: sonar::UserRole,
     ) -> Result<sonar::User, tonic::Status> {
-        self.require_admin_mt(request.metadata()).await
+        let user = self.require_user(request, access).await?;
+        check_role(&user, role)?;
+        Ok(user)
     }
 
-    async fn artist_lookup(&self, id_or_name: &str) -> Result<sonar::Artist, tonic::Status> {
-        match id_or_name.parse::<sonar::ArtistId>() {
-            Ok(artist_id) => sonar::artist_get(&self.context, artist_id).await.m(),
+    async fn require_library_access(
+        &self,
+        user: &sonar::User,
+        library_id: sonar::LibraryId,
+    ) -> Result<(), tonic::Status> {
+        match sonar::library_check_access(&self.context, user.id, library_id).await {
+            Ok(()) => Ok(()),
+            Err(err) if err.kind() == sonar::ErrorKind::Unauthorized => {
+                Err(tonic::Status::permission_denied("no access to library"))
+            }
+            Err(err) => Err(err).m(),
+        }
+    }
+
+    /// Gets an artist by id or name, checking that `user` has access to its library.
+    async fn artist_lookup(
+        &self,
+        user: &sonar::User,
+        id_or_name: &str,
+    ) -> Result<sonar::Artist, tonic::Status> {
+        let artist = match id_or_name.parse::<sonar::ArtistId>() {
+            Ok(artist_id) => sonar::artist_get(&self.context, artist_id).await.m()?,
             Err(_) => sonar::artist_get_by_name(&self.context, id_or_name)
                 .await
-                .m(),
-        }
+                .m()?,
+        };
+        self.require_library_access(user, artist.library).await?;
+        Ok(artist)
     }
 
-    async fn album_lookup(&self, id_or_name: &str) -> Result<sonar::Album, tonic::Status> {
-        match id_or_name.parse::<sonar::AlbumId>() {
-            Ok(album_id) => sonar::album_get(&self.context, album_id).await.m(),
+    /// Gets an album by id or name, checking that `user` has access to its library.
+    async fn album_lookup(
+        &self,
+        user: &sonar::User,
+        id_or_name: &str,
+    ) -> Result<sonar::Album, tonic::Status> {
+        let album = match id_or_name.parse::<sonar::AlbumId>() {
+            Ok(album_id) => sonar::album_get(&self.context, album_id).await.m()?,
             Err(_) => sonar::album_get_by_name(&self.context, id_or_name)
                 .await
-                .m(),
-        }
+                .m()?,
+        };
+        self.require_library_access(user, album.library).await?;
+        Ok(album)
     }
 
-    async fn track_lookup(&self, id_or_name: &str) -> Result<sonar::Track, tonic::Status> {
-        match id_or_name.parse::<sonar::TrackId>() {
-            Ok(track_id) => sonar::track_get(&self.context, track_id).await.m(),
+    /// Gets a track by id or name, checking that `user` has access to its library.
+    async fn track_lookup(
+        &self,
+        user: &sonar::User,
+        id_or_name: &str,
+    ) -> Result<sonar::Track, tonic::Status> {
+        let track = match id_or_name.parse::<sonar::TrackId>() {
+            Ok(track_id) => sonar::track_get(&self.context, track_id).await.m()?,
             Err(_) => sonar::track_get_by_name(&self.context, id_or_name)
                 .await
-                .m(),
+                .m()?,
+        };
+        self.require_library_access(user, track.library).await?;
+        Ok(track)
+    }
+
+    /// Gets a radio, checking that it belongs to `user` unless they are an admin.
+    async fn radio_lookup(
+        &self,
+        user: &sonar::User,
+        radio_id: &str,
+    ) -> Result<sonar::Radio, tonic::Status> {
+        let radio_id = radio_id.parse::<sonar::RadioId>().m()?;
+        let radio = sonar::radio_get(&self.context, radio_id).await.m()?;
+        if radio.user != user.id && !user.admin {
+            return Err(tonic::Status::permission_denied(
+                "cannot access other user's radio",
+            ));
+        }
+        Ok(radio)
+    }
+
+    /// Checks that the subscription belongs to `user` unless they are an admin.
+    async fn subscription_lookup(
+        &self,
+        user: &sonar::User,
+        subscription_id: &str,
+    ) -> Result<sonar::SubscriptionId, tonic::Status> {
+        let subscription_id = subscription_id.parse::<sonar::SubscriptionId>().m()?;
+        if !user.admin {
+            let subscriptions = sonar::subscription_list(&self.context, user.id).await.m()?;
+            if !subscriptions.iter().any(|s| s.id == subscription_id) {
+                return Err(tonic::Status::permission_denied(
+                    "cannot access other user's subscription",
+                ));
+            }
         }
+        Ok(subscription_id)
     }
 }
 
+fn check_role(user: &sonar::User, role: sonar::UserRole) -> Result<(), tonic::Status> {
+    if !user.has_role(role) {
+        return Err(tonic::Status::permission_denied(format!(
+            "missing role {role}"
+        )));
+    }
+    Ok(())
+}
+
 #[tonic::async_trait]
 impl sonar_service_server::SonarService for Server {
     type ImageDownloadStream = SonarImageDownloadStream;
     type TrackDownloadStream = SonarTrackDownloadStream;
+    type BlobFsckStream =
+        UnboundedReceiverStream<std::result::Result<BlobFsckResponse, tonic::Status>>;
+    type RadioStreamStream = ReceiverStream<std::result::Result<Track, tonic::Status>>;
+    type WatchStream = ReceiverStream<std::result::Result<Event, tonic::Status>>;
 
     async fn user_list(
         &self,
@@ -139,14 +272,7 @@ impl sonar_service_server::SonarService for Server {
         self.require_admin(&request).await?;
 
         let req = request.into_inner();
-        let username = req.username.parse::<sonar::Username>().m()?;
-        let avatar = parse_imageid_opt(req.avatar_id)?;
-        let create = sonar::UserCreate {
-            username,
-            password: req.password,
-            avatar,
-            admin: req.admin.unwrap_or(false),
-        };
+        let create = sonar::UserCreate::try_from(req)?;
         let user = sonar::user_create(&self.context, create).await.m()?;
         Ok(tonic::Response::new(user.into()))
     }
@@ -156,7 +282,12 @@ impl sonar_service_server::SonarService for Server {
     ) -> std::result::Result<tonic::Response<User>, tonic::Status> {
         self.require_admin(&request).await?;
 
-        todo!()
+        let req = request.into_inner();
+        let (user_id, update) = TryFrom::try_from(req)?;
+        let user = sonar::user_update(&self.context, user_id, update)
+            .await
+            .m()?;
+        Ok(tonic::Response::new(user.into()))
     }
     async fn user_delete(
         &self,
@@ -176,7 +307,15 @@ impl sonar_service_server::SonarService for Server {
         let req = request.into_inner();
         let username = req.username.parse::<sonar::Username>().m()?;
         let password = req.password;
-        let (user_id, user_token) = sonar::user_login(&self.context, &username, &password)
+        let params = sonar::UserLoginParams {
+            device: req.device,
+            expires_in: req
+                .expires_in
+                .map(TryFrom::try_from)
+                .transpose()
+                .map_err(|_| tonic::Status::invalid_argument("invalid session expiration"))?,
+        };
+        let (user_id, user_token) = sonar::user_login(&self.context, &username, &password, params)
             .await
             .m()?;
         Ok(tonic::Response::new(UserLoginResponse {
@@ -193,11 +332,154 @@ impl sonar_service_server::SonarService for Server {
         sonar::user_logout(&self.context, &user_token).await.m()?;
         Ok(tonic::Response::new(()))
     }
+    async fn user_session_list(
+        &self,
+        request: tonic::Request<UserSessionListRequest>,
+    ) -> std::result::Result<tonic::Response<UserSessionListResponse>, tonic::Status> {
+        let user = self
+            .require_user(&request, sonar::ApiKeyAccess::Read)
+            .await?;
+        let req = request.into_inner();
+        let user_id = parse_userid(req.user_id)?;
+        if user.id != user_id && !user.admin {
+            return Err(tonic::Status::permission_denied(
+                "cannot list other user's sessions",
+            ));
+        }
+        let sessions = sonar::user_session_list(&self.context, user_id).await.m()?;
+        let sessions = sessions.into_iter().map(Into::into).collect();
+        Ok(tonic::Response::new(UserSessionListResponse { sessions }))
+    }
+    async fn user_session_revoke(
+        &self,
+        request: tonic::Request<UserSessionRevokeRequest>,
+    ) -> std::result::Result<tonic::Response<()>, tonic::Status> {
+        let user = self
+            .require_user(&request, sonar::ApiKeyAccess::Write)
+            .await?;
+        let req = request.into_inner();
+        let session_id = req.session_id.parse::<sonar::UserSessionId>().m()?;
+        let session = sonar::user_session_get(&self.context, session_id)
+            .await
+            .m()?;
+        if user.id != session.user && !user.admin {
+            return Err(tonic::Status::permission_denied(
+                "cannot revoke other user's sessions",
+            ));
+        }
+        sonar::user_session_revoke(&self.context, session_id)
+            .await
+            .m()?;
+        Ok(tonic::Response::new(()))
+    }
+    async fn user_api_key_list(
+        &self,
+        request: tonic::Request<UserApiKeyListRequest>,
+    ) -> std::result::Result<tonic::Response<UserApiKeyListResponse>, tonic::Status> {
+        let user = self
+            .require_user(&request, sonar::ApiKeyAccess::Read)
+            .await?;
+        let req = request.into_inner();
+        let user_id = parse_userid(req.user_id)?;
+        if user.id != user_id && !user.admin {
+            return Err(tonic::Status::permission_denied(
+                "cannot list other user's api keys",
+            ));
+        }
+        let api_keys = sonar::user_api_key_list(&self.context, user_id).await.m()?;
+        let api_keys = api_keys.into_iter().map(Into::into).collect();
+        Ok(tonic::Response::new(UserApiKeyListResponse { api_keys }))
+    }
+    async fn user_api_key_create(
+        &self,
+        request: tonic::Request<UserApiKeyCreateRequest>,
+    ) -> std::result::Result<tonic::Response<UserApiKeyCreateResponse>, tonic::Status> {
+        let user = self
+            .require_user(&request, sonar::ApiKeyAccess::Write)
+            .await?;
+        let req = request.into_inner();
+        let user_id = parse_userid(req.user_id)?;
+        if user.id != user_id && !user.admin {
+            return Err(tonic::Status::permission_denied(
+                "cannot create api keys for other users",
+            ));
+        }
+        let scope = req
+            .scope
+            .map(|s| s.parse::<sonar::ApiKeyScope>())
+            .transpose()
+            .m()?;
+        if scope == Some(sonar::ApiKeyScope::Admin) && !user.admin {
+            return Err(tonic::Status::permission_denied(
+                "only admins can create admin api keys",
+            ));
+        }
+        let (api_key, key) = sonar::user_api_key_create(
+            &self.context,
+            sonar::ApiKeyCreate {
+                user: user_id,
+                name: req.name,
+                scope,
+            },
+        )
+        .await
+        .m()?;
+        Ok(tonic::Response::new(UserApiKeyCreateResponse {
+            api_key: Some(api_key.into()),
+            key,
+        }))
+    }
+    async fn user_api_key_revoke(
+        &self,
+        request: tonic::Request<UserApiKeyRevokeRequest>,
+    ) -> std::result::Result<tonic::Response<()>, tonic::Status> {
+        let user = self
+            .require_user(&request, sonar::ApiKeyAccess::Write)
+            .await?;
+        let req = request.into_inner();
+        let api_key_id = req.api_key_id.parse::<sonar::ApiKeyId>().m()?;
+        let api_key = sonar::user_api_key_get(&self.context, api_key_id)
+            .await
+            .m()?;
+        if user.id != api_key.user && !user.admin {
+            return Err(tonic::Status::permission_denied(
+                "cannot revoke other user's api keys",
+            ));
+        }
+        sonar::user_api_key_revoke(&self.context, api_key_id)
+            .await
+            .m()?;
+        Ok(tonic::Response::new(()))
+    }
+    async fn user_subsonic_password_set(
+        &self,
+        request: tonic::Request<UserSubsonicPasswordSetRequest>,
+    ) -> std::result::Result<tonic::Response<()>, tonic::Status> {
+        let user = self
+            .require_user(&request, sonar::ApiKeyAccess::Write)
+            .await?;
+        let req = request.into_inner();
+        let user_id = req.user_id.parse::<sonar::UserId>().m()?;
+        if user.id != user_id && !user.admin {
+            return Err(tonic::Status::permission_denied(
+                "cannot set other user's subsonic password",
+            ));
+        }
+        sonar::user_subsonic_password_set(&self.context, user_id, req.password.as_deref())
+            .await
+            .m()?;
+        Ok(tonic::Response::new(()))
+    }
     async fn image_create(
         &self,
         request: tonic::Request<ImageCreateRequest>,
     ) -> std::result::Result<tonic::Response<ImageCreateResponse>, tonic::Status> {
-        self.require_admin(&request).await?;
+        self.require_role(
+            &request,
+            sonar::ApiKeyAccess::Write,
+            sonar::UserRole::EditMetadata,
+        )
+        .await?;
 
         let req = request.into_inner();
         let image_id = sonar::image_create(
@@ -216,7 +498,12 @@ impl sonar_service_server::SonarService for Server {
         &self,
         request: tonic::Request<ImageDeleteRequest>,
     ) -> std::result::Result<tonic::Response<()>, tonic::Status> {
-        self.require_admin(&request).await?;
+        self.require_role(
+            &request,
+            sonar::ApiKeyAccess::Write,
+            sonar::UserRole::Delete,
+        )
+        .await?;
 
         todo!()
     }
@@ -237,9 +524,16 @@ impl sonar_service_server::SonarService for Server {
         &self,
         request: tonic::Request<ArtistListRequest>,
     ) -> std::result::Result<tonic::Response<ArtistListResponse>, tonic::Status> {
+        let user = self
+            .require_user(&request, sonar::ApiKeyAccess::Read)
+            .await?;
         let req = request.into_inner();
+        let filter =
+            sonar::LibraryFilter::user(user.id).with_library(parse_libraryid_opt(req.library_id)?);
         let params = sonar::ListParams::from((req.offset, req.count));
-        let artists = sonar::artist_list(&self.context, params).await.m()?;
+        let artists = sonar::artist_list_filtered(&self.context, filter, params)
+            .await
+            .m()?;
         let artists = artists.into_iter().map(Into::into).collect();
         Ok(tonic::Response::new(ArtistListResponse { artists }))
     }
@@ -247,15 +541,23 @@ impl sonar_service_server::SonarService for Server {
         &self,
         request: tonic::Request<ArtistGetRequest>,
     ) -> std::result::Result<tonic::Response<Artist>, tonic::Status> {
+        let user = self
+            .require_user(&request, sonar::ApiKeyAccess::Read)
+            .await?;
         let req = request.into_inner();
-        let artist = self.artist_lookup(&req.artist).await?;
+        let artist = self.artist_lookup(&user, &req.artist).await?;
         Ok(tonic::Response::new(artist.into()))
     }
     async fn artist_create(
         &self,
         request: tonic::Request<ArtistCreateRequest>,
     ) -> std::result::Result<tonic::Response<Artist>, tonic::Status> {
-        self.require_admin(&request).await?;
+        self.require_role(
+            &request,
+            sonar::ApiKeyAccess::Write,
+            sonar::UserRole::EditMetadata,
+        )
+        .await?;
 
         let req = request.into_inner();
         let create = sonar::ArtistCreate {
@@ -263,6 +565,7 @@ impl sonar_service_server::SonarService for Server {
             cover_art: parse_imageid_opt(req.coverart_id)?,
             genres: convert_genres_from_pb(req.genres)?,
             properties: convert_properties_from_pb(req.properties)?,
+            library: parse_libraryid_opt(req.library_id)?,
         };
         let artist = sonar::artist_create(&self.context, create).await.m()?;
         Ok(tonic::Response::new(artist.into()))
@@ -271,11 +574,17 @@ impl sonar_service_server::SonarService for Server {
         &self,
         request: tonic::Request<ArtistUpdateRequest>,
     ) -> std::result::Result<tonic::Response<Artist>, tonic::Status> {
-        self.require_admin(&request).await?;
+        let user = self
+            .require_role(
+                &request,
+                sonar::ApiKeyAccess::Write,
+                sonar::UserRole::EditMetadata,
+            )
+            .await?;
 
         let req = request.into_inner();
         let (artist_id, update) = TryFrom::try_from(req)?;
-        let artist = self.artist_lookup(&artist_id).await?;
+        let artist = self.artist_lookup(&user, &artist_id).await?;
         let artist = sonar::artist_update(&self.context, artist.id, update)
             .await
             .m()?;
@@ -285,10 +594,16 @@ impl sonar_service_server::SonarService for Server {
         &self,
         request: tonic::Request<ArtistDeleteRequest>,
     ) -> std::result::Result<tonic::Response<()>, tonic::Status> {
-        self.require_admin(&request).await?;
+        let user = self
+            .require_role(
+                &request,
+                sonar::ApiKeyAccess::Write,
+                sonar::UserRole::Delete,
+            )
+            .await?;
 
         let req = request.into_inner();
-        let artist = self.artist_lookup(&req.artist_id).await?;
+        let artist = self.artist_lookup(&user, &req.artist_id).await?;
         sonar::artist_delete(&self.context, artist.id).await.m()?;
         Ok(tonic::Response::new(()))
     }
@@ -296,9 +611,16 @@ impl sonar_service_server::SonarService for Server {
         &self,
         request: tonic::Request<AlbumListRequest>,
     ) -> std::result::Result<tonic::Response<AlbumListResponse>, tonic::Status> {
+        let user = self
+            .require_user(&request, sonar::ApiKeyAccess::Read)
+            .await?;
         let req = request.into_inner();
+        let filter =
+            sonar::LibraryFilter::user(user.id).with_library(parse_libraryid_opt(req.library_id)?);
         let params = sonar::ListParams::from((req.offset, req.count));
-        let albums = sonar::album_list(&self.context, params).await.m()?;
+        let albums = sonar::album_list_filtered(&self.context, filter, params)
+            .await
+            .m()?;
         let albums = albums.into_iter().map(Into::into).collect();
         Ok(tonic::Response::new(AlbumListResponse { albums }))
     }
@@ -306,11 +628,15 @@ impl sonar_service_server::SonarService for Server {
         &self,
         request: tonic::Request<AlbumListByArtistRequest>,
     ) -> std::result::Result<tonic::Response<AlbumListResponse>, tonic::Status> {
+        let user = self
+            .require_user(&request, sonar::ApiKeyAccess::Read)
+            .await?;
         let req = request.into_inner();
-        let artist = self.artist_lookup(&req.artist_id).await?;
+        let artist = self.artist_lookup(&user, &req.artist_id).await?;
         let artist_id = artist.id;
         let params = sonar::ListParams::from((req.offset, req.count));
-        let albums = sonar::album_list_by_artist(&self.context, artist_id, params)
+        let filter = sonar::LibraryFilter::user(user.id);
+        let albums = sonar::album_list_by_artist_filtered(&self.context, filter, artist_id, params)
             .await
             .m()?;
         let albums = albums.into_iter().map(Into::into).collect();
@@ -320,14 +646,24 @@ impl sonar_service_server::SonarService for Server {
         &self,
         request: tonic::Request<AlbumGetRequest>,
     ) -> std::result::Result<tonic::Response<Album>, tonic::Status> {
+        let user = self
+            .require_user(&request, sonar::ApiKeyAccess::Read)
+            .await?;
         let req = request.into_inner();
-        let album = self.album_lookup(&req.album).await?;
+        let album = self.album_lookup(&user, &req.album).await?;
         Ok(tonic::Response::new(album.into()))
     }
     async fn album_create(
         &self,
         request: tonic::Request<AlbumCreateRequest>,
     ) -> std::result::Result<tonic::Response<Album>, tonic::Status> {
+        self.require_role(
+            &request,
+            sonar::ApiKeyAccess::Write,
+            sonar::UserRole::EditMetadata,
+        )
+        .await?;
+
         let req = request.into_inner();
         let create = TryFrom::try_from(req)?;
         let album = sonar::album_create(&self.context, create).await.m()?;
@@ -337,11 +673,17 @@ impl sonar_service_server::SonarService for Server {
         &self,
         request: tonic::Request<AlbumUpdateRequest>,
     ) -> std::result::Result<tonic::Response<Album>, tonic::Status> {
-        self.require_admin(&request).await?;
+        let user = self
+            .require_role(
+                &request,
+                sonar::ApiKeyAccess::Write,
+                sonar::UserRole::EditMetadata,
+            )
+            .await?;
 
         let req = request.into_inner();
         let (album_id, update) = TryFrom::try_from(req)?;
-        let album = self.album_lookup(&album_id).await?;
+        let album = self.album_lookup(&user, &album_id).await?;
         let album = sonar::album_update(&self.context, album.id, update)
             .await
             .m()?;
@@ -351,10 +693,16 @@ impl sonar_service_server::SonarService for Server {
         &self,
         request: tonic::Request<AlbumDeleteRequest>,
     ) -> std::result::Result<tonic::Response<()>, tonic::Status> {
-        self.require_admin(&request).await?;
+        let user = self
+            .require_role(
+                &request,
+                sonar::ApiKeyAccess::Write,
+                sonar::UserRole::Delete,
+            )
+            .await?;
 
         let req = request.into_inner();
-        let album = self.album_lookup(&req.album_id).await?;
+        let album = self.album_lookup(&user, &req.album_id).await?;
         sonar::album_delete(&self.context, album.id).await.m()?;
         Ok(tonic::Response::new(()))
     }
@@ -362,9 +710,16 @@ impl sonar_service_server::SonarService for Server {
         &self,
         request: tonic::Request<TrackListRequest>,
     ) -> std::result::Result<tonic::Response<TrackListResponse>, tonic::Status> {
+        let user = self
+            .require_user(&request, sonar::ApiKeyAccess::Read)
+            .await?;
         let req = request.into_inner();
+        let filter =
+            sonar::LibraryFilter::user(user.id).with_library(parse_libraryid_opt(req.library_id)?);
         let params = sonar::ListParams::from((req.offset, req.count));
-        let tracks = sonar::track_list(&self.context, params).await.m()?;
+        let tracks = sonar::track_list_filtered(&self.context, filter, params)
+            .await
+            .m()?;
         let tracks = tracks.into_iter().map(Into::into).collect();
         Ok(tonic::Response::new(TrackListResponse { tracks }))
     }
@@ -372,10 +727,14 @@ impl sonar_service_server::SonarService for Server {
         &self,
         request: tonic::Request<TrackListByAlbumRequest>,
     ) -> std::result::Result<tonic::Response<TrackListResponse>, tonic::Status> {
+        let user = self
+            .require_user(&request, sonar::ApiKeyAccess::Read)
+            .await?;
         let req = request.into_inner();
-        let album = self.album_lookup(&req.album_id).await?;
+        let album = self.album_lookup(&user, &req.album_id).await?;
         let params = sonar::ListParams::from((req.offset, req.count));
-        let tracks = sonar::track_list_by_album(&self.context, album.id, params)
+        let filter = sonar::LibraryFilter::user(user.id);
+        let tracks = sonar::track_list_by_album_filtered(&self.context, filter, album.id, params)
             .await
             .m()?;
         let tracks = tracks.into_iter().map(Into::into).collect();
@@ -385,15 +744,23 @@ impl sonar_service_server::SonarService for Server {
         &self,
         request: tonic::Request<TrackGetRequest>,
     ) -> std::result::Result<tonic::Response<Track>, tonic::Status> {
+        let user = self
+            .require_user(&request, sonar::ApiKeyAccess::Read)
+            .await?;
         let req = request.into_inner();
-        let track = self.track_lookup(&req.track).await?;
+        let track = self.track_lookup(&user, &req.track).await?;
         Ok(tonic::Response::new(track.into()))
     }
     async fn track_create(
         &self,
         request: tonic::Request<TrackCreateRequest>,
     ) -> std::result::Result<tonic::Response<Track>, tonic::Status> {
-        self.require_admin(&request).await?;
+        self.require_role(
+            &request,
+            sonar::ApiKeyAccess::Write,
+            sonar::UserRole::EditMetadata,
+        )
+        .await?;
 
         let req = request.into_inner();
         let create = TryFrom::try_from(req)?;
@@ -404,11 +771,17 @@ impl sonar_service_server::SonarService for Server {
         &self,
         request: tonic::Request<TrackUpdateRequest>,
     ) -> std::result::Result<tonic::Response<Track>, tonic::Status> {
-        self.require_admin(&request).await?;
+        let user = self
+            .require_role(
+                &request,
+                sonar::ApiKeyAccess::Write,
+                sonar::UserRole::EditMetadata,
+            )
+            .await?;
 
         let req = request.into_inner();
         let (track_id, update) = TryFrom::try_from(req)?;
-        let track = self.track_lookup(&track_id).await?;
+        let track = self.track_lookup(&user, &track_id).await?;
         let track = sonar::track_update(&self.context, track.id, update)
             .await
             .m()?;
@@ -418,10 +791,16 @@ impl sonar_service_server::SonarService for Server {
         &self,
         request: tonic::Request<TrackDeleteRequest>,
     ) -> std::result::Result<tonic::Response<()>, tonic::Status> {
-        self.require_admin(&request).await?;
+        let user = self
+            .require_role(
+                &request,
+                sonar::ApiKeyAccess::Write,
+                sonar::UserRole::Delete,
+            )
+            .await?;
 
         let req = request.into_inner();
-        let track = self.track_lookup(&req.track_id).await?;
+        let track = self.track_lookup(&user, &req.track_id).await?;
         sonar::track_delete(&self.context, track.id).await.m()?;
         Ok(tonic::Response::new(()))
     }
@@ -429,8 +808,11 @@ impl sonar_service_server::SonarService for Server {
         &self,
         request: tonic::Request<TrackLyricsRequest>,
     ) -> std::result::Result<tonic::Response<TrackLyricsResponse>, tonic::Status> {
+        let user = self
+            .require_user(&request, sonar::ApiKeyAccess::Read)
+            .await?;
         let req = request.into_inner();
-        let track = self.track_lookup(&req.track_id).await?;
+        let track = self.track_lookup(&user, &req.track_id).await?;
         let lyrics = sonar::track_get_lyrics(&self.context, track.id).await.m()?;
         Ok(tonic::Response::new(TrackLyricsResponse {
             lyrics: Some(lyrics.into()),
@@ -440,8 +822,16 @@ impl sonar_service_server::SonarService for Server {
         &self,
         request: tonic::Request<TrackDownloadRequest>,
     ) -> std::result::Result<tonic::Response<Self::TrackDownloadStream>, tonic::Status> {
+        let user = self
+            .require_role(
+                &request,
+                sonar::ApiKeyAccess::Stream,
+                sonar::UserRole::Download,
+            )
+            .await?;
+
         let req = request.into_inner();
-        let track = self.track_lookup(&req.track_id).await?;
+        let track = self.track_lookup(&user, &req.track_id).await?;
         let download = sonar::track_download(&self.context, track.id, Default::default())
             .await
             .m()?;
@@ -453,8 +843,11 @@ impl sonar_service_server::SonarService for Server {
         &self,
         request: tonic::Request<TrackStatRequest>,
     ) -> std::result::Result<tonic::Response<TrackStatResponse>, tonic::Status> {
+        let user = self
+            .require_user(&request, sonar::ApiKeyAccess::Read)
+            .await?;
         let req = request.into_inner();
-        let track = self.track_lookup(&req.track_id).await?;
+        let track = self.track_lookup(&user, &req.track_id).await?;
         let stat = sonar::track_stat(&self.context, track.id).await.m()?;
         Ok(tonic::Response::new(TrackStatResponse {
             track_id: track.id.to_string(),
@@ -465,8 +858,16 @@ impl sonar_service_server::SonarService for Server {
         &self,
         request: tonic::Request<TrackDownloadChunkRequest>,
     ) -> std::result::Result<tonic::Response<TrackDownloadChunkResponse>, tonic::Status> {
+        let user = self
+            .require_role(
+                &request,
+                sonar::ApiKeyAccess::Stream,
+                sonar::UserRole::Download,
+            )
+            .await?;
+
         let req = request.into_inner();
-        let track = self.track_lookup(&req.track_id).await?;
+        let track = self.track_lookup(&user, &req.track_id).await?;
         let range = sonar::ByteRange::new(req.offset as u64, req.size as u64);
         let mut download = sonar::track_download(&self.context, track.id, range)
             .await
@@ -483,7 +884,9 @@ impl sonar_service_server::SonarService for Server {
         &self,
         request: tonic::Request<FavoriteListRequest>,
     ) -> std::result::Result<tonic::Response<FavoriteListResponse>, tonic::Status> {
-        let user = self.require_user(&request).await?;
+        let user = self
+            .require_user(&request, sonar::ApiKeyAccess::Read)
+            .await?;
         let req = request.into_inner();
         let target_user = parse_userid(req.user_id)?;
 
@@ -509,7 +912,9 @@ impl sonar_service_server::SonarService for Server {
         &self,
         request: tonic::Request<FavoriteAddRequest>,
     ) -> std::result::Result<tonic::Response<()>, tonic::Status> {
-        let user = self.require_user(&request).await?;
+        let user = self
+            .require_user(&request, sonar::ApiKeyAccess::Write)
+            .await?;
         let req = request.into_inner();
         let target_user = parse_userid(req.user_id)?;
         let item_id = parse_sonarid(req.item_id)?;
@@ -529,7 +934,9 @@ impl sonar_service_server::SonarService for Server {
         &self,
         request: tonic::Request<FavoriteRemoveRequest>,
     ) -> std::result::Result<tonic::Response<()>, tonic::Status> {
-        let user = self.require_user(&request).await?;
+        let user = self
+            .require_user(&request, sonar::ApiKeyAccess::Write)
+            .await?;
         let req = request.into_inner();
         let target_user = parse_userid(req.user_id)?;
         let item_id = parse_sonarid(req.item_id)?;
@@ -545,113 +952,426 @@ impl sonar_service_server::SonarService for Server {
             .m()?;
         Ok(tonic::Response::new(()))
     }
-    async fn playlist_list(
-        &self,
-        request: tonic::Request<PlaylistListRequest>,
-    ) -> std::result::Result<tonic::Response<PlaylistListResponse>, tonic::Status> {
-        let req = request.into_inner();
-        let params = sonar::ListParams::from((req.offset, req.count));
-        let playlists = sonar::playlist_list(&self.context, params).await.m()?;
-        let playlists = playlists.into_iter().map(Into::into).collect();
-        Ok(tonic::Response::new(PlaylistListResponse { playlists }))
-    }
-    async fn playlist_get(
-        &self,
-        request: tonic::Request<PlaylistGetRequest>,
-    ) -> std::result::Result<tonic::Response<Playlist>, tonic::Status> {
-        let req = request.into_inner();
-        let playlist_id = req.playlist_id.parse::<sonar::PlaylistId>().m()?;
-        let playlist = sonar::playlist_get(&self.context, playlist_id).await.m()?;
-        Ok(tonic::Response::new(playlist.into()))
-    }
-    async fn playlist_create(
+    async fn rating_list(
         &self,
-        request: tonic::Request<PlaylistCreateRequest>,
-    ) -> std::result::Result<tonic::Response<Playlist>, tonic::Status> {
-        let user = self.require_user(&request).await?;
-
+        request: tonic::Request<RatingListRequest>,
+    ) -> std::result::Result<tonic::Response<RatingListResponse>, tonic::Status> {
+        let user = self
+            .require_user(&request, sonar::ApiKeyAccess::Read)
+            .await?;
         let req = request.into_inner();
-        let create: sonar::PlaylistCreate = TryFrom::try_from(req)?;
+        let target_user = parse_userid(req.user_id)?;
+        let item_ids = parse_sonarids(req.item_ids)?;
 
-        if user.id != create.owner && !user.admin {
+        if target_user != user.id && !user.admin {
             return Err(tonic::Status::permission_denied(
-                "not the owner of the playlist",
+                "not allowed to view user's ratings",
             ));
         }
 
-        let playlist = sonar::playlist_create(&self.context, create).await.m()?;
-        Ok(tonic::Response::new(playlist.into()))
+        let ratings = if item_ids.is_empty() {
+            sonar::rating_list(&self.context, target_user).await.m()?
+        } else {
+            sonar::rating_get_bulk(&self.context, target_user, &item_ids)
+                .await
+                .m()?
+        };
+        let ratings = ratings.into_iter().map(Into::into).collect();
+        Ok(tonic::Response::new(RatingListResponse { ratings }))
     }
-    async fn playlist_duplicate(
+    async fn rating_set(
         &self,
-        request: tonic::Request<PlaylistDuplicateRequest>,
-    ) -> std::result::Result<tonic::Response<Playlist>, tonic::Status> {
-        let user = self.require_user(&request).await?;
-
+        request: tonic::Request<RatingSetRequest>,
+    ) -> std::result::Result<tonic::Response<()>, tonic::Status> {
+        let user = self
+            .require_user(&request, sonar::ApiKeyAccess::Write)
+            .await?;
         let req = request.into_inner();
-        let user_id = req.user_id.parse::<sonar::UserId>().m()?;
-        let playlist_id = req.playlist_id.parse::<sonar::PlaylistId>().m()?;
-        let new_name = req.new_playlist_name;
-        let playlist = sonar::playlist_get(&self.context, playlist_id).await.m()?;
-        if playlist.owner != user_id {
-            return Err(tonic::Status::permission_denied(
-                "not the owner of the playlist",
-            ));
-        }
+        let target_user = parse_userid(req.user_id)?;
+        let item_id = parse_sonarid(req.item_id)?;
+        let rating = sonar::Rating::new(req.rating).m()?;
 
-        if user.id != playlist.owner && !user.admin {
+        if target_user != user.id && !user.admin {
             return Err(tonic::Status::permission_denied(
-                "not the owner of the playlist",
+                "not allowed to rate items for target user",
             ));
         }
 
-        let playlist = sonar::playlist_duplicate(&self.context, playlist_id, &new_name)
+        sonar::rating_set(&self.context, target_user, item_id, rating)
             .await
             .m()?;
-        Ok(tonic::Response::new(playlist.into()))
+        Ok(tonic::Response::new(()))
     }
-    async fn playlist_update(
+    async fn rating_remove(
         &self,
-        request: tonic::Request<PlaylistUpdateRequest>,
-    ) -> std::result::Result<tonic::Response<Playlist>, tonic::Status> {
-        let user = self.require_user(&request).await?;
-
+        request: tonic::Request<RatingRemoveRequest>,
+    ) -> std::result::Result<tonic::Response<()>, tonic::Status> {
+        let user = self
+            .require_user(&request, sonar::ApiKeyAccess::Write)
+            .await?;
         let req = request.into_inner();
-        let (playlist_id, update): (sonar::PlaylistId, sonar::PlaylistUpdate) =
-            TryFrom::try_from(req)?;
-        let playlist = sonar::playlist_get(&self.context, playlist_id).await.m()?;
+        let target_user = parse_userid(req.user_id)?;
+        let item_id = parse_sonarid(req.item_id)?;
 
-        if user.id != playlist.owner && !user.admin {
+        if target_user != user.id && !user.admin {
             return Err(tonic::Status::permission_denied(
-                "not the owner of the playlist",
+                "not allowed to remove ratings from target user",
             ));
         }
 
-        let playlist = sonar::playlist_update(&self.context, playlist_id, update)
+        sonar::rating_remove(&self.context, target_user, item_id)
             .await
             .m()?;
-        Ok(tonic::Response::new(playlist.into()))
+        Ok(tonic::Response::new(()))
     }
-    async fn playlist_delete(
+    async fn rating_average_list(
         &self,
-        request: tonic::Request<PlaylistDeleteRequest>,
-    ) -> std::result::Result<tonic::Response<()>, tonic::Status> {
-        let user = self.require_user(&request).await?;
-
+        request: tonic::Request<RatingAverageListRequest>,
+    ) -> std::result::Result<tonic::Response<RatingAverageListResponse>, tonic::Status> {
+        self.require_user(&request, sonar::ApiKeyAccess::Read)
+            .await?;
         let req = request.into_inner();
-        let playlist_id = req.playlist_id.parse::<sonar::PlaylistId>().m()?;
-        let playlist = sonar::playlist_get(&self.context, playlist_id).await.m()?;
+        let item_ids = parse_sonarids(req.item_ids)?;
+        let ratings = sonar::rating_average_get_bulk(&self.context, &item_ids)
+            .await
+            .m()?
+            .into_iter()
+            .map(Into::into)
+            .collect();
+        Ok(tonic::Response::new(RatingAverageListResponse { ratings }))
+    }
+    async fn play_queue_get(
+        &self,
+        request: tonic::Request<PlayQueueGetRequest>,
+    ) -> std::result::Result<tonic::Response<PlayQueue>, tonic::Status> {
+        let user = self
+            .require_user(&request, sonar::ApiKeyAccess::Read)
+            .await?;
+        let req = request.into_inner();
+        let target_user = parse_userid(req.user_id)?;
 
-        if user.id != playlist.owner && !user.admin {
+        if target_user != user.id && !user.admin {
             return Err(tonic::Status::permission_denied(
-                "not the owner of the playlist",
+                "not allowed to view user's play queue",
             ));
         }
 
-        sonar::playlist_delete(&self.context, playlist_id)
+        let queue = match sonar::play_queue_get(&self.context, target_user)
             .await
-            .m()?;
-        Ok(tonic::Response::new(()))
+            .m()?
+        {
+            Some(queue) => queue.into(),
+            None => PlayQueue {
+                user_id: target_user.to_string(),
+                ..Default::default()
+            },
+        };
+        Ok(tonic::Response::new(queue))
+    }
+    async fn play_queue_save(
+        &self,
+        request: tonic::Request<PlayQueueSaveRequest>,
+    ) -> std::result::Result<tonic::Response<PlayQueue>, tonic::Status> {
+        let user = self
+            .require_user(&request, sonar::ApiKeyAccess::Write)
+            .await?;
+        let (target_user, save) = TryFrom::try_from(request.into_inner())?;
+
+        if target_user != user.id && !user.admin {
+            return Err(tonic::Status::permission_denied(
+                "not allowed to save play queue for target user",
+            ));
+        }
+
+        let queue = sonar::play_queue_save(&self.context, target_user, save)
+            .await
+            .m()?;
+        Ok(tonic::Response::new(queue.into()))
+    }
+    async fn play_queue_clear(
+        &self,
+        request: tonic::Request<PlayQueueClearRequest>,
+    ) -> std::result::Result<tonic::Response<()>, tonic::Status> {
+        let user = self
+            .require_user(&request, sonar::ApiKeyAccess::Write)
+            .await?;
+        let req = request.into_inner();
+        let target_user = parse_userid(req.user_id)?;
+
+        if target_user != user.id && !user.admin {
+            return Err(tonic::Status::permission_denied(
+                "not allowed to clear play queue of target user",
+            ));
+        }
+
+        sonar::play_queue_clear(&self.context, target_user)
+            .await
+            .m()?;
+        Ok(tonic::Response::new(()))
+    }
+    async fn bookmark_list(
+        &self,
+        request: tonic::Request<BookmarkListRequest>,
+    ) -> std::result::Result<tonic::Response<BookmarkListResponse>, tonic::Status> {
+        let user = self
+            .require_user(&request, sonar::ApiKeyAccess::Read)
+            .await?;
+        let req = request.into_inner();
+        let target_user = parse_userid(req.user_id)?;
+
+        if target_user != user.id && !user.admin {
+            return Err(tonic::Status::permission_denied(
+                "not allowed to view user's bookmarks",
+            ));
+        }
+
+        let bookmarks = sonar::bookmark_list(&self.context, target_user)
+            .await
+            .m()?
+            .into_iter()
+            .map(Into::into)
+            .collect();
+        Ok(tonic::Response::new(BookmarkListResponse { bookmarks }))
+    }
+    async fn bookmark_create(
+        &self,
+        request: tonic::Request<BookmarkCreateRequest>,
+    ) -> std::result::Result<tonic::Response<Bookmark>, tonic::Status> {
+        let user = self
+            .require_user(&request, sonar::ApiKeyAccess::Write)
+            .await?;
+        let create = sonar::BookmarkCreate::try_from(request.into_inner())?;
+
+        if create.user != user.id && !user.admin {
+            return Err(tonic::Status::permission_denied(
+                "not allowed to create bookmarks for target user",
+            ));
+        }
+
+        let bookmark = sonar::bookmark_create(&self.context, create).await.m()?;
+        Ok(tonic::Response::new(bookmark.into()))
+    }
+    async fn bookmark_delete(
+        &self,
+        request: tonic::Request<BookmarkDeleteRequest>,
+    ) -> std::result::Result<tonic::Response<()>, tonic::Status> {
+        let user = self
+            .require_user(&request, sonar::ApiKeyAccess::Write)
+            .await?;
+        let req = request.into_inner();
+        let target_user = parse_userid(req.user_id)?;
+        let track_id = parse_trackid(req.track_id)?;
+
+        if target_user != user.id && !user.admin {
+            return Err(tonic::Status::permission_denied(
+                "not allowed to delete bookmarks of target user",
+            ));
+        }
+
+        sonar::bookmark_delete(&self.context, target_user, track_id)
+            .await
+            .m()?;
+        Ok(tonic::Response::new(()))
+    }
+    async fn playlist_list(
+        &self,
+        request: tonic::Request<PlaylistListRequest>,
+    ) -> std::result::Result<tonic::Response<PlaylistListResponse>, tonic::Status> {
+        let req = request.into_inner();
+        let params = sonar::ListParams::from((req.offset, req.count));
+        let playlists = sonar::playlist_list(&self.context, params).await.m()?;
+        let playlists = playlists.into_iter().map(Into::into).collect();
+        Ok(tonic::Response::new(PlaylistListResponse { playlists }))
+    }
+    async fn playlist_get(
+        &self,
+        request: tonic::Request<PlaylistGetRequest>,
+    ) -> std::result::Result<tonic::Response<Playlist>, tonic::Status> {
+        let req = request.into_inner();
+        let playlist_id = req.playlist_id.parse::<sonar::PlaylistId>().m()?;
+        let playlist = sonar::playlist_get(&self.context, playlist_id).await.m()?;
+        Ok(tonic::Response::new(playlist.into()))
+    }
+    async fn playlist_create(
+        &self,
+        request: tonic::Request<PlaylistCreateRequest>,
+    ) -> std::result::Result<tonic::Response<Playlist>, tonic::Status> {
+        let user = self
+            .require_user(&request, sonar::ApiKeyAccess::Write)
+            .await?;
+
+        let req = request.into_inner();
+        let create: sonar::PlaylistCreate = TryFrom::try_from(req)?;
+
+        if user.id != create.owner && !user.admin {
+            return Err(tonic::Status::permission_denied(
+                "not the owner of the playlist",
+            ));
+        }
+
+        let playlist = sonar::playlist_create(&self.context, create).await.m()?;
+        Ok(tonic::Response::new(playlist.into()))
+    }
+    async fn playlist_duplicate(
+        &self,
+        request: tonic::Request<PlaylistDuplicateRequest>,
+    ) -> std::result::Result<tonic::Response<Playlist>, tonic::Status> {
+        let user = self
+            .require_user(&request, sonar::ApiKeyAccess::Write)
+            .await?;
+
+        let req = request.into_inner();
+        let user_id = req.user_id.parse::<sonar::UserId>().m()?;
+        let playlist_id = req.playlist_id.parse::<sonar::PlaylistId>().m()?;
+        let new_name = req.new_playlist_name;
+        let playlist = sonar::playlist_get(&self.context, playlist_id).await.m()?;
+        if playlist.owner != user_id {
+            return Err(tonic::Status::permission_denied(
+                "not the owner of the playlist",
+            ));
+        }
+
+        if user.id != playlist.owner && !user.admin {
+            return Err(tonic::Status::permission_denied(
+                "not the owner of the playlist",
+            ));
+        }
+
+        let playlist = sonar::playlist_duplicate(&self.context, playlist_id, &new_name)
+            .await
+            .m()?;
+        Ok(tonic::Response::new(playlist.into()))
+    }
+    async fn playlist_update(
+        &self,
+        request: tonic::Request<PlaylistUpdateRequest>,
+    ) -> std::result::Result<tonic::Response<Playlist>, tonic::Status> {
+        let user = self
+            .require_user(&request, sonar::ApiKeyAccess::Write)
+            .await?;
+
+        let req = request.into_inner();
+        let (playlist_id, update): (sonar::PlaylistId, sonar::PlaylistUpdate) =
+            TryFrom::try_from(req)?;
+        let playlist = sonar::playlist_get(&self.context, playlist_id).await.m()?;
+
+        if user.id != playlist.owner && !user.admin {
+            return Err(tonic::Status::permission_denied(
+                "not the owner of the playlist",
+            ));
+        }
+
+        let playlist = sonar::playlist_update(&self.context, playlist_id, update)
+            .await
+            .m()?;
+        Ok(tonic::Response::new(playlist.into()))
+    }
+    async fn playlist_delete(
+        &self,
+        request: tonic::Request<PlaylistDeleteRequest>,
+    ) -> std::result::Result<tonic::Response<()>, tonic::Status> {
+        let user = self
+            .require_user(&request, sonar::ApiKeyAccess::Write)
+            .await?;
+
+        let req = request.into_inner();
+        let playlist_id = req.playlist_id.parse::<sonar::PlaylistId>().m()?;
+        let playlist = sonar::playlist_get(&self.context, playlist_id).await.m()?;
+
+        if user.id != playlist.owner && !user.admin {
+            return Err(tonic::Status::permission_denied(
+                "not the owner of the playlist",
+            ));
+        }
+
+        sonar::playlist_delete(&self.context, playlist_id)
+            .await
+            .m()?;
+        Ok(tonic::Response::new(()))
+    }
+    async fn smart_playlist_get(
+        &self,
+        request: tonic::Request<SmartPlaylistGetRequest>,
+    ) -> std::result::Result<tonic::Response<SmartPlaylist>, tonic::Status> {
+        let req = request.into_inner();
+        let playlist_id = req.playlist_id.parse::<sonar::PlaylistId>().m()?;
+        let smart_playlist = sonar::smart_playlist_get(&self.context, playlist_id)
+            .await
+            .m()?;
+        Ok(tonic::Response::new(smart_playlist.into()))
+    }
+    async fn smart_playlist_create(
+        &self,
+        request: tonic::Request<SmartPlaylistCreateRequest>,
+    ) -> std::result::Result<tonic::Response<Playlist>, tonic::Status> {
+        let user = self
+            .require_user(&request, sonar::ApiKeyAccess::Write)
+            .await?;
+
+        let req = request.into_inner();
+        let create: sonar::SmartPlaylistCreate = TryFrom::try_from(req)?;
+
+        if user.id != create.owner && !user.admin {
+            return Err(tonic::Status::permission_denied(
+                "not the owner of the playlist",
+            ));
+        }
+
+        let playlist = sonar::smart_playlist_create(&self.context, create)
+            .await
+            .m()?;
+        Ok(tonic::Response::new(playlist.into()))
+    }
+    async fn smart_playlist_update(
+        &self,
+        request: tonic::Request<SmartPlaylistUpdateRequest>,
+    ) -> std::result::Result<tonic::Response<Playlist>, tonic::Status> {
+        let user = self
+            .require_user(&request, sonar::ApiKeyAccess::Write)
+            .await?;
+
+        let req = request.into_inner();
+        let playlist_id = req.playlist_id.parse::<sonar::PlaylistId>().m()?;
+        let rules: sonar::SmartPlaylistRules = req
+            .rules
+            .map(TryFrom::try_from)
+            .transpose()?
+            .unwrap_or_default();
+        let playlist = sonar::playlist_get(&self.context, playlist_id).await.m()?;
+
+        if user.id != playlist.owner && !user.admin {
+            return Err(tonic::Status::permission_denied(
+                "not the owner of the playlist",
+            ));
+        }
+
+        let playlist = sonar::smart_playlist_update(&self.context, playlist_id, rules)
+            .await
+            .m()?;
+        Ok(tonic::Response::new(playlist.into()))
+    }
+    async fn smart_playlist_refresh(
+        &self,
+        request: tonic::Request<SmartPlaylistRefreshRequest>,
+    ) -> std::result::Result<tonic::Response<Playlist>, tonic::Status> {
+        let user = self
+            .require_user(&request, sonar::ApiKeyAccess::Write)
+            .await?;
+
+        let req = request.into_inner();
+        let playlist_id = req.playlist_id.parse::<sonar::PlaylistId>().m()?;
+        let playlist = sonar::playlist_get(&self.context, playlist_id).await.m()?;
+
+        if user.id != playlist.owner && !user.admin {
+            return Err(tonic::Status::permission_denied(
+                "not the owner of the playlist",
+            ));
+        }
+
+        let playlist = sonar::smart_playlist_refresh(&self.context, playlist_id)
+            .await
+            .m()?;
+        Ok(tonic::Response::new(playlist.into()))
     }
     async fn playlist_track_list(
         &self,
@@ -664,34 +1384,59 @@ impl sonar_service_server::SonarService for Server {
                 .await
                 .m()?;
         let track_ids = playlist_tracks
-            .into_iter()
+            .iter()
             .map(|playlist_track| playlist_track.track)
             .collect::<Vec<_>>();
         let tracks = sonar::track_get_bulk(&self.context, &track_ids).await.m()?;
         let tracks = tracks.into_iter().map(Into::into).collect();
-        Ok(tonic::Response::new(PlaylistTrackListResponse { tracks }))
+        let playlist_tracks = playlist_tracks.into_iter().map(Into::into).collect();
+        Ok(tonic::Response::new(PlaylistTrackListResponse {
+            tracks,
+            playlist_tracks,
+        }))
     }
     async fn playlist_track_insert(
         &self,
         request: tonic::Request<PlaylistTrackInsertRequest>,
     ) -> std::result::Result<tonic::Response<()>, tonic::Status> {
+        let user = self
+            .require_user(&request, sonar::ApiKeyAccess::Write)
+            .await?;
+
         let req = request.into_inner();
         let playlist_id = req.playlist_id.parse::<sonar::PlaylistId>().m()?;
+        let playlist = sonar::playlist_get(&self.context, playlist_id).await.m()?;
+
+        if user.id != playlist.owner && !user.admin {
+            return Err(tonic::Status::permission_denied(
+                "not the owner of the playlist",
+            ));
+        }
+
         let track_ids = req
             .track_ids
             .into_iter()
             .map(parse_trackid)
             .collect::<Result<Vec<_>, _>>()?;
-        sonar::playlist_insert_tracks(&self.context, playlist_id, &track_ids)
-            .await
-            .m()?;
+        match req.position {
+            Some(position) => {
+                sonar::playlist_insert_tracks_at(&self.context, playlist_id, position, &track_ids)
+                    .await
+                    .m()?
+            }
+            None => sonar::playlist_insert_tracks(&self.context, playlist_id, &track_ids)
+                .await
+                .m()?,
+        }
         Ok(tonic::Response::new(()))
     }
     async fn playlist_track_remove(
         &self,
         request: tonic::Request<PlaylistTrackRemoveRequest>,
     ) -> std::result::Result<tonic::Response<()>, tonic::Status> {
-        let user = self.require_user(&request).await?;
+        let user = self
+            .require_user(&request, sonar::ApiKeyAccess::Write)
+            .await?;
 
         let req = request.into_inner();
         let playlist_id = req.playlist_id.parse::<sonar::PlaylistId>().m()?;
@@ -708,71 +1453,300 @@ impl sonar_service_server::SonarService for Server {
             .into_iter()
             .map(parse_trackid)
             .collect::<Result<Vec<_>, _>>()?;
-        sonar::playlist_remove_tracks(&self.context, playlist_id, &track_ids)
+        if !req.positions.is_empty() {
+            sonar::playlist_remove_at(&self.context, playlist_id, &req.positions)
+                .await
+                .m()?;
+        }
+        if !track_ids.is_empty() {
+            sonar::playlist_remove_tracks(&self.context, playlist_id, &track_ids)
+                .await
+                .m()?;
+        }
+        Ok(tonic::Response::new(()))
+    }
+    async fn playlist_track_move(
+        &self,
+        request: tonic::Request<PlaylistTrackMoveRequest>,
+    ) -> std::result::Result<tonic::Response<()>, tonic::Status> {
+        let user = self
+            .require_user(&request, sonar::ApiKeyAccess::Write)
+            .await?;
+
+        let req = request.into_inner();
+        let playlist_id = req.playlist_id.parse::<sonar::PlaylistId>().m()?;
+        let playlist = sonar::playlist_get(&self.context, playlist_id).await.m()?;
+
+        if user.id != playlist.owner && !user.admin {
+            return Err(tonic::Status::permission_denied(
+                "not the owner of the playlist",
+            ));
+        }
+
+        sonar::playlist_move_tracks(&self.context, playlist_id, req.from, req.count, req.to)
+            .await
+            .m()?;
+        Ok(tonic::Response::new(()))
+    }
+    async fn playlist_track_clear(
+        &self,
+        request: tonic::Request<PlaylistTrackClearRequest>,
+    ) -> std::result::Result<tonic::Response<()>, tonic::Status> {
+        let user = self
+            .require_user(&request, sonar::ApiKeyAccess::Write)
+            .await?;
+
+        let req = request.into_inner();
+        let playlist_id = req.playlist_id.parse::<sonar::PlaylistId>().m()?;
+        let playlist = sonar::playlist_get(&self.context, playlist_id).await.m()?;
+
+        if user.id != playlist.owner && !user.admin {
+            return Err(tonic::Status::permission_denied(
+                "not the owner of the playlist",
+            ));
+        }
+
+        sonar::playlist_clear_tracks(&self.context, playlist_id)
+            .await
+            .m()?;
+        Ok(tonic::Response::new(()))
+    }
+    async fn scrobble_list(
+        &self,
+        request: tonic::Request<ScrobbleListRequest>,
+    ) -> std::result::Result<tonic::Response<ScrobbleListResponse>, tonic::Status> {
+        let req = request.into_inner();
+        let params = sonar::ListParams::from((req.offset, req.count));
+        let scrobbles = sonar::scrobble_list(&self.context, params).await.m()?;
+        let scrobbles = scrobbles.into_iter().map(Into::into).collect();
+        Ok(tonic::Response::new(ScrobbleListResponse { scrobbles }))
+    }
+    async fn scrobble_create(
+        &self,
+        request: tonic::Request<ScrobbleCreateRequest>,
+    ) -> std::result::Result<tonic::Response<Scrobble>, tonic::Status> {
+        self.require_admin(&request).await?;
+
+        let req = request.into_inner();
+        let create = TryFrom::try_from(req)?;
+        let scrobble = sonar::scrobble_create(&self.context, create).await.m()?;
+        Ok(tonic::Response::new(scrobble.into()))
+    }
+    async fn scrobble_delete(
+        &self,
+        request: tonic::Request<ScrobbleDeleteRequest>,
+    ) -> std::result::Result<tonic::Response<()>, tonic::Status> {
+        self.require_admin(&request).await?;
+
+        let req = request.into_inner();
+        let scrobble_id = req.scrobble_id.parse::<sonar::ScrobbleId>().m()?;
+        sonar::scrobble_delete(&self.context, scrobble_id)
+            .await
+            .m()?;
+        Ok(tonic::Response::new(()))
+    }
+    async fn scrobble_import(
+        &self,
+        request: tonic::Request<ScrobbleImportRequest>,
+    ) -> std::result::Result<tonic::Response<ScrobbleImportResponse>, tonic::Status> {
+        let user = self
+            .require_user(&request, sonar::ApiKeyAccess::Write)
+            .await?;
+        let req = request.into_inner();
+        let import = sonar::ScrobbleImport::try_from(req)?;
+        if import.user != user.id && !user.admin {
+            return Err(tonic::Status::permission_denied(
+                "cannot import other user's scrobbles",
+            ));
+        }
+        let report = sonar::scrobble_import(&self.context, import).await.m()?;
+        Ok(tonic::Response::new(report.into()))
+    }
+    async fn stats_get(
+        &self,
+        request: tonic::Request<StatsGetRequest>,
+    ) -> std::result::Result<tonic::Response<Stats>, tonic::Status> {
+        let user = self
+            .require_user(&request, sonar::ApiKeyAccess::Read)
+            .await?;
+        let req = request.into_inner();
+        let params = sonar::StatsParams::try_from(req)?;
+        if params.user != user.id && !user.admin {
+            return Err(tonic::Status::permission_denied(
+                "cannot get other user's stats",
+            ));
+        }
+        let stats = sonar::stats_get(&self.context, params).await.m()?;
+        Ok(tonic::Response::new(stats.into()))
+    }
+    async fn similar_track_list(
+        &self,
+        request: tonic::Request<SimilarTrackListRequest>,
+    ) -> std::result::Result<tonic::Response<TrackListResponse>, tonic::Status> {
+        let user = self
+            .require_user(&request, sonar::ApiKeyAccess::Read)
+            .await?;
+        let req = request.into_inner();
+        let id = req.id.parse::<sonar::SonarId>().m()?;
+        let limit = req.limit.unwrap_or(DEFAULT_SIMILAR_LIMIT);
+        let filter = sonar::LibraryFilter::user(user.id);
+        let track_ids = sonar::recommend_similar_tracks_filtered(&self.context, filter, id, limit)
             .await
             .m()?;
-        Ok(tonic::Response::new(()))
+        let tracks = sonar::track_get_bulk(&self.context, &track_ids).await.m()?;
+        let tracks = tracks.into_iter().map(Into::into).collect();
+        Ok(tonic::Response::new(TrackListResponse { tracks }))
     }
-    async fn playlist_track_clear(
+    async fn similar_artist_list(
         &self,
-        request: tonic::Request<PlaylistTrackClearRequest>,
-    ) -> std::result::Result<tonic::Response<()>, tonic::Status> {
-        let user = self.require_user(&request).await?;
-
+        request: tonic::Request<SimilarArtistListRequest>,
+    ) -> std::result::Result<tonic::Response<ArtistListResponse>, tonic::Status> {
+        let user = self
+            .require_user(&request, sonar::ApiKeyAccess::Read)
+            .await?;
         let req = request.into_inner();
-        let playlist_id = req.playlist_id.parse::<sonar::PlaylistId>().m()?;
-        let playlist = sonar::playlist_get(&self.context, playlist_id).await.m()?;
-
-        if user.id != playlist.owner && !user.admin {
+        let artist_id = req.artist_id.parse::<sonar::ArtistId>().m()?;
+        let limit = req.limit.unwrap_or(DEFAULT_SIMILAR_LIMIT);
+        let filter = sonar::LibraryFilter::user(user.id);
+        let artist_ids =
+            sonar::recommend_similar_artists_filtered(&self.context, filter, artist_id, limit)
+                .await
+                .m()?;
+        let artists = sonar::artist_get_bulk(&self.context, &artist_ids)
+            .await
+            .m()?;
+        let artists = artists.into_iter().map(Into::into).collect();
+        Ok(tonic::Response::new(ArtistListResponse { artists }))
+    }
+    async fn daily_mix_refresh(
+        &self,
+        request: tonic::Request<DailyMixRefreshRequest>,
+    ) -> std::result::Result<tonic::Response<DailyMixRefreshResponse>, tonic::Status> {
+        let user = self
+            .require_user(&request, sonar::ApiKeyAccess::Write)
+            .await?;
+        let req = request.into_inner();
+        let params = sonar::DailyMixParams::try_from(req)?;
+        if params.user != user.id && !user.admin {
             return Err(tonic::Status::permission_denied(
-                "not the owner of the playlist",
+                "cannot refresh other user's daily mixes",
             ));
         }
-
-        sonar::playlist_clear_tracks(&self.context, playlist_id)
+        let playlists = sonar::recommend_daily_mix_refresh(&self.context, params)
             .await
             .m()?;
-        Ok(tonic::Response::new(()))
+        let playlists = playlists.into_iter().map(Into::into).collect();
+        Ok(tonic::Response::new(DailyMixRefreshResponse { playlists }))
     }
-    async fn scrobble_list(
+    async fn radio_get(
         &self,
-        request: tonic::Request<ScrobbleListRequest>,
-    ) -> std::result::Result<tonic::Response<ScrobbleListResponse>, tonic::Status> {
+        request: tonic::Request<RadioGetRequest>,
+    ) -> std::result::Result<tonic::Response<Radio>, tonic::Status> {
+        let user = self
+            .require_user(&request, sonar::ApiKeyAccess::Read)
+            .await?;
         let req = request.into_inner();
-        let params = sonar::ListParams::from((req.offset, req.count));
-        let scrobbles = sonar::scrobble_list(&self.context, params).await.m()?;
-        let scrobbles = scrobbles.into_iter().map(Into::into).collect();
-        Ok(tonic::Response::new(ScrobbleListResponse { scrobbles }))
+        let radio = self.radio_lookup(&user, &req.radio_id).await?;
+        Ok(tonic::Response::new(radio.into()))
     }
-    async fn scrobble_create(
+    async fn radio_create(
         &self,
-        request: tonic::Request<ScrobbleCreateRequest>,
-    ) -> std::result::Result<tonic::Response<Scrobble>, tonic::Status> {
-        self.require_admin(&request).await?;
-
+        request: tonic::Request<RadioCreateRequest>,
+    ) -> std::result::Result<tonic::Response<Radio>, tonic::Status> {
+        let user = self
+            .require_user(&request, sonar::ApiKeyAccess::Write)
+            .await?;
         let req = request.into_inner();
-        let create = TryFrom::try_from(req)?;
-        let scrobble = sonar::scrobble_create(&self.context, create).await.m()?;
-        Ok(tonic::Response::new(scrobble.into()))
+        let create = sonar::RadioCreate::try_from(req)?;
+        if create.user != user.id && !user.admin {
+            return Err(tonic::Status::permission_denied(
+                "cannot create radio for other user",
+            ));
+        }
+        let radio = sonar::radio_create(&self.context, create).await.m()?;
+        Ok(tonic::Response::new(radio.into()))
     }
-    async fn scrobble_delete(
+    async fn radio_delete(
         &self,
-        request: tonic::Request<ScrobbleDeleteRequest>,
+        request: tonic::Request<RadioDeleteRequest>,
     ) -> std::result::Result<tonic::Response<()>, tonic::Status> {
-        self.require_admin(&request).await?;
-
+        let user = self
+            .require_user(&request, sonar::ApiKeyAccess::Write)
+            .await?;
         let req = request.into_inner();
-        let scrobble_id = req.scrobble_id.parse::<sonar::ScrobbleId>().m()?;
-        sonar::scrobble_delete(&self.context, scrobble_id)
+        let radio = self.radio_lookup(&user, &req.radio_id).await?;
+        sonar::radio_delete(&self.context, radio.id).await.m()?;
+        Ok(tonic::Response::new(()))
+    }
+    async fn radio_next(
+        &self,
+        request: tonic::Request<RadioNextRequest>,
+    ) -> std::result::Result<tonic::Response<TrackListResponse>, tonic::Status> {
+        let user = self
+            .require_user(&request, sonar::ApiKeyAccess::Read)
+            .await?;
+        let req = request.into_inner();
+        let radio = self.radio_lookup(&user, &req.radio_id).await?;
+        let count = req.count.unwrap_or(DEFAULT_RADIO_COUNT);
+        let track_ids = sonar::radio_next(&self.context, radio.id, count)
             .await
             .m()?;
-        Ok(tonic::Response::new(()))
+        let tracks = sonar::track_get_bulk(&self.context, &track_ids).await.m()?;
+        let tracks = tracks.into_iter().map(Into::into).collect();
+        Ok(tonic::Response::new(TrackListResponse { tracks }))
+    }
+    async fn radio_stream(
+        &self,
+        request: tonic::Request<RadioStreamRequest>,
+    ) -> std::result::Result<tonic::Response<Self::RadioStreamStream>, tonic::Status> {
+        let user = self
+            .require_user(&request, sonar::ApiKeyAccess::Read)
+            .await?;
+        let req = request.into_inner();
+        let radio = self.radio_lookup(&user, &req.radio_id).await?;
+        let batch_size = req.batch_size.unwrap_or(DEFAULT_RADIO_COUNT);
+        if batch_size == 0 {
+            return Err(tonic::Status::invalid_argument(
+                "batch size must be greater than zero",
+            ));
+        }
+
+        // the channel is bounded so at most one batch is generated ahead of what the client read
+        let (sender, receiver) = tokio::sync::mpsc::channel(batch_size as usize);
+        let context = self.context.clone();
+        tokio::spawn(async move {
+            loop {
+                let tracks = match sonar::radio_next(&context, radio.id, batch_size).await {
+                    Ok(track_ids) => sonar::track_get_bulk(&context, &track_ids).await,
+                    Err(err) => Err(err),
+                };
+                let tracks = match tracks {
+                    Ok(tracks) if tracks.is_empty() => break,
+                    Ok(tracks) => tracks,
+                    Err(err) => {
+                        tracing::error!("radio stream failed: {err}");
+                        let _ = sender
+                            .send(Err(tonic::Status::internal(err.to_string())))
+                            .await;
+                        break;
+                    }
+                };
+                for track in tracks {
+                    if sender.send(Ok(track.into())).await.is_err() {
+                        return;
+                    }
+                }
+            }
+        });
+        Ok(tonic::Response::new(ReceiverStream::new(receiver)))
     }
     async fn pin_list(
         &self,
         request: tonic::Request<PinListRequest>,
     ) -> std::result::Result<tonic::Response<PinListResponse>, tonic::Status> {
-        let user = self.require_user(&request).await?;
+        let user = self
+            .require_user(&request, sonar::ApiKeyAccess::Read)
+            .await?;
 
         let req = request.into_inner();
         let user_id = req.user_id.parse::<sonar::UserId>().m()?;
@@ -790,7 +1764,9 @@ impl sonar_service_server::SonarService for Server {
         &self,
         request: tonic::Request<PinSetRequest>,
     ) -> std::result::Result<tonic::Response<()>, tonic::Status> {
-        let user = self.require_user(&request).await?;
+        let user = self
+            .require_user(&request, sonar::ApiKeyAccess::Write)
+            .await?;
 
         let req = request.into_inner();
         let user_id = req.user_id.parse::<sonar::UserId>().m()?;
@@ -814,7 +1790,9 @@ impl sonar_service_server::SonarService for Server {
         &self,
         request: tonic::Request<PinUnsetRequest>,
     ) -> std::result::Result<tonic::Response<()>, tonic::Status> {
-        let user = self.require_user(&request).await?;
+        let user = self
+            .require_user(&request, sonar::ApiKeyAccess::Write)
+            .await?;
 
         let req = request.into_inner();
         let user_id = req.user_id.parse::<sonar::UserId>().m()?;
@@ -840,10 +1818,21 @@ impl sonar_service_server::SonarService for Server {
         &self,
         request: tonic::Request<SubscriptionListRequest>,
     ) -> std::result::Result<tonic::Response<SubscriptionListResponse>, tonic::Status> {
-        self.require_admin(&request).await?;
+        let user = self
+            .require_role(
+                &request,
+                sonar::ApiKeyAccess::Read,
+                sonar::UserRole::ManageSubscriptions,
+            )
+            .await?;
 
         let req = request.into_inner();
         let user_id = req.user_id.parse::<sonar::UserId>().m()?;
+        if user.id != user_id && !user.admin {
+            return Err(tonic::Status::permission_denied(
+                "cannot list other user's subscriptions",
+            ));
+        }
         let subscriptions = sonar::subscription_list(&self.context, user_id).await.m()?;
         let subscriptions = subscriptions.into_iter().map(Into::into).collect();
         Ok(tonic::Response::new(SubscriptionListResponse {
@@ -854,10 +1843,21 @@ impl sonar_service_server::SonarService for Server {
         &self,
         request: tonic::Request<SubscriptionCreateRequest>,
     ) -> std::result::Result<tonic::Response<()>, tonic::Status> {
-        self.require_admin(&request).await?;
+        let user = self
+            .require_role(
+                &request,
+                sonar::ApiKeyAccess::Write,
+                sonar::UserRole::ManageSubscriptions,
+            )
+            .await?;
 
         let req = request.into_inner();
         let user_id = req.user_id.parse::<sonar::UserId>().m()?;
+        if user.id != user_id && !user.admin {
+            return Err(tonic::Status::permission_denied(
+                "cannot create other user's subscriptions",
+            ));
+        }
         sonar::subscription_create(
             &self.context,
             sonar::SubscriptionCreate {
@@ -879,6 +1879,7 @@ impl sonar_service_server::SonarService for Server {
                     },
                     None => None,
                 },
+                library: parse_libraryid_opt(req.library_id)?,
             },
         )
         .await
@@ -889,9 +1890,15 @@ impl sonar_service_server::SonarService for Server {
         &self,
         request: tonic::Request<SubscriptionDeleteRequest>,
     ) -> std::result::Result<tonic::Response<()>, tonic::Status> {
-        self.require_admin(&request).await?;
+        let user = self
+            .require_role(
+                &request,
+                sonar::ApiKeyAccess::Write,
+                sonar::UserRole::ManageSubscriptions,
+            )
+            .await?;
         let req = request.into_inner();
-        let subscription_id = req.id.parse::<sonar::SubscriptionId>().m()?;
+        let subscription_id = self.subscription_lookup(&user, &req.id).await?;
         sonar::subscription_delete(&self.context, subscription_id)
             .await
             .m()?;
@@ -901,9 +1908,15 @@ impl sonar_service_server::SonarService for Server {
         &self,
         request: tonic::Request<SubscriptionSubmitRequest>,
     ) -> std::result::Result<tonic::Response<()>, tonic::Status> {
-        self.require_admin(&request).await?;
+        let user = self
+            .require_role(
+                &request,
+                sonar::ApiKeyAccess::Write,
+                sonar::UserRole::ManageSubscriptions,
+            )
+            .await?;
         let req = request.into_inner();
-        let subscription_id = req.id.parse::<sonar::SubscriptionId>().m()?;
+        let subscription_id = self.subscription_lookup(&user, &req.id).await?;
         sonar::subscription_submit(&self.context, subscription_id)
             .await
             .m()?;
@@ -914,7 +1927,9 @@ impl sonar_service_server::SonarService for Server {
         request: tonic::Request<tonic::Streaming<ImportRequest>>,
     ) -> std::result::Result<tonic::Response<Track>, tonic::Status> {
         let (mt, _, mut stream) = request.into_parts();
-        self.require_admin_mt(&mt).await?;
+        let user = self
+            .require_role_mt(&mt, sonar::ApiKeyAccess::Write, sonar::UserRole::Upload)
+            .await?;
 
         let first_message = match stream.message().await? {
             Some(message) => message,
@@ -931,11 +1946,16 @@ impl sonar_service_server::SonarService for Server {
             .map(|id| id.parse::<sonar::AlbumId>())
             .transpose()
             .m()?;
+        let library = parse_libraryid_opt(first_message.library_id)?;
+        if let Some(library_id) = library {
+            self.require_library_access(&user, library_id).await?;
+        }
         let track = sonar::import(
             &self.context,
             sonar::Import {
                 artist,
                 album,
+                library,
                 filepath,
                 stream: Box::new(ImportStream {
                     first_chunk: Some(Bytes::from(first_message.chunk)),
@@ -951,18 +1971,231 @@ impl sonar_service_server::SonarService for Server {
         &self,
         request: tonic::Request<SearchRequest>,
     ) -> std::result::Result<tonic::Response<SearchResponse>, tonic::Status> {
+        let user = self
+            .require_user(&request, sonar::ApiKeyAccess::Read)
+            .await?;
         let req = request.into_inner();
         let (user_id, query) = TryFrom::try_from(req)?;
+        if user.id != user_id && !user.admin {
+            return Err(tonic::Status::permission_denied(
+                "cannot search as another user",
+            ));
+        }
         let results = sonar::search(&self.context, user_id, query).await.m()?;
         Ok(tonic::Response::new(SearchResponse {
             results: results.results.into_iter().map(Into::into).collect(),
         }))
     }
+    async fn watch(
+        &self,
+        request: tonic::Request<WatchRequest>,
+    ) -> std::result::Result<tonic::Response<Self::WatchStream>, tonic::Status> {
+        let user = self
+            .require_user(&request, sonar::ApiKeyAccess::Read)
+            .await?;
+        let req = request.into_inner();
+        let mut filter = sonar::EventFilter::try_from(req)?;
+        if !user.admin {
+            filter.user = Some(user.id);
+        }
+        let mut subscription = sonar::event_subscribe(&self.context, filter).m()?;
+
+        let (sender, receiver) = tokio::sync::mpsc::channel(WATCH_CHANNEL_CAPACITY);
+        tokio::spawn(async move {
+            loop {
+                let event = tokio::select! {
+                    event = subscription.recv() => event,
+                    _ = sender.closed() => break,
+                };
+                let event = event
+                    .map(Event::from)
+                    .map_err(|err| tonic::Status::aborted(err.to_string()));
+                let failed = event.is_err();
+                if sender.send(event).await.is_err() || failed {
+                    break;
+                }
+            }
+        });
+        Ok(tonic::Response::new(ReceiverStream::new(receiver)))
+    }
+    async fn library_list(
+        &self,
+        request: tonic::Request<LibraryListRequest>,
+    ) -> std::result::Result<tonic::Response<LibraryListResponse>, tonic::Status> {
+        let user = self
+            .require_user(&request, sonar::ApiKeyAccess::Read)
+            .await?;
+
+        let libraries = sonar::library_list_by_user(&self.context, user.id)
+            .await
+            .m()?;
+        let libraries = libraries.into_iter().map(Into::into).collect();
+        Ok(tonic::Response::new(LibraryListResponse { libraries }))
+    }
+    async fn library_create(
+        &self,
+        request: tonic::Request<LibraryCreateRequest>,
+    ) -> std::result::Result<tonic::Response<Library>, tonic::Status> {
+        self.require_admin(&request).await?;
+
+        let req = request.into_inner();
+        let library = sonar::library_create(&self.context, sonar::LibraryCreate { name: req.name })
+            .await
+            .m()?;
+        Ok(tonic::Response::new(library.into()))
+    }
+    async fn library_update(
+        &self,
+        request: tonic::Request<LibraryUpdateRequest>,
+    ) -> std::result::Result<tonic::Response<Library>, tonic::Status> {
+        self.require_admin(&request).await?;
+
+        let req = request.into_inner();
+        let library_id = parse_libraryid(req.library_id)?;
+        let update = sonar::LibraryUpdate {
+            name: sonar::ValueUpdate::from_option_unchanged(req.name),
+        };
+        let library = sonar::library_update(&self.context, library_id, update)
+            .await
+            .m()?;
+        Ok(tonic::Response::new(library.into()))
+    }
+    async fn library_delete(
+        &self,
+        request: tonic::Request<LibraryDeleteRequest>,
+    ) -> std::result::Result<tonic::Response<()>, tonic::Status> {
+        self.require_admin(&request).await?;
+
+        let req = request.into_inner();
+        let library_id = parse_libraryid(req.library_id)?;
+        sonar::library_delete(&self.context, library_id).await.m()?;
+        Ok(tonic::Response::new(()))
+    }
+    async fn library_user_list(
+        &self,
+        request: tonic::Request<LibraryUserListRequest>,
+    ) -> std::result::Result<tonic::Response<LibraryUserListResponse>, tonic::Status> {
+        self.require_admin(&request).await?;
+
+        let req = request.into_inner();
+        let library_id = parse_libraryid(req.library_id)?;
+        let user_ids = sonar::library_user_list(&self.context, library_id)
+            .await
+            .m()?;
+        let user_ids = user_ids.into_iter().map(|id| id.to_string()).collect();
+        Ok(tonic::Response::new(LibraryUserListResponse { user_ids }))
+    }
+    async fn library_grant(
+        &self,
+        request: tonic::Request<LibraryGrantRequest>,
+    ) -> std::result::Result<tonic::Response<()>, tonic::Status> {
+        self.require_admin(&request).await?;
+
+        let req = request.into_inner();
+        let library_id = parse_libraryid(req.library_id)?;
+        let user_id = parse_userid(req.user_id)?;
+        sonar::library_grant(&self.context, library_id, user_id)
+            .await
+            .m()?;
+        Ok(tonic::Response::new(()))
+    }
+    async fn library_revoke(
+        &self,
+        request: tonic::Request<LibraryRevokeRequest>,
+    ) -> std::result::Result<tonic::Response<()>, tonic::Status> {
+        self.require_admin(&request).await?;
+
+        let req = request.into_inner();
+        let library_id = parse_libraryid(req.library_id)?;
+        let user_id = parse_userid(req.user_id)?;
+        sonar::library_revoke(&self.context, library_id, user_id)
+            .await
+            .m()?;
+        Ok(tonic::Response::new(()))
+    }
+    async fn webhook_list(
+        &self,
+        request: tonic::Request<WebhookListRequest>,
+    ) -> std::result::Result<tonic::Response<WebhookListResponse>, tonic::Status> {
+        self.require_admin(&request).await?;
+
+        let webhooks = sonar::webhook_list(&self.context).await.m()?;
+        let webhooks = webhooks.into_iter().map(Into::into).collect();
+        Ok(tonic::Response::new(WebhookListResponse { webhooks }))
+    }
+    async fn webhook_get(
+        &self,
+        request: tonic::Request<WebhookGetRequest>,
+    ) -> std::result::Result<tonic::Response<Webhook>, tonic::Status> {
+        self.require_admin(&request).await?;
+
+        let req = request.into_inner();
+        let webhook_id = req.webhook_id.parse::<sonar::WebhookId>().m()?;
+        let webhook = sonar::webhook_get(&self.context, webhook_id).await.m()?;
+        Ok(tonic::Response::new(webhook.into()))
+    }
+    async fn webhook_create(
+        &self,
+        request: tonic::Request<WebhookCreateRequest>,
+    ) -> std::result::Result<tonic::Response<Webhook>, tonic::Status> {
+        self.require_admin(&request).await?;
+
+        let req = request.into_inner();
+        let create = sonar::WebhookCreate::try_from(req)?;
+        let webhook = sonar::webhook_create(&self.context, create).await.m()?;
+        Ok(tonic::Response::new(webhook.into()))
+    }
+    async fn webhook_update(
+        &self,
+        request: tonic::Request<WebhookUpdateRequest>,
+    ) -> std::result::Result<tonic::Response<Webhook>, tonic::Status> {
+        self.require_admin(&request).await?;
+
+        let req = request.into_inner();
+        let (webhook_id, update) = TryFrom::try_from(req)?;
+        let webhook = sonar::webhook_update(&self.context, webhook_id, update)
+            .await
+            .m()?;
+        Ok(tonic::Response::new(webhook.into()))
+    }
+    async fn webhook_delete(
+        &self,
+        request: tonic::Request<WebhookDeleteRequest>,
+    ) -> std::result::Result<tonic::Response<()>, tonic::Status> {
+        self.require_admin(&request).await?;
+
+        let req = request.into_inner();
+        let webhook_id = req.webhook_id.parse::<sonar::WebhookId>().m()?;
+        sonar::webhook_delete(&self.context, webhook_id).await.m()?;
+        Ok(tonic::Response::new(()))
+    }
+    async fn webhook_delivery_list(
+        &self,
+        request: tonic::Request<WebhookDeliveryListRequest>,
+    ) -> std::result::Result<tonic::Response<WebhookDeliveryListResponse>, tonic::Status> {
+        self.require_admin(&request).await?;
+
+        let req = request.into_inner();
+        let webhook_id = req.webhook_id.parse::<sonar::WebhookId>().m()?;
+        let params = sonar::ListParams::from((req.offset, req.count));
+        let deliveries = sonar::webhook_delivery_list(&self.context, webhook_id, params)
+            .await
+            .m()?;
+        let deliveries = deliveries.into_iter().map(Into::into).collect();
+        Ok(tonic::Response::new(WebhookDeliveryListResponse {
+            deliveries,
+        }))
+    }
     async fn metadata_providers(
         &self,
         request: tonic::Request<MetadataProvidersRequest>,
     ) -> std::result::Result<tonic::Response<MetadataProvidersResponse>, tonic::Status> {
-        self.require_admin(&request).await?;
+        self.require_role(
+            &request,
+            sonar::ApiKeyAccess::Read,
+            sonar::UserRole::EditMetadata,
+        )
+        .await?;
 
         let providers = sonar::metadata_providers(&self.context);
         Ok(tonic::Response::new(MetadataProvidersResponse {
@@ -973,7 +2206,12 @@ impl sonar_service_server::SonarService for Server {
         &self,
         request: tonic::Request<MetadataFetchRequest>,
     ) -> std::result::Result<tonic::Response<()>, tonic::Status> {
-        self.require_admin(&request).await?;
+        self.require_role(
+            &request,
+            sonar::ApiKeyAccess::Write,
+            sonar::UserRole::EditMetadata,
+        )
+        .await?;
 
         let req = request.into_inner();
         let mask = metadata_mask_from_fields(req.fields)?;
@@ -1019,7 +2257,12 @@ impl sonar_service_server::SonarService for Server {
         &self,
         request: tonic::Request<MetadataAlbumTracksRequest>,
     ) -> std::result::Result<tonic::Response<MetadataAlbumTracksResponse>, tonic::Status> {
-        self.require_admin(&request).await?;
+        self.require_role(
+            &request,
+            sonar::ApiKeyAccess::Write,
+            sonar::UserRole::EditMetadata,
+        )
+        .await?;
 
         let request = request.into_inner();
         let album_id = request.album_id.parse::<sonar::AlbumId>().m()?;
@@ -1029,6 +2272,67 @@ impl sonar_service_server::SonarService for Server {
                 .m()?;
         Ok(tonic::Response::new(metadata.into()))
     }
+    async fn blob_fsck(
+        &self,
+        request: tonic::Request<BlobFsckRequest>,
+    ) -> std::result::Result<tonic::Response<Self::BlobFsckStream>, tonic::Status> {
+        self.require_admin(&request).await?;
+
+        let req = request.into_inner();
+        let params = sonar::FsckParams {
+            repair: match req.repair {
+                Some(repair) => repair.parse::<sonar::FsckRepair>().m()?,
+                None => Default::default(),
+            },
+        };
+
+        let (sender, receiver) = tokio::sync::mpsc::unbounded_channel();
+        let context = self.context.clone();
+        tokio::spawn(async move {
+            let progress = |progress: &sonar::FsckProgress| {
+                let _ = sender.send(Ok(progress.clone().into()));
+            };
+            if let Err(err) = sonar::blob_fsck(&context, params, &progress).await {
+                tracing::error!("blob fsck failed: {err}");
+                let _ = sender.send(Err(tonic::Status::internal(err.to_string())));
+            }
+        });
+        Ok(tonic::Response::new(UnboundedReceiverStream::new(receiver)))
+    }
+    async fn garbage_collect(
+        &self,
+        request: tonic::Request<GarbageCollectRequest>,
+    ) -> std::result::Result<tonic::Response<GarbageCollectResponse>, tonic::Status> {
+        self.require_admin(&request).await?;
+
+        let params = From::from(request.into_inner());
+        let report = sonar::garbage_collect(&self.context, params).await.m()?;
+        Ok(tonic::Response::new(report.into()))
+    }
+    async fn orphan_sweep(
+        &self,
+        request: tonic::Request<OrphanSweepRequest>,
+    ) -> std::result::Result<tonic::Response<OrphanSweepResponse>, tonic::Status> {
+        self.require_admin(&request).await?;
+
+        let params = From::from(request.into_inner());
+        let report = sonar::orphan_sweep(&self.context, params).await.m()?;
+        Ok(tonic::Response::new(report.into()))
+    }
+    async fn backup(
+        &self,
+        request: tonic::Request<BackupRequest>,
+    ) -> std::result::Result<tonic::Response<BackupResponse>, tonic::Status> {
+        self.require_admin(&request).await?;
+
+        let req = request.into_inner();
+        if req.path.is_empty() {
+            return Err(tonic::Status::invalid_argument("path is required"));
+        }
+        let path = std::path::PathBuf::from(req.path);
+        let report = sonar::backup_create(&self.context, &path).await.m()?;
+        Ok(tonic::Response::new(report.into()))
+    }
 }
 
 pub async fn client(endpoint: &str) -> eyre::Result<Client> {
@@ -1167,3 +2471,238 @@ impl tokio_stream::Stream for SonarTrackDownloadStream {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use sonar_service_server::SonarService;
+
+    async fn create_server_and_key(scope: sonar::ApiKeyScope) -> (Server, String) {
+        create_server_and_key_for(scope, false, sonar::UserRole::ALL.to_vec()).await
+    }
+
+    async fn create_server_and_key_for(
+        scope: sonar::ApiKeyScope,
+        admin: bool,
+        roles: Vec<sonar::UserRole>,
+    ) -> (Server, String) {
+        let ctx = sonar::test::create_context_memory().await;
+        let user = sonar::user_create(
+            &ctx,
+            sonar::UserCreate {
+                username: "user".parse().unwrap(),
+                password: "password".to_string(),
+                avatar: None,
+                admin,
+                roles,
+            },
+        )
+        .await
+        .unwrap();
+        let (_, key) = sonar::user_api_key_create(
+            &ctx,
+            sonar::ApiKeyCreate {
+                user: user.id,
+                name: "key".to_string(),
+                scope: Some(scope),
+            },
+        )
+        .await
+        .unwrap();
+        (Server::new(ctx), key)
+    }
+
+    fn request<T>(key: &str, message: T) -> tonic::Request<T> {
+        let mut request = tonic::Request::new(message);
+        request
+            .metadata_mut()
+            .insert("authorization", key.parse().unwrap());
+        request
+    }
+
+    #[tokio::test]
+    async fn api_key_read_only() {
+        let (server, key) = create_server_and_key(sonar::ApiKeyScope::ReadOnly).await;
+        sonar::test::create_artist(&server.context, "artist").await;
+
+        let response = server
+            .artist_list(request(&key, ArtistListRequest::default()))
+            .await
+            .unwrap();
+        assert_eq!(response.into_inner().artists.len(), 1);
+
+        let status = server
+            .artist_create(request(
+                &key,
+                ArtistCreateRequest {
+                    name: "other".to_string(),
+                    ..Default::default()
+                },
+            ))
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), tonic::Code::PermissionDenied);
+    }
+
+    #[tokio::test]
+    async fn api_key_streaming() {
+        let (server, key) = create_server_and_key(sonar::ApiKeyScope::Streaming).await;
+        let (_, album, _) =
+            sonar::test::create_artist_album_track(&server.context, "artist", "album", "track")
+                .await;
+        let audio = sonar::test::create_audio(&server.context, sonar::test::SMALL_AUDIO_MP3).await;
+        let track =
+            sonar::test::create_track_with_audio(&server.context, album.id, "audio", audio.id)
+                .await;
+
+        let response = server
+            .track_download_chunk(request(
+                &key,
+                TrackDownloadChunkRequest {
+                    track_id: track.id.to_string(),
+                    offset: 0,
+                    size: 16,
+                },
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.into_inner().data.len(), 16);
+
+        let status = server
+            .favorite_list(request(&key, FavoriteListRequest::default()))
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), tonic::Code::PermissionDenied);
+    }
+
+    #[tokio::test]
+    async fn playlist_track_insert_requires_owner() {
+        let (server, key) = create_server_and_key(sonar::ApiKeyScope::Admin).await;
+        let (_, _, track) =
+            sonar::test::create_artist_album_track(&server.context, "artist", "album", "track")
+                .await;
+        let user = sonar::user_lookup(&server.context, &"user".parse().unwrap())
+            .await
+            .unwrap()
+            .unwrap();
+        let other = sonar::test::create_user(&server.context, "other").await;
+        let owned = sonar::test::create_playlist(&server.context, user, "owned").await;
+        let foreign = sonar::test::create_playlist(&server.context, other.id, "foreign").await;
+
+        server
+            .playlist_track_insert(request(
+                &key,
+                PlaylistTrackInsertRequest {
+                    playlist_id: owned.id.to_string(),
+                    track_ids: vec![track.id.to_string()],
+                    position: None,
+                },
+            ))
+            .await
+            .unwrap();
+
+        let status = server
+            .playlist_track_insert(request(
+                &key,
+                PlaylistTrackInsertRequest {
+                    playlist_id: foreign.id.to_string(),
+                    track_ids: vec![track.id.to_string()],
+                    position: None,
+                },
+            ))
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), tonic::Code::PermissionDenied);
+    }
+
+    #[tokio::test]
+    async fn api_key_admin_without_admin_scope() {
+        let (server, key) = create_server_and_key_for(
+            sonar::ApiKeyScope::Streaming,
+            true,
+            vec![sonar::UserRole::Stream],
+        )
+        .await;
+        let (_, album, _) =
+            sonar::test::create_artist_album_track(&server.context, "artist", "album", "track")
+                .await;
+        let audio = sonar::test::create_audio(&server.context, sonar::test::SMALL_AUDIO_MP3).await;
+        let track =
+            sonar::test::create_track_with_audio(&server.context, album.id, "audio", audio.id)
+                .await;
+
+        // the admin only has the roles stored for them when using a key without admin access
+        let status = server
+            .track_download_chunk(request(
+                &key,
+                TrackDownloadChunkRequest {
+                    track_id: track.id.to_string(),
+                    offset: 0,
+                    size: 16,
+                },
+            ))
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), tonic::Code::PermissionDenied);
+    }
+
+    #[tokio::test]
+    async fn library_access() {
+        let (server, key) = create_server_and_key(sonar::ApiKeyScope::ReadOnly).await;
+        let (_, _, public) =
+            sonar::test::create_artist_album_track(&server.context, "public", "album", "track")
+                .await;
+        let library = sonar::test::create_library(&server.context, "private").await;
+        let artist =
+            sonar::test::create_artist_in_library(&server.context, library.id, "private").await;
+        let album = sonar::test::create_album(&server.context, artist.id, "album").await;
+        let audio = sonar::test::create_audio(&server.context, sonar::test::SMALL_AUDIO_MP3).await;
+        let track =
+            sonar::test::create_track_with_audio(&server.context, album.id, "audio", audio.id)
+                .await;
+
+        server
+            .track_get(request(
+                &key,
+                TrackGetRequest {
+                    track: public.id.to_string(),
+                },
+            ))
+            .await
+            .unwrap();
+
+        // media of libraries the user was not granted can't be read or downloaded by id
+        let status = server
+            .track_get(request(
+                &key,
+                TrackGetRequest {
+                    track: track.id.to_string(),
+                },
+            ))
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), tonic::Code::PermissionDenied);
+        let status = server
+            .album_get(request(
+                &key,
+                AlbumGetRequest {
+                    album: album.id.to_string(),
+                },
+            ))
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), tonic::Code::PermissionDenied);
+        let status = server
+            .track_download_chunk(request(
+                &key,
+                TrackDownloadChunkRequest {
+                    track_id: track.id.to_string(),
+                    offset: 0,
+                    size: 16,
+                },
+            ))
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), tonic::Code::PermissionDenied);
+    }
+}
@@ -48,3 +48,57 @@ impl<T> ResultExt<T> for sonar::Result<T, sonar::InvalidUserTokenError> {
         self.map_err(|e| tonic::Status::invalid_argument(e.to_string()))
     }
 }
+
+impl<T> ResultExt<T> for sonar::Result<T, sonar::InvalidApiKeyScopeError> {
+    fn m(self) -> Result<T, tonic::Status> {
+        self.map_err(|e| tonic::Status::invalid_argument(e.to_string()))
+    }
+}
+
+impl<T> ResultExt<T> for sonar::Result<T, sonar::InvalidArtistRoleError> {
+    fn m(self) -> Result<T, tonic::Status> {
+        self.map_err(|e| tonic::Status::invalid_argument(e.to_string()))
+    }
+}
+
+impl<T> ResultExt<T> for sonar::Result<T, sonar::InvalidRatingError> {
+    fn m(self) -> Result<T, tonic::Status> {
+        self.map_err(|e| tonic::Status::invalid_argument(e.to_string()))
+    }
+}
+
+impl<T> ResultExt<T> for sonar::Result<T, sonar::InvalidFsckRepairError> {
+    fn m(self) -> Result<T, tonic::Status> {
+        self.map_err(|e| tonic::Status::invalid_argument(e.to_string()))
+    }
+}
+
+impl<T> ResultExt<T> for sonar::Result<T, sonar::InvalidSmartPlaylistRuleError> {
+    fn m(self) -> Result<T, tonic::Status> {
+        self.map_err(|e| tonic::Status::invalid_argument(e.to_string()))
+    }
+}
+
+impl<T> ResultExt<T> for sonar::Result<T, sonar::InvalidSmartPlaylistSortError> {
+    fn m(self) -> Result<T, tonic::Status> {
+        self.map_err(|e| tonic::Status::invalid_argument(e.to_string()))
+    }
+}
+
+impl<T> ResultExt<T> for sonar::Result<T, sonar::InvalidRadioSeedError> {
+    fn m(self) -> Result<T, tonic::Status> {
+        self.map_err(|e| tonic::Status::invalid_argument(e.to_string()))
+    }
+}
+
+impl<T> ResultExt<T> for sonar::Result<T, sonar::InvalidEventKindError> {
+    fn m(self) -> Result<T, tonic::Status> {
+        self.map_err(|e| tonic::Status::invalid_argument(e.to_string()))
+    }
+}
+
+impl<T> ResultExt<T> for sonar::Result<T, sonar::InvalidUserRoleError> {
+    fn m(self) -> Result<T, tonic::Status> {
+        self.map_err(|e| tonic::Status::invalid_argument(e.to_string()))
+    }
+}
@@ -0,0 +1,108 @@
+use opensubsonic::request::{
+    annotation::*, bookmark::*, browsing::*, chat::*, jukebox::*, lists::*, playlists::*,
+    podcast::*, radio::*, retrieval::*, scan::*, search::*, sharing::*, system::*, user::*,
+};
+
+/// The access required to perform a request.
+/// This is used to check the scope of api keys.
+pub trait RequestAccess {
+    const ACCESS: sonar::ApiKeyAccess;
+}
+
+macro_rules! impl_request_access {
+    ($access:ident => $($t:ty),* $(,)?) => {
+        $(
+            impl RequestAccess for $t {
+                const ACCESS: sonar::ApiKeyAccess = sonar::ApiKeyAccess::$access;
+            }
+        )*
+    };
+}
+
+impl_request_access!(Stream =>
+    Download,
+    GetAvatar,
+    GetCaptions,
+    GetCoverArt,
+    GetLyrics,
+    Hls,
+    Stream,
+);
+
+impl_request_access!(Read =>
+    GetAlbum,
+    GetAlbumInfo,
+    GetAlbumInfo2,
+    GetAlbumList,
+    GetAlbumList2,
+    GetArtist,
+    GetArtistInfo,
+    GetArtistInfo2,
+    GetArtists,
+    GetBookmarks,
+    GetChatMessages,
+    GetGenres,
+    GetIndexes,
+    GetInternetRadioStations,
+    GetLicense,
+    GetMusicDirectory,
+    GetMusicFolders,
+    GetNewestPodcasts,
+    GetNowPlaying,
+    GetPlayQueue,
+    GetPlaylist,
+    GetPlaylists,
+    GetPodcasts,
+    GetRandomSongs,
+    GetScanStatus,
+    GetShares,
+    GetSimilarSongs,
+    GetSimilarSongs2,
+    GetSong,
+    GetSongsByGenre,
+    GetStarred,
+    GetStarred2,
+    GetTopSongs,
+    GetUser,
+    GetVideoInfo,
+    GetVideos,
+    Ping,
+    Search,
+    Search2,
+    Search3,
+);
+
+impl_request_access!(Write =>
+    AddChatMessage,
+    ChangePassword,
+    CreateBookmark,
+    CreatePlaylist,
+    CreateShare,
+    DeleteBookmark,
+    DeletePlaylist,
+    DeleteShare,
+    SavePlayQueue,
+    Scrobble,
+    SetRating,
+    Star,
+    Unstar,
+    UpdatePlaylist,
+    UpdateShare,
+);
+
+impl_request_access!(Admin =>
+    CreateInternetRadioStation,
+    CreatePodcastChannel,
+    CreateUser,
+    DeleteInternetRadioStation,
+    DeletePodcastChannel,
+    DeletePodcastEpisode,
+    DeleteUser,
+    DownloadPodcastEpisode,
+    GetUsers,
+    JukeboxControl,
+    RefreshPodcasts,
+    StartScan,
+    UpdateInternetRadioStation,
+    UpdateUser,
+);
@@ -1,18 +1,27 @@
-use std::{collections::HashMap, net::SocketAddr, sync::Mutex};
+use std::{
+    collections::HashMap,
+    hash::{BuildHasher, RandomState},
+    net::SocketAddr,
+    sync::Mutex,
+};
 
 use eyre::Context;
 use opensubsonic::service::prelude::*;
 use sonar::PropertyKey;
 use tower_http::cors::Any;
 
+mod access;
+use access::RequestAccess;
+
 const PROPERTY_USER_STARRED: PropertyKey =
     PropertyKey::new_const("user.opensubsonic.sonar.io/starred");
-const DEFAULT_MUSIC_FOLDER_ID: u32 = 1;
-const DEFAULT_MUSIC_FOLDER_NAME: &str = "sonar";
 
+/// The favorites and ratings of the items in a response.
 #[derive(Debug, Default)]
 struct FavoritesSet {
     favorites: HashMap<sonar::SonarId, sonar::Favorite>,
+    ratings: HashMap<sonar::SonarId, sonar::UserRating>,
+    averages: HashMap<sonar::SonarId, sonar::AverageRating>,
 }
 
 impl FavoritesSet {
@@ -27,10 +36,19 @@ impl FavoritesSet {
         ID: Into<sonar::SonarId>,
     {
         let ids = ids.into_iter().map(|id| id.into()).collect::<Vec<_>>();
-        let favorites = sonar::favorite_get_bulk(context, user_id, &ids).await.m()?;
+        let favorites = sonar::favorite_get_bulk(context, user_id, &ids);
+        let ratings = sonar::rating_get_bulk(context, user_id, &ids);
+        let averages = sonar::rating_average_get_bulk(context, &ids);
+        let (favorites, ratings, averages) = tokio::try_join!(favorites, ratings, averages).m()?;
         for favorite in favorites {
             self.favorites.insert(favorite.id, favorite);
         }
+        for rating in ratings {
+            self.ratings.insert(rating.id, rating);
+        }
+        for average in averages {
+            self.averages.insert(average.id, average);
+        }
         Ok(())
     }
 
@@ -39,10 +57,21 @@ impl FavoritesSet {
         let favorite = self.favorites.get(&id)?;
         Some(DateTime::from_unix_seconds(favorite.favorite_at.seconds()))
     }
+
+    fn user_rating(&self, id: impl Into<sonar::SonarId>) -> Option<UserRating> {
+        let rating = self.ratings.get(&id.into())?;
+        UserRating::new(rating.rating.value()).ok()
+    }
+
+    fn average_rating(&self, id: impl Into<sonar::SonarId>) -> Option<AverageRating> {
+        let average = self.averages.get(&id.into())?;
+        AverageRating::new(average.average as f32).ok()
+    }
 }
 
 struct CommonSearchParams {
     user_id: sonar::UserId,
+    library: Option<sonar::LibraryId>,
     query: String,
     artist_count: Option<u32>,
     artist_offset: Option<u32>,
@@ -67,7 +96,9 @@ struct CommonSearchResults {
 struct Server {
     image_url_prefix: String,
     context: sonar::Context,
-    tokens: Mutex<HashMap<String, sonar::UserToken>>,
+    /// session tokens of password logins keyed by username and a hash of the password.
+    tokens: Mutex<HashMap<(String, u64), sonar::UserToken>>,
+    password_hasher: RandomState,
 }
 
 impl Server {
@@ -76,22 +107,83 @@ impl Server {
             image_url_prefix,
             context,
             tokens: Default::default(),
+            password_hasher: RandomState::new(),
         }
     }
 
-    async fn authenticate<R: SubsonicRequest>(
+    async fn authenticate<R: SubsonicRequest + RequestAccess>(
         &self,
         request: &Request<R>,
     ) -> Result<sonar::UserId> {
-        if let Some(token) = self.get_token(&request.username) {
-            if let Ok(user_id) = sonar::user_validate_token(&self.context, &token).await {
-                return Ok(user_id);
-            } else {
-                tracing::warn!("invalid token for user {}", request.username);
+        if let Authentication::ApiKey(ref key) = request.authentication {
+            if request.username.is_some() {
+                return Err(opensubsonic::response::Error::with_message(
+                    opensubsonic::response::ErrorCode::MultipleConflictingAuthenticationMechanisms,
+                    "username must not be provided when using an api key".to_string(),
+                ));
+            }
+            let api_key = self.authenticate_api_key::<R>(key).await?;
+            return Ok(api_key.user);
+        }
+
+        let username = match request.username {
+            Some(ref username) => username,
+            None => {
+                return Err(opensubsonic::response::Error::with_message(
+                    opensubsonic::response::ErrorCode::RequiredParameterMissing,
+                    "missing username".to_string(),
+                ))
+            }
+        };
+
+        // app passwords can be used in place of the user's password
+        if let Authentication::Password(ref password) = request.authentication {
+            if let Ok(api_key) = sonar::user_api_key_validate(&self.context, password).await {
+                let username = username.parse::<sonar::Username>().m()?;
+                let user_id = sonar::user_lookup(&self.context, &username).await.m()?;
+                if user_id != Some(api_key.user) {
+                    return Err(opensubsonic::response::Error::with_message(
+                        opensubsonic::response::ErrorCode::WrongUsernameOrPassword,
+                        "invalid username or password".to_string(),
+                    ));
+                }
+                if !api_key.allows(R::ACCESS) {
+                    return Err(api_key_scope_error());
+                }
+                return Ok(api_key.user);
             }
         }
 
-        let username = request.username.parse::<sonar::Username>().m()?;
+        if let Authentication::Token {
+            ref token,
+            ref salt,
+        } = request.authentication
+        {
+            let username = username.parse::<sonar::Username>().m()?;
+            let user_id = match sonar::user_lookup(&self.context, &username).await.m()? {
+                Some(user_id) => user_id,
+                None => {
+                    return Err(opensubsonic::response::Error::with_message(
+                        opensubsonic::response::ErrorCode::WrongUsernameOrPassword,
+                        "invalid username or password".to_string(),
+                    ))
+                }
+            };
+            if !sonar::user_subsonic_password_enabled(&self.context, user_id)
+                .await
+                .m()?
+            {
+                return Err(opensubsonic::response::Error::with_message(
+                    opensubsonic::response::ErrorCode::TokenAuthenticationNotSupported,
+                    "token authentication is not enabled for this user".to_string(),
+                ));
+            }
+            sonar::user_subsonic_authenticate(&self.context, user_id, token, salt)
+                .await
+                .m()?;
+            return Ok(user_id);
+        }
+
         let password = match &request.authentication {
             Authentication::Password(password) => password,
             _ => {
@@ -101,15 +193,86 @@ impl Server {
                 ))
             }
         };
-        let (user_id, token) = sonar::user_login(&self.context, &username, password)
+
+        // the cached token is only used if the request carries the password it was created with
+        if let Some(token) = self.get_token(username, password) {
+            if let Ok(user_id) = sonar::user_validate_token(&self.context, &token).await {
+                return Ok(user_id);
+            } else {
+                tracing::warn!("invalid token for user {}", username);
+            }
+        }
+
+        let username = username.parse::<sonar::Username>().m()?;
+
+        let params = sonar::UserLoginParams {
+            device: Some(format!("opensubsonic ({})", request.client)),
+            ..Default::default()
+        };
+        let (user_id, token) = sonar::user_login(&self.context, &username, password, params)
             .await
             .m()?;
-        self.set_token(username.as_str(), token);
+        self.set_token(username.as_str(), password, token);
         Ok(user_id)
     }
 
-    async fn get_artists_id3(&self, user_id: sonar::UserId) -> Result<ArtistsID3> {
-        let artists = sonar::artist_list(&self.context, Default::default())
+    /// Authenticates the request and checks that the user has `role`.
+    async fn authenticate_role<R: SubsonicRequest + RequestAccess>(
+        &self,
+        request: &Request<R>,
+        role: sonar::UserRole,
+    ) -> Result<sonar::User> {
+        let user_id = self.authenticate(request).await?;
+        let user = sonar::user_get(&self.context, user_id).await.m()?;
+        if !user.has_role(role) {
+            return Err(opensubsonic::response::Error::with_message(
+                opensubsonic::response::ErrorCode::UserNotAuthorizedForTheGivenOperation,
+                format!("user does not have the {role} role"),
+            ));
+        }
+        Ok(user)
+    }
+
+    /// Fails unless the user has access to the library.
+    async fn require_library_access(
+        &self,
+        user_id: sonar::UserId,
+        library_id: sonar::LibraryId,
+    ) -> Result<()> {
+        match sonar::library_check_access(&self.context, user_id, library_id).await {
+            Ok(()) => Ok(()),
+            Err(err) if err.kind() == sonar::ErrorKind::Unauthorized => {
+                Err(opensubsonic::response::Error::with_message(
+                    opensubsonic::response::ErrorCode::UserNotAuthorizedForTheGivenOperation,
+                    "no access to library".to_string(),
+                ))
+            }
+            Err(err) => Err(err).m(),
+        }
+    }
+
+    async fn authenticate_api_key<R: RequestAccess>(&self, key: &str) -> Result<sonar::ApiKey> {
+        let api_key = sonar::user_api_key_validate(&self.context, key)
+            .await
+            .map_err(|err| {
+                opensubsonic::response::Error::with_message(
+                    opensubsonic::response::ErrorCode::InvalidApiKey,
+                    err.to_string(),
+                )
+            })?;
+        if !api_key.allows(R::ACCESS) {
+            return Err(api_key_scope_error());
+        }
+        Ok(api_key)
+    }
+
+    async fn get_artists_id3(
+        &self,
+        user_id: sonar::UserId,
+        library: Option<sonar::LibraryId>,
+    ) -> Result<ArtistsID3> {
+        let filter = sonar::LibraryFilter::user(user_id).with_library(library);
+        let artists = sonar::artist_list_filtered(&self.context, filter, Default::default())
             .await
             .m()?;
 
@@ -142,6 +305,38 @@ impl Server {
         })
     }
 
+    async fn get_similar_songs_children(
+        &self,
+        user_id: sonar::UserId,
+        track_ids: Vec<sonar::TrackId>,
+    ) -> Result<Vec<Child>> {
+        let tracks = sonar::track_get_bulk(&self.context, &track_ids).await.m()?;
+        let albums = sonar::ext::get_tracks_albums_map(&self.context, &tracks)
+            .await
+            .m()?;
+        let artists = sonar::ext::get_credited_artists_map(&self.context, albums.values(), &tracks)
+            .await
+            .m()?;
+        let audios = sonar::ext::get_tracks_audios_map(&self.context, &tracks)
+            .await
+            .m()?;
+
+        let mut favorites = FavoritesSet::default();
+        favorites
+            .populate_with(&self.context, user_id, track_ids)
+            .await?;
+
+        let mut child = Vec::with_capacity(tracks.len());
+        for track in tracks {
+            let album = &albums[&track.album];
+            let audio = track.audio.map(|id| audios[&id].clone());
+            child.push(child_from_audio_track_and_album_and_artist(
+                &favorites, &artists, album, track, audio,
+            ));
+        }
+        Ok(child)
+    }
+
     async fn search(&self, request: CommonSearchParams) -> Result<CommonSearchResults> {
         const DEFAULT_LIMIT: u32 = 50;
 
@@ -160,9 +355,10 @@ impl Server {
             let album_params = sonar::ListParams::from((album_offset, album_limit));
             let song_params = sonar::ListParams::from((song_offset, song_limit));
 
-            let artists = sonar::artist_list(&self.context, artist_params);
-            let albums = sonar::album_list(&self.context, album_params);
-            let tracks = sonar::track_list(&self.context, song_params);
+            let filter = sonar::LibraryFilter::user(request.user_id).with_library(request.library);
+            let artists = sonar::artist_list_filtered(&self.context, filter, artist_params);
+            let albums = sonar::album_list_filtered(&self.context, filter, album_params);
+            let tracks = sonar::track_list_filtered(&self.context, filter, song_params);
             let (artists, albums, songs) = tokio::try_join!(artists, albums, tracks).m()?;
 
             let mut favorites = FavoritesSet::default();
@@ -178,9 +374,12 @@ impl Server {
 
             let album_artists = sonar::ext::get_albums_artists_map(&self.context, &albums);
             let track_albums = sonar::ext::get_tracks_albums_map(&self.context, &songs);
-            let track_artists = sonar::ext::get_tracks_artists_map(&self.context, &songs);
-            let (album_artists, track_albums, track_artists) =
-                tokio::try_join!(album_artists, track_albums, track_artists).m()?;
+            let (album_artists, track_albums) =
+                tokio::try_join!(album_artists, track_albums).m()?;
+            let track_artists =
+                sonar::ext::get_credited_artists_map(&self.context, track_albums.values(), &songs)
+                    .await
+                    .m()?;
             let audios = sonar::ext::get_tracks_audios_map(&self.context, &songs)
                 .await
                 .m()?;
@@ -214,6 +413,7 @@ impl Server {
                     query: query.to_string(),
                     limit: Some(artist_limit + album_limit + song_limit),
                     flags,
+                    library: request.library,
                 },
             )
             .await
@@ -244,9 +444,15 @@ impl Server {
 
             let album_artists = sonar::ext::get_albums_artists_map(&self.context, result.albums());
             let track_albums = sonar::ext::get_tracks_albums_map(&self.context, result.tracks());
-            let track_artists = sonar::ext::get_tracks_artists_map(&self.context, result.tracks());
-            let (album_artists, track_albums, track_artists) =
-                tokio::try_join!(album_artists, track_albums, track_artists).m()?;
+            let (album_artists, track_albums) =
+                tokio::try_join!(album_artists, track_albums).m()?;
+            let track_artists = sonar::ext::get_credited_artists_map(
+                &self.context,
+                track_albums.values(),
+                result.tracks(),
+            )
+            .await
+            .m()?;
             let audios = sonar::ext::get_tracks_audios_map(&self.context, result.tracks())
                 .await
                 .m()?;
@@ -266,14 +472,22 @@ impl Server {
         }
     }
 
-    fn get_token(&self, username: &str) -> Option<sonar::UserToken> {
+    fn get_token(&self, username: &str, password: &str) -> Option<sonar::UserToken> {
+        let key = (
+            username.to_string(),
+            self.password_hasher.hash_one(password),
+        );
         let tokens = self.tokens.lock().unwrap();
-        tokens.get(username).cloned()
+        tokens.get(&key).cloned()
     }
 
-    fn set_token(&self, username: &str, token: sonar::UserToken) {
+    fn set_token(&self, username: &str, password: &str, token: sonar::UserToken) {
+        let key = (
+            username.to_string(),
+            self.password_hasher.hash_one(password),
+        );
         let mut tokens = self.tokens.lock().unwrap();
-        tokens.insert(username.to_string(), token);
+        tokens.insert(key, token);
     }
 }
 
@@ -284,9 +498,77 @@ impl OpenSubsonicServer for Server {
         let _user_id = self.authenticate(&request).await?;
         Ok(())
     }
-    async fn get_bookmarks(&self, _request: Request<GetBookmarks>) -> Result<Bookmarks> {
-        Ok(Default::default())
+    #[tracing::instrument(skip(self))]
+    async fn get_bookmarks(&self, request: Request<GetBookmarks>) -> Result<Bookmarks> {
+        let user_id = self.authenticate(&request).await?;
+        let user = sonar::user_get(&self.context, user_id).await.m()?;
+        let bookmarks = sonar::bookmark_list(&self.context, user_id).await.m()?;
+        let track_ids = bookmarks.iter().map(|b| b.track).collect::<Vec<_>>();
+        let tracks = sonar::track_get_bulk(&self.context, &track_ids)
+            .await
+            .m()?
+            .into_iter()
+            .map(|track| (track.id, track))
+            .collect::<HashMap<_, _>>();
+        let albums = sonar::ext::get_tracks_albums_map(&self.context, tracks.values())
+            .await
+            .m()?;
+        let artists =
+            sonar::ext::get_credited_artists_map(&self.context, albums.values(), tracks.values())
+                .await
+                .m()?;
+        let audios = sonar::ext::get_tracks_audios_map(&self.context, tracks.values())
+            .await
+            .m()?;
+
+        let mut favorites = FavoritesSet::default();
+        favorites
+            .populate_with(&self.context, user_id, track_ids.iter().copied())
+            .await?;
+
+        let mut bookmark = Vec::with_capacity(bookmarks.len());
+        for b in bookmarks {
+            let track = tracks[&b.track].clone();
+            let album = &albums[&track.album];
+            let audio = track.audio.map(|id| &audios[&id]).cloned();
+            bookmark.push(Bookmark {
+                position: Milliseconds::from(b.position),
+                username: user.username.to_string(),
+                comment: b.comment,
+                created: DateTime::from_unix_seconds(b.created_at.seconds()),
+                changed: DateTime::from_unix_seconds(b.updated_at.seconds()),
+                entry: child_from_audio_track_and_album_and_artist(
+                    &favorites, &artists, album, track, audio,
+                ),
+            });
+        }
+        Ok(Bookmarks { bookmark })
     }
+
+    #[tracing::instrument(skip(self))]
+    async fn create_bookmark(&self, request: Request<CreateBookmark>) -> Result<()> {
+        let user_id = self.authenticate(&request).await?;
+        let track_id = request.body.id.parse::<sonar::TrackId>().m()?;
+        let create = sonar::BookmarkCreate {
+            user: user_id,
+            track: track_id,
+            position: request.body.position.into(),
+            comment: request.body.comment,
+        };
+        sonar::bookmark_create(&self.context, create).await.m()?;
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn delete_bookmark(&self, request: Request<DeleteBookmark>) -> Result<()> {
+        let user_id = self.authenticate(&request).await?;
+        let track_id = request.body.id.parse::<sonar::TrackId>().m()?;
+        sonar::bookmark_delete(&self.context, user_id, track_id)
+            .await
+            .m()?;
+        Ok(())
+    }
+
     async fn get_genres(&self, _request: Request<GetGenres>) -> Result<Genres> {
         let genres = sonar::genre_list(&self.context)
             .await
@@ -304,6 +586,7 @@ impl OpenSubsonicServer for Server {
         let user_id = self.authenticate(&request).await?;
         let params = CommonSearchParams {
             user_id,
+            library: parse_music_folder_id_opt(request.body.music_folder_id.as_deref())?,
             query: request.body.query,
             artist_count: request.body.artist_count,
             artist_offset: request.body.artist_offset,
@@ -334,14 +617,13 @@ impl OpenSubsonicServer for Server {
             .into_iter()
             .map(|track| {
                 let album = &results.track_albums[&track.album];
-                let artist = &results.track_artists[&track.artist];
                 let audio = track
                     .audio
                     .and_then(|id| results.track_audios.get(&id))
                     .cloned();
                 child_from_audio_track_and_album_and_artist(
                     &results.favorites,
-                    artist,
+                    &results.track_artists,
                     album,
                     track,
                     audio,
@@ -359,6 +641,7 @@ impl OpenSubsonicServer for Server {
         let user_id = self.authenticate(&request).await?;
         let params = CommonSearchParams {
             user_id,
+            library: parse_music_folder_id_opt(request.body.music_folder_id.as_deref())?,
             query: request.body.query,
             artist_count: request.body.artist_count,
             artist_offset: request.body.artist_offset,
@@ -386,14 +669,13 @@ impl OpenSubsonicServer for Server {
             .into_iter()
             .map(|track| {
                 let album = &results.track_albums[&track.album];
-                let artist = &results.track_artists[&track.artist];
                 let audio = track
                     .audio
                     .and_then(|id| results.track_audios.get(&id))
                     .cloned();
                 child_from_audio_track_and_album_and_artist(
                     &results.favorites,
-                    artist,
+                    &results.track_artists,
                     album,
                     track,
                     audio,
@@ -410,7 +692,8 @@ impl OpenSubsonicServer for Server {
 
     async fn get_indexes(&self, request: Request<GetIndexes>) -> Result<ArtistsID3> {
         let user_id = self.authenticate(&request).await?;
-        self.get_artists_id3(user_id).await
+        let library = parse_music_folder_id_opt(request.body.music_folder_id.as_deref())?;
+        self.get_artists_id3(user_id, library).await
     }
 
     async fn get_random_songs(&self, request: Request<GetRandomSongs>) -> Result<Songs> {
@@ -420,17 +703,21 @@ impl OpenSubsonicServer for Server {
             Some(genre) => Some(genre.parse::<sonar::Genre>().m()?),
             None => None,
         };
+        let library = parse_music_folder_id_opt(request.body.music_folder_id.as_deref())?;
+        let filter = sonar::LibraryFilter::user(user_id).with_library(library);
         let params = sonar::TrackListRandom {
             limit: Some(limit),
             genre,
         };
-        let tracks = sonar::track_list_random(&self.context, params).await.m()?;
-        let artists = sonar::ext::get_tracks_artists_map(&self.context, &tracks)
+        let tracks = sonar::track_list_random_filtered(&self.context, filter, params)
             .await
             .m()?;
         let albums = sonar::ext::get_tracks_albums_map(&self.context, &tracks)
             .await
             .m()?;
+        let artists = sonar::ext::get_credited_artists_map(&self.context, albums.values(), &tracks)
+            .await
+            .m()?;
         let audios = sonar::ext::get_tracks_audios_map(&self.context, &tracks)
             .await
             .m()?;
@@ -442,11 +729,10 @@ impl OpenSubsonicServer for Server {
 
         let mut child = Vec::with_capacity(tracks.len());
         for track in tracks {
-            let artist = &artists[&track.artist];
             let album = &albums[&track.album];
             let audio = track.audio.map(|id| &audios[&id]).cloned();
             child.push(child_from_audio_track_and_album_and_artist(
-                &favorites, artist, album, track, audio,
+                &favorites, &artists, album, track, audio,
             ));
         }
 
@@ -456,11 +742,18 @@ impl OpenSubsonicServer for Server {
     #[tracing::instrument(skip(self))]
     async fn get_artists(&self, request: Request<GetArtists>) -> Result<ArtistsID3> {
         let user_id = self.authenticate(&request).await?;
-        self.get_artists_id3(user_id).await
+        let library = request
+            .body
+            .music_folder_id
+            .map(sonar::LibraryId::try_from)
+            .transpose()
+            .m()?;
+        self.get_artists_id3(user_id, library).await
     }
 
     #[tracing::instrument(skip(self))]
     async fn get_artist_info2(&self, request: Request<GetArtistInfo2>) -> Result<ArtistInfo2> {
+        let user_id = self.authenticate(&request).await?;
         let artist_id = request.body.id.parse::<sonar::ArtistId>().m()?;
         let artist = sonar::artist_get(&self.context, artist_id).await.m()?;
         let cover_art = artist.cover_art.map(|cover_id| {
@@ -470,23 +763,89 @@ impl OpenSubsonicServer for Server {
             )
         });
 
+        let count = request.body.count.unwrap_or(GetArtistInfo2::DEFAULT_COUNT);
+        let filter = sonar::LibraryFilter::user(user_id);
+        let similar_ids =
+            sonar::recommend_similar_artists_filtered(&self.context, filter, artist_id, count)
+                .await
+                .m()?;
+        let similar = sonar::artist_get_bulk(&self.context, &similar_ids)
+            .await
+            .m()?;
+        let mut favorites = FavoritesSet::default();
+        favorites
+            .populate_with(&self.context, user_id, similar_ids)
+            .await?;
+        let similar_artist = similar
+            .into_iter()
+            .map(|artist| artistid3_from_artist(&favorites, artist))
+            .collect();
+
         Ok(ArtistInfo2 {
             info: ArtistInfoBase {
                 large_image_url: cover_art,
                 ..Default::default()
             },
-            similar_artist: Default::default(),
+            similar_artist,
         })
     }
 
+    #[tracing::instrument(skip(self))]
+    async fn get_similar_songs(&self, request: Request<GetSimilarSongs>) -> Result<SimilarSongs> {
+        let user_id = self.authenticate(&request).await?;
+        let count = request.body.count.unwrap_or(GetSimilarSongs::DEFAULT_COUNT);
+        let id = request.body.id.parse::<sonar::SonarId>().m()?;
+        let filter = sonar::LibraryFilter::user(user_id);
+        let track_ids = sonar::recommend_similar_tracks_filtered(&self.context, filter, id, count)
+            .await
+            .m()?;
+        let song = self.get_similar_songs_children(user_id, track_ids).await?;
+        Ok(SimilarSongs { song })
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn get_similar_songs2(
+        &self,
+        request: Request<GetSimilarSongs2>,
+    ) -> Result<SimilarSongs2> {
+        let user_id = self.authenticate(&request).await?;
+        let count = request
+            .body
+            .count
+            .unwrap_or(GetSimilarSongs2::DEFAULT_COUNT);
+        let seed = request.body.id.parse::<sonar::RadioSeed>().m()?;
+        // repeated requests for the same artist continue the same radio instead of starting over
+        let radio = sonar::radio_find_or_create(
+            &self.context,
+            sonar::RadioCreate {
+                user: user_id,
+                seed,
+            },
+        )
+        .await
+        .m()?;
+        let track_ids = sonar::radio_next(&self.context, radio.id, count)
+            .await
+            .m()?;
+        let song = self.get_similar_songs_children(user_id, track_ids).await?;
+        Ok(SimilarSongs2 { song })
+    }
+
     #[tracing::instrument(skip(self))]
     async fn get_artist(&self, request: Request<GetArtist>) -> Result<ArtistWithAlbumsID3> {
         let user_id = self.authenticate(&request).await?;
         let artist_id = request.body.id.parse::<sonar::ArtistId>().m()?;
         let artist = sonar::artist_get(&self.context, artist_id).await.m()?;
-        let albums = sonar::album_list_by_artist(&self.context, artist_id, Default::default())
-            .await
-            .m()?;
+        self.require_library_access(user_id, artist.library).await?;
+        let filter = sonar::LibraryFilter::user(user_id);
+        let albums = sonar::album_list_by_artist_filtered(
+            &self.context,
+            filter,
+            artist_id,
+            Default::default(),
+        )
+        .await
+        .m()?;
 
         let mut favorites = FavoritesSet::default();
         favorites
@@ -496,9 +855,12 @@ impl OpenSubsonicServer for Server {
             .populate_with(&self.context, user_id, albums.iter().map(|v| v.id))
             .await?;
 
+        let artists = sonar::ext::get_albums_artists_map(&self.context, &albums)
+            .await
+            .m()?;
         let album = albums
             .into_iter()
-            .map(|album| albumid3_from_album_and_artist(&favorites, &artist, album))
+            .map(|album| albumid3_from_album_and_artist(&favorites, &artists, album))
             .collect();
         let artist = artistid3_from_artist(&favorites, artist);
         Ok(ArtistWithAlbumsID3 { artist, album })
@@ -516,6 +878,9 @@ impl OpenSubsonicServer for Server {
         let albums = sonar::ext::get_tracks_albums_map(&self.context, &tracks)
             .await
             .m()?;
+        let artists = sonar::ext::get_credited_artists_map(&self.context, albums.values(), &tracks)
+            .await
+            .m()?;
         let audios = sonar::ext::get_tracks_audios_map(&self.context, &tracks)
             .await
             .m()?;
@@ -536,7 +901,7 @@ impl OpenSubsonicServer for Server {
             let album = &albums[&track.album];
             let audio = track.audio.map(|id| audios[&id].clone());
             child.push(child_from_audio_track_and_album_and_artist(
-                &favorites, &artist, album, track, audio,
+                &favorites, &artists, album, track, audio,
             ));
         }
 
@@ -548,10 +913,17 @@ impl OpenSubsonicServer for Server {
         let user_id = self.authenticate(&request).await?;
         let album_id = request.body.id.parse::<sonar::AlbumId>().m()?;
         let album = sonar::album_get(&self.context, album_id).await.m()?;
+        self.require_library_access(user_id, album.library).await?;
         let artist = sonar::artist_get(&self.context, album.artist).await.m()?;
-        let tracks = sonar::track_list_by_album(&self.context, album_id, Default::default())
-            .await
-            .m()?;
+        let filter = sonar::LibraryFilter::user(user_id);
+        let tracks = sonar::track_list_by_album_filtered(
+            &self.context,
+            filter,
+            album_id,
+            Default::default(),
+        )
+        .await
+        .m()?;
 
         let mut favorites = FavoritesSet::default();
         favorites
@@ -567,18 +939,22 @@ impl OpenSubsonicServer for Server {
         let audios = sonar::ext::get_tracks_audios_map(&self.context, &tracks)
             .await
             .m()?;
+        let artists =
+            sonar::ext::get_credited_artists_map(&self.context, std::iter::once(&album), &tracks)
+                .await
+                .m()?;
         let mut song: Vec<Child> = tracks
             .into_iter()
             .map(|track| {
                 let audio = track.audio.and_then(|id| audios.get(&id)).cloned();
                 child_from_audio_track_and_album_and_artist(
-                    &favorites, &artist, &album, track, audio,
+                    &favorites, &artists, &album, track, audio,
                 )
             })
             .collect();
         song.sort_by(child_sort_disc_track);
         Ok(AlbumWithSongsID3 {
-            album: albumid3_from_album_and_artist(&favorites, &artist, album),
+            album: albumid3_from_album_and_artist(&favorites, &artists, album),
             song,
         })
     }
@@ -588,6 +964,8 @@ impl OpenSubsonicServer for Server {
     #[tracing::instrument(skip(self))]
     async fn get_album_list2(&self, request: Request<GetAlbumList2>) -> Result<AlbumList2> {
         let user_id = self.authenticate(&request).await?;
+        let library = parse_music_folder_id_opt(request.body.music_folder_id.as_deref())?;
+        let filter = sonar::LibraryFilter::user(user_id).with_library(library);
         let params = sonar::ListParams::from((request.body.offset, request.body.size));
 
         let albums = match request.body.list_type {
@@ -598,15 +976,22 @@ impl OpenSubsonicServer for Server {
                     .unwrap_or_default()
                     .parse::<sonar::Genre>()
                     .m()?;
-                sonar::album_list_by_genre(&self.context, &genre, params)
+                sonar::album_list_by_genre_filtered(&self.context, filter, &genre, params)
                     .await
                     .m()?
             }
-            _ => sonar::album_list(&self.context, params).await.m()?,
+            ListType::Highest => {
+                sonar::album_list_highest_rated_filtered(&self.context, filter, params)
+                    .await
+                    .m()?
+            }
+            _ => sonar::album_list_filtered(&self.context, filter, params)
+                .await
+                .m()?,
         };
 
         let albums = sonar::ext::albums_map(albums);
-        let artists = sonar::ext::artist_bulk_map(&self.context, albums.values().map(|v| v.artist))
+        let artists = sonar::ext::get_albums_artists_map(&self.context, albums.values())
             .await
             .m()?;
 
@@ -627,6 +1012,7 @@ impl OpenSubsonicServer for Server {
         let user_id = self.authenticate(&request).await?;
         let track_id = request.body.id.parse::<sonar::TrackId>().m()?;
         let track = sonar::track_get(&self.context, track_id).await.m()?;
+        self.require_library_access(user_id, track.library).await?;
         let album = sonar::album_get(&self.context, track.album).await.m()?;
         let artist = sonar::artist_get(&self.context, track.artist).await.m()?;
         let audio = match track.audio {
@@ -647,8 +1033,15 @@ impl OpenSubsonicServer for Server {
             )
             .await?;
 
+        let artists = sonar::ext::get_credited_artists_map(
+            &self.context,
+            std::iter::once(&album),
+            std::iter::once(&track),
+        )
+        .await
+        .m()?;
         let child =
-            child_from_audio_track_and_album_and_artist(&favorites, &artist, &album, track, audio);
+            child_from_audio_track_and_album_and_artist(&favorites, &artists, &album, track, audio);
         Ok(child)
     }
 
@@ -675,6 +1068,118 @@ impl OpenSubsonicServer for Server {
         Ok(())
     }
 
+    #[tracing::instrument(skip(self))]
+    async fn set_rating(&self, request: Request<SetRating>) -> Result<()> {
+        let user_id = self.authenticate(&request).await?;
+        let sonar_id = request.body.id.parse::<sonar::SonarId>().m()?;
+        // a rating of 0 removes the rating
+        if request.body.rating == 0 {
+            sonar::rating_remove(&self.context, user_id, sonar_id)
+                .await
+                .m()?;
+        } else {
+            let rating = sonar::Rating::new(request.body.rating).m()?;
+            sonar::rating_set(&self.context, user_id, sonar_id, rating)
+                .await
+                .m()?;
+        }
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn get_play_queue(&self, request: Request<GetPlayQueue>) -> Result<PlayQueue> {
+        let user_id = self.authenticate(&request).await?;
+        let user = sonar::user_get(&self.context, user_id).await.m()?;
+        let queue = match sonar::play_queue_get(&self.context, user_id).await.m()? {
+            Some(queue) => queue,
+            None => {
+                return Ok(PlayQueue {
+                    current: None,
+                    position: None,
+                    username: user.username.to_string(),
+                    changed: DateTime::from_unix_seconds(sonar::Timestamp::now().seconds()),
+                    changed_by: request.client,
+                    entry: Default::default(),
+                })
+            }
+        };
+
+        let tracks = sonar::track_get_bulk(&self.context, &queue.tracks)
+            .await
+            .m()?
+            .into_iter()
+            .map(|track| (track.id, track))
+            .collect::<HashMap<_, _>>();
+        let albums = sonar::ext::get_tracks_albums_map(&self.context, tracks.values())
+            .await
+            .m()?;
+        let artists =
+            sonar::ext::get_credited_artists_map(&self.context, albums.values(), tracks.values())
+                .await
+                .m()?;
+        let audios = sonar::ext::get_tracks_audios_map(&self.context, tracks.values())
+            .await
+            .m()?;
+
+        let mut favorites = FavoritesSet::default();
+        favorites
+            .populate_with(&self.context, user_id, queue.tracks.iter().copied())
+            .await?;
+
+        let mut entry = Vec::with_capacity(queue.tracks.len());
+        for track_id in &queue.tracks {
+            let track = tracks[track_id].clone();
+            let album = &albums[&track.album];
+            let audio = track.audio.map(|id| &audios[&id]).cloned();
+            entry.push(child_from_audio_track_and_album_and_artist(
+                &favorites, &artists, album, track, audio,
+            ));
+        }
+
+        let current = queue
+            .current
+            .map(|index| queue.tracks[index as usize].to_string());
+        Ok(PlayQueue {
+            current,
+            position: Some(Milliseconds::from(queue.position)),
+            username: user.username.to_string(),
+            changed: DateTime::from_unix_seconds(queue.changed_at.seconds()),
+            changed_by: queue.changed_by,
+            entry,
+        })
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn save_play_queue(&self, request: Request<SavePlayQueue>) -> Result<()> {
+        let user_id = self.authenticate(&request).await?;
+        let tracks = request
+            .body
+            .id
+            .iter()
+            .map(|id| id.parse::<sonar::TrackId>().m())
+            .collect::<Result<Vec<_>>>()?;
+        let current = match request.body.current {
+            Some(ref current) => {
+                let current = current.parse::<sonar::TrackId>().m()?;
+                tracks
+                    .iter()
+                    .position(|id| *id == current)
+                    .map(|index| index as u32)
+            }
+            None => None,
+        };
+        let save = sonar::PlayQueueSave {
+            tracks,
+            current,
+            position: request.body.position.map(Into::into).unwrap_or_default(),
+            changed_by: request.client,
+        };
+        sonar::play_queue_save(&self.context, user_id, save)
+            .await
+            .m()?;
+        Ok(())
+    }
+
     #[tracing::instrument(skip(self))]
     async fn get_starred(&self, request: Request<GetStarred>) -> Result<Starred> {
         let user_id = self.authenticate(&request).await?;
@@ -705,11 +1210,14 @@ impl OpenSubsonicServer for Server {
         let audio_ids = tracks.iter().filter_map(|t| t.audio).collect::<Vec<_>>();
 
         let album_artists = sonar::ext::get_albums_artists_map(&self.context, &albums);
-        let track_artists = sonar::ext::get_tracks_artists_map(&self.context, &tracks);
         let track_albums = sonar::ext::get_tracks_albums_map(&self.context, &tracks);
         let audios = sonar::ext::audio_bulk_map(&self.context, audio_ids);
-        let (album_artists, track_artists, track_albums, audios) =
-            tokio::try_join!(album_artists, track_artists, track_albums, audios).m()?;
+        let (album_artists, track_albums, audios) =
+            tokio::try_join!(album_artists, track_albums, audios).m()?;
+        let track_artists =
+            sonar::ext::get_credited_artists_map(&self.context, track_albums.values(), &tracks)
+                .await
+                .m()?;
 
         let mut song = Vec::with_capacity(tracks.len());
         let mut album = Vec::with_capacity(albums.len());
@@ -717,11 +1225,10 @@ impl OpenSubsonicServer for Server {
 
         for track in tracks {
             let album = &track_albums[&track.album];
-            let artist = &track_artists[&track.artist];
             let audio = track.audio.and_then(|id| audios.get(&id));
             song.push(child_from_audio_track_and_album_and_artist(
                 &favorites,
-                artist,
+                &track_artists,
                 album,
                 track,
                 audio.cloned(),
@@ -769,11 +1276,14 @@ impl OpenSubsonicServer for Server {
         let audio_ids = tracks.iter().filter_map(|t| t.audio).collect::<Vec<_>>();
 
         let album_artists = sonar::ext::get_albums_artists_map(&self.context, &albums);
-        let track_artists = sonar::ext::get_tracks_artists_map(&self.context, &tracks);
         let track_albums = sonar::ext::get_tracks_albums_map(&self.context, &tracks);
         let audios = sonar::ext::audio_bulk_map(&self.context, audio_ids);
-        let (album_artists, track_artists, track_albums, audios) =
-            tokio::try_join!(album_artists, track_artists, track_albums, audios).m()?;
+        let (album_artists, track_albums, audios) =
+            tokio::try_join!(album_artists, track_albums, audios).m()?;
+        let track_artists =
+            sonar::ext::get_credited_artists_map(&self.context, track_albums.values(), &tracks)
+                .await
+                .m()?;
 
         let mut song = Vec::with_capacity(tracks.len());
         let mut album = Vec::with_capacity(albums.len());
@@ -781,11 +1291,10 @@ impl OpenSubsonicServer for Server {
 
         for track in tracks {
             let album = &track_albums[&track.album];
-            let artist = &track_artists[&track.artist];
             let audio = track.audio.and_then(|id| audios.get(&id));
             song.push(child_from_audio_track_and_album_and_artist(
                 &favorites,
-                artist,
+                &track_artists,
                 album,
                 track,
                 audio.cloned(),
@@ -793,8 +1302,11 @@ impl OpenSubsonicServer for Server {
         }
 
         for alb in albums {
-            let artist = &album_artists[&alb.artist];
-            album.push(albumid3_from_album_and_artist(&favorites, artist, alb));
+            album.push(albumid3_from_album_and_artist(
+                &favorites,
+                &album_artists,
+                alb,
+            ));
         }
 
         for art in artists {
@@ -835,9 +1347,11 @@ impl OpenSubsonicServer for Server {
 
         let tracks = sonar::track_get_bulk(&self.context, &track_ids).await.m()?;
         let albums = sonar::ext::get_tracks_albums_map(&self.context, &tracks);
-        let artists = sonar::ext::get_tracks_artists_map(&self.context, &tracks);
         let audios = sonar::ext::get_tracks_audios_map(&self.context, &tracks);
-        let (albums, artists, audios) = tokio::try_join!(albums, artists, audios).m()?;
+        let (albums, audios) = tokio::try_join!(albums, audios).m()?;
+        let artists = sonar::ext::get_credited_artists_map(&self.context, albums.values(), &tracks)
+            .await
+            .m()?;
 
         let mut favorites = FavoritesSet::default();
         favorites
@@ -908,9 +1422,11 @@ impl OpenSubsonicServer for Server {
             .collect::<Vec<_>>();
         let tracks = sonar::track_get_bulk(&self.context, &track_ids).await.m()?;
         let albums = sonar::ext::get_tracks_albums_map(&self.context, &tracks);
-        let artists = sonar::ext::get_tracks_artists_map(&self.context, &tracks);
         let audios = sonar::ext::get_tracks_audios_map(&self.context, &tracks);
-        let (albums, artists, audios) = tokio::try_join!(albums, artists, audios).m()?;
+        let (albums, audios) = tokio::try_join!(albums, audios).m()?;
+        let artists = sonar::ext::get_credited_artists_map(&self.context, albums.values(), &tracks)
+            .await
+            .m()?;
 
         let mut favorites = FavoritesSet::default();
         favorites
@@ -930,6 +1446,58 @@ impl OpenSubsonicServer for Server {
         })
     }
 
+    #[tracing::instrument(skip(self))]
+    async fn update_playlist(&self, request: Request<UpdatePlaylist>) -> Result<()> {
+        let user_id = self.authenticate(&request).await?;
+        let playlist_id = request.body.playlist_id.parse::<sonar::PlaylistId>().m()?;
+        let playlist = sonar::playlist_get(&self.context, playlist_id).await.m()?;
+        if playlist.owner != user_id {
+            return Err(opensubsonic::response::Error::with_message(
+                opensubsonic::response::ErrorCode::UserNotAuthorizedForTheGivenOperation,
+                "not the owner of the playlist".to_string(),
+            ));
+        }
+
+        let track_ids = request
+            .body
+            .song_id_to_add
+            .into_iter()
+            .map(|id| id.parse::<sonar::TrackId>().m())
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if let Some(name) = request.body.name {
+            sonar::playlist_update(
+                &self.context,
+                playlist_id,
+                sonar::PlaylistUpdate {
+                    name: sonar::ValueUpdate::set(name),
+                    ..Default::default()
+                },
+            )
+            .await
+            .m()?;
+        }
+
+        // indices refer to the playlist before any changes so they are removed before adding
+        if !request.body.song_index_to_remove.is_empty() {
+            sonar::playlist_remove_at(
+                &self.context,
+                playlist_id,
+                &request.body.song_index_to_remove,
+            )
+            .await
+            .m()?;
+        }
+
+        if !track_ids.is_empty() {
+            sonar::playlist_insert_tracks(&self.context, playlist_id, &track_ids)
+                .await
+                .m()?;
+        }
+
+        Ok(())
+    }
+
     #[tracing::instrument(skip(self))]
     async fn get_cover_art(&self, request: Request<GetCoverArt>) -> Result<Image> {
         // NOTE: I saw substreamer making some requests with 'al-' prefixed for albums so I will
@@ -1016,7 +1584,12 @@ impl OpenSubsonicServer for Server {
 
     #[tracing::instrument(skip(self))]
     async fn download(&self, request: Request<Download>) -> Result<ByteStream> {
+        let user = self
+            .authenticate_role(&request, sonar::UserRole::Download)
+            .await?;
         let track_id = request.body.id.parse::<sonar::TrackId>().m()?;
+        let track = sonar::track_get(&self.context, track_id).await.m()?;
+        self.require_library_access(user.id, track.library).await?;
         let download = sonar::track_download(&self.context, track_id, sonar::ByteRange::default())
             .await
             .m()?;
@@ -1028,7 +1601,12 @@ impl OpenSubsonicServer for Server {
 
     #[tracing::instrument(skip(self))]
     async fn stream(&self, request: Request<Stream>, range: ByteRange) -> Result<StreamChunk> {
+        let user = self
+            .authenticate_role(&request, sonar::UserRole::Stream)
+            .await?;
         let track_id = request.body.id.parse::<sonar::TrackId>().m()?;
+        let track = sonar::track_get(&self.context, track_id).await.m()?;
+        self.require_library_access(user.id, track.library).await?;
         let range = sonar::ByteRange {
             offset: range.offset,
             length: range.length,
@@ -1050,12 +1628,19 @@ impl OpenSubsonicServer for Server {
     }
 
     #[tracing::instrument(skip(self))]
-    async fn get_music_folders(&self, _request: Request<GetMusicFolders>) -> Result<MusicFolders> {
+    async fn get_music_folders(&self, request: Request<GetMusicFolders>) -> Result<MusicFolders> {
+        let user_id = self.authenticate(&request).await?;
+        let libraries = sonar::library_list_by_user(&self.context, user_id)
+            .await
+            .m()?;
         Ok(MusicFolders {
-            music_folder: vec![MusicFolder {
-                id: DEFAULT_MUSIC_FOLDER_ID,
-                name: Some(DEFAULT_MUSIC_FOLDER_NAME.to_string()),
-            }],
+            music_folder: libraries
+                .into_iter()
+                .map(|library| MusicFolder {
+                    id: u32::from(library.id),
+                    name: Some(library.name),
+                })
+                .collect(),
         })
     }
 
@@ -1071,6 +1656,69 @@ impl OpenSubsonicServer for Server {
     ) -> Result<InternetRadioStations> {
         Ok(Default::default())
     }
+
+    #[tracing::instrument(skip(self))]
+    async fn get_user(&self, request: Request<GetUser>) -> Result<User> {
+        let user_id = self.authenticate(&request).await?;
+        let user = sonar::user_get(&self.context, user_id).await.m()?;
+        if user.username.as_str() == request.body.username {
+            let libraries = sonar::library_list_by_user(&self.context, user.id)
+                .await
+                .m()?;
+            return Ok(user_from_user(user, libraries));
+        }
+        if !user.admin {
+            return Err(opensubsonic::response::Error::with_message(
+                opensubsonic::response::ErrorCode::UserNotAuthorizedForTheGivenOperation,
+                "only admins can get other users".to_string(),
+            ));
+        }
+        let username = request.body.username.parse::<sonar::Username>().m()?;
+        let user_id = match sonar::user_lookup(&self.context, &username).await.m()? {
+            Some(user_id) => user_id,
+            None => {
+                return Err(opensubsonic::response::Error::with_message(
+                    opensubsonic::response::ErrorCode::DataNotFound,
+                    "user not found".to_string(),
+                ))
+            }
+        };
+        let user = sonar::user_get(&self.context, user_id).await.m()?;
+        let libraries = sonar::library_list_by_user(&self.context, user_id)
+            .await
+            .m()?;
+        Ok(user_from_user(user, libraries))
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn create_share(&self, request: Request<CreateShare>) -> Result<Shares> {
+        self.authenticate_role(&request, sonar::UserRole::Share)
+            .await?;
+        Err(sharing_unsupported_error())
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn update_share(&self, request: Request<UpdateShare>) -> Result<()> {
+        self.authenticate_role(&request, sonar::UserRole::Share)
+            .await?;
+        Err(sharing_unsupported_error())
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn delete_share(&self, request: Request<DeleteShare>) -> Result<()> {
+        self.authenticate_role(&request, sonar::UserRole::Share)
+            .await?;
+        Err(sharing_unsupported_error())
+    }
+}
+
+// share links are not implemented yet but the role is already checked so clients can tell
+// missing permissions apart from missing support.
+fn sharing_unsupported_error() -> opensubsonic::response::Error {
+    opensubsonic::response::Error::with_message(
+        opensubsonic::response::ErrorCode::Generic,
+        "sharing is not supported".to_string(),
+    )
 }
 
 fn artist_from_artist(favorites: &FavoritesSet, artist: sonar::Artist) -> Artist {
@@ -1079,8 +1727,8 @@ fn artist_from_artist(favorites: &FavoritesSet, artist: sonar::Artist) -> Artist
         name: artist.name,
         artist_image_url: None,
         starred: favorites.starred(artist.id),
-        user_rating: None,
-        average_rating: None,
+        user_rating: favorites.user_rating(artist.id),
+        average_rating: favorites.average_rating(artist.id),
     }
 }
 
@@ -1097,15 +1745,18 @@ fn artistid3_from_artist(favorites: &FavoritesSet, artist: sonar::Artist) -> Art
 
 fn albumid3_from_album_and_artist(
     favorites: &FavoritesSet,
-    artist: &sonar::Artist,
+    artists: &HashMap<sonar::ArtistId, sonar::Artist>,
     album: sonar::Album,
 ) -> AlbumID3 {
-    let genre = genre_string_from_genres(artist.genres.iter().chain(album.genres.iter()));
+    let artist = artists.get(&album.artist);
+    let artist_genres = artist.into_iter().flat_map(|artist| artist.genres.iter());
+    let genre = genre_string_from_genres(artist_genres.chain(album.genres.iter()));
+    let album_artists = artistid3_refs_from_ids(artists, album.artists.iter().copied());
     AlbumID3 {
         id: album.id.to_string(),
         name: album.name,
-        artist: Some(artist.name.clone()),
-        artist_id: Some(artist.id.to_string()),
+        artist: artist.map(|artist| artist.name.clone()),
+        artist_id: Some(album.artist.to_string()),
         cover_art: album.cover_art.map(|id| id.to_string()),
         song_count: album.track_count,
         duration: Default::default(),
@@ -1114,8 +1765,10 @@ fn albumid3_from_album_and_artist(
         starred: favorites.starred(album.id),
         year: None,
         genre: Some(genre),
-        user_rating: None,
+        user_rating: favorites.user_rating(album.id),
         record_labels: Default::default(),
+        display_artist: display_artist_from_artistid3s(&album_artists),
+        artists: album_artists,
     }
 }
 
@@ -1126,8 +1779,7 @@ fn multi_albumid3_from_album_and_artist(
 ) -> Vec<AlbumID3> {
     let mut albumid3s = Vec::with_capacity(albums.len());
     for album in albums.values() {
-        let artist = &artists[&album.artist];
-        let album = albumid3_from_album_and_artist(favorites, artist, album.clone());
+        let album = albumid3_from_album_and_artist(favorites, artists, album.clone());
         albumid3s.push(album);
     }
     albumid3s
@@ -1149,24 +1801,26 @@ fn child_from_album_and_artist(
         album_id: Some(album.id.to_string()),
         artist_id: Some(artist.id.to_string()),
         starred: favorites.starred(album.id),
+        user_rating: favorites.user_rating(album.id),
+        average_rating: favorites.average_rating(album.id),
         ..Default::default()
     }
 }
 
 fn child_from_audio_track_and_album_and_artist(
     favorites: &FavoritesSet,
-    artist: &sonar::Artist,
+    artists: &HashMap<sonar::ArtistId, sonar::Artist>,
     album: &sonar::Album,
     track: sonar::Track,
     audio: Option<sonar::Audio>,
 ) -> Child {
-    Child {
+    let mut child = Child {
         id: track.id.to_string(),
         parent: Some(track.album.to_string()),
         is_dir: false,
-        title: track.name,
+        title: track.name.clone(),
         album: Some(album.name.clone()),
-        artist: Some(artist.name.clone()),
+        artist: artists.get(&track.artist).map(|a| a.name.clone()),
         track: track.properties.get_parsed(sonar::prop::TRACK_NUMBER),
         genre: None,
         cover_art: track
@@ -1177,17 +1831,82 @@ fn child_from_audio_track_and_album_and_artist(
         play_count: Some(track.listen_count as u64),
         disc_number: track.properties.get_parsed(sonar::prop::DISC_NUMBER),
         starred: favorites.starred(track.id),
+        user_rating: favorites.user_rating(track.id),
+        average_rating: favorites.average_rating(track.id),
         album_id: Some(album.id.to_string()),
-        artist_id: Some(artist.id.to_string()),
+        artist_id: Some(track.artist.to_string()),
         media_type: Some(MediaType::Music),
         is_video: Some(false),
         content_type: audio.as_ref().map(|a| a.mime_type.clone()),
         bit_rate: audio.as_ref().map(|a| a.bitrate),
         size: audio.as_ref().map(|a| u64::from(a.size)),
         ..Default::default()
+    };
+    child_set_credits(&mut child, artists, album, &track);
+    child
+}
+
+/// Fills the artist lists of a song from the credits of the track and its album.
+fn child_set_credits(
+    child: &mut Child,
+    artists: &HashMap<sonar::ArtistId, sonar::Artist>,
+    album: &sonar::Album,
+    track: &sonar::Track,
+) {
+    let performers = track
+        .artists
+        .iter()
+        .filter(|credit| credit.role != sonar::ArtistRole::Remixer)
+        .map(|credit| credit.artist);
+    child.artists = artistid3_refs_from_ids(artists, performers);
+    child.display_artist = display_artist_from_artistid3s(&child.artists);
+    child.album_artists = artistid3_refs_from_ids(artists, album.artists.iter().copied());
+    child.display_album_artist = display_artist_from_artistid3s(&child.album_artists);
+    child.contributors = track
+        .artists
+        .iter()
+        .filter(|credit| credit.role == sonar::ArtistRole::Remixer)
+        .filter_map(|credit| {
+            let artist = artists.get(&credit.artist)?;
+            Some(Contributor {
+                role: credit.role.to_string(),
+                sub_role: None,
+                artist: artistid3_ref_from_artist(artist),
+            })
+        })
+        .collect();
+}
+
+/// The minimal artist object used to list the artists of albums and songs.
+fn artistid3_ref_from_artist(artist: &sonar::Artist) -> ArtistID3 {
+    ArtistID3 {
+        id: artist.id.to_string(),
+        name: artist.name.clone(),
+        cover_art: artist.cover_art.map(|id| id.to_string()),
+        album_count: artist.album_count,
+        ..Default::default()
     }
 }
 
+fn artistid3_refs_from_ids(
+    artists: &HashMap<sonar::ArtistId, sonar::Artist>,
+    artist_ids: impl IntoIterator<Item = sonar::ArtistId>,
+) -> Vec<ArtistID3> {
+    artist_ids
+        .into_iter()
+        .filter_map(|id| artists.get(&id))
+        .map(artistid3_ref_from_artist)
+        .collect()
+}
+
+fn display_artist_from_artistid3s(artists: &[ArtistID3]) -> Option<String> {
+    if artists.is_empty() {
+        return None;
+    }
+    let names = artists.iter().map(|a| a.name.as_str()).collect::<Vec<_>>();
+    Some(names.join(", "))
+}
+
 fn playlist_from_playlist(playlist: sonar::Playlist) -> Playlist {
     Playlist {
         id: playlist.id.to_string(),
@@ -1201,6 +1920,33 @@ fn playlist_from_playlist(playlist: sonar::Playlist) -> Playlist {
         changed: Default::default(),
         cover_art: playlist.cover_art.map(|id| id.to_string()),
         allowed_user: Default::default(),
+        readonly: Some(playlist.smart),
+    }
+}
+
+fn user_from_user(user: sonar::User, libraries: Vec<sonar::Library>) -> User {
+    User {
+        username: user.username.to_string(),
+        email: None,
+        scrobbling_enabled: true,
+        max_bit_rate: None,
+        admin_role: user.admin,
+        settings_role: true,
+        download_role: user.has_role(sonar::UserRole::Download),
+        upload_role: user.has_role(sonar::UserRole::Upload),
+        playlist_role: true,
+        cover_art_role: user.has_role(sonar::UserRole::EditMetadata),
+        comment_role: user.has_role(sonar::UserRole::EditMetadata),
+        podcast_role: false,
+        stream_role: user.has_role(sonar::UserRole::Stream),
+        jukebox_role: false,
+        share_role: user.has_role(sonar::UserRole::Share),
+        video_conversion_role: false,
+        avatar_last_changed: None,
+        folder: libraries
+            .into_iter()
+            .map(|library| u32::from(library.id))
+            .collect(),
     }
 }
 
@@ -1214,19 +1960,18 @@ fn child_from_playlist_tracks(
     favorites: &FavoritesSet,
 ) -> Vec<Child> {
     let mut children = Vec::with_capacity(playlist_tracks.len());
-    for (idx, track) in tracks.iter().enumerate() {
-        let artist = &artists[&track.artist];
+    for (playlist_track, track) in playlist_tracks.iter().zip(tracks.iter()) {
         let album = &albums[&track.album];
         let audio = track.audio.and_then(|id| audios.get(&id));
 
-        children.push(Child {
+        let mut child = Child {
             id: track.id.to_string(),
             parent: Some(track.album.to_string()),
             is_dir: false,
             title: track.name.clone(),
             album: Some(album.name.clone()),
-            artist: Some(artist.name.clone()),
-            track: Some(idx as u32 + 1),
+            artist: artists.get(&track.artist).map(|a| a.name.clone()),
+            track: Some(playlist_track.position + 1),
             genre: None,
             cover_art: track
                 .cover_art
@@ -1236,15 +1981,19 @@ fn child_from_playlist_tracks(
             play_count: Some(track.listen_count as u64),
             disc_number: None,
             starred: favorites.starred(track.id),
+            user_rating: favorites.user_rating(track.id),
+            average_rating: favorites.average_rating(track.id),
             album_id: Some(album.id.to_string()),
-            artist_id: Some(artist.id.to_string()),
+            artist_id: Some(track.artist.to_string()),
             media_type: Some(MediaType::Music),
             is_video: Some(false),
             content_type: audio.as_ref().map(|a| a.mime_type.clone()),
             bit_rate: audio.as_ref().map(|a| a.bitrate),
             size: audio.as_ref().map(|a| u64::from(a.size)),
             ..Default::default()
-        });
+        };
+        child_set_credits(&mut child, artists, album, track);
+        children.push(child);
     }
     children
 }
@@ -1303,6 +2052,27 @@ pub async fn start_server(
     Ok(())
 }
 
+/// Parses a music folder id, music folders are the libraries the user has access to.
+fn parse_music_folder_id_opt(id: Option<&str>) -> Result<Option<sonar::LibraryId>> {
+    let Some(id) = id else {
+        return Ok(None);
+    };
+    let id = id.parse::<u32>().map_err(|_| {
+        opensubsonic::response::Error::with_message(
+            opensubsonic::response::ErrorCode::Generic,
+            format!("invalid music folder id '{id}'"),
+        )
+    })?;
+    sonar::LibraryId::try_from(id).map(Some).m()
+}
+
+fn api_key_scope_error() -> opensubsonic::response::Error {
+    opensubsonic::response::Error::with_message(
+        opensubsonic::response::ErrorCode::UserNotAuthorizedForTheGivenOperation,
+        "api key scope does not allow this operation".to_string(),
+    )
+}
+
 trait ResultExt<T> {
     fn m(self) -> Result<T, opensubsonic::response::Error>;
 }
@@ -1343,6 +2113,17 @@ impl<T> ResultExt<T> for sonar::Result<T, sonar::InvalidUsernameError> {
     }
 }
 
+impl<T> ResultExt<T> for sonar::Result<T, sonar::InvalidRatingError> {
+    fn m(self) -> Result<T, opensubsonic::response::Error> {
+        self.map_err(|err| {
+            opensubsonic::response::Error::with_message(
+                opensubsonic::response::ErrorCode::Generic,
+                err.to_string(),
+            )
+        })
+    }
+}
+
 impl<T> ResultExt<T> for sonar::Result<T, sonar::InvalidGenreError> {
     fn m(self) -> Result<T, opensubsonic::response::Error> {
         self.map_err(|err| {
@@ -1353,3 +2134,13 @@ impl<T> ResultExt<T> for sonar::Result<T, sonar::InvalidGenreError> {
         })
     }
 }
+impl<T> ResultExt<T> for sonar::Result<T, sonar::InvalidRadioSeedError> {
+    fn m(self) -> Result<T, opensubsonic::response::Error> {
+        self.map_err(|err| {
+            opensubsonic::response::Error::with_message(
+                opensubsonic::response::ErrorCode::Generic,
+                err.to_string(),
+            )
+        })
+    }
+}
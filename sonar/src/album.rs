@@ -3,9 +3,11 @@ use std::time::Duration;
 use sqlx::Row;
 
 use crate::{
+    credit,
     db::{self, Db, DbC, SonarView},
-    genre, property, AlbumId, ArtistId, Error, ErrorKind, GenreUpdate, Genres, ImageId, ListParams,
-    Properties, PropertyUpdate, Result, SonarId, Timestamp, ValueUpdate,
+    genre, property, AlbumId, ArtistId, Error, ErrorKind, GenreUpdate, Genres, ImageId, LibraryId,
+    ListParams, Properties, PropertyUpdate, Result, SonarId, Timestamp, ValueUpdate,
+    ID_NAMESPACE_ALBUM,
 };
 
 #[derive(Debug, Clone)]
@@ -13,12 +15,17 @@ pub struct Album {
     pub id: AlbumId,
     pub name: String,
     pub duration: Duration,
+    /// the first album artist.
     pub artist: ArtistId,
+    /// every album artist, starting with `artist`.
+    pub artists: Vec<ArtistId>,
     pub track_count: u32,
     pub listen_count: u32,
     pub cover_art: Option<ImageId>,
     pub genres: Genres,
     pub properties: Properties,
+    /// set from the library of `artist` when the album is created.
+    pub library: LibraryId,
     pub created_at: Timestamp,
 }
 
@@ -26,6 +33,8 @@ pub struct Album {
 pub struct AlbumCreate {
     pub name: String,
     pub artist: ArtistId,
+    /// other album artists, credited after `artist`.
+    pub artists: Vec<ArtistId>,
     pub cover_art: Option<ImageId>,
     pub genres: Genres,
     pub properties: Properties,
@@ -34,7 +43,10 @@ pub struct AlbumCreate {
 #[derive(Debug, Default, Clone)]
 pub struct AlbumUpdate {
     pub name: ValueUpdate<String>,
+    /// replaces the first album artist.
     pub artist: ValueUpdate<ArtistId>,
+    /// replaces every album artist, the first one becomes the album's `artist`.
+    pub artists: ValueUpdate<Vec<ArtistId>>,
     pub cover_art: ValueUpdate<ImageId>,
     pub genres: Vec<GenreUpdate>,
     pub properties: Vec<PropertyUpdate>,
@@ -49,6 +61,7 @@ struct AlbumView {
     listen_count: Option<i64>,
     cover_art: Option<i64>,
     track_count: Option<i64>,
+    library: i64,
     created_at: i64,
 }
 
@@ -65,23 +78,37 @@ impl From<(AlbumView, Genres, Properties)> for Album {
             name: value.name,
             duration: Duration::from_millis(value.duration_ms.unwrap_or_default() as u64),
             artist: ArtistId::from_db(value.artist),
+            artists: vec![ArtistId::from_db(value.artist)],
             listen_count: value.listen_count.unwrap_or_default() as u32,
             cover_art: value.cover_art.map(ImageId::from_db),
             genres,
             properties,
             track_count: value.track_count.unwrap_or_default() as u32,
+            library: LibraryId::from_db(value.library),
             created_at: Timestamp::from_seconds(value.created_at as u64),
         }
     }
 }
 
 #[tracing::instrument(skip(db))]
-pub async fn list(db: &mut DbC, params: ListParams) -> Result<Vec<Album>> {
-    let views = db::list::<AlbumView>(db, "sqlx_album", params).await?;
+pub async fn list(
+    db: &mut DbC,
+    libraries: Option<&[LibraryId]>,
+    params: ListParams,
+) -> Result<Vec<Album>> {
+    let views = match libraries {
+        Some(libraries) => {
+            db::list_where_field_in::<AlbumView, _>(db, "sqlx_album", "library", libraries, params)
+                .await?
+        }
+        None => db::list::<AlbumView>(db, "sqlx_album", params).await?,
+    };
     let genres = genre::get_bulk(db, views.iter().map(|view| AlbumId::from_db(view.id))).await?;
     let properties =
         property::get_bulk(db, views.iter().map(|view| AlbumId::from_db(view.id))).await?;
-    Ok(db::merge_view_genres_properties(views, genres, properties))
+    let mut albums = db::merge_view_genres_properties(views, genres, properties);
+    credit::fill_albums(db, &mut albums).await?;
+    Ok(albums)
 }
 
 #[tracing::instrument(skip(db))]
@@ -95,23 +122,38 @@ pub async fn list_ids(db: &mut DbC) -> Result<Vec<AlbumId>> {
 #[tracing::instrument(skip(db))]
 pub async fn list_by_artist(
     db: &mut DbC,
+    libraries: Option<&[LibraryId]>,
     artist_id: ArtistId,
     params: ListParams,
 ) -> Result<Vec<Album>> {
     let (offset, limit) = params.to_db_offset_limit();
-    let views = sqlx::QueryBuilder::new(
-        "SELECT * FROM sqlx_album WHERE artist = ? ORDER BY id ASC LIMIT ? OFFSET ?",
-    )
-    .build_query_as::<AlbumView>()
-    .bind(artist_id.to_db())
-    .bind(limit)
-    .bind(offset)
-    .fetch_all(&mut *db)
-    .await?;
+    // includes the albums where the artist is only credited on some tracks
+    let mut query = sqlx::QueryBuilder::new(
+        "SELECT * FROM sqlx_album WHERE id IN (
+            SELECT album FROM album_credit WHERE artist = ?
+            UNION
+            SELECT track.album FROM track_credit INNER JOIN track ON track.id = track_credit.track WHERE track_credit.artist = ?
+        ) ",
+    );
+    if let Some(libraries) = libraries {
+        query.push("AND library IN ");
+        db::query_builder_push_id_tuple(&mut query, libraries);
+    }
+    let views = query
+        .push("ORDER BY id ASC LIMIT ? OFFSET ?")
+        .build_query_as::<AlbumView>()
+        .bind(artist_id.to_db())
+        .bind(artist_id.to_db())
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(&mut *db)
+        .await?;
     let genres = genre::get_bulk(db, views.iter().map(|view| AlbumId::from_db(view.id))).await?;
     let properties =
         property::get_bulk(db, views.iter().map(|view| AlbumId::from_db(view.id))).await?;
-    Ok(db::merge_view_genres_properties(views, genres, properties))
+    let mut albums = db::merge_view_genres_properties(views, genres, properties);
+    credit::fill_albums(db, &mut albums).await?;
+    Ok(albums)
 }
 
 #[tracing::instrument(skip(db))]
@@ -125,6 +167,39 @@ pub async fn list_artist_id_pairs(db: &mut DbC) -> Result<Vec<(AlbumId, ArtistId
         .collect())
 }
 
+/// Lists the albums that have been rated, ordered by their average rating.
+#[tracing::instrument(skip(db))]
+pub async fn list_highest_rated(
+    db: &mut DbC,
+    libraries: Option<&[LibraryId]>,
+    params: ListParams,
+) -> Result<Vec<Album>> {
+    let (offset, limit) = params.to_db_offset_limit();
+    let mut query = sqlx::QueryBuilder::new(
+        "SELECT album.id FROM album INNER JOIN rating ON rating.namespace = ",
+    );
+    query.push_bind(ID_NAMESPACE_ALBUM);
+    query.push(" AND rating.identifier = album.id ");
+    if let Some(libraries) = libraries {
+        query.push("WHERE album.library IN ");
+        db::query_builder_push_id_tuple(&mut query, libraries);
+    }
+    query.push(
+        "GROUP BY album.id ORDER BY AVG(rating.rating) DESC, COUNT(*) DESC, album.id ASC LIMIT ",
+    );
+    query.push_bind(limit);
+    query.push(" OFFSET ");
+    query.push_bind(offset);
+    let ids = query
+        .build_query_scalar::<i64>()
+        .fetch_all(&mut *db)
+        .await?
+        .into_iter()
+        .map(AlbumId::from_db)
+        .collect::<Vec<_>>();
+    get_bulk(db, &ids).await
+}
+
 #[tracing::instrument(skip(db))]
 pub async fn get(db: &mut DbC, album_id: AlbumId) -> Result<Album> {
     let view = sqlx::query_as::<_, AlbumView>("SELECT * FROM sqlx_album WHERE id = ?")
@@ -133,7 +208,9 @@ pub async fn get(db: &mut DbC, album_id: AlbumId) -> Result<Album> {
         .await?;
     let genres = genre::get(db, AlbumId::from_db(view.id)).await?;
     let properties = property::get(db, AlbumId::from_db(view.id)).await?;
-    Ok(From::from((view, genres, properties)))
+    let mut album = Album::from((view, genres, properties));
+    credit::fill_albums(db, std::slice::from_mut(&mut album)).await?;
+    Ok(album)
 }
 
 #[tracing::instrument(skip(db))]
@@ -142,9 +219,9 @@ pub async fn get_bulk(db: &mut DbC, album_ids: &[AlbumId]) -> Result<Vec<Album>>
     let expanded = db::expand_views(views, album_ids);
     let genres = genre::get_bulk(db, album_ids.iter().copied()).await?;
     let properties = property::get_bulk(db, album_ids.iter().copied()).await?;
-    Ok(db::merge_view_genres_properties(
-        expanded, genres, properties,
-    ))
+    let mut albums = db::merge_view_genres_properties(expanded, genres, properties);
+    credit::fill_albums(db, &mut albums).await?;
+    Ok(albums)
 }
 
 #[tracing::instrument(skip(db))]
@@ -164,14 +241,21 @@ pub async fn get_by_name(db: &mut DbC, name: &str) -> Result<Album> {
 #[tracing::instrument(skip(db))]
 pub async fn create(db: &mut DbC, create: AlbumCreate) -> Result<Album> {
     let cover_art = create.cover_art.map(|id| id.to_db());
-    let query =
-        sqlx::query("INSERT INTO album (artist, name, cover_art) VALUES (?, ?, ?) RETURNING id")
-            .bind(create.artist)
-            .bind(&create.name)
-            .bind(cover_art)
-            .fetch_one(&mut *db)
-            .await?;
+    let query = sqlx::query(
+        "INSERT INTO album (artist, name, cover_art, library)
+        VALUES (?, ?, ?, (SELECT library FROM artist WHERE id = ?)) RETURNING id",
+    )
+    .bind(create.artist)
+    .bind(&create.name)
+    .bind(cover_art)
+    .bind(create.artist)
+    .fetch_one(&mut *db)
+    .await?;
     let album_id = AlbumId::from_db(query.get(0));
+    let artists = std::iter::once(create.artist)
+        .chain(create.artists.iter().copied())
+        .collect::<Vec<_>>();
+    credit::album_set(db, album_id, &artists).await?;
     genre::set(db, album_id, &create.genres).await?;
     property::set(db, album_id, &create.properties).await?;
     get(db, album_id).await
@@ -181,7 +265,56 @@ pub async fn create(db: &mut DbC, create: AlbumCreate) -> Result<Album> {
 pub async fn update(db: &mut DbC, album_id: AlbumId, update: AlbumUpdate) -> Result<Album> {
     tracing::info!("updating album {} with {:#?}", album_id, update);
     db::value_update_string_non_null(db, "album", "name", album_id, update.name).await?;
-    db::value_update_id_non_null(db, "album", "artist", album_id, update.artist).await?;
+    let artists = match (update.artist, update.artists) {
+        (ValueUpdate::Unchanged, ValueUpdate::Set(artists)) => Some(artists),
+        (_, ValueUpdate::Set(_)) => {
+            return Err(Error::new(
+                ErrorKind::Invalid,
+                "album artist and artists cannot be updated together",
+            ))
+        }
+        (_, ValueUpdate::Unset) => {
+            return Err(Error::new(
+                ErrorKind::Invalid,
+                "album must have at least one artist",
+            ))
+        }
+        (ValueUpdate::Set(artist), ValueUpdate::Unchanged) => {
+            let current = credit::album_get_bulk(db, &[album_id])
+                .await?
+                .remove(&album_id)
+                .unwrap_or_default();
+            Some(
+                std::iter::once(artist)
+                    .chain(current.into_iter().skip(1))
+                    .collect(),
+            )
+        }
+        (ValueUpdate::Unset, ValueUpdate::Unchanged) => {
+            return Err(Error::new(
+                ErrorKind::Invalid,
+                "album artist cannot be unset",
+            ))
+        }
+        (ValueUpdate::Unchanged, ValueUpdate::Unchanged) => None,
+    };
+    if let Some(artists) = artists {
+        credit::album_set(db, album_id, &artists).await?;
+        // albums are in the library of their artist and tracks in the library of their album
+        sqlx::query(
+            "UPDATE album SET library = (SELECT library FROM artist WHERE id = album.artist) WHERE id = ?",
+        )
+        .bind(album_id)
+        .execute(&mut *db)
+        .await?;
+        sqlx::query(
+            "UPDATE track SET library = (SELECT library FROM album WHERE id = ?) WHERE album = ?",
+        )
+        .bind(album_id)
+        .bind(album_id)
+        .execute(&mut *db)
+        .await?;
+    }
     db::value_update_id_nullable(db, "album", "cover_art", album_id, update.cover_art).await?;
     genre::update(db, album_id, &update.genres).await?;
     property::update(db, album_id, &update.properties).await?;
@@ -190,6 +323,7 @@ pub async fn update(db: &mut DbC, album_id: AlbumId, update: AlbumUpdate) -> Res
 
 #[tracing::instrument(skip(db))]
 pub async fn delete(db: &mut DbC, album_id: AlbumId) -> Result<()> {
+    credit::album_clear(db, album_id).await?;
     sqlx::query("DELETE FROM album WHERE id = ?")
         .bind(album_id)
         .execute(&mut *db)
@@ -201,11 +335,14 @@ pub async fn delete(db: &mut DbC, album_id: AlbumId) -> Result<()> {
 
 #[tracing::instrument(skip(db))]
 pub async fn find_or_create_by_name(db: &mut DbC, create_: AlbumCreate) -> Result<Album> {
-    let album_id = sqlx::query("SELECT id FROM album WHERE name = ?")
-        .bind(&create_.name)
-        .fetch_optional(&mut *db)
-        .await?
-        .map(|row| AlbumId::from_db(row.get(0)));
+    let album_id = sqlx::query(
+        "SELECT id FROM album WHERE name = ? AND library = (SELECT library FROM artist WHERE id = ?)",
+    )
+    .bind(&create_.name)
+    .bind(create_.artist)
+    .fetch_optional(&mut *db)
+    .await?
+    .map(|row| AlbumId::from_db(row.get(0)));
 
     if let Some(album_id) = album_id {
         return get(db, album_id).await;
@@ -1,8 +1,9 @@
 use crate::{
+    credit,
     db::{self, Db, DbC, SonarView},
     genre::{self, GenreUpdate},
-    property, ArtistId, Error, ErrorKind, Genres, ImageId, ListParams, Properties, PropertyUpdate,
-    Result, Timestamp, ValueUpdate,
+    library, property, ArtistId, Error, ErrorKind, Genres, ImageId, LibraryId, ListParams,
+    Properties, PropertyUpdate, Result, Timestamp, ValueUpdate,
 };
 
 #[derive(Debug, Clone)]
@@ -14,6 +15,7 @@ pub struct Artist {
     pub cover_art: Option<ImageId>,
     pub genres: Genres,
     pub properties: Properties,
+    pub library: LibraryId,
     pub created_at: Timestamp,
 }
 
@@ -23,6 +25,8 @@ pub struct ArtistCreate {
     pub cover_art: Option<ImageId>,
     pub genres: Genres,
     pub properties: Properties,
+    /// the default library if none is given.
+    pub library: Option<LibraryId>,
 }
 
 #[derive(Debug, Default, Clone)]
@@ -40,6 +44,7 @@ struct ArtistView {
     listen_count: i64,
     cover_art: Option<i64>,
     album_count: i64,
+    library: i64,
     created_at: i64,
 }
 
@@ -59,14 +64,31 @@ impl From<(ArtistView, Genres, Properties)> for Artist {
             genres,
             properties,
             album_count: value.album_count as u32,
+            library: LibraryId::from_db(value.library),
             created_at: Timestamp::from_seconds(value.created_at as u64),
         }
     }
 }
 
 #[tracing::instrument(skip(db))]
-pub async fn list(db: &mut DbC, params: ListParams) -> Result<Vec<Artist>> {
-    let views = db::list::<ArtistView>(db, "sqlx_artist", params).await?;
+pub async fn list(
+    db: &mut DbC,
+    libraries: Option<&[LibraryId]>,
+    params: ListParams,
+) -> Result<Vec<Artist>> {
+    let views = match libraries {
+        Some(libraries) => {
+            db::list_where_field_in::<ArtistView, _>(
+                db,
+                "sqlx_artist",
+                "library",
+                libraries,
+                params,
+            )
+            .await?
+        }
+        None => db::list::<ArtistView>(db, "sqlx_artist", params).await?,
+    };
     let properties =
         property::get_bulk(db, views.iter().map(|view| ArtistId::from_db(view.id))).await?;
     let genres = genre::get_bulk(db, views.iter().map(|view| ArtistId::from_db(view.id))).await?;
@@ -120,11 +142,17 @@ pub async fn get_by_name(db: &mut DbC, name: &str) -> Result<Artist> {
 #[tracing::instrument(skip(db))]
 pub async fn create(db: &mut DbC, create: ArtistCreate) -> Result<Artist> {
     let cover_art = create.cover_art.map(|id| id.to_db());
-    let id = sqlx::query_scalar("INSERT INTO artist (name, cover_art) VALUES (?, ?) RETURNING id")
-        .bind(&create.name)
-        .bind(cover_art)
-        .fetch_one(&mut *db)
-        .await?;
+    let library_id = library::get(db, create.library.unwrap_or(LibraryId::DEFAULT))
+        .await?
+        .id;
+    let id = sqlx::query_scalar(
+        "INSERT INTO artist (name, cover_art, library) VALUES (?, ?, ?) RETURNING id",
+    )
+    .bind(&create.name)
+    .bind(cover_art)
+    .bind(library_id)
+    .fetch_one(&mut *db)
+    .await?;
     let artist_id = ArtistId::from_db(id);
     genre::set(db, artist_id, &create.genres).await?;
     property::set(db, artist_id, &create.properties).await?;
@@ -142,6 +170,7 @@ pub async fn update(db: &mut DbC, artist_id: ArtistId, update: ArtistUpdate) ->
 }
 
 pub async fn delete(db: &mut DbC, artist_id: ArtistId) -> Result<()> {
+    credit::artist_clear(db, artist_id).await?;
     sqlx::query("DELETE FROM artist WHERE id = ?")
         .bind(artist_id)
         .execute(&mut *db)
@@ -153,8 +182,9 @@ pub async fn delete(db: &mut DbC, artist_id: ArtistId) -> Result<()> {
 
 #[tracing::instrument(skip(db))]
 pub async fn find_or_create_by_name(db: &mut DbC, create_: ArtistCreate) -> Result<Artist> {
-    let artist_id = sqlx::query_scalar("SELECT id FROM artist WHERE name = ?")
+    let artist_id = sqlx::query_scalar("SELECT id FROM artist WHERE name = ? AND library = ?")
         .bind(&create_.name)
+        .bind(create_.library.unwrap_or(LibraryId::DEFAULT))
         .fetch_optional(&mut *db)
         .await?;
     if let Some(artist_id) = artist_id {
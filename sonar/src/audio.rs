@@ -7,7 +7,7 @@ use crate::{
     blob::{self, BlobStorage},
     bytestream::{self, ByteStream},
     db::{self, DbC},
-    ks, AudioId, ByteRange, Error, ErrorKind, Result, TrackId,
+    AudioId, ByteRange, Error, ErrorKind, Result, TrackId,
 };
 
 #[derive(Debug, Clone)]
@@ -108,10 +108,7 @@ pub async fn create(db: &mut DbC, storage: &dyn BlobStorage, create: AudioCreate
         new_path
     };
 
-    let filesize = temp_file_path.metadata()?.len() as u32;
     let tagged_file = lofty::read_from_path(&temp_file_path).map_err(Error::wrap)?;
-    let blob_key = blob::random_key_with_prefix("audio");
-    let blob_sha256 = ks::sha256_file(&temp_file_path).await?;
     let properties = tagged_file.properties();
     eprintln!("properties: {:#?}", properties);
 
@@ -132,17 +129,7 @@ pub async fn create(db: &mut DbC, storage: &dyn BlobStorage, create: AudioCreate
         as u32;
     let mime_type = file_type.mime_type();
 
-    let stream = bytestream::from_file(&temp_file_path).await?;
-    storage.write(&blob_key, stream).await?;
-
-    let blob_id = sqlx::query_scalar::<_, i64>(
-        "INSERT INTO blob (key, size, sha256) VALUES (?, ?, ?) RETURNING id",
-    )
-    .bind(blob_key)
-    .bind(filesize)
-    .bind(blob_sha256)
-    .fetch_one(&mut *db)
-    .await?;
+    let blob_id = blob::create_from_file(db, storage, "audio", &temp_file_path).await?;
 
     let audio_id = sqlx::query_scalar(
         "INSERT INTO audio (bitrate, duration_ms, num_channels, sample_freq, mime_type, blob, filename) VALUES (?, ?, ?, ?, ?, ?, ?) RETURNING id")
@@ -169,12 +156,16 @@ pub async fn get_bulk(db: &mut DbC, audio_ids: &[AudioId]) -> Result<Vec<Audio>>
     Ok(views.into_iter().map(Audio::from).collect())
 }
 
-pub async fn delete(db: &mut DbC, audio_id: AudioId) -> Result<()> {
-    sqlx::query("DELETE FROM audio WHERE id = ?")
+/// Deletes the audio, returning the key of its blob if it must be removed from storage.
+pub async fn delete(db: &mut DbC, audio_id: AudioId) -> Result<Option<String>> {
+    let blob_id = sqlx::query_scalar::<_, i64>("DELETE FROM audio WHERE id = ? RETURNING blob")
         .bind(audio_id)
-        .execute(&mut *db)
+        .fetch_optional(&mut *db)
         .await?;
-    Ok(())
+    match blob_id {
+        Some(blob_id) => blob::release(db, blob_id).await,
+        None => Ok(None),
+    }
 }
 
 pub async fn download(
@@ -0,0 +1,404 @@
+//! Backups of the database and the blob storage.
+//! A backup is a directory with a snapshot of the database, every blob stored by its sha256 and
+//! a manifest with the checksums of all files. The snapshot is taken with `VACUUM INTO` so the
+//! server can keep running while the backup is created.
+//! Blobs already present in the directory are not copied again, so backing up to the same
+//! directory only copies the blobs added since the previous backup.
+
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+use sqlx::{
+    sqlite::{SqliteConnectOptions, SqliteConnection},
+    ConnectOptions, Connection,
+};
+
+use crate::{blob::BlobStorage, bytestream, db::DbC, ks, Error, ErrorKind, Result, Timestamp};
+
+const MANIFEST_VERSION: u32 = 1;
+const MANIFEST_FILENAME: &str = "manifest.json";
+const DATABASE_FILENAME: &str = "sonar.db";
+const BLOBS_DIRECTORY: &str = "blobs";
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Manifest {
+    version: u32,
+    created_at: u64,
+    database: ManifestFile,
+    blobs: Vec<ManifestFile>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ManifestFile {
+    sha256: String,
+    size: u64,
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct BackupReport {
+    /// Number of blobs in the backup.
+    pub blobs: u64,
+    /// Number of blobs that were not already present in the backup directory.
+    pub blobs_copied: u64,
+    pub bytes_copied: u64,
+    /// Keys of the blobs that were deleted while the backup was being created.
+    /// They are removed from the database snapshot along with the images and audios using them.
+    pub blobs_skipped: Vec<String>,
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct RestoreReport {
+    pub blobs: u64,
+    pub bytes: u64,
+}
+
+#[tracing::instrument(skip(db, storage))]
+pub async fn create(db: &mut DbC, storage: &dyn BlobStorage, path: &Path) -> Result<BackupReport> {
+    let blobs_path = path.join(BLOBS_DIRECTORY);
+    tokio::fs::create_dir_all(&blobs_path).await?;
+
+    // VACUUM INTO requires the destination to not exist
+    let snapshot_path = path.join(format!("{DATABASE_FILENAME}.tmp"));
+    remove_file_if_exists(&snapshot_path).await?;
+    sqlx::query("VACUUM INTO ?")
+        .bind(snapshot_path.display().to_string())
+        .execute(&mut *db)
+        .await?;
+
+    let mut snapshot = SqliteConnectOptions::new()
+        .filename(&snapshot_path)
+        .connect()
+        .await?;
+    let blobs = sqlx::query_as::<_, (String, i64, String)>(
+        "SELECT key, size, sha256 FROM blob ORDER BY id ASC",
+    )
+    .fetch_all(&mut snapshot)
+    .await?;
+
+    let mut report = BackupReport::default();
+    let mut manifest_blobs = Vec::with_capacity(blobs.len());
+    for (key, size, sha256) in blobs {
+        let size = size as u64;
+        let blob_path = blobs_path.join(&sha256);
+        if file_size(&blob_path).await? != Some(size) {
+            tracing::debug!("copying blob {} to backup", key);
+            let stream = match storage.read(&key, Default::default()).await {
+                Ok(stream) => stream,
+                Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+                    // blobs deleted after the snapshot was taken are no longer in the database
+                    if blob_exists(db, &key).await? {
+                        return Err(err.into());
+                    }
+                    tracing::warn!("blob {} was deleted during the backup, skipping it", key);
+                    report.blobs_skipped.push(key);
+                    continue;
+                }
+                Err(err) => return Err(err.into()),
+            };
+            let tmp_path = blobs_path.join(format!("{sha256}.tmp"));
+            bytestream::to_file(stream, &tmp_path).await?;
+            if ks::sha256_file(&tmp_path).await? != sha256 {
+                tokio::fs::remove_file(&tmp_path).await?;
+                return Err(Error::new(
+                    ErrorKind::Internal,
+                    format!("blob {key} does not match its checksum"),
+                ));
+            }
+            tokio::fs::rename(&tmp_path, &blob_path).await?;
+            report.blobs_copied += 1;
+            report.bytes_copied += size;
+        }
+        report.blobs += 1;
+        manifest_blobs.push(ManifestFile { sha256, size });
+    }
+    remove_snapshot_blobs(&mut snapshot, &report.blobs_skipped).await?;
+    snapshot.close().await?;
+
+    let database_path = path.join(DATABASE_FILENAME);
+    tokio::fs::rename(&snapshot_path, &database_path).await?;
+    let manifest = Manifest {
+        version: MANIFEST_VERSION,
+        created_at: Timestamp::now().seconds(),
+        database: ManifestFile {
+            sha256: ks::sha256_file(&database_path).await?,
+            size: tokio::fs::metadata(&database_path).await?.len(),
+        },
+        blobs: manifest_blobs,
+    };
+    write_manifest(path, &manifest).await?;
+    remove_unlisted_blobs(&blobs_path, &manifest).await?;
+
+    Ok(report)
+}
+
+/// Restores a backup into an empty database file and blob storage.
+#[tracing::instrument(skip(storage))]
+pub async fn restore(
+    archive: &Path,
+    database_path: &Path,
+    storage: &dyn BlobStorage,
+) -> Result<RestoreReport> {
+    if tokio::fs::try_exists(database_path).await? {
+        return Err(Error::new(
+            ErrorKind::Invalid,
+            format!("database {} already exists", database_path.display()),
+        ));
+    }
+
+    let manifest = read_manifest(archive).await?;
+    let archive_database = archive.join(DATABASE_FILENAME);
+    verify_file(&archive_database, &manifest.database).await?;
+    for blob in manifest.blobs.iter() {
+        verify_file(&archive.join(BLOBS_DIRECTORY).join(&blob.sha256), blob).await?;
+    }
+
+    if let Some(parent) = database_path.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+    tokio::fs::copy(&archive_database, database_path).await?;
+
+    let mut conn = SqliteConnectOptions::new()
+        .filename(database_path)
+        .read_only(true)
+        .connect()
+        .await?;
+    let blobs = sqlx::query_as::<_, (String, i64, String)>(
+        "SELECT key, size, sha256 FROM blob ORDER BY id ASC",
+    )
+    .fetch_all(&mut conn)
+    .await?;
+    conn.close().await?;
+
+    let listed = manifest
+        .blobs
+        .iter()
+        .map(|blob| blob.sha256.as_str())
+        .collect::<HashSet<_>>();
+    let mut report = RestoreReport::default();
+    for (key, size, sha256) in blobs {
+        if !listed.contains(sha256.as_str()) {
+            return Err(Error::new(
+                ErrorKind::Invalid,
+                format!("blob {key} is missing from the backup"),
+            ));
+        }
+        let blob_path = archive.join(BLOBS_DIRECTORY).join(&sha256);
+        let stream = bytestream::from_file(&blob_path).await?;
+        storage.write(&key, stream).await?;
+        report.blobs += 1;
+        report.bytes += size as u64;
+    }
+
+    Ok(report)
+}
+
+/// Removes the blobs missing from the backup from the database snapshot, along with the images
+/// and audios stored in them.
+async fn remove_snapshot_blobs(snapshot: &mut SqliteConnection, keys: &[String]) -> Result<()> {
+    if keys.is_empty() {
+        return Ok(());
+    }
+
+    let images = "SELECT image.id FROM image JOIN blob ON blob.id = image.blob WHERE blob.key = ?";
+    let audios = "SELECT audio.id FROM audio JOIN blob ON blob.id = audio.blob WHERE blob.key = ?";
+    let statements = [
+        format!("UPDATE user SET avatar = NULL WHERE avatar IN ({images})"),
+        format!("UPDATE artist SET cover_art = NULL WHERE cover_art IN ({images})"),
+        format!("UPDATE album SET cover_art = NULL WHERE cover_art IN ({images})"),
+        format!("UPDATE track SET cover_art = NULL WHERE cover_art IN ({images})"),
+        format!("UPDATE playlist SET cover_art = NULL WHERE cover_art IN ({images})"),
+        format!("DELETE FROM track_audio WHERE audio IN ({audios})"),
+        format!("DELETE FROM image WHERE id IN ({images})"),
+        format!("DELETE FROM audio WHERE id IN ({audios})"),
+        "DELETE FROM blob WHERE key = ?".to_string(),
+    ];
+    let mut tx = snapshot.begin().await?;
+    for key in keys {
+        for statement in statements.iter() {
+            sqlx::query(statement).bind(key).execute(&mut *tx).await?;
+        }
+    }
+    tx.commit().await?;
+    Ok(())
+}
+
+async fn blob_exists(db: &mut DbC, key: &str) -> Result<bool> {
+    let exists = sqlx::query_scalar::<_, bool>("SELECT EXISTS(SELECT 1 FROM blob WHERE key = ?)")
+        .bind(key)
+        .fetch_one(&mut *db)
+        .await?;
+    Ok(exists)
+}
+
+async fn read_manifest(archive: &Path) -> Result<Manifest> {
+    let content = tokio::fs::read(archive.join(MANIFEST_FILENAME)).await?;
+    let manifest: Manifest = serde_json::from_slice(&content)
+        .map_err(|e| Error::with_source(ErrorKind::Invalid, "invalid backup manifest", e))?;
+    if manifest.version != MANIFEST_VERSION {
+        return Err(Error::new(
+            ErrorKind::Invalid,
+            format!("unsupported backup version {}", manifest.version),
+        ));
+    }
+    Ok(manifest)
+}
+
+async fn write_manifest(archive: &Path, manifest: &Manifest) -> Result<()> {
+    let content = serde_json::to_vec_pretty(manifest).map_err(|e| {
+        Error::with_source(
+            ErrorKind::Internal,
+            "failed to serialize backup manifest",
+            e,
+        )
+    })?;
+    let tmp_path = archive.join(format!("{MANIFEST_FILENAME}.tmp"));
+    tokio::fs::write(&tmp_path, content).await?;
+    tokio::fs::rename(&tmp_path, archive.join(MANIFEST_FILENAME)).await?;
+    Ok(())
+}
+
+/// Removes blobs left over from previous backups that are no longer in use.
+async fn remove_unlisted_blobs(blobs_path: &Path, manifest: &Manifest) -> Result<()> {
+    let listed = manifest
+        .blobs
+        .iter()
+        .map(|blob| blobs_path.join(&blob.sha256))
+        .collect::<HashSet<PathBuf>>();
+    let mut entries = tokio::fs::read_dir(blobs_path).await?;
+    while let Some(entry) = entries.next_entry().await? {
+        if !listed.contains(&entry.path()) {
+            tokio::fs::remove_file(entry.path()).await?;
+        }
+    }
+    Ok(())
+}
+
+async fn verify_file(path: &Path, expected: &ManifestFile) -> Result<()> {
+    let size = file_size(path).await?;
+    if size != Some(expected.size) || ks::sha256_file(path).await? != expected.sha256 {
+        return Err(Error::new(
+            ErrorKind::Invalid,
+            format!("backup file {} is missing or corrupted", path.display()),
+        ));
+    }
+    Ok(())
+}
+
+async fn file_size(path: &Path) -> Result<Option<u64>> {
+    match tokio::fs::metadata(path).await {
+        Ok(metadata) => Ok(Some(metadata.len())),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(err.into()),
+    }
+}
+
+async fn remove_file_if_exists(path: &Path) -> Result<()> {
+    match tokio::fs::remove_file(path).await {
+        Ok(()) => Ok(()),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        Err(err) => Err(err.into()),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use bytes::Bytes;
+
+    use super::*;
+    use crate::{async_trait, bytestream::ByteStream, db::Db, ByteRange};
+
+    /// Storage that behaves as if every blob was garbage collected right before it is read.
+    #[derive(Debug)]
+    struct DeletingBlobStorage {
+        db: Db,
+        inner: Arc<dyn BlobStorage>,
+    }
+
+    #[async_trait]
+    impl BlobStorage for DeletingBlobStorage {
+        async fn read(&self, key: &str, _range: ByteRange) -> std::io::Result<ByteStream> {
+            let mut conn = self.db.acquire().await.unwrap();
+            sqlx::query("DELETE FROM image WHERE blob = (SELECT id FROM blob WHERE key = ?)")
+                .bind(key)
+                .execute(&mut *conn)
+                .await
+                .unwrap();
+            sqlx::query("DELETE FROM blob WHERE key = ?")
+                .bind(key)
+                .execute(&mut *conn)
+                .await
+                .unwrap();
+            self.inner.delete(key).await?;
+            Err(std::io::ErrorKind::NotFound.into())
+        }
+        async fn put(&self, key: &str, bytes: Bytes) -> std::io::Result<()> {
+            self.inner.put(key, bytes).await
+        }
+        async fn write(&self, key: &str, reader: ByteStream) -> std::io::Result<()> {
+            self.inner.write(key, reader).await
+        }
+        async fn delete(&self, key: &str) -> std::io::Result<()> {
+            self.inner.delete(key).await
+        }
+        async fn list(&self) -> std::io::Result<Vec<String>> {
+            self.inner.list().await
+        }
+    }
+
+    #[tokio::test]
+    async fn create_skips_blobs_deleted_during_backup() {
+        let context = crate::test::create_context_memory().await;
+        let _image = crate::test::create_image(&context).await;
+        let key = crate::test::storage_list(&context).await.remove(0);
+        let storage = DeletingBlobStorage {
+            db: context.db.clone(),
+            inner: context.storage.clone(),
+        };
+        let dir = tempfile::tempdir().unwrap();
+
+        let mut conn = context.db.acquire().await.unwrap();
+        let report = create(&mut conn, &storage, dir.path()).await.unwrap();
+        assert_eq!(report.blobs, 0);
+        assert_eq!(report.blobs_skipped, vec![key]);
+        drop(conn);
+
+        let restore_dir = tempfile::tempdir().unwrap();
+        let database_path = restore_dir.path().join("sonar.db");
+        let storage = crate::blob::MemoryBlobStorage::default();
+        let report = restore(dir.path(), &database_path, &storage).await.unwrap();
+        assert_eq!(report.blobs, 0);
+
+        // the snapshot no longer references the skipped blob
+        let mut conn = SqliteConnectOptions::new()
+            .filename(&database_path)
+            .connect()
+            .await
+            .unwrap();
+        let count = sqlx::query_scalar::<_, i64>(
+            "SELECT (SELECT COUNT(*) FROM blob) + (SELECT COUNT(*) FROM image)",
+        )
+        .fetch_one(&mut conn)
+        .await
+        .unwrap();
+        assert_eq!(count, 0);
+    }
+
+    #[tokio::test]
+    async fn create_fails_on_missing_blob() {
+        let context = crate::test::create_context_memory().await;
+        let _image = crate::test::create_image(&context).await;
+        let key = crate::test::storage_list(&context).await.remove(0);
+        context.storage.delete(&key).await.unwrap();
+        let dir = tempfile::tempdir().unwrap();
+
+        let mut conn = context.db.acquire().await.unwrap();
+        assert!(create(&mut conn, &*context.storage, dir.path())
+            .await
+            .is_err());
+    }
+}
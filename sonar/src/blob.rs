@@ -1,6 +1,6 @@
-use std::io::Result;
+use std::{io::Result, path::Path};
 
-use crate::{async_trait, bytestream, ByteRange};
+use crate::{async_trait, bytestream, db::DbC, ks, ByteRange};
 use bytes::Bytes;
 
 mod memory;
@@ -21,6 +21,8 @@ pub trait BlobStorage: std::fmt::Debug + Send + Sync + 'static {
     async fn put(&self, key: &str, bytes: Bytes) -> Result<()>;
     async fn write(&self, key: &str, reader: ByteStream) -> Result<()>;
     async fn delete(&self, key: &str) -> Result<()>;
+    /// Lists the keys of every stored blob.
+    async fn list(&self) -> Result<Vec<String>>;
 }
 
 pub fn random_key() -> String {
@@ -31,12 +33,120 @@ pub fn random_key_with_prefix(prefix: &str) -> String {
     format!("{}/{}", prefix, random_key())
 }
 
+/// Stores the file as a blob with a key under `prefix`.
+/// If a blob with the same content already exists it is reused instead.
+/// Returns the id of the blob, the caller holds one reference to it.
+pub(crate) async fn create_from_file(
+    db: &mut DbC,
+    storage: &dyn BlobStorage,
+    prefix: &str,
+    path: &Path,
+) -> crate::Result<i64> {
+    let sha256 = ks::sha256_file(path).await?;
+    let size = path.metadata()?.len() as i64;
+
+    let existing = sqlx::query_scalar::<_, i64>(
+        "UPDATE blob SET ref_count = ref_count + 1 WHERE id = (SELECT id FROM blob WHERE sha256 = ? AND size = ? LIMIT 1) RETURNING id",
+    )
+    .bind(&sha256)
+    .bind(size)
+    .fetch_optional(&mut *db)
+    .await?;
+    if let Some(blob_id) = existing {
+        tracing::debug!("reusing blob {blob_id} with sha256 {sha256}");
+        return Ok(blob_id);
+    }
+
+    let key = random_key_with_prefix(prefix);
+    let stream = bytestream::from_file(path).await?;
+    storage.write(&key, stream).await?;
+    let blob_id = sqlx::query_scalar::<_, i64>(
+        "INSERT INTO blob (key, size, sha256, ref_count) VALUES (?, ?, ?, 1) RETURNING id",
+    )
+    .bind(key)
+    .bind(size)
+    .bind(sha256)
+    .fetch_one(&mut *db)
+    .await?;
+    Ok(blob_id)
+}
+
+/// Drops one reference to the blob, the blob row is deleted once it is no longer referenced.
+/// The rows referencing the blob must be deleted before calling this.
+/// Returns the key of the deleted blob, it must only be removed from storage with
+/// [`delete_keys`] after the transaction commits so a rollback doesn't leave rows without data.
+pub(crate) async fn release(db: &mut DbC, blob_id: i64) -> crate::Result<Option<String>> {
+    let row = sqlx::query_as::<_, (i64, String)>(
+        "UPDATE blob SET ref_count = ref_count - 1 WHERE id = ? RETURNING ref_count, key",
+    )
+    .bind(blob_id)
+    .fetch_optional(&mut *db)
+    .await?;
+    let (ref_count, key) = match row {
+        Some(row) => row,
+        None => return Ok(None),
+    };
+    if ref_count > 0 {
+        return Ok(None);
+    }
+
+    sqlx::query("DELETE FROM blob WHERE id = ?")
+        .bind(blob_id)
+        .execute(&mut *db)
+        .await?;
+    Ok(Some(key))
+}
+
+/// Removes blobs released by a committed transaction from storage.
+/// Failures are only logged, the leftover blobs are reported by fsck as unreferenced.
+pub(crate) async fn delete_keys(storage: &dyn BlobStorage, keys: impl IntoIterator<Item = String>) {
+    for key in keys {
+        if let Err(err) = storage.delete(&key).await {
+            tracing::warn!("failed to delete blob {key}: {err}");
+        }
+    }
+}
+
 #[cfg(test)]
 mod test {
     use crate::bytestream;
 
     use super::*;
 
+    #[tokio::test]
+    async fn release_rollback_keeps_blob() {
+        let context = crate::test::create_context_memory().await;
+        let storage = &*context.storage;
+        let file = tempfile::NamedTempFile::new().unwrap();
+        std::fs::write(file.path(), b"hello world").unwrap();
+
+        let mut tx = context.db.begin().await.unwrap();
+        let blob_id = create_from_file(&mut tx, storage, "test", file.path())
+            .await
+            .unwrap();
+        tx.commit().await.unwrap();
+
+        let mut tx = context.db.begin().await.unwrap();
+        let key = release(&mut tx, blob_id).await.unwrap().unwrap();
+        tx.rollback().await.unwrap();
+
+        let mut conn = context.db.acquire().await.unwrap();
+        let ref_count = sqlx::query_scalar::<_, i64>("SELECT ref_count FROM blob WHERE id = ?")
+            .bind(blob_id)
+            .fetch_one(&mut *conn)
+            .await
+            .unwrap();
+        assert_eq!(ref_count, 1);
+        assert!(storage.get(&key, Default::default()).await.is_ok());
+        drop(conn);
+
+        let mut tx = context.db.begin().await.unwrap();
+        let key = release(&mut tx, blob_id).await.unwrap().unwrap();
+        tx.commit().await.unwrap();
+        delete_keys(storage, Some(key.clone())).await;
+        assert!(storage.get(&key, Default::default()).await.is_err());
+    }
+
     #[tokio::test]
     async fn memory() {
         let storage = MemoryBlobStorage::default();
@@ -59,6 +169,7 @@ mod test {
         generic_write(storage).await;
         generic_write_delete(storage).await;
         generic_read_range(storage).await;
+        generic_list(storage).await;
     }
 
     async fn generic_get_missing(storage: &dyn BlobStorage) {
@@ -124,4 +235,17 @@ mod test {
         let result = bytestream::to_bytes(result).await.unwrap();
         assert_eq!(result, Bytes::from_static(b"ell"));
     }
+
+    async fn generic_list(storage: &dyn BlobStorage) {
+        let key = random_key_with_prefix("list");
+        storage
+            .put(&key, Bytes::from_static(b"hello world"))
+            .await
+            .unwrap();
+        let keys = storage.list().await.unwrap();
+        assert!(keys.contains(&key));
+        storage.delete(&key).await.unwrap();
+        let keys = storage.list().await.unwrap();
+        assert!(!keys.contains(&key));
+    }
 }
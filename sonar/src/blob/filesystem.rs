@@ -55,4 +55,27 @@ impl BlobStorage for FilesystemBlobStorage {
         }
         Ok(())
     }
+    async fn list(&self) -> Result<Vec<String>> {
+        let mut keys = Vec::new();
+        if !self.root.exists() {
+            return Ok(keys);
+        }
+        let mut dirs = vec![self.root.clone()];
+        while let Some(dir) = dirs.pop() {
+            let mut entries = tokio::fs::read_dir(&dir).await?;
+            while let Some(entry) = entries.next_entry().await? {
+                let path = entry.path();
+                if entry.file_type().await?.is_dir() {
+                    dirs.push(path);
+                } else if let Ok(relative) = path.strip_prefix(&self.root) {
+                    let components = relative
+                        .components()
+                        .map(|c| c.as_os_str().to_string_lossy())
+                        .collect::<Vec<_>>();
+                    keys.push(components.join("/"));
+                }
+            }
+        }
+        Ok(keys)
+    }
 }
@@ -63,4 +63,8 @@ impl BlobStorage for MemoryBlobStorage {
         blobs.remove(key);
         Ok(())
     }
+    async fn list(&self) -> Result<Vec<String>> {
+        let blobs = self.blobs.lock().unwrap();
+        Ok(blobs.keys().cloned().collect())
+    }
 }
@@ -0,0 +1,97 @@
+use std::time::Duration;
+
+use crate::{db::DbC, Error, ErrorKind, Result, Timestamp, TrackId, UserId};
+
+/// A position within a track saved by a user, used to resume long tracks.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Bookmark {
+    pub user: UserId,
+    pub track: TrackId,
+    pub position: Duration,
+    pub comment: Option<String>,
+    pub created_at: Timestamp,
+    pub updated_at: Timestamp,
+}
+
+/// Creates a bookmark or replaces the existing bookmark of the user for the track.
+#[derive(Debug, Clone)]
+pub struct BookmarkCreate {
+    pub user: UserId,
+    pub track: TrackId,
+    pub position: Duration,
+    pub comment: Option<String>,
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct BookmarkView {
+    user: i64,
+    track: i64,
+    position_ms: i64,
+    comment: Option<String>,
+    created_at: i64,
+    updated_at: i64,
+}
+
+impl From<BookmarkView> for Bookmark {
+    fn from(value: BookmarkView) -> Self {
+        Self {
+            user: UserId::from_db(value.user),
+            track: TrackId::from_db(value.track),
+            position: Duration::from_millis(value.position_ms as u64),
+            comment: value.comment,
+            created_at: Timestamp::from_seconds(value.created_at as u64),
+            updated_at: Timestamp::from_seconds(value.updated_at as u64),
+        }
+    }
+}
+
+#[tracing::instrument(skip(db))]
+pub async fn list(db: &mut DbC, user_id: UserId) -> Result<Vec<Bookmark>> {
+    let views = sqlx::query_as::<_, BookmarkView>(
+        "SELECT * FROM bookmark WHERE user = ? ORDER BY updated_at DESC",
+    )
+    .bind(user_id)
+    .fetch_all(db)
+    .await?;
+    Ok(views.into_iter().map(Bookmark::from).collect())
+}
+
+#[tracing::instrument(skip(db))]
+pub async fn get(db: &mut DbC, user_id: UserId, track_id: TrackId) -> Result<Bookmark> {
+    let view =
+        sqlx::query_as::<_, BookmarkView>("SELECT * FROM bookmark WHERE user = ? AND track = ?")
+            .bind(user_id)
+            .bind(track_id)
+            .fetch_optional(db)
+            .await?;
+    match view {
+        Some(view) => Ok(Bookmark::from(view)),
+        None => Err(Error::new(ErrorKind::NotFound, "bookmark not found")),
+    }
+}
+
+#[tracing::instrument(skip(db))]
+pub async fn create(db: &mut DbC, create: BookmarkCreate) -> Result<Bookmark> {
+    sqlx::query(
+        "INSERT INTO bookmark (user, track, position_ms, comment) VALUES (?, ?, ?, ?)
+        ON CONFLICT(user, track)
+        DO UPDATE SET position_ms = excluded.position_ms, comment = excluded.comment, updated_at = unixepoch()",
+    )
+    .bind(create.user)
+    .bind(create.track)
+    .bind(create.position.as_millis() as i64)
+    .bind(&create.comment)
+    .execute(&mut *db)
+    .await?;
+    get(db, create.user, create.track).await
+}
+
+#[tracing::instrument(skip(db))]
+pub async fn delete(db: &mut DbC, user_id: UserId, track_id: TrackId) -> Result<()> {
+    sqlx::query("DELETE FROM bookmark WHERE user = ? AND track = ?")
+        .bind(user_id)
+        .bind(track_id)
+        .execute(db)
+        .await?;
+    Ok(())
+}
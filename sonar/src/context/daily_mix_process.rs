@@ -0,0 +1,23 @@
+use std::time::Duration;
+
+use crate::{Context, DailyMixParams, Result};
+
+pub(super) async fn run(context: &Context, interval: Duration) {
+    loop {
+        tokio::time::sleep(interval).await;
+        if let Err(err) = iteration(context).await {
+            tracing::error!("failed to run daily mix process iteration: {err}");
+        }
+    }
+}
+
+async fn iteration(context: &Context) -> Result<()> {
+    let users = super::user_list(context, Default::default()).await?;
+    for user in users {
+        let params = DailyMixParams::new(user.id);
+        if let Err(err) = super::recommend_daily_mix_refresh(context, params).await {
+            tracing::error!("failed to refresh daily mixes of user {}: {}", user.id, err);
+        }
+    }
+    Ok(())
+}
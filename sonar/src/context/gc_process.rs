@@ -0,0 +1,31 @@
+use std::time::Duration;
+
+use crate::{Context, GarbageCollectParams, Result, SweepParams};
+
+pub(super) async fn run(context: &Context, interval: Duration, params: GarbageCollectParams) {
+    loop {
+        tokio::time::sleep(interval).await;
+        if let Err(err) = iteration(context, params.clone()).await {
+            tracing::error!("failed to run garbage collection process iteration: {err}");
+        }
+    }
+}
+
+async fn iteration(context: &Context, params: GarbageCollectParams) -> Result<()> {
+    let dry_run = params.dry_run;
+    let report = super::garbage_collect(context, params).await?;
+    tracing::info!("garbage collected {} items", report.items.len());
+
+    let params = SweepParams {
+        dry_run,
+        ..Default::default()
+    };
+    let report = super::orphan_sweep(context, params).await?;
+    tracing::info!(
+        "swept {} images and {} blobs, reclaimed {} bytes",
+        report.images.len(),
+        report.blobs,
+        report.reclaimed_bytes
+    );
+    Ok(())
+}
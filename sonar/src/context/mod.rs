@@ -1,6 +1,5 @@
 use std::{
-    collections::HashMap,
-    path::PathBuf,
+    path::{Path, PathBuf},
     sync::{Arc, Mutex},
     time::Duration,
 };
@@ -10,43 +9,65 @@ use tokio::sync::Notify;
 
 use crate::{
     album, artist, audio,
+    backup::{self, BackupReport, RestoreReport},
     blob::{self, BlobStorage},
-    bytestream,
+    bookmark, bytestream,
     db::Db,
     download,
+    event::{EventBus, EventFilter, EventKind, EventSubscription},
     external::{ExternalService, ExternalServices, ExternalServicesEntry},
     extractor::{Extractor, SonarExtractor},
-    favorite, gc,
+    favorite,
+    fsck::{self, FsckParams, FsckProgress, FsckReport},
+    gc::{self, GarbageCollectParams, GarbageCollectReport},
     genre::GenreStats,
     image,
     importer::{self, Importer},
+    library::{self, Library, LibraryCreate, LibraryFilter, LibraryUpdate},
     metadata::{
         AlbumMetadata, AlbumMetadataRequest, AlbumTracksMetadata, AlbumTracksMetadataRequest,
         MetadataProvider, MetadataRequestKind, SonarMetadataProvider,
     },
-    migrations, pin, playlist, property, scrobble,
+    migrations, pin, play_queue, playlist, property, radio, rating,
+    recommend::{self, DailyMix, DailyMixParams},
+    scrobble,
+    scrobble_import::{self, ScrobbleImport, ScrobbleImportReport},
     scrobbler::{self, SonarScrobbler},
-    search::{BuiltInSearchEngine, MeiliSearchEngine, SearchEngine, SearchResults},
+    search::{
+        BuiltInSearchEngine, MeiliSearchEngine, SearchEngine, SearchResults, TantivySearchEngine,
+    },
+    smart_playlist::{self, SmartPlaylist, SmartPlaylistCreate, SmartPlaylistRules},
+    stats::{self, Stats, StatsParams},
     subscription,
+    sweep::{self, SweepParams, SweepReport},
     track::{self, TrackListRandom},
-    user, Album, AlbumCreate, AlbumId, AlbumUpdate, Artist, ArtistCreate, ArtistId, ArtistMetadata,
-    ArtistMetadataRequest, ArtistUpdate, Audio, AudioCreate, AudioDownload, AudioId, AudioStat,
-    ByteRange, Error, ErrorKind, ExternalMediaRequest, ExternalMediaType, Favorite, Genre, Genres,
-    ImageCreate, ImageDownload, ImageId, Import, ListParams, Lyrics, MetadataFetchMask,
-    MetadataFetchParams, Playlist, PlaylistCreate, PlaylistId, PlaylistTrack, PlaylistUpdate,
-    Properties, PropertyKey, PropertyUpdate, Result, Scrobble, ScrobbleCreate, ScrobbleId,
-    ScrobbleUpdate, SearchQuery, SonarId, Subscription, SubscriptionCreate, SubscriptionId, Track,
-    TrackCreate, TrackId, TrackMetadata, TrackMetadataRequest, TrackUpdate, User, UserCreate,
-    UserId, UserToken, UserUpdate, Username, ValueUpdate, METADATA_FETCH_MASK_COVER,
-    METADATA_FETCH_MASK_GENRES, METADATA_FETCH_MASK_NAME, METADATA_FETCH_MASK_PROPERTIES,
+    user,
+    webhook::{self, Webhook, WebhookCreate, WebhookDelivery, WebhookUpdate},
+    Album, AlbumCreate, AlbumId, AlbumUpdate, ApiKey, ApiKeyCreate, ApiKeyId, Artist, ArtistCreate,
+    ArtistId, ArtistMetadata, ArtistMetadataRequest, ArtistUpdate, Audio, AudioCreate,
+    AudioDownload, AudioId, AudioStat, AverageRating, Bookmark, BookmarkCreate, ByteRange, Error,
+    ErrorKind, ExternalMediaRequest, ExternalMediaType, Favorite, Genre, Genres, ImageCreate,
+    ImageDownload, ImageId, Import, LibraryId, ListParams, Lyrics, MetadataFetchMask,
+    MetadataFetchParams, PlayQueue, PlayQueueSave, Playlist, PlaylistCreate, PlaylistId,
+    PlaylistTrack, PlaylistUpdate, Properties, PropertyKey, PropertyUpdate, Radio, RadioCreate,
+    RadioId, Rating, Result, Scrobble, ScrobbleCreate, ScrobbleId, ScrobbleUpdate, SearchQuery,
+    SonarId, Subscription, SubscriptionCreate, SubscriptionId, Track, TrackCreate, TrackId,
+    TrackMetadata, TrackMetadataRequest, TrackUpdate, User, UserCreate, UserId, UserLoginParams,
+    UserRating, UserSession, UserSessionId, UserToken, UserUpdate, Username, ValueUpdate,
+    WebhookId, METADATA_FETCH_MASK_COVER, METADATA_FETCH_MASK_GENRES, METADATA_FETCH_MASK_NAME,
+    METADATA_FETCH_MASK_PROPERTIES,
 };
 
 mod memory_indexes;
 use memory_indexes::*;
 
+mod daily_mix_process;
+mod gc_process;
 mod playlist_cover_process;
 mod scrobbler_process;
+mod smart_playlist_process;
 mod subscription_process;
+mod webhook_process;
 
 #[derive(Debug, Default, Clone)]
 pub enum StorageBackend {
@@ -65,6 +86,9 @@ pub enum SearchBackend {
         endpoint: String,
         key: String,
     },
+    Tantivy {
+        path: PathBuf,
+    },
 }
 
 #[derive(Debug)]
@@ -78,6 +102,10 @@ pub struct Config {
     external: Vec<ExternalServicesEntry>,
     max_import_size: usize,
     max_parallel_imports: usize,
+    garbage_collection: Option<(Duration, GarbageCollectParams)>,
+    smart_playlist_refresh_interval: Duration,
+    daily_mix_refresh_interval: Duration,
+    webhook_retry_delay: Duration,
 }
 
 impl Config {
@@ -96,9 +124,33 @@ impl Config {
             external: Vec::new(),
             max_import_size: 1024 * 1024 * 1024,
             max_parallel_imports: 8,
+            garbage_collection: None,
+            smart_playlist_refresh_interval: Duration::from_secs(15 * 60),
+            daily_mix_refresh_interval: Duration::from_secs(24 * 60 * 60),
+            webhook_retry_delay: Duration::from_secs(30),
         }
     }
 
+    /// periodically run garbage collection with the given parameters, followed by an orphan sweep.
+    pub fn enable_garbage_collection(&mut self, interval: Duration, params: GarbageCollectParams) {
+        self.garbage_collection = Some((interval, params));
+    }
+
+    /// how often the tracks of smart playlists are regenerated from their rules.
+    pub fn set_smart_playlist_refresh_interval(&mut self, interval: Duration) {
+        self.smart_playlist_refresh_interval = interval;
+    }
+
+    /// how often the daily mix playlists of every user are regenerated.
+    pub fn set_daily_mix_refresh_interval(&mut self, interval: Duration) {
+        self.daily_mix_refresh_interval = interval;
+    }
+
+    /// how long to wait before retrying a failed webhook delivery, doubled after every attempt.
+    pub fn set_webhook_retry_delay(&mut self, delay: Duration) {
+        self.webhook_retry_delay = delay;
+    }
+
     pub fn register_extractor(
         &mut self,
         name: impl Into<String>,
@@ -191,8 +243,7 @@ impl Config {
 #[derive(Debug, Clone)]
 pub struct Context {
     pub(crate) db: Db,
-    tokens: Arc<Mutex<HashMap<UserToken, UserId>>>,
-    storage: Arc<dyn BlobStorage>,
+    pub(crate) storage: Arc<dyn BlobStorage>,
     importer: Arc<Importer>,
     search: Arc<dyn SearchEngine>,
     extractors: Arc<Vec<SonarExtractor>>,
@@ -201,6 +252,7 @@ pub struct Context {
     external: ExternalServices,
     scrobbler_notify: Arc<Notify>,
     memory_indexes: Arc<Mutex<MemoryIndexes>>,
+    events: EventBus,
 }
 
 pub async fn new(config: Config) -> Result<Context> {
@@ -218,14 +270,7 @@ pub async fn new(config: Config) -> Result<Context> {
         .map_err(|e| Error::with_source(ErrorKind::Internal, "failed to connect to database", e))?;
     migrations::run(&db).await?;
 
-    let storage = match config.storage_backend {
-        StorageBackend::Memory => {
-            Arc::new(blob::MemoryBlobStorage::default()) as Arc<dyn BlobStorage>
-        }
-        StorageBackend::Filesystem { ref path } => {
-            Arc::new(blob::FilesystemBlobStorage::new(path.clone())) as Arc<dyn BlobStorage>
-        }
-    };
+    let storage = create_storage(&config.storage_backend);
 
     let importer = importer::new(importer::Config {
         max_import_size: config.max_import_size,
@@ -240,11 +285,13 @@ pub async fn new(config: Config) -> Result<Context> {
             Arc::new(MeiliSearchEngine::new(db.clone(), endpoint, key).await?)
                 as Arc<dyn SearchEngine>
         }
+        SearchBackend::Tantivy { path } => {
+            Arc::new(TantivySearchEngine::new(db.clone(), path)?) as Arc<dyn SearchEngine>
+        }
     };
 
     let context = Context {
         db,
-        tokens: Default::default(),
         storage,
         importer: Arc::new(importer),
         search: search_engine,
@@ -254,6 +301,7 @@ pub async fn new(config: Config) -> Result<Context> {
         external: ExternalServices::new(config.external),
         scrobbler_notify: Arc::new(Notify::new()),
         memory_indexes: Default::default(),
+        events: Default::default(),
     };
 
     for scrobbler in context.scrobblers.iter().cloned() {
@@ -280,6 +328,33 @@ pub async fn new(config: Config) -> Result<Context> {
         async move { update_listen_counts(&context).await }
     });
 
+    tokio::spawn({
+        let context = context.clone();
+        let interval = config.smart_playlist_refresh_interval;
+        async move { smart_playlist_process::run(&context, interval).await }
+    });
+
+    tokio::spawn({
+        let context = context.clone();
+        let interval = config.daily_mix_refresh_interval;
+        async move { daily_mix_process::run(&context, interval).await }
+    });
+
+    tokio::spawn({
+        let context = context.clone();
+        // subscribe before returning so no events are missed
+        let subscription = context.events.subscribe(Default::default())?;
+        let retry_delay = config.webhook_retry_delay;
+        async move { webhook_process::run(&context, subscription, retry_delay).await }
+    });
+
+    if let Some((interval, params)) = config.garbage_collection {
+        tokio::spawn({
+            let context = context.clone();
+            async move { gc_process::run(&context, interval, params).await }
+        });
+    }
+
     memory_indexes_rebuild(&context).await;
     context.search.synchronize_all().await;
 
@@ -288,6 +363,29 @@ pub async fn new(config: Config) -> Result<Context> {
     Ok(context)
 }
 
+fn create_storage(backend: &StorageBackend) -> Arc<dyn BlobStorage> {
+    match backend {
+        StorageBackend::Memory => {
+            Arc::new(blob::MemoryBlobStorage::default()) as Arc<dyn BlobStorage>
+        }
+        StorageBackend::Filesystem { path } => {
+            Arc::new(blob::FilesystemBlobStorage::new(path.clone())) as Arc<dyn BlobStorage>
+        }
+    }
+}
+
+/// restore a backup created with [`backup_create`].
+/// the database file must not exist and the storage should be empty.
+#[tracing::instrument]
+pub async fn backup_restore(
+    archive: &Path,
+    database_path: &Path,
+    storage_backend: StorageBackend,
+) -> Result<RestoreReport> {
+    let storage = create_storage(&storage_backend);
+    backup::restore(archive, database_path, &*storage).await
+}
+
 #[tracing::instrument(skip(context))]
 pub async fn user_list(context: &Context, params: ListParams) -> Result<Vec<User>> {
     let mut conn = context.db.acquire().await?;
@@ -345,31 +443,118 @@ pub async fn user_login(
     context: &Context,
     username: &Username,
     password: &str,
+    params: UserLoginParams,
 ) -> Result<(UserId, UserToken)> {
     let user_id = user_authenticate(context, username, password).await?;
     let token = UserToken::random();
-    context
-        .tokens
-        .lock()
-        .unwrap()
-        .insert(token.clone(), user_id);
+    let mut tx = context.db.begin().await?;
+    user::session_create(&mut tx, user_id, &token, params).await?;
+    tx.commit().await?;
     Ok((user_id, token))
 }
 
 #[tracing::instrument(skip(context))]
 pub async fn user_logout(context: &Context, token: &UserToken) -> Result<()> {
-    context.tokens.lock().unwrap().remove(token);
+    let mut tx = context.db.begin().await?;
+    user::session_delete_by_token(&mut tx, token).await?;
+    tx.commit().await?;
     Ok(())
 }
 
 #[tracing::instrument(skip(context))]
 pub async fn user_validate_token(context: &Context, token: &UserToken) -> Result<UserId> {
-    let tokens = context.tokens.lock().unwrap();
-    if let Some(user_id) = tokens.get(token) {
-        Ok(*user_id)
-    } else {
-        Err(Error::new(ErrorKind::Unauthorized, "invalid user token"))
-    }
+    let mut tx = context.db.begin().await?;
+    let result = user::session_validate(&mut tx, token).await;
+    tx.commit().await?;
+    result
+}
+
+#[tracing::instrument(skip(context))]
+pub async fn user_session_list(context: &Context, user_id: UserId) -> Result<Vec<UserSession>> {
+    let mut conn = context.db.acquire().await?;
+    user::session_list(&mut conn, user_id).await
+}
+
+#[tracing::instrument(skip(context))]
+pub async fn user_session_get(context: &Context, session_id: UserSessionId) -> Result<UserSession> {
+    let mut conn = context.db.acquire().await?;
+    user::session_get(&mut conn, session_id).await
+}
+
+#[tracing::instrument(skip(context))]
+pub async fn user_session_revoke(context: &Context, session_id: UserSessionId) -> Result<()> {
+    let mut tx = context.db.begin().await?;
+    user::session_delete(&mut tx, session_id).await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+#[tracing::instrument(skip(context))]
+pub async fn user_api_key_list(context: &Context, user_id: UserId) -> Result<Vec<ApiKey>> {
+    let mut conn = context.db.acquire().await?;
+    user::api_key_list(&mut conn, user_id).await
+}
+
+#[tracing::instrument(skip(context))]
+pub async fn user_api_key_get(context: &Context, key_id: ApiKeyId) -> Result<ApiKey> {
+    let mut conn = context.db.acquire().await?;
+    user::api_key_get(&mut conn, key_id).await
+}
+
+#[tracing::instrument(skip(context))]
+pub async fn user_api_key_create(
+    context: &Context,
+    create: ApiKeyCreate,
+) -> Result<(ApiKey, String)> {
+    let mut tx = context.db.begin().await?;
+    let result = user::api_key_create(&mut tx, create).await?;
+    tx.commit().await?;
+    Ok(result)
+}
+
+#[tracing::instrument(skip(context))]
+pub async fn user_api_key_revoke(context: &Context, key_id: ApiKeyId) -> Result<()> {
+    let mut tx = context.db.begin().await?;
+    user::api_key_delete(&mut tx, key_id).await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+#[tracing::instrument(skip(context, key))]
+pub async fn user_api_key_validate(context: &Context, key: &str) -> Result<ApiKey> {
+    let mut tx = context.db.begin().await?;
+    let result = user::api_key_validate(&mut tx, key).await?;
+    tx.commit().await?;
+    Ok(result)
+}
+
+#[tracing::instrument(skip(context))]
+pub async fn user_subsonic_password_enabled(context: &Context, user_id: UserId) -> Result<bool> {
+    let mut conn = context.db.acquire().await?;
+    user::subsonic_password_enabled(&mut conn, user_id).await
+}
+
+#[tracing::instrument(skip(context, password))]
+pub async fn user_subsonic_password_set(
+    context: &Context,
+    user_id: UserId,
+    password: Option<&str>,
+) -> Result<()> {
+    let mut tx = context.db.begin().await?;
+    user::subsonic_password_set(&mut tx, user_id, password).await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+#[tracing::instrument(skip(context, token, salt))]
+pub async fn user_subsonic_authenticate(
+    context: &Context,
+    user_id: UserId,
+    token: &str,
+    salt: &str,
+) -> Result<()> {
+    let mut conn = context.db.acquire().await?;
+    user::subsonic_authenticate(&mut conn, user_id, token, salt).await
 }
 
 #[tracing::instrument(skip(context))]
@@ -426,8 +611,9 @@ pub async fn image_create(context: &Context, create: ImageCreate) -> Result<Imag
 #[tracing::instrument(skip(context))]
 pub async fn image_delete(context: &Context, image_id: ImageId) -> Result<()> {
     let mut tx = context.db.begin().await?;
-    image::delete(&mut tx, image_id).await?;
+    let key = image::delete(&mut tx, image_id).await?;
     tx.commit().await?;
+    blob::delete_keys(&*context.storage, key).await;
     Ok(())
 }
 
@@ -437,10 +623,32 @@ pub async fn image_download(context: &Context, image_id: ImageId) -> Result<Imag
     image::download(&mut conn, &*context.storage, image_id).await
 }
 
+/// Verifies every blob in storage against the size and hash recorded in the database.
+/// `progress` is called after each blob is checked and for every unreferenced file.
+#[tracing::instrument(skip(context, progress))]
+pub async fn blob_fsck(
+    context: &Context,
+    params: FsckParams,
+    progress: &(dyn Fn(&FsckProgress) + Send + Sync),
+) -> Result<FsckReport> {
+    let mut conn = context.db.acquire().await?;
+    fsck::run(&mut conn, &*context.storage, params, progress).await
+}
+
 #[tracing::instrument(skip(context))]
 pub async fn artist_list(context: &Context, params: ListParams) -> Result<Vec<Artist>> {
+    artist_list_filtered(context, Default::default(), params).await
+}
+
+#[tracing::instrument(skip(context))]
+pub async fn artist_list_filtered(
+    context: &Context,
+    filter: LibraryFilter,
+    params: ListParams,
+) -> Result<Vec<Artist>> {
     let mut conn = context.db.acquire().await?;
-    artist::list(&mut conn, params).await
+    let libraries = library::resolve(&mut conn, filter).await?;
+    artist::list(&mut conn, libraries.as_deref(), params).await
 }
 
 #[tracing::instrument(skip(context))]
@@ -465,7 +673,7 @@ pub async fn artist_create(context: &Context, create: ArtistCreate) -> Result<Ar
     let mut tx = context.db.begin().await?;
     let result = artist::create(&mut tx, create).await?;
     tx.commit().await?;
-    on_artist_crud(context, result.id).await;
+    on_artist_crud(context, result.id, EventKind::ArtistCreated).await;
     Ok(result)
 }
 
@@ -478,7 +686,7 @@ pub async fn artist_update(
     let mut tx = context.db.begin().await?;
     let result = artist::update(&mut tx, id, update).await?;
     tx.commit().await?;
-    on_artist_crud(context, id).await;
+    on_artist_crud(context, id, EventKind::ArtistUpdated).await;
     Ok(result)
 }
 
@@ -487,7 +695,7 @@ pub async fn artist_delete(context: &Context, id: ArtistId) -> Result<()> {
     let mut tx = context.db.begin().await?;
     artist::delete(&mut tx, id).await?;
     tx.commit().await?;
-    on_artist_crud(context, id).await;
+    on_artist_crud(context, id, EventKind::ArtistDeleted).await;
     Ok(())
 }
 
@@ -504,8 +712,18 @@ pub async fn artist_find_or_create_by_name(
 
 #[tracing::instrument(skip(context))]
 pub async fn album_list(context: &Context, params: ListParams) -> Result<Vec<Album>> {
+    album_list_filtered(context, Default::default(), params).await
+}
+
+#[tracing::instrument(skip(context))]
+pub async fn album_list_filtered(
+    context: &Context,
+    filter: LibraryFilter,
+    params: ListParams,
+) -> Result<Vec<Album>> {
     let mut conn = context.db.acquire().await?;
-    album::list(&mut conn, params).await
+    let libraries = library::resolve(&mut conn, filter).await?;
+    album::list(&mut conn, libraries.as_deref(), params).await
 }
 
 #[tracing::instrument(skip(context))]
@@ -513,9 +731,36 @@ pub async fn album_list_by_artist(
     context: &Context,
     artist_id: ArtistId,
     params: ListParams,
+) -> Result<Vec<Album>> {
+    album_list_by_artist_filtered(context, Default::default(), artist_id, params).await
+}
+
+#[tracing::instrument(skip(context))]
+pub async fn album_list_by_artist_filtered(
+    context: &Context,
+    filter: LibraryFilter,
+    artist_id: ArtistId,
+    params: ListParams,
+) -> Result<Vec<Album>> {
+    let mut conn = context.db.acquire().await?;
+    let libraries = library::resolve(&mut conn, filter).await?;
+    album::list_by_artist(&mut conn, libraries.as_deref(), artist_id, params).await
+}
+
+#[tracing::instrument(skip(context))]
+pub async fn album_list_highest_rated(context: &Context, params: ListParams) -> Result<Vec<Album>> {
+    album_list_highest_rated_filtered(context, Default::default(), params).await
+}
+
+#[tracing::instrument(skip(context))]
+pub async fn album_list_highest_rated_filtered(
+    context: &Context,
+    filter: LibraryFilter,
+    params: ListParams,
 ) -> Result<Vec<Album>> {
     let mut conn = context.db.acquire().await?;
-    album::list_by_artist(&mut conn, artist_id, params).await
+    let libraries = library::resolve(&mut conn, filter).await?;
+    album::list_highest_rated(&mut conn, libraries.as_deref(), params).await
 }
 
 #[tracing::instrument(skip(context))]
@@ -524,9 +769,36 @@ pub async fn album_list_by_genre(
     genre: &Genre,
     params: ListParams,
 ) -> Result<Vec<Album>> {
+    album_list_by_genre_filtered(context, Default::default(), genre, params).await
+}
+
+#[tracing::instrument(skip(context))]
+pub async fn album_list_by_genre_filtered(
+    context: &Context,
+    filter: LibraryFilter,
+    genre: &Genre,
+    params: ListParams,
+) -> Result<Vec<Album>> {
+    let libraries = {
+        let mut conn = context.db.acquire().await?;
+        library::resolve(&mut conn, filter).await?
+    };
     let indexes = clone_memory_indexes(context);
-    let album_ids = indexes.genres().list_albums_by_genre(genre, params);
-    album_get_bulk(context, &album_ids).await
+    let Some(libraries) = libraries else {
+        let album_ids = indexes.genres().list_albums_by_genre(genre, params);
+        return album_get_bulk(context, &album_ids).await;
+    };
+    // the genre index does not know about libraries so the page is taken after filtering
+    let album_ids = indexes
+        .genres()
+        .list_albums_by_genre(genre, Default::default());
+    let albums = album_get_bulk(context, &album_ids).await?;
+    Ok(albums
+        .into_iter()
+        .filter(|album| libraries.contains(&album.library))
+        .skip(params.offset.unwrap_or(0) as usize)
+        .take(params.limit.unwrap_or(u32::MAX) as usize)
+        .collect())
 }
 
 #[tracing::instrument(skip(context))]
@@ -552,7 +824,7 @@ pub async fn album_create(context: &Context, create: AlbumCreate) -> Result<Albu
     let mut tx = context.db.begin().await?;
     let result = album::create(&mut tx, create).await?;
     tx.commit().await?;
-    on_album_crud(context, result.id).await;
+    on_album_crud(context, result.id, EventKind::AlbumCreated).await;
     Ok(result)
 }
 
@@ -561,7 +833,7 @@ pub async fn album_update(context: &Context, id: AlbumId, update: AlbumUpdate) -
     let mut tx = context.db.begin().await?;
     let result = album::update(&mut tx, id, update).await?;
     tx.commit().await?;
-    on_album_crud(context, id).await;
+    on_album_crud(context, id, EventKind::AlbumUpdated).await;
     Ok(result)
 }
 
@@ -570,7 +842,7 @@ pub async fn album_delete(context: &Context, id: AlbumId) -> Result<()> {
     let mut tx = context.db.begin().await?;
     album::delete(&mut tx, id).await?;
     tx.commit().await?;
-    on_album_crud(context, id).await;
+    on_album_crud(context, id, EventKind::AlbumDeleted).await;
     Ok(())
 }
 
@@ -584,8 +856,18 @@ pub async fn album_find_or_create_by_name(context: &Context, create: AlbumCreate
 
 #[tracing::instrument(skip(context))]
 pub async fn track_list(context: &Context, params: ListParams) -> Result<Vec<Track>> {
+    track_list_filtered(context, Default::default(), params).await
+}
+
+#[tracing::instrument(skip(context))]
+pub async fn track_list_filtered(
+    context: &Context,
+    filter: LibraryFilter,
+    params: ListParams,
+) -> Result<Vec<Track>> {
     let mut conn = context.db.acquire().await?;
-    track::list(&mut conn, params).await
+    let libraries = library::resolve(&mut conn, filter).await?;
+    track::list(&mut conn, libraries.as_deref(), params).await
 }
 
 #[tracing::instrument(skip(context))]
@@ -593,15 +875,36 @@ pub async fn track_list_by_album(
     context: &Context,
     album_id: AlbumId,
     params: ListParams,
+) -> Result<Vec<Track>> {
+    track_list_by_album_filtered(context, Default::default(), album_id, params).await
+}
+
+#[tracing::instrument(skip(context))]
+pub async fn track_list_by_album_filtered(
+    context: &Context,
+    filter: LibraryFilter,
+    album_id: AlbumId,
+    params: ListParams,
 ) -> Result<Vec<Track>> {
     let mut conn = context.db.acquire().await?;
-    track::list_by_album(&mut conn, album_id, params).await
+    let libraries = library::resolve(&mut conn, filter).await?;
+    track::list_by_album(&mut conn, libraries.as_deref(), album_id, params).await
 }
 
 #[tracing::instrument(skip(context))]
 pub async fn track_list_random(context: &Context, params: TrackListRandom) -> Result<Vec<Track>> {
+    track_list_random_filtered(context, Default::default(), params).await
+}
+
+#[tracing::instrument(skip(context))]
+pub async fn track_list_random_filtered(
+    context: &Context,
+    filter: LibraryFilter,
+    params: TrackListRandom,
+) -> Result<Vec<Track>> {
     let mut conn = context.db.acquire().await?;
-    track::list_random(&mut conn, params).await
+    let libraries = library::resolve(&mut conn, filter).await?;
+    track::list_random(&mut conn, libraries.as_deref(), params).await
 }
 
 pub async fn track_list_top_from_artist(
@@ -635,7 +938,7 @@ pub async fn track_create(context: &Context, create: TrackCreate) -> Result<Trac
     let mut tx = context.db.begin().await?;
     let result = track::create(&mut tx, create).await?;
     tx.commit().await?;
-    on_track_crud(context, result.id).await;
+    on_track_crud(context, result.id, EventKind::TrackCreated).await;
     Ok(result)
 }
 
@@ -644,7 +947,7 @@ pub async fn track_update(context: &Context, id: TrackId, update: TrackUpdate) -
     let mut tx = context.db.begin().await?;
     let result = track::update(&mut tx, id, update).await?;
     tx.commit().await?;
-    on_track_crud(context, id).await;
+    on_track_crud(context, id, EventKind::TrackUpdated).await;
     Ok(result)
 }
 
@@ -653,7 +956,7 @@ pub async fn track_delete(context: &Context, id: TrackId) -> Result<()> {
     let mut tx = context.db.begin().await?;
     track::delete(&mut tx, id).await?;
     tx.commit().await?;
-    on_track_crud(context, id).await;
+    on_track_crud(context, id, EventKind::TrackDeleted).await;
     Ok(())
 }
 
@@ -716,8 +1019,9 @@ pub async fn audio_create(context: &Context, create: AudioCreate) -> Result<Audi
 #[tracing::instrument(skip(context))]
 pub async fn audio_delete(context: &Context, audio_id: AudioId) -> Result<()> {
     let mut tx = context.db.begin().await?;
-    audio::delete(&mut tx, audio_id).await?;
+    let key = audio::delete(&mut tx, audio_id).await?;
     tx.commit().await?;
+    blob::delete_keys(&*context.storage, key).await;
     Ok(())
 }
 
@@ -786,7 +1090,7 @@ pub async fn playlist_create(context: &Context, create: PlaylistCreate) -> Resul
     let mut tx = context.db.begin().await?;
     let result = playlist::create(&mut tx, create).await?;
     tx.commit().await?;
-    on_playlist_crud(context, result.id).await;
+    on_playlist_crud(context, result.id, result.owner, EventKind::PlaylistCreated).await;
     Ok(result)
 }
 
@@ -799,7 +1103,7 @@ pub async fn playlist_duplicate(
     let mut tx = context.db.begin().await?;
     let result = playlist::duplicate(&mut tx, playlist_id, new_name).await?;
     tx.commit().await?;
-    on_playlist_crud(context, result.id).await;
+    on_playlist_crud(context, result.id, result.owner, EventKind::PlaylistCreated).await;
     Ok(result)
 }
 
@@ -812,16 +1116,17 @@ pub async fn playlist_update(
     let mut tx = context.db.begin().await?;
     let result = playlist::update(&mut tx, id, update).await?;
     tx.commit().await?;
-    on_playlist_crud(context, id).await;
+    on_playlist_crud(context, id, result.owner, EventKind::PlaylistUpdated).await;
     Ok(result)
 }
 
 #[tracing::instrument(skip(context))]
 pub async fn playlist_delete(context: &Context, id: PlaylistId) -> Result<()> {
     let mut tx = context.db.begin().await?;
+    let owner = playlist::get_owner(&mut tx, id).await?;
     playlist::delete(&mut tx, id).await?;
     tx.commit().await?;
-    on_playlist_crud(context, id).await;
+    on_playlist_crud(context, id, owner, EventKind::PlaylistDeleted).await;
     Ok(())
 }
 
@@ -838,8 +1143,13 @@ pub async fn playlist_list_tracks(
 #[tracing::instrument(skip(context))]
 pub async fn playlist_clear_tracks(context: &Context, id: PlaylistId) -> Result<()> {
     let mut tx = context.db.begin().await?;
+    let owner = playlist::get_owner(&mut tx, id).await?;
+    smart_playlist::ensure_editable(&mut tx, id).await?;
     playlist::clear_tracks(&mut tx, id).await?;
     tx.commit().await?;
+    context
+        .events
+        .publish(EventKind::PlaylistUpdated, id, Some(owner));
     Ok(())
 }
 
@@ -850,129 +1160,645 @@ pub async fn playlist_insert_tracks(
     tracks: &[TrackId],
 ) -> Result<()> {
     let mut tx = context.db.begin().await?;
+    let owner = playlist::get_owner(&mut tx, id).await?;
+    smart_playlist::ensure_editable(&mut tx, id).await?;
     playlist::insert_tracks(&mut tx, id, tracks).await?;
     tx.commit().await?;
+    context
+        .events
+        .publish(EventKind::PlaylistUpdated, id, Some(owner));
     Ok(())
 }
 
 #[tracing::instrument(skip(context))]
-pub async fn playlist_remove_tracks(
+pub async fn playlist_insert_tracks_at(
     context: &Context,
     id: PlaylistId,
+    position: u32,
     tracks: &[TrackId],
 ) -> Result<()> {
     let mut tx = context.db.begin().await?;
-    playlist::remove_tracks(&mut tx, id, tracks).await?;
+    let owner = playlist::get_owner(&mut tx, id).await?;
+    smart_playlist::ensure_editable(&mut tx, id).await?;
+    playlist::insert_tracks_at(&mut tx, id, position, tracks).await?;
     tx.commit().await?;
+    context
+        .events
+        .publish(EventKind::PlaylistUpdated, id, Some(owner));
     Ok(())
 }
 
 #[tracing::instrument(skip(context))]
-pub async fn playlist_generate_cover(context: &Context, id: PlaylistId) -> Result<()> {
+pub async fn playlist_move_tracks(
+    context: &Context,
+    id: PlaylistId,
+    from: u32,
+    count: u32,
+    to: u32,
+) -> Result<()> {
     let mut tx = context.db.begin().await?;
-    playlist::generate_cover(&mut tx, &*context.storage, id).await?;
+    let owner = playlist::get_owner(&mut tx, id).await?;
+    smart_playlist::ensure_editable(&mut tx, id).await?;
+    playlist::move_tracks(&mut tx, id, from, count, to).await?;
     tx.commit().await?;
+    context
+        .events
+        .publish(EventKind::PlaylistUpdated, id, Some(owner));
     Ok(())
 }
 
 #[tracing::instrument(skip(context))]
-pub async fn genre_list(context: &Context) -> Result<Vec<GenreStats>> {
-    let indexes = clone_memory_indexes(context);
-    Ok(indexes.genres().list_genres())
-}
-
-#[tracing::instrument(skip(context))]
-pub async fn favorite_list(context: &Context, user_id: UserId) -> Result<Vec<Favorite>> {
-    let mut conn = context.db.acquire().await?;
-    favorite::user_list(&mut conn, user_id).await
-}
-
-#[tracing::instrument(skip(context))]
-pub async fn favorite_get_bulk(
+pub async fn playlist_remove_at(
     context: &Context,
-    user_id: UserId,
-    ids: &[SonarId],
-) -> Result<Vec<Favorite>> {
-    let mut conn = context.db.acquire().await?;
-    favorite::user_get_bulk(&mut conn, user_id, ids).await
+    id: PlaylistId,
+    positions: &[u32],
+) -> Result<()> {
+    let mut tx = context.db.begin().await?;
+    let owner = playlist::get_owner(&mut tx, id).await?;
+    smart_playlist::ensure_editable(&mut tx, id).await?;
+    playlist::remove_at(&mut tx, id, positions).await?;
+    tx.commit().await?;
+    context
+        .events
+        .publish(EventKind::PlaylistUpdated, id, Some(owner));
+    Ok(())
 }
 
 #[tracing::instrument(skip(context))]
-pub async fn favorite_find(
+pub async fn playlist_remove_tracks(
     context: &Context,
-    user_id: UserId,
-    id: SonarId,
-) -> Result<Option<Favorite>> {
-    let mut conn = context.db.acquire().await?;
-    favorite::user_find(&mut conn, user_id, id).await
-}
-
-#[tracing::instrument(skip(context))]
-pub async fn favorite_add(context: &Context, user_id: UserId, id: SonarId) -> Result<()> {
+    id: PlaylistId,
+    tracks: &[TrackId],
+) -> Result<()> {
     let mut tx = context.db.begin().await?;
-    favorite::user_put(&mut tx, user_id, id).await?;
+    let owner = playlist::get_owner(&mut tx, id).await?;
+    smart_playlist::ensure_editable(&mut tx, id).await?;
+    playlist::remove_tracks(&mut tx, id, tracks).await?;
     tx.commit().await?;
+    context
+        .events
+        .publish(EventKind::PlaylistUpdated, id, Some(owner));
     Ok(())
 }
 
 #[tracing::instrument(skip(context))]
-pub async fn favorite_remove(context: &Context, user_id: UserId, id: SonarId) -> Result<()> {
+pub async fn playlist_generate_cover(context: &Context, id: PlaylistId) -> Result<()> {
     let mut tx = context.db.begin().await?;
-    favorite::user_remove(&mut tx, user_id, id).await?;
+    playlist::generate_cover(&mut tx, &*context.storage, id).await?;
     tx.commit().await?;
     Ok(())
 }
 
 #[tracing::instrument(skip(context))]
-pub async fn search(
-    context: &Context,
-    user_id: UserId,
-    query: SearchQuery,
-) -> Result<SearchResults> {
-    context.search.search(user_id, &query).await
-}
-
-#[tracing::instrument(skip(context))]
-pub async fn scrobble_list(context: &Context, params: ListParams) -> Result<Vec<Scrobble>> {
-    let mut conn = context.db.acquire().await?;
-    scrobble::list(&mut conn, params).await
-}
-
-#[tracing::instrument(skip(context))]
-pub async fn scrobble_get(context: &Context, scrobble_id: ScrobbleId) -> Result<Scrobble> {
+pub async fn smart_playlist_get(context: &Context, id: PlaylistId) -> Result<SmartPlaylist> {
     let mut conn = context.db.acquire().await?;
-    scrobble::get(&mut conn, scrobble_id).await
+    smart_playlist::get(&mut conn, id).await
 }
 
 #[tracing::instrument(skip(context))]
-pub async fn scrobble_create(context: &Context, create: ScrobbleCreate) -> Result<Scrobble> {
+pub async fn smart_playlist_create(
+    context: &Context,
+    create: SmartPlaylistCreate,
+) -> Result<Playlist> {
     let mut tx = context.db.begin().await?;
-    let result = scrobble::create(&mut tx, create).await?;
+    let result = smart_playlist::create(&mut tx, create).await?;
     tx.commit().await?;
-    context.scrobbler_notify.notify_waiters();
+    on_playlist_crud(context, result.id, result.owner, EventKind::PlaylistCreated).await;
     Ok(result)
 }
 
 #[tracing::instrument(skip(context))]
-pub async fn scrobble_update(
+pub async fn smart_playlist_update(
     context: &Context,
-    id: ScrobbleId,
-    update: ScrobbleUpdate,
-) -> Result<Scrobble> {
+    id: PlaylistId,
+    rules: SmartPlaylistRules,
+) -> Result<Playlist> {
     let mut tx = context.db.begin().await?;
-    let result = scrobble::update(&mut tx, id, update).await?;
+    let result = smart_playlist::update(&mut tx, id, rules).await?;
     tx.commit().await?;
+    on_playlist_crud(context, id, result.owner, EventKind::PlaylistUpdated).await;
+    Ok(result)
+}
+
+#[tracing::instrument(skip(context))]
+pub async fn smart_playlist_refresh(context: &Context, id: PlaylistId) -> Result<Playlist> {
+    let mut tx = context.db.begin().await?;
+    let result = smart_playlist::refresh(&mut tx, id).await?;
+    tx.commit().await?;
+    context
+        .events
+        .publish(EventKind::PlaylistUpdated, id, Some(result.owner));
+    Ok(result)
+}
+
+#[tracing::instrument(skip(context))]
+pub(crate) async fn smart_playlist_list_ids(context: &Context) -> Result<Vec<PlaylistId>> {
+    let mut conn = context.db.acquire().await?;
+    smart_playlist::list_ids(&mut conn).await
+}
+
+#[tracing::instrument(skip(context))]
+pub async fn recommend_similar_tracks(
+    context: &Context,
+    id: SonarId,
+    limit: u32,
+) -> Result<Vec<TrackId>> {
+    recommend_similar_tracks_filtered(context, Default::default(), id, limit).await
+}
+
+#[tracing::instrument(skip(context))]
+pub async fn recommend_similar_tracks_filtered(
+    context: &Context,
+    filter: LibraryFilter,
+    id: SonarId,
+    limit: u32,
+) -> Result<Vec<TrackId>> {
+    let mut conn = context.db.acquire().await?;
+    let libraries = library::resolve(&mut conn, filter).await?;
+    recommend::similar_tracks(&mut conn, libraries.as_deref(), id, limit).await
+}
+
+#[tracing::instrument(skip(context))]
+pub async fn recommend_similar_artists(
+    context: &Context,
+    id: ArtistId,
+    limit: u32,
+) -> Result<Vec<ArtistId>> {
+    recommend_similar_artists_filtered(context, Default::default(), id, limit).await
+}
+
+#[tracing::instrument(skip(context))]
+pub async fn recommend_similar_artists_filtered(
+    context: &Context,
+    filter: LibraryFilter,
+    id: ArtistId,
+    limit: u32,
+) -> Result<Vec<ArtistId>> {
+    let mut conn = context.db.acquire().await?;
+    let libraries = library::resolve(&mut conn, filter).await?;
+    recommend::similar_artists(&mut conn, libraries.as_deref(), id, limit).await
+}
+
+#[tracing::instrument(skip(context))]
+pub async fn recommend_daily_mixes(
+    context: &Context,
+    params: DailyMixParams,
+) -> Result<Vec<DailyMix>> {
+    let mut conn = context.db.acquire().await?;
+    recommend::daily_mixes(&mut conn, params).await
+}
+
+#[tracing::instrument(skip(context))]
+pub async fn recommend_daily_mix_refresh(
+    context: &Context,
+    params: DailyMixParams,
+) -> Result<Vec<Playlist>> {
+    let owner = params.user;
+    let mut tx = context.db.begin().await?;
+    let (playlists, deleted) = recommend::refresh_daily_mix_playlists(&mut tx, params).await?;
+    tx.commit().await?;
+    for playlist in playlists.iter() {
+        on_playlist_crud(context, playlist.id, owner, EventKind::PlaylistUpdated).await;
+    }
+    for playlist_id in deleted {
+        on_playlist_crud(context, playlist_id, owner, EventKind::PlaylistDeleted).await;
+    }
+    Ok(playlists)
+}
+
+#[tracing::instrument(skip(context))]
+pub async fn radio_get(context: &Context, id: RadioId) -> Result<Radio> {
+    let mut conn = context.db.acquire().await?;
+    radio::get(&mut conn, id).await
+}
+
+#[tracing::instrument(skip(context))]
+pub async fn radio_create(context: &Context, create: RadioCreate) -> Result<Radio> {
+    let mut tx = context.db.begin().await?;
+    let radio = radio::create(&mut tx, create).await?;
+    tx.commit().await?;
+    Ok(radio)
+}
+
+#[tracing::instrument(skip(context))]
+pub async fn radio_find_or_create(context: &Context, create: RadioCreate) -> Result<Radio> {
+    let mut tx = context.db.begin().await?;
+    let radio = radio::find_or_create(&mut tx, create).await?;
+    tx.commit().await?;
+    Ok(radio)
+}
+
+#[tracing::instrument(skip(context))]
+pub async fn radio_delete(context: &Context, id: RadioId) -> Result<()> {
+    let mut tx = context.db.begin().await?;
+    radio::delete(&mut tx, id).await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+#[tracing::instrument(skip(context))]
+pub async fn radio_next(context: &Context, id: RadioId, count: u32) -> Result<Vec<TrackId>> {
+    let mut tx = context.db.begin().await?;
+    let tracks = radio::next(&mut tx, id, count).await?;
+    tx.commit().await?;
+    Ok(tracks)
+}
+
+#[tracing::instrument(skip(context))]
+pub fn event_subscribe(context: &Context, filter: EventFilter) -> Result<EventSubscription> {
+    context.events.subscribe(filter)
+}
+
+#[tracing::instrument(skip(context))]
+pub async fn library_list(context: &Context) -> Result<Vec<Library>> {
+    let mut conn = context.db.acquire().await?;
+    library::list(&mut conn).await
+}
+
+/// Lists the libraries the user has access to, admins have access to every library.
+#[tracing::instrument(skip(context))]
+pub async fn library_list_by_user(context: &Context, user_id: UserId) -> Result<Vec<Library>> {
+    let mut conn = context.db.acquire().await?;
+    library::list_by_user(&mut conn, user_id).await
+}
+
+#[tracing::instrument(skip(context))]
+pub async fn library_get(context: &Context, library_id: LibraryId) -> Result<Library> {
+    let mut conn = context.db.acquire().await?;
+    library::get(&mut conn, library_id).await
+}
+
+#[tracing::instrument(skip(context))]
+pub async fn library_create(context: &Context, create: LibraryCreate) -> Result<Library> {
+    let mut tx = context.db.begin().await?;
+    let library = library::create(&mut tx, create).await?;
+    tx.commit().await?;
+    Ok(library)
+}
+
+#[tracing::instrument(skip(context))]
+pub async fn library_update(
+    context: &Context,
+    library_id: LibraryId,
+    update: LibraryUpdate,
+) -> Result<Library> {
+    let mut tx = context.db.begin().await?;
+    let library = library::update(&mut tx, library_id, update).await?;
+    tx.commit().await?;
+    Ok(library)
+}
+
+/// Deletes a library, only empty libraries can be deleted.
+#[tracing::instrument(skip(context))]
+pub async fn library_delete(context: &Context, library_id: LibraryId) -> Result<()> {
+    let mut tx = context.db.begin().await?;
+    library::delete(&mut tx, library_id).await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Lists the users that were granted access to the library.
+#[tracing::instrument(skip(context))]
+pub async fn library_user_list(context: &Context, library_id: LibraryId) -> Result<Vec<UserId>> {
+    let mut conn = context.db.acquire().await?;
+    library::user_list(&mut conn, library_id).await
+}
+
+#[tracing::instrument(skip(context))]
+pub async fn library_grant(
+    context: &Context,
+    library_id: LibraryId,
+    user_id: UserId,
+) -> Result<()> {
+    let mut tx = context.db.begin().await?;
+    library::grant(&mut tx, library_id, user_id).await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Fails with [`ErrorKind::Unauthorized`] unless the user is an admin or was granted access to
+/// the library.
+#[tracing::instrument(skip(context))]
+pub async fn library_check_access(
+    context: &Context,
+    user_id: UserId,
+    library_id: LibraryId,
+) -> Result<()> {
+    let mut conn = context.db.acquire().await?;
+    let filter = LibraryFilter::user(user_id).with_library(Some(library_id));
+    library::resolve(&mut conn, filter).await?;
+    Ok(())
+}
+
+#[tracing::instrument(skip(context))]
+pub async fn library_revoke(
+    context: &Context,
+    library_id: LibraryId,
+    user_id: UserId,
+) -> Result<()> {
+    let mut tx = context.db.begin().await?;
+    library::revoke(&mut tx, library_id, user_id).await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+#[tracing::instrument(skip(context))]
+pub async fn webhook_list(context: &Context) -> Result<Vec<Webhook>> {
+    let mut conn = context.db.acquire().await?;
+    webhook::list(&mut conn).await
+}
+
+#[tracing::instrument(skip(context))]
+pub async fn webhook_get(context: &Context, id: WebhookId) -> Result<Webhook> {
+    let mut conn = context.db.acquire().await?;
+    webhook::get(&mut conn, id).await
+}
+
+#[tracing::instrument(skip(context))]
+pub async fn webhook_create(context: &Context, create: WebhookCreate) -> Result<Webhook> {
+    let mut tx = context.db.begin().await?;
+    let webhook = webhook::create(&mut tx, create).await?;
+    tx.commit().await?;
+    Ok(webhook)
+}
+
+#[tracing::instrument(skip(context))]
+pub async fn webhook_update(
+    context: &Context,
+    id: WebhookId,
+    update: WebhookUpdate,
+) -> Result<Webhook> {
+    let mut tx = context.db.begin().await?;
+    let webhook = webhook::update(&mut tx, id, update).await?;
+    tx.commit().await?;
+    Ok(webhook)
+}
+
+#[tracing::instrument(skip(context))]
+pub async fn webhook_delete(context: &Context, id: WebhookId) -> Result<()> {
+    let mut tx = context.db.begin().await?;
+    webhook::delete(&mut tx, id).await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+#[tracing::instrument(skip(context))]
+pub async fn webhook_delivery_list(
+    context: &Context,
+    id: WebhookId,
+    params: ListParams,
+) -> Result<Vec<WebhookDelivery>> {
+    let mut conn = context.db.acquire().await?;
+    webhook::get(&mut conn, id).await?;
+    webhook::delivery_list(&mut conn, id, params).await
+}
+
+#[tracing::instrument(skip(context))]
+pub async fn genre_list(context: &Context) -> Result<Vec<GenreStats>> {
+    let indexes = clone_memory_indexes(context);
+    Ok(indexes.genres().list_genres())
+}
+
+#[tracing::instrument(skip(context))]
+pub async fn favorite_list(context: &Context, user_id: UserId) -> Result<Vec<Favorite>> {
+    let mut conn = context.db.acquire().await?;
+    favorite::user_list(&mut conn, user_id).await
+}
+
+#[tracing::instrument(skip(context))]
+pub async fn favorite_get_bulk(
+    context: &Context,
+    user_id: UserId,
+    ids: &[SonarId],
+) -> Result<Vec<Favorite>> {
+    let mut conn = context.db.acquire().await?;
+    favorite::user_get_bulk(&mut conn, user_id, ids).await
+}
+
+#[tracing::instrument(skip(context))]
+pub async fn favorite_find(
+    context: &Context,
+    user_id: UserId,
+    id: SonarId,
+) -> Result<Option<Favorite>> {
+    let mut conn = context.db.acquire().await?;
+    favorite::user_find(&mut conn, user_id, id).await
+}
+
+#[tracing::instrument(skip(context))]
+pub async fn favorite_add(context: &Context, user_id: UserId, id: SonarId) -> Result<()> {
+    let mut tx = context.db.begin().await?;
+    favorite::user_put(&mut tx, user_id, id).await?;
+    tx.commit().await?;
+    context
+        .events
+        .publish(EventKind::FavoriteCreated, id, Some(user_id));
+    Ok(())
+}
+
+#[tracing::instrument(skip(context))]
+pub async fn favorite_remove(context: &Context, user_id: UserId, id: SonarId) -> Result<()> {
+    let mut tx = context.db.begin().await?;
+    favorite::user_remove(&mut tx, user_id, id).await?;
+    tx.commit().await?;
+    context
+        .events
+        .publish(EventKind::FavoriteDeleted, id, Some(user_id));
+    Ok(())
+}
+
+#[tracing::instrument(skip(context))]
+pub async fn rating_list(context: &Context, user_id: UserId) -> Result<Vec<UserRating>> {
+    let mut conn = context.db.acquire().await?;
+    rating::user_list(&mut conn, user_id).await
+}
+
+#[tracing::instrument(skip(context))]
+pub async fn rating_get_bulk(
+    context: &Context,
+    user_id: UserId,
+    ids: &[SonarId],
+) -> Result<Vec<UserRating>> {
+    let mut conn = context.db.acquire().await?;
+    rating::user_get_bulk(&mut conn, user_id, ids).await
+}
+
+#[tracing::instrument(skip(context))]
+pub async fn rating_set(
+    context: &Context,
+    user_id: UserId,
+    id: SonarId,
+    rating: Rating,
+) -> Result<()> {
+    let mut tx = context.db.begin().await?;
+    rating::user_set(&mut tx, user_id, id, rating).await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+#[tracing::instrument(skip(context))]
+pub async fn rating_remove(context: &Context, user_id: UserId, id: SonarId) -> Result<()> {
+    let mut tx = context.db.begin().await?;
+    rating::user_remove(&mut tx, user_id, id).await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+#[tracing::instrument(skip(context))]
+pub async fn rating_average_get_bulk(
+    context: &Context,
+    ids: &[SonarId],
+) -> Result<Vec<AverageRating>> {
+    let mut conn = context.db.acquire().await?;
+    rating::average_get_bulk(&mut conn, ids).await
+}
+
+#[tracing::instrument(skip(context))]
+pub async fn bookmark_list(context: &Context, user_id: UserId) -> Result<Vec<Bookmark>> {
+    let mut conn = context.db.acquire().await?;
+    bookmark::list(&mut conn, user_id).await
+}
+
+#[tracing::instrument(skip(context))]
+pub async fn bookmark_get(
+    context: &Context,
+    user_id: UserId,
+    track_id: TrackId,
+) -> Result<Bookmark> {
+    let mut conn = context.db.acquire().await?;
+    bookmark::get(&mut conn, user_id, track_id).await
+}
+
+#[tracing::instrument(skip(context))]
+pub async fn bookmark_create(context: &Context, create: BookmarkCreate) -> Result<Bookmark> {
+    let mut tx = context.db.begin().await?;
+    let bookmark = bookmark::create(&mut tx, create).await?;
+    tx.commit().await?;
+    Ok(bookmark)
+}
+
+#[tracing::instrument(skip(context))]
+pub async fn bookmark_delete(context: &Context, user_id: UserId, track_id: TrackId) -> Result<()> {
+    let mut tx = context.db.begin().await?;
+    bookmark::delete(&mut tx, user_id, track_id).await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+#[tracing::instrument(skip(context))]
+pub async fn play_queue_get(context: &Context, user_id: UserId) -> Result<Option<PlayQueue>> {
+    let mut conn = context.db.acquire().await?;
+    play_queue::get(&mut conn, user_id).await
+}
+
+#[tracing::instrument(skip(context))]
+pub async fn play_queue_save(
+    context: &Context,
+    user_id: UserId,
+    save: PlayQueueSave,
+) -> Result<PlayQueue> {
+    let mut tx = context.db.begin().await?;
+    let queue = play_queue::save(&mut tx, user_id, save).await?;
+    tx.commit().await?;
+    Ok(queue)
+}
+
+#[tracing::instrument(skip(context))]
+pub async fn play_queue_clear(context: &Context, user_id: UserId) -> Result<()> {
+    let mut tx = context.db.begin().await?;
+    play_queue::clear(&mut tx, user_id).await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+#[tracing::instrument(skip(context))]
+pub async fn search(
+    context: &Context,
+    user_id: UserId,
+    query: SearchQuery,
+) -> Result<SearchResults> {
+    context.search.search(user_id, &query).await
+}
+
+#[tracing::instrument(skip(context))]
+pub async fn scrobble_list(context: &Context, params: ListParams) -> Result<Vec<Scrobble>> {
+    let mut conn = context.db.acquire().await?;
+    scrobble::list(&mut conn, params).await
+}
+
+#[tracing::instrument(skip(context))]
+pub async fn scrobble_get(context: &Context, scrobble_id: ScrobbleId) -> Result<Scrobble> {
+    let mut conn = context.db.acquire().await?;
+    scrobble::get(&mut conn, scrobble_id).await
+}
+
+#[tracing::instrument(skip(context))]
+pub async fn scrobble_create(context: &Context, create: ScrobbleCreate) -> Result<Scrobble> {
+    let mut tx = context.db.begin().await?;
+    let result = scrobble::create(&mut tx, create).await?;
+    tx.commit().await?;
+    context.scrobbler_notify.notify_waiters();
+    context
+        .events
+        .publish(EventKind::ScrobbleCreated, result.id, Some(result.user));
+    Ok(result)
+}
+
+#[tracing::instrument(skip(context))]
+pub async fn scrobble_update(
+    context: &Context,
+    id: ScrobbleId,
+    update: ScrobbleUpdate,
+) -> Result<Scrobble> {
+    let mut tx = context.db.begin().await?;
+    let result = scrobble::update(&mut tx, id, update).await?;
+    tx.commit().await?;
+    context
+        .events
+        .publish(EventKind::ScrobbleUpdated, id, Some(result.user));
     Ok(result)
 }
 
 #[tracing::instrument(skip(context))]
 pub async fn scrobble_delete(context: &Context, id: ScrobbleId) -> Result<()> {
     let mut tx = context.db.begin().await?;
+    let scrobble = scrobble::get(&mut tx, id).await?;
     scrobble::delete(&mut tx, id).await?;
     tx.commit().await?;
+    context
+        .events
+        .publish(EventKind::ScrobbleDeleted, id, Some(scrobble.user));
     Ok(())
 }
 
+#[tracing::instrument(skip(context, import))]
+pub async fn scrobble_import(
+    context: &Context,
+    import: ScrobbleImport,
+) -> Result<ScrobbleImportReport> {
+    let scrobblers = context
+        .scrobblers
+        .iter()
+        .map(|scrobbler| scrobbler.identifier())
+        .collect::<Vec<_>>();
+    let user_id = import.user;
+    let mut tx = context.db.begin().await?;
+    let (report, created) = scrobble_import::import(&mut tx, import, &scrobblers).await?;
+    tx.commit().await?;
+    for scrobble_id in created {
+        context
+            .events
+            .publish(EventKind::ScrobbleCreated, scrobble_id, Some(user_id));
+    }
+    Ok(report)
+}
+
+#[tracing::instrument(skip(context))]
+pub async fn stats_get(context: &Context, params: StatsParams) -> Result<Stats> {
+    let mut conn = context.db.acquire().await?;
+    stats::compute(&mut conn, params).await
+}
+
 #[tracing::instrument(skip(context))]
 pub(crate) async fn scrobble_list_unsubmitted(
     context: &Context,
@@ -1056,6 +1882,10 @@ pub async fn subscription_create(
     create: SubscriptionCreate,
 ) -> Result<Subscription> {
     let mut tx = context.db.begin().await?;
+    if create.library.is_some() {
+        let filter = LibraryFilter::user(create.user).with_library(create.library);
+        library::resolve(&mut tx, filter).await?;
+    }
     let subscription = subscription::create(&mut tx, create).await?;
     tx.commit().await?;
     subscription_submit(context, subscription.id).await?;
@@ -1082,6 +1912,7 @@ pub async fn subscription_submit(context: &Context, subscription_id: Subscriptio
 
     tokio::spawn({
         let db = context.db.clone();
+        let events = context.events.clone();
         let services = context.external.clone();
         let storage = context.storage.clone();
         let request = ExternalMediaRequest {
@@ -1100,7 +1931,16 @@ pub async fn subscription_submit(context: &Context, subscription_id: Subscriptio
         };
 
         async move {
-            if let Err(err) = download::download(&db, &services, &*storage, sub.user, request).await
+            if let Err(err) = download::download(
+                &db,
+                &events,
+                &services,
+                &*storage,
+                sub.user,
+                sub.library,
+                request,
+            )
+            .await
             {
                 tracing::error!("failed to download {}: {}", sub.id, err);
             }
@@ -1139,16 +1979,75 @@ pub async fn garbage_collection_candidates(context: &Context) -> Result<Vec<Sona
     gc::list_gc_candidates(&mut conn).await
 }
 
+#[tracing::instrument(skip(context))]
+pub async fn garbage_collect(
+    context: &Context,
+    params: GarbageCollectParams,
+) -> Result<GarbageCollectReport> {
+    let dry_run = params.dry_run;
+    let mut tx = context.db.begin().await?;
+    let (report, keys) = gc::run(&mut tx, params).await?;
+    tx.commit().await?;
+    blob::delete_keys(&*context.storage, keys).await;
+
+    if !dry_run && !report.items.is_empty() {
+        for &sonar_id in report.items.iter() {
+            match sonar_id {
+                SonarId::Artist(artist_id) => {
+                    context.search.synchronize_artist(artist_id).await;
+                    context
+                        .events
+                        .publish(EventKind::ArtistDeleted, artist_id, None);
+                }
+                SonarId::Album(album_id) => {
+                    context.search.synchronize_album(album_id).await;
+                    context
+                        .events
+                        .publish(EventKind::AlbumDeleted, album_id, None);
+                }
+                SonarId::Track(track_id) => {
+                    context.search.synchronize_track(track_id).await;
+                    context
+                        .events
+                        .publish(EventKind::TrackDeleted, track_id, None);
+                }
+                _ => {}
+            }
+        }
+        memory_indexes_rebuild(context).await;
+    }
+    Ok(report)
+}
+
+#[tracing::instrument(skip(context))]
+pub async fn backup_create(context: &Context, path: &Path) -> Result<BackupReport> {
+    let mut conn = context.db.acquire().await?;
+    backup::create(&mut conn, &*context.storage, path).await
+}
+
+#[tracing::instrument(skip(context))]
+pub async fn orphan_sweep(context: &Context, params: SweepParams) -> Result<SweepReport> {
+    let mut tx = context.db.begin().await?;
+    let (report, keys) = sweep::run(&mut tx, params).await?;
+    tx.commit().await?;
+    blob::delete_keys(&*context.storage, keys).await;
+    Ok(report)
+}
+
 #[tracing::instrument(skip(context, import))]
 pub async fn import(context: &Context, import: Import) -> Result<Track> {
-    importer::import(
+    let track = importer::import(
         &context.importer,
         &context.db,
         &*context.storage,
         &context.extractors,
         import,
     )
-    .await
+    .await?;
+    context
+        .events
+        .publish(EventKind::ImportFinished, track.id, None);
+    Ok(track)
 }
 
 fn merge_metadata_covers(a: Option<Bytes>, b: Option<Bytes>) -> Option<Bytes> {
@@ -1509,32 +2408,61 @@ pub async fn metadata_view_track(
         .fold(Default::default(), merge_metadata_track))
 }
 
-async fn on_artist_crud(context: &Context, artist_id: ArtistId) {
+// local search indexes are updated before returning so that searches see the changes right away.
+async fn on_artist_crud(context: &Context, artist_id: ArtistId, kind: EventKind) {
+    if context.search.synchronize_inline() {
+        context.search.synchronize_artist(artist_id).await;
+    }
+    context.events.publish(kind, artist_id, None);
     let context = context.clone();
     tokio::spawn(async move {
-        context.search.synchronize_artist(artist_id).await;
+        if !context.search.synchronize_inline() {
+            context.search.synchronize_artist(artist_id).await;
+        }
         memory_indexes_rebuild(&context).await;
     });
 }
-async fn on_album_crud(context: &Context, album_id: AlbumId) {
+async fn on_album_crud(context: &Context, album_id: AlbumId, kind: EventKind) {
+    if context.search.synchronize_inline() {
+        context.search.synchronize_album(album_id).await;
+    }
+    context.events.publish(kind, album_id, None);
     let context = context.clone();
     tokio::spawn(async move {
-        context.search.synchronize_album(album_id).await;
+        if !context.search.synchronize_inline() {
+            context.search.synchronize_album(album_id).await;
+        }
         memory_indexes_rebuild(&context).await;
     });
 }
-async fn on_track_crud(context: &Context, track_id: TrackId) {
+async fn on_track_crud(context: &Context, track_id: TrackId, kind: EventKind) {
+    if context.search.synchronize_inline() {
+        context.search.synchronize_track(track_id).await;
+    }
+    context.events.publish(kind, track_id, None);
     let context = context.clone();
     tokio::spawn(async move {
-        context.search.synchronize_track(track_id).await;
+        if !context.search.synchronize_inline() {
+            context.search.synchronize_track(track_id).await;
+        }
         memory_indexes_rebuild(&context).await;
     });
 }
-async fn on_playlist_crud(context: &Context, playlist_id: PlaylistId) {
-    let search = context.search.clone();
-    tokio::spawn(async move {
-        search.synchronize_playlist(playlist_id).await;
-    });
+async fn on_playlist_crud(
+    context: &Context,
+    playlist_id: PlaylistId,
+    owner: UserId,
+    kind: EventKind,
+) {
+    if context.search.synchronize_inline() {
+        context.search.synchronize_playlist(playlist_id).await;
+    } else {
+        let search = context.search.clone();
+        tokio::spawn(async move {
+            search.synchronize_playlist(playlist_id).await;
+        });
+    }
+    context.events.publish(kind, playlist_id, Some(owner));
 }
 
 fn clone_memory_indexes(context: &Context) -> MemoryIndexes {
@@ -0,0 +1,22 @@
+use std::time::Duration;
+
+use crate::{Context, Result};
+
+pub(super) async fn run(context: &Context, interval: Duration) {
+    loop {
+        tokio::time::sleep(interval).await;
+        if let Err(err) = iteration(context).await {
+            tracing::error!("failed to run smart playlist process iteration: {err}");
+        }
+    }
+}
+
+async fn iteration(context: &Context) -> Result<()> {
+    let playlists = super::smart_playlist_list_ids(context).await?;
+    for playlist_id in playlists {
+        if let Err(err) = super::smart_playlist_refresh(context, playlist_id).await {
+            tracing::error!("failed to refresh smart playlist {}: {}", playlist_id, err);
+        }
+    }
+    Ok(())
+}
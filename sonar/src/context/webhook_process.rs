@@ -0,0 +1,139 @@
+use std::{collections::HashMap, sync::Arc, time::Duration};
+
+use tokio::{sync::Notify, task::JoinSet};
+
+use crate::{
+    webhook::{self, PendingDelivery},
+    Context, Event, EventFilter, EventSubscription, Result,
+};
+
+/// Maximum number of deliveries sent per iteration.
+const DELIVERY_BATCH_SIZE: u32 = 64;
+/// Deliveries are checked at least this often, even if no new events arrived.
+const MAX_POLL_INTERVAL: Duration = Duration::from_secs(60);
+
+pub(super) async fn run(context: &Context, subscription: EventSubscription, retry_delay: Duration) {
+    let notify = Arc::new(Notify::new());
+    tokio::join!(
+        enqueue(context, subscription, &notify),
+        deliver(context, &notify, retry_delay)
+    );
+}
+
+async fn enqueue(context: &Context, mut subscription: EventSubscription, notify: &Notify) {
+    let mut last_sequence = None;
+    loop {
+        let event = match subscription.recv().await {
+            Ok(event) => event,
+            Err(err) => {
+                tracing::warn!("webhook event subscription failed: {err}");
+                subscription = resubscribe(context, last_sequence);
+                continue;
+            }
+        };
+        last_sequence = Some(event.sequence);
+        if let Err(err) = enqueue_event(context, &event).await {
+            tracing::error!("failed to enqueue webhook deliveries for event {event:?}: {err}");
+        }
+        notify.notify_one();
+    }
+}
+
+async fn enqueue_event(context: &Context, event: &Event) -> Result<()> {
+    let mut tx = context.db.begin().await?;
+    webhook::enqueue(&mut tx, event).await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+fn resubscribe(context: &Context, since: Option<u64>) -> EventSubscription {
+    let filter = EventFilter {
+        since,
+        ..Default::default()
+    };
+    match context.events.subscribe(filter) {
+        Ok(subscription) => subscription,
+        Err(err) => {
+            tracing::error!("some events were not delivered to webhooks: {err}");
+            context
+                .events
+                .subscribe(Default::default())
+                .expect("subscribing without a sequence number cannot fail")
+        }
+    }
+}
+
+async fn deliver(context: &Context, notify: &Notify, retry_delay: Duration) {
+    let client = reqwest::Client::new();
+    let poll_interval = retry_delay.min(MAX_POLL_INTERVAL);
+    loop {
+        match iteration(context, &client, retry_delay).await {
+            // there might be more deliveries that are due
+            Ok(count) if count == DELIVERY_BATCH_SIZE as usize => continue,
+            Ok(_) => {}
+            Err(err) => tracing::error!("failed to run webhook process iteration: {err}"),
+        }
+        tokio::select! {
+            _ = notify.notified() => {}
+            _ = tokio::time::sleep(poll_interval) => {}
+        }
+    }
+}
+
+async fn iteration(
+    context: &Context,
+    client: &reqwest::Client,
+    retry_delay: Duration,
+) -> Result<usize> {
+    let deliveries = {
+        let mut conn = context.db.acquire().await?;
+        webhook::delivery_prune(&mut conn).await?;
+        webhook::delivery_due(&mut conn, DELIVERY_BATCH_SIZE).await?
+    };
+    let count = deliveries.len();
+
+    // webhooks are delivered to concurrently so a slow webhook does not hold up the others,
+    // the deliveries of a single webhook are still sent in order.
+    let mut webhook_deliveries = HashMap::<i64, Vec<PendingDelivery>>::new();
+    for delivery in deliveries {
+        webhook_deliveries
+            .entry(delivery.webhook)
+            .or_default()
+            .push(delivery);
+    }
+    let mut tasks = JoinSet::new();
+    for (_, deliveries) in webhook_deliveries {
+        let context = context.clone();
+        let client = client.clone();
+        tasks.spawn(async move { deliver_all(&context, &client, deliveries, retry_delay).await });
+    }
+    while let Some(result) = tasks.join_next().await {
+        match result {
+            Ok(Ok(())) => {}
+            Ok(Err(err)) => tracing::error!("failed to record webhook delivery: {err}"),
+            Err(err) => tracing::error!("webhook delivery task failed: {err}"),
+        }
+    }
+    Ok(count)
+}
+
+async fn deliver_all(
+    context: &Context,
+    client: &reqwest::Client,
+    deliveries: Vec<PendingDelivery>,
+    retry_delay: Duration,
+) -> Result<()> {
+    for delivery in deliveries {
+        let (response_status, error) = webhook::deliver(client, &delivery).await;
+        if let Some(error) = &error {
+            tracing::warn!(
+                "webhook delivery {} to {} failed: {error}",
+                delivery.id,
+                delivery.url
+            );
+        }
+        let mut conn = context.db.acquire().await?;
+        webhook::delivery_record(&mut conn, &delivery, response_status, error, retry_delay).await?;
+    }
+    Ok(())
+}
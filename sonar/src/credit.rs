@@ -0,0 +1,266 @@
+use std::{borrow::Cow, collections::HashMap, str::FromStr};
+
+use sqlx::Row;
+
+use crate::{
+    db::{self, DbC},
+    Album, AlbumId, ArtistId, Error, ErrorKind, Result, Track, TrackId,
+};
+
+#[derive(Debug)]
+pub struct InvalidArtistRoleError {
+    message: Cow<'static, str>,
+}
+
+impl InvalidArtistRoleError {
+    fn new(message: impl Into<Cow<'static, str>>) -> Self {
+        Self {
+            message: message.into(),
+        }
+    }
+}
+
+impl std::fmt::Display for InvalidArtistRoleError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} is not a valid artist role", self.message)
+    }
+}
+
+impl std::error::Error for InvalidArtistRoleError {}
+
+/// The role of an artist credited on a track.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ArtistRole {
+    Primary,
+    Featured,
+    Remixer,
+}
+
+impl ArtistRole {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ArtistRole::Primary => "primary",
+            ArtistRole::Featured => "featured",
+            ArtistRole::Remixer => "remixer",
+        }
+    }
+}
+
+impl std::fmt::Display for ArtistRole {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for ArtistRole {
+    type Err = InvalidArtistRoleError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "primary" => Ok(ArtistRole::Primary),
+            "featured" => Ok(ArtistRole::Featured),
+            "remixer" => Ok(ArtistRole::Remixer),
+            _ => Err(InvalidArtistRoleError::new(s.to_owned())),
+        }
+    }
+}
+
+/// An artist credited on a track.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ArtistCredit {
+    pub artist: ArtistId,
+    pub role: ArtistRole,
+}
+
+impl ArtistCredit {
+    pub fn new(artist: ArtistId, role: ArtistRole) -> Self {
+        Self { artist, role }
+    }
+
+    pub fn primary(artist: ArtistId) -> Self {
+        Self::new(artist, ArtistRole::Primary)
+    }
+}
+
+#[tracing::instrument(skip(db))]
+pub async fn album_get_bulk(
+    db: &mut DbC,
+    album_ids: &[AlbumId],
+) -> Result<HashMap<AlbumId, Vec<ArtistId>>> {
+    let mut query =
+        sqlx::QueryBuilder::new("SELECT album, artist FROM album_credit WHERE album IN ");
+    db::query_builder_push_id_tuple(&mut query, album_ids.iter().copied());
+    query.push("ORDER BY album ASC, position ASC");
+    let rows = query.build().fetch_all(&mut *db).await?;
+
+    let mut artists = HashMap::<AlbumId, Vec<ArtistId>>::new();
+    for row in rows {
+        artists
+            .entry(AlbumId::from_db(row.get(0)))
+            .or_default()
+            .push(ArtistId::from_db(row.get(1)));
+    }
+    Ok(artists)
+}
+
+/// Sets the album artists, the first one is also stored as the album's artist.
+#[tracing::instrument(skip(db))]
+pub async fn album_set(db: &mut DbC, album_id: AlbumId, artists: &[ArtistId]) -> Result<()> {
+    let mut unique = Vec::with_capacity(artists.len());
+    for artist in artists {
+        if !unique.contains(artist) {
+            unique.push(*artist);
+        }
+    }
+    if unique.is_empty() {
+        return Err(Error::new(
+            ErrorKind::Invalid,
+            "album must have at least one artist",
+        ));
+    }
+
+    album_clear(db, album_id).await?;
+    sqlx::query("UPDATE album SET artist = ? WHERE id = ?")
+        .bind(unique[0])
+        .bind(album_id)
+        .execute(&mut *db)
+        .await?;
+    for (position, artist) in unique.into_iter().enumerate() {
+        sqlx::query("INSERT INTO album_credit (album, artist, position) VALUES (?, ?, ?)")
+            .bind(album_id)
+            .bind(artist)
+            .bind(position as i64)
+            .execute(&mut *db)
+            .await?;
+    }
+    Ok(())
+}
+
+#[tracing::instrument(skip(db))]
+pub async fn album_clear(db: &mut DbC, album_id: AlbumId) -> Result<()> {
+    sqlx::query("DELETE FROM album_credit WHERE album = ?")
+        .bind(album_id)
+        .execute(&mut *db)
+        .await?;
+    Ok(())
+}
+
+/// Returns the credits stored for each track, tracks that only have the album artists are not
+/// included.
+#[tracing::instrument(skip(db))]
+pub async fn track_get_bulk(
+    db: &mut DbC,
+    track_ids: &[TrackId],
+) -> Result<HashMap<TrackId, Vec<ArtistCredit>>> {
+    let mut query =
+        sqlx::QueryBuilder::new("SELECT track, artist, role FROM track_credit WHERE track IN ");
+    db::query_builder_push_id_tuple(&mut query, track_ids.iter().copied());
+    query.push("ORDER BY track ASC, position ASC");
+    let rows = query.build().fetch_all(&mut *db).await?;
+
+    let mut credits = HashMap::<TrackId, Vec<ArtistCredit>>::new();
+    for row in rows {
+        let role = row
+            .get::<String, _>(2)
+            .parse::<ArtistRole>()
+            .map_err(|err| Error::with_source(ErrorKind::Internal, "invalid artist role", err))?;
+        credits
+            .entry(TrackId::from_db(row.get(0)))
+            .or_default()
+            .push(ArtistCredit::new(ArtistId::from_db(row.get(1)), role));
+    }
+    Ok(credits)
+}
+
+/// Sets the artists credited on the track.
+/// If none of them is a primary artist the track is also credited to the album artists.
+#[tracing::instrument(skip(db))]
+pub async fn track_set(db: &mut DbC, track_id: TrackId, credits: &[ArtistCredit]) -> Result<()> {
+    track_clear(db, track_id).await?;
+    let mut inserted = Vec::with_capacity(credits.len());
+    for credit in credits {
+        if inserted.contains(credit) {
+            continue;
+        }
+        sqlx::query("INSERT INTO track_credit (track, artist, role, position) VALUES (?, ?, ?, ?)")
+            .bind(track_id)
+            .bind(credit.artist)
+            .bind(credit.role.as_str())
+            .bind(inserted.len() as i64)
+            .execute(&mut *db)
+            .await?;
+        inserted.push(*credit);
+    }
+    Ok(())
+}
+
+#[tracing::instrument(skip(db))]
+pub async fn track_clear(db: &mut DbC, track_id: TrackId) -> Result<()> {
+    sqlx::query("DELETE FROM track_credit WHERE track = ?")
+        .bind(track_id)
+        .execute(&mut *db)
+        .await?;
+    Ok(())
+}
+
+/// Removes every credit of the artist.
+#[tracing::instrument(skip(db))]
+pub async fn artist_clear(db: &mut DbC, artist_id: ArtistId) -> Result<()> {
+    sqlx::query("DELETE FROM track_credit WHERE artist = ?")
+        .bind(artist_id)
+        .execute(&mut *db)
+        .await?;
+    sqlx::query("DELETE FROM album_credit WHERE artist = ?")
+        .bind(artist_id)
+        .execute(&mut *db)
+        .await?;
+    Ok(())
+}
+
+/// Loads the album artists of each album.
+#[tracing::instrument(skip(db, albums))]
+pub async fn fill_albums(db: &mut DbC, albums: &mut [Album]) -> Result<()> {
+    let album_ids = albums.iter().map(|album| album.id).collect::<Vec<_>>();
+    let artists = album_get_bulk(db, &album_ids).await?;
+    for album in albums.iter_mut() {
+        if let Some(artists) = artists.get(&album.id) {
+            album.artists = artists.clone();
+        }
+    }
+    Ok(())
+}
+
+/// Loads the artist credits of each track.
+#[tracing::instrument(skip(db, tracks))]
+pub async fn fill_tracks(db: &mut DbC, tracks: &mut [Track]) -> Result<()> {
+    let track_ids = tracks.iter().map(|track| track.id).collect::<Vec<_>>();
+    let album_ids = tracks.iter().map(|track| track.album).collect::<Vec<_>>();
+    let credits = track_get_bulk(db, &track_ids).await?;
+    let album_artists = album_get_bulk(db, &album_ids).await?;
+    for track in tracks.iter_mut() {
+        let mut artists = credits.get(&track.id).cloned().unwrap_or_default();
+        if !artists.iter().any(|c| c.role == ArtistRole::Primary) {
+            let album_artists = album_artists.get(&track.album).into_iter().flatten();
+            artists.splice(0..0, album_artists.copied().map(ArtistCredit::primary));
+        }
+        track.artists = artists;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_artist_role_roundtrip() {
+        for role in [
+            ArtistRole::Primary,
+            ArtistRole::Featured,
+            ArtistRole::Remixer,
+        ] {
+            assert_eq!(role.as_str().parse::<ArtistRole>().unwrap(), role);
+        }
+        assert!("producer".parse::<ArtistRole>().is_err());
+    }
+}
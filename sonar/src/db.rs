@@ -83,6 +83,30 @@ where
         .await?)
 }
 
+pub async fn list_where_field_in<T, ID>(
+    db: &mut DbC,
+    view: &str,
+    field: &str,
+    values: impl IntoIterator<Item = ID>,
+    params: ListParams,
+) -> Result<Vec<T>>
+where
+    T: for<'a> sqlx::FromRow<'a, sqlx::sqlite::SqliteRow> + Send + Unpin,
+    ID: SonarIdentifier,
+{
+    let (offset, limit) = params.to_db_offset_limit();
+    let mut query = sqlx::QueryBuilder::new("SELECT * FROM ");
+    query.push(view).push(" WHERE ").push(field).push(" IN ");
+    query_builder_push_id_tuple(&mut query, values);
+    Ok(query
+        .push(" ORDER BY id ASC LIMIT ? OFFSET ?")
+        .build_query_as::<T>()
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(&mut *db)
+        .await?)
+}
+
 pub async fn list_bulk<T, ID>(
     db: &mut DbC,
     table: &str,
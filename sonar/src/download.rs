@@ -3,20 +3,23 @@ use crate::{
     blob::BlobStorage,
     bytestream,
     db::Db,
+    event::{EventBus, EventKind},
     external::{
         ExternalAlbum, ExternalArtist, ExternalMediaId, ExternalMediaType, ExternalServices,
         ExternalTrack,
     },
     image, playlist, track, Album, AlbumCreate, AlbumId, AlbumUpdate, Artist, ArtistCreate,
-    ArtistId, AudioCreate, ExternalMediaRequest, ExternalService, ImageCreate, Playlist,
+    ArtistId, AudioCreate, ExternalMediaRequest, ExternalService, ImageCreate, LibraryId, Playlist,
     PlaylistCreate, Properties, Result, Track, TrackCreate, TrackId, UserId, ValueUpdate,
 };
 
 pub async fn download(
     db: &Db,
+    events: &EventBus,
     services: &ExternalServices,
     storage: &dyn BlobStorage,
     user_id: UserId,
+    library: Option<LibraryId>,
     mut request: ExternalMediaRequest,
 ) -> Result<()> {
     tracing::debug!("enriching {request:#?}");
@@ -28,14 +31,22 @@ pub async fn download(
     match media_type {
         ExternalMediaType::Artist => {
             let external_artist = service.fetch_artist(&external_id).await?;
-            let artist = find_or_create_artist(db, &external_artist).await?;
+            let artist = find_or_create_artist(db, library, &external_artist).await?;
 
             for album_external_id in external_artist.albums.iter() {
                 let album_request = ExternalMediaRequest {
                     external_ids: vec![album_external_id.clone()],
                     ..Default::default()
                 };
-                let download = Box::pin(download(db, services, storage, user_id, album_request));
+                let download = Box::pin(download(
+                    db,
+                    events,
+                    services,
+                    storage,
+                    user_id,
+                    library,
+                    album_request,
+                ));
                 if let Err(err) = download.await {
                     tracing::error!(
                         "failed to download album {} for artist {}: {}",
@@ -49,7 +60,7 @@ pub async fn download(
         ExternalMediaType::Album => {
             let external_album = service.fetch_album(&external_id).await?;
             let external_artist = service.fetch_artist(&external_album.artist).await?;
-            let artist = find_or_create_artist(db, &external_artist).await?;
+            let artist = find_or_create_artist(db, library, &external_artist).await?;
             let album = find_or_create_album(db, storage, &external_album, artist.id).await?;
 
             for track_external_id in external_album.tracks {
@@ -57,7 +68,15 @@ pub async fn download(
                     external_ids: vec![track_external_id.clone()],
                     ..Default::default()
                 };
-                let download = Box::pin(download(db, services, storage, user_id, track_request));
+                let download = Box::pin(download(
+                    db,
+                    events,
+                    services,
+                    storage,
+                    user_id,
+                    library,
+                    track_request,
+                ));
                 if let Err(err) = download.await {
                     tracing::error!(
                         "failed to download track {} for album {}/{}: {}",
@@ -73,10 +92,10 @@ pub async fn download(
             let external_track = service.fetch_track(&external_id).await?;
             let external_album = service.fetch_album(&external_track.album).await?;
             let external_artist = service.fetch_artist(&external_track.artist).await?;
-            let artist = find_or_create_artist(db, &external_artist).await?;
+            let artist = find_or_create_artist(db, library, &external_artist).await?;
             let album = find_or_create_album(db, storage, &external_album, artist.id).await?;
             let track = find_or_create_track(db, &external_track, album.id).await?;
-            download_audio(db, service, storage, &external_id, track.id).await?;
+            download_audio(db, events, service, storage, &external_id, track.id).await?;
         }
         ExternalMediaType::Playlist => {
             let external_playlist = service.fetch_playlist(&external_id).await?;
@@ -89,7 +108,16 @@ pub async fn download(
                     ..Default::default()
                 };
 
-                match download_track_request(db, services, storage, track_request.clone()).await {
+                match download_track_request(
+                    db,
+                    events,
+                    services,
+                    storage,
+                    library,
+                    track_request.clone(),
+                )
+                .await
+                {
                     Ok(track_id) => tracks.push(track_id),
                     Err(err) => {
                         tracing::warn!(
@@ -115,6 +143,11 @@ pub async fn download(
             playlist::clear_tracks(&mut tx, playlist.id).await?;
             playlist::insert_tracks(&mut tx, playlist.id, &tracks).await?;
             tx.commit().await?;
+            events.publish(
+                EventKind::PlaylistUpdated,
+                playlist.id,
+                Some(playlist.owner),
+            );
         }
         ExternalMediaType::Compilation => {
             let external_compilation = service.fetch_compilation(&external_id).await?;
@@ -129,7 +162,16 @@ pub async fn download(
                     ..Default::default()
                 };
 
-                match download_track_request(db, services, storage, track_request.clone()).await {
+                match download_track_request(
+                    db,
+                    events,
+                    services,
+                    storage,
+                    library,
+                    track_request.clone(),
+                )
+                .await
+                {
                     Ok(track_id) => tracks.push(track_id),
                     Err(err) => {
                         tracing::warn!(
@@ -154,6 +196,11 @@ pub async fn download(
             playlist::clear_tracks(&mut tx, playlist.id).await?;
             playlist::insert_tracks(&mut tx, playlist.id, &tracks).await?;
             tx.commit().await?;
+            events.publish(
+                EventKind::PlaylistUpdated,
+                playlist.id,
+                Some(playlist.owner),
+            );
         }
         ExternalMediaType::Group => {
             let external_group = service.fetch_group(&external_id).await?;
@@ -163,8 +210,16 @@ pub async fn download(
                     external_ids: vec![group_item],
                     ..Default::default()
                 };
-                if let Err(err) =
-                    Box::pin(download(db, services, storage, user_id, item_request)).await
+                if let Err(err) = Box::pin(download(
+                    db,
+                    events,
+                    services,
+                    storage,
+                    user_id,
+                    library,
+                    item_request,
+                ))
+                .await
                 {
                     tracing::warn!("failed to download group item: {}", err);
                 }
@@ -174,12 +229,17 @@ pub async fn download(
     Ok(())
 }
 
-async fn find_or_create_artist(db: &Db, external_artist: &ExternalArtist) -> Result<Artist> {
+async fn find_or_create_artist(
+    db: &Db,
+    library: Option<LibraryId>,
+    external_artist: &ExternalArtist,
+) -> Result<Artist> {
     let create = ArtistCreate {
         name: external_artist.name.clone(),
         cover_art: None,
         genres: external_artist.genres.clone(),
         properties: external_artist.properties.clone(),
+        library,
     };
     let artist = artist::find_or_create_by_name_tx(db, create).await?;
     Ok(artist)
@@ -194,6 +254,7 @@ async fn find_or_create_album(
     let create = AlbumCreate {
         name: external_album.name.clone(),
         artist: artist_id,
+        artists: Vec::new(),
         cover_art: None,
         genres: external_album.genres.clone(),
         properties: external_album.properties.clone(),
@@ -231,6 +292,7 @@ async fn find_or_create_track(
     let create = TrackCreate {
         name: external_track.name.clone(),
         album: album_id,
+        artists: Vec::new(),
         cover_art: None,
         lyrics: external_track.lyrics.clone(),
         audio: None,
@@ -259,35 +321,40 @@ async fn find_or_create_playlist(
 
 async fn download_track_request(
     db: &Db,
+    events: &EventBus,
     services: &ExternalServices,
     storage: &dyn BlobStorage,
+    library: Option<LibraryId>,
     mut request: ExternalMediaRequest,
 ) -> Result<TrackId> {
     request.media_type = Some(ExternalMediaType::Track);
     services.enrich(&mut request).await?;
     let (service, track_media_type, track_external_id) = services.extract(&request).await?;
     assert_eq!(track_media_type, ExternalMediaType::Track);
-    download_track(db, service, storage, &track_external_id).await
+    download_track(db, events, service, storage, library, &track_external_id).await
 }
 
 async fn download_track(
     db: &Db,
+    events: &EventBus,
     service: &dyn ExternalService,
     storage: &dyn BlobStorage,
+    library: Option<LibraryId>,
     external_id: &ExternalMediaId,
 ) -> Result<TrackId> {
     let external_track = service.fetch_track(external_id).await?;
     let external_album = service.fetch_album(&external_track.album).await?;
     let external_artist = service.fetch_artist(&external_track.artist).await?;
-    let artist = find_or_create_artist(db, &external_artist).await?;
+    let artist = find_or_create_artist(db, library, &external_artist).await?;
     let album = find_or_create_album(db, storage, &external_album, artist.id).await?;
     let track = find_or_create_track(db, &external_track, album.id).await?;
-    download_audio(db, service, storage, external_id, track.id).await?;
+    download_audio(db, events, service, storage, external_id, track.id).await?;
     Ok(track.id)
 }
 
 async fn download_audio(
     db: &Db,
+    events: &EventBus,
     service: &dyn ExternalService,
     storage: &dyn BlobStorage,
     external_id: &ExternalMediaId,
@@ -311,6 +378,7 @@ async fn download_audio(
     let audio = audio::create(&mut tx, storage, create).await?;
     audio::set_preferred(&mut tx, audio.id, track_id).await?;
     tx.commit().await?;
+    events.publish(EventKind::ImportFinished, track_id, None);
     Ok(())
 }
 
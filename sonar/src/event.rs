@@ -0,0 +1,290 @@
+//! In memory bus of library change events.
+//!
+//! Every event gets a sequence number, the last events are kept around so subscribers that
+//! reconnect can resume from the last sequence number they saw. Sequence numbers restart when
+//! the server restarts, subscribers that try to resume from a sequence number that is no longer
+//! available get an error and should resynchronize.
+
+use std::{
+    collections::VecDeque,
+    str::FromStr,
+    sync::{Arc, Mutex},
+};
+
+use tokio::sync::broadcast;
+
+use crate::{Error, ErrorKind, Result, SonarId, Timestamp, UserId};
+
+/// Number of events kept around for subscribers to resume from.
+const EVENT_HISTORY: usize = 4096;
+const EVENT_CHANNEL_CAPACITY: usize = 1024;
+
+#[derive(Debug)]
+pub struct InvalidEventKindError {
+    value: String,
+}
+
+impl InvalidEventKindError {
+    fn new(value: impl Into<String>) -> Self {
+        Self {
+            value: value.into(),
+        }
+    }
+}
+
+impl std::fmt::Display for InvalidEventKindError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "invalid event kind '{}'", self.value)
+    }
+}
+
+impl std::error::Error for InvalidEventKindError {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EventKind {
+    ArtistCreated,
+    ArtistUpdated,
+    ArtistDeleted,
+    AlbumCreated,
+    AlbumUpdated,
+    AlbumDeleted,
+    TrackCreated,
+    TrackUpdated,
+    TrackDeleted,
+    PlaylistCreated,
+    PlaylistUpdated,
+    PlaylistDeleted,
+    FavoriteCreated,
+    FavoriteDeleted,
+    ScrobbleCreated,
+    ScrobbleUpdated,
+    ScrobbleDeleted,
+    ImportFinished,
+}
+
+impl EventKind {
+    pub const ALL: &'static [EventKind] = &[
+        EventKind::ArtistCreated,
+        EventKind::ArtistUpdated,
+        EventKind::ArtistDeleted,
+        EventKind::AlbumCreated,
+        EventKind::AlbumUpdated,
+        EventKind::AlbumDeleted,
+        EventKind::TrackCreated,
+        EventKind::TrackUpdated,
+        EventKind::TrackDeleted,
+        EventKind::PlaylistCreated,
+        EventKind::PlaylistUpdated,
+        EventKind::PlaylistDeleted,
+        EventKind::FavoriteCreated,
+        EventKind::FavoriteDeleted,
+        EventKind::ScrobbleCreated,
+        EventKind::ScrobbleUpdated,
+        EventKind::ScrobbleDeleted,
+        EventKind::ImportFinished,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EventKind::ArtistCreated => "artist-created",
+            EventKind::ArtistUpdated => "artist-updated",
+            EventKind::ArtistDeleted => "artist-deleted",
+            EventKind::AlbumCreated => "album-created",
+            EventKind::AlbumUpdated => "album-updated",
+            EventKind::AlbumDeleted => "album-deleted",
+            EventKind::TrackCreated => "track-created",
+            EventKind::TrackUpdated => "track-updated",
+            EventKind::TrackDeleted => "track-deleted",
+            EventKind::PlaylistCreated => "playlist-created",
+            EventKind::PlaylistUpdated => "playlist-updated",
+            EventKind::PlaylistDeleted => "playlist-deleted",
+            EventKind::FavoriteCreated => "favorite-created",
+            EventKind::FavoriteDeleted => "favorite-deleted",
+            EventKind::ScrobbleCreated => "scrobble-created",
+            EventKind::ScrobbleUpdated => "scrobble-updated",
+            EventKind::ScrobbleDeleted => "scrobble-deleted",
+            EventKind::ImportFinished => "import-finished",
+        }
+    }
+}
+
+impl std::fmt::Display for EventKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for EventKind {
+    type Err = InvalidEventKindError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        EventKind::ALL
+            .iter()
+            .find(|kind| kind.as_str() == s)
+            .copied()
+            .ok_or_else(|| InvalidEventKindError::new(s))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Event {
+    pub sequence: u64,
+    pub kind: EventKind,
+    /// The entity the event is about.
+    /// For favorites this is the favorited item and for finished imports the imported track.
+    pub id: SonarId,
+    /// The user the event belongs to, for favorites, scrobbles and playlists.
+    pub user: Option<UserId>,
+    pub timestamp: Timestamp,
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct EventFilter {
+    /// Only events of these kinds, all kinds if empty.
+    pub kinds: Vec<EventKind>,
+    /// Only events about these ids, all ids if empty.
+    pub ids: Vec<SonarId>,
+    /// Leave out events that belong to other users.
+    pub user: Option<UserId>,
+    /// Resume after this sequence number, only new events are received if none.
+    pub since: Option<u64>,
+}
+
+impl EventFilter {
+    fn matches(&self, event: &Event) -> bool {
+        (self.kinds.is_empty() || self.kinds.contains(&event.kind))
+            && (self.ids.is_empty() || self.ids.contains(&event.id))
+            && match (self.user, event.user) {
+                (Some(user), Some(event_user)) => user == event_user,
+                _ => true,
+            }
+    }
+}
+
+#[derive(Debug)]
+pub struct EventSubscription {
+    filter: EventFilter,
+    backlog: VecDeque<Event>,
+    receiver: broadcast::Receiver<Event>,
+}
+
+impl EventSubscription {
+    /// Waits for the next event that matches the filter.
+    /// Fails if the subscriber fell too far behind, it can resubscribe from the last sequence
+    /// number it received.
+    pub async fn recv(&mut self) -> Result<Event> {
+        while let Some(event) = self.backlog.pop_front() {
+            if self.filter.matches(&event) {
+                return Ok(event);
+            }
+        }
+        loop {
+            match self.receiver.recv().await {
+                Ok(event) if self.filter.matches(&event) => return Ok(event),
+                Ok(_) => continue,
+                Err(broadcast::error::RecvError::Lagged(count)) => {
+                    return Err(Error::new(
+                        ErrorKind::Internal,
+                        format!("event subscription fell behind by {count} events"),
+                    ))
+                }
+                Err(broadcast::error::RecvError::Closed) => {
+                    return Err(Error::new(ErrorKind::Internal, "event bus closed"))
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+struct EventBusInner {
+    sequence: u64,
+    history: VecDeque<Event>,
+    sender: broadcast::Sender<Event>,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct EventBus(Arc<Mutex<EventBusInner>>);
+
+impl Default for EventBus {
+    fn default() -> Self {
+        let (sender, _) = broadcast::channel(EVENT_CHANNEL_CAPACITY);
+        Self(Arc::new(Mutex::new(EventBusInner {
+            sequence: 0,
+            history: VecDeque::with_capacity(EVENT_HISTORY),
+            sender,
+        })))
+    }
+}
+
+impl EventBus {
+    pub fn publish(&self, kind: EventKind, id: impl Into<SonarId>, user: Option<UserId>) {
+        let mut inner = self.0.lock().unwrap();
+        inner.sequence += 1;
+        let event = Event {
+            sequence: inner.sequence,
+            kind,
+            id: id.into(),
+            user,
+            timestamp: Timestamp::now(),
+        };
+        tracing::trace!("publishing event {event:?}");
+        if inner.history.len() == EVENT_HISTORY {
+            inner.history.pop_front();
+        }
+        inner.history.push_back(event.clone());
+        // sending only fails if there are no subscribers
+        let _ = inner.sender.send(event);
+    }
+
+    pub fn subscribe(&self, filter: EventFilter) -> Result<EventSubscription> {
+        // the lock is held until the receiver is created so no event is missed or received twice
+        let inner = self.0.lock().unwrap();
+        let backlog = match filter.since {
+            Some(since) if since > inner.sequence => {
+                return Err(Error::new(
+                    ErrorKind::Invalid,
+                    format!("event sequence {since} is in the future"),
+                ));
+            }
+            Some(since) => {
+                let oldest = inner
+                    .history
+                    .front()
+                    .map(|event| event.sequence)
+                    .unwrap_or(inner.sequence + 1);
+                if since + 1 < oldest {
+                    return Err(Error::new(
+                        ErrorKind::Invalid,
+                        format!("events after sequence {since} are no longer available"),
+                    ));
+                }
+                inner
+                    .history
+                    .iter()
+                    .filter(|event| event.sequence > since)
+                    .cloned()
+                    .collect()
+            }
+            None => VecDeque::new(),
+        };
+        Ok(EventSubscription {
+            filter,
+            backlog,
+            receiver: inner.sender.subscribe(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_event_kind_roundtrip() {
+        for kind in EventKind::ALL {
+            assert_eq!(kind.as_str().parse::<EventKind>().unwrap(), *kind);
+        }
+        assert!("artist-renamed".parse::<EventKind>().is_err());
+    }
+}
@@ -117,6 +117,7 @@ pub async fn get_albums_artists_map(
     let mut artist_ids = Vec::new();
     for album in albums.into_iter() {
         artist_ids.push(album.artist);
+        artist_ids.extend(album.artists.iter().copied());
     }
     let artists = artist_bulk(context, artist_ids).await?;
     Ok(artists_map(artists))
@@ -129,6 +130,26 @@ pub async fn get_tracks_artists_map(
     let mut artist_ids = Vec::new();
     for track in tracks.into_iter() {
         artist_ids.push(track.artist);
+        artist_ids.extend(track.artists.iter().map(|credit| credit.artist));
+    }
+    let artists = artist_bulk(context, artist_ids).await?;
+    Ok(artists_map(artists))
+}
+
+/// Returns every artist credited on the albums and tracks.
+pub async fn get_credited_artists_map<'a>(
+    context: &Context,
+    albums: impl IntoIterator<Item = &'a Album>,
+    tracks: impl IntoIterator<Item = &'a Track>,
+) -> Result<HashMap<ArtistId, Artist>> {
+    let mut artist_ids = Vec::new();
+    for album in albums.into_iter() {
+        artist_ids.push(album.artist);
+        artist_ids.extend(album.artists.iter().copied());
+    }
+    for track in tracks.into_iter() {
+        artist_ids.push(track.artist);
+        artist_ids.extend(track.artists.iter().map(|credit| credit.artist));
     }
     let artists = artist_bulk(context, artist_ids).await?;
     Ok(artists_map(artists))
@@ -40,6 +40,23 @@ pub(crate) async fn user_list(db: &mut DbC, user_id: UserId) -> Result<Vec<Favor
     Ok(favorites)
 }
 
+/// Lists every item favorited by at least one user.
+pub(crate) async fn list_all(db: &mut DbC) -> Result<Vec<SonarId>> {
+    let rows = sqlx::query("SELECT DISTINCT namespace, identifier FROM favorite")
+        .fetch_all(db)
+        .await?;
+
+    let mut sonar_ids = Vec::with_capacity(rows.len());
+    for row in rows {
+        let namespace = row.get::<u32, _>(0);
+        let identifier = row.get::<u32, _>(1);
+        let sonar_id = SonarId::from_namespace_and_id(namespace, identifier)
+            .expect("invalid sonar_id in database");
+        sonar_ids.push(sonar_id);
+    }
+    Ok(sonar_ids)
+}
+
 pub(crate) async fn user_get_bulk(
     db: &mut DbC,
     user_id: UserId,
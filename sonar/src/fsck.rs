@@ -0,0 +1,278 @@
+//! Verification of the blob storage against the blob table.
+//! Every blob referenced in the database is streamed from storage and its size and sha256 are
+//! compared with the recorded values. Files in storage that no blob references are also reported.
+
+use std::{
+    borrow::Cow,
+    collections::HashSet,
+    str::FromStr,
+    time::{Duration, SystemTime},
+};
+
+use sha2::{Digest, Sha256};
+use tokio_stream::StreamExt;
+
+use crate::{blob::BlobStorage, db::DbC, Result};
+
+/// Prefix under which quarantined files are moved, these are never reported as unreferenced.
+const QUARANTINE_PREFIX: &str = "quarantine/";
+/// Unreferenced files written less than this long ago are kept since they might belong to an
+/// import that has not committed the blob referencing them yet.
+const UNREFERENCED_MIN_AGE: Duration = Duration::from_secs(60 * 60);
+
+#[derive(Debug)]
+pub struct InvalidFsckRepairError {
+    message: Cow<'static, str>,
+}
+
+impl InvalidFsckRepairError {
+    fn new(message: impl Into<Cow<'static, str>>) -> Self {
+        Self {
+            message: message.into(),
+        }
+    }
+}
+
+impl std::fmt::Display for InvalidFsckRepairError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} is not a valid fsck repair mode", self.message)
+    }
+}
+
+impl std::error::Error for InvalidFsckRepairError {}
+
+/// What to do with the files that failed verification.
+/// Blobs that are missing from storage can not be repaired and are only reported.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FsckRepair {
+    #[default]
+    None,
+    /// Move the files under the quarantine prefix.
+    Quarantine,
+    Delete,
+}
+
+impl FsckRepair {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FsckRepair::None => "none",
+            FsckRepair::Quarantine => "quarantine",
+            FsckRepair::Delete => "delete",
+        }
+    }
+}
+
+impl std::fmt::Display for FsckRepair {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for FsckRepair {
+    type Err = InvalidFsckRepairError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "none" => Ok(FsckRepair::None),
+            "quarantine" => Ok(FsckRepair::Quarantine),
+            "delete" => Ok(FsckRepair::Delete),
+            _ => Err(InvalidFsckRepairError::new(s.to_owned())),
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct FsckParams {
+    pub repair: FsckRepair,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FsckIssueKind {
+    /// The blob is referenced but its file does not exist.
+    Missing,
+    /// The file is smaller than the recorded size.
+    Truncated,
+    /// The file size or hash do not match the recorded values.
+    Corrupted,
+    /// The file exists but no blob references it.
+    Unreferenced,
+}
+
+impl FsckIssueKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FsckIssueKind::Missing => "missing",
+            FsckIssueKind::Truncated => "truncated",
+            FsckIssueKind::Corrupted => "corrupted",
+            FsckIssueKind::Unreferenced => "unreferenced",
+        }
+    }
+}
+
+impl std::fmt::Display for FsckIssueKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FsckIssue {
+    pub key: String,
+    pub kind: FsckIssueKind,
+    /// Whether the file was quarantined or deleted.
+    pub repaired: bool,
+}
+
+/// Reported after every blob is checked, `issue` is set if the blob failed verification.
+#[derive(Debug, Clone)]
+pub struct FsckProgress {
+    pub checked: u64,
+    pub total: u64,
+    pub issue: Option<FsckIssue>,
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct FsckReport {
+    pub checked: u64,
+    pub issues: Vec<FsckIssue>,
+}
+
+#[tracing::instrument(skip(db, storage, progress))]
+pub async fn run(
+    db: &mut DbC,
+    storage: &dyn BlobStorage,
+    params: FsckParams,
+    progress: &(dyn Fn(&FsckProgress) + Send + Sync),
+) -> Result<FsckReport> {
+    let unreferenced_cutoff = SystemTime::now() - UNREFERENCED_MIN_AGE;
+    let blobs = sqlx::query_as::<_, (String, i64, String)>(
+        "SELECT key, size, sha256 FROM blob ORDER BY id ASC",
+    )
+    .fetch_all(&mut *db)
+    .await?;
+    let total = blobs.len() as u64;
+
+    let mut report = FsckReport::default();
+    let mut referenced = HashSet::with_capacity(blobs.len());
+    for (key, size, sha256) in blobs {
+        let kind = verify(storage, &key, size as u64, &sha256).await?;
+        let issue = match kind {
+            Some(kind) => Some(repair(storage, params.repair, key.clone(), kind).await?),
+            None => None,
+        };
+        referenced.insert(key);
+        report.checked += 1;
+        report_progress(&mut report, total, issue, progress);
+    }
+
+    for key in storage.list().await? {
+        if referenced.contains(&key) || key.starts_with(QUARANTINE_PREFIX) {
+            continue;
+        }
+        if key_written_at(&key).is_some_and(|written_at| written_at > unreferenced_cutoff) {
+            tracing::debug!("skipping recently written blob {key}");
+            continue;
+        }
+        let issue = repair(storage, params.repair, key, FsckIssueKind::Unreferenced).await?;
+        report_progress(&mut report, total, Some(issue), progress);
+    }
+
+    Ok(report)
+}
+
+fn report_progress(
+    report: &mut FsckReport,
+    total: u64,
+    issue: Option<FsckIssue>,
+    progress: &(dyn Fn(&FsckProgress) + Send + Sync),
+) {
+    if let Some(ref issue) = issue {
+        tracing::warn!("blob {} is {}", issue.key, issue.kind);
+        report.issues.push(issue.clone());
+    }
+    progress(&FsckProgress {
+        checked: report.checked,
+        total,
+        issue,
+    });
+}
+
+/// Returns when the file was written from the ulid at the end of its key.
+/// Keys that don't end in a ulid were not created by sonar and have no known age.
+fn key_written_at(key: &str) -> Option<SystemTime> {
+    let ulid = key.rsplit('/').next()?;
+    ulid.parse::<ulid::Ulid>().ok().map(|ulid| ulid.datetime())
+}
+
+async fn verify(
+    storage: &dyn BlobStorage,
+    key: &str,
+    size: u64,
+    sha256: &str,
+) -> Result<Option<FsckIssueKind>> {
+    let mut stream = match storage.read(key, Default::default()).await {
+        Ok(stream) => stream,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+            return Ok(Some(FsckIssueKind::Missing))
+        }
+        Err(err) => return Err(err.into()),
+    };
+
+    let mut hasher = <Sha256 as Digest>::new();
+    let mut read = 0u64;
+    while let Some(chunk) = stream.next().await {
+        let chunk = chunk?;
+        read += chunk.len() as u64;
+        hasher.update(&chunk);
+    }
+
+    let kind = if read < size {
+        Some(FsckIssueKind::Truncated)
+    } else if read > size || hex::encode(hasher.finalize()) != sha256 {
+        Some(FsckIssueKind::Corrupted)
+    } else {
+        None
+    };
+    Ok(kind)
+}
+
+async fn repair(
+    storage: &dyn BlobStorage,
+    mode: FsckRepair,
+    key: String,
+    kind: FsckIssueKind,
+) -> Result<FsckIssue> {
+    let repaired = match (mode, kind) {
+        (FsckRepair::None, _) | (_, FsckIssueKind::Missing) => false,
+        (FsckRepair::Quarantine, _) => {
+            let stream = storage.read(&key, Default::default()).await?;
+            storage
+                .write(&format!("{QUARANTINE_PREFIX}{key}"), stream)
+                .await?;
+            storage.delete(&key).await?;
+            true
+        }
+        (FsckRepair::Delete, _) => {
+            storage.delete(&key).await?;
+            true
+        }
+    };
+    Ok(FsckIssue {
+        key,
+        kind,
+        repaired,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_fsck_repair_roundtrip() {
+        for repair in [FsckRepair::None, FsckRepair::Quarantine, FsckRepair::Delete] {
+            assert_eq!(repair.as_str().parse::<FsckRepair>().unwrap(), repair);
+        }
+        assert!("fix".parse::<FsckRepair>().is_err());
+    }
+}
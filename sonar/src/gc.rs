@@ -5,14 +5,50 @@
 //! not somehow marked as "liked" or "saved" they should be eventually deleted.
 //! To do this we need will garbage collect artists, albums and tracks.
 //! One of this items is a candidate for garbage collection if:
-//! - It is not part of any playlist
-//! - It is not pinned by any user
-//! - None of its ancestors are pinned or part of any playlist
-//! - None of its descendants are pinned or part of any playlist
+//! - It is not part of any playlist other than a smart playlist
+//! - It is not pinned, favorited, rated or scrobbled by any user
+//! - None of its ancestors are pinned, favorited or part of any playlist
+//! - None of its descendants are pinned, favorited or part of any playlist
+//!
+//! Candidates are only deleted once they are older than the configured minimum age.
+//! Deleting a track also deletes any audio that is not used by another track,
+//! cover art is deleted once nothing else references it.
+//! Ratings and scrobbles are never deleted so the listening history of every user is kept.
 
-use std::collections::{HashMap, HashSet};
+use std::{
+    collections::{HashMap, HashSet},
+    time::Duration,
+};
 
-use crate::{album, artist, db::DbC, pin, playlist, track, Result, SonarId};
+use crate::{
+    album, artist, audio, db::DbC, favorite, image, pin, playlist, rating, scrobble,
+    smart_playlist, track, AlbumId, ArtistId, AudioId, ImageId, PlaylistId, Result, SonarId,
+    TrackId, UserId,
+};
+
+#[derive(Debug, Clone)]
+pub struct GarbageCollectParams {
+    /// Candidates created less than this long ago are kept.
+    pub min_age: Duration,
+    /// Only report what would be deleted.
+    pub dry_run: bool,
+}
+
+impl Default for GarbageCollectParams {
+    fn default() -> Self {
+        Self {
+            min_age: Duration::from_secs(30 * 24 * 60 * 60),
+            dry_run: false,
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct GarbageCollectReport {
+    /// The deleted artists, albums, tracks, audios and images.
+    /// On a dry run these are the items that would have been deleted.
+    pub items: Vec<SonarId>,
+}
 
 pub async fn list_gc_candidates(db: &mut DbC) -> Result<Vec<SonarId>> {
     struct Rels {
@@ -20,7 +56,18 @@ pub async fn list_gc_candidates(db: &mut DbC) -> Result<Vec<SonarId>> {
         children: Vec<SonarId>,
     }
 
-    let user_pinned: HashSet<SonarId> = pin::list_all(db).await?.into_iter().collect();
+    let user_pinned: HashSet<SonarId> = pin::list_all(db)
+        .await?
+        .into_iter()
+        .chain(favorite::list_all(db).await?)
+        .chain(rating::list_all(db).await?)
+        .chain(
+            scrobble::list_scrobbled_tracks(db)
+                .await?
+                .into_iter()
+                .map(SonarId::from),
+        )
+        .collect();
     let playlist_pinned: HashSet<SonarId> = playlist::list_tracks_in_all_playlists(db)
         .await?
         .into_iter()
@@ -60,7 +107,7 @@ pub async fn list_gc_candidates(db: &mut DbC) -> Result<Vec<SonarId>> {
             })
             .parent = Some(artist);
     }
-    for (track, album) in track_album_pairs {
+    for (album, track) in track_album_pairs {
         let track = SonarId::from(track);
         let album = SonarId::from(album);
         rels.entry(album)
@@ -95,3 +142,175 @@ pub async fn list_gc_candidates(db: &mut DbC) -> Result<Vec<SonarId>> {
 
     Ok(candidates.into_iter().collect())
 }
+
+/// Collects the garbage, returning the report along with the keys of the blobs that must be
+/// removed from storage once the transaction commits.
+#[tracing::instrument(skip(db))]
+pub async fn run(
+    db: &mut DbC,
+    params: GarbageCollectParams,
+) -> Result<(GarbageCollectReport, Vec<String>)> {
+    let candidates: HashSet<SonarId> = list_gc_candidates(db).await?.into_iter().collect();
+    let min_age = params.min_age.as_secs() as i64;
+
+    let tracks: HashSet<TrackId> = list_expired(db, "track", min_age)
+        .await?
+        .into_iter()
+        .map(TrackId::from_db)
+        .filter(|id| candidates.contains(&SonarId::from(*id)))
+        .collect();
+
+    // albums and artists are only deleted if everything that depends on them is also deleted
+    let mut kept_albums = HashSet::new();
+    for (album, track) in track::list_album_id_pairs(db).await? {
+        if !tracks.contains(&track) {
+            kept_albums.insert(album);
+        }
+    }
+    let albums: HashSet<AlbumId> = list_expired(db, "album", min_age)
+        .await?
+        .into_iter()
+        .map(AlbumId::from_db)
+        .filter(|id| candidates.contains(&SonarId::from(*id)) && !kept_albums.contains(id))
+        .collect();
+
+    let mut kept_artists = HashSet::new();
+    for (album, artist) in album::list_artist_id_pairs(db).await? {
+        if !albums.contains(&album) {
+            kept_artists.insert(artist);
+        }
+    }
+    for (artist, album) in sqlx::query_as::<_, (i64, i64)>("SELECT artist, album FROM album_credit")
+        .fetch_all(&mut *db)
+        .await?
+    {
+        if !albums.contains(&AlbumId::from_db(album)) {
+            kept_artists.insert(ArtistId::from_db(artist));
+        }
+    }
+    for (artist, track) in sqlx::query_as::<_, (i64, i64)>("SELECT artist, track FROM track_credit")
+        .fetch_all(&mut *db)
+        .await?
+    {
+        if !tracks.contains(&TrackId::from_db(track)) {
+            kept_artists.insert(ArtistId::from_db(artist));
+        }
+    }
+    let artists: HashSet<ArtistId> = list_expired(db, "artist", min_age)
+        .await?
+        .into_iter()
+        .map(ArtistId::from_db)
+        .filter(|id| candidates.contains(&SonarId::from(*id)) && !kept_artists.contains(id))
+        .collect();
+
+    let mut deleted: HashSet<SonarId> = HashSet::new();
+    deleted.extend(tracks.iter().copied().map(SonarId::from));
+    deleted.extend(albums.iter().copied().map(SonarId::from));
+    deleted.extend(artists.iter().copied().map(SonarId::from));
+
+    let mut audio_tracks: HashMap<AudioId, Vec<TrackId>> = HashMap::new();
+    for (track, audio) in sqlx::query_as::<_, (i64, i64)>("SELECT track, audio FROM track_audio")
+        .fetch_all(&mut *db)
+        .await?
+    {
+        audio_tracks
+            .entry(AudioId::from_db(audio))
+            .or_default()
+            .push(TrackId::from_db(track));
+    }
+    let audios: Vec<AudioId> = audio_tracks
+        .into_iter()
+        .filter(|(_, users)| users.iter().all(|track| tracks.contains(track)))
+        .map(|(audio, _)| audio)
+        .collect();
+
+    let images: Vec<ImageId> = list_image_references(db)
+        .await?
+        .into_iter()
+        .filter(|(_, users)| users.iter().all(|id| deleted.contains(id)))
+        .map(|(image, _)| image)
+        .collect();
+
+    let mut report = GarbageCollectReport::default();
+    report
+        .items
+        .extend(tracks.iter().copied().map(SonarId::from));
+    report
+        .items
+        .extend(albums.iter().copied().map(SonarId::from));
+    report
+        .items
+        .extend(artists.iter().copied().map(SonarId::from));
+    report
+        .items
+        .extend(audios.iter().copied().map(SonarId::from));
+    report
+        .items
+        .extend(images.iter().copied().map(SonarId::from));
+    if params.dry_run {
+        return Ok((report, Vec::new()));
+    }
+
+    // smart playlists may still list collected tracks until their next refresh
+    let collected_tracks: Vec<TrackId> = tracks.iter().copied().collect();
+    for playlist_id in smart_playlist::list_ids(db).await? {
+        playlist::remove_tracks(db, playlist_id, &collected_tracks).await?;
+    }
+    for &track_id in tracks.iter() {
+        tracing::info!("garbage collecting track {}", track_id);
+        track::delete(db, track_id).await?;
+    }
+    for &album_id in albums.iter() {
+        tracing::info!("garbage collecting album {}", album_id);
+        album::delete(db, album_id).await?;
+    }
+    for &artist_id in artists.iter() {
+        tracing::info!("garbage collecting artist {}", artist_id);
+        artist::delete(db, artist_id).await?;
+    }
+    let mut keys = Vec::new();
+    for &audio_id in audios.iter() {
+        keys.extend(audio::delete(db, audio_id).await?);
+    }
+    for &image_id in images.iter() {
+        keys.extend(image::delete(db, image_id).await?);
+    }
+
+    Ok((report, keys))
+}
+
+/// Lists the ids of the rows in `table` created at least `min_age` seconds ago.
+async fn list_expired(db: &mut DbC, table: &str, min_age: i64) -> Result<Vec<i64>> {
+    let query = format!("SELECT id FROM {table} WHERE created_at <= unixepoch() - ?");
+    let ids = sqlx::query_scalar(&query)
+        .bind(min_age)
+        .fetch_all(&mut *db)
+        .await?;
+    Ok(ids)
+}
+
+/// Lists every image along with the items that use it.
+async fn list_image_references(db: &mut DbC) -> Result<HashMap<ImageId, Vec<SonarId>>> {
+    let sources: [(&str, &str, fn(i64) -> SonarId); 5] = [
+        ("artist", "cover_art", |id| ArtistId::from_db(id).into()),
+        ("album", "cover_art", |id| AlbumId::from_db(id).into()),
+        ("track", "cover_art", |id| TrackId::from_db(id).into()),
+        ("playlist", "cover_art", |id| PlaylistId::from_db(id).into()),
+        ("user", "avatar", |id| UserId::from_db(id).into()),
+    ];
+
+    let mut references: HashMap<ImageId, Vec<SonarId>> = HashMap::new();
+    for (table, column, to_sonar_id) in sources {
+        let query = format!("SELECT id, {column} FROM {table} WHERE {column} IS NOT NULL");
+        let rows = sqlx::query_as::<_, (i64, i64)>(&query)
+            .fetch_all(&mut *db)
+            .await?;
+        for (id, image) in rows {
+            references
+                .entry(ImageId::from_db(image))
+                .or_default()
+                .push(to_sonar_id(id));
+        }
+    }
+    Ok(references)
+}
@@ -13,6 +13,11 @@ pub(crate) const ID_NAMESPACE_USER: u32 = 7;
 pub(crate) const ID_NAMESPACE_LYRICS: u32 = 8;
 pub(crate) const ID_NAMESPACE_SCROBBLE: u32 = 9;
 pub(crate) const ID_NAMESPACE_SUBSCRIPTION: u32 = 10;
+pub(crate) const ID_NAMESPACE_SESSION: u32 = 11;
+pub(crate) const ID_NAMESPACE_APIKEY: u32 = 12;
+pub(crate) const ID_NAMESPACE_RADIO: u32 = 13;
+pub(crate) const ID_NAMESPACE_WEBHOOK: u32 = 14;
+pub(crate) const ID_NAMESPACE_LIBRARY: u32 = 15;
 
 const ID_NAMESPACE_ARTIST_STR: &str = "artist";
 const ID_NAMESPACE_ALBUM_STR: &str = "album";
@@ -24,6 +29,11 @@ const ID_NAMESPACE_USER_STR: &str = "user";
 const ID_NAMESPACE_LYRICS_STR: &str = "lyrics";
 const ID_NAMESPACE_SCROBBLE_STR: &str = "scrobble";
 const ID_NAMESPACE_SUBSCRIPTION_STR: &str = "subscription";
+const ID_NAMESPACE_SESSION_STR: &str = "session";
+const ID_NAMESPACE_APIKEY_STR: &str = "apikey";
+const ID_NAMESPACE_RADIO_STR: &str = "radio";
+const ID_NAMESPACE_WEBHOOK_STR: &str = "webhook";
+const ID_NAMESPACE_LIBRARY_STR: &str = "library";
 
 #[derive(Debug)]
 pub struct InvalidIdError {
@@ -86,7 +96,7 @@ macro_rules! impl_id {
 
         #[allow(dead_code)]
         impl $t {
-            pub(crate) fn from_db(id: i64) -> Self {
+            pub(crate) const fn from_db(id: i64) -> Self {
                 Self(id as u32 | $k << ID_NAMESPACE_SHIFT)
             }
 
@@ -184,6 +194,11 @@ impl_id!(
     "subscription",
     ID_NAMESPACE_SUBSCRIPTION
 );
+impl_id!(UserSessionId, UserSession, "session", ID_NAMESPACE_SESSION);
+impl_id!(ApiKeyId, ApiKey, "apikey", ID_NAMESPACE_APIKEY);
+impl_id!(RadioId, Radio, "radio", ID_NAMESPACE_RADIO);
+impl_id!(WebhookId, Webhook, "webhook", ID_NAMESPACE_WEBHOOK);
+impl_id!(LibraryId, Library, "library", ID_NAMESPACE_LIBRARY);
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum SonarId {
@@ -197,6 +212,11 @@ pub enum SonarId {
     Lyrics(LyricsId),
     Scrobble(ScrobbleId),
     Subscription(SubscriptionId),
+    UserSession(UserSessionId),
+    ApiKey(ApiKeyId),
+    Radio(RadioId),
+    Webhook(WebhookId),
+    Library(LibraryId),
 }
 
 impl std::fmt::Display for SonarId {
@@ -214,6 +234,11 @@ impl std::fmt::Display for SonarId {
             ID_NAMESPACE_LYRICS => write!(f, "{}", ID_NAMESPACE_LYRICS_STR)?,
             ID_NAMESPACE_SCROBBLE => write!(f, "{}", ID_NAMESPACE_SCROBBLE_STR)?,
             ID_NAMESPACE_SUBSCRIPTION => write!(f, "{}", ID_NAMESPACE_SUBSCRIPTION_STR)?,
+            ID_NAMESPACE_SESSION => write!(f, "{}", ID_NAMESPACE_SESSION_STR)?,
+            ID_NAMESPACE_APIKEY => write!(f, "{}", ID_NAMESPACE_APIKEY_STR)?,
+            ID_NAMESPACE_RADIO => write!(f, "{}", ID_NAMESPACE_RADIO_STR)?,
+            ID_NAMESPACE_WEBHOOK => write!(f, "{}", ID_NAMESPACE_WEBHOOK_STR)?,
+            ID_NAMESPACE_LIBRARY => write!(f, "{}", ID_NAMESPACE_LIBRARY_STR)?,
             _ => unreachable!(),
         };
         write!(f, ":{:x}", id)
@@ -241,6 +266,11 @@ impl TryFrom<u32> for SonarId {
             ID_NAMESPACE_LYRICS => Ok(Self::Lyrics(LyricsId::try_from(id)?)),
             ID_NAMESPACE_SCROBBLE => Ok(Self::Scrobble(ScrobbleId::try_from(id)?)),
             ID_NAMESPACE_SUBSCRIPTION => Ok(Self::Subscription(SubscriptionId::try_from(id)?)),
+            ID_NAMESPACE_SESSION => Ok(Self::UserSession(UserSessionId::try_from(id)?)),
+            ID_NAMESPACE_APIKEY => Ok(Self::ApiKey(ApiKeyId::try_from(id)?)),
+            ID_NAMESPACE_RADIO => Ok(Self::Radio(RadioId::try_from(id)?)),
+            ID_NAMESPACE_WEBHOOK => Ok(Self::Webhook(WebhookId::try_from(id)?)),
+            ID_NAMESPACE_LIBRARY => Ok(Self::Library(LibraryId::try_from(id)?)),
             _ => Err(InvalidIdError::new(id, "unknown ID type")),
         }
     }
@@ -259,6 +289,11 @@ impl From<SonarId> for u32 {
             SonarId::Lyrics(id) => id.into(),
             SonarId::Scrobble(id) => id.into(),
             SonarId::Subscription(id) => id.into(),
+            SonarId::UserSession(id) => id.into(),
+            SonarId::ApiKey(id) => id.into(),
+            SonarId::Radio(id) => id.into(),
+            SonarId::Webhook(id) => id.into(),
+            SonarId::Library(id) => id.into(),
         }
     }
 }
@@ -296,6 +331,11 @@ impl FromStr for SonarId {
             ID_NAMESPACE_LYRICS_STR => Ok(Self::Lyrics(LyricsId::try_from(id)?)),
             ID_NAMESPACE_SCROBBLE_STR => Ok(Self::Scrobble(ScrobbleId::try_from(id)?)),
             ID_NAMESPACE_SUBSCRIPTION_STR => Ok(Self::Subscription(SubscriptionId::try_from(id)?)),
+            ID_NAMESPACE_SESSION_STR => Ok(Self::UserSession(UserSessionId::try_from(id)?)),
+            ID_NAMESPACE_APIKEY_STR => Ok(Self::ApiKey(ApiKeyId::try_from(id)?)),
+            ID_NAMESPACE_RADIO_STR => Ok(Self::Radio(RadioId::try_from(id)?)),
+            ID_NAMESPACE_WEBHOOK_STR => Ok(Self::Webhook(WebhookId::try_from(id)?)),
+            ID_NAMESPACE_LIBRARY_STR => Ok(Self::Library(LibraryId::try_from(id)?)),
             _ => Err(InvalidIdError::new(id, "unknown ID type")),
         }
     }
@@ -314,6 +354,11 @@ impl SonarIdentifier for SonarId {
             SonarId::Lyrics(id) => id.name(),
             SonarId::Scrobble(id) => id.name(),
             SonarId::Subscription(id) => id.name(),
+            SonarId::UserSession(id) => id.name(),
+            SonarId::ApiKey(id) => id.name(),
+            SonarId::Radio(id) => id.name(),
+            SonarId::Webhook(id) => id.name(),
+            SonarId::Library(id) => id.name(),
         }
     }
 
@@ -329,6 +374,11 @@ impl SonarIdentifier for SonarId {
             SonarId::Lyrics(id) => id.namespace(),
             SonarId::Scrobble(id) => id.namespace(),
             SonarId::Subscription(id) => id.namespace(),
+            SonarId::UserSession(id) => id.namespace(),
+            SonarId::ApiKey(id) => id.namespace(),
+            SonarId::Radio(id) => id.namespace(),
+            SonarId::Webhook(id) => id.namespace(),
+            SonarId::Library(id) => id.namespace(),
         }
     }
 
@@ -344,6 +394,11 @@ impl SonarIdentifier for SonarId {
             SonarId::Lyrics(id) => id.identifier(),
             SonarId::Scrobble(id) => id.identifier(),
             SonarId::Subscription(id) => id.identifier(),
+            SonarId::UserSession(id) => id.identifier(),
+            SonarId::ApiKey(id) => id.identifier(),
+            SonarId::Radio(id) => id.identifier(),
+            SonarId::Webhook(id) => id.identifier(),
+            SonarId::Library(id) => id.identifier(),
         }
     }
 }
@@ -466,4 +521,64 @@ mod test {
         assert_eq!(ScrobbleId::from_db(1), id);
         assert_eq!(id.to_string(), "sonar:scrobble:9000001");
     }
+
+    #[test]
+    fn test_user_session_id() {
+        let id = UserSessionId::try_from(0x0B000001).unwrap();
+        assert_eq!(id, UserSessionId(0x0B000001));
+        assert_eq!(id.name(), "session");
+        assert_eq!(id.namespace(), ID_NAMESPACE_SESSION);
+        assert_eq!(id.identifier(), 1);
+        assert_eq!(id.to_db(), 1);
+        assert_eq!(UserSessionId::from_db(1), id);
+        assert_eq!(id.to_string(), "sonar:session:b000001");
+    }
+
+    #[test]
+    fn test_apikey_id() {
+        let id = ApiKeyId::try_from(0x0C000001).unwrap();
+        assert_eq!(id, ApiKeyId(0x0C000001));
+        assert_eq!(id.name(), "apikey");
+        assert_eq!(id.namespace(), ID_NAMESPACE_APIKEY);
+        assert_eq!(id.identifier(), 1);
+        assert_eq!(id.to_db(), 1);
+        assert_eq!(ApiKeyId::from_db(1), id);
+        assert_eq!(id.to_string(), "sonar:apikey:c000001");
+    }
+
+    #[test]
+    fn test_radio_id() {
+        let id = RadioId::try_from(0x0D000001).unwrap();
+        assert_eq!(id, RadioId(0x0D000001));
+        assert_eq!(id.name(), "radio");
+        assert_eq!(id.namespace(), ID_NAMESPACE_RADIO);
+        assert_eq!(id.identifier(), 1);
+        assert_eq!(id.to_db(), 1);
+        assert_eq!(RadioId::from_db(1), id);
+        assert_eq!(id.to_string(), "sonar:radio:d000001");
+    }
+
+    #[test]
+    fn test_webhook_id() {
+        let id = WebhookId::try_from(0x0E000001).unwrap();
+        assert_eq!(id, WebhookId(0x0E000001));
+        assert_eq!(id.name(), "webhook");
+        assert_eq!(id.namespace(), ID_NAMESPACE_WEBHOOK);
+        assert_eq!(id.identifier(), 1);
+        assert_eq!(id.to_db(), 1);
+        assert_eq!(WebhookId::from_db(1), id);
+        assert_eq!(id.to_string(), "sonar:webhook:e000001");
+    }
+
+    #[test]
+    fn test_library_id() {
+        let id = LibraryId::try_from(0x0F000001).unwrap();
+        assert_eq!(id, LibraryId(0x0F000001));
+        assert_eq!(id.name(), "library");
+        assert_eq!(id.namespace(), ID_NAMESPACE_LIBRARY);
+        assert_eq!(id.identifier(), 1);
+        assert_eq!(id.to_db(), 1);
+        assert_eq!(LibraryId::from_db(1), id);
+        assert_eq!(id.to_string(), "sonar:library:f000001");
+    }
 }
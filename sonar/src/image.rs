@@ -5,7 +5,7 @@ use crate::{
     blob::{self, BlobStorage},
     bytestream::{self, ByteStream},
     db::DbC,
-    ks, Error, ErrorKind, ImageId, Result,
+    Error, ErrorKind, ImageId, Result,
 };
 
 pub struct ImageCreate {
@@ -64,11 +64,8 @@ pub async fn create(
     storage: &dyn BlobStorage,
     create: ImageCreate,
 ) -> Result<ImageId> {
-    let blob_key = blob::random_key_with_prefix("image");
     let img_file = tempfile::NamedTempFile::new()?;
     bytestream::to_file(create.data, img_file.path()).await?;
-    let blob_sha256 = ks::sha256_file(img_file.path()).await?;
-    let blob_size = img_file.path().metadata()?.len() as u32;
 
     let mime_type = match infer::get_from_path(img_file.path())? {
         Some(ty) => match ty.extension() {
@@ -81,16 +78,7 @@ pub async fn create(
         return Err(Error::new(ErrorKind::Invalid, "invalid image type"));
     }
 
-    let stream = bytestream::from_file(img_file.path()).await?;
-    storage.write(&blob_key, stream).await?;
-    let blob_id = sqlx::query_scalar::<_, i64>(
-        "INSERT INTO blob (key, size, sha256) VALUES (?, ?, ?) RETURNING id",
-    )
-    .bind(blob_key)
-    .bind(blob_size)
-    .bind(blob_sha256)
-    .fetch_one(&mut *db)
-    .await?;
+    let blob_id = blob::create_from_file(db, storage, "image", img_file.path()).await?;
 
     let db_id =
         sqlx::query_scalar("INSERT INTO image (mime_type, blob) VALUES (?, ?) RETURNING id")
@@ -102,11 +90,15 @@ pub async fn create(
     Ok(ImageId::from_db(db_id))
 }
 
+/// Deletes the image, returning the key of its blob if it must be removed from storage.
 #[tracing::instrument(skip(db))]
-pub async fn delete(db: &mut DbC, image_id: ImageId) -> Result<()> {
-    sqlx::query("DELETE FROM image WHERE id = ?")
+pub async fn delete(db: &mut DbC, image_id: ImageId) -> Result<Option<String>> {
+    let blob_id = sqlx::query_scalar::<_, i64>("DELETE FROM image WHERE id = ? RETURNING blob")
         .bind(image_id)
-        .execute(db)
+        .fetch_optional(&mut *db)
         .await?;
-    Ok(())
+    match blob_id {
+        Some(blob_id) => blob::release(db, blob_id).await,
+        None => Ok(None),
+    }
 }
@@ -6,8 +6,8 @@ use crate::{
     bytestream::{self, ByteStream},
     db::Db,
     extractor::SonarExtractor,
-    track, AlbumCreate, AlbumId, ArtistCreate, ArtistId, AudioCreate, Error, ErrorKind, Properties,
-    PropertyValue, Result, Track, TrackCreate,
+    track, AlbumCreate, AlbumId, ArtistCreate, ArtistId, AudioCreate, Error, ErrorKind, LibraryId,
+    Properties, PropertyValue, Result, Track, TrackCreate,
 };
 
 #[derive(Debug)]
@@ -19,6 +19,9 @@ pub struct Config {
 pub struct Import {
     pub artist: Option<ArtistId>,
     pub album: Option<AlbumId>,
+    /// library new artists are created in, the default library if none.
+    /// new albums and tracks are in the library of their artist.
+    pub library: Option<LibraryId>,
     pub filepath: Option<String>,
     pub stream: ByteStream,
 }
@@ -28,6 +31,7 @@ impl std::fmt::Debug for Import {
         f.debug_struct("Import")
             .field("artist", &self.artist)
             .field("album", &self.album)
+            .field("library", &self.library)
             .field("filename", &self.filepath)
             .finish()
     }
@@ -170,6 +174,7 @@ pub async fn import(
             cover_art: Default::default(),
             genres: Default::default(),
             properties: Default::default(),
+            library: import.library,
         };
 
         artist::find_or_create_by_name_tx(db, artist_create)
@@ -198,6 +203,7 @@ pub async fn import(
         let album_create = AlbumCreate {
             name: album_name.to_owned(),
             artist: artist_id,
+            artists: Default::default(),
             cover_art: Default::default(),
             genres: Default::default(),
             properties: Default::default(),
@@ -236,6 +242,7 @@ pub async fn import(
     let track_create = TrackCreate {
         name: track_name.to_owned(),
         album: album_id,
+        artists: Default::default(),
         cover_art: None, // TODO: extract cover art
         lyrics: None,    // TODO: extract lyrics
         audio: Some(audio.id),
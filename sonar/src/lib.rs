@@ -29,32 +29,51 @@ pub mod test;
 pub(crate) mod album;
 pub(crate) mod artist;
 pub(crate) mod audio;
+pub(crate) mod backup;
 pub(crate) mod blob;
+pub(crate) mod bookmark;
+pub(crate) mod credit;
 pub(crate) mod db;
 pub(crate) mod download;
+pub(crate) mod event;
 pub(crate) mod external;
 pub(crate) mod extractor;
 pub(crate) mod favorite;
+pub(crate) mod fsck;
 pub(crate) mod gc;
 pub(crate) mod genre;
 pub(crate) mod image;
 pub(crate) mod importer;
 pub(crate) mod ks;
+pub(crate) mod library;
 pub(crate) mod metadata;
 pub(crate) mod migrations;
 pub(crate) mod pin;
+pub(crate) mod play_queue;
 pub(crate) mod playlist;
 pub(crate) mod property;
+pub(crate) mod radio;
+pub(crate) mod rating;
+pub(crate) mod recommend;
 pub(crate) mod scrobble;
+pub(crate) mod scrobble_import;
 pub(crate) mod scrobbler;
 pub(crate) mod search;
+pub(crate) mod smart_playlist;
+pub(crate) mod stats;
 pub(crate) mod subscription;
+pub(crate) mod sweep;
 pub(crate) mod track;
 pub(crate) mod user;
+pub(crate) mod webhook;
 
 pub use album::{Album, AlbumCreate, AlbumUpdate};
 pub use artist::{Artist, ArtistCreate, ArtistUpdate};
 pub use audio::{Audio, AudioCreate, AudioDownload, AudioStat};
+pub use backup::{BackupReport, RestoreReport};
+pub use bookmark::{Bookmark, BookmarkCreate};
+pub use credit::{ArtistCredit, ArtistRole, InvalidArtistRoleError};
+pub use event::{Event, EventFilter, EventKind, EventSubscription, InvalidEventKindError};
 pub use external::{
     ExternalAlbum, ExternalArtist, ExternalCompilation, ExternalCompilationTrack, ExternalImage,
     ExternalMediaEnrichStatus, ExternalMediaId, ExternalMediaRequest, ExternalMediaType,
@@ -62,9 +81,15 @@ pub use external::{
 };
 pub use extractor::{ExtractedImage, ExtractedMetadata, Extractor};
 pub use favorite::Favorite;
+pub use fsck::{
+    FsckIssue, FsckIssueKind, FsckParams, FsckProgress, FsckRepair, FsckReport,
+    InvalidFsckRepairError,
+};
+pub use gc::{GarbageCollectParams, GarbageCollectReport};
 pub use genre::{Genre, GenreUpdate, GenreUpdateAction, Genres, InvalidGenreError};
 pub use image::{ImageCreate, ImageDownload};
 pub use importer::Import;
+pub use library::{Library, LibraryCreate, LibraryFilter, LibraryUpdate};
 pub use metadata::{
     AlbumMetadata, AlbumMetadataRequest, AlbumTracksMetadata, AlbumTracksMetadataRequest,
     ArtistMetadata, ArtistMetadataRequest, MetadataFetchMask, MetadataFetchParams,
@@ -72,21 +97,41 @@ pub use metadata::{
     METADATA_FETCH_MASK_ALL, METADATA_FETCH_MASK_COVER, METADATA_FETCH_MASK_EMPTY,
     METADATA_FETCH_MASK_GENRES, METADATA_FETCH_MASK_NAME, METADATA_FETCH_MASK_PROPERTIES,
 };
+pub use play_queue::{PlayQueue, PlayQueueSave};
 pub use playlist::{Playlist, PlaylistCreate, PlaylistTrack, PlaylistUpdate};
 pub use property::{
     InvalidPropertyKeyError, InvalidPropertyValueError, Properties, PropertyKey, PropertyUpdate,
     PropertyUpdateAction, PropertyValue,
 };
+pub use radio::{InvalidRadioSeedError, Radio, RadioCreate, RadioSeed};
+pub use rating::{AverageRating, InvalidRatingError, Rating, UserRating};
+pub use recommend::{DailyMix, DailyMixParams};
 pub use scrobble::{Scrobble, ScrobbleCreate, ScrobbleUpdate};
+pub use scrobble_import::{
+    InvalidScrobbleImportFormatError, ScrobbleImport, ScrobbleImportEntry, ScrobbleImportFormat,
+    ScrobbleImportReport,
+};
 pub use scrobbler::Scrobbler;
-pub use search::{SearchFlags, SearchQuery, SearchResult};
+pub use search::{
+    InvalidSearchQueryError, SearchClause, SearchExpression, SearchFilter, SearchFlags,
+    SearchQuery, SearchRange, SearchResult,
+};
+pub use smart_playlist::{
+    InvalidSmartPlaylistRuleError, InvalidSmartPlaylistSortError, SmartPlaylist,
+    SmartPlaylistCreate, SmartPlaylistRule, SmartPlaylistRules, SmartPlaylistSort,
+};
+pub use stats::{InvalidStatsPeriodError, Stats, StatsEntry, StatsParams, StatsPeriod};
 pub use subscription::{Subscription, SubscriptionCreate, SubscriptionMediaType};
+pub use sweep::{SweepParams, SweepReport};
 pub use track::{
     Lyrics, LyricsKind, LyricsLine, Track, TrackCreate, TrackListRandom, TrackLyrics, TrackUpdate,
 };
 pub use user::{
-    InvalidUserTokenError, InvalidUsernameError, User, UserCreate, UserToken, UserUpdate, Username,
+    ApiKey, ApiKeyAccess, ApiKeyCreate, ApiKeyScope, InvalidApiKeyScopeError, InvalidUserRoleError,
+    InvalidUserTokenError, InvalidUsernameError, User, UserCreate, UserLoginParams, UserRole,
+    UserSession, UserToken, UserUpdate, Username,
 };
+pub use webhook::{Webhook, WebhookCreate, WebhookDelivery, WebhookDeliveryStatus, WebhookUpdate};
 
 pub use async_trait::async_trait;
 pub use bytes;
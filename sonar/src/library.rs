@@ -0,0 +1,253 @@
+//! Libraries partition artists, albums and tracks.
+//!
+//! Albums are in the library of their artist and tracks in the library of their album. Users only
+//! see the media of the libraries they were granted access to, admins see every library.
+
+use crate::{db::DbC, user, Error, ErrorKind, LibraryId, Result, Timestamp, UserId, ValueUpdate};
+
+impl LibraryId {
+    /// The library existing media was migrated to and new media is added to by default.
+    pub const DEFAULT: LibraryId = LibraryId::from_db(1);
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Library {
+    pub id: LibraryId,
+    pub name: String,
+    pub created_at: Timestamp,
+}
+
+#[derive(Debug, Clone)]
+pub struct LibraryCreate {
+    pub name: String,
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct LibraryUpdate {
+    pub name: ValueUpdate<String>,
+}
+
+/// Restricts listings to the media of some libraries.
+/// The default filter does not restrict anything.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct LibraryFilter {
+    /// Only libraries this user has access to.
+    pub user: Option<UserId>,
+    /// Only this library, which the user must have access to.
+    pub library: Option<LibraryId>,
+}
+
+impl LibraryFilter {
+    pub fn user(user_id: UserId) -> Self {
+        Self {
+            user: Some(user_id),
+            library: None,
+        }
+    }
+
+    pub fn with_library(mut self, library: Option<LibraryId>) -> Self {
+        self.library = library;
+        self
+    }
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct LibraryView {
+    id: i64,
+    name: String,
+    created_at: i64,
+}
+
+impl From<LibraryView> for Library {
+    fn from(value: LibraryView) -> Self {
+        Self {
+            id: LibraryId::from_db(value.id),
+            name: value.name,
+            created_at: Timestamp::from_seconds(value.created_at as u64),
+        }
+    }
+}
+
+#[tracing::instrument(skip(db))]
+pub async fn list(db: &mut DbC) -> Result<Vec<Library>> {
+    let views = sqlx::query_as::<_, LibraryView>("SELECT * FROM library ORDER BY id ASC")
+        .fetch_all(db)
+        .await?;
+    Ok(views.into_iter().map(Library::from).collect())
+}
+
+/// Lists the libraries the user has access to.
+#[tracing::instrument(skip(db))]
+pub async fn list_by_user(db: &mut DbC, user_id: UserId) -> Result<Vec<Library>> {
+    let user = user::get(db, user_id).await?;
+    if user.admin {
+        return list(db).await;
+    }
+    let views = sqlx::query_as::<_, LibraryView>(
+        "SELECT library.* FROM library INNER JOIN library_user ON library_user.library = library.id
+        WHERE library_user.user = ? ORDER BY library.id ASC",
+    )
+    .bind(user_id)
+    .fetch_all(db)
+    .await?;
+    Ok(views.into_iter().map(Library::from).collect())
+}
+
+#[tracing::instrument(skip(db))]
+pub async fn get(db: &mut DbC, library_id: LibraryId) -> Result<Library> {
+    let view = sqlx::query_as::<_, LibraryView>("SELECT * FROM library WHERE id = ?")
+        .bind(library_id)
+        .fetch_optional(db)
+        .await?;
+    match view {
+        Some(view) => Ok(Library::from(view)),
+        None => Err(Error::new(ErrorKind::NotFound, "library not found")),
+    }
+}
+
+#[tracing::instrument(skip(db))]
+pub async fn create(db: &mut DbC, create: LibraryCreate) -> Result<Library> {
+    validate_name(db, &create.name).await?;
+    let library_id = sqlx::query_scalar("INSERT INTO library (name) VALUES (?) RETURNING id")
+        .bind(&create.name)
+        .fetch_one(&mut *db)
+        .await?;
+    get(db, LibraryId::from_db(library_id)).await
+}
+
+#[tracing::instrument(skip(db))]
+pub async fn update(db: &mut DbC, library_id: LibraryId, update: LibraryUpdate) -> Result<Library> {
+    let library = get(db, library_id).await?;
+    match update.name {
+        ValueUpdate::Set(name) if name != library.name => {
+            validate_name(db, &name).await?;
+            sqlx::query("UPDATE library SET name = ? WHERE id = ?")
+                .bind(&name)
+                .bind(library_id)
+                .execute(&mut *db)
+                .await?;
+        }
+        ValueUpdate::Unset => {
+            return Err(Error::new(
+                ErrorKind::Invalid,
+                "library name cannot be unset",
+            ))
+        }
+        _ => {}
+    }
+    get(db, library_id).await
+}
+
+/// Deletes an empty library.
+#[tracing::instrument(skip(db))]
+pub async fn delete(db: &mut DbC, library_id: LibraryId) -> Result<()> {
+    if library_id == LibraryId::DEFAULT {
+        return Err(Error::new(
+            ErrorKind::Invalid,
+            "the default library cannot be deleted",
+        ));
+    }
+    get(db, library_id).await?;
+    let in_use = sqlx::query_scalar::<_, bool>(
+        "SELECT EXISTS (SELECT 1 FROM artist WHERE library = ?)
+        OR EXISTS (SELECT 1 FROM album WHERE library = ?)
+        OR EXISTS (SELECT 1 FROM track WHERE library = ?)",
+    )
+    .bind(library_id)
+    .bind(library_id)
+    .bind(library_id)
+    .fetch_one(&mut *db)
+    .await?;
+    if in_use {
+        return Err(Error::new(ErrorKind::Invalid, "library is not empty"));
+    }
+    sqlx::query("DELETE FROM library WHERE id = ?")
+        .bind(library_id)
+        .execute(db)
+        .await?;
+    Ok(())
+}
+
+/// Lists the users that were granted access to the library.
+#[tracing::instrument(skip(db))]
+pub async fn user_list(db: &mut DbC, library_id: LibraryId) -> Result<Vec<UserId>> {
+    get(db, library_id).await?;
+    let ids = sqlx::query_scalar("SELECT user FROM library_user WHERE library = ? ORDER BY user")
+        .bind(library_id)
+        .fetch_all(db)
+        .await?;
+    Ok(ids.into_iter().map(UserId::from_db).collect())
+}
+
+#[tracing::instrument(skip(db))]
+pub async fn grant(db: &mut DbC, library_id: LibraryId, user_id: UserId) -> Result<()> {
+    get(db, library_id).await?;
+    user::get(db, user_id).await?;
+    sqlx::query("INSERT OR IGNORE INTO library_user (library, user) VALUES (?, ?)")
+        .bind(library_id)
+        .bind(user_id)
+        .execute(db)
+        .await?;
+    Ok(())
+}
+
+#[tracing::instrument(skip(db))]
+pub async fn revoke(db: &mut DbC, library_id: LibraryId, user_id: UserId) -> Result<()> {
+    sqlx::query("DELETE FROM library_user WHERE library = ? AND user = ?")
+        .bind(library_id)
+        .bind(user_id)
+        .execute(db)
+        .await?;
+    Ok(())
+}
+
+/// Resolves a filter to the libraries whose media is listed, `None` if every library is.
+#[tracing::instrument(skip(db))]
+pub(crate) async fn resolve(db: &mut DbC, filter: LibraryFilter) -> Result<Option<Vec<LibraryId>>> {
+    let accessible = match filter.user {
+        Some(user_id) => {
+            let user = user::get(db, user_id).await?;
+            if user.admin {
+                None
+            } else {
+                let ids = sqlx::query_scalar("SELECT library FROM library_user WHERE user = ?")
+                    .bind(user_id)
+                    .fetch_all(&mut *db)
+                    .await?;
+                Some(ids.into_iter().map(LibraryId::from_db).collect::<Vec<_>>())
+            }
+        }
+        None => None,
+    };
+    match (filter.library, accessible) {
+        (Some(library_id), Some(accessible)) if !accessible.contains(&library_id) => {
+            Err(Error::new(ErrorKind::Unauthorized, "no access to library"))
+        }
+        (Some(library_id), _) => {
+            get(db, library_id).await?;
+            Ok(Some(vec![library_id]))
+        }
+        (None, accessible) => Ok(accessible),
+    }
+}
+
+async fn validate_name(db: &mut DbC, name: &str) -> Result<()> {
+    if name.trim().is_empty() {
+        return Err(Error::new(
+            ErrorKind::Invalid,
+            "library name cannot be empty",
+        ));
+    }
+    let exists =
+        sqlx::query_scalar::<_, bool>("SELECT EXISTS (SELECT 1 FROM library WHERE name = ?)")
+            .bind(name)
+            .fetch_one(db)
+            .await?;
+    if exists {
+        return Err(Error::new(
+            ErrorKind::Invalid,
+            "library name already in use",
+        ));
+    }
+    Ok(())
+}
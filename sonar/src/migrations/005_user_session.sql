@@ -0,0 +1,11 @@
+CREATE TABLE user_session (
+	id			INTEGER PRIMARY KEY NOT NULL,
+	user			INTEGER NOT NULL REFERENCES user(id),
+	-- hex encoded sha256 of the session token
+	token_hash		TEXT NOT NULL UNIQUE,
+	device			TEXT,
+	created_at		INTEGER NOT NULL DEFAULT (unixepoch()),
+	last_used_at		INTEGER NOT NULL DEFAULT (unixepoch()),
+	expires_at		INTEGER
+);
+CREATE INDEX user_session_user ON user_session(user);
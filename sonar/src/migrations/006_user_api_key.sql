@@ -0,0 +1,13 @@
+CREATE TABLE user_api_key (
+	id			INTEGER PRIMARY KEY NOT NULL,
+	user			INTEGER NOT NULL REFERENCES user(id),
+	name			TEXT NOT NULL,
+	-- hex encoded sha256 of the key
+	key_hash		TEXT NOT NULL UNIQUE,
+	-- one of 'read-only', 'streaming' or 'admin'. NULL means regular user access.
+	scope			TEXT,
+	created_at		INTEGER NOT NULL DEFAULT (unixepoch()),
+	last_used_at		INTEGER,
+	UNIQUE(user, name)
+);
+CREATE INDEX user_api_key_user ON user_api_key(user);
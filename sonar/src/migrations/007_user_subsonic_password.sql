@@ -0,0 +1,7 @@
+-- secret used to verify opensubsonic salted md5 tokens.
+-- the protocol requires the server to know the plaintext secret so it is kept
+-- separate from the user's password, which is only stored as a scrypt hash.
+CREATE TABLE user_subsonic_password (
+	user			INTEGER PRIMARY KEY NOT NULL REFERENCES user(id),
+	password		TEXT NOT NULL
+);
@@ -0,0 +1,20 @@
+-- playlist tracks are now identified by their position in the playlist,
+-- which allows the same track to be added multiple times.
+-- the old table is copied instead of renamed so views referencing playlist_track are kept intact.
+CREATE TABLE playlist_track_old AS SELECT rowid AS old_rowid, playlist, track, created_at FROM playlist_track;
+DROP TABLE playlist_track;
+
+CREATE TABLE playlist_track (
+	id		INTEGER PRIMARY KEY NOT NULL,
+	playlist	INTEGER NOT NULL REFERENCES playlist(id),
+	track		INTEGER NOT NULL REFERENCES track(id),
+	-- zero based position of the track in the playlist
+	position	INTEGER NOT NULL,
+	created_at	INTEGER NOT NULL DEFAULT (unixepoch())
+);
+CREATE INDEX playlist_track_playlist_position ON playlist_track(playlist, position);
+
+INSERT INTO playlist_track (playlist, track, position, created_at)
+	SELECT playlist, track, ROW_NUMBER() OVER (PARTITION BY playlist ORDER BY old_rowid) - 1, created_at
+	FROM playlist_track_old;
+DROP TABLE playlist_track_old;
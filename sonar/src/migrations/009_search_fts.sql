@@ -0,0 +1,8 @@
+-- full text index used by the builtin search engine.
+-- the rowid is the sonar id of the indexed item so the namespace identifies its type.
+CREATE VIRTUAL TABLE search_fts USING fts5(
+	name,
+	-- only set for playlists, which are only searchable by their owner
+	owner UNINDEXED,
+	tokenize = 'unicode61 remove_diacritics 2'
+);
@@ -0,0 +1,57 @@
+CREATE TABLE album_credit (
+	album		INTEGER NOT NULL REFERENCES album(id),
+	artist		INTEGER NOT NULL REFERENCES artist(id),
+	position	INTEGER NOT NULL,
+	PRIMARY KEY(album, artist)
+);
+CREATE INDEX album_credit_artist ON album_credit(artist);
+
+CREATE TABLE track_credit (
+	track		INTEGER NOT NULL REFERENCES track(id),
+	artist		INTEGER NOT NULL REFERENCES artist(id),
+	role		TEXT NOT NULL CHECK (role IN ('primary', 'featured', 'remixer')),
+	position	INTEGER NOT NULL,
+	PRIMARY KEY(track, artist, role)
+);
+CREATE INDEX track_credit_artist ON track_credit(artist);
+
+-- album.artist is kept as the first album artist
+INSERT INTO album_credit (album, artist, position) SELECT id, artist, 0 FROM album;
+
+-- tracks without a primary artist are credited to the album artists
+CREATE VIEW view_track_credit (
+	track, artist
+) AS
+	SELECT track, artist FROM track_credit
+	UNION
+	SELECT track.id, album_credit.artist
+	FROM track
+	INNER JOIN album_credit ON album_credit.album = track.album
+	WHERE NOT EXISTS (SELECT 1 FROM track_credit WHERE track_credit.track = track.id AND track_credit.role = 'primary');
+
+DROP VIEW view_artist_extra;
+CREATE VIEW view_artist_extra (
+	id, album_count
+) AS
+	SELECT artist.id, COUNT(album_credit.album)
+	FROM artist
+	LEFT JOIN album_credit ON artist.id = album_credit.artist
+	GROUP BY artist.id;
+
+DROP VIEW sqlx_track;
+CREATE VIEW sqlx_track (
+	id, name, artist, album, duration_ms, audio, listen_count, cover_art, created_at
+) AS
+	SELECT
+		track.id,
+		track.name,
+		COALESCE((SELECT track_credit.artist FROM track_credit WHERE track_credit.track = track.id AND track_credit.role = 'primary' ORDER BY track_credit.position ASC LIMIT 1), album.artist),
+		track.album,
+		view_track_extra.duration_ms,
+		view_track_extra.audio,
+		track.listen_count,
+		track.cover_art,
+		track.created_at
+	FROM track
+	INNER JOIN album ON album.id = track.album
+	INNER JOIN view_track_extra ON view_track_extra.id = track.id;
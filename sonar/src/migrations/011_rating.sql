@@ -0,0 +1,9 @@
+CREATE TABLE rating (
+	user		INTEGER NOT NULL REFERENCES user(id) ON DELETE CASCADE,
+	namespace	INTEGER NOT NULL,
+	identifier	INTEGER NOT NULL,
+	rating		INTEGER NOT NULL CHECK(rating BETWEEN 1 AND 5),
+	updated_at	INTEGER NOT NULL DEFAULT(unixepoch()),
+	PRIMARY KEY(user, namespace, identifier)
+);
+CREATE INDEX rating_item ON rating(namespace, identifier);
@@ -0,0 +1,17 @@
+CREATE TABLE play_queue (
+	user		INTEGER PRIMARY KEY NOT NULL REFERENCES user(id) ON DELETE CASCADE,
+	-- position of the current entry in play_queue_track
+	current		INTEGER,
+	-- playback position within the current track, in milliseconds
+	position_ms	INTEGER NOT NULL DEFAULT 0,
+	changed_by	TEXT NOT NULL,
+	changed_at	INTEGER NOT NULL DEFAULT (unixepoch())
+);
+
+CREATE TABLE play_queue_track (
+	user		INTEGER NOT NULL REFERENCES play_queue(user) ON DELETE CASCADE,
+	-- zero based position of the track in the queue
+	position	INTEGER NOT NULL,
+	track		INTEGER NOT NULL REFERENCES track(id) ON DELETE CASCADE,
+	PRIMARY KEY(user, position)
+);
@@ -0,0 +1,10 @@
+CREATE TABLE bookmark (
+	user		INTEGER NOT NULL REFERENCES user(id) ON DELETE CASCADE,
+	track		INTEGER NOT NULL REFERENCES track(id) ON DELETE CASCADE,
+	-- position within the track, in milliseconds
+	position_ms	INTEGER NOT NULL,
+	comment		TEXT,
+	created_at	INTEGER NOT NULL DEFAULT (unixepoch()),
+	updated_at	INTEGER NOT NULL DEFAULT (unixepoch()),
+	PRIMARY KEY(user, track)
+);
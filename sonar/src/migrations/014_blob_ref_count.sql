@@ -0,0 +1,7 @@
+-- blobs are shared by images and audios with the same content.
+-- a blob is deleted once nothing references it.
+ALTER TABLE blob ADD COLUMN ref_count INTEGER NOT NULL DEFAULT 0;
+UPDATE blob SET ref_count =
+	(SELECT COUNT(*) FROM image WHERE image.blob = blob.id)
+	+ (SELECT COUNT(*) FROM audio WHERE audio.blob = blob.id);
+CREATE INDEX blob_sha256_size ON blob(sha256, size);
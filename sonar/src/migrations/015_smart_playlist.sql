@@ -0,0 +1,21 @@
+-- smart playlists are regular playlists whose tracks are generated from a set of rules.
+-- the generated tracks are stored in playlist_track and replaced every time the playlist is refreshed.
+CREATE TABLE smart_playlist (
+	playlist	INTEGER PRIMARY KEY NOT NULL REFERENCES playlist(id),
+	-- one rule per line
+	rules		TEXT NOT NULL,
+	match_any	INTEGER NOT NULL DEFAULT 0,
+	sort		TEXT NOT NULL,
+	descending	INTEGER NOT NULL DEFAULT 0,
+	max_tracks	INTEGER,
+	refreshed_at	INTEGER
+);
+
+DROP VIEW sqlx_playlist;
+CREATE VIEW sqlx_playlist (
+	id, name, owner, track_count, duration_ms, cover_art, smart, created_at
+) AS
+	SELECT playlist.id, name, owner, track_count, duration_ms, cover_art, smart_playlist.playlist IS NOT NULL, created_at
+	FROM playlist
+	INNER JOIN view_playlist_extra ON view_playlist_extra.id = playlist.id
+	LEFT JOIN smart_playlist ON smart_playlist.playlist = playlist.id;
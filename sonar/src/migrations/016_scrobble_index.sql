@@ -0,0 +1,4 @@
+-- used by the recommender to find co-listened tracks
+CREATE INDEX scrobble_track ON scrobble(track);
+CREATE INDEX scrobble_user_listen_at ON scrobble(user, listen_at);
+CREATE INDEX favorite_namespace_identifier ON favorite(namespace, identifier);
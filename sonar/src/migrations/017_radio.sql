@@ -0,0 +1,19 @@
+-- radios generate tracks in batches from a seed.
+-- the tracks returned so far are kept so the radio does not repeat them.
+CREATE TABLE radio (
+	id		INTEGER PRIMARY KEY NOT NULL,
+	user		INTEGER NOT NULL REFERENCES user(id) ON DELETE CASCADE,
+	-- a track, album or artist id or 'genre:<name>'
+	seed		TEXT NOT NULL,
+	created_at	INTEGER NOT NULL DEFAULT (unixepoch()),
+	updated_at	INTEGER NOT NULL DEFAULT (unixepoch())
+);
+CREATE INDEX radio_user_seed ON radio(user, seed);
+
+CREATE TABLE radio_track (
+	radio		INTEGER NOT NULL REFERENCES radio(id) ON DELETE CASCADE,
+	track		INTEGER NOT NULL REFERENCES track(id) ON DELETE CASCADE,
+	-- zero based position of the track in the radio
+	position	INTEGER NOT NULL,
+	PRIMARY KEY(radio, track)
+);
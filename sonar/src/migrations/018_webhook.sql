@@ -0,0 +1,29 @@
+CREATE TABLE webhook (
+	id		INTEGER PRIMARY KEY NOT NULL,
+	url		TEXT NOT NULL,
+	-- comma separated event kinds, empty for all events
+	kinds		TEXT NOT NULL DEFAULT '',
+	-- key used to sign the payloads
+	secret		TEXT NOT NULL,
+	enabled		INTEGER NOT NULL DEFAULT 1,
+	created_at	INTEGER NOT NULL DEFAULT (unixepoch())
+);
+
+CREATE TABLE webhook_delivery (
+	id		INTEGER PRIMARY KEY NOT NULL,
+	webhook		INTEGER NOT NULL REFERENCES webhook(id) ON DELETE CASCADE,
+	event_sequence	INTEGER NOT NULL,
+	event_kind	TEXT NOT NULL,
+	payload		TEXT NOT NULL,
+	status		TEXT NOT NULL CHECK (status IN ('pending', 'delivered', 'failed')) DEFAULT 'pending',
+	attempts	INTEGER NOT NULL DEFAULT 0,
+	-- http status code of the last attempt
+	response_status	INTEGER,
+	-- error of the last attempt
+	error		TEXT,
+	next_attempt_at	INTEGER NOT NULL DEFAULT (unixepoch()),
+	created_at	INTEGER NOT NULL DEFAULT (unixepoch()),
+	updated_at	INTEGER NOT NULL DEFAULT (unixepoch())
+);
+CREATE INDEX webhook_delivery_webhook ON webhook_delivery(webhook, id);
+CREATE INDEX webhook_delivery_status_next_attempt_at ON webhook_delivery(status, next_attempt_at);
@@ -0,0 +1,5 @@
+-- comma separated roles, admins have every role
+ALTER TABLE user ADD roles TEXT NOT NULL DEFAULT '';
+
+-- existing users keep being able to stream and download
+UPDATE user SET roles = 'download,stream';
@@ -0,0 +1,64 @@
+CREATE TABLE library (
+	id		INTEGER PRIMARY KEY NOT NULL,
+	name		TEXT NOT NULL UNIQUE,
+	created_at	INTEGER NOT NULL DEFAULT (unixepoch())
+);
+
+-- existing media and users belong to the default library
+INSERT INTO library (id, name) VALUES (1, 'default');
+
+CREATE TABLE library_user (
+	library		INTEGER NOT NULL REFERENCES library(id) ON DELETE CASCADE,
+	user		INTEGER NOT NULL REFERENCES user(id) ON DELETE CASCADE,
+	PRIMARY KEY (library, user)
+);
+CREATE INDEX library_user_user ON library_user(user);
+
+INSERT INTO library_user (library, user) SELECT 1, id FROM user;
+
+-- columns added to existing tables cannot have both a REFERENCES clause and a non null default.
+-- albums are in the library of their artist and tracks in the library of their album.
+ALTER TABLE artist ADD library INTEGER NOT NULL DEFAULT 1;
+ALTER TABLE album ADD library INTEGER NOT NULL DEFAULT 1;
+ALTER TABLE track ADD library INTEGER NOT NULL DEFAULT 1;
+CREATE INDEX artist_library ON artist(library);
+CREATE INDEX album_library ON album(library);
+CREATE INDEX track_library ON track(library);
+
+DROP VIEW sqlx_artist;
+CREATE VIEW sqlx_artist (
+	id, name, listen_count, cover_art, album_count, library, created_at
+) AS
+	SELECT artist.id, name, listen_count, cover_art, album_count, library, created_at
+	FROM artist
+	INNER JOIN view_artist_extra ON view_artist_extra.id = artist.id;
+
+DROP VIEW sqlx_album;
+CREATE VIEW sqlx_album (
+	id, name, duration_ms, artist, listen_count, cover_art, track_count, library, created_at
+) AS
+	SELECT album.id, name, duration_ms, artist, listen_count, cover_art, track_count, library, created_at
+	FROM album
+	INNER JOIN view_album_extra ON view_album_extra.id = album.id;
+
+DROP VIEW sqlx_track;
+CREATE VIEW sqlx_track (
+	id, name, artist, album, duration_ms, audio, listen_count, cover_art, library, created_at
+) AS
+	SELECT
+		track.id,
+		track.name,
+		COALESCE((SELECT track_credit.artist FROM track_credit WHERE track_credit.track = track.id AND track_credit.role = 'primary' ORDER BY track_credit.position ASC LIMIT 1), album.artist),
+		track.album,
+		view_track_extra.duration_ms,
+		view_track_extra.audio,
+		track.listen_count,
+		track.cover_art,
+		track.library,
+		track.created_at
+	FROM track
+	INNER JOIN album ON album.id = track.album
+	INNER JOIN view_track_extra ON view_track_extra.id = track.id;
+
+-- library that downloaded media is added to, the default library if null
+ALTER TABLE subscription ADD library INTEGER REFERENCES library(id) ON DELETE SET NULL;
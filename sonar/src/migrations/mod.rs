@@ -29,6 +29,22 @@ pub async fn run(db: &Db) -> Result<()> {
     run_migration(db, migration!("002_genre_index_namespace_genre.sql")).await?;
     run_migration(db, migration!("003_rework_subscription.sql")).await?;
     run_migration(db, migration!("004_playlist_cover_art.sql")).await?;
+    run_migration(db, migration!("005_user_session.sql")).await?;
+    run_migration(db, migration!("006_user_api_key.sql")).await?;
+    run_migration(db, migration!("007_user_subsonic_password.sql")).await?;
+    run_migration(db, migration!("008_playlist_track_position.sql")).await?;
+    run_migration(db, migration!("009_search_fts.sql")).await?;
+    run_migration(db, migration!("010_artist_credit.sql")).await?;
+    run_migration(db, migration!("011_rating.sql")).await?;
+    run_migration(db, migration!("012_play_queue.sql")).await?;
+    run_migration(db, migration!("013_bookmark.sql")).await?;
+    run_migration(db, migration!("014_blob_ref_count.sql")).await?;
+    run_migration(db, migration!("015_smart_playlist.sql")).await?;
+    run_migration(db, migration!("016_scrobble_index.sql")).await?;
+    run_migration(db, migration!("017_radio.sql")).await?;
+    run_migration(db, migration!("018_webhook.sql")).await?;
+    run_migration(db, migration!("019_user_role.sql")).await?;
+    run_migration(db, migration!("020_library.sql")).await?;
     tracing::info!("migrations complete");
     Ok(())
 }
@@ -0,0 +1,118 @@
+use std::time::Duration;
+
+use sqlx::Row;
+
+use crate::{db::DbC, Error, ErrorKind, Result, Timestamp, TrackId, UserId};
+
+/// The play queue of a user, shared between all of their clients.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlayQueue {
+    pub user: UserId,
+    pub tracks: Vec<TrackId>,
+    /// Index of the current track in `tracks`.
+    pub current: Option<u32>,
+    /// Playback position within the current track.
+    pub position: Duration,
+    /// The client that last saved the queue.
+    pub changed_by: String,
+    pub changed_at: Timestamp,
+}
+
+#[derive(Debug, Clone)]
+pub struct PlayQueueSave {
+    pub tracks: Vec<TrackId>,
+    pub current: Option<u32>,
+    pub position: Duration,
+    pub changed_by: String,
+}
+
+#[tracing::instrument(skip(db))]
+pub async fn get(db: &mut DbC, user_id: UserId) -> Result<Option<PlayQueue>> {
+    let row = sqlx::query(
+        "SELECT current, position_ms, changed_by, changed_at FROM play_queue WHERE user = ?",
+    )
+    .bind(user_id)
+    .fetch_optional(&mut *db)
+    .await?;
+    let row = match row {
+        Some(row) => row,
+        None => return Ok(None),
+    };
+
+    let rows = sqlx::query(
+        "SELECT position, track FROM play_queue_track WHERE user = ? ORDER BY position ASC",
+    )
+    .bind(user_id)
+    .fetch_all(&mut *db)
+    .await?;
+
+    // tracks may have been deleted since the queue was saved so the index of the current track
+    // is computed from the position of its entry.
+    let current_position = row.get::<Option<i64>, _>("current");
+    let mut tracks = Vec::with_capacity(rows.len());
+    let mut current = None;
+    for row in rows {
+        if Some(row.get::<i64, _>(0)) == current_position {
+            current = Some(tracks.len() as u32);
+        }
+        tracks.push(TrackId::from_db(row.get(1)));
+    }
+
+    Ok(Some(PlayQueue {
+        user: user_id,
+        tracks,
+        current,
+        position: Duration::from_millis(row.get::<i64, _>("position_ms") as u64),
+        changed_by: row.get("changed_by"),
+        changed_at: Timestamp::from_seconds(row.get::<i64, _>("changed_at") as u64),
+    }))
+}
+
+#[tracing::instrument(skip(db))]
+pub async fn save(db: &mut DbC, user_id: UserId, save: PlayQueueSave) -> Result<PlayQueue> {
+    if let Some(current) = save.current {
+        if current as usize >= save.tracks.len() {
+            return Err(Error::new(
+                ErrorKind::Invalid,
+                "current track index is out of bounds",
+            ));
+        }
+    }
+
+    clear(db, user_id).await?;
+    sqlx::query(
+        "INSERT INTO play_queue (user, current, position_ms, changed_by) VALUES (?, ?, ?, ?)",
+    )
+    .bind(user_id)
+    .bind(save.current.map(i64::from))
+    .bind(save.position.as_millis() as i64)
+    .bind(&save.changed_by)
+    .execute(&mut *db)
+    .await?;
+    for (position, track_id) in save.tracks.iter().enumerate() {
+        sqlx::query("INSERT INTO play_queue_track (user, position, track) VALUES (?, ?, ?)")
+            .bind(user_id)
+            .bind(position as i64)
+            .bind(track_id)
+            .execute(&mut *db)
+            .await?;
+    }
+
+    match get(db, user_id).await? {
+        Some(queue) => Ok(queue),
+        None => Err(Error::new(ErrorKind::Internal, "play queue not saved")),
+    }
+}
+
+#[tracing::instrument(skip(db))]
+pub async fn clear(db: &mut DbC, user_id: UserId) -> Result<()> {
+    sqlx::query("DELETE FROM play_queue_track WHERE user = ?")
+        .bind(user_id)
+        .execute(&mut *db)
+        .await?;
+    sqlx::query("DELETE FROM play_queue WHERE user = ?")
+        .bind(user_id)
+        .execute(&mut *db)
+        .await?;
+    Ok(())
+}
@@ -8,14 +8,16 @@ use crate::blob::BlobStorage;
 use crate::{album, track, ImageCreate};
 use crate::{
     db::{self, Db, DbC},
-    property, Error, ImageId, ListParams, PlaylistId, Properties, PropertyUpdate, Result,
-    Timestamp, TrackId, UserId, ValueUpdate,
+    property, Error, ErrorKind, ImageId, ListParams, PlaylistId, Properties, PropertyUpdate,
+    Result, Timestamp, TrackId, UserId, ValueUpdate,
 };
 
 #[derive(Debug, Clone)]
 pub struct PlaylistTrack {
     pub playlist: PlaylistId,
     pub track: TrackId,
+    /// zero based position of the track in the playlist.
+    pub position: u32,
     pub inserted_at: Timestamp,
 }
 
@@ -28,6 +30,8 @@ pub struct Playlist {
     pub track_count: u32,
     pub duration: Duration,
     pub cover_art: Option<ImageId>,
+    /// the tracks of smart playlists are generated from rules and can not be edited.
+    pub smart: bool,
     pub properties: Properties,
     pub created_at: Timestamp,
 }
@@ -52,6 +56,7 @@ pub struct PlaylistUpdate {
 struct PlaylistTrackView {
     playlist: i64,
     track: i64,
+    position: i64,
     created_at: i64,
 }
 
@@ -60,6 +65,7 @@ impl PlaylistTrackView {
         PlaylistTrack {
             playlist: PlaylistId::from_db(self.playlist),
             track: TrackId::from_db(self.track),
+            position: self.position as u32,
             inserted_at: Timestamp::from_seconds(self.created_at as u64),
         }
     }
@@ -73,6 +79,7 @@ struct PlaylistView {
     owner: i64,
     track_count: i64,
     cover_art: Option<i64>,
+    smart: bool,
     created_at: i64,
 }
 
@@ -85,6 +92,7 @@ impl From<(PlaylistView, Properties)> for Playlist {
             track_count: value.track_count as u32,
             duration: Duration::from_millis(value.duration_ms as u64),
             cover_art: value.cover_art.map(ImageId::from_db),
+            smart: value.smart,
             properties,
             created_at: Timestamp::from_seconds(value.created_at as u64),
         }
@@ -103,6 +111,14 @@ pub async fn list(db: &mut DbC, params: ListParams) -> Result<Vec<Playlist>> {
         .collect())
 }
 
+#[tracing::instrument(skip(db))]
+pub async fn list_ids(db: &mut DbC) -> Result<Vec<PlaylistId>> {
+    let ids = sqlx::query_scalar("SELECT id FROM playlist")
+        .fetch_all(&mut *db)
+        .await?;
+    Ok(ids.into_iter().map(PlaylistId::from_db).collect())
+}
+
 #[tracing::instrument(skip(db))]
 pub async fn get(db: &mut DbC, playlist_id: PlaylistId) -> Result<Playlist> {
     let view = db::get_by_id(db, "sqlx_playlist", playlist_id).await?;
@@ -110,6 +126,18 @@ pub async fn get(db: &mut DbC, playlist_id: PlaylistId) -> Result<Playlist> {
     Ok(Playlist::from((view, properties)))
 }
 
+#[tracing::instrument(skip(db))]
+pub async fn get_owner(db: &mut DbC, playlist_id: PlaylistId) -> Result<UserId> {
+    let owner = sqlx::query_scalar::<_, i64>("SELECT owner FROM playlist WHERE id = ?")
+        .bind(playlist_id)
+        .fetch_optional(&mut *db)
+        .await?;
+    match owner {
+        Some(owner) => Ok(UserId::from_db(owner)),
+        None => Err(Error::new(ErrorKind::NotFound, "playlist not found")),
+    }
+}
+
 #[tracing::instrument(skip(db))]
 pub async fn get_bulk(db: &mut DbC, playlist_ids: &[PlaylistId]) -> Result<Vec<Playlist>> {
     let mut playlists = Vec::with_capacity(playlist_ids.len());
@@ -211,6 +239,10 @@ pub async fn update(
 
 #[tracing::instrument(skip(db))]
 pub async fn delete(db: &mut DbC, playlist_id: PlaylistId) -> Result<()> {
+    sqlx::query("DELETE FROM smart_playlist WHERE playlist = ?")
+        .bind(playlist_id)
+        .execute(&mut *db)
+        .await?;
     sqlx::query("DELETE FROM playlist WHERE id = ?")
         .bind(playlist_id)
         .execute(&mut *db)
@@ -227,7 +259,7 @@ pub async fn list_tracks(
 ) -> Result<Vec<PlaylistTrack>> {
     let (offset, limit) = params.to_db_offset_limit();
     let tracks = sqlx::query_as::<_,PlaylistTrackView>(
-        "SELECT playlist, track, created_at FROM playlist_track WHERE playlist = ? ORDER BY rowid ASC LIMIT ? OFFSET ?")
+        "SELECT playlist, track, position, created_at FROM playlist_track WHERE playlist = ? ORDER BY position ASC LIMIT ? OFFSET ?")
         .bind(playlist_id)
         .bind(limit)
         .bind(offset)
@@ -241,9 +273,12 @@ pub async fn list_tracks(
 
 #[tracing::instrument(skip(db))]
 pub async fn list_tracks_in_all_playlists(db: &mut DbC) -> Result<Vec<TrackId>> {
-    let tracks = sqlx::query_scalar("SELECT track FROM playlist_track")
-        .fetch_all(&mut *db)
-        .await?;
+    // smart playlist tracks are derived from their rules so they don't pin anything
+    let tracks = sqlx::query_scalar(
+        "SELECT track FROM playlist_track WHERE playlist NOT IN (SELECT playlist FROM smart_playlist)",
+    )
+    .fetch_all(&mut *db)
+    .await?;
     Ok(tracks.into_iter().map(TrackId::from_db).collect())
 }
 
@@ -270,21 +305,111 @@ pub async fn clear_tracks(db: &mut DbC, playlist_id: PlaylistId) -> Result<()> {
     Ok(())
 }
 
+/// Appends the tracks to the end of the playlist.
+#[tracing::instrument(skip(db))]
 pub async fn insert_tracks(
     db: &mut DbC,
     playlist_id: PlaylistId,
     tracks: &[TrackId],
 ) -> Result<()> {
-    for track_id in tracks {
-        sqlx::query("INSERT INTO playlist_track (playlist, track) VALUES (?, ?)")
+    let position = track_count(db, playlist_id).await?;
+    insert_tracks_at(db, playlist_id, position, tracks).await
+}
+
+/// Inserts the tracks starting at `position`, shifting the tracks at or after it.
+#[tracing::instrument(skip(db))]
+pub async fn insert_tracks_at(
+    db: &mut DbC,
+    playlist_id: PlaylistId,
+    position: u32,
+    tracks: &[TrackId],
+) -> Result<()> {
+    let count = track_count(db, playlist_id).await?;
+    if position > count {
+        return Err(Error::new(
+            ErrorKind::Invalid,
+            "insert position is past the end of the playlist",
+        ));
+    }
+
+    sqlx::query(
+        "UPDATE playlist_track SET position = position + ? WHERE playlist = ? AND position >= ?",
+    )
+    .bind(tracks.len() as i64)
+    .bind(playlist_id)
+    .bind(position)
+    .execute(&mut *db)
+    .await?;
+    for (offset, track_id) in tracks.iter().enumerate() {
+        sqlx::query("INSERT INTO playlist_track (playlist, track, position) VALUES (?, ?, ?)")
             .bind(playlist_id)
             .bind(track_id)
+            .bind(position + offset as u32)
             .execute(&mut *db)
             .await?;
     }
     Ok(())
 }
 
+/// Moves `count` tracks starting at `from` so that the first of them ends up at position `to`.
+#[tracing::instrument(skip(db))]
+pub async fn move_tracks(
+    db: &mut DbC,
+    playlist_id: PlaylistId,
+    from: u32,
+    count: u32,
+    to: u32,
+) -> Result<()> {
+    let mut entries = sqlx::query_scalar::<_, i64>(
+        "SELECT id FROM playlist_track WHERE playlist = ? ORDER BY position ASC",
+    )
+    .bind(playlist_id)
+    .fetch_all(&mut *db)
+    .await?;
+
+    let (from, count, to) = (from as usize, count as usize, to as usize);
+    if from + count > entries.len() || to + count > entries.len() {
+        return Err(Error::new(
+            ErrorKind::Invalid,
+            "move range is past the end of the playlist",
+        ));
+    }
+
+    let moved = entries.drain(from..from + count).collect::<Vec<_>>();
+    entries.splice(to..to, moved);
+    for (position, entry_id) in entries.into_iter().enumerate() {
+        sqlx::query("UPDATE playlist_track SET position = ? WHERE id = ?")
+            .bind(position as i64)
+            .bind(entry_id)
+            .execute(&mut *db)
+            .await?;
+    }
+    Ok(())
+}
+
+/// Removes the tracks at the given positions.
+/// Positions refer to the playlist before any of the tracks are removed.
+#[tracing::instrument(skip(db))]
+pub async fn remove_at(db: &mut DbC, playlist_id: PlaylistId, positions: &[u32]) -> Result<()> {
+    let count = track_count(db, playlist_id).await?;
+    if positions.iter().any(|&position| position >= count) {
+        return Err(Error::new(
+            ErrorKind::Invalid,
+            "remove position is past the end of the playlist",
+        ));
+    }
+
+    for position in positions {
+        sqlx::query("DELETE FROM playlist_track WHERE playlist = ? AND position = ?")
+            .bind(playlist_id)
+            .bind(position)
+            .execute(&mut *db)
+            .await?;
+    }
+    compact_positions(db, playlist_id).await
+}
+
+/// Removes every occurrence of the given tracks from the playlist.
 #[tracing::instrument(skip(db))]
 pub async fn remove_tracks(
     db: &mut DbC,
@@ -298,6 +423,33 @@ pub async fn remove_tracks(
             .execute(&mut *db)
             .await?;
     }
+    compact_positions(db, playlist_id).await
+}
+
+async fn track_count(db: &mut DbC, playlist_id: PlaylistId) -> Result<u32> {
+    let count =
+        sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM playlist_track WHERE playlist = ?")
+            .bind(playlist_id)
+            .fetch_one(&mut *db)
+            .await?;
+    Ok(count as u32)
+}
+
+/// Renumbers the tracks of the playlist so that positions are contiguous after removals.
+async fn compact_positions(db: &mut DbC, playlist_id: PlaylistId) -> Result<()> {
+    let entries = sqlx::query_scalar::<_, i64>(
+        "SELECT id FROM playlist_track WHERE playlist = ? ORDER BY position ASC",
+    )
+    .bind(playlist_id)
+    .fetch_all(&mut *db)
+    .await?;
+    for (position, entry_id) in entries.into_iter().enumerate() {
+        sqlx::query("UPDATE playlist_track SET position = ? WHERE id = ?")
+            .bind(position as i64)
+            .bind(entry_id)
+            .execute(&mut *db)
+            .await?;
+    }
     Ok(())
 }
 
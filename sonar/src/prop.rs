@@ -6,6 +6,9 @@ pub const RELEASE_DATE: PropertyKey = PropertyKey::new_const("sonar.io/release-d
 pub const TRACK_NUMBER: PropertyKey = PropertyKey::new_const("sonar.io/track-number");
 pub const DISC_NUMBER: PropertyKey = PropertyKey::new_const("sonar.io/disc-number");
 
+/// Set on the generated daily mix playlists of a user, the value is the number of the mix.
+pub const DAILY_MIX: PropertyKey = PropertyKey::new_const("sonar.io/daily-mix");
+
 pub const EXTERNAL_SPOTIFY_ID: PropertyKey = PropertyKey::new_const("external.sonar.io/spotify-id");
 pub const EXTERNAL_MUSICBRAINZ_ID: PropertyKey =
     PropertyKey::new_const("external.sonar.io/musicbrainz-id");
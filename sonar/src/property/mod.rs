@@ -448,6 +448,32 @@ pub(crate) async fn user_list_with_property(
     Ok(ids)
 }
 
+/// Lists the items that have the given non-user property value.
+pub(crate) async fn list_with_value(
+    db: &mut DbC,
+    key: &PropertyKey,
+    value: &str,
+) -> Result<Vec<SonarId>> {
+    let rows = sqlx::query(
+        "SELECT namespace, identifier FROM property WHERE key = ? AND value = ? AND user IS NULL",
+    )
+    .bind(key.as_str())
+    .bind(value)
+    .fetch_all(&mut *db)
+    .await?;
+    let mut ids = Vec::with_capacity(rows.len());
+    for row in rows {
+        ids.push(
+            SonarId::from_namespace_and_id(
+                row.get::<i64, _>(0) as u32,
+                row.get::<i64, _>(1) as u32,
+            )
+            .expect("invalid id in database"),
+        );
+    }
+    Ok(ids)
+}
+
 #[cfg(test)]
 mod test {
     use crate::ArtistId;
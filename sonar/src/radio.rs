@@ -0,0 +1,596 @@
+//! Endless radios that generate successive batches of tracks from a seed.
+//!
+//! A radio remembers the tracks it already returned so it does not repeat them and uses the last
+//! ones to steer the next batch, so the stream drifts slowly away from the seed instead of
+//! jumping around. Tracks the user listened recently are left out, as are tracks in libraries the
+//! user has no access to.
+
+use std::collections::{HashMap, HashSet};
+
+use rand::Rng;
+
+use crate::{
+    db::{self, DbC},
+    library, recommend, AlbumId, ArtistId, Error, ErrorKind, Genre, InvalidGenreError,
+    InvalidIdError, LibraryFilter, LibraryId, RadioId, Result, SonarId, Timestamp, TrackId, UserId,
+    ID_NAMESPACE_ALBUM, ID_NAMESPACE_ARTIST, ID_NAMESPACE_TRACK,
+};
+
+/// Tracks the user listened in this period, in seconds, are not played.
+const RECENT_PERIOD: i64 = 24 * 60 * 60;
+/// Radios that were not used in this period, in seconds, are deleted.
+const STALE_PERIOD: i64 = 7 * 24 * 60 * 60;
+/// Maximum number of tracks used as seeds.
+const MAX_SEED_TRACKS: u32 = 25;
+/// Maximum number of artists used as seeds of a genre radio.
+const MAX_SEED_ARTISTS: u32 = 10;
+/// Number of related artists mixed in for each seed artist.
+const RELATED_ARTISTS: usize = 10;
+/// Number of the last returned tracks used to steer the next batch.
+const CONTEXT_TRACKS: u32 = 5;
+/// Maximum number of tracks of the same artist in a batch, unless there is nothing else to play.
+const MAX_ARTIST_TRACKS: usize = 2;
+pub const MAX_BATCH_SIZE: u32 = 500;
+
+const SEED_TRACK_WEIGHT: f64 = 2.0;
+const SEED_WEIGHT: f64 = 1.0;
+const RELATED_ARTIST_WEIGHT: f64 = 0.5;
+const CONTEXT_WEIGHT: f64 = 0.5;
+const FAVORITE_WEIGHT: f64 = 1.0;
+
+#[derive(Debug)]
+pub enum InvalidRadioSeedError {
+    Id(InvalidIdError),
+    Genre(InvalidGenreError),
+    Kind(SonarId),
+}
+
+impl std::fmt::Display for InvalidRadioSeedError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            InvalidRadioSeedError::Id(err) => write!(f, "invalid radio seed: {err}"),
+            InvalidRadioSeedError::Genre(err) => write!(f, "invalid radio seed: {err}"),
+            InvalidRadioSeedError::Kind(id) => write!(
+                f,
+                "invalid radio seed: {id} is not a track, album or artist"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for InvalidRadioSeedError {}
+
+/// The starting point of a radio.
+/// Written as a track, album or artist id or as `genre:<name>`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RadioSeed {
+    Track(TrackId),
+    Album(AlbumId),
+    Artist(ArtistId),
+    Genre(Genre),
+}
+
+impl std::fmt::Display for RadioSeed {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RadioSeed::Track(id) => write!(f, "{id}"),
+            RadioSeed::Album(id) => write!(f, "{id}"),
+            RadioSeed::Artist(id) => write!(f, "{id}"),
+            RadioSeed::Genre(genre) => write!(f, "genre:{genre}"),
+        }
+    }
+}
+
+impl std::str::FromStr for RadioSeed {
+    type Err = InvalidRadioSeedError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(genre) = s.strip_prefix("genre:") {
+            let genre = genre.parse().map_err(InvalidRadioSeedError::Genre)?;
+            return Ok(RadioSeed::Genre(genre));
+        }
+        let id = s.parse::<SonarId>().map_err(InvalidRadioSeedError::Id)?;
+        RadioSeed::try_from(id)
+    }
+}
+
+impl TryFrom<SonarId> for RadioSeed {
+    type Error = InvalidRadioSeedError;
+
+    fn try_from(value: SonarId) -> Result<Self, Self::Error> {
+        match value {
+            SonarId::Track(id) => Ok(RadioSeed::Track(id)),
+            SonarId::Album(id) => Ok(RadioSeed::Album(id)),
+            SonarId::Artist(id) => Ok(RadioSeed::Artist(id)),
+            _ => Err(InvalidRadioSeedError::Kind(value)),
+        }
+    }
+}
+
+impl From<Genre> for RadioSeed {
+    fn from(value: Genre) -> Self {
+        RadioSeed::Genre(value)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Radio {
+    pub id: RadioId,
+    pub user: UserId,
+    pub seed: RadioSeed,
+    /// Number of tracks returned so far.
+    pub track_count: u32,
+    pub created_at: Timestamp,
+    pub updated_at: Timestamp,
+}
+
+#[derive(Debug, Clone)]
+pub struct RadioCreate {
+    pub user: UserId,
+    pub seed: RadioSeed,
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct RadioView {
+    id: i64,
+    user: i64,
+    seed: String,
+    track_count: i64,
+    created_at: i64,
+    updated_at: i64,
+}
+
+impl TryFrom<RadioView> for Radio {
+    type Error = Error;
+
+    fn try_from(value: RadioView) -> Result<Self, Self::Error> {
+        let seed = value
+            .seed
+            .parse()
+            .map_err(|e| Error::with_source(ErrorKind::Internal, "invalid seed in database", e))?;
+        Ok(Self {
+            id: RadioId::from_db(value.id),
+            user: UserId::from_db(value.user),
+            seed,
+            track_count: value.track_count as u32,
+            created_at: Timestamp::from_seconds(value.created_at as u64),
+            updated_at: Timestamp::from_seconds(value.updated_at as u64),
+        })
+    }
+}
+
+#[tracing::instrument(skip(db))]
+pub async fn get(db: &mut DbC, radio_id: RadioId) -> Result<Radio> {
+    let view = sqlx::query_as::<_, RadioView>(
+        "SELECT radio.id, radio.user, radio.seed, COUNT(radio_track.track) AS track_count,
+            radio.created_at, radio.updated_at
+        FROM radio
+        LEFT JOIN radio_track ON radio_track.radio = radio.id
+        WHERE radio.id = ?
+        GROUP BY radio.id",
+    )
+    .bind(radio_id)
+    .fetch_optional(&mut *db)
+    .await?;
+    match view {
+        Some(view) => Radio::try_from(view),
+        None => Err(Error::new(ErrorKind::NotFound, "radio not found")),
+    }
+}
+
+#[tracing::instrument(skip(db))]
+pub async fn create(db: &mut DbC, create: RadioCreate) -> Result<Radio> {
+    delete_stale(db, create.user).await?;
+    let radio_id = sqlx::query_scalar("INSERT INTO radio (user, seed) VALUES (?, ?) RETURNING id")
+        .bind(create.user)
+        .bind(create.seed.to_string())
+        .fetch_one(&mut *db)
+        .await?;
+    get(db, RadioId::from_db(radio_id)).await
+}
+
+/// Finds the most recently used radio of the user with the same seed, or creates a new one.
+#[tracing::instrument(skip(db))]
+pub async fn find_or_create(db: &mut DbC, create: RadioCreate) -> Result<Radio> {
+    let radio_id = sqlx::query_scalar(
+        "SELECT id FROM radio WHERE user = ? AND seed = ? AND updated_at >= unixepoch() - ?
+        ORDER BY updated_at DESC LIMIT 1",
+    )
+    .bind(create.user)
+    .bind(create.seed.to_string())
+    .bind(STALE_PERIOD)
+    .fetch_optional(&mut *db)
+    .await?;
+    match radio_id {
+        Some(radio_id) => get(db, RadioId::from_db(radio_id)).await,
+        None => self::create(db, create).await,
+    }
+}
+
+#[tracing::instrument(skip(db))]
+pub async fn delete(db: &mut DbC, radio_id: RadioId) -> Result<()> {
+    sqlx::query("DELETE FROM radio WHERE id = ?")
+        .bind(radio_id)
+        .execute(&mut *db)
+        .await?;
+    Ok(())
+}
+
+/// Generates the next batch of tracks of a radio.
+/// Returns fewer tracks than requested only if the library does not have enough tracks.
+#[tracing::instrument(skip(db))]
+pub async fn next(db: &mut DbC, radio_id: RadioId, count: u32) -> Result<Vec<TrackId>> {
+    if count > MAX_BATCH_SIZE {
+        return Err(Error::new(
+            ErrorKind::Invalid,
+            format!("radio batch size must be at most {MAX_BATCH_SIZE}"),
+        ));
+    }
+    let radio = get(db, radio_id).await?;
+    let libraries = library::resolve(db, LibraryFilter::user(radio.user)).await?;
+
+    let played = sqlx::query_scalar::<_, i64>(
+        "SELECT track FROM radio_track WHERE radio = ? ORDER BY position DESC",
+    )
+    .bind(radio_id)
+    .fetch_all(&mut *db)
+    .await?
+    .into_iter()
+    .map(TrackId::from_db)
+    .collect::<Vec<_>>();
+    let mut excluded = sqlx::query_scalar::<_, i64>(
+        "SELECT DISTINCT track FROM scrobble WHERE user = ? AND listen_at >= unixepoch() - ?",
+    )
+    .bind(radio.user)
+    .bind(RECENT_PERIOD)
+    .fetch_all(&mut *db)
+    .await?
+    .into_iter()
+    .map(TrackId::from_db)
+    .collect::<HashSet<_>>();
+    excluded.extend(played.iter().copied());
+
+    let (seed_tracks, seed_artists) = seeds(db, &radio.seed).await?;
+    let mut scores = HashMap::<TrackId, f64>::new();
+    for &track in seed_tracks.iter() {
+        *scores.entry(track).or_default() += SEED_TRACK_WEIGHT;
+    }
+    for (track, score) in recommend::track_scores(db, &seed_tracks).await? {
+        *scores.entry(track).or_default() += SEED_WEIGHT * score;
+    }
+    for artist in seed_artists {
+        let mut related = recommend::artist_scores(db, artist)
+            .await?
+            .into_iter()
+            .collect::<Vec<_>>();
+        related.sort_by(|a, b| b.1.total_cmp(&a.1));
+        related.truncate(RELATED_ARTISTS);
+        for (artist, score) in related {
+            for track in artist_tracks(db, artist).await? {
+                *scores.entry(track).or_default() += RELATED_ARTIST_WEIGHT * score;
+            }
+        }
+    }
+    let context = &played[..played.len().min(CONTEXT_TRACKS as usize)];
+    for (track, score) in recommend::track_scores(db, context).await? {
+        *scores.entry(track).or_default() += CONTEXT_WEIGHT * score;
+    }
+    let favorites = sqlx::query_scalar::<_, i64>(&format!(
+        "SELECT identifier FROM favorite WHERE user = ? AND namespace = {ID_NAMESPACE_TRACK}"
+    ))
+    .bind(radio.user)
+    .fetch_all(&mut *db)
+    .await?;
+    for track in favorites {
+        if let Some(score) = scores.get_mut(&TrackId::from_db(track)) {
+            *score += FAVORITE_WEIGHT;
+        }
+    }
+
+    // some randomness so two radios with the same seed do not play the same tracks
+    if let Some(libraries) = libraries.as_deref() {
+        let tracks = scores.keys().copied().collect::<Vec<_>>();
+        let accessible = tracks_in_libraries(db, &tracks, libraries).await?;
+        scores.retain(|track, _| accessible.contains(track));
+    }
+
+    let mut candidates = {
+        let mut rng = rand::thread_rng();
+        scores
+            .into_iter()
+            .filter(|(track, _)| !excluded.contains(track))
+            .map(|(track, score)| (track, score * rng.gen_range(0.5..1.5)))
+            .collect::<Vec<_>>()
+    };
+    candidates.sort_by(|a, b| b.1.total_cmp(&a.1));
+    let candidates = candidates
+        .into_iter()
+        .map(|(track, _)| track)
+        .collect::<Vec<_>>();
+
+    let mut tracks = select(db, &candidates, count as usize).await?;
+    if tracks.len() < count as usize {
+        let genre = match radio.seed {
+            RadioSeed::Genre(ref genre) => Some(genre),
+            _ => None,
+        };
+        let missing = count as usize - tracks.len();
+        let random =
+            random_tracks(db, &radio, libraries.as_deref(), genre, &tracks, missing).await?;
+        tracks.extend(random);
+    }
+
+    let position = played.len() as i64;
+    for (offset, track) in tracks.iter().enumerate() {
+        sqlx::query("INSERT OR REPLACE INTO radio_track (radio, track, position) VALUES (?, ?, ?)")
+            .bind(radio_id)
+            .bind(track)
+            .bind(position + offset as i64)
+            .execute(&mut *db)
+            .await?;
+    }
+    sqlx::query("UPDATE radio SET updated_at = unixepoch() WHERE id = ?")
+        .bind(radio_id)
+        .execute(&mut *db)
+        .await?;
+    Ok(tracks)
+}
+
+async fn delete_stale(db: &mut DbC, user_id: UserId) -> Result<()> {
+    sqlx::query("DELETE FROM radio WHERE user = ? AND updated_at < unixepoch() - ?")
+        .bind(user_id)
+        .bind(STALE_PERIOD)
+        .execute(&mut *db)
+        .await?;
+    Ok(())
+}
+
+/// The tracks and artists a radio starts from.
+async fn seeds(db: &mut DbC, seed: &RadioSeed) -> Result<(Vec<TrackId>, Vec<ArtistId>)> {
+    let (tracks, artists) = match seed {
+        RadioSeed::Track(track_id) => {
+            let artists =
+                sqlx::query_scalar("SELECT artist FROM view_track_credit WHERE track = ?")
+                    .bind(track_id)
+                    .fetch_all(&mut *db)
+                    .await?;
+            (vec![track_id.to_db()], artists)
+        }
+        RadioSeed::Album(album_id) => {
+            let tracks = sqlx::query_scalar(
+                "SELECT id FROM track WHERE album = ? ORDER BY listen_count DESC LIMIT ?",
+            )
+            .bind(album_id)
+            .bind(MAX_SEED_TRACKS)
+            .fetch_all(&mut *db)
+            .await?;
+            let artists = sqlx::query_scalar("SELECT artist FROM album_credit WHERE album = ?")
+                .bind(album_id)
+                .fetch_all(&mut *db)
+                .await?;
+            (tracks, artists)
+        }
+        RadioSeed::Artist(artist_id) => {
+            let tracks = sqlx::query_scalar(
+                "SELECT track.id FROM view_track_credit
+                INNER JOIN track ON track.id = view_track_credit.track
+                WHERE view_track_credit.artist = ?
+                ORDER BY track.listen_count DESC LIMIT ?",
+            )
+            .bind(artist_id)
+            .bind(MAX_SEED_TRACKS)
+            .fetch_all(&mut *db)
+            .await?;
+            (tracks, vec![artist_id.to_db()])
+        }
+        RadioSeed::Genre(genre) => {
+            let tracks = sqlx::query_scalar(&format!(
+                "SELECT DISTINCT track.id FROM track
+                INNER JOIN album ON album.id = track.album
+                INNER JOIN genre ON genre.genre = ?1 AND (
+                    (genre.namespace = {ID_NAMESPACE_TRACK} AND genre.identifier = track.id)
+                    OR (genre.namespace = {ID_NAMESPACE_ALBUM} AND genre.identifier = track.album)
+                    OR (genre.namespace = {ID_NAMESPACE_ARTIST} AND genre.identifier = album.artist))
+                ORDER BY RANDOM() LIMIT ?2"
+            ))
+            .bind(genre.as_str())
+            .bind(MAX_SEED_TRACKS)
+            .fetch_all(&mut *db)
+            .await?;
+            let artists = sqlx::query_scalar(&format!(
+                "SELECT DISTINCT artist FROM (
+                    SELECT identifier AS artist FROM genre
+                    WHERE namespace = {ID_NAMESPACE_ARTIST} AND genre = ?1
+                    UNION
+                    SELECT album_credit.artist FROM album_credit
+                    INNER JOIN genre ON genre.namespace = {ID_NAMESPACE_ALBUM} AND genre.identifier = album_credit.album
+                    WHERE genre.genre = ?1
+                )
+                ORDER BY RANDOM() LIMIT ?2"
+            ))
+            .bind(genre.as_str())
+            .bind(MAX_SEED_ARTISTS)
+            .fetch_all(&mut *db)
+            .await?;
+            (tracks, artists)
+        }
+    };
+    Ok((
+        tracks.into_iter().map(TrackId::from_db).collect(),
+        artists.into_iter().map(ArtistId::from_db).collect(),
+    ))
+}
+
+/// The tracks that are in one of the libraries.
+async fn tracks_in_libraries(
+    db: &mut DbC,
+    tracks: &[TrackId],
+    libraries: &[LibraryId],
+) -> Result<HashSet<TrackId>> {
+    if tracks.is_empty() {
+        return Ok(HashSet::new());
+    }
+    let mut query = sqlx::QueryBuilder::new("SELECT id FROM track WHERE id IN ");
+    db::query_builder_push_id_tuple(&mut query, tracks.iter().copied());
+    query.push("AND library IN ");
+    db::query_builder_push_id_tuple(&mut query, libraries);
+    let tracks = query
+        .build_query_scalar::<i64>()
+        .fetch_all(&mut *db)
+        .await?
+        .into_iter()
+        .map(TrackId::from_db)
+        .collect();
+    Ok(tracks)
+}
+
+async fn artist_tracks(db: &mut DbC, artist_id: ArtistId) -> Result<Vec<TrackId>> {
+    let tracks =
+        sqlx::query_scalar::<_, i64>("SELECT track FROM view_track_credit WHERE artist = ?")
+            .bind(artist_id)
+            .fetch_all(&mut *db)
+            .await?;
+    Ok(tracks.into_iter().map(TrackId::from_db).collect())
+}
+
+/// Picks the first `count` candidates, limiting how many tracks of the same artist are picked
+/// unless there are not enough other candidates.
+async fn select(db: &mut DbC, candidates: &[TrackId], count: usize) -> Result<Vec<TrackId>> {
+    let pool = &candidates[..candidates.len().min(count * 4)];
+    if pool.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let mut query = sqlx::QueryBuilder::new("SELECT id, artist FROM sqlx_track WHERE id IN ");
+    db::query_builder_push_id_tuple(&mut query, pool.iter().copied());
+    let artists = query
+        .build_query_as::<(i64, i64)>()
+        .fetch_all(&mut *db)
+        .await?
+        .into_iter()
+        .map(|(track, artist)| (TrackId::from_db(track), artist))
+        .collect::<HashMap<_, _>>();
+
+    let mut selected = Vec::with_capacity(count);
+    let mut skipped = Vec::new();
+    let mut artist_counts = HashMap::<i64, usize>::new();
+    for &track in pool {
+        if selected.len() >= count {
+            break;
+        }
+        let artist_count = artists
+            .get(&track)
+            .map(|artist| artist_counts.entry(*artist).or_default());
+        match artist_count {
+            Some(artist_count) if *artist_count >= MAX_ARTIST_TRACKS => skipped.push(track),
+            Some(artist_count) => {
+                *artist_count += 1;
+                selected.push(track);
+            }
+            None => selected.push(track),
+        }
+    }
+    let missing = count.saturating_sub(selected.len());
+    selected.extend(
+        skipped
+            .into_iter()
+            .chain(candidates[pool.len()..].iter().copied())
+            .take(missing),
+    );
+    Ok(selected)
+}
+
+/// Random tracks not in `selected`, preferring the ones that are not excluded, used when a radio
+/// runs out of related tracks.
+async fn random_tracks(
+    db: &mut DbC,
+    radio: &Radio,
+    libraries: Option<&[LibraryId]>,
+    genre: Option<&Genre>,
+    selected: &[TrackId],
+    count: usize,
+) -> Result<Vec<TrackId>> {
+    let mut tracks =
+        query_random_tracks(db, Some(radio), libraries, genre, selected, count).await?;
+    // the radio is endless so it starts repeating tracks once it went through all of them
+    if tracks.len() < count {
+        let skipped = selected
+            .iter()
+            .chain(tracks.iter())
+            .copied()
+            .collect::<Vec<_>>();
+        let missing = count - tracks.len();
+        let repeated = query_random_tracks(db, None, libraries, genre, &skipped, missing).await?;
+        tracks.extend(repeated);
+    }
+    Ok(tracks)
+}
+
+/// Picks at most `limit` random tracks that are not in `skipped`, in one of `libraries` if set.
+/// If `radio` is given then tracks played by the radio or recently by its user are also skipped.
+async fn query_random_tracks(
+    db: &mut DbC,
+    radio: Option<&Radio>,
+    libraries: Option<&[LibraryId]>,
+    genre: Option<&Genre>,
+    skipped: &[TrackId],
+    limit: usize,
+) -> Result<Vec<TrackId>> {
+    let mut query = sqlx::QueryBuilder::<sqlx::Sqlite>::new(
+        "SELECT track.id FROM track INNER JOIN album ON album.id = track.album WHERE 1",
+    );
+    if let Some(libraries) = libraries {
+        query.push(" AND track.library IN ");
+        db::query_builder_push_id_tuple(&mut query, libraries);
+    }
+    if let Some(genre) = genre {
+        query.push(" AND EXISTS (SELECT 1 FROM genre WHERE genre.genre = ");
+        query.push_bind(genre.as_str().to_owned());
+        query.push(format!(
+            " AND ((genre.namespace = {ID_NAMESPACE_TRACK} AND genre.identifier = track.id)
+            OR (genre.namespace = {ID_NAMESPACE_ALBUM} AND genre.identifier = track.album)
+            OR (genre.namespace = {ID_NAMESPACE_ARTIST} AND genre.identifier = album.artist)))"
+        ));
+    }
+    if let Some(radio) = radio {
+        query.push(" AND track.id NOT IN (SELECT track FROM radio_track WHERE radio = ");
+        query.push_bind(radio.id);
+        query.push(") AND track.id NOT IN (SELECT track FROM scrobble WHERE user = ");
+        query.push_bind(radio.user);
+        query.push(" AND listen_at >= unixepoch() - ");
+        query.push_bind(RECENT_PERIOD);
+        query.push(")");
+    }
+    if !skipped.is_empty() {
+        query.push(" AND track.id NOT IN (");
+        let mut separated = query.separated(", ");
+        for track in skipped {
+            separated.push_bind(*track);
+        }
+        query.push(")");
+    }
+    query.push(" ORDER BY RANDOM() LIMIT ");
+    query.push_bind(limit as i64);
+    let tracks = query
+        .build_query_scalar::<i64>()
+        .fetch_all(&mut *db)
+        .await?
+        .into_iter()
+        .map(TrackId::from_db)
+        .collect();
+    Ok(tracks)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_seed_roundtrip() {
+        for seed in ["genre:rock", "sonar:track:3000001", "sonar:artist:1000002"] {
+            assert_eq!(seed.parse::<RadioSeed>().unwrap().to_string(), seed);
+        }
+        assert!("sonar:playlist:4000001".parse::<RadioSeed>().is_err());
+        assert!("rock".parse::<RadioSeed>().is_err());
+    }
+}
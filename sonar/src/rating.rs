@@ -0,0 +1,218 @@
+use std::str::FromStr;
+
+use sqlx::Row;
+
+use crate::{db::DbC, Error, ErrorKind, Result, SonarId, SonarIdentifier, Timestamp, UserId};
+
+#[derive(Debug)]
+pub struct InvalidRatingError {
+    value: String,
+}
+
+impl InvalidRatingError {
+    fn new(value: impl Into<String>) -> Self {
+        Self {
+            value: value.into(),
+        }
+    }
+}
+
+impl std::fmt::Display for InvalidRatingError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "invalid rating '{}', must be between {} and {}",
+            self.value,
+            Rating::MIN,
+            Rating::MAX
+        )
+    }
+}
+
+impl std::error::Error for InvalidRatingError {}
+
+/// A rating between 1 and 5 stars.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Rating(u8);
+
+impl Rating {
+    pub const MIN: u32 = 1;
+    pub const MAX: u32 = 5;
+
+    pub fn new(value: u32) -> Result<Self, InvalidRatingError> {
+        if (Self::MIN..=Self::MAX).contains(&value) {
+            Ok(Self(value as u8))
+        } else {
+            Err(InvalidRatingError::new(value.to_string()))
+        }
+    }
+
+    pub fn value(&self) -> u32 {
+        u32::from(self.0)
+    }
+}
+
+impl std::fmt::Display for Rating {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl FromStr for Rating {
+    type Err = InvalidRatingError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let value = s.parse::<u32>().map_err(|_| InvalidRatingError::new(s))?;
+        Self::new(value)
+    }
+}
+
+/// The rating a user gave to an item.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct UserRating {
+    pub id: SonarId,
+    pub rating: Rating,
+    pub rated_at: Timestamp,
+}
+
+/// The average of the ratings all users gave to an item.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AverageRating {
+    pub id: SonarId,
+    pub average: f64,
+    pub count: u32,
+}
+
+pub(crate) async fn user_list(db: &mut DbC, user_id: UserId) -> Result<Vec<UserRating>> {
+    let rows = sqlx::query("SELECT * FROM rating WHERE user = ? ORDER BY updated_at DESC")
+        .bind(user_id)
+        .fetch_all(db)
+        .await?;
+    rows.into_iter().map(user_rating_from_row).collect()
+}
+
+pub(crate) async fn user_get_bulk(
+    db: &mut DbC,
+    user_id: UserId,
+    ids: &[SonarId],
+) -> Result<Vec<UserRating>> {
+    if ids.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let mut query = sqlx::QueryBuilder::new("SELECT * FROM rating WHERE user = ");
+    query.push_bind(user_id);
+    query.push(" AND (namespace, identifier) IN ");
+    push_item_values(&mut query, ids);
+    let rows = query.build().fetch_all(db).await?;
+    rows.into_iter().map(user_rating_from_row).collect()
+}
+
+pub(crate) async fn user_set(
+    db: &mut DbC,
+    user_id: UserId,
+    id: SonarId,
+    rating: Rating,
+) -> Result<()> {
+    if !std::matches!(
+        id,
+        SonarId::Artist(_) | SonarId::Album(_) | SonarId::Track(_)
+    ) {
+        return Err(Error::new(ErrorKind::Invalid, "cannot rate item type"));
+    }
+
+    sqlx::query(
+        "INSERT INTO rating(user, namespace, identifier, rating) VALUES (?, ?, ?, ?)
+        ON CONFLICT(user, namespace, identifier)
+        DO UPDATE SET rating = excluded.rating, updated_at = unixepoch()",
+    )
+    .bind(user_id)
+    .bind(id.namespace())
+    .bind(id.identifier())
+    .bind(rating.value())
+    .execute(db)
+    .await?;
+    Ok(())
+}
+
+pub(crate) async fn user_remove(db: &mut DbC, user_id: UserId, id: SonarId) -> Result<()> {
+    sqlx::query("DELETE FROM rating WHERE user = ? AND namespace = ? AND identifier = ?")
+        .bind(user_id)
+        .bind(id.namespace())
+        .bind(id.identifier())
+        .execute(db)
+        .await?;
+    Ok(())
+}
+
+/// Lists every item rated by at least one user.
+pub(crate) async fn list_all(db: &mut DbC) -> Result<Vec<SonarId>> {
+    let rows = sqlx::query("SELECT DISTINCT namespace, identifier FROM rating")
+        .fetch_all(db)
+        .await?;
+    rows.iter().map(sonar_id_from_row).collect()
+}
+
+/// Returns the average rating of the given items, items without ratings are not included.
+pub(crate) async fn average_get_bulk(db: &mut DbC, ids: &[SonarId]) -> Result<Vec<AverageRating>> {
+    if ids.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let mut query = sqlx::QueryBuilder::new(
+        "SELECT namespace, identifier, AVG(rating), COUNT(*) FROM rating WHERE (namespace, identifier) IN ",
+    );
+    push_item_values(&mut query, ids);
+    query.push(" GROUP BY namespace, identifier");
+    let rows = query.build().fetch_all(db).await?;
+
+    let mut averages = Vec::with_capacity(rows.len());
+    for row in rows {
+        averages.push(AverageRating {
+            id: sonar_id_from_row(&row)?,
+            average: row.get(2),
+            count: row.get(3),
+        });
+    }
+    Ok(averages)
+}
+
+fn push_item_values(query: &mut sqlx::QueryBuilder<sqlx::Sqlite>, ids: &[SonarId]) {
+    query.push("(VALUES ");
+    let mut separated = query.separated(", ");
+    for id in ids {
+        separated.push(format!("({}, {})", id.namespace(), id.identifier()));
+    }
+    query.push(")");
+}
+
+fn sonar_id_from_row(row: &sqlx::sqlite::SqliteRow) -> Result<SonarId> {
+    let namespace = row.get::<u32, _>("namespace");
+    let identifier = row.get::<u32, _>("identifier");
+    SonarId::from_namespace_and_id(namespace, identifier)
+        .map_err(|err| Error::with_source(ErrorKind::Internal, "invalid rating item", err))
+}
+
+fn user_rating_from_row(row: sqlx::sqlite::SqliteRow) -> Result<UserRating> {
+    let rating = Rating::new(row.get("rating"))
+        .map_err(|err| Error::with_source(ErrorKind::Internal, "invalid rating", err))?;
+    Ok(UserRating {
+        id: sonar_id_from_row(&row)?,
+        rating,
+        rated_at: Timestamp::from_seconds(row.get::<i64, _>("updated_at") as u64),
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_rating_range() {
+        assert!(Rating::new(0).is_err());
+        assert_eq!(Rating::new(1).unwrap().value(), 1);
+        assert_eq!("5".parse::<Rating>().unwrap().value(), 5);
+        assert!("6".parse::<Rating>().is_err());
+        assert!("abc".parse::<Rating>().is_err());
+    }
+}
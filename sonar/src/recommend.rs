@@ -0,0 +1,543 @@
+//! Offline recommendations built from the listening data of every user on the server.
+//!
+//! Two tracks are similar when they are listened in the same sessions, listened or favorited by
+//! the same users, share genres or share artists. Artists are compared the same way using the
+//! tracks they are credited on. Nothing is fetched from external services, so recommendations
+//! get better as more music is listened on the server.
+
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    hash::{DefaultHasher, Hash, Hasher},
+};
+
+use crate::{
+    db::{self, DbC},
+    library, playlist, prop, ArtistId, Error, ErrorKind, LibraryFilter, LibraryId, Playlist,
+    PlaylistCreate, PlaylistId, Properties, PropertyValue, Result, SonarId, Timestamp, TrackId,
+    UserId, ID_NAMESPACE_ALBUM, ID_NAMESPACE_ARTIST, ID_NAMESPACE_PLAYLIST, ID_NAMESPACE_TRACK,
+};
+
+const SECONDS_PER_DAY: i64 = 24 * 60 * 60;
+/// Scrobbles of the same user this close to each other, in seconds, belong to the same session.
+const SESSION_WINDOW: i64 = 30 * 60;
+const SESSION_WEIGHT: f64 = 3.0;
+const FAVORITE_WEIGHT: f64 = 2.0;
+const LISTENER_WEIGHT: f64 = 1.0;
+const GENRE_WEIGHT: f64 = 0.5;
+const CREDIT_WEIGHT: f64 = 0.5;
+/// Maximum number of tracks of an album or artist used when looking for similar tracks.
+const MAX_SEED_TRACKS: u32 = 25;
+/// Daily mixes are built around the artists most listened in this period.
+const DAILY_MIX_SEED_PERIOD: i64 = 90 * SECONDS_PER_DAY;
+/// Tracks listened in this period are left out of daily mixes.
+const DAILY_MIX_RECENT_PERIOD: i64 = SECONDS_PER_DAY;
+/// Number of similar artists added to the seed artist of a daily mix.
+const DAILY_MIX_SIMILAR_ARTISTS: u32 = 5;
+
+#[derive(Debug, Clone)]
+pub struct DailyMixParams {
+    pub user: UserId,
+    /// Maximum number of mixes to generate.
+    pub mixes: u32,
+    /// Maximum number of tracks in each mix.
+    pub tracks: u32,
+}
+
+impl DailyMixParams {
+    pub const DEFAULT_MIXES: u32 = 3;
+    pub const DEFAULT_TRACKS: u32 = 50;
+
+    pub fn new(user: UserId) -> Self {
+        Self {
+            user,
+            mixes: Self::DEFAULT_MIXES,
+            tracks: Self::DEFAULT_TRACKS,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DailyMix {
+    /// The artist the mix was built around.
+    pub artist: ArtistId,
+    pub tracks: Vec<TrackId>,
+}
+
+/// Tracks similar to a track, album or artist, most similar first.
+/// Only tracks in `libraries` are returned, if set.
+#[tracing::instrument(skip(db))]
+pub async fn similar_tracks(
+    db: &mut DbC,
+    libraries: Option<&[LibraryId]>,
+    id: SonarId,
+    limit: u32,
+) -> Result<Vec<TrackId>> {
+    let seeds =
+        match id {
+            SonarId::Track(track_id) => vec![track_id.to_db()],
+            SonarId::Album(album_id) => sqlx::query_scalar(
+                "SELECT id FROM track WHERE album = ? ORDER BY listen_count DESC, id ASC LIMIT ?",
+            )
+            .bind(album_id.to_db())
+            .bind(MAX_SEED_TRACKS)
+            .fetch_all(&mut *db)
+            .await?,
+            SonarId::Artist(artist_id) => {
+                sqlx::query_scalar(
+                    "SELECT track.id FROM view_track_credit
+                INNER JOIN track ON track.id = view_track_credit.track
+                WHERE view_track_credit.artist = ?
+                ORDER BY track.listen_count DESC, track.id ASC LIMIT ?",
+                )
+                .bind(artist_id.to_db())
+                .bind(MAX_SEED_TRACKS)
+                .fetch_all(&mut *db)
+                .await?
+            }
+            _ => {
+                return Err(Error::new(
+                    ErrorKind::Invalid,
+                    "similar tracks can only be found for tracks, albums and artists",
+                ))
+            }
+        };
+
+    let mut scores = scores(db, &track_sources(), &seeds).await?;
+    if let Some(libraries) = libraries {
+        retain_in_libraries(db, "track", &mut scores, libraries).await?;
+    }
+    Ok(rank(scores, &seeds, limit)
+        .into_iter()
+        .map(TrackId::from_db)
+        .collect())
+}
+
+/// Similarity scores of the tracks related to any of the seed tracks.
+/// Seeds are only scored if they are related to other seeds.
+#[tracing::instrument(skip(db))]
+pub async fn track_scores(db: &mut DbC, seeds: &[TrackId]) -> Result<HashMap<TrackId, f64>> {
+    let seeds = seeds.iter().map(|seed| seed.to_db()).collect::<Vec<_>>();
+    let scores = scores(db, &track_sources(), &seeds).await?;
+    Ok(scores
+        .into_iter()
+        .map(|(id, score)| (TrackId::from_db(id), score))
+        .collect())
+}
+
+/// Similarity scores of the artists related to an artist.
+#[tracing::instrument(skip(db))]
+pub async fn artist_scores(db: &mut DbC, artist_id: ArtistId) -> Result<HashMap<ArtistId, f64>> {
+    let scores = scores(db, &artist_sources(), &[artist_id.to_db()]).await?;
+    Ok(scores
+        .into_iter()
+        .map(|(id, score)| (ArtistId::from_db(id), score))
+        .collect())
+}
+
+/// Artists similar to an artist, most similar first.
+/// Only artists in `libraries` are returned, if set.
+#[tracing::instrument(skip(db))]
+pub async fn similar_artists(
+    db: &mut DbC,
+    libraries: Option<&[LibraryId]>,
+    artist_id: ArtistId,
+    limit: u32,
+) -> Result<Vec<ArtistId>> {
+    let seed = artist_id.to_db();
+    let mut scores = scores(db, &artist_sources(), &[seed]).await?;
+    if let Some(libraries) = libraries {
+        retain_in_libraries(db, "artist", &mut scores, libraries).await?;
+    }
+    Ok(rank(scores, &[seed], limit)
+        .into_iter()
+        .map(ArtistId::from_db)
+        .collect())
+}
+
+/// Generates mixes for a user, each built around one of the artists the user listens the most
+/// together with similar artists. The order of the tracks changes every day and tracks the user
+/// listened in the last day are left out, as are tracks in libraries the user has no access to.
+#[tracing::instrument(skip(db))]
+pub async fn daily_mixes(db: &mut DbC, params: DailyMixParams) -> Result<Vec<DailyMix>> {
+    let libraries = library::resolve(db, LibraryFilter::user(params.user)).await?;
+    let user = params.user.to_db();
+    let now = Timestamp::now().seconds() as i64;
+    let day = now / SECONDS_PER_DAY;
+
+    let mut seeds = sqlx::query_scalar::<_, i64>(
+        "SELECT view_track_credit.artist FROM scrobble
+        INNER JOIN view_track_credit ON view_track_credit.track = scrobble.track
+        WHERE scrobble.user = ? AND scrobble.listen_at >= ?
+        GROUP BY view_track_credit.artist
+        ORDER BY COUNT(*) DESC, view_track_credit.artist ASC
+        LIMIT ?",
+    )
+    .bind(user)
+    .bind(now - DAILY_MIX_SEED_PERIOD)
+    .bind(params.mixes)
+    .fetch_all(&mut *db)
+    .await?;
+    // users that did not listen to much recently still get mixes from their favorite artists
+    let favorites = sqlx::query_scalar::<_, i64>(&format!(
+        "SELECT identifier FROM favorite WHERE user = ? AND namespace = {ID_NAMESPACE_ARTIST}
+        ORDER BY created_at DESC"
+    ))
+    .bind(user)
+    .fetch_all(&mut *db)
+    .await?;
+    for artist in favorites {
+        if seeds.len() >= params.mixes as usize {
+            break;
+        }
+        if !seeds.contains(&artist) {
+            seeds.push(artist);
+        }
+    }
+
+    let mut excluded = sqlx::query_scalar::<_, i64>(
+        "SELECT DISTINCT track FROM scrobble WHERE user = ? AND listen_at >= ?",
+    )
+    .bind(user)
+    .bind(now - DAILY_MIX_RECENT_PERIOD)
+    .fetch_all(&mut *db)
+    .await?
+    .into_iter()
+    .collect::<HashSet<_>>();
+
+    let mut mixes = Vec::with_capacity(seeds.len());
+    for seed in seeds {
+        let mut artists = vec![seed];
+        artists.extend(
+            similar_artists(
+                db,
+                libraries.as_deref(),
+                ArtistId::from_db(seed),
+                DAILY_MIX_SIMILAR_ARTISTS,
+            )
+            .await?
+            .into_iter()
+            .map(|artist| artist.to_db()),
+        );
+
+        let mut queues = Vec::with_capacity(artists.len());
+        for artist in artists {
+            let mut query = sqlx::QueryBuilder::<sqlx::Sqlite>::new(
+                "SELECT view_track_credit.track FROM view_track_credit
+                INNER JOIN track ON track.id = view_track_credit.track
+                WHERE view_track_credit.artist = ",
+            );
+            query.push_bind(artist);
+            if let Some(libraries) = libraries.as_deref() {
+                query.push(" AND track.library IN ");
+                db::query_builder_push_id_tuple(&mut query, libraries);
+            }
+            let mut tracks = query
+                .build_query_scalar::<i64>()
+                .fetch_all(&mut *db)
+                .await?;
+            tracks.retain(|track| !excluded.contains(track));
+            tracks.sort_by_key(|&track| shuffle_key(user, day, track));
+            queues.push(VecDeque::from(tracks));
+        }
+
+        // artists take turns so a mix is not taken over by the seed artist
+        let mut tracks = Vec::new();
+        while tracks.len() < params.tracks as usize && queues.iter().any(|q| !q.is_empty()) {
+            for queue in queues.iter_mut() {
+                if tracks.len() >= params.tracks as usize {
+                    break;
+                }
+                if let Some(track) = queue.pop_front() {
+                    // tracks only show up in one of the mixes
+                    if excluded.insert(track) {
+                        tracks.push(TrackId::from_db(track));
+                    }
+                }
+            }
+        }
+
+        if !tracks.is_empty() {
+            mixes.push(DailyMix {
+                artist: ArtistId::from_db(seed),
+                tracks,
+            });
+        }
+    }
+    Ok(mixes)
+}
+
+/// Regenerates the daily mixes of a user and stores them as playlists named `Daily Mix N`.
+/// Daily mix playlists are marked with the [`prop::DAILY_MIX`] property, existing ones are reused
+/// and the ones no longer needed are deleted.
+/// Returns the daily mix playlists and the ids of the deleted playlists.
+#[tracing::instrument(skip(db))]
+pub async fn refresh_daily_mix_playlists(
+    db: &mut DbC,
+    params: DailyMixParams,
+) -> Result<(Vec<Playlist>, Vec<PlaylistId>)> {
+    let owner = params.user;
+    let mixes = daily_mixes(db, params).await?;
+
+    let mut existing = sqlx::query_as::<_, (i64, String)>(&format!(
+        "SELECT playlist.id, property.value FROM playlist
+        INNER JOIN property ON property.namespace = {ID_NAMESPACE_PLAYLIST} AND property.identifier = playlist.id
+        WHERE playlist.owner = ? AND property.key = ?"
+    ))
+    .bind(owner.to_db())
+    .bind(prop::DAILY_MIX.as_str())
+    .fetch_all(&mut *db)
+    .await?
+    .into_iter()
+    .filter_map(|(id, number)| Some((number.parse::<usize>().ok()?, PlaylistId::from_db(id))))
+    .collect::<HashMap<_, _>>();
+
+    let mut playlists = Vec::with_capacity(mixes.len());
+    for (index, mix) in mixes.into_iter().enumerate() {
+        let number = index + 1;
+        let playlist = match existing.remove(&number) {
+            Some(playlist_id) => {
+                playlist::clear_tracks(db, playlist_id).await?;
+                playlist::insert_tracks(db, playlist_id, &mix.tracks).await?;
+                playlist::get(db, playlist_id).await?
+            }
+            None => {
+                let mut properties = Properties::default();
+                properties.insert(
+                    prop::DAILY_MIX,
+                    PropertyValue::new(number.to_string()).expect("number is a valid value"),
+                );
+                playlist::create(
+                    db,
+                    PlaylistCreate {
+                        name: format!("Daily Mix {number}"),
+                        owner,
+                        tracks: mix.tracks,
+                        cover_art: None,
+                        properties,
+                    },
+                )
+                .await?
+            }
+        };
+        playlists.push(playlist);
+    }
+
+    let deleted = existing.into_values().collect::<Vec<_>>();
+    for &playlist_id in deleted.iter() {
+        playlist::delete(db, playlist_id).await?;
+    }
+    Ok((playlists, deleted))
+}
+
+/// Queries that score tracks related to the track bound to `?1`.
+fn track_sources() -> [(String, f64); 5] {
+    [
+        (
+            format!(
+                "SELECT other.track, COUNT(*) FROM scrobble AS seed
+                INNER JOIN scrobble AS other ON other.user = seed.user
+                    AND other.listen_at BETWEEN seed.listen_at - {SESSION_WINDOW} AND seed.listen_at + {SESSION_WINDOW}
+                WHERE seed.track = ?1 AND other.track != ?1
+                GROUP BY other.track"
+            ),
+            SESSION_WEIGHT,
+        ),
+        (
+            "SELECT track, COUNT(DISTINCT user) FROM scrobble
+            WHERE user IN (SELECT user FROM scrobble WHERE track = ?1) AND track != ?1
+            GROUP BY track"
+                .to_string(),
+            LISTENER_WEIGHT,
+        ),
+        (
+            format!(
+                "SELECT other.identifier, COUNT(*) FROM favorite AS seed
+                INNER JOIN favorite AS other ON other.user = seed.user AND other.namespace = {ID_NAMESPACE_TRACK}
+                WHERE seed.namespace = {ID_NAMESPACE_TRACK} AND seed.identifier = ?1 AND other.identifier != ?1
+                GROUP BY other.identifier"
+            ),
+            FAVORITE_WEIGHT,
+        ),
+        (
+            format!(
+                "WITH track_genre (track, genre) AS (
+                    SELECT identifier, genre FROM genre WHERE namespace = {ID_NAMESPACE_TRACK}
+                    UNION
+                    SELECT track.id, genre.genre FROM track
+                    INNER JOIN genre ON genre.namespace = {ID_NAMESPACE_ALBUM} AND genre.identifier = track.album
+                    UNION
+                    SELECT view_track_credit.track, genre.genre FROM view_track_credit
+                    INNER JOIN genre ON genre.namespace = {ID_NAMESPACE_ARTIST} AND genre.identifier = view_track_credit.artist
+                )
+                SELECT other.track, COUNT(*) FROM track_genre AS seed
+                INNER JOIN track_genre AS other ON other.genre = seed.genre
+                WHERE seed.track = ?1 AND other.track != ?1
+                GROUP BY other.track"
+            ),
+            GENRE_WEIGHT,
+        ),
+        (
+            "SELECT other.track, COUNT(*) FROM view_track_credit AS seed
+            INNER JOIN view_track_credit AS other ON other.artist = seed.artist
+            WHERE seed.track = ?1 AND other.track != ?1
+            GROUP BY other.track"
+                .to_string(),
+            CREDIT_WEIGHT,
+        ),
+    ]
+}
+
+/// Queries that score artists related to the artist bound to `?1`.
+fn artist_sources() -> [(String, f64); 5] {
+    [
+        (
+            format!(
+                "SELECT other_credit.artist, COUNT(*) FROM view_track_credit AS seed_credit
+                INNER JOIN scrobble AS seed ON seed.track = seed_credit.track
+                INNER JOIN scrobble AS other ON other.user = seed.user
+                    AND other.listen_at BETWEEN seed.listen_at - {SESSION_WINDOW} AND seed.listen_at + {SESSION_WINDOW}
+                INNER JOIN view_track_credit AS other_credit ON other_credit.track = other.track
+                WHERE seed_credit.artist = ?1 AND other_credit.artist != ?1
+                GROUP BY other_credit.artist"
+            ),
+            SESSION_WEIGHT,
+        ),
+        (
+            "SELECT view_track_credit.artist, COUNT(DISTINCT scrobble.user) FROM scrobble
+            INNER JOIN view_track_credit ON view_track_credit.track = scrobble.track
+            WHERE scrobble.user IN (
+                SELECT scrobble.user FROM scrobble
+                INNER JOIN view_track_credit ON view_track_credit.track = scrobble.track
+                WHERE view_track_credit.artist = ?1
+            ) AND view_track_credit.artist != ?1
+            GROUP BY view_track_credit.artist"
+                .to_string(),
+            LISTENER_WEIGHT,
+        ),
+        (
+            format!(
+                "SELECT other.identifier, COUNT(*) FROM favorite AS seed
+                INNER JOIN favorite AS other ON other.user = seed.user AND other.namespace = {ID_NAMESPACE_ARTIST}
+                WHERE seed.namespace = {ID_NAMESPACE_ARTIST} AND seed.identifier = ?1 AND other.identifier != ?1
+                GROUP BY other.identifier"
+            ),
+            FAVORITE_WEIGHT,
+        ),
+        (
+            format!(
+                "WITH artist_genre (artist, genre) AS (
+                    SELECT identifier, genre FROM genre WHERE namespace = {ID_NAMESPACE_ARTIST}
+                    UNION
+                    SELECT album_credit.artist, genre.genre FROM album_credit
+                    INNER JOIN genre ON genre.namespace = {ID_NAMESPACE_ALBUM} AND genre.identifier = album_credit.album
+                )
+                SELECT other.artist, COUNT(*) FROM artist_genre AS seed
+                INNER JOIN artist_genre AS other ON other.genre = seed.genre
+                WHERE seed.artist = ?1 AND other.artist != ?1
+                GROUP BY other.artist"
+            ),
+            GENRE_WEIGHT,
+        ),
+        (
+            "SELECT other.artist, COUNT(*) FROM view_track_credit AS seed
+            INNER JOIN view_track_credit AS other ON other.track = seed.track
+            WHERE seed.artist = ?1 AND other.artist != ?1
+            GROUP BY other.artist"
+                .to_string(),
+            CREDIT_WEIGHT,
+        ),
+    ]
+}
+
+async fn scores(
+    db: &mut DbC,
+    sources: &[(String, f64)],
+    seeds: &[i64],
+) -> Result<HashMap<i64, f64>> {
+    let mut scores = HashMap::new();
+    for &seed in seeds {
+        for (query, weight) in sources {
+            let rows = sqlx::query_as::<_, (i64, i64)>(query)
+                .bind(seed)
+                .fetch_all(&mut *db)
+                .await?;
+            for (id, count) in rows {
+                // the logarithm keeps a single very popular source from drowning out the others
+                *scores.entry(id).or_default() += weight * (count as f64).ln_1p();
+            }
+        }
+    }
+    Ok(scores)
+}
+
+/// Removes the scored ids of `table` that are not in one of the libraries.
+async fn retain_in_libraries(
+    db: &mut DbC,
+    table: &str,
+    scores: &mut HashMap<i64, f64>,
+    libraries: &[LibraryId],
+) -> Result<()> {
+    if scores.is_empty() {
+        return Ok(());
+    }
+    let mut query = sqlx::QueryBuilder::<sqlx::Sqlite>::new(format!(
+        "SELECT id FROM {table} WHERE library IN "
+    ));
+    db::query_builder_push_id_tuple(&mut query, libraries);
+    query.push("AND id IN (");
+    let mut separated = query.separated(", ");
+    for id in scores.keys() {
+        separated.push(id);
+    }
+    query.push(")");
+    let retained = query
+        .build_query_scalar::<i64>()
+        .fetch_all(&mut *db)
+        .await?
+        .into_iter()
+        .collect::<HashSet<_>>();
+    scores.retain(|id, _| retained.contains(id));
+    Ok(())
+}
+
+/// Sorts the scored ids from highest to lowest score, leaving out the excluded ones.
+fn rank(scores: HashMap<i64, f64>, exclude: &[i64], limit: u32) -> Vec<i64> {
+    let mut scores = scores
+        .into_iter()
+        .filter(|(id, _)| !exclude.contains(id))
+        .collect::<Vec<_>>();
+    scores.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(&b.0)));
+    scores
+        .into_iter()
+        .take(limit as usize)
+        .map(|(id, _)| id)
+        .collect()
+}
+
+/// A key that shuffles tracks differently for each user and day.
+fn shuffle_key(user: i64, day: i64, track: i64) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    (user, day, track).hash(&mut hasher);
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_rank() {
+        let scores = HashMap::from([(1, 0.5), (2, 2.0), (3, 1.0), (4, 1.0)]);
+        assert_eq!(rank(scores.clone(), &[], 10), vec![2, 3, 4, 1]);
+        assert_eq!(rank(scores.clone(), &[3], 2), vec![2, 4]);
+        assert_eq!(rank(scores, &[], 0), Vec::<i64>::new());
+    }
+
+    #[test]
+    fn test_shuffle_key() {
+        assert_eq!(shuffle_key(1, 2, 3), shuffle_key(1, 2, 3));
+        let day1 = (0..20).map(|t| shuffle_key(1, 1, t)).collect::<Vec<_>>();
+        let day2 = (0..20).map(|t| shuffle_key(1, 2, t)).collect::<Vec<_>>();
+        assert_ne!(day1, day2);
+    }
+}
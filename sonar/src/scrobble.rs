@@ -126,6 +126,14 @@ pub async fn delete(db: &mut DbC, scrobble_id: ScrobbleId) -> Result<()> {
     Ok(())
 }
 
+/// Lists every track scrobbled by at least one user.
+pub async fn list_scrobbled_tracks(db: &mut DbC) -> Result<Vec<TrackId>> {
+    let track_ids = sqlx::query_scalar::<_, i64>("SELECT DISTINCT track FROM scrobble")
+        .fetch_all(&mut *db)
+        .await?;
+    Ok(track_ids.into_iter().map(TrackId::from_db).collect())
+}
+
 #[tracing::instrument(skip(db))]
 pub async fn list_unsubmitted(db: &mut DbC, scrobbler: &str) -> Result<Vec<Scrobble>> {
     let ids = sqlx::query_scalar(
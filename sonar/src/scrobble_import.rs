@@ -0,0 +1,538 @@
+//! Bulk import of listens exported from other services.
+//! Every listen is matched to a local track, first by its MusicBrainz recording id and then by
+//! artist and track name. The album name and duration are used to choose between tracks with the
+//! same name. Listens that can not be matched are returned in the report.
+
+use std::{borrow::Cow, collections::HashMap, str::FromStr, time::Duration};
+
+use serde_json::Value;
+
+use crate::{
+    db::DbC, prop, property, scrobble, Error, ErrorKind, Result, ScrobbleCreate, ScrobbleId,
+    SonarId, Timestamp, TrackId, UserId,
+};
+
+/// Tracks whose duration differs from the listen by more than this are not a match.
+const DURATION_TOLERANCE: Duration = Duration::from_secs(10);
+
+#[derive(Debug)]
+pub struct InvalidScrobbleImportFormatError {
+    message: Cow<'static, str>,
+}
+
+impl InvalidScrobbleImportFormatError {
+    fn new(message: impl Into<Cow<'static, str>>) -> Self {
+        Self {
+            message: message.into(),
+        }
+    }
+}
+
+impl std::fmt::Display for InvalidScrobbleImportFormatError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} is not a valid scrobble import format", self.message)
+    }
+}
+
+impl std::error::Error for InvalidScrobbleImportFormatError {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ScrobbleImportFormat {
+    /// ListenBrainz export, one listen per line or a single json array.
+    ListenBrainz,
+    /// Last.fm `user.getRecentTracks` responses.
+    LastFmJson,
+    /// Last.fm csv dump, either with a header or with `artist,album,track,date` rows.
+    LastFmCsv,
+}
+
+impl ScrobbleImportFormat {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ScrobbleImportFormat::ListenBrainz => "listenbrainz",
+            ScrobbleImportFormat::LastFmJson => "lastfm-json",
+            ScrobbleImportFormat::LastFmCsv => "lastfm-csv",
+        }
+    }
+
+    /// Parses the listens of an export file.
+    pub fn parse(&self, content: &str) -> Result<Vec<ScrobbleImportEntry>> {
+        let content = content.trim_start_matches('\u{feff}');
+        match self {
+            ScrobbleImportFormat::ListenBrainz => parse_listenbrainz(content),
+            ScrobbleImportFormat::LastFmJson => parse_lastfm_json(content),
+            ScrobbleImportFormat::LastFmCsv => parse_lastfm_csv(content),
+        }
+    }
+}
+
+impl std::fmt::Display for ScrobbleImportFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for ScrobbleImportFormat {
+    type Err = InvalidScrobbleImportFormatError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "listenbrainz" => Ok(ScrobbleImportFormat::ListenBrainz),
+            "lastfm-json" => Ok(ScrobbleImportFormat::LastFmJson),
+            "lastfm-csv" => Ok(ScrobbleImportFormat::LastFmCsv),
+            _ => Err(InvalidScrobbleImportFormatError::new(s.to_owned())),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScrobbleImportEntry {
+    pub artist: String,
+    pub album: Option<String>,
+    pub title: String,
+    pub duration: Option<Duration>,
+    /// MusicBrainz recording id.
+    pub recording_mbid: Option<String>,
+    pub listen_at: Timestamp,
+}
+
+#[derive(Debug, Clone)]
+pub struct ScrobbleImport {
+    pub user: UserId,
+    /// Identifier of the scrobbler the listens were exported from.
+    /// The imported scrobbles are registered as submitted to it and to every registered scrobbler
+    /// so they are never forwarded.
+    pub scrobbler: Option<String>,
+    pub listen_device: String,
+    pub entries: Vec<ScrobbleImportEntry>,
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct ScrobbleImportReport {
+    pub imported: u64,
+    /// Number of entries for which the user already had a scrobble.
+    pub duplicates: u64,
+    pub unmatched: Vec<ScrobbleImportEntry>,
+}
+
+type MatchKey = (Option<String>, String, Option<String>, String, Option<u64>);
+
+/// Imports the listens, `scrobblers` are the identifiers of the registered scrobblers.
+/// Returns the report along with the ids of the created scrobbles.
+#[tracing::instrument(skip(db, import), fields(entries = import.entries.len()))]
+pub async fn import(
+    db: &mut DbC,
+    import: ScrobbleImport,
+    scrobblers: &[&str],
+) -> Result<(ScrobbleImportReport, Vec<ScrobbleId>)> {
+    let mut submitted_to = scrobblers.to_vec();
+    if let Some(ref scrobbler) = import.scrobbler {
+        if !submitted_to.contains(&scrobbler.as_str()) {
+            submitted_to.push(scrobbler.as_str());
+        }
+    }
+
+    let mut report = ScrobbleImportReport::default();
+    let mut created = Vec::new();
+    let mut matches: HashMap<MatchKey, Option<(TrackId, Duration)>> = HashMap::new();
+    for entry in import.entries {
+        let key = (
+            entry.recording_mbid.clone(),
+            entry.artist.to_lowercase(),
+            entry.album.as_ref().map(|album| album.to_lowercase()),
+            entry.title.to_lowercase(),
+            entry.duration.map(|duration| duration.as_secs()),
+        );
+        let matched = match matches.get(&key) {
+            Some(matched) => *matched,
+            None => {
+                let matched = find_track(db, &entry).await?;
+                matches.insert(key, matched);
+                matched
+            }
+        };
+        let (track_id, track_duration) = match matched {
+            Some(matched) => matched,
+            None => {
+                report.unmatched.push(entry);
+                continue;
+            }
+        };
+
+        let exists = sqlx::query_scalar::<_, bool>(
+            "SELECT EXISTS (SELECT 1 FROM scrobble WHERE user = ? AND track = ? AND listen_at = ?)",
+        )
+        .bind(import.user.to_db())
+        .bind(track_id.to_db())
+        .bind(entry.listen_at.seconds() as i64)
+        .fetch_one(&mut *db)
+        .await?;
+        if exists {
+            report.duplicates += 1;
+            continue;
+        }
+
+        let scrobble = scrobble::create(
+            db,
+            ScrobbleCreate {
+                user: import.user,
+                track: track_id,
+                listen_at: entry.listen_at,
+                listen_duration: entry.duration.unwrap_or(track_duration),
+                listen_device: import.listen_device.clone(),
+                properties: Default::default(),
+            },
+        )
+        .await?;
+        for scrobbler in submitted_to.iter() {
+            scrobble::register_submission(db, scrobble.id, scrobbler).await?;
+        }
+        created.push(scrobble.id);
+        report.imported += 1;
+    }
+    Ok((report, created))
+}
+
+async fn find_track(
+    db: &mut DbC,
+    entry: &ScrobbleImportEntry,
+) -> Result<Option<(TrackId, Duration)>> {
+    if let Some(ref mbid) = entry.recording_mbid {
+        let ids = property::list_with_value(db, &prop::EXTERNAL_MUSICBRAINZ_ID, mbid).await?;
+        for id in ids {
+            if let SonarId::Track(track_id) = id {
+                let duration_ms = sqlx::query_scalar::<_, Option<i64>>(
+                    "SELECT duration_ms FROM sqlx_track WHERE id = ?",
+                )
+                .bind(track_id.to_db())
+                .fetch_one(&mut *db)
+                .await?;
+                let duration = Duration::from_millis(duration_ms.unwrap_or_default() as u64);
+                return Ok(Some((track_id, duration)));
+            }
+        }
+    }
+
+    let candidates = sqlx::query_as::<_, (i64, String, Option<i64>)>(
+        "SELECT sqlx_track.id, album.name, sqlx_track.duration_ms
+        FROM sqlx_track
+        INNER JOIN album ON album.id = sqlx_track.album
+        WHERE sqlx_track.name = ? COLLATE NOCASE
+        AND EXISTS (
+            SELECT 1 FROM view_track_credit
+            INNER JOIN artist ON artist.id = view_track_credit.artist
+            WHERE view_track_credit.track = sqlx_track.id AND artist.name = ? COLLATE NOCASE
+        )",
+    )
+    .bind(entry.title.trim())
+    .bind(entry.artist.trim())
+    .fetch_all(&mut *db)
+    .await?;
+
+    // tracks from the same album are preferred, then the one with the closest duration
+    let best = candidates
+        .into_iter()
+        .map(|(track_id, album, duration_ms)| {
+            let duration = Duration::from_millis(duration_ms.unwrap_or_default() as u64);
+            (TrackId::from_db(track_id), album, duration)
+        })
+        .filter(|(_, _, duration)| match entry.duration {
+            Some(expected) => {
+                duration.is_zero() || duration.abs_diff(expected) <= DURATION_TOLERANCE
+            }
+            None => true,
+        })
+        .min_by_key(|(_, album, duration)| {
+            let album_mismatch = match entry.album {
+                Some(ref expected) => !expected.trim().eq_ignore_ascii_case(album.trim()),
+                None => false,
+            };
+            let duration_diff = entry
+                .duration
+                .map(|expected| duration.abs_diff(expected))
+                .unwrap_or_default();
+            (album_mismatch, duration_diff)
+        });
+    Ok(best.map(|(track_id, _, duration)| (track_id, duration)))
+}
+
+fn parse_listenbrainz(content: &str) -> Result<Vec<ScrobbleImportEntry>> {
+    let listens: Vec<Value> = if content.trim_start().starts_with('[') {
+        serde_json::from_str(content).map_err(invalid_json)?
+    } else {
+        content
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(serde_json::from_str)
+            .collect::<Result<_, _>>()
+            .map_err(invalid_json)?
+    };
+
+    let mut entries = Vec::with_capacity(listens.len());
+    for listen in listens {
+        let metadata = &listen["track_metadata"];
+        let info = &metadata["additional_info"];
+        let (Some(listened_at), Some(artist), Some(title)) = (
+            listen["listened_at"].as_u64(),
+            metadata["artist_name"].as_str(),
+            metadata["track_name"].as_str(),
+        ) else {
+            return Err(Error::new(
+                ErrorKind::Invalid,
+                "listenbrainz listen is missing required fields",
+            ));
+        };
+        let duration = info["duration_ms"]
+            .as_u64()
+            .map(Duration::from_millis)
+            .or_else(|| info["duration"].as_u64().map(Duration::from_secs));
+        let recording_mbid = metadata["mbid_mapping"]["recording_mbid"]
+            .as_str()
+            .or_else(|| info["recording_mbid"].as_str());
+        entries.push(ScrobbleImportEntry {
+            artist: artist.to_owned(),
+            album: non_empty(metadata["release_name"].as_str()),
+            title: title.to_owned(),
+            duration,
+            recording_mbid: non_empty(recording_mbid),
+            listen_at: Timestamp::from_seconds(listened_at),
+        });
+    }
+    Ok(entries)
+}
+
+fn parse_lastfm_json(content: &str) -> Result<Vec<ScrobbleImportEntry>> {
+    let value: Value = serde_json::from_str(content).map_err(invalid_json)?;
+    let mut entries = Vec::new();
+    collect_lastfm_tracks(&value, &mut entries)?;
+    Ok(entries)
+}
+
+/// Dumps are either a list of tracks or a list of `recenttracks` pages.
+fn collect_lastfm_tracks(value: &Value, entries: &mut Vec<ScrobbleImportEntry>) -> Result<()> {
+    match value {
+        Value::Array(values) => {
+            for value in values {
+                collect_lastfm_tracks(value, entries)?;
+            }
+        }
+        Value::Object(object) if object.contains_key("recenttracks") => {
+            collect_lastfm_tracks(&object["recenttracks"], entries)?;
+        }
+        Value::Object(object) if object.contains_key("track") => {
+            collect_lastfm_tracks(&object["track"], entries)?;
+        }
+        Value::Object(_) => entries.extend(parse_lastfm_track(value)?),
+        _ => {}
+    }
+    Ok(())
+}
+
+fn parse_lastfm_track(track: &Value) -> Result<Option<ScrobbleImportEntry>> {
+    let uts = &track["date"]["uts"];
+    let listened_at = match uts.as_str() {
+        Some(uts) => uts.parse::<u64>().ok(),
+        None => uts.as_u64(),
+    };
+    // the track that is currently playing has no date
+    let listened_at = match listened_at {
+        Some(listened_at) => listened_at,
+        None if uts.is_null() => return Ok(None),
+        None => return Err(Error::new(ErrorKind::Invalid, "invalid last.fm timestamp")),
+    };
+
+    let artist = track["artist"]["#text"]
+        .as_str()
+        .or_else(|| track["artist"]["name"].as_str());
+    let (Some(artist), Some(title)) = (artist, track["name"].as_str()) else {
+        return Err(Error::new(
+            ErrorKind::Invalid,
+            "last.fm track is missing required fields",
+        ));
+    };
+    Ok(Some(ScrobbleImportEntry {
+        artist: artist.to_owned(),
+        album: non_empty(track["album"]["#text"].as_str()),
+        title: title.to_owned(),
+        duration: None,
+        recording_mbid: non_empty(track["mbid"].as_str()),
+        listen_at: Timestamp::from_seconds(listened_at),
+    }))
+}
+
+fn parse_lastfm_csv(content: &str) -> Result<Vec<ScrobbleImportEntry>> {
+    let mut rows = content
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(index, line)| (index + 1, parse_csv_row(line)))
+        .peekable();
+
+    let is_header = |row: &[String]| {
+        let has_column = |names: &[&str]| {
+            row.iter()
+                .any(|field| names.iter().any(|name| field.eq_ignore_ascii_case(name)))
+        };
+        has_column(&["artist"]) && has_column(&["uts", "utc_time", "date", "timestamp"])
+    };
+    let header = match rows.peek() {
+        Some((_, row)) if is_header(row) => {
+            let header = row
+                .iter()
+                .map(|field| field.to_lowercase())
+                .collect::<Vec<_>>();
+            rows.next();
+            Some(header)
+        }
+        _ => None,
+    };
+    let column = |names: &[&str]| -> Option<usize> {
+        let header = header.as_ref()?;
+        names
+            .iter()
+            .find_map(|name| header.iter().position(|field| field == name))
+    };
+    // files without a header are `artist,album,track,date`
+    let artist_column = column(&["artist"]).unwrap_or(0);
+    let album_column = column(&["album"]).unwrap_or(1);
+    let title_column = column(&["track", "title", "name"]).unwrap_or(2);
+    let date_column = column(&["utc_time", "date"]).unwrap_or(3);
+    let uts_column = column(&["uts", "timestamp"]);
+    let mbid_column = column(&["track_mbid", "mbid"]);
+
+    let mut entries = Vec::new();
+    for (line, row) in rows {
+        let field = |column: usize| row.get(column).map(String::as_str).unwrap_or_default();
+        let listen_at = match uts_column {
+            Some(column) => field(column).parse::<u64>().ok(),
+            None => parse_lastfm_date(field(date_column)),
+        };
+        let (Some(listen_at), false, false) = (
+            listen_at,
+            field(artist_column).is_empty(),
+            field(title_column).is_empty(),
+        ) else {
+            return Err(Error::new(
+                ErrorKind::Invalid,
+                format!("invalid last.fm csv row on line {line}"),
+            ));
+        };
+        entries.push(ScrobbleImportEntry {
+            artist: field(artist_column).to_owned(),
+            album: non_empty(Some(field(album_column))),
+            title: field(title_column).to_owned(),
+            duration: None,
+            recording_mbid: non_empty(mbid_column.map(field)),
+            listen_at: Timestamp::from_seconds(listen_at),
+        });
+    }
+    Ok(entries)
+}
+
+/// Parses dates like `31 Jan 2020 12:34` or `31 Jan 2020, 12:34`, in UTC.
+fn parse_lastfm_date(date: &str) -> Option<u64> {
+    ["%d %b %Y %H:%M", "%d %b %Y, %H:%M"]
+        .iter()
+        .find_map(|format| chrono::NaiveDateTime::parse_from_str(date.trim(), format).ok())
+        .and_then(|date| u64::try_from(date.and_utc().timestamp()).ok())
+}
+
+fn parse_csv_row(line: &str) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => fields.push(std::mem::take(&mut field)),
+            _ => field.push(c),
+        }
+    }
+    fields.push(field);
+    fields
+}
+
+fn non_empty(value: Option<&str>) -> Option<String> {
+    value
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+        .map(ToOwned::to_owned)
+}
+
+fn invalid_json(err: serde_json::Error) -> Error {
+    Error::with_source(ErrorKind::Invalid, "invalid json in scrobble export", err)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_scrobble_import_format_roundtrip() {
+        for format in [
+            ScrobbleImportFormat::ListenBrainz,
+            ScrobbleImportFormat::LastFmJson,
+            ScrobbleImportFormat::LastFmCsv,
+        ] {
+            assert_eq!(
+                format.as_str().parse::<ScrobbleImportFormat>().unwrap(),
+                format
+            );
+        }
+        assert!("spotify".parse::<ScrobbleImportFormat>().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_import_marks_submitted_to_all_scrobblers() {
+        let context = crate::test::create_context_memory().await;
+        let user = crate::test::create_user(&context, "user").await;
+        crate::test::create_artist_album_track(&context, "Artist", "Album", "Track").await;
+
+        let mut conn = context.db.acquire().await.unwrap();
+        let import = ScrobbleImport {
+            user: user.id,
+            scrobbler: Some("listenbrainz".to_string()),
+            listen_device: "import".to_string(),
+            entries: vec![ScrobbleImportEntry {
+                artist: "Artist".to_string(),
+                album: None,
+                title: "Track".to_string(),
+                duration: None,
+                recording_mbid: None,
+                listen_at: Timestamp::from_seconds(1000),
+            }],
+        };
+        let (report, _) = super::import(&mut conn, import, &["lastfm", "listenbrainz"])
+            .await
+            .unwrap();
+        assert_eq!(report.imported, 1);
+
+        for scrobbler in ["lastfm", "listenbrainz"] {
+            let unsubmitted = scrobble::list_unsubmitted(&mut conn, scrobbler)
+                .await
+                .unwrap();
+            assert!(unsubmitted.is_empty());
+        }
+    }
+
+    #[test]
+    fn test_parse_csv_row() {
+        assert_eq!(parse_csv_row("a,b,c"), vec!["a", "b", "c"]);
+        assert_eq!(
+            parse_csv_row("\"a, b\",,\"c \"\"d\"\"\""),
+            vec!["a, b", "", "c \"d\""]
+        );
+    }
+
+    #[test]
+    fn test_parse_lastfm_date() {
+        assert_eq!(parse_lastfm_date("01 Jan 2020 00:01"), Some(1577836860));
+        assert_eq!(parse_lastfm_date("01 Jan 2020, 00:01"), Some(1577836860));
+        assert_eq!(parse_lastfm_date("yesterday"), None);
+    }
+}
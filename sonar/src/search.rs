@@ -1,21 +1,35 @@
 use crate::{
-    async_trait, Album, AlbumId, Artist, ArtistId, Playlist, PlaylistId, Result, Track, TrackId,
-    UserId,
+    async_trait, Album, AlbumId, Artist, ArtistId, Error, ErrorKind, LibraryId, Playlist,
+    PlaylistId, Result, Track, TrackId, UserId,
 };
 
+mod query;
+pub use query::{
+    InvalidSearchQueryError, SearchClause, SearchExpression, SearchFilter, SearchRange,
+};
+
+mod filter;
+pub(crate) use filter::collect_results;
+
 mod builtin;
 pub use builtin::BuiltInSearchEngine;
 
 mod meilisearch;
 pub use meilisearch::MeiliSearchEngine;
 
+mod tantivy;
+pub use self::tantivy::TantivySearchEngine;
+
 pub type SearchFlags = u32;
 
 #[derive(Debug, Clone)]
 pub struct SearchQuery {
+    /// the query, see [`SearchExpression`] for the syntax.
     pub query: String,
     pub limit: Option<u32>,
     pub flags: SearchFlags,
+    /// only return media from this library.
+    pub library: Option<LibraryId>,
 }
 
 impl SearchQuery {
@@ -26,6 +40,12 @@ impl SearchQuery {
     pub const FLAG_PLAYLIST: SearchFlags = 1 << 3;
     pub const FLAG_ALL: SearchFlags =
         Self::FLAG_ARTIST | Self::FLAG_ALBUM | Self::FLAG_TRACK | Self::FLAG_PLAYLIST;
+
+    pub fn expression(&self) -> Result<SearchExpression> {
+        self.query
+            .parse()
+            .map_err(|err| Error::with_source(ErrorKind::Invalid, "invalid search query", err))
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -83,6 +103,13 @@ pub trait SearchEngine: std::fmt::Debug + Send + Sync + 'static {
     async fn synchronize_track(&self, track: TrackId);
     async fn synchronize_playlist(&self, playlist: PlaylistId);
     async fn synchronize_all(&self);
+
+    /// Whether writes should wait for the synchronize methods before returning.
+    /// Engines backed by an external service are synchronized in the background so that writes
+    /// neither block on nor fail with the service.
+    fn synchronize_inline(&self) -> bool {
+        true
+    }
 }
 
 impl SearchResults {
@@ -1,6 +1,27 @@
+use std::collections::HashMap;
+
 use super::*;
-use crate::{album, artist, async_trait, db::Db, playlist, track};
+use crate::{
+    album, artist, async_trait,
+    db::{Db, DbC},
+    playlist,
+    search::collect_results,
+    track, SonarId, ID_NAMESPACE_ALBUM, ID_NAMESPACE_ARTIST, ID_NAMESPACE_MASK,
+    ID_NAMESPACE_PLAYLIST, ID_NAMESPACE_SHIFT, ID_NAMESPACE_TRACK,
+};
+
+const DEFAULT_SEARCH_LIMIT: u32 = 50;
+
+// each query selects the columns (sonar id, name, owner) of the search index.
+// the first parameter is the namespace of the item, shifted into place.
+pub(super) const ARTIST_QUERY: &str = "SELECT ? | artist.id, artist.name, NULL FROM artist";
+pub(super) const ALBUM_QUERY: &str = "SELECT ? | album.id, album.name, NULL FROM album";
+pub(super) const TRACK_QUERY: &str = "SELECT ? | track.id, track.name, NULL FROM track";
+pub(super) const PLAYLIST_QUERY: &str =
+    "SELECT ? | playlist.id, playlist.name, playlist.owner FROM playlist";
 
+/// Search engine backed by an sqlite FTS5 index.
+/// Matching ignores diacritics and word order and results are ranked by relevance.
 #[derive(Debug)]
 pub struct BuiltInSearchEngine {
     db: Db,
@@ -10,73 +31,237 @@ impl BuiltInSearchEngine {
     pub fn new(db: Db) -> Self {
         Self { db }
     }
+
+    async fn synchronize(&self, namespace: u32, id: i64, query: &str, table: &str) {
+        if let Err(err) = self.synchronize_inner(namespace, id, query, table).await {
+            tracing::error!("failed to synchronize {table} {id} with search index: {err}");
+        }
+    }
+
+    async fn synchronize_inner(
+        &self,
+        namespace: u32,
+        id: i64,
+        query: &str,
+        table: &str,
+    ) -> Result<()> {
+        let rowid = (namespace << ID_NAMESPACE_SHIFT) as i64 | id;
+        let mut tx = self.db.begin().await?;
+        sqlx::query("DELETE FROM search_fts WHERE rowid = ?")
+            .bind(rowid)
+            .execute(&mut *tx)
+            .await?;
+        // inserts nothing if the item was deleted
+        sqlx::query(&format!(
+            "INSERT INTO search_fts (rowid, name, owner) {query} WHERE {table}.id = ?"
+        ))
+        .bind((namespace << ID_NAMESPACE_SHIFT) as i64)
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn synchronize_all_inner(&self) -> Result<()> {
+        let mut tx = self.db.begin().await?;
+        sqlx::query("DELETE FROM search_fts")
+            .execute(&mut *tx)
+            .await?;
+        for (namespace, query) in [
+            (ID_NAMESPACE_ARTIST, ARTIST_QUERY),
+            (ID_NAMESPACE_ALBUM, ALBUM_QUERY),
+            (ID_NAMESPACE_TRACK, TRACK_QUERY),
+            (ID_NAMESPACE_PLAYLIST, PLAYLIST_QUERY),
+        ] {
+            sqlx::query(&format!(
+                "INSERT INTO search_fts (rowid, name, owner) {query}"
+            ))
+            .bind((namespace << ID_NAMESPACE_SHIFT) as i64)
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
 }
 
 #[async_trait]
 impl SearchEngine for BuiltInSearchEngine {
     #[tracing::instrument(skip(self))]
     async fn search(&self, user_id: UserId, query: &SearchQuery) -> Result<SearchResults> {
-        let mut conn = self.db.acquire().await?;
-        let mut results = SearchResults::default();
-        let query_term = format!("%{}%", query.query);
-
-        if query.flags & SearchQuery::FLAG_ARTIST != 0 {
-            let artist_ids = sqlx::query_scalar("SELECT id FROM artist WHERE name LIKE ?")
-                .bind(&query_term)
-                .fetch_all(&mut *conn)
-                .await?
-                .into_iter()
-                .map(ArtistId::from_db)
-                .collect::<Vec<_>>();
-            let artists = artist::get_bulk(&mut conn, &artist_ids).await?;
-            results.results.extend(artists.into_iter().map(From::from));
+        let expr = query.expression()?;
+        let limit = query.limit.unwrap_or(DEFAULT_SEARCH_LIMIT);
+        let namespaces = [
+            (SearchQuery::FLAG_ARTIST, ID_NAMESPACE_ARTIST),
+            (SearchQuery::FLAG_ALBUM, ID_NAMESPACE_ALBUM),
+            (SearchQuery::FLAG_TRACK, ID_NAMESPACE_TRACK),
+            (SearchQuery::FLAG_PLAYLIST, ID_NAMESPACE_PLAYLIST),
+        ]
+        .into_iter()
+        .filter(|(flag, _)| query.flags & flag != 0)
+        .map(|(_, namespace)| namespace)
+        .collect::<Vec<_>>();
+        if namespaces.is_empty() {
+            return Ok(SearchResults::default());
         }
 
-        if query.flags & SearchQuery::FLAG_ALBUM != 0 {
-            let album_ids = sqlx::query_scalar("SELECT id FROM album WHERE name LIKE ?")
-                .bind(&query_term)
-                .fetch_all(&mut *conn)
-                .await?
-                .into_iter()
-                .map(AlbumId::from_db)
-                .collect::<Vec<_>>();
-            let albums = album::get_bulk(&mut conn, &album_ids).await?;
-            results.results.extend(albums.into_iter().map(From::from));
-        }
+        let fts = fts_match_expression(&expr);
+        let (db, namespaces, fts) = (&self.db, &namespaces, fts.as_deref());
+        let results = collect_results(
+            db,
+            user_id,
+            query.library,
+            &expr,
+            limit as usize,
+            move |offset, count| async move {
+                let mut builder = sqlx::QueryBuilder::<sqlx::Sqlite>::new(
+                    "SELECT rowid FROM search_fts WHERE (rowid & ",
+                );
+                builder.push(ID_NAMESPACE_MASK);
+                builder.push(") >> ");
+                builder.push(ID_NAMESPACE_SHIFT);
+                builder.push(" IN (");
+                let mut separated = builder.separated(", ");
+                for namespace in namespaces {
+                    separated.push(namespace);
+                }
+                builder.push(") AND (owner IS NULL OR owner = ");
+                builder.push_bind(user_id);
+                builder.push(")");
+                match fts {
+                    // an empty query matches everything
+                    None => builder.push(" ORDER BY rowid ASC"),
+                    Some(fts) => builder
+                        .push(" AND search_fts MATCH ")
+                        .push_bind(fts)
+                        .push(" ORDER BY rank"),
+                };
+                builder.push(" LIMIT ");
+                builder.push_bind(count as i64);
+                builder.push(" OFFSET ");
+                builder.push_bind(offset as i64);
 
-        if query.flags & SearchQuery::FLAG_TRACK != 0 {
-            let track_ids = sqlx::query_scalar("SELECT id FROM track WHERE name LIKE ?")
-                .bind(&query_term)
-                .fetch_all(&mut *conn)
-                .await?
-                .into_iter()
-                .map(TrackId::from_db)
-                .collect::<Vec<_>>();
-            let tracks = track::get_bulk(&mut conn, &track_ids).await?;
-            results.results.extend(tracks.into_iter().map(From::from));
-        }
-
-        if query.flags & SearchQuery::FLAG_PLAYLIST != 0 {
-            let playlist_ids =
-                sqlx::query_scalar("SELECT id FROM playlist WHERE owner = ? AND name LIKE ?")
-                    .bind(user_id)
-                    .bind(query_term)
+                let mut conn = db.acquire().await?;
+                let ids = builder
+                    .build_query_scalar::<i64>()
                     .fetch_all(&mut *conn)
                     .await?
                     .into_iter()
-                    .map(PlaylistId::from_db)
+                    .filter_map(|rowid| SonarId::try_from(rowid as u32).ok())
                     .collect::<Vec<_>>();
-            let playlists = playlist::get_bulk(&mut conn, &playlist_ids).await?;
-            results
-                .results
-                .extend(playlists.into_iter().map(From::from));
+                Ok(ids)
+            },
+        )
+        .await?;
+        Ok(SearchResults { results })
+    }
+    async fn synchronize_artist(&self, artist: ArtistId) {
+        self.synchronize(ID_NAMESPACE_ARTIST, artist.to_db(), ARTIST_QUERY, "artist")
+            .await
+    }
+    async fn synchronize_album(&self, album: AlbumId) {
+        self.synchronize(ID_NAMESPACE_ALBUM, album.to_db(), ALBUM_QUERY, "album")
+            .await
+    }
+    async fn synchronize_track(&self, track: TrackId) {
+        self.synchronize(ID_NAMESPACE_TRACK, track.to_db(), TRACK_QUERY, "track")
+            .await
+    }
+    async fn synchronize_playlist(&self, playlist: PlaylistId) {
+        self.synchronize(
+            ID_NAMESPACE_PLAYLIST,
+            playlist.to_db(),
+            PLAYLIST_QUERY,
+            "playlist",
+        )
+        .await
+    }
+    async fn synchronize_all(&self) {
+        if let Err(err) = self.synchronize_all_inner().await {
+            tracing::error!("failed to rebuild search index: {err}");
         }
+    }
+}
+
+/// Converts the text of the expression into an FTS5 match expression where every word must match
+/// as a prefix and every phrase must match exactly.
+/// Returns `None` if the expression has no text.
+fn fts_match_expression(expr: &SearchExpression) -> Option<String> {
+    let terms = expr
+        .text()
+        .filter_map(|filter| match filter {
+            SearchFilter::Text(word) => Some(format!("\"{}\"*", word.replace('"', "\"\""))),
+            SearchFilter::Phrase(phrase) => Some(format!("\"{}\"", phrase.replace('"', "\"\""))),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    if terms.is_empty() {
+        None
+    } else {
+        Some(terms.join(" "))
+    }
+}
+
+/// Fetches the items with the given ids, keeping their order.
+pub(super) async fn fetch_results(db: &mut DbC, ids: &[SonarId]) -> Result<Vec<SearchResult>> {
+    let mut artist_ids = Vec::new();
+    let mut album_ids = Vec::new();
+    let mut track_ids = Vec::new();
+    let mut playlist_ids = Vec::new();
+    for id in ids {
+        match *id {
+            SonarId::Artist(id) => artist_ids.push(id),
+            SonarId::Album(id) => album_ids.push(id),
+            SonarId::Track(id) => track_ids.push(id),
+            SonarId::Playlist(id) => playlist_ids.push(id),
+            _ => {}
+        }
+    }
+
+    let mut items = HashMap::<SonarId, SearchResult>::with_capacity(ids.len());
+    for artist in artist::get_bulk(db, &artist_ids).await? {
+        items.insert(artist.id.into(), artist.into());
+    }
+    for album in album::get_bulk(db, &album_ids).await? {
+        items.insert(album.id.into(), album.into());
+    }
+    for track in track::get_bulk(db, &track_ids).await? {
+        items.insert(track.id.into(), track.into());
+    }
+    for playlist_id in playlist_ids {
+        // playlists may have been removed before the index was updated
+        match playlist::get(db, playlist_id).await {
+            Ok(playlist) => {
+                items.insert(playlist.id.into(), playlist.into());
+            }
+            Err(err) => tracing::warn!("search result {playlist_id} not found: {err}"),
+        }
+    }
+
+    Ok(ids.iter().filter_map(|id| items.remove(id)).collect())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn match_expression(query: &str) -> Option<String> {
+        fts_match_expression(&query.parse().unwrap())
+    }
 
-        Ok(results)
+    #[test]
+    fn test_fts_match_expression() {
+        assert_eq!(match_expression(""), None);
+        assert_eq!(match_expression("  "), None);
+        assert_eq!(match_expression("-live year:2000"), None);
+        assert_eq!(
+            match_expression("bjork homogenic"),
+            Some("\"bjork\"* \"homogenic\"*".to_string())
+        );
+        assert_eq!(
+            match_expression("say \"hello world\" it\"s"),
+            Some("\"say\"* \"hello world\" \"it\"\"s\"*".to_string())
+        );
     }
-    async fn synchronize_artist(&self, _artist: ArtistId) {}
-    async fn synchronize_album(&self, _album: AlbumId) {}
-    async fn synchronize_track(&self, _track: TrackId) {}
-    async fn synchronize_playlist(&self, _playlist: PlaylistId) {}
-    async fn synchronize_all(&self) {}
 }
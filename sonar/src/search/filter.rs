@@ -0,0 +1,224 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    album, artist,
+    db::{Db, DbC},
+    favorite, library, prop, Album, AlbumId, Artist, ArtistId, Genres, LibraryFilter, LibraryId,
+    Properties, Result, SonarId, UserId,
+};
+
+use super::{builtin::fetch_results, SearchClause, SearchExpression, SearchFilter, SearchResult};
+
+// number of candidates fetched at a time when the results still have to be filtered
+const FILTERED_SEARCH_BATCH: usize = 200;
+
+/// The information about an item that search clauses are matched against.
+struct ItemInfo<'a> {
+    id: SonarId,
+    name: &'a str,
+    artist: Option<&'a str>,
+    album: Option<&'a str>,
+    genres: Option<&'a Genres>,
+    year: Option<u32>,
+    duration: Option<u32>,
+    properties: &'a Properties,
+}
+
+/// Pages through the candidates of a search, in order, until `limit` of them match the expression
+/// and are in a library the user has access to, or in `library` if set.
+/// `candidates` is called with an offset and a count and returns the ids of the candidates in
+/// that range, returning less than `count` ids once there are no more candidates.
+pub(crate) async fn collect_results<F, Fut>(
+    db: &Db,
+    user_id: UserId,
+    library: Option<LibraryId>,
+    expr: &SearchExpression,
+    limit: usize,
+    mut candidates: F,
+) -> Result<Vec<SearchResult>>
+where
+    F: FnMut(usize, usize) -> Fut,
+    Fut: std::future::Future<Output = Result<Vec<SonarId>>>,
+{
+    let libraries = {
+        let mut conn = db.acquire().await?;
+        let filter = LibraryFilter::user(user_id).with_library(library);
+        library::resolve(&mut conn, filter).await?
+    };
+    let batch = if expr.has_filters() || libraries.is_some() {
+        FILTERED_SEARCH_BATCH.max(limit)
+    } else {
+        limit
+    };
+    let mut results = Vec::new();
+    let mut offset = 0;
+    while results.len() < limit {
+        let ids = candidates(offset, batch).await?;
+        let exhausted = ids.len() < batch;
+        offset += batch;
+
+        let mut conn = db.acquire().await?;
+        let mut batch_results = fetch_results(&mut conn, &ids).await?;
+        batch_results.retain(|result| result_visible(result, user_id, libraries.as_deref()));
+        results.extend(filter_results(&mut conn, user_id, expr, batch_results).await?);
+        if exhausted {
+            break;
+        }
+    }
+    results.truncate(limit);
+    Ok(results)
+}
+
+/// Removes the results that don't match the non text clauses of the expression.
+pub(crate) async fn filter_results(
+    db: &mut DbC,
+    user_id: UserId,
+    expr: &SearchExpression,
+    results: Vec<SearchResult>,
+) -> Result<Vec<SearchResult>> {
+    if !expr.has_filters() || results.is_empty() {
+        return Ok(results);
+    }
+
+    let mut artist_ids = Vec::new();
+    let mut album_ids = Vec::new();
+    for result in results.iter() {
+        match result {
+            SearchResult::Album(album) => artist_ids.push(album.artist),
+            SearchResult::Track(track) => {
+                artist_ids.push(track.artist);
+                album_ids.push(track.album);
+            }
+            _ => {}
+        }
+    }
+    let artists = artist::get_bulk(db, &artist_ids)
+        .await?
+        .into_iter()
+        .map(|artist| (artist.id, artist))
+        .collect::<HashMap<ArtistId, Artist>>();
+    let albums = album::get_bulk(db, &album_ids)
+        .await?
+        .into_iter()
+        .map(|album| (album.id, album))
+        .collect::<HashMap<AlbumId, Album>>();
+
+    let ids = results.iter().map(result_id).collect::<Vec<_>>();
+    let favorites = favorite::user_get_bulk(db, user_id, &ids)
+        .await?
+        .into_iter()
+        .map(|favorite| favorite.id)
+        .collect::<HashSet<_>>();
+
+    Ok(results
+        .into_iter()
+        .filter(|result| {
+            let info = item_info(result, &artists, &albums);
+            expr.clauses
+                .iter()
+                .all(|clause| clause_matches(clause, &info, &favorites))
+        })
+        .collect())
+}
+
+fn result_visible(result: &SearchResult, user_id: UserId, libraries: Option<&[LibraryId]>) -> bool {
+    let in_libraries = |library| libraries.map_or(true, |libraries| libraries.contains(&library));
+    match result {
+        SearchResult::Artist(artist) => in_libraries(artist.library),
+        SearchResult::Album(album) => in_libraries(album.library),
+        SearchResult::Track(track) => in_libraries(track.library),
+        // playlists belong to users, not libraries
+        SearchResult::Playlist(playlist) => playlist.owner == user_id,
+    }
+}
+
+fn result_id(result: &SearchResult) -> SonarId {
+    match result {
+        SearchResult::Artist(artist) => artist.id.into(),
+        SearchResult::Album(album) => album.id.into(),
+        SearchResult::Track(track) => track.id.into(),
+        SearchResult::Playlist(playlist) => playlist.id.into(),
+    }
+}
+
+fn item_info<'a>(
+    result: &'a SearchResult,
+    artists: &'a HashMap<ArtistId, Artist>,
+    albums: &'a HashMap<AlbumId, Album>,
+) -> ItemInfo<'a> {
+    match result {
+        SearchResult::Artist(artist) => ItemInfo {
+            id: artist.id.into(),
+            name: &artist.name,
+            artist: Some(&artist.name),
+            album: None,
+            genres: Some(&artist.genres),
+            year: None,
+            duration: None,
+            properties: &artist.properties,
+        },
+        SearchResult::Album(album) => ItemInfo {
+            id: album.id.into(),
+            name: &album.name,
+            artist: artists.get(&album.artist).map(|a| a.name.as_str()),
+            album: Some(&album.name),
+            genres: Some(&album.genres),
+            year: release_year(&album.properties),
+            duration: Some(album.duration.as_secs() as u32),
+            properties: &album.properties,
+        },
+        SearchResult::Track(track) => {
+            // tracks use the genres and release date of their album
+            let album = albums.get(&track.album);
+            ItemInfo {
+                id: track.id.into(),
+                name: &track.name,
+                artist: artists.get(&track.artist).map(|a| a.name.as_str()),
+                album: album.map(|a| a.name.as_str()),
+                genres: album.map(|a| &a.genres),
+                year: album.and_then(|a| release_year(&a.properties)),
+                duration: Some(track.duration.as_secs() as u32),
+                properties: &track.properties,
+            }
+        }
+        SearchResult::Playlist(playlist) => ItemInfo {
+            id: playlist.id.into(),
+            name: &playlist.name,
+            artist: None,
+            album: None,
+            genres: None,
+            year: None,
+            duration: Some(playlist.duration.as_secs() as u32),
+            properties: &playlist.properties,
+        },
+    }
+}
+
+fn clause_matches(clause: &SearchClause, info: &ItemInfo, favorites: &HashSet<SonarId>) -> bool {
+    let matches = match &clause.filter {
+        // positive text is matched by the search engine
+        SearchFilter::Text(_) | SearchFilter::Phrase(_) if !clause.negated => true,
+        SearchFilter::Text(text) | SearchFilter::Phrase(text) => contains(info.name, text),
+        SearchFilter::Artist(name) => info.artist.map(|a| contains(a, name)).unwrap_or(false),
+        SearchFilter::Album(name) => info.album.map(|a| contains(a, name)).unwrap_or(false),
+        SearchFilter::Genre(genre) => info.genres.map(|g| g.contains(genre)).unwrap_or(false),
+        SearchFilter::Year(range) => info.year.map(|y| range.contains(y)).unwrap_or(false),
+        SearchFilter::Favorite(favorite) => favorites.contains(&info.id) == *favorite,
+        SearchFilter::Duration(range) => info.duration.map(|d| range.contains(d)).unwrap_or(false),
+        SearchFilter::Property(key, value) => match value {
+            Some(value) => info.properties.get(key).as_ref() == Some(value),
+            None => info.properties.contains_key(key),
+        },
+    };
+    matches != clause.negated
+}
+
+fn contains(haystack: &str, needle: &str) -> bool {
+    haystack.to_lowercase().contains(&needle.to_lowercase())
+}
+
+fn release_year(properties: &Properties) -> Option<u32> {
+    // release dates are stored as YYYY-MM-DD but may only contain the year
+    let date = properties.get(prop::RELEASE_DATE)?;
+    date.as_str().get(..4)?.parse().ok()
+}
@@ -1,4 +1,4 @@
-use std::{collections::HashMap, time::Duration};
+use std::time::Duration;
 
 use async_trait::async_trait;
 use meilisearch_sdk::{Client, Index};
@@ -6,13 +6,10 @@ use serde::{Deserialize, Serialize};
 
 use crate::{
     album, artist, db::Db, ext, playlist, track, AlbumId, ArtistId, Error, PlaylistId, Result,
-    SearchQuery, SearchResult, SonarId, TrackId, UserId,
+    SearchFilter, SearchQuery, SonarId, TrackId, UserId,
 };
 
-use super::{SearchEngine, SearchResults};
-
-// TODO: synchronize playlists.
-// TODO: take care of user id when searching for playlists
+use super::{collect_results, SearchEngine, SearchResults};
 
 const DEFAULT_SEARCH_LIMIT: u32 = 50;
 const INDEX_NAME: &str = "items";
@@ -47,6 +44,8 @@ struct Document {
     track_name: Option<String>,
     playlist_name: Option<String>,
     lyrics: Option<String>,
+    /// the owner of playlists, other documents can be seen by every user.
+    owner: Option<i64>,
 }
 
 #[derive(Debug)]
@@ -74,6 +73,10 @@ impl MeiliSearchEngine {
             .set_primary_key(INDEX_KEY)
             .await
             .map_err(Error::wrap)?;
+        index
+            .set_filterable_attributes(["kind", "owner"])
+            .await
+            .map_err(Error::wrap)?;
 
         Ok(Self { db, client, index })
     }
@@ -128,6 +131,7 @@ impl MeiliSearchEngine {
                 track_name: None,
                 playlist_name: None,
                 lyrics: None,
+                owner: None,
             });
         }
         self.insert_documents(&documents).await;
@@ -151,6 +155,7 @@ impl MeiliSearchEngine {
                 track_name: None,
                 playlist_name: None,
                 lyrics: None,
+                owner: None,
             });
         }
         self.insert_documents(&documents).await;
@@ -190,6 +195,7 @@ impl MeiliSearchEngine {
                 track_name: Some(track.name),
                 playlist_name: None,
                 lyrics,
+                owner: None,
             });
         }
         self.insert_documents(&documents).await;
@@ -207,6 +213,7 @@ impl MeiliSearchEngine {
                 track_name: None,
                 playlist_name: Some(playlist.name),
                 lyrics: None,
+                owner: Some(playlist.owner.to_db()),
             });
         }
         self.insert_documents(&documents).await;
@@ -215,83 +222,64 @@ impl MeiliSearchEngine {
 
 #[async_trait]
 impl SearchEngine for MeiliSearchEngine {
-    async fn search(&self, _user_id: UserId, query: &SearchQuery) -> Result<SearchResults> {
+    async fn search(&self, user_id: UserId, query: &SearchQuery) -> Result<SearchResults> {
         tracing::info!("searching for {:?}", query);
-        let mut q = self.index.search();
-        q.with_query(&query.query);
-        q.with_limit(query.limit.unwrap_or(DEFAULT_SEARCH_LIMIT) as usize);
-        let results = q.execute::<Document>().await.map_err(Error::wrap)?;
-        tracing::debug!("found {} results", results.hits.len());
-        tracing::trace!("results: {:#?}", results);
-
-        let mut artist_ids = Vec::new();
-        let mut album_ids = Vec::new();
-        let mut track_ids = Vec::new();
-        let mut playlist_ids = Vec::new();
-
-        for document in results.hits.iter() {
-            match document.result.kind {
-                DocumentKind::Artist => {
-                    artist_ids.push(meilisearch_id_to_artist_id(&document.result.id))
-                }
-                DocumentKind::Album => {
-                    album_ids.push(meilisearch_id_to_album_id(&document.result.id))
-                }
-                DocumentKind::Track => {
-                    track_ids.push(meilisearch_id_to_track_id(&document.result.id))
-                }
-                DocumentKind::Playlist => {
-                    playlist_ids.push(meilisearch_id_to_playlist_id(&document.result.id))
-                }
-            }
-        }
-
-        let mut conn = self.db.acquire().await.unwrap();
-        let artists = artist::get_bulk(&mut conn, &artist_ids).await.unwrap();
-        let albums = album::get_bulk(&mut conn, &album_ids).await.unwrap();
-        let tracks = track::get_bulk(&mut conn, &track_ids).await.unwrap();
-        let playlists = playlist::get_bulk(&mut conn, &playlist_ids).await.unwrap();
-
-        // TODO: maybe add some trait 'Entity' with associated type 'Key' and add a function to
-        // ks to transform a vector of entities into a map of keys to entities
-        let artists = artists
-            .into_iter()
-            .map(|x| (x.id, x))
-            .collect::<HashMap<_, _>>();
-        let albums = albums
-            .into_iter()
-            .map(|x| (x.id, x))
-            .collect::<HashMap<_, _>>();
-        let tracks = tracks
-            .into_iter()
-            .map(|x| (x.id, x))
-            .collect::<HashMap<_, _>>();
-        let playlists = playlists
-            .into_iter()
-            .map(|x| (x.id, x))
-            .collect::<HashMap<_, _>>();
-
-        let mut search_results = Vec::new();
-        for document in results.hits {
-            match document.result.kind {
-                DocumentKind::Artist => search_results.push(SearchResult::Artist(
-                    artists[&meilisearch_id_to_artist_id(&document.result.id)].clone(),
-                )),
-                DocumentKind::Album => search_results.push(SearchResult::Album(
-                    albums[&meilisearch_id_to_album_id(&document.result.id)].clone(),
-                )),
-                DocumentKind::Track => search_results.push(SearchResult::Track(
-                    tracks[&meilisearch_id_to_track_id(&document.result.id)].clone(),
-                )),
-                DocumentKind::Playlist => search_results.push(SearchResult::Playlist(
-                    playlists[&meilisearch_id_to_playlist_id(&document.result.id)].clone(),
-                )),
-            }
+        let expr = query.expression()?;
+        let limit = query.limit.unwrap_or(DEFAULT_SEARCH_LIMIT) as usize;
+        // documents are serialized with the name of their kind's variant
+        let kinds = [
+            (SearchQuery::FLAG_ARTIST, DocumentKind::Artist),
+            (SearchQuery::FLAG_ALBUM, DocumentKind::Album),
+            (SearchQuery::FLAG_TRACK, DocumentKind::Track),
+            (SearchQuery::FLAG_PLAYLIST, DocumentKind::Playlist),
+        ]
+        .into_iter()
+        .filter(|(flag, _)| query.flags & flag != 0)
+        .map(|(_, kind)| format!("{kind:?}"))
+        .collect::<Vec<_>>();
+        if kinds.is_empty() || limit == 0 {
+            return Ok(SearchResults::default());
         }
-
-        Ok(SearchResults {
-            results: search_results,
-        })
+        let filter = format!(
+            "kind IN [{}] AND (owner IS NULL OR owner = {})",
+            kinds.join(", "),
+            user_id.to_db()
+        );
+        // meilisearch supports phrases in double quotes, everything else is filtered afterwards
+        let text = expr
+            .text()
+            .filter_map(|filter| match filter {
+                SearchFilter::Text(word) => Some(word.clone()),
+                SearchFilter::Phrase(phrase) => Some(format!("\"{phrase}\"")),
+                _ => None,
+            })
+            .collect::<Vec<_>>()
+            .join(" ");
+        let (index, text, filter) = (&self.index, text.as_str(), filter.as_str());
+        let results = collect_results(
+            &self.db,
+            user_id,
+            query.library,
+            &expr,
+            limit,
+            move |offset, count| async move {
+                let mut q = index.search();
+                q.with_query(text);
+                q.with_filter(filter);
+                q.with_offset(offset);
+                q.with_limit(count);
+                let results = q.execute::<Document>().await.map_err(Error::wrap)?;
+                tracing::debug!("found {} results", results.hits.len());
+                tracing::trace!("results: {:#?}", results);
+                Ok(results
+                    .hits
+                    .into_iter()
+                    .map(|document| meilisearch_id_to_sonar_id(&document.result.id))
+                    .collect())
+            },
+        )
+        .await?;
+        Ok(SearchResults { results })
     }
     async fn synchronize_artist(&self, artist: ArtistId) {
         self.synchronize_artists(vec![artist]).await;
@@ -310,10 +298,16 @@ impl SearchEngine for MeiliSearchEngine {
         let track_ids = track::list_ids(&mut conn).await.unwrap();
         let album_ids = album::list_ids(&mut conn).await.unwrap();
         let artist_ids = artist::list_ids(&mut conn).await.unwrap();
+        let playlist_ids = playlist::list_ids(&mut conn).await.unwrap();
 
         self.synchronize_tracks(track_ids).await;
         self.synchronize_albums(album_ids).await;
         self.synchronize_artists(artist_ids).await;
+        self.synchronize_playlists(playlist_ids).await;
+    }
+
+    fn synchronize_inline(&self) -> bool {
+        false
     }
 }
 
@@ -321,18 +315,6 @@ fn sonar_id_to_meilisearch_id(id: impl Into<SonarId>) -> String {
     id.into().to_string().replace(':', "_")
 }
 
-fn meilisearch_id_to_artist_id(id: &str) -> ArtistId {
-    id.replace('_', ":").parse().unwrap()
-}
-
-fn meilisearch_id_to_album_id(id: &str) -> AlbumId {
-    id.replace('_', ":").parse().unwrap()
-}
-
-fn meilisearch_id_to_track_id(id: &str) -> TrackId {
-    id.replace('_', ":").parse().unwrap()
-}
-
-fn meilisearch_id_to_playlist_id(id: &str) -> PlaylistId {
+fn meilisearch_id_to_sonar_id(id: &str) -> SonarId {
     id.replace('_', ":").parse().unwrap()
 }
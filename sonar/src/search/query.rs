@@ -0,0 +1,358 @@
+//! Search query syntax.
+//!
+//! A query is a list of whitespace separated clauses that must all match:
+//! - `word` matches items whose name contains the word.
+//! - `"some words"` matches items whose name contains the phrase.
+//! - `artist:name` and `album:name` match items by their artist or album name.
+//! - `genre:rock` matches items with the given genre.
+//! - `year:1990..1999` matches items released in the given range of years.
+//! - `favorite:true` matches items that the user has marked as favorite.
+//! - `duration:>300` matches items by their duration in seconds.
+//! - `prop:key=value` matches items with the given property, `prop:key` only checks that it exists.
+//!
+//! Field values can be quoted, like `artist:"pink floyd"`, and any clause can be negated by
+//! prefixing it with `-`.
+//!
+//! Ranges accept `N`, `N..M`, `N..`, `..M`, `>N`, `>=N`, `<N` and `<=N`.
+
+use std::str::FromStr;
+
+use crate::{Genre, PropertyKey, PropertyValue};
+
+#[derive(Debug)]
+pub struct InvalidSearchQueryError {
+    message: String,
+}
+
+impl InvalidSearchQueryError {
+    fn new(message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+        }
+    }
+}
+
+impl std::fmt::Display for InvalidSearchQueryError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "invalid search query: {}", self.message)
+    }
+}
+
+impl std::error::Error for InvalidSearchQueryError {}
+
+/// An inclusive range of values, where a missing bound is unbounded.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct SearchRange {
+    pub min: Option<u32>,
+    pub max: Option<u32>,
+}
+
+impl SearchRange {
+    pub fn contains(&self, value: u32) -> bool {
+        self.min.map(|min| value >= min).unwrap_or(true)
+            && self.max.map(|max| value <= max).unwrap_or(true)
+    }
+}
+
+impl FromStr for SearchRange {
+    type Err = InvalidSearchQueryError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        fn number(s: &str) -> Result<u32, InvalidSearchQueryError> {
+            s.parse()
+                .map_err(|_| InvalidSearchQueryError::new(format!("invalid number '{s}'")))
+        }
+
+        let range = if let Some(v) = s.strip_prefix(">=") {
+            SearchRange {
+                min: Some(number(v)?),
+                max: None,
+            }
+        } else if let Some(v) = s.strip_prefix("<=") {
+            SearchRange {
+                min: None,
+                max: Some(number(v)?),
+            }
+        } else if let Some(v) = s.strip_prefix('>') {
+            SearchRange {
+                min: Some(number(v)?.saturating_add(1)),
+                max: None,
+            }
+        } else if let Some(v) = s.strip_prefix('<') {
+            let max = number(v)?;
+            if max == 0 {
+                return Err(InvalidSearchQueryError::new("empty range"));
+            }
+            SearchRange {
+                min: None,
+                max: Some(max - 1),
+            }
+        } else if let Some((min, max)) = s.split_once("..") {
+            SearchRange {
+                min: (!min.is_empty()).then(|| number(min)).transpose()?,
+                max: (!max.is_empty()).then(|| number(max)).transpose()?,
+            }
+        } else {
+            let value = number(s)?;
+            SearchRange {
+                min: Some(value),
+                max: Some(value),
+            }
+        };
+        Ok(range)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SearchFilter {
+    Text(String),
+    Phrase(String),
+    Artist(String),
+    Album(String),
+    Genre(Genre),
+    Year(SearchRange),
+    Favorite(bool),
+    /// duration in seconds.
+    Duration(SearchRange),
+    Property(PropertyKey, Option<PropertyValue>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SearchClause {
+    pub negated: bool,
+    pub filter: SearchFilter,
+}
+
+/// A parsed search query.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct SearchExpression {
+    pub clauses: Vec<SearchClause>,
+}
+
+impl SearchExpression {
+    /// The words and phrases that are not negated.
+    /// These are matched by the search engine itself, all other clauses are applied afterwards.
+    pub fn text(&self) -> impl Iterator<Item = &SearchFilter> {
+        self.clauses
+            .iter()
+            .filter(|clause| !clause.negated)
+            .map(|clause| &clause.filter)
+            .filter(|filter| std::matches!(filter, SearchFilter::Text(_) | SearchFilter::Phrase(_)))
+    }
+
+    /// Returns true if the query has clauses other than the ones in [`SearchExpression::text`].
+    pub fn has_filters(&self) -> bool {
+        self.clauses.iter().any(|clause| {
+            clause.negated
+                || !std::matches!(
+                    clause.filter,
+                    SearchFilter::Text(_) | SearchFilter::Phrase(_)
+                )
+        })
+    }
+}
+
+impl FromStr for SearchExpression {
+    type Err = InvalidSearchQueryError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut clauses = Vec::new();
+        let mut chars = s.chars().peekable();
+
+        loop {
+            while chars.next_if(|c| c.is_whitespace()).is_some() {}
+            if chars.peek().is_none() {
+                break;
+            }
+
+            let negated = chars.next_if_eq(&'-').is_some();
+            if chars.peek() == Some(&'"') {
+                chars.next();
+                let phrase = read_quoted(&mut chars);
+                if !phrase.is_empty() {
+                    clauses.push(SearchClause {
+                        negated,
+                        filter: SearchFilter::Phrase(phrase),
+                    });
+                }
+                continue;
+            }
+
+            let mut word = String::new();
+            while let Some(c) = chars.next_if(|c| !c.is_whitespace() && *c != ':') {
+                word.push(c);
+            }
+
+            let filter = if chars.next_if_eq(&':').is_some() {
+                let value = if chars.next_if_eq(&'"').is_some() {
+                    read_quoted(&mut chars)
+                } else {
+                    read_word(&mut chars)
+                };
+                match parse_field(&word, &value)? {
+                    Some(filter) => filter,
+                    // not a known field so it is just text
+                    None => SearchFilter::Text(format!("{word}:{value}")),
+                }
+            } else if word.is_empty() {
+                continue;
+            } else {
+                SearchFilter::Text(word)
+            };
+            clauses.push(SearchClause { negated, filter });
+        }
+
+        Ok(Self { clauses })
+    }
+}
+
+fn read_quoted(chars: &mut std::iter::Peekable<std::str::Chars>) -> String {
+    // an unterminated quote extends to the end of the query
+    let mut value = String::new();
+    for c in chars.by_ref() {
+        if c == '"' {
+            break;
+        }
+        value.push(c);
+    }
+    value
+}
+
+fn read_word(chars: &mut std::iter::Peekable<std::str::Chars>) -> String {
+    let mut value = String::new();
+    while let Some(c) = chars.next_if(|c| !c.is_whitespace()) {
+        value.push(c);
+    }
+    value
+}
+
+fn parse_field(field: &str, value: &str) -> Result<Option<SearchFilter>, InvalidSearchQueryError> {
+    let filter = match field {
+        "artist" => SearchFilter::Artist(value.to_owned()),
+        "album" => SearchFilter::Album(value.to_owned()),
+        "genre" => SearchFilter::Genre(
+            Genre::new(value.to_lowercase())
+                .map_err(|e| InvalidSearchQueryError::new(e.to_string()))?,
+        ),
+        "year" => SearchFilter::Year(value.parse()?),
+        "favorite" => SearchFilter::Favorite(
+            value
+                .parse()
+                .map_err(|_| InvalidSearchQueryError::new("favorite must be 'true' or 'false'"))?,
+        ),
+        "duration" => SearchFilter::Duration(value.parse()?),
+        "prop" => {
+            let (key, value) = match value.split_once('=') {
+                Some((key, value)) => (key, Some(value)),
+                None => (value, None),
+            };
+            let key =
+                PropertyKey::new(key).map_err(|e| InvalidSearchQueryError::new(e.to_string()))?;
+            let value = value
+                .map(PropertyValue::new)
+                .transpose()
+                .map_err(|e| InvalidSearchQueryError::new(e.to_string()))?;
+            SearchFilter::Property(key, value)
+        }
+        _ => return Ok(None),
+    };
+    Ok(Some(filter))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn clause(negated: bool, filter: SearchFilter) -> SearchClause {
+        SearchClause { negated, filter }
+    }
+
+    #[test]
+    fn test_parse_text() {
+        let expr = "wish  you \"were here\" -live"
+            .parse::<SearchExpression>()
+            .unwrap();
+        assert_eq!(
+            expr.clauses,
+            vec![
+                clause(false, SearchFilter::Text("wish".into())),
+                clause(false, SearchFilter::Text("you".into())),
+                clause(false, SearchFilter::Phrase("were here".into())),
+                clause(true, SearchFilter::Text("live".into())),
+            ]
+        );
+        assert!(expr.has_filters());
+        assert_eq!(expr.text().count(), 3);
+    }
+
+    #[test]
+    fn test_parse_fields() {
+        let expr = "artist:\"pink floyd\" -album:live genre:Rock year:1990..1999 favorite:true duration:>300 prop:sonar.io/description=hello"
+            .parse::<SearchExpression>()
+            .unwrap();
+        assert_eq!(
+            expr.clauses,
+            vec![
+                clause(false, SearchFilter::Artist("pink floyd".into())),
+                clause(true, SearchFilter::Album("live".into())),
+                clause(false, SearchFilter::Genre(Genre::new("rock").unwrap())),
+                clause(
+                    false,
+                    SearchFilter::Year(SearchRange {
+                        min: Some(1990),
+                        max: Some(1999)
+                    })
+                ),
+                clause(false, SearchFilter::Favorite(true)),
+                clause(
+                    false,
+                    SearchFilter::Duration(SearchRange {
+                        min: Some(301),
+                        max: None
+                    })
+                ),
+                clause(
+                    false,
+                    SearchFilter::Property(
+                        PropertyKey::new("sonar.io/description").unwrap(),
+                        Some(PropertyValue::new("hello").unwrap())
+                    )
+                ),
+            ]
+        );
+        assert_eq!(expr.text().count(), 0);
+    }
+
+    #[test]
+    fn test_parse_unknown_field_is_text() {
+        let expr = "re:member".parse::<SearchExpression>().unwrap();
+        assert_eq!(
+            expr.clauses,
+            vec![clause(false, SearchFilter::Text("re:member".into()))]
+        );
+        assert!(!expr.has_filters());
+    }
+
+    #[test]
+    fn test_parse_invalid() {
+        assert!("year:abc".parse::<SearchExpression>().is_err());
+        assert!("favorite:maybe".parse::<SearchExpression>().is_err());
+        assert!("duration:<0".parse::<SearchExpression>().is_err());
+    }
+
+    #[test]
+    fn test_range() {
+        let range = "..1999".parse::<SearchRange>().unwrap();
+        assert!(range.contains(0));
+        assert!(range.contains(1999));
+        assert!(!range.contains(2000));
+
+        let range = "<=10".parse::<SearchRange>().unwrap();
+        assert!(range.contains(10));
+        assert!(!range.contains(11));
+
+        let range = "2000".parse::<SearchRange>().unwrap();
+        assert!(range.contains(2000));
+        assert!(!range.contains(2001));
+    }
+}
@@ -0,0 +1,408 @@
+use std::{
+    path::Path,
+    sync::{Arc, Mutex},
+};
+
+use tantivy::{
+    collector::TopDocs,
+    directory::MmapDirectory,
+    query::{
+        BooleanQuery, BoostQuery, ConstScoreQuery, FuzzyTermQuery, Occur, PhraseQuery, Query,
+        TermQuery,
+    },
+    schema::{
+        Field, IndexRecordOption, Schema, TextFieldIndexing, TextOptions, Value, FAST, INDEXED,
+        STORED,
+    },
+    tokenizer::{
+        AsciiFoldingFilter, LowerCaser, RemoveLongFilter, SimpleTokenizer, TextAnalyzer,
+        TokenStream,
+    },
+    DocAddress, Index, IndexReader, IndexWriter, Order, ReloadPolicy, TantivyDocument, Term,
+};
+
+use super::{
+    builtin::{ALBUM_QUERY, ARTIST_QUERY, PLAYLIST_QUERY, TRACK_QUERY},
+    collect_results, SearchEngine, SearchFilter, SearchQuery, SearchResults,
+};
+use crate::{
+    async_trait, db::Db, AlbumId, ArtistId, Error, PlaylistId, Result, SonarId, TrackId, UserId,
+    ID_NAMESPACE_ALBUM, ID_NAMESPACE_ARTIST, ID_NAMESPACE_PLAYLIST, ID_NAMESPACE_SHIFT,
+    ID_NAMESPACE_TRACK,
+};
+
+const DEFAULT_SEARCH_LIMIT: u32 = 50;
+const WRITER_MEMORY_BUDGET: usize = 50_000_000;
+const TOKENIZER_NAME: &str = "sonar";
+// owner of the items that are visible to every user
+const OWNER_NONE: u64 = 0;
+
+/// (sonar id, name, owner) as selected by the search index queries.
+type Entry = (i64, String, Option<i64>);
+
+#[derive(Debug, Clone, Copy)]
+struct Fields {
+    id: Field,
+    namespace: Field,
+    owner: Field,
+    name: Field,
+}
+
+/// Search engine backed by an on-disk tantivy index.
+/// Words match as prefixes, tolerate typos and results are ranked by relevance.
+pub struct TantivySearchEngine {
+    db: Db,
+    index: Index,
+    reader: IndexReader,
+    writer: Arc<Mutex<IndexWriter>>,
+    fields: Fields,
+}
+
+impl std::fmt::Debug for TantivySearchEngine {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TantivySearchEngine")
+            .field("fields", &self.fields)
+            .finish_non_exhaustive()
+    }
+}
+
+impl TantivySearchEngine {
+    pub fn new(db: Db, path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        std::fs::create_dir_all(path)?;
+
+        let mut builder = Schema::builder();
+        builder.add_u64_field("id", INDEXED | STORED | FAST);
+        builder.add_u64_field("namespace", INDEXED);
+        builder.add_u64_field("owner", INDEXED);
+        builder.add_text_field(
+            "name",
+            TextOptions::default().set_indexing_options(
+                TextFieldIndexing::default()
+                    .set_tokenizer(TOKENIZER_NAME)
+                    .set_index_option(IndexRecordOption::WithFreqsAndPositions),
+            ),
+        );
+        let directory = MmapDirectory::open(path).map_err(Error::wrap)?;
+        let index = Index::open_or_create(directory, builder.build()).map_err(Error::wrap)?;
+        // tokenizers are not persisted with the index so they must be registered every time
+        index.tokenizers().register(
+            TOKENIZER_NAME,
+            TextAnalyzer::builder(SimpleTokenizer::default())
+                .filter(RemoveLongFilter::limit(40))
+                .filter(LowerCaser)
+                .filter(AsciiFoldingFilter)
+                .build(),
+        );
+
+        let schema = index.schema();
+        let fields = Fields {
+            id: schema.get_field("id").map_err(Error::wrap)?,
+            namespace: schema.get_field("namespace").map_err(Error::wrap)?,
+            owner: schema.get_field("owner").map_err(Error::wrap)?,
+            name: schema.get_field("name").map_err(Error::wrap)?,
+        };
+        let reader = index
+            .reader_builder()
+            .reload_policy(ReloadPolicy::Manual)
+            .try_into()
+            .map_err(Error::wrap)?;
+        let writer = index.writer(WRITER_MEMORY_BUDGET).map_err(Error::wrap)?;
+
+        Ok(Self {
+            db,
+            index,
+            reader,
+            writer: Arc::new(Mutex::new(writer)),
+            fields,
+        })
+    }
+
+    async fn synchronize(&self, namespace: u32, id: i64, query: &str, table: &str) {
+        if let Err(err) = self.synchronize_inner(namespace, id, query, table).await {
+            tracing::error!("failed to synchronize {table} {id} with search index: {err}");
+        }
+    }
+
+    async fn synchronize_inner(
+        &self,
+        namespace: u32,
+        id: i64,
+        query: &str,
+        table: &str,
+    ) -> Result<()> {
+        let shifted = (namespace << ID_NAMESPACE_SHIFT) as i64;
+        let mut conn = self.db.acquire().await?;
+        // finds nothing if the item was deleted
+        let entry = sqlx::query_as::<_, Entry>(&format!("{query} WHERE {table}.id = ?"))
+            .bind(shifted)
+            .bind(id)
+            .fetch_optional(&mut *conn)
+            .await?;
+        drop(conn);
+
+        self.write(move |writer, fields| {
+            writer.delete_term(Term::from_field_u64(fields.id, (shifted | id) as u64));
+            if let Some(entry) = entry {
+                writer.add_document(document(fields, namespace, entry))?;
+            }
+            Ok(())
+        })
+        .await
+    }
+
+    async fn synchronize_all_inner(&self) -> Result<()> {
+        let mut conn = self.db.acquire().await?;
+        let mut documents = Vec::new();
+        for (namespace, query) in [
+            (ID_NAMESPACE_ARTIST, ARTIST_QUERY),
+            (ID_NAMESPACE_ALBUM, ALBUM_QUERY),
+            (ID_NAMESPACE_TRACK, TRACK_QUERY),
+            (ID_NAMESPACE_PLAYLIST, PLAYLIST_QUERY),
+        ] {
+            let entries = sqlx::query_as::<_, Entry>(query)
+                .bind((namespace << ID_NAMESPACE_SHIFT) as i64)
+                .fetch_all(&mut *conn)
+                .await?;
+            documents.extend(entries.into_iter().map(|entry| (namespace, entry)));
+        }
+        drop(conn);
+
+        self.write(move |writer, fields| {
+            writer.delete_all_documents()?;
+            for (namespace, entry) in documents {
+                writer.add_document(document(fields, namespace, entry))?;
+            }
+            Ok(())
+        })
+        .await
+    }
+
+    /// Applies the changes and commits them, the writer is blocking so this runs in a separate
+    /// thread.
+    async fn write<F>(&self, f: F) -> Result<()>
+    where
+        F: FnOnce(&mut IndexWriter, Fields) -> tantivy::Result<()> + Send + 'static,
+    {
+        let writer = self.writer.clone();
+        let reader = self.reader.clone();
+        let fields = self.fields;
+        tokio::task::spawn_blocking(move || {
+            let mut writer = writer.lock().unwrap();
+            f(&mut writer, fields)?;
+            writer.commit()?;
+            reader.reload()
+        })
+        .await
+        .map_err(Error::wrap)?
+        .map_err(Error::wrap)
+    }
+
+    fn tokenize(&self, text: &str) -> Result<Vec<String>> {
+        let mut analyzer = self
+            .index
+            .tokenizer_for_field(self.fields.name)
+            .map_err(Error::wrap)?;
+        let mut stream = analyzer.token_stream(text);
+        let mut tokens = Vec::new();
+        while stream.advance() {
+            tokens.push(stream.token().text.clone());
+        }
+        Ok(tokens)
+    }
+}
+
+#[async_trait]
+impl SearchEngine for TantivySearchEngine {
+    #[tracing::instrument(skip(self))]
+    async fn search(&self, user_id: UserId, query: &SearchQuery) -> Result<SearchResults> {
+        let expr = query.expression()?;
+        let limit = query.limit.unwrap_or(DEFAULT_SEARCH_LIMIT) as usize;
+        let namespaces = [
+            (SearchQuery::FLAG_ARTIST, ID_NAMESPACE_ARTIST),
+            (SearchQuery::FLAG_ALBUM, ID_NAMESPACE_ALBUM),
+            (SearchQuery::FLAG_TRACK, ID_NAMESPACE_TRACK),
+            (SearchQuery::FLAG_PLAYLIST, ID_NAMESPACE_PLAYLIST),
+        ]
+        .into_iter()
+        .filter(|(flag, _)| query.flags & flag != 0)
+        .map(|(_, namespace)| namespace as u64)
+        .collect::<Vec<_>>();
+        if namespaces.is_empty() || limit == 0 {
+            return Ok(SearchResults::default());
+        }
+
+        let mut clauses = vec![
+            (Occur::Must, any_of(self.fields.namespace, namespaces)),
+            (
+                Occur::Must,
+                any_of(self.fields.owner, [OWNER_NONE, user_id.to_db() as u64]),
+            ),
+        ];
+        let mut has_text = false;
+        for filter in expr.text() {
+            match filter {
+                SearchFilter::Text(text) => {
+                    for token in self.tokenize(text)? {
+                        clauses.push((Occur::Must, word_query(self.fields.name, &token)));
+                        has_text = true;
+                    }
+                }
+                SearchFilter::Phrase(phrase) => {
+                    let terms = self
+                        .tokenize(phrase)?
+                        .into_iter()
+                        .map(|token| Term::from_field_text(self.fields.name, &token))
+                        .collect::<Vec<_>>();
+                    let query: Box<dyn Query> = match terms.len() {
+                        0 => continue,
+                        1 => Box::new(TermQuery::new(
+                            terms.into_iter().next().unwrap(),
+                            IndexRecordOption::WithFreqs,
+                        )),
+                        _ => Box::new(PhraseQuery::new(terms)),
+                    };
+                    clauses.push((Occur::Must, query));
+                    has_text = true;
+                }
+                _ => {}
+            }
+        }
+        let search_query = BooleanQuery::new(clauses);
+
+        let (engine, search_query) = (self, &search_query);
+        let results = collect_results(
+            &self.db,
+            user_id,
+            query.library,
+            &expr,
+            limit,
+            move |offset, count| async move {
+                let searcher = engine.reader.searcher();
+                let top_docs = TopDocs::with_limit(count).and_offset(offset);
+                let addresses = if has_text {
+                    searcher
+                        .search(search_query, &top_docs)
+                        .map_err(Error::wrap)?
+                        .into_iter()
+                        .map(|(_, address)| address)
+                        .collect::<Vec<DocAddress>>()
+                } else {
+                    // an empty query matches everything
+                    searcher
+                        .search(
+                            search_query,
+                            &top_docs.order_by_fast_field::<u64>("id", Order::Asc),
+                        )
+                        .map_err(Error::wrap)?
+                        .into_iter()
+                        .map(|(_, address)| address)
+                        .collect::<Vec<DocAddress>>()
+                };
+
+                let mut ids = Vec::with_capacity(addresses.len());
+                for address in addresses {
+                    let document = searcher
+                        .doc::<TantivyDocument>(address)
+                        .map_err(Error::wrap)?;
+                    let id = document
+                        .get_first(engine.fields.id)
+                        .and_then(|value| value.as_u64())
+                        .and_then(|id| SonarId::try_from(id as u32).ok());
+                    if let Some(id) = id {
+                        ids.push(id);
+                    }
+                }
+                Ok(ids)
+            },
+        )
+        .await?;
+        Ok(SearchResults { results })
+    }
+    async fn synchronize_artist(&self, artist: ArtistId) {
+        self.synchronize(ID_NAMESPACE_ARTIST, artist.to_db(), ARTIST_QUERY, "artist")
+            .await
+    }
+    async fn synchronize_album(&self, album: AlbumId) {
+        self.synchronize(ID_NAMESPACE_ALBUM, album.to_db(), ALBUM_QUERY, "album")
+            .await
+    }
+    async fn synchronize_track(&self, track: TrackId) {
+        self.synchronize(ID_NAMESPACE_TRACK, track.to_db(), TRACK_QUERY, "track")
+            .await
+    }
+    async fn synchronize_playlist(&self, playlist: PlaylistId) {
+        self.synchronize(
+            ID_NAMESPACE_PLAYLIST,
+            playlist.to_db(),
+            PLAYLIST_QUERY,
+            "playlist",
+        )
+        .await
+    }
+    async fn synchronize_all(&self) {
+        if let Err(err) = self.synchronize_all_inner().await {
+            tracing::error!("failed to rebuild search index: {err}");
+        }
+    }
+}
+
+fn document(fields: Fields, namespace: u32, (id, name, owner): Entry) -> TantivyDocument {
+    let mut document = TantivyDocument::default();
+    document.add_u64(fields.id, id as u64);
+    document.add_u64(fields.namespace, namespace as u64);
+    document.add_u64(fields.owner, owner.map(|o| o as u64).unwrap_or(OWNER_NONE));
+    document.add_text(fields.name, name);
+    document
+}
+
+/// Matches documents where the field has any of the values, without affecting the score.
+fn any_of(field: Field, values: impl IntoIterator<Item = u64>) -> Box<dyn Query> {
+    let clauses = values
+        .into_iter()
+        .map(|value| {
+            let query =
+                TermQuery::new(Term::from_field_u64(field, value), IndexRecordOption::Basic);
+            (Occur::Should, Box::new(query) as Box<dyn Query>)
+        })
+        .collect();
+    Box::new(ConstScoreQuery::new(
+        Box::new(BooleanQuery::new(clauses)),
+        0.0,
+    ))
+}
+
+/// Matches the word as a prefix, allowing for typos in longer words.
+/// Exact matches are boosted since prefix and fuzzy matches all have the same score.
+fn word_query(field: Field, word: &str) -> Box<dyn Query> {
+    let term = Term::from_field_text(field, word);
+    let exact = TermQuery::new(term.clone(), IndexRecordOption::WithFreqs);
+    let fuzzy = FuzzyTermQuery::new_prefix(term, typo_distance(word), true);
+    Box::new(BooleanQuery::new(vec![
+        (
+            Occur::Should,
+            Box::new(BoostQuery::new(Box::new(exact), 2.0)) as Box<dyn Query>,
+        ),
+        (Occur::Should, Box::new(fuzzy) as Box<dyn Query>),
+    ]))
+}
+
+fn typo_distance(word: &str) -> u8 {
+    match word.chars().count() {
+        0..=4 => 0,
+        5..=8 => 1,
+        _ => 2,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_typo_distance() {
+        assert_eq!(typo_distance("blue"), 0);
+        assert_eq!(typo_distance("björk"), 1);
+        assert_eq!(typo_distance("homogenic"), 2);
+    }
+}
@@ -0,0 +1,577 @@
+//! Smart playlists are playlists whose tracks are generated from a set of rules.
+//! The rules are evaluated from the point of view of the playlist owner, so favorites, ratings
+//! and listens are those of the owner and only tracks in libraries the owner has access to are
+//! included. The generated tracks are stored like the tracks of any other playlist and are
+//! replaced every time the playlist is refreshed.
+
+use std::{borrow::Cow, str::FromStr, time::Duration};
+
+use crate::{
+    db::{self, DbC},
+    library, playlist, ArtistId, Error, ErrorKind, Genre, LibraryFilter, Playlist, PlaylistCreate,
+    PlaylistId, Properties, PropertyKey, PropertyValue, Rating, Result, Timestamp, TrackId, UserId,
+    ID_NAMESPACE_ALBUM, ID_NAMESPACE_ARTIST, ID_NAMESPACE_TRACK,
+};
+
+#[derive(Debug)]
+pub struct InvalidSmartPlaylistRuleError {
+    message: Cow<'static, str>,
+}
+
+impl InvalidSmartPlaylistRuleError {
+    fn new(message: impl Into<Cow<'static, str>>) -> Self {
+        Self {
+            message: message.into(),
+        }
+    }
+}
+
+impl std::fmt::Display for InvalidSmartPlaylistRuleError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "invalid smart playlist rule: {}", self.message)
+    }
+}
+
+impl std::error::Error for InvalidSmartPlaylistRuleError {}
+
+#[derive(Debug)]
+pub struct InvalidSmartPlaylistSortError {
+    value: String,
+}
+
+impl std::fmt::Display for InvalidSmartPlaylistSortError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} is not a valid smart playlist sort", self.value)
+    }
+}
+
+impl std::error::Error for InvalidSmartPlaylistSortError {}
+
+/// A condition a track must satisfy to be included in a smart playlist.
+///
+/// Rules are written as `name` or `name:value`, for example `genre:rock`, `favorite`,
+/// `min-listens:5` or `not-played-within:30d`. Durations are a number followed by one of `s`,
+/// `m`, `h`, `d` or `w`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SmartPlaylistRule {
+    /// The track, its album or its primary artist has the genre.
+    Genre(Genre),
+    /// The artist is credited on the track.
+    Artist(ArtistId),
+    Favorite,
+    NotFavorite,
+    MinListens(u32),
+    MaxListens(u32),
+    PlayedWithin(Duration),
+    NotPlayedWithin(Duration),
+    AddedWithin(Duration),
+    MinRating(Rating),
+    Property(PropertyKey, PropertyValue),
+}
+
+impl std::fmt::Display for SmartPlaylistRule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SmartPlaylistRule::Genre(genre) => write!(f, "genre:{genre}"),
+            SmartPlaylistRule::Artist(artist) => write!(f, "artist:{artist}"),
+            SmartPlaylistRule::Favorite => write!(f, "favorite"),
+            SmartPlaylistRule::NotFavorite => write!(f, "not-favorite"),
+            SmartPlaylistRule::MinListens(count) => write!(f, "min-listens:{count}"),
+            SmartPlaylistRule::MaxListens(count) => write!(f, "max-listens:{count}"),
+            SmartPlaylistRule::PlayedWithin(duration) => {
+                write!(f, "played-within:{}", format_duration(*duration))
+            }
+            SmartPlaylistRule::NotPlayedWithin(duration) => {
+                write!(f, "not-played-within:{}", format_duration(*duration))
+            }
+            SmartPlaylistRule::AddedWithin(duration) => {
+                write!(f, "added-within:{}", format_duration(*duration))
+            }
+            SmartPlaylistRule::MinRating(rating) => write!(f, "min-rating:{rating}"),
+            SmartPlaylistRule::Property(key, value) => write!(f, "property:{key}={value}"),
+        }
+    }
+}
+
+impl FromStr for SmartPlaylistRule {
+    type Err = InvalidSmartPlaylistRuleError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, value) = match s.trim().split_once(':') {
+            Some((name, value)) => (name, Some(value)),
+            None => (s.trim(), None),
+        };
+        let value = || {
+            value.ok_or_else(|| {
+                InvalidSmartPlaylistRuleError::new(format!("{name} requires a value"))
+            })
+        };
+        let invalid =
+            |err: &dyn std::fmt::Display| InvalidSmartPlaylistRuleError::new(format!("{s}: {err}"));
+        let rule = match name {
+            "genre" => SmartPlaylistRule::Genre(value()?.parse().map_err(|e| invalid(&e))?),
+            "artist" => SmartPlaylistRule::Artist(value()?.parse().map_err(|e| invalid(&e))?),
+            "favorite" => SmartPlaylistRule::Favorite,
+            "not-favorite" => SmartPlaylistRule::NotFavorite,
+            "min-listens" => {
+                SmartPlaylistRule::MinListens(value()?.parse().map_err(|e| invalid(&e))?)
+            }
+            "max-listens" => {
+                SmartPlaylistRule::MaxListens(value()?.parse().map_err(|e| invalid(&e))?)
+            }
+            "played-within" => SmartPlaylistRule::PlayedWithin(parse_duration(value()?)?),
+            "not-played-within" => SmartPlaylistRule::NotPlayedWithin(parse_duration(value()?)?),
+            "added-within" => SmartPlaylistRule::AddedWithin(parse_duration(value()?)?),
+            "min-rating" => {
+                SmartPlaylistRule::MinRating(value()?.parse().map_err(|e| invalid(&e))?)
+            }
+            "property" => {
+                let (key, property_value) = value()?.split_once('=').ok_or_else(|| {
+                    InvalidSmartPlaylistRuleError::new("property rules must be key=value")
+                })?;
+                SmartPlaylistRule::Property(
+                    key.parse().map_err(|e| invalid(&e))?,
+                    property_value.parse().map_err(|e| invalid(&e))?,
+                )
+            }
+            _ => {
+                return Err(InvalidSmartPlaylistRuleError::new(format!(
+                    "unknown rule '{name}'"
+                )))
+            }
+        };
+        Ok(rule)
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SmartPlaylistSort {
+    #[default]
+    Random,
+    Name,
+    Listens,
+    LastPlayed,
+    Added,
+    Rating,
+}
+
+impl SmartPlaylistSort {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SmartPlaylistSort::Random => "random",
+            SmartPlaylistSort::Name => "name",
+            SmartPlaylistSort::Listens => "listens",
+            SmartPlaylistSort::LastPlayed => "last-played",
+            SmartPlaylistSort::Added => "added",
+            SmartPlaylistSort::Rating => "rating",
+        }
+    }
+}
+
+impl std::fmt::Display for SmartPlaylistSort {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for SmartPlaylistSort {
+    type Err = InvalidSmartPlaylistSortError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "random" => Ok(SmartPlaylistSort::Random),
+            "name" => Ok(SmartPlaylistSort::Name),
+            "listens" => Ok(SmartPlaylistSort::Listens),
+            "last-played" => Ok(SmartPlaylistSort::LastPlayed),
+            "added" => Ok(SmartPlaylistSort::Added),
+            "rating" => Ok(SmartPlaylistSort::Rating),
+            _ => Err(InvalidSmartPlaylistSortError {
+                value: s.to_owned(),
+            }),
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct SmartPlaylistRules {
+    pub rules: Vec<SmartPlaylistRule>,
+    /// Include tracks that match any of the rules instead of all of them.
+    pub match_any: bool,
+    pub sort: SmartPlaylistSort,
+    pub descending: bool,
+    /// Maximum number of tracks in the playlist.
+    pub limit: Option<u32>,
+}
+
+#[derive(Debug, Clone)]
+pub struct SmartPlaylist {
+    pub playlist: PlaylistId,
+    pub rules: SmartPlaylistRules,
+    pub refreshed_at: Option<Timestamp>,
+}
+
+#[derive(Debug, Clone)]
+pub struct SmartPlaylistCreate {
+    pub name: String,
+    pub owner: UserId,
+    pub rules: SmartPlaylistRules,
+    pub properties: Properties,
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct SmartPlaylistView {
+    playlist: i64,
+    rules: String,
+    match_any: bool,
+    sort: String,
+    descending: bool,
+    max_tracks: Option<i64>,
+    refreshed_at: Option<i64>,
+}
+
+impl TryFrom<SmartPlaylistView> for SmartPlaylist {
+    type Error = Error;
+
+    fn try_from(value: SmartPlaylistView) -> Result<Self, Self::Error> {
+        let rules = value
+            .rules
+            .lines()
+            .map(SmartPlaylistRule::from_str)
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| Error::with_source(ErrorKind::Internal, "invalid rule in database", e))?;
+        let sort = value
+            .sort
+            .parse()
+            .map_err(|e| Error::with_source(ErrorKind::Internal, "invalid sort in database", e))?;
+        Ok(Self {
+            playlist: PlaylistId::from_db(value.playlist),
+            rules: SmartPlaylistRules {
+                rules,
+                match_any: value.match_any,
+                sort,
+                descending: value.descending,
+                limit: value.max_tracks.map(|limit| limit as u32),
+            },
+            refreshed_at: value
+                .refreshed_at
+                .map(|refreshed_at| Timestamp::from_seconds(refreshed_at as u64)),
+        })
+    }
+}
+
+#[tracing::instrument(skip(db))]
+pub async fn list_ids(db: &mut DbC) -> Result<Vec<PlaylistId>> {
+    let ids = sqlx::query_scalar::<_, i64>("SELECT playlist FROM smart_playlist")
+        .fetch_all(&mut *db)
+        .await?;
+    Ok(ids.into_iter().map(PlaylistId::from_db).collect())
+}
+
+#[tracing::instrument(skip(db))]
+pub async fn get(db: &mut DbC, playlist_id: PlaylistId) -> Result<SmartPlaylist> {
+    let view = sqlx::query_as::<_, SmartPlaylistView>(
+        "SELECT playlist, rules, match_any, sort, descending, max_tracks, refreshed_at
+        FROM smart_playlist WHERE playlist = ?",
+    )
+    .bind(playlist_id.to_db())
+    .fetch_optional(&mut *db)
+    .await?;
+    match view {
+        Some(view) => SmartPlaylist::try_from(view),
+        None => Err(Error::new(ErrorKind::NotFound, "smart playlist not found")),
+    }
+}
+
+#[tracing::instrument(skip(db))]
+pub async fn create(db: &mut DbC, create: SmartPlaylistCreate) -> Result<Playlist> {
+    let playlist = playlist::create(
+        db,
+        PlaylistCreate {
+            name: create.name,
+            owner: create.owner,
+            tracks: Vec::new(),
+            cover_art: None,
+            properties: create.properties,
+        },
+    )
+    .await?;
+    sqlx::query("INSERT INTO smart_playlist (playlist, rules, sort) VALUES (?, '', ?)")
+        .bind(playlist.id.to_db())
+        .bind(create.rules.sort.as_str())
+        .execute(&mut *db)
+        .await?;
+    update(db, playlist.id, create.rules).await
+}
+
+/// Replaces the rules of a smart playlist and refreshes its tracks.
+#[tracing::instrument(skip(db))]
+pub async fn update(
+    db: &mut DbC,
+    playlist_id: PlaylistId,
+    rules: SmartPlaylistRules,
+) -> Result<Playlist> {
+    let text = rules
+        .rules
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join("\n");
+    let result = sqlx::query(
+        "UPDATE smart_playlist SET rules = ?, match_any = ?, sort = ?, descending = ?, max_tracks = ?
+        WHERE playlist = ?",
+    )
+    .bind(text)
+    .bind(rules.match_any)
+    .bind(rules.sort.as_str())
+    .bind(rules.descending)
+    .bind(rules.limit.map(i64::from))
+    .bind(playlist_id.to_db())
+    .execute(&mut *db)
+    .await?;
+    if result.rows_affected() == 0 {
+        return Err(Error::new(ErrorKind::NotFound, "smart playlist not found"));
+    }
+    refresh(db, playlist_id).await
+}
+
+/// Evaluates the rules of a smart playlist and replaces its tracks.
+#[tracing::instrument(skip(db))]
+pub async fn refresh(db: &mut DbC, playlist_id: PlaylistId) -> Result<Playlist> {
+    let smart = get(db, playlist_id).await?;
+    let owner = playlist::get(db, playlist_id).await?.owner;
+    let tracks = evaluate(db, owner, &smart.rules).await?;
+    playlist::clear_tracks(db, playlist_id).await?;
+    playlist::insert_tracks(db, playlist_id, &tracks).await?;
+    sqlx::query("UPDATE smart_playlist SET refreshed_at = unixepoch() WHERE playlist = ?")
+        .bind(playlist_id.to_db())
+        .execute(&mut *db)
+        .await?;
+    playlist::get(db, playlist_id).await
+}
+
+/// Fails if the playlist is a smart playlist, their tracks can only be changed by the rules.
+#[tracing::instrument(skip(db))]
+pub async fn ensure_editable(db: &mut DbC, playlist_id: PlaylistId) -> Result<()> {
+    let smart = sqlx::query_scalar::<_, bool>(
+        "SELECT EXISTS (SELECT 1 FROM smart_playlist WHERE playlist = ?)",
+    )
+    .bind(playlist_id.to_db())
+    .fetch_one(&mut *db)
+    .await?;
+    if smart {
+        return Err(Error::new(
+            ErrorKind::Invalid,
+            "the tracks of a smart playlist can not be edited",
+        ));
+    }
+    Ok(())
+}
+
+async fn evaluate(db: &mut DbC, owner: UserId, rules: &SmartPlaylistRules) -> Result<Vec<TrackId>> {
+    let libraries = library::resolve(db, LibraryFilter::user(owner)).await?;
+    let owner = owner.to_db();
+    let mut query = sqlx::QueryBuilder::<sqlx::Sqlite>::new(
+        "SELECT sqlx_track.id FROM sqlx_track
+        LEFT JOIN (
+            SELECT track, COUNT(*) AS listens, MAX(listen_at) AS last_played
+            FROM scrobble WHERE user = ",
+    );
+    query.push_bind(owner);
+    query.push(" GROUP BY track) AS plays ON plays.track = sqlx_track.id WHERE ");
+    if let Some(libraries) = libraries.as_deref() {
+        query.push("sqlx_track.library IN ");
+        db::query_builder_push_id_tuple(&mut query, libraries);
+        query.push("AND ");
+    }
+    query.push("(");
+
+    if rules.rules.is_empty() {
+        query.push("1");
+    }
+    for (index, rule) in rules.rules.iter().enumerate() {
+        if index > 0 {
+            query.push(if rules.match_any { " OR " } else { " AND " });
+        }
+        query.push("(");
+        match rule {
+            SmartPlaylistRule::Genre(genre) => {
+                query.push("EXISTS (SELECT 1 FROM genre WHERE genre.genre = ");
+                query.push_bind(genre.as_str().to_owned());
+                query.push(format!(
+                    " AND ((genre.namespace = {ID_NAMESPACE_TRACK} AND genre.identifier = sqlx_track.id)
+                    OR (genre.namespace = {ID_NAMESPACE_ALBUM} AND genre.identifier = sqlx_track.album)
+                    OR (genre.namespace = {ID_NAMESPACE_ARTIST} AND genre.identifier = sqlx_track.artist)))"
+                ));
+            }
+            SmartPlaylistRule::Artist(artist) => {
+                query.push(
+                    "EXISTS (SELECT 1 FROM view_track_credit WHERE view_track_credit.track = sqlx_track.id AND view_track_credit.artist = ",
+                );
+                query.push_bind(artist.to_db());
+                query.push(")");
+            }
+            SmartPlaylistRule::Favorite | SmartPlaylistRule::NotFavorite => {
+                if *rule == SmartPlaylistRule::NotFavorite {
+                    query.push("NOT ");
+                }
+                query.push("EXISTS (SELECT 1 FROM favorite WHERE favorite.user = ");
+                query.push_bind(owner);
+                query.push(format!(
+                    " AND favorite.namespace = {ID_NAMESPACE_TRACK} AND favorite.identifier = sqlx_track.id)"
+                ));
+            }
+            SmartPlaylistRule::MinListens(count) => {
+                query.push("COALESCE(plays.listens, 0) >= ");
+                query.push_bind(*count);
+            }
+            SmartPlaylistRule::MaxListens(count) => {
+                query.push("COALESCE(plays.listens, 0) <= ");
+                query.push_bind(*count);
+            }
+            SmartPlaylistRule::PlayedWithin(duration) => {
+                query.push("plays.last_played >= unixepoch() - ");
+                query.push_bind(duration.as_secs() as i64);
+            }
+            SmartPlaylistRule::NotPlayedWithin(duration) => {
+                query.push("COALESCE(plays.last_played, 0) < unixepoch() - ");
+                query.push_bind(duration.as_secs() as i64);
+            }
+            SmartPlaylistRule::AddedWithin(duration) => {
+                query.push("sqlx_track.created_at >= unixepoch() - ");
+                query.push_bind(duration.as_secs() as i64);
+            }
+            SmartPlaylistRule::MinRating(rating) => {
+                query.push("(SELECT rating.rating FROM rating WHERE rating.user = ");
+                query.push_bind(owner);
+                query.push(format!(
+                    " AND rating.namespace = {ID_NAMESPACE_TRACK} AND rating.identifier = sqlx_track.id) >= "
+                ));
+                query.push_bind(rating.value());
+            }
+            SmartPlaylistRule::Property(key, value) => {
+                query.push(format!(
+                    "EXISTS (SELECT 1 FROM property WHERE property.namespace = {ID_NAMESPACE_TRACK}
+                    AND property.identifier = sqlx_track.id AND property.user IS NULL AND property.key = "
+                ));
+                query.push_bind(key.as_str().to_owned());
+                query.push(" AND property.value = ");
+                query.push_bind(value.as_str().to_owned());
+                query.push(")");
+            }
+        }
+        query.push(")");
+    }
+    query.push(")");
+
+    query.push(" ORDER BY ");
+    match rules.sort {
+        SmartPlaylistSort::Random => query.push("RANDOM()"),
+        SmartPlaylistSort::Name => query.push("sqlx_track.name COLLATE NOCASE"),
+        SmartPlaylistSort::Listens => query.push("COALESCE(plays.listens, 0)"),
+        SmartPlaylistSort::LastPlayed => query.push("COALESCE(plays.last_played, 0)"),
+        SmartPlaylistSort::Added => query.push("sqlx_track.created_at"),
+        SmartPlaylistSort::Rating => {
+            query.push("COALESCE((SELECT rating.rating FROM rating WHERE rating.user = ");
+            query.push_bind(owner);
+            query.push(format!(
+                " AND rating.namespace = {ID_NAMESPACE_TRACK} AND rating.identifier = sqlx_track.id), 0)"
+            ))
+        }
+    };
+    query.push(if rules.descending { " DESC" } else { " ASC" });
+    query.push(", sqlx_track.id ASC");
+    if let Some(limit) = rules.limit {
+        query.push(" LIMIT ");
+        query.push_bind(limit);
+    }
+
+    let ids = query
+        .build_query_scalar::<i64>()
+        .fetch_all(&mut *db)
+        .await?;
+    Ok(ids.into_iter().map(TrackId::from_db).collect())
+}
+
+fn parse_duration(value: &str) -> Result<Duration, InvalidSmartPlaylistRuleError> {
+    let invalid = || InvalidSmartPlaylistRuleError::new(format!("invalid duration '{value}'"));
+    let (number, unit) = value.split_at(
+        value
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(value.len()),
+    );
+    let number = number.parse::<u64>().map_err(|_| invalid())?;
+    let unit = match unit {
+        "" | "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        "w" => 7 * 24 * 60 * 60,
+        _ => return Err(invalid()),
+    };
+    // durations are bound as i64 seconds when the rules are evaluated
+    let seconds = number
+        .checked_mul(unit)
+        .filter(|&seconds| i64::try_from(seconds).is_ok())
+        .ok_or_else(invalid)?;
+    Ok(Duration::from_secs(seconds))
+}
+
+fn format_duration(duration: Duration) -> String {
+    let seconds = duration.as_secs();
+    for (unit, size) in [
+        ("w", 7 * 24 * 60 * 60),
+        ("d", 24 * 60 * 60),
+        ("h", 60 * 60),
+        ("m", 60),
+    ] {
+        if seconds != 0 && seconds % size == 0 {
+            return format!("{}{}", seconds / size, unit);
+        }
+    }
+    format!("{seconds}s")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_rule_roundtrip() {
+        for rule in [
+            "genre:rock",
+            "favorite",
+            "not-favorite",
+            "min-listens:5",
+            "max-listens:0",
+            "played-within:7d",
+            "not-played-within:2w",
+            "added-within:12h",
+            "min-rating:4",
+            "property:key=value",
+        ] {
+            let parsed = rule.parse::<SmartPlaylistRule>().unwrap();
+            assert_eq!(parsed.to_string(), rule);
+        }
+    }
+
+    #[test]
+    fn test_rule_invalid() {
+        assert!("genre".parse::<SmartPlaylistRule>().is_err());
+        assert!("min-listens:many".parse::<SmartPlaylistRule>().is_err());
+        assert!("played-within:7y".parse::<SmartPlaylistRule>().is_err());
+        assert!("loudness:10".parse::<SmartPlaylistRule>().is_err());
+    }
+
+    #[test]
+    fn test_duration() {
+        assert_eq!(parse_duration("90").unwrap(), Duration::from_secs(90));
+        assert_eq!(
+            parse_duration("3d").unwrap(),
+            Duration::from_secs(3 * 86400)
+        );
+        assert!(parse_duration("18446744073709551615w").is_err());
+        assert!(parse_duration("9223372036854775808").is_err());
+        assert_eq!(format_duration(Duration::from_secs(90)), "90s");
+        assert_eq!(format_duration(Duration::from_secs(14 * 86400)), "2w");
+    }
+}
@@ -0,0 +1,331 @@
+//! Listening statistics computed from the scrobbles of a user.
+//! Artists are counted using the primary artist of each track and genres using the genres of the
+//! album the track belongs to.
+
+use std::{str::FromStr, time::Duration};
+
+use crate::{
+    db::DbC, AlbumId, ArtistId, Error, ErrorKind, Genre, Result, Timestamp, TrackId, UserId,
+    ID_NAMESPACE_ALBUM,
+};
+
+const SECONDS_PER_DAY: i64 = 24 * 60 * 60;
+// seconds since the start of the local day, the same as `rem_euclid` since sqlite's modulo keeps
+// the sign of negative local times. binds the utc offset.
+const SECONDS_OF_DAY: &str = "(((listen_at + ?) % 86400 + 86400) % 86400)";
+const SCROBBLE_FILTER: &str =
+    "scrobble.user = ? AND scrobble.listen_at >= ? AND scrobble.listen_at < ?";
+
+#[derive(Debug)]
+pub struct InvalidStatsPeriodError {
+    value: String,
+}
+
+impl InvalidStatsPeriodError {
+    fn new(value: impl Into<String>) -> Self {
+        Self {
+            value: value.into(),
+        }
+    }
+}
+
+impl std::fmt::Display for InvalidStatsPeriodError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "invalid stats period '{}', must be one of week, month, year or all-time",
+            self.value
+        )
+    }
+}
+
+impl std::error::Error for InvalidStatsPeriodError {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum StatsPeriod {
+    Week,
+    Month,
+    Year,
+    AllTime,
+}
+
+impl StatsPeriod {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            StatsPeriod::Week => "week",
+            StatsPeriod::Month => "month",
+            StatsPeriod::Year => "year",
+            StatsPeriod::AllTime => "all-time",
+        }
+    }
+
+    /// Start of the period if it ends at `now`.
+    pub fn since(&self, now: Timestamp) -> Option<Timestamp> {
+        let days = match self {
+            StatsPeriod::Week => 7,
+            StatsPeriod::Month => 30,
+            StatsPeriod::Year => 365,
+            StatsPeriod::AllTime => return None,
+        };
+        let seconds = now.seconds().saturating_sub(days * SECONDS_PER_DAY as u64);
+        Some(Timestamp::from_seconds(seconds))
+    }
+}
+
+impl std::fmt::Display for StatsPeriod {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for StatsPeriod {
+    type Err = InvalidStatsPeriodError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "week" => Ok(StatsPeriod::Week),
+            "month" => Ok(StatsPeriod::Month),
+            "year" => Ok(StatsPeriod::Year),
+            "all-time" => Ok(StatsPeriod::AllTime),
+            _ => Err(InvalidStatsPeriodError::new(s)),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct StatsParams {
+    pub user: UserId,
+    /// Only scrobbles listened at or after this time are included.
+    pub since: Option<Timestamp>,
+    /// Only scrobbles listened before this time are included.
+    pub until: Option<Timestamp>,
+    /// Maximum number of entries in each of the top lists.
+    pub limit: u32,
+    /// Offset from UTC, in seconds, used to split scrobbles into days and hours.
+    pub utc_offset: i32,
+}
+
+impl StatsParams {
+    pub const DEFAULT_LIMIT: u32 = 10;
+    /// Largest offset from UTC, in seconds, of any time zone.
+    pub const MAX_UTC_OFFSET: i32 = 14 * 60 * 60;
+
+    pub fn new(user: UserId) -> Self {
+        Self {
+            user,
+            since: None,
+            until: None,
+            limit: Self::DEFAULT_LIMIT,
+            utc_offset: 0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StatsEntry<T> {
+    pub item: T,
+    pub listen_count: u64,
+    pub listen_time: Duration,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Stats {
+    pub listen_count: u64,
+    pub listen_time: Duration,
+    pub top_artists: Vec<StatsEntry<ArtistId>>,
+    pub top_albums: Vec<StatsEntry<AlbumId>>,
+    pub top_tracks: Vec<StatsEntry<TrackId>>,
+    pub top_genres: Vec<StatsEntry<Genre>>,
+    /// Number of scrobbles for each hour of the day.
+    pub hour_histogram: [u64; 24],
+    /// Number of scrobbles for each day of the week, starting on monday.
+    pub weekday_histogram: [u64; 7],
+    /// Number of consecutive days with scrobbles up to the end of the range.
+    /// Days without scrobbles only break the streak once they are over.
+    pub current_streak: u32,
+    pub longest_streak: u32,
+}
+
+#[tracing::instrument(skip(db))]
+pub async fn compute(db: &mut DbC, params: StatsParams) -> Result<Stats> {
+    let max_offset = StatsParams::MAX_UTC_OFFSET;
+    if !(-max_offset..=max_offset).contains(&params.utc_offset) {
+        return Err(Error::new(
+            ErrorKind::Invalid,
+            "utc offset must be at most 14 hours",
+        ));
+    }
+    let since = params.since.map(|t| t.seconds() as i64).unwrap_or(0);
+    let until = params.until.map(|t| t.seconds() as i64).unwrap_or(i64::MAX);
+    let filter = (params.user.to_db(), since, until);
+    let offset = i64::from(params.utc_offset);
+    let mut stats = Stats::default();
+
+    let (listen_count, listen_secs) = sqlx::query_as::<_, (i64, i64)>(&format!(
+        "SELECT COUNT(*), COALESCE(SUM(listen_secs), 0) FROM scrobble WHERE {SCROBBLE_FILTER}"
+    ))
+    .bind(filter.0)
+    .bind(filter.1)
+    .bind(filter.2)
+    .fetch_one(&mut *db)
+    .await?;
+    stats.listen_count = listen_count as u64;
+    stats.listen_time = Duration::from_secs(listen_secs as u64);
+
+    stats.top_artists = top(
+        db,
+        "sqlx_track.artist",
+        "INNER JOIN sqlx_track ON sqlx_track.id = scrobble.track",
+        filter,
+        params.limit,
+    )
+    .await?;
+    stats.top_albums = top(
+        db,
+        "track.album",
+        "INNER JOIN track ON track.id = scrobble.track",
+        filter,
+        params.limit,
+    )
+    .await?;
+    stats.top_tracks = top(db, "scrobble.track", "", filter, params.limit).await?;
+    stats.top_genres = top::<String>(
+        db,
+        "genre.genre",
+        &format!(
+            "INNER JOIN track ON track.id = scrobble.track
+            INNER JOIN genre ON genre.namespace = {ID_NAMESPACE_ALBUM} AND genre.identifier = track.album"
+        ),
+        filter,
+        params.limit,
+    )
+    .await?
+    .into_iter()
+    .map(|entry| StatsEntry {
+        item: Genre::new_unchecked(entry.item),
+        listen_count: entry.listen_count,
+        listen_time: entry.listen_time,
+    })
+    .collect();
+
+    let hours = sqlx::query_as::<_, (i64, i64)>(&format!(
+        "SELECT {SECONDS_OF_DAY} / 3600, COUNT(*) FROM scrobble
+        WHERE {SCROBBLE_FILTER} GROUP BY 1"
+    ))
+    .bind(offset)
+    .bind(filter.0)
+    .bind(filter.1)
+    .bind(filter.2)
+    .fetch_all(&mut *db)
+    .await?;
+    for (hour, count) in hours {
+        stats.hour_histogram[hour as usize] = count as u64;
+    }
+
+    let days = sqlx::query_as::<_, (i64, i64)>(&format!(
+        "SELECT (listen_at + ? - {SECONDS_OF_DAY}) / {SECONDS_PER_DAY}, COUNT(*) FROM scrobble
+        WHERE {SCROBBLE_FILTER} GROUP BY 1 ORDER BY 1 ASC"
+    ))
+    .bind(offset)
+    .bind(offset)
+    .bind(filter.0)
+    .bind(filter.1)
+    .bind(filter.2)
+    .fetch_all(&mut *db)
+    .await?;
+    for &(day, count) in days.iter() {
+        stats.weekday_histogram[weekday(day)] += count as u64;
+    }
+    let days = days.into_iter().map(|(day, _)| day).collect::<Vec<_>>();
+
+    let end = params.until.unwrap_or_else(Timestamp::now).seconds() as i64;
+    let (current_streak, longest_streak) =
+        streaks(&days, (end + offset).div_euclid(SECONDS_PER_DAY));
+    stats.current_streak = current_streak;
+    stats.longest_streak = longest_streak;
+
+    Ok(stats)
+}
+
+async fn top<T>(
+    db: &mut DbC,
+    item: &str,
+    joins: &str,
+    filter: (i64, i64, i64),
+    limit: u32,
+) -> Result<Vec<StatsEntry<T>>>
+where
+    T: for<'r> sqlx::Decode<'r, sqlx::Sqlite> + sqlx::Type<sqlx::Sqlite> + Send + Unpin,
+{
+    let rows = sqlx::query_as::<_, (T, i64, i64)>(&format!(
+        "SELECT {item}, COUNT(*) AS listen_count, SUM(scrobble.listen_secs) AS listen_secs
+        FROM scrobble {joins}
+        WHERE {SCROBBLE_FILTER}
+        GROUP BY {item}
+        ORDER BY listen_count DESC, listen_secs DESC
+        LIMIT ?"
+    ))
+    .bind(filter.0)
+    .bind(filter.1)
+    .bind(filter.2)
+    .bind(limit)
+    .fetch_all(&mut *db)
+    .await?;
+    Ok(rows
+        .into_iter()
+        .map(|(item, listen_count, listen_secs)| StatsEntry {
+            item,
+            listen_count: listen_count as u64,
+            listen_time: Duration::from_secs(listen_secs as u64),
+        })
+        .collect())
+}
+
+/// Day of the week, starting on monday, of a day counted from the unix epoch.
+fn weekday(day: i64) -> usize {
+    // the unix epoch was a thursday
+    (day + 3).rem_euclid(7) as usize
+}
+
+/// Computes the current and longest streaks from a sorted list of days with scrobbles.
+fn streaks(days: &[i64], today: i64) -> (u32, u32) {
+    let mut longest = 0;
+    let mut streak = 0;
+    let mut previous = None;
+    for &day in days {
+        streak = match previous {
+            Some(previous) if previous + 1 == day => streak + 1,
+            _ => 1,
+        };
+        longest = longest.max(streak);
+        previous = Some(day);
+    }
+    let current = match previous {
+        Some(last) if last == today || last + 1 == today => streak,
+        _ => 0,
+    };
+    (current, longest)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_weekday() {
+        // 1970-01-01
+        assert_eq!(weekday(0), 3);
+        // 2024-01-01
+        assert_eq!(weekday(19723), 0);
+        // 1969-12-31
+        assert_eq!(weekday(-1), 2);
+    }
+
+    #[test]
+    fn test_streaks() {
+        assert_eq!(streaks(&[], 10), (0, 0));
+        assert_eq!(streaks(&[1, 2, 3, 5, 6], 6), (2, 3));
+        assert_eq!(streaks(&[1, 2, 3, 5, 6], 7), (2, 3));
+        assert_eq!(streaks(&[1, 2, 3, 5, 6], 8), (0, 3));
+    }
+}
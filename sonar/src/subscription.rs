@@ -4,7 +4,7 @@ use sqlx::prelude::FromRow;
 
 use crate::{
     db::{self, DbC},
-    ListParams, Result, SubscriptionId, Timestamp, UserId,
+    LibraryId, ListParams, Result, SubscriptionId, Timestamp, UserId,
 };
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -29,6 +29,8 @@ pub struct Subscription {
     pub playlist: Option<String>,
     pub external_id: Option<String>,
     pub media_type: Option<SubscriptionMediaType>,
+    /// library downloaded media is added to, the default library if none.
+    pub library: Option<LibraryId>,
 }
 
 #[derive(Debug, Clone)]
@@ -42,6 +44,8 @@ pub struct SubscriptionCreate {
     pub playlist: Option<String>,
     pub external_id: Option<String>,
     pub media_type: Option<SubscriptionMediaType>,
+    /// library downloaded media is added to, the default library if none.
+    pub library: Option<LibraryId>,
 }
 
 #[derive(Debug, FromRow)]
@@ -58,6 +62,7 @@ struct SubscriptionView {
     playlist: Option<String>,
     external_id: Option<String>,
     media_type: Option<String>,
+    library: Option<i64>,
 }
 
 impl std::fmt::Display for SubscriptionMediaType {
@@ -95,6 +100,7 @@ impl From<SubscriptionView> for Subscription {
                 None => None,
                 _ => panic!("database contained invalid subscription media type"),
             },
+            library: value.library.map(LibraryId::from_db),
         }
     }
 }
@@ -117,7 +123,7 @@ pub async fn get(db: &mut DbC, subscription_id: SubscriptionId) -> Result<Subscr
 }
 
 pub async fn create(db: &mut DbC, create: SubscriptionCreate) -> Result<Subscription> {
-    let row=  sqlx::query("INSERT INTO subscription(user, interval_sec, description, artist, album, track, playlist, external_id, media_type) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?) RETURNING *")
+    let row=  sqlx::query("INSERT INTO subscription(user, interval_sec, description, artist, album, track, playlist, external_id, media_type, library) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?) RETURNING *")
         .bind(create.user)
         .bind(create.interval.map(|i| i.as_secs() as i64))
         .bind(create.description)
@@ -127,6 +133,7 @@ pub async fn create(db: &mut DbC, create: SubscriptionCreate) -> Result<Subscrip
         .bind(create.playlist)
         .bind(create.external_id)
         .bind(create.media_type.map(|m| m.to_string()))
+        .bind(create.library)
         .fetch_one(db)
         .await?;
     let view = SubscriptionView::from_row(&row)?;
@@ -0,0 +1,121 @@
+//! Removal of images and blobs that are no longer referenced.
+//! Deleting artists, albums, tracks or playlists does not delete their cover art and older
+//! databases can contain blobs that no audio or image uses, these are left behind in storage.
+//! An image is an orphan if no artist, album, track, playlist or user avatar references it
+//! and a blob is an orphan if no audio or image references it.
+
+use std::{
+    collections::{HashMap, HashSet},
+    time::Duration,
+};
+
+use crate::{db::DbC, image, ImageId, Result};
+
+#[derive(Debug, Clone)]
+pub struct SweepParams {
+    /// Images created less than this long ago are kept since they might not be in use yet.
+    pub min_age: Duration,
+    /// Only report what would be deleted.
+    pub dry_run: bool,
+}
+
+impl Default for SweepParams {
+    fn default() -> Self {
+        Self {
+            min_age: Duration::from_secs(60 * 60),
+            dry_run: false,
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct SweepReport {
+    pub images: Vec<ImageId>,
+    /// Number of blobs removed from storage.
+    pub blobs: u64,
+    /// Total size of the removed blobs.
+    pub reclaimed_bytes: u64,
+}
+
+/// Sweeps the orphans, returning the report along with the keys of the blobs that must be removed
+/// from storage once the transaction commits.
+#[tracing::instrument(skip(db))]
+pub async fn run(db: &mut DbC, params: SweepParams) -> Result<(SweepReport, Vec<String>)> {
+    let orphan_images = sqlx::query_as::<_, (i64, i64)>(
+        "SELECT id, blob FROM image WHERE created_at <= unixepoch() - ?
+        AND NOT EXISTS (SELECT 1 FROM artist WHERE artist.cover_art = image.id)
+        AND NOT EXISTS (SELECT 1 FROM album WHERE album.cover_art = image.id)
+        AND NOT EXISTS (SELECT 1 FROM track WHERE track.cover_art = image.id)
+        AND NOT EXISTS (SELECT 1 FROM playlist WHERE playlist.cover_art = image.id)
+        AND NOT EXISTS (SELECT 1 FROM user WHERE user.avatar = image.id)",
+    )
+    .bind(params.min_age.as_secs() as i64)
+    .fetch_all(&mut *db)
+    .await?;
+
+    // blobs become orphans once every image using them is deleted
+    let mut image_blobs: HashMap<i64, i64> = HashMap::new();
+    for &(_, blob_id) in orphan_images.iter() {
+        *image_blobs.entry(blob_id).or_default() += 1;
+    }
+    let mut blobs: HashSet<i64> = HashSet::new();
+    for (blob_id, orphan_count) in image_blobs {
+        let references = sqlx::query_scalar::<_, i64>(
+            "SELECT (SELECT COUNT(*) FROM image WHERE blob = ?) + (SELECT COUNT(*) FROM audio WHERE blob = ?)",
+        )
+        .bind(blob_id)
+        .bind(blob_id)
+        .fetch_one(&mut *db)
+        .await?;
+        if references == orphan_count {
+            blobs.insert(blob_id);
+        }
+    }
+    blobs.extend(
+        sqlx::query_scalar::<_, i64>(
+            "SELECT id FROM blob
+            WHERE NOT EXISTS (SELECT 1 FROM image WHERE image.blob = blob.id)
+            AND NOT EXISTS (SELECT 1 FROM audio WHERE audio.blob = blob.id)",
+        )
+        .fetch_all(&mut *db)
+        .await?,
+    );
+
+    let mut report = SweepReport {
+        images: orphan_images
+            .iter()
+            .map(|&(image_id, _)| ImageId::from_db(image_id))
+            .collect(),
+        blobs: blobs.len() as u64,
+        reclaimed_bytes: 0,
+    };
+    for &blob_id in blobs.iter() {
+        let size = sqlx::query_scalar::<_, i64>("SELECT size FROM blob WHERE id = ?")
+            .bind(blob_id)
+            .fetch_one(&mut *db)
+            .await?;
+        report.reclaimed_bytes += size as u64;
+    }
+    if params.dry_run {
+        return Ok((report, Vec::new()));
+    }
+
+    let mut keys = Vec::new();
+    for &image_id in report.images.iter() {
+        tracing::info!("sweeping image {}", image_id);
+        keys.extend(image::delete(db, image_id).await?);
+    }
+    for blob_id in blobs {
+        // blobs released by the deleted images are already gone
+        let key = sqlx::query_scalar::<_, String>("DELETE FROM blob WHERE id = ? RETURNING key")
+            .bind(blob_id)
+            .fetch_optional(&mut *db)
+            .await?;
+        if let Some(key) = key {
+            tracing::info!("sweeping blob {}", key);
+            keys.push(key);
+        }
+    }
+
+    Ok((report, keys))
+}
@@ -5,8 +5,8 @@ use bytes::Bytes;
 use crate::{
     bytestream::ByteStream,
     extractor::{ExtractedMetadata, Extractor},
-    Album, AlbumId, Artist, ArtistId, Audio, AudioId, Context, ImageId, Playlist, Track, User,
-    UserId,
+    Album, AlbumId, Artist, ArtistId, Audio, AudioId, Context, ImageId, Library, LibraryId,
+    Playlist, Track, User, UserId,
 };
 
 // https://gist.github.com/scotthaleen/32f76a413e0dfd4b4d79c2a534d49c0b
@@ -343,6 +343,18 @@ pub async fn create_user_with_password(ctx: &Context, username: &str, password:
             password: password.to_owned(),
             avatar: None,
             admin: true,
+            roles: crate::UserRole::DEFAULT.to_vec(),
+        },
+    )
+    .await
+    .unwrap()
+}
+
+pub async fn create_library(ctx: &Context, name: &str) -> Library {
+    crate::library_create(
+        ctx,
+        crate::LibraryCreate {
+            name: name.to_string(),
         },
     )
     .await
@@ -350,6 +362,10 @@ pub async fn create_user_with_password(ctx: &Context, username: &str, password:
 }
 
 pub async fn create_artist(ctx: &Context, name: &str) -> Artist {
+    create_artist_in_library(ctx, LibraryId::DEFAULT, name).await
+}
+
+pub async fn create_artist_in_library(ctx: &Context, library: LibraryId, name: &str) -> Artist {
     crate::artist_create(
         ctx,
         crate::ArtistCreate {
@@ -357,6 +373,7 @@ pub async fn create_artist(ctx: &Context, name: &str) -> Artist {
             cover_art: None,
             genres: Default::default(),
             properties: Default::default(),
+            library: Some(library),
         },
     )
     .await
@@ -368,6 +385,7 @@ pub async fn create_album(ctx: &Context, artist: ArtistId, name: &str) -> Album
         ctx,
         crate::AlbumCreate {
             artist,
+            artists: Vec::new(),
             name: name.to_string(),
             cover_art: None,
             genres: Default::default(),
@@ -414,6 +432,7 @@ pub async fn create_track_with_audio_opt(
         crate::TrackCreate {
             name: name.to_string(),
             album,
+            artists: Vec::new(),
             cover_art: None,
             lyrics: None,
             audio,
@@ -462,6 +481,22 @@ pub async fn create_image(ctx: &Context) -> ImageId {
     .unwrap()
 }
 
+pub async fn storage_list(ctx: &Context) -> Vec<String> {
+    ctx.storage.list().await.unwrap()
+}
+
+/// Returns a new storage key like the ones used for blobs.
+pub fn storage_random_key(prefix: &str) -> String {
+    crate::blob::random_key_with_prefix(prefix)
+}
+
+pub async fn storage_put(ctx: &Context, key: &str, data: &[u8]) {
+    ctx.storage
+        .put(key, Bytes::copy_from_slice(data))
+        .await
+        .unwrap()
+}
+
 pub fn create_simple_genres() -> crate::Genres {
     let mut genres = crate::Genres::default();
     genres.set(&"heavy metal".parse().unwrap());
@@ -5,23 +5,30 @@ use sqlx::{FromRow as _, Row};
 use crate::{
     audio::{self, AudioDownload, AudioStat},
     blob::BlobStorage,
+    credit,
     db::{self, Db, DbC, SonarView},
-    property, AlbumId, ArtistId, AudioId, ByteRange, Error, ErrorKind, Genre, ImageId, ListParams,
-    Properties, PropertyUpdate, Result, SonarId, Timestamp, TrackId, ValueUpdate,
-    ID_NAMESPACE_ARTIST,
+    property, AlbumId, ArtistCredit, ArtistId, AudioId, ByteRange, Error, ErrorKind, Genre,
+    ImageId, LibraryId, ListParams, Properties, PropertyUpdate, Result, SonarId, Timestamp,
+    TrackId, ValueUpdate, ID_NAMESPACE_ARTIST,
 };
 
 #[derive(Debug, Clone)]
 pub struct Track {
     pub id: TrackId,
     pub name: String,
+    /// the first primary artist.
     pub artist: ArtistId,
+    /// every artist credited on the track.
+    /// tracks without a primary artist are credited to the album artists.
+    pub artists: Vec<ArtistCredit>,
     pub album: AlbumId,
     pub duration: Duration,
     pub listen_count: u32,
     pub audio: Option<AudioId>,
     pub cover_art: Option<ImageId>,
     pub properties: Properties,
+    /// the library of `album`.
+    pub library: LibraryId,
     pub created_at: Timestamp,
 }
 
@@ -34,6 +41,8 @@ pub struct TrackLyrics {
 pub struct TrackCreate {
     pub name: String,
     pub album: AlbumId,
+    /// artists credited on the track, if there is no primary artist the album artists are used.
+    pub artists: Vec<ArtistCredit>,
     pub cover_art: Option<ImageId>,
     pub lyrics: Option<TrackLyrics>,
     pub audio: Option<AudioId>,
@@ -45,6 +54,7 @@ impl std::fmt::Debug for TrackCreate {
         f.debug_struct("TrackCreate")
             .field("name", &self.name)
             .field("album", &self.album)
+            .field("artists", &self.artists)
             .field("cover_art", &self.cover_art)
             .field("lyrics", &self.lyrics)
             .finish()
@@ -55,6 +65,8 @@ impl std::fmt::Debug for TrackCreate {
 pub struct TrackUpdate {
     pub name: ValueUpdate<String>,
     pub album: ValueUpdate<AlbumId>,
+    /// unsetting the artists credits the track to the album artists.
+    pub artists: ValueUpdate<Vec<ArtistCredit>>,
     pub cover_art: ValueUpdate<ImageId>,
     pub lyrics: ValueUpdate<TrackLyrics>,
     pub properties: Vec<PropertyUpdate>,
@@ -96,6 +108,7 @@ struct TrackView {
     audio: Option<i64>,
     listen_count: Option<i64>,
     cover_art: Option<i64>,
+    library: i64,
     created_at: i64,
 }
 
@@ -111,27 +124,41 @@ impl From<(TrackView, Properties)> for Track {
             id: TrackId::from_db(value.id),
             name: value.name,
             artist: ArtistId::from_db(value.artist),
+            artists: vec![ArtistCredit::primary(ArtistId::from_db(value.artist))],
             album: AlbumId::from_db(value.album),
             duration: Duration::from_millis(value.duration_ms.unwrap_or_default() as u64),
             audio: value.audio.map(AudioId::from_db),
             listen_count: value.listen_count.unwrap_or_default() as u32,
             cover_art: value.cover_art.map(ImageId::from_db),
             properties,
+            library: LibraryId::from_db(value.library),
             created_at: Timestamp::from_seconds(value.created_at as u64),
         }
     }
 }
 
 #[tracing::instrument(skip(db))]
-pub async fn list(db: &mut DbC, params: ListParams) -> Result<Vec<Track>> {
-    let views = db::list::<TrackView>(db, "sqlx_track", params).await?;
+pub async fn list(
+    db: &mut DbC,
+    libraries: Option<&[LibraryId]>,
+    params: ListParams,
+) -> Result<Vec<Track>> {
+    let views = match libraries {
+        Some(libraries) => {
+            db::list_where_field_in::<TrackView, _>(db, "sqlx_track", "library", libraries, params)
+                .await?
+        }
+        None => db::list::<TrackView>(db, "sqlx_track", params).await?,
+    };
     let properties =
         property::get_bulk(db, views.iter().map(|view| TrackId::from_db(view.id))).await?;
-    Ok(views
+    let mut tracks = views
         .into_iter()
         .zip(properties.into_iter())
         .map(Track::from)
-        .collect())
+        .collect::<Vec<_>>();
+    credit::fill_tracks(db, &mut tracks).await?;
+    Ok(tracks)
 }
 
 #[tracing::instrument(skip(db))]
@@ -145,19 +172,39 @@ pub async fn list_ids(db: &mut DbC) -> Result<Vec<TrackId>> {
 #[tracing::instrument(skip(db))]
 pub async fn list_by_album(
     db: &mut DbC,
+    libraries: Option<&[LibraryId]>,
     album_id: AlbumId,
     params: ListParams,
 ) -> Result<Vec<Track>> {
-    let views =
-        db::list_where_field_eq::<TrackView, _>(db, "sqlx_track", "album", album_id, params)
-            .await?;
+    let views = match libraries {
+        Some(libraries) => {
+            let (offset, limit) = params.to_db_offset_limit();
+            let mut query =
+                sqlx::QueryBuilder::new("SELECT * FROM sqlx_track WHERE album = ? AND library IN ");
+            db::query_builder_push_id_tuple(&mut query, libraries);
+            query
+                .push("ORDER BY id ASC LIMIT ? OFFSET ?")
+                .build_query_as::<TrackView>()
+                .bind(album_id)
+                .bind(limit)
+                .bind(offset)
+                .fetch_all(&mut *db)
+                .await?
+        }
+        None => {
+            db::list_where_field_eq::<TrackView, _>(db, "sqlx_track", "album", album_id, params)
+                .await?
+        }
+    };
     let properties =
         property::get_bulk(db, views.iter().map(|view| TrackId::from_db(view.id))).await?;
-    Ok(views
+    let mut tracks = views
         .into_iter()
         .zip(properties.into_iter())
         .map(Track::from)
-        .collect())
+        .collect::<Vec<_>>();
+    credit::fill_tracks(db, &mut tracks).await?;
+    Ok(tracks)
 }
 
 #[tracing::instrument(skip(db))]
@@ -182,15 +229,20 @@ pub async fn list_top_from_artist(
 ) -> Result<Vec<Track>> {
     let mut query = sqlx::QueryBuilder::<sqlx::Sqlite>::new("");
     query.push("SELECT * FROM sqlx_track");
-    query.push(" WHERE artist = ");
+    query.push(" WHERE id IN (SELECT track FROM view_track_credit WHERE artist = ");
     query.push_bind(artist_id);
+    query.push(")");
     query.push(" ORDER BY listen_count DESC");
     query.push(params.sql_display());
     tracks_from_views_query(db, query).await
 }
 
 #[tracing::instrument(skip(db))]
-pub async fn list_random(db: &mut DbC, params: TrackListRandom) -> Result<Vec<Track>> {
+pub async fn list_random(
+    db: &mut DbC,
+    libraries: Option<&[LibraryId]>,
+    params: TrackListRandom,
+) -> Result<Vec<Track>> {
     let mut query = sqlx::QueryBuilder::<sqlx::Sqlite>::new("SELECT * FROM sqlx_track ");
 
     if let Some(genre) = params.genre {
@@ -201,6 +253,13 @@ pub async fn list_random(db: &mut DbC, params: TrackListRandom) -> Result<Vec<Tr
         query.push(" WHERE genre.genre = '");
         query.push(genre);
         query.push("' ");
+        if let Some(libraries) = libraries {
+            query.push("AND sqlx_track.library IN ");
+            db::query_builder_push_id_tuple(&mut query, libraries);
+        }
+    } else if let Some(libraries) = libraries {
+        query.push("WHERE sqlx_track.library IN ");
+        db::query_builder_push_id_tuple(&mut query, libraries);
     }
 
     query.push("ORDER BY RANDOM() ");
@@ -219,7 +278,9 @@ pub async fn get(db: &mut DbC, track_id: TrackId) -> Result<Track> {
         .fetch_one(&mut *db)
         .await?;
     let properties = property::get(db, track_id).await?;
-    Ok(Track::from((track_view, properties)))
+    let mut track = Track::from((track_view, properties));
+    credit::fill_tracks(db, std::slice::from_mut(&mut track)).await?;
+    Ok(track)
 }
 
 #[tracing::instrument(skip(db))]
@@ -227,7 +288,9 @@ pub async fn get_bulk(db: &mut DbC, track_ids: &[TrackId]) -> Result<Vec<Track>>
     let views = db::list_bulk::<TrackView, _>(db, "sqlx_track", track_ids).await?;
     let expanded = db::expand_views(views, track_ids);
     let properties = property::get_bulk(db, track_ids.iter().copied()).await?;
-    Ok(db::merge_view_properties(expanded, properties))
+    let mut tracks = db::merge_view_properties(expanded, properties);
+    credit::fill_tracks(db, &mut tracks).await?;
+    Ok(tracks)
 }
 
 #[tracing::instrument(skip(db))]
@@ -248,16 +311,18 @@ pub async fn get_by_name(db: &mut DbC, name: &str) -> Result<Track> {
 pub async fn create(db: &mut DbC, create: TrackCreate) -> Result<Track> {
     let cover_art = create.cover_art.map(|id| id.to_db());
     let track_id = sqlx::query_scalar(
-        "INSERT INTO track (name, album, cover_art)
-        VALUES (?, ?, ?) RETURNING id",
+        "INSERT INTO track (name, album, cover_art, library)
+        VALUES (?, ?, ?, (SELECT library FROM album WHERE id = ?)) RETURNING id",
     )
     .bind(create.name)
     .bind(create.album)
     .bind(cover_art)
+    .bind(create.album)
     .fetch_one(&mut *db)
     .await?;
 
     let track_id = TrackId::from_db(track_id);
+    credit::track_set(db, track_id, &create.artists).await?;
     property::set(db, track_id, &create.properties).await?;
     if let Some(audio_id) = create.audio {
         audio::set_preferred(db, audio_id, track_id).await?;
@@ -275,8 +340,27 @@ pub async fn update(db: &mut DbC, track_id: TrackId, update: TrackUpdate) -> Res
     tracing::info!("updating track {} with {:#?}", track_id, update);
 
     db::value_update_string_non_null(db, "track", "name", track_id, update.name).await?;
+    let moved_to = match update.album {
+        ValueUpdate::Set(album_id) => Some(album_id),
+        _ => None,
+    };
     db::value_update_id_non_null(db, "track", "album", track_id, update.album).await?;
+    if let Some(album_id) = moved_to {
+        // tracks are in the library of their album
+        sqlx::query(
+            "UPDATE track SET library = (SELECT library FROM album WHERE id = ?) WHERE id = ?",
+        )
+        .bind(album_id)
+        .bind(track_id)
+        .execute(&mut *db)
+        .await?;
+    }
     db::value_update_id_nullable(db, "track", "cover_art", track_id, update.cover_art).await?;
+    match update.artists {
+        ValueUpdate::Set(artists) => credit::track_set(db, track_id, &artists).await?,
+        ValueUpdate::Unset => credit::track_clear(db, track_id).await?,
+        ValueUpdate::Unchanged => {}
+    }
     match update.lyrics {
         ValueUpdate::Set(lyrics) => set_lyrics(db, track_id, lyrics).await?,
         ValueUpdate::Unset => clear_lyrics(db, track_id).await?,
@@ -299,6 +383,7 @@ pub async fn delete(db: &mut DbC, track_id: TrackId) -> Result<()> {
         .bind(track_id)
         .execute(&mut *db)
         .await?;
+    credit::track_clear(db, track_id).await?;
     sqlx::query("DELETE FROM track WHERE id = ?")
         .bind(track_id)
         .execute(&mut *db)
@@ -464,5 +549,7 @@ async fn tracks_from_views_query(
     }
     let expanded = db::expand_views(views, &ids);
     let properties = property::get_bulk(db, ids.iter().copied()).await?;
-    Ok(db::merge_view_properties(expanded, properties))
+    let mut tracks = db::merge_view_properties(expanded, properties);
+    credit::fill_tracks(db, &mut tracks).await?;
+    Ok(tracks)
 }
@@ -0,0 +1,294 @@
+use std::str::FromStr;
+
+use rand::Rng;
+
+use crate::{db::DbC, ApiKeyId, Error, ErrorKind, Result, Timestamp, UserId};
+
+const API_KEY_PREFIX: &str = "sonar_";
+const API_KEY_ALPHABET: &[u8] = b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789";
+const API_KEY_LENGTH: usize = 40;
+
+#[derive(Debug)]
+pub struct InvalidApiKeyScopeError;
+
+impl std::fmt::Display for InvalidApiKeyScopeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "invalid api key scope")
+    }
+}
+
+impl std::error::Error for InvalidApiKeyScopeError {}
+
+/// Restricts what an api key can be used for.
+/// Keys without a scope have the same access as the user, except for admin operations.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ApiKeyScope {
+    ReadOnly,
+    Streaming,
+    Admin,
+}
+
+impl ApiKeyScope {
+    pub fn allows(&self, access: ApiKeyAccess) -> bool {
+        match self {
+            ApiKeyScope::ReadOnly => {
+                std::matches!(access, ApiKeyAccess::Stream | ApiKeyAccess::Read)
+            }
+            ApiKeyScope::Streaming => access == ApiKeyAccess::Stream,
+            ApiKeyScope::Admin => true,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ApiKeyScope::ReadOnly => "read-only",
+            ApiKeyScope::Streaming => "streaming",
+            ApiKeyScope::Admin => "admin",
+        }
+    }
+}
+
+impl std::fmt::Display for ApiKeyScope {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for ApiKeyScope {
+    type Err = InvalidApiKeyScopeError;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "read-only" => Ok(ApiKeyScope::ReadOnly),
+            "streaming" => Ok(ApiKeyScope::Streaming),
+            "admin" => Ok(ApiKeyScope::Admin),
+            _ => Err(InvalidApiKeyScopeError),
+        }
+    }
+}
+
+/// The kind of access an operation requires.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ApiKeyAccess {
+    /// Downloading or streaming media.
+    Stream,
+    /// Reading metadata.
+    Read,
+    /// Creating, updating or deleting anything.
+    Write,
+    /// Operations that require an admin user.
+    Admin,
+}
+
+#[derive(Debug, Clone)]
+pub struct ApiKey {
+    pub id: ApiKeyId,
+    pub user: UserId,
+    pub name: String,
+    pub scope: Option<ApiKeyScope>,
+    pub created_at: Timestamp,
+    pub last_used_at: Option<Timestamp>,
+}
+
+impl ApiKey {
+    pub fn allows(&self, access: ApiKeyAccess) -> bool {
+        match self.scope {
+            Some(scope) => scope.allows(access),
+            None => access != ApiKeyAccess::Admin,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ApiKeyCreate {
+    pub user: UserId,
+    pub name: String,
+    pub scope: Option<ApiKeyScope>,
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct ApiKeyView {
+    id: i64,
+    user: i64,
+    name: String,
+    scope: Option<String>,
+    created_at: i64,
+    last_used_at: Option<i64>,
+}
+
+impl From<ApiKeyView> for ApiKey {
+    fn from(value: ApiKeyView) -> Self {
+        Self {
+            id: ApiKeyId::from_db(value.id),
+            user: UserId::from_db(value.user),
+            name: value.name,
+            scope: value
+                .scope
+                .map(|s| s.parse().expect("invalid api key scope in database")),
+            created_at: Timestamp::from_seconds(value.created_at as u64),
+            last_used_at: value
+                .last_used_at
+                .map(|v| Timestamp::from_seconds(v as u64)),
+        }
+    }
+}
+
+#[tracing::instrument(skip(db))]
+pub async fn api_key_list(db: &mut DbC, user_id: UserId) -> Result<Vec<ApiKey>> {
+    let views = sqlx::query_as::<_, ApiKeyView>(
+        "SELECT id, user, name, scope, created_at, last_used_at FROM user_api_key WHERE user = ? ORDER BY id ASC",
+    )
+    .bind(user_id)
+    .fetch_all(db)
+    .await?;
+    Ok(views.into_iter().map(ApiKey::from).collect())
+}
+
+#[tracing::instrument(skip(db))]
+pub async fn api_key_get(db: &mut DbC, key_id: ApiKeyId) -> Result<ApiKey> {
+    let view = sqlx::query_as::<_, ApiKeyView>(
+        "SELECT id, user, name, scope, created_at, last_used_at FROM user_api_key WHERE id = ?",
+    )
+    .bind(key_id)
+    .fetch_optional(db)
+    .await?;
+    match view {
+        Some(view) => Ok(ApiKey::from(view)),
+        None => Err(Error::new(ErrorKind::NotFound, "api key not found")),
+    }
+}
+
+/// Creates a new api key.
+/// Returns the key metadata and the key itself, which is not stored and can't be recovered.
+#[tracing::instrument(skip(db))]
+pub async fn api_key_create(db: &mut DbC, create: ApiKeyCreate) -> Result<(ApiKey, String)> {
+    if create.name.is_empty() {
+        return Err(Error::new(
+            ErrorKind::Invalid,
+            "api key name cannot be empty",
+        ));
+    }
+
+    let exists = sqlx::query_scalar::<_, i64>(
+        "SELECT COUNT(*) FROM user_api_key WHERE user = ? AND name = ?",
+    )
+    .bind(create.user)
+    .bind(&create.name)
+    .fetch_one(&mut *db)
+    .await?;
+    if exists > 0 {
+        return Err(Error::new(
+            ErrorKind::Invalid,
+            "an api key with that name already exists",
+        ));
+    }
+
+    let key = generate_key();
+    let key_id = sqlx::query_scalar(
+        "INSERT INTO user_api_key (user, name, key_hash, scope) VALUES (?, ?, ?, ?) RETURNING id",
+    )
+    .bind(create.user)
+    .bind(&create.name)
+    .bind(key_hash(&key))
+    .bind(create.scope.map(|s| s.as_str()))
+    .fetch_one(&mut *db)
+    .await?;
+    let api_key = api_key_get(db, ApiKeyId::from_db(key_id)).await?;
+    Ok((api_key, key))
+}
+
+/// Validates the key and updates its last used timestamp.
+#[tracing::instrument(skip(db, key))]
+pub async fn api_key_validate(db: &mut DbC, key: &str) -> Result<ApiKey> {
+    if !key.starts_with(API_KEY_PREFIX) {
+        return Err(Error::new(ErrorKind::Unauthorized, "invalid api key"));
+    }
+
+    let key_id = sqlx::query_scalar::<_, i64>("SELECT id FROM user_api_key WHERE key_hash = ?")
+        .bind(key_hash(key))
+        .fetch_optional(&mut *db)
+        .await?;
+    let key_id = match key_id {
+        Some(key_id) => key_id,
+        None => return Err(Error::new(ErrorKind::Unauthorized, "invalid api key")),
+    };
+
+    sqlx::query("UPDATE user_api_key SET last_used_at = ? WHERE id = ?")
+        .bind(Timestamp::now().seconds() as i64)
+        .bind(key_id)
+        .execute(&mut *db)
+        .await?;
+
+    api_key_get(db, ApiKeyId::from_db(key_id)).await
+}
+
+#[tracing::instrument(skip(db))]
+pub async fn api_key_delete(db: &mut DbC, key_id: ApiKeyId) -> Result<()> {
+    let result = sqlx::query("DELETE FROM user_api_key WHERE id = ?")
+        .bind(key_id)
+        .execute(db)
+        .await?;
+    if result.rows_affected() == 0 {
+        return Err(Error::new(ErrorKind::NotFound, "api key not found"));
+    }
+    Ok(())
+}
+
+#[tracing::instrument(skip(db))]
+pub async fn api_key_delete_all(db: &mut DbC, user_id: UserId) -> Result<()> {
+    sqlx::query("DELETE FROM user_api_key WHERE user = ?")
+        .bind(user_id)
+        .execute(db)
+        .await?;
+    Ok(())
+}
+
+fn generate_key() -> String {
+    let mut rng = rand::thread_rng();
+    let mut key = String::with_capacity(API_KEY_PREFIX.len() + API_KEY_LENGTH);
+    key.push_str(API_KEY_PREFIX);
+    for _ in 0..API_KEY_LENGTH {
+        let idx = rng.gen_range(0..API_KEY_ALPHABET.len());
+        key.push(API_KEY_ALPHABET[idx] as char);
+    }
+    key
+}
+
+fn key_hash(key: &str) -> String {
+    use sha2::{Digest, Sha256};
+    let hash = <Sha256 as Digest>::digest(key.as_bytes());
+    hex::encode(hash)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_api_key_scope_from_str() {
+        for scope in [
+            ApiKeyScope::ReadOnly,
+            ApiKeyScope::Streaming,
+            ApiKeyScope::Admin,
+        ] {
+            assert_eq!(scope.as_str().parse::<ApiKeyScope>().unwrap(), scope);
+        }
+        assert!("invalid".parse::<ApiKeyScope>().is_err());
+    }
+
+    #[test]
+    fn test_api_key_scope_allows() {
+        assert!(ApiKeyScope::Streaming.allows(ApiKeyAccess::Stream));
+        assert!(!ApiKeyScope::Streaming.allows(ApiKeyAccess::Read));
+        assert!(ApiKeyScope::ReadOnly.allows(ApiKeyAccess::Read));
+        assert!(!ApiKeyScope::ReadOnly.allows(ApiKeyAccess::Write));
+        assert!(ApiKeyScope::Admin.allows(ApiKeyAccess::Admin));
+    }
+
+    #[test]
+    fn test_generate_key() {
+        let key = generate_key();
+        assert!(key.starts_with(API_KEY_PREFIX));
+        assert_eq!(key.len(), API_KEY_PREFIX.len() + API_KEY_LENGTH);
+    }
+}
@@ -1,6 +1,6 @@
 use crate::{
     db::{self, DbC},
-    Error, ErrorKind, ImageId, ListParams, Result, UserId, ValueUpdate,
+    library, Error, ErrorKind, ImageId, LibraryId, ListParams, Result, UserId, ValueUpdate,
 };
 
 mod username;
@@ -10,6 +10,18 @@ pub use username::*;
 mod token;
 pub use token::*;
 
+mod session;
+pub use session::*;
+
+mod api_key;
+pub use api_key::*;
+
+mod subsonic;
+pub use subsonic::*;
+
+mod role;
+pub use role::*;
+
 const PASSWORD_MIN_LENGTH: usize = 8;
 const PASSWORD_MAX_LENGTH: usize = 48;
 
@@ -19,6 +31,14 @@ pub struct User {
     pub username: Username,
     pub avatar: Option<ImageId>,
     pub admin: bool,
+    pub roles: Vec<UserRole>,
+}
+
+impl User {
+    /// Whether the user has the given role, admins have every role.
+    pub fn has_role(&self, role: UserRole) -> bool {
+        self.admin || self.roles.contains(&role)
+    }
 }
 
 #[derive(Clone)]
@@ -27,6 +47,7 @@ pub struct UserCreate {
     pub password: String,
     pub avatar: Option<ImageId>,
     pub admin: bool,
+    pub roles: Vec<UserRole>,
 }
 
 impl std::fmt::Debug for UserCreate {
@@ -36,6 +57,7 @@ impl std::fmt::Debug for UserCreate {
             .field("password", &"****")
             .field("avatar", &self.avatar)
             .field("admin", &self.admin)
+            .field("roles", &self.roles)
             .finish()
     }
 }
@@ -45,6 +67,8 @@ pub struct UserUpdate {
     pub password: ValueUpdate<String>,
     pub avatar: ValueUpdate<ImageId>,
     pub admin: ValueUpdate<bool>,
+    /// Replaces all roles of the user.
+    pub roles: ValueUpdate<Vec<UserRole>>,
 }
 
 impl std::fmt::Debug for UserUpdate {
@@ -53,6 +77,7 @@ impl std::fmt::Debug for UserUpdate {
             .field("password", &"****")
             .field("avatar", &self.avatar)
             .field("admin", &self.admin)
+            .field("roles", &self.roles)
             .finish()
     }
 }
@@ -63,6 +88,7 @@ struct UserView {
     username: String,
     avatar: Option<i64>,
     admin: bool,
+    roles: String,
 }
 
 impl From<UserView> for User {
@@ -72,6 +98,7 @@ impl From<UserView> for User {
             username: Username::new_uncheked(value.username),
             avatar: value.avatar.map(ImageId::from_db),
             admin: value.admin,
+            roles: roles_from_db(&value.roles),
         }
     }
 }
@@ -90,26 +117,31 @@ pub async fn create(db: &mut DbC, create: UserCreate) -> Result<User> {
     let avatar_id = create.avatar.map(|id| id.to_db());
     let user_id = sqlx::query_scalar(
         r#"
-INSERT INTO user (username, password_hash, avatar, admin)
-VALUES (?, ?, ?, ?) RETURNING id
+INSERT INTO user (username, password_hash, avatar, admin, roles)
+VALUES (?, ?, ?, ?, ?) RETURNING id
 "#,
     )
     .bind(username)
     .bind(password_hash)
     .bind(avatar_id)
     .bind(create.admin)
+    .bind(roles_to_db(&create.roles))
     .fetch_one(&mut *db)
     .await?;
-    get(db, UserId::from_db(user_id)).await
+    let user_id = UserId::from_db(user_id);
+    // new users have access to the default library
+    library::grant(db, LibraryId::DEFAULT, user_id).await?;
+    get(db, user_id).await
 }
 
 #[tracing::instrument(skip(db))]
 pub async fn get(db: &mut DbC, user_id: UserId) -> Result<User> {
-    let user_view =
-        sqlx::query_as::<_, UserView>("SELECT id, username, avatar, admin FROM user WHERE id = ?")
-            .bind(user_id)
-            .fetch_one(db)
-            .await?;
+    let user_view = sqlx::query_as::<_, UserView>(
+        "SELECT id, username, avatar, admin, roles FROM user WHERE id = ?",
+    )
+    .bind(user_id)
+    .fetch_one(db)
+    .await?;
     Ok(User::from(user_view))
 }
 
@@ -129,6 +161,12 @@ pub async fn update(db: &mut DbC, user_id: UserId, update: UserUpdate) -> Result
     db::value_update_string_non_null(db, "user", "password_hash", user_id, password_update).await?;
     db::value_update_id_nullable(db, "user", "avatar", user_id, update.avatar).await?;
     db::value_update_bool_non_null(db, "user", "admin", user_id, update.admin).await?;
+    let roles_update = match update.roles {
+        ValueUpdate::Set(roles) => ValueUpdate::Set(roles_to_db(&roles)),
+        ValueUpdate::Unset => ValueUpdate::Unset,
+        ValueUpdate::Unchanged => ValueUpdate::Unchanged,
+    };
+    db::value_update_string_non_null(db, "user", "roles", user_id, roles_update).await?;
 
     get(db, user_id).await
 }
@@ -144,6 +182,9 @@ pub async fn lookup(db: &mut DbC, username: &Username) -> Result<Option<UserId>>
 
 #[tracing::instrument(skip(db))]
 pub async fn delete(db: &mut DbC, user_id: UserId) -> Result<()> {
+    session_delete_all(db, user_id).await?;
+    api_key_delete_all(db, user_id).await?;
+    subsonic_password_set(db, user_id, None).await?;
     sqlx::query("DELETE FROM user WHERE id = ?")
         .bind(user_id)
         .execute(db)
@@ -0,0 +1,126 @@
+use std::str::FromStr;
+
+#[derive(Debug)]
+pub struct InvalidUserRoleError {
+    value: String,
+}
+
+impl InvalidUserRoleError {
+    fn new(value: impl Into<String>) -> Self {
+        Self {
+            value: value.into(),
+        }
+    }
+}
+
+impl std::fmt::Display for InvalidUserRoleError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "invalid user role '{}'", self.value)
+    }
+}
+
+impl std::error::Error for InvalidUserRoleError {}
+
+/// A capability granted to a user.
+/// Admins have every role.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum UserRole {
+    /// Downloading the original audio files.
+    Download,
+    /// Streaming audio.
+    Stream,
+    /// Uploading and importing new media.
+    Upload,
+    /// Creating and editing artists, albums, tracks and their images.
+    EditMetadata,
+    /// Creating and submitting external media subscriptions.
+    ManageSubscriptions,
+    /// Deleting artists, albums, tracks and images.
+    Delete,
+    /// Sharing media with other people, checked by the OpenSubsonic share endpoints.
+    Share,
+}
+
+impl UserRole {
+    pub const ALL: &'static [UserRole] = &[
+        UserRole::Download,
+        UserRole::Stream,
+        UserRole::Upload,
+        UserRole::EditMetadata,
+        UserRole::ManageSubscriptions,
+        UserRole::Delete,
+        UserRole::Share,
+    ];
+
+    /// Roles of users created without explicit roles.
+    pub const DEFAULT: &'static [UserRole] = &[UserRole::Download, UserRole::Stream];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            UserRole::Download => "download",
+            UserRole::Stream => "stream",
+            UserRole::Upload => "upload",
+            UserRole::EditMetadata => "edit-metadata",
+            UserRole::ManageSubscriptions => "manage-subscriptions",
+            UserRole::Delete => "delete",
+            UserRole::Share => "share",
+        }
+    }
+}
+
+impl std::fmt::Display for UserRole {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for UserRole {
+    type Err = InvalidUserRoleError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        UserRole::ALL
+            .iter()
+            .find(|role| role.as_str() == s)
+            .copied()
+            .ok_or_else(|| InvalidUserRoleError::new(s))
+    }
+}
+
+pub(super) fn roles_to_db(roles: &[UserRole]) -> String {
+    let mut roles = roles.iter().map(|role| role.as_str()).collect::<Vec<_>>();
+    roles.sort_unstable();
+    roles.dedup();
+    roles.join(",")
+}
+
+pub(super) fn roles_from_db(roles: &str) -> Vec<UserRole> {
+    roles
+        .split(',')
+        .filter(|role| !role.is_empty())
+        .map(|role| role.parse().expect("invalid user role in database"))
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_user_role_roundtrip() {
+        for role in UserRole::ALL {
+            assert_eq!(role.as_str().parse::<UserRole>().unwrap(), *role);
+        }
+        assert!("jukebox".parse::<UserRole>().is_err());
+    }
+
+    #[test]
+    fn test_roles_db_roundtrip() {
+        let roles = roles_to_db(&[UserRole::Stream, UserRole::Delete, UserRole::Stream]);
+        assert_eq!(roles, "delete,stream");
+        assert_eq!(
+            roles_from_db(&roles),
+            vec![UserRole::Delete, UserRole::Stream]
+        );
+        assert!(roles_from_db("").is_empty());
+    }
+}
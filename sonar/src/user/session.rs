@@ -0,0 +1,169 @@
+use std::time::Duration;
+
+use crate::{db::DbC, Error, ErrorKind, Result, Timestamp, UserId, UserSessionId};
+
+use super::UserToken;
+
+/// A user session is created every time a user logs in.
+/// Only the hash of the session token is stored in the database.
+#[derive(Debug, Clone)]
+pub struct UserSession {
+    pub id: UserSessionId,
+    pub user: UserId,
+    pub device: Option<String>,
+    pub created_at: Timestamp,
+    pub last_used_at: Timestamp,
+    pub expires_at: Option<Timestamp>,
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct UserLoginParams {
+    /// label describing the device or client that created the session.
+    pub device: Option<String>,
+    /// how long the session is valid for. sessions without expiration are valid until revoked.
+    pub expires_in: Option<Duration>,
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct UserSessionView {
+    id: i64,
+    user: i64,
+    device: Option<String>,
+    created_at: i64,
+    last_used_at: i64,
+    expires_at: Option<i64>,
+}
+
+impl From<UserSessionView> for UserSession {
+    fn from(value: UserSessionView) -> Self {
+        Self {
+            id: UserSessionId::from_db(value.id),
+            user: UserId::from_db(value.user),
+            device: value.device,
+            created_at: Timestamp::from_seconds(value.created_at as u64),
+            last_used_at: Timestamp::from_seconds(value.last_used_at as u64),
+            expires_at: value.expires_at.map(|v| Timestamp::from_seconds(v as u64)),
+        }
+    }
+}
+
+#[tracing::instrument(skip(db))]
+pub async fn session_list(db: &mut DbC, user_id: UserId) -> Result<Vec<UserSession>> {
+    let views = sqlx::query_as::<_, UserSessionView>(
+        "SELECT id, user, device, created_at, last_used_at, expires_at FROM user_session WHERE user = ? ORDER BY id ASC",
+    )
+    .bind(user_id)
+    .fetch_all(db)
+    .await?;
+    Ok(views.into_iter().map(UserSession::from).collect())
+}
+
+#[tracing::instrument(skip(db))]
+pub async fn session_get(db: &mut DbC, session_id: UserSessionId) -> Result<UserSession> {
+    let view = sqlx::query_as::<_, UserSessionView>(
+        "SELECT id, user, device, created_at, last_used_at, expires_at FROM user_session WHERE id = ?",
+    )
+    .bind(session_id)
+    .fetch_optional(db)
+    .await?;
+    match view {
+        Some(view) => Ok(UserSession::from(view)),
+        None => Err(Error::new(ErrorKind::NotFound, "session not found")),
+    }
+}
+
+#[tracing::instrument(skip(db, token))]
+pub async fn session_create(
+    db: &mut DbC,
+    user_id: UserId,
+    token: &UserToken,
+    params: UserLoginParams,
+) -> Result<UserSession> {
+    let token_hash = token_hash(token);
+    let expires_at = params
+        .expires_in
+        .map(|d| (Timestamp::now().seconds() + d.as_secs()) as i64);
+    let session_id = sqlx::query_scalar(
+        "INSERT INTO user_session (user, token_hash, device, expires_at) VALUES (?, ?, ?, ?) RETURNING id",
+    )
+    .bind(user_id)
+    .bind(token_hash)
+    .bind(params.device)
+    .bind(expires_at)
+    .fetch_one(&mut *db)
+    .await?;
+    session_get(db, UserSessionId::from_db(session_id)).await
+}
+
+/// Validates the token and updates the session's last used timestamp.
+/// Expired sessions are removed.
+#[tracing::instrument(skip(db, token))]
+pub async fn session_validate(db: &mut DbC, token: &UserToken) -> Result<UserId> {
+    let token_hash = token_hash(token);
+    let row = sqlx::query_as::<_, (i64, i64, Option<i64>)>(
+        "SELECT id, user, expires_at FROM user_session WHERE token_hash = ?",
+    )
+    .bind(&token_hash)
+    .fetch_optional(&mut *db)
+    .await?;
+
+    let (session_id, user_id, expires_at) = match row {
+        Some(row) => row,
+        None => return Err(Error::new(ErrorKind::Unauthorized, "invalid user token")),
+    };
+
+    let now = Timestamp::now().seconds() as i64;
+    if let Some(expires_at) = expires_at
+        && expires_at <= now
+    {
+        sqlx::query("DELETE FROM user_session WHERE id = ?")
+            .bind(session_id)
+            .execute(&mut *db)
+            .await?;
+        return Err(Error::new(ErrorKind::Unauthorized, "user session expired"));
+    }
+
+    sqlx::query("UPDATE user_session SET last_used_at = ? WHERE id = ?")
+        .bind(now)
+        .bind(session_id)
+        .execute(&mut *db)
+        .await?;
+
+    Ok(UserId::from_db(user_id))
+}
+
+#[tracing::instrument(skip(db, token))]
+pub async fn session_delete_by_token(db: &mut DbC, token: &UserToken) -> Result<()> {
+    sqlx::query("DELETE FROM user_session WHERE token_hash = ?")
+        .bind(token_hash(token))
+        .execute(db)
+        .await?;
+    Ok(())
+}
+
+#[tracing::instrument(skip(db))]
+pub async fn session_delete(db: &mut DbC, session_id: UserSessionId) -> Result<()> {
+    let result = sqlx::query("DELETE FROM user_session WHERE id = ?")
+        .bind(session_id)
+        .execute(db)
+        .await?;
+    if result.rows_affected() == 0 {
+        return Err(Error::new(ErrorKind::NotFound, "session not found"));
+    }
+    Ok(())
+}
+
+#[tracing::instrument(skip(db))]
+pub async fn session_delete_all(db: &mut DbC, user_id: UserId) -> Result<()> {
+    sqlx::query("DELETE FROM user_session WHERE user = ?")
+        .bind(user_id)
+        .execute(db)
+        .await?;
+    Ok(())
+}
+
+fn token_hash(token: &UserToken) -> String {
+    use sha2::{Digest, Sha256};
+    let hash = <Sha256 as Digest>::digest(token.as_str().as_bytes());
+    hex::encode(hash)
+}
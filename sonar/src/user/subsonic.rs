@@ -0,0 +1,105 @@
+use crate::{db::DbC, Error, ErrorKind, Result, UserId};
+
+use super::validate_password;
+
+/// Checks if the user has a subsonic password, which is required for token authentication.
+#[tracing::instrument(skip(db))]
+pub async fn subsonic_password_enabled(db: &mut DbC, user_id: UserId) -> Result<bool> {
+    let count =
+        sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM user_subsonic_password WHERE user = ?")
+            .bind(user_id)
+            .fetch_one(db)
+            .await?;
+    Ok(count > 0)
+}
+
+/// Sets the user's subsonic password or, if `None`, disables token authentication for the user.
+#[tracing::instrument(skip(db, password))]
+pub async fn subsonic_password_set(
+    db: &mut DbC,
+    user_id: UserId,
+    password: Option<&str>,
+) -> Result<()> {
+    match password {
+        Some(password) => {
+            validate_password(password)?;
+            sqlx::query(
+                "INSERT INTO user_subsonic_password (user, password) VALUES (?, ?) ON CONFLICT (user) DO UPDATE SET password = excluded.password",
+            )
+            .bind(user_id)
+            .bind(password)
+            .execute(db)
+            .await?;
+        }
+        None => {
+            sqlx::query("DELETE FROM user_subsonic_password WHERE user = ?")
+                .bind(user_id)
+                .execute(db)
+                .await?;
+        }
+    }
+    Ok(())
+}
+
+/// Verifies a subsonic token, computed as `md5(password + salt)`.
+#[tracing::instrument(skip(db, token, salt))]
+pub async fn subsonic_authenticate(
+    db: &mut DbC,
+    user_id: UserId,
+    token: &str,
+    salt: &str,
+) -> Result<()> {
+    let password = sqlx::query_scalar::<_, String>(
+        "SELECT password FROM user_subsonic_password WHERE user = ?",
+    )
+    .bind(user_id)
+    .fetch_optional(db)
+    .await?;
+    let password = match password {
+        Some(password) => password,
+        None => {
+            return Err(Error::new(
+                ErrorKind::Unauthorized,
+                "token authentication is not enabled for this user",
+            ))
+        }
+    };
+
+    let expected = subsonic_token(&password, salt);
+    if !constant_time_eq(expected.as_bytes(), token.to_ascii_lowercase().as_bytes()) {
+        return Err(Error::new(
+            ErrorKind::Unauthorized,
+            "invalid username or password",
+        ));
+    }
+    Ok(())
+}
+
+fn subsonic_token(password: &str, salt: &str) -> String {
+    use md5::{Digest, Md5};
+    let mut hasher = Md5::new();
+    hasher.update(password.as_bytes());
+    hasher.update(salt.as_bytes());
+    hex::encode(hasher.finalize())
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_subsonic_token() {
+        // example from the subsonic api documentation
+        assert_eq!(
+            subsonic_token("sesame", "c19b2d"),
+            "26719a1196d2a940705a59634eb18eab"
+        );
+    }
+}
@@ -0,0 +1,520 @@
+//! Outgoing webhooks.
+//!
+//! Events that match a webhook are queued as deliveries in the database and POSTed as JSON to the
+//! webhook url by a background process. The body is signed with HMAC-SHA256 using the webhook
+//! secret and the hex encoded signature is sent in the `x-sonar-signature` header as
+//! `sha256=<signature>`. Failed deliveries are retried with exponential backoff.
+
+use std::time::Duration;
+
+use hmac::{Hmac, Mac};
+use rand::Rng;
+use sha2::Sha256;
+
+use crate::{
+    db::DbC, Error, ErrorKind, Event, EventKind, ListParams, Result, Timestamp, ValueUpdate,
+    WebhookId,
+};
+
+/// Deliveries are given up after this many attempts.
+pub const MAX_ATTEMPTS: u32 = 8;
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(6 * 60 * 60);
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+/// Finished deliveries older than this are removed from the delivery log.
+const DELIVERY_RETENTION: Duration = Duration::from_secs(30 * 24 * 60 * 60);
+const SECRET_LENGTH: usize = 32;
+
+pub const HEADER_EVENT: &str = "x-sonar-event";
+pub const HEADER_DELIVERY: &str = "x-sonar-delivery";
+pub const HEADER_SIGNATURE: &str = "x-sonar-signature";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Webhook {
+    pub id: WebhookId,
+    pub url: String,
+    /// Event kinds that are delivered, all events if empty.
+    pub kinds: Vec<EventKind>,
+    pub secret: String,
+    pub enabled: bool,
+    pub created_at: Timestamp,
+}
+
+#[derive(Debug, Clone)]
+pub struct WebhookCreate {
+    pub url: String,
+    pub kinds: Vec<EventKind>,
+    /// A random secret is generated if none is given.
+    pub secret: Option<String>,
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct WebhookUpdate {
+    pub url: ValueUpdate<String>,
+    pub kinds: ValueUpdate<Vec<EventKind>>,
+    pub secret: ValueUpdate<String>,
+    pub enabled: ValueUpdate<bool>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum WebhookDeliveryStatus {
+    Pending,
+    Delivered,
+    Failed,
+}
+
+impl WebhookDeliveryStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            WebhookDeliveryStatus::Pending => "pending",
+            WebhookDeliveryStatus::Delivered => "delivered",
+            WebhookDeliveryStatus::Failed => "failed",
+        }
+    }
+
+    fn from_db(value: &str) -> Result<Self> {
+        match value {
+            "pending" => Ok(WebhookDeliveryStatus::Pending),
+            "delivered" => Ok(WebhookDeliveryStatus::Delivered),
+            "failed" => Ok(WebhookDeliveryStatus::Failed),
+            _ => Err(Error::new(
+                ErrorKind::Internal,
+                format!("invalid webhook delivery status in database: {value}"),
+            )),
+        }
+    }
+}
+
+impl std::fmt::Display for WebhookDeliveryStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct WebhookDelivery {
+    pub id: u64,
+    pub webhook: WebhookId,
+    pub event_sequence: u64,
+    pub event_kind: EventKind,
+    pub payload: String,
+    pub status: WebhookDeliveryStatus,
+    pub attempts: u32,
+    /// Http status code of the last attempt.
+    pub response_status: Option<u16>,
+    /// Error of the last attempt.
+    pub error: Option<String>,
+    pub next_attempt_at: Timestamp,
+    pub created_at: Timestamp,
+    pub updated_at: Timestamp,
+}
+
+/// A delivery that is due, with what is needed to send it.
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub(crate) struct PendingDelivery {
+    pub(crate) id: i64,
+    pub(crate) webhook: i64,
+    pub(crate) url: String,
+    secret: String,
+    event_kind: String,
+    payload: String,
+    attempts: i64,
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct WebhookView {
+    id: i64,
+    url: String,
+    kinds: String,
+    secret: String,
+    enabled: bool,
+    created_at: i64,
+}
+
+impl TryFrom<WebhookView> for Webhook {
+    type Error = Error;
+
+    fn try_from(value: WebhookView) -> Result<Self, Self::Error> {
+        Ok(Self {
+            id: WebhookId::from_db(value.id),
+            url: value.url,
+            kinds: kinds_from_db(&value.kinds)?,
+            secret: value.secret,
+            enabled: value.enabled,
+            created_at: Timestamp::from_seconds(value.created_at as u64),
+        })
+    }
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct WebhookDeliveryView {
+    id: i64,
+    webhook: i64,
+    event_sequence: i64,
+    event_kind: String,
+    payload: String,
+    status: String,
+    attempts: i64,
+    response_status: Option<i64>,
+    error: Option<String>,
+    next_attempt_at: i64,
+    created_at: i64,
+    updated_at: i64,
+}
+
+impl TryFrom<WebhookDeliveryView> for WebhookDelivery {
+    type Error = Error;
+
+    fn try_from(value: WebhookDeliveryView) -> Result<Self, Self::Error> {
+        Ok(Self {
+            id: value.id as u64,
+            webhook: WebhookId::from_db(value.webhook),
+            event_sequence: value.event_sequence as u64,
+            event_kind: value.event_kind.parse().map_err(|e| {
+                Error::with_source(ErrorKind::Internal, "invalid event kind in database", e)
+            })?,
+            payload: value.payload,
+            status: WebhookDeliveryStatus::from_db(&value.status)?,
+            attempts: value.attempts as u32,
+            response_status: value.response_status.map(|status| status as u16),
+            error: value.error,
+            next_attempt_at: Timestamp::from_seconds(value.next_attempt_at as u64),
+            created_at: Timestamp::from_seconds(value.created_at as u64),
+            updated_at: Timestamp::from_seconds(value.updated_at as u64),
+        })
+    }
+}
+
+#[tracing::instrument(skip(db))]
+pub async fn list(db: &mut DbC) -> Result<Vec<Webhook>> {
+    let views = sqlx::query_as::<_, WebhookView>("SELECT * FROM webhook ORDER BY id ASC")
+        .fetch_all(db)
+        .await?;
+    views.into_iter().map(Webhook::try_from).collect()
+}
+
+#[tracing::instrument(skip(db))]
+pub async fn get(db: &mut DbC, webhook_id: WebhookId) -> Result<Webhook> {
+    let view = sqlx::query_as::<_, WebhookView>("SELECT * FROM webhook WHERE id = ?")
+        .bind(webhook_id)
+        .fetch_optional(db)
+        .await?;
+    match view {
+        Some(view) => Webhook::try_from(view),
+        None => Err(Error::new(ErrorKind::NotFound, "webhook not found")),
+    }
+}
+
+#[tracing::instrument(skip(db))]
+pub async fn create(db: &mut DbC, create: WebhookCreate) -> Result<Webhook> {
+    validate_url(&create.url)?;
+    let secret = match create.secret {
+        Some(secret) => {
+            validate_secret(&secret)?;
+            secret
+        }
+        None => generate_secret(),
+    };
+    let webhook_id = sqlx::query_scalar(
+        "INSERT INTO webhook (url, kinds, secret) VALUES (?, ?, ?) RETURNING id",
+    )
+    .bind(&create.url)
+    .bind(kinds_to_db(&create.kinds))
+    .bind(&secret)
+    .fetch_one(&mut *db)
+    .await?;
+    get(db, WebhookId::from_db(webhook_id)).await
+}
+
+#[tracing::instrument(skip(db))]
+pub async fn update(db: &mut DbC, webhook_id: WebhookId, update: WebhookUpdate) -> Result<Webhook> {
+    let webhook = get(db, webhook_id).await?;
+    let url = match update.url {
+        ValueUpdate::Set(url) => {
+            validate_url(&url)?;
+            url
+        }
+        ValueUpdate::Unset => {
+            return Err(Error::new(
+                ErrorKind::Invalid,
+                "webhook url cannot be unset",
+            ))
+        }
+        ValueUpdate::Unchanged => webhook.url,
+    };
+    let kinds = match update.kinds {
+        ValueUpdate::Set(kinds) => kinds,
+        ValueUpdate::Unset => Vec::new(),
+        ValueUpdate::Unchanged => webhook.kinds,
+    };
+    let secret = match update.secret {
+        ValueUpdate::Set(secret) => {
+            validate_secret(&secret)?;
+            secret
+        }
+        ValueUpdate::Unset => generate_secret(),
+        ValueUpdate::Unchanged => webhook.secret,
+    };
+    let enabled = match update.enabled {
+        ValueUpdate::Set(enabled) => enabled,
+        ValueUpdate::Unset => true,
+        ValueUpdate::Unchanged => webhook.enabled,
+    };
+    sqlx::query("UPDATE webhook SET url = ?, kinds = ?, secret = ?, enabled = ? WHERE id = ?")
+        .bind(&url)
+        .bind(kinds_to_db(&kinds))
+        .bind(&secret)
+        .bind(enabled)
+        .bind(webhook_id)
+        .execute(&mut *db)
+        .await?;
+    get(db, webhook_id).await
+}
+
+#[tracing::instrument(skip(db))]
+pub async fn delete(db: &mut DbC, webhook_id: WebhookId) -> Result<()> {
+    sqlx::query("DELETE FROM webhook WHERE id = ?")
+        .bind(webhook_id)
+        .execute(db)
+        .await?;
+    Ok(())
+}
+
+/// Lists the deliveries of a webhook, most recent first.
+#[tracing::instrument(skip(db))]
+pub async fn delivery_list(
+    db: &mut DbC,
+    webhook_id: WebhookId,
+    params: ListParams,
+) -> Result<Vec<WebhookDelivery>> {
+    let (offset, limit) = params.to_db_offset_limit();
+    let views = sqlx::query_as::<_, WebhookDeliveryView>(
+        "SELECT * FROM webhook_delivery WHERE webhook = ? ORDER BY id DESC LIMIT ? OFFSET ?",
+    )
+    .bind(webhook_id)
+    .bind(limit)
+    .bind(offset)
+    .fetch_all(db)
+    .await?;
+    views.into_iter().map(WebhookDelivery::try_from).collect()
+}
+
+/// Queues a delivery of the event for every enabled webhook that matches it.
+/// Returns the number of queued deliveries.
+#[tracing::instrument(skip(db))]
+pub(crate) async fn enqueue(db: &mut DbC, event: &Event) -> Result<u64> {
+    let webhooks = list(db).await?;
+    let payload = payload(event);
+    let mut count = 0;
+    for webhook in webhooks {
+        if !webhook.enabled || !(webhook.kinds.is_empty() || webhook.kinds.contains(&event.kind)) {
+            continue;
+        }
+        sqlx::query(
+            "INSERT INTO webhook_delivery (webhook, event_sequence, event_kind, payload) VALUES (?, ?, ?, ?)",
+        )
+        .bind(webhook.id)
+        .bind(event.sequence as i64)
+        .bind(event.kind.as_str())
+        .bind(&payload)
+        .execute(&mut *db)
+        .await?;
+        count += 1;
+    }
+    Ok(count)
+}
+
+/// Pending deliveries of enabled webhooks whose next attempt is due, oldest first.
+pub(crate) async fn delivery_due(db: &mut DbC, limit: u32) -> Result<Vec<PendingDelivery>> {
+    let deliveries = sqlx::query_as::<_, PendingDelivery>(
+        "SELECT webhook_delivery.id, webhook_delivery.webhook, webhook.url, webhook.secret,
+            webhook_delivery.event_kind, webhook_delivery.payload, webhook_delivery.attempts
+        FROM webhook_delivery
+        INNER JOIN webhook ON webhook.id = webhook_delivery.webhook
+        WHERE webhook_delivery.status = 'pending' AND webhook_delivery.next_attempt_at <= unixepoch()
+            AND webhook.enabled
+        ORDER BY webhook_delivery.id ASC
+        LIMIT ?",
+    )
+    .bind(limit)
+    .fetch_all(db)
+    .await?;
+    Ok(deliveries)
+}
+
+/// Records the result of a delivery attempt, scheduling a retry if it failed.
+pub(crate) async fn delivery_record(
+    db: &mut DbC,
+    delivery: &PendingDelivery,
+    response_status: Option<u16>,
+    error: Option<String>,
+    retry_delay: Duration,
+) -> Result<()> {
+    let attempts = delivery.attempts as u32 + 1;
+    let (status, next_attempt_in) = match error {
+        None => (WebhookDeliveryStatus::Delivered, Duration::ZERO),
+        Some(_) if attempts >= MAX_ATTEMPTS => (WebhookDeliveryStatus::Failed, Duration::ZERO),
+        Some(_) => (
+            WebhookDeliveryStatus::Pending,
+            backoff(retry_delay, attempts),
+        ),
+    };
+    sqlx::query(
+        "UPDATE webhook_delivery
+        SET status = ?, attempts = ?, response_status = ?, error = ?,
+            next_attempt_at = unixepoch() + ?, updated_at = unixepoch()
+        WHERE id = ?",
+    )
+    .bind(status.as_str())
+    .bind(attempts)
+    .bind(response_status)
+    .bind(error)
+    .bind(next_attempt_in.as_secs() as i64)
+    .bind(delivery.id)
+    .execute(db)
+    .await?;
+    Ok(())
+}
+
+/// Removes delivered and failed deliveries older than the retention period.
+pub(crate) async fn delivery_prune(db: &mut DbC) -> Result<()> {
+    sqlx::query(
+        "DELETE FROM webhook_delivery WHERE status != 'pending' AND updated_at < unixepoch() - ?",
+    )
+    .bind(DELIVERY_RETENTION.as_secs() as i64)
+    .execute(db)
+    .await?;
+    Ok(())
+}
+
+/// Sends a delivery.
+/// Returns the response status code, if any, and an error if the delivery failed.
+pub(crate) async fn deliver(
+    client: &reqwest::Client,
+    delivery: &PendingDelivery,
+) -> (Option<u16>, Option<String>) {
+    let response = client
+        .post(&delivery.url)
+        .timeout(REQUEST_TIMEOUT)
+        .header(reqwest::header::CONTENT_TYPE, "application/json")
+        .header(HEADER_EVENT, &delivery.event_kind)
+        .header(HEADER_DELIVERY, delivery.id.to_string())
+        .header(
+            HEADER_SIGNATURE,
+            format!("sha256={}", sign(&delivery.secret, &delivery.payload)),
+        )
+        .body(delivery.payload.clone())
+        .send()
+        .await;
+    match response {
+        Ok(response) if response.status().is_success() => (Some(response.status().as_u16()), None),
+        Ok(response) => (
+            Some(response.status().as_u16()),
+            Some(format!("unexpected response status {}", response.status())),
+        ),
+        Err(err) => (None, Some(err.to_string())),
+    }
+}
+
+fn payload(event: &Event) -> String {
+    serde_json::json!({
+        "sequence": event.sequence,
+        "kind": event.kind.as_str(),
+        "id": event.id.to_string(),
+        "user": event.user.map(|user| user.to_string()),
+        "timestamp": event.timestamp.seconds(),
+    })
+    .to_string()
+}
+
+/// Hex encoded HMAC-SHA256 of the payload.
+fn sign(secret: &str, payload: &str) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("hmac accepts keys of any size");
+    mac.update(payload.as_bytes());
+    hex::encode(mac.finalize().into_bytes())
+}
+
+fn backoff(retry_delay: Duration, attempts: u32) -> Duration {
+    retry_delay
+        .saturating_mul(1 << attempts.saturating_sub(1).min(16))
+        .min(MAX_RETRY_DELAY)
+}
+
+fn generate_secret() -> String {
+    let mut secret = [0u8; SECRET_LENGTH];
+    rand::thread_rng().fill(&mut secret);
+    hex::encode(secret)
+}
+
+fn validate_url(url: &str) -> Result<()> {
+    let parsed = reqwest::Url::parse(url)
+        .map_err(|err| Error::with_source(ErrorKind::Invalid, "invalid webhook url", err))?;
+    match parsed.scheme() {
+        "http" | "https" => Ok(()),
+        _ => Err(Error::new(
+            ErrorKind::Invalid,
+            "webhook url must be an http or https url",
+        )),
+    }
+}
+
+fn validate_secret(secret: &str) -> Result<()> {
+    if secret.is_empty() {
+        return Err(Error::new(
+            ErrorKind::Invalid,
+            "webhook secret cannot be empty",
+        ));
+    }
+    Ok(())
+}
+
+fn kinds_to_db(kinds: &[EventKind]) -> String {
+    kinds
+        .iter()
+        .map(|kind| kind.as_str())
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+fn kinds_from_db(kinds: &str) -> Result<Vec<EventKind>> {
+    kinds
+        .split(',')
+        .filter(|kind| !kind.is_empty())
+        .map(|kind| {
+            kind.parse().map_err(|e| {
+                Error::with_source(ErrorKind::Internal, "invalid event kind in database", e)
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_backoff() {
+        let delay = Duration::from_secs(30);
+        assert_eq!(backoff(delay, 1), Duration::from_secs(30));
+        assert_eq!(backoff(delay, 2), Duration::from_secs(60));
+        assert_eq!(backoff(delay, 4), Duration::from_secs(240));
+        assert_eq!(backoff(delay, 30), MAX_RETRY_DELAY);
+    }
+
+    #[test]
+    fn test_sign() {
+        // rfc 4231 test case 2
+        assert_eq!(
+            sign("Jefe", "what do ya want for nothing?"),
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+    }
+
+    #[test]
+    fn test_kinds_roundtrip() {
+        let kinds = vec![EventKind::ScrobbleCreated, EventKind::ImportFinished];
+        assert_eq!(kinds_from_db(&kinds_to_db(&kinds)).unwrap(), kinds);
+        assert!(kinds_from_db("").unwrap().is_empty());
+        assert!(kinds_from_db("artist-created,unknown").is_err());
+    }
+}
@@ -14,6 +14,7 @@ async fn album_create_one() {
     let create = sonar::AlbumCreate {
         name: "Album".to_string(),
         artist: artist.id,
+        artists: Vec::new(),
         cover_art: None,
         genres: Default::default(),
         properties: sonar::test::create_simple_properties(),
@@ -30,6 +31,7 @@ async fn album_update_one() {
     let create = sonar::AlbumCreate {
         name: "Album".to_string(),
         artist: artist.id,
+        artists: Vec::new(),
         cover_art: None,
         genres: Default::default(),
         properties: sonar::test::create_simple_properties(),
@@ -44,6 +46,7 @@ async fn album_update_one() {
         sonar::AlbumUpdate {
             name: sonar::ValueUpdate::Set("Album2".to_string()),
             artist: Default::default(),
+            artists: Default::default(),
             cover_art: Default::default(),
             genres: vec![sonar::GenreUpdate {
                 action: sonar::GenreUpdateAction::Set,
@@ -164,3 +167,92 @@ async fn album_delete_one() {
     assert_eq!(albums.len(), 1);
     assert_eq!(albums[0].id, album2.id);
 }
+
+#[tokio::test]
+async fn album_create_multiple_artists() {
+    let ctx = sonar::test::create_context_memory().await;
+    let artist1 = sonar::test::create_artist(&ctx, "artist1").await;
+    let artist2 = sonar::test::create_artist(&ctx, "artist2").await;
+    let create = sonar::AlbumCreate {
+        name: "Album".to_string(),
+        artist: artist1.id,
+        artists: vec![artist2.id, artist1.id],
+        cover_art: None,
+        genres: Default::default(),
+        properties: Default::default(),
+    };
+    let album = sonar::album_create(&ctx, create).await.unwrap();
+    assert_eq!(album.artist, artist1.id);
+    assert_eq!(album.artists, vec![artist1.id, artist2.id]);
+
+    let albums = sonar::album_list_by_artist(&ctx, artist2.id, Default::default())
+        .await
+        .unwrap();
+    assert_eq!(albums.len(), 1);
+    assert_eq!(albums[0].id, album.id);
+}
+
+#[tokio::test]
+async fn album_update_artists() {
+    let ctx = sonar::test::create_context_memory().await;
+    let artist1 = sonar::test::create_artist(&ctx, "artist1").await;
+    let artist2 = sonar::test::create_artist(&ctx, "artist2").await;
+    let artist3 = sonar::test::create_artist(&ctx, "artist3").await;
+    let album = sonar::test::create_album(&ctx, artist1.id, "album").await;
+
+    let update = sonar::AlbumUpdate {
+        artists: sonar::ValueUpdate::Set(vec![artist2.id, artist3.id]),
+        ..Default::default()
+    };
+    let album = sonar::album_update(&ctx, album.id, update).await.unwrap();
+    assert_eq!(album.artist, artist2.id);
+    assert_eq!(album.artists, vec![artist2.id, artist3.id]);
+
+    let update = sonar::AlbumUpdate {
+        artist: sonar::ValueUpdate::Set(artist1.id),
+        ..Default::default()
+    };
+    let album = sonar::album_update(&ctx, album.id, update).await.unwrap();
+    assert_eq!(album.artists, vec![artist1.id, artist3.id]);
+
+    let update = sonar::AlbumUpdate {
+        artists: sonar::ValueUpdate::Set(Vec::new()),
+        ..Default::default()
+    };
+    let err = sonar::album_update(&ctx, album.id, update)
+        .await
+        .unwrap_err();
+    assert_eq!(err.kind(), sonar::ErrorKind::Invalid);
+
+    let update = sonar::AlbumUpdate {
+        artist: sonar::ValueUpdate::Set(artist2.id),
+        artists: sonar::ValueUpdate::Set(vec![artist3.id]),
+        ..Default::default()
+    };
+    let err = sonar::album_update(&ctx, album.id, update)
+        .await
+        .unwrap_err();
+    assert_eq!(err.kind(), sonar::ErrorKind::Invalid);
+}
+
+#[tokio::test]
+async fn album_list_by_featured_artist() {
+    let ctx = sonar::test::create_context_memory().await;
+    let (_, album, track) =
+        sonar::test::create_artist_album_track(&ctx, "artist", "album", "track").await;
+    let featured = sonar::test::create_artist(&ctx, "featured").await;
+    let update = sonar::TrackUpdate {
+        artists: sonar::ValueUpdate::Set(vec![sonar::ArtistCredit::new(
+            featured.id,
+            sonar::ArtistRole::Featured,
+        )]),
+        ..Default::default()
+    };
+    sonar::track_update(&ctx, track.id, update).await.unwrap();
+
+    let albums = sonar::album_list_by_artist(&ctx, featured.id, Default::default())
+        .await
+        .unwrap();
+    assert_eq!(albums.len(), 1);
+    assert_eq!(albums[0].id, album.id);
+}
@@ -15,6 +15,7 @@ async fn artist_create_one() {
         cover_art: None,
         genres: Default::default(),
         properties: sonar::test::create_simple_properties(),
+        library: None,
     };
     let artist = sonar::artist_create(&ctx, create).await.unwrap();
     assert_eq!(artist.name, "Artist");
@@ -82,6 +83,7 @@ async fn artist_update_one() {
         cover_art: None,
         genres: Default::default(),
         properties: sonar::test::create_simple_properties(),
+        library: None,
     };
     let artist = sonar::artist_create(&ctx, create).await.unwrap();
     let update = sonar::ArtistUpdate {
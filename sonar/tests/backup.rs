@@ -0,0 +1,85 @@
+#[tokio::test]
+async fn backup_create() {
+    let ctx = sonar::test::create_context_memory().await;
+    let _image = sonar::test::create_image(&ctx).await;
+    let _audio = sonar::test::create_audio(&ctx, sonar::test::SMALL_AUDIO_MP3).await;
+    let dir = tempfile::tempdir().unwrap();
+
+    let report = sonar::backup_create(&ctx, dir.path()).await.unwrap();
+    assert_eq!(report.blobs, 2);
+    assert_eq!(report.blobs_copied, 2);
+    assert_eq!(
+        report.bytes_copied,
+        (sonar::test::SMALL_IMAGE_JPEG.len() + sonar::test::SMALL_AUDIO_MP3.len()) as u64
+    );
+    assert!(dir.path().join("manifest.json").exists());
+    assert!(dir.path().join("sonar.db").exists());
+}
+
+#[tokio::test]
+async fn backup_incremental() {
+    let ctx = sonar::test::create_context_memory().await;
+    let _image = sonar::test::create_image(&ctx).await;
+    let dir = tempfile::tempdir().unwrap();
+
+    sonar::backup_create(&ctx, dir.path()).await.unwrap();
+    let _audio = sonar::test::create_audio(&ctx, sonar::test::SMALL_AUDIO_MP3).await;
+    let report = sonar::backup_create(&ctx, dir.path()).await.unwrap();
+    assert_eq!(report.blobs, 2);
+    assert_eq!(report.blobs_copied, 1);
+    assert_eq!(
+        report.bytes_copied,
+        sonar::test::SMALL_AUDIO_MP3.len() as u64
+    );
+}
+
+#[tokio::test]
+async fn backup_restore() {
+    let ctx = sonar::test::create_context_memory().await;
+    let image = sonar::test::create_image(&ctx).await;
+    let (_, _, track) =
+        sonar::test::create_artist_album_track(&ctx, "artist", "album", "track").await;
+    let backup_dir = tempfile::tempdir().unwrap();
+    let data_dir = tempfile::tempdir().unwrap();
+    sonar::backup_create(&ctx, backup_dir.path()).await.unwrap();
+
+    let database_path = data_dir.path().join("sonar.db");
+    let storage_backend = sonar::StorageBackend::Filesystem {
+        path: data_dir.path().join("storage"),
+    };
+    let report = sonar::backup_restore(backup_dir.path(), &database_path, storage_backend.clone())
+        .await
+        .unwrap();
+    assert_eq!(report.blobs, 1);
+
+    let config = sonar::Config::new(
+        database_path.display().to_string(),
+        storage_backend,
+        sonar::SearchBackend::BuiltIn,
+    );
+    let restored = sonar::test::create_context(config).await;
+    let restored_track = sonar::track_get(&restored, track.id).await.unwrap();
+    assert_eq!(restored_track.name, "track");
+    let download = sonar::image_download(&restored, image).await.unwrap();
+    let data = sonar::bytestream::to_bytes(download.stream).await.unwrap();
+    assert_eq!(&data[..], sonar::test::SMALL_IMAGE_JPEG);
+}
+
+#[tokio::test]
+async fn backup_restore_existing_database() {
+    let ctx = sonar::test::create_context_memory().await;
+    let backup_dir = tempfile::tempdir().unwrap();
+    let data_dir = tempfile::tempdir().unwrap();
+    sonar::backup_create(&ctx, backup_dir.path()).await.unwrap();
+
+    let database_path = data_dir.path().join("sonar.db");
+    std::fs::write(&database_path, b"").unwrap();
+    let err = sonar::backup_restore(
+        backup_dir.path(),
+        &database_path,
+        sonar::StorageBackend::Memory,
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(err.kind(), sonar::ErrorKind::Invalid);
+}
@@ -0,0 +1,127 @@
+use std::time::Duration;
+
+use sonar::BookmarkCreate;
+
+#[tokio::test]
+async fn bookmark_empty() {
+    let ctx = sonar::test::create_context_memory().await;
+    let user = sonar::test::create_user(&ctx, "user").await;
+    let bookmarks = sonar::bookmark_list(&ctx, user.id).await.unwrap();
+    assert!(bookmarks.is_empty());
+}
+
+#[tokio::test]
+async fn bookmark_create_and_update() {
+    let ctx = sonar::test::create_context_memory().await;
+    let user = sonar::test::create_user(&ctx, "user").await;
+    let (_, _, track) =
+        sonar::test::create_artist_album_track(&ctx, "artist", "album", "track").await;
+
+    let bookmark = sonar::bookmark_create(
+        &ctx,
+        BookmarkCreate {
+            user: user.id,
+            track: track.id,
+            position: Duration::from_millis(60_000),
+            comment: Some("intro".to_string()),
+        },
+    )
+    .await
+    .unwrap();
+    assert_eq!(bookmark.track, track.id);
+    assert_eq!(bookmark.position, Duration::from_millis(60_000));
+    assert_eq!(bookmark.comment.as_deref(), Some("intro"));
+
+    sonar::bookmark_create(
+        &ctx,
+        BookmarkCreate {
+            user: user.id,
+            track: track.id,
+            position: Duration::from_millis(90_000),
+            comment: None,
+        },
+    )
+    .await
+    .unwrap();
+
+    let bookmarks = sonar::bookmark_list(&ctx, user.id).await.unwrap();
+    assert_eq!(bookmarks.len(), 1);
+    assert_eq!(bookmarks[0].position, Duration::from_millis(90_000));
+    assert_eq!(bookmarks[0].comment, None);
+}
+
+#[tokio::test]
+async fn bookmark_per_user() {
+    let ctx = sonar::test::create_context_memory().await;
+    let user1 = sonar::test::create_user(&ctx, "user1").await;
+    let user2 = sonar::test::create_user(&ctx, "user2").await;
+    let (_, _, track) =
+        sonar::test::create_artist_album_track(&ctx, "artist", "album", "track").await;
+
+    sonar::bookmark_create(
+        &ctx,
+        BookmarkCreate {
+            user: user1.id,
+            track: track.id,
+            position: Duration::from_millis(1000),
+            comment: None,
+        },
+    )
+    .await
+    .unwrap();
+
+    let bookmarks = sonar::bookmark_list(&ctx, user2.id).await.unwrap();
+    assert!(bookmarks.is_empty());
+    let err = sonar::bookmark_get(&ctx, user2.id, track.id)
+        .await
+        .unwrap_err();
+    assert_eq!(err.kind(), sonar::ErrorKind::NotFound);
+}
+
+#[tokio::test]
+async fn bookmark_delete() {
+    let ctx = sonar::test::create_context_memory().await;
+    let user = sonar::test::create_user(&ctx, "user").await;
+    let (_, _, track) =
+        sonar::test::create_artist_album_track(&ctx, "artist", "album", "track").await;
+
+    sonar::bookmark_create(
+        &ctx,
+        BookmarkCreate {
+            user: user.id,
+            track: track.id,
+            position: Duration::from_millis(1000),
+            comment: None,
+        },
+    )
+    .await
+    .unwrap();
+    sonar::bookmark_delete(&ctx, user.id, track.id)
+        .await
+        .unwrap();
+    let bookmarks = sonar::bookmark_list(&ctx, user.id).await.unwrap();
+    assert!(bookmarks.is_empty());
+}
+
+#[tokio::test]
+async fn bookmark_user_delete() {
+    let ctx = sonar::test::create_context_memory().await;
+    let user = sonar::test::create_user(&ctx, "user").await;
+    let (_, _, track) =
+        sonar::test::create_artist_album_track(&ctx, "artist", "album", "track").await;
+    sonar::bookmark_create(
+        &ctx,
+        BookmarkCreate {
+            user: user.id,
+            track: track.id,
+            position: Duration::from_millis(60_000),
+            comment: None,
+        },
+    )
+    .await
+    .unwrap();
+
+    sonar::user_delete(&ctx, user.id).await.unwrap();
+    let bookmarks = sonar::bookmark_list(&ctx, user.id).await.unwrap();
+    assert!(bookmarks.is_empty());
+}
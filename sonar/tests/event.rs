@@ -0,0 +1,162 @@
+use std::time::Duration;
+
+use sonar::{ErrorKind, EventFilter, EventKind};
+
+async fn recv(subscription: &mut sonar::EventSubscription) -> sonar::Event {
+    tokio::time::timeout(Duration::from_secs(5), subscription.recv())
+        .await
+        .unwrap()
+        .unwrap()
+}
+
+#[tokio::test]
+async fn event_artist_crud() {
+    let ctx = sonar::test::create_context_memory().await;
+    let mut subscription = sonar::event_subscribe(&ctx, Default::default()).unwrap();
+
+    let artist = sonar::test::create_artist(&ctx, "artist").await;
+    sonar::artist_delete(&ctx, artist.id).await.unwrap();
+
+    let created = recv(&mut subscription).await;
+    assert_eq!(created.kind, EventKind::ArtistCreated);
+    assert_eq!(created.id, sonar::SonarId::from(artist.id));
+    assert_eq!(created.user, None);
+    let deleted = recv(&mut subscription).await;
+    assert_eq!(deleted.kind, EventKind::ArtistDeleted);
+    assert_eq!(deleted.sequence, created.sequence + 1);
+}
+
+#[tokio::test]
+async fn event_filter() {
+    let ctx = sonar::test::create_context_memory().await;
+    let user = sonar::test::create_user(&ctx, "user").await;
+    let other = sonar::test::create_user(&ctx, "other").await;
+    let (_, _, track) =
+        sonar::test::create_artist_album_track(&ctx, "artist", "album", "track").await;
+
+    let mut subscription = sonar::event_subscribe(
+        &ctx,
+        EventFilter {
+            kinds: vec![EventKind::FavoriteCreated, EventKind::FavoriteDeleted],
+            user: Some(user.id),
+            ..Default::default()
+        },
+    )
+    .unwrap();
+
+    sonar::favorite_add(&ctx, other.id, track.id.into())
+        .await
+        .unwrap();
+    sonar::favorite_add(&ctx, user.id, track.id.into())
+        .await
+        .unwrap();
+    sonar::favorite_remove(&ctx, user.id, track.id.into())
+        .await
+        .unwrap();
+
+    let event = recv(&mut subscription).await;
+    assert_eq!(event.kind, EventKind::FavoriteCreated);
+    assert_eq!(event.user, Some(user.id));
+    assert_eq!(event.id, sonar::SonarId::from(track.id));
+    let event = recv(&mut subscription).await;
+    assert_eq!(event.kind, EventKind::FavoriteDeleted);
+}
+
+#[tokio::test]
+async fn event_resume() {
+    let ctx = sonar::test::create_context_memory().await;
+    let user = sonar::test::create_user(&ctx, "user").await;
+    let mut subscription = sonar::event_subscribe(&ctx, Default::default()).unwrap();
+
+    let playlist = sonar::test::create_playlist(&ctx, user.id, "playlist").await;
+    let first = recv(&mut subscription).await;
+    assert_eq!(first.kind, EventKind::PlaylistCreated);
+    assert_eq!(first.user, Some(user.id));
+    sonar::playlist_delete(&ctx, playlist.id).await.unwrap();
+
+    // resuming after the first event only receives the ones after it
+    let mut resumed = sonar::event_subscribe(
+        &ctx,
+        EventFilter {
+            ids: vec![playlist.id.into()],
+            since: Some(first.sequence),
+            ..Default::default()
+        },
+    )
+    .unwrap();
+    let event = recv(&mut resumed).await;
+    assert_eq!(event.kind, EventKind::PlaylistDeleted);
+    assert_eq!(event.id, sonar::SonarId::from(playlist.id));
+
+    let err = sonar::event_subscribe(
+        &ctx,
+        EventFilter {
+            since: Some(event.sequence + 100),
+            ..Default::default()
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::Invalid);
+}
+
+#[tokio::test]
+async fn event_garbage_collect() {
+    let ctx = sonar::test::create_context_memory().await;
+    let (artist, album, track) =
+        sonar::test::create_artist_album_track(&ctx, "artist", "album", "track").await;
+    let mut subscription = sonar::event_subscribe(&ctx, Default::default()).unwrap();
+
+    sonar::garbage_collect(
+        &ctx,
+        sonar::GarbageCollectParams {
+            min_age: Duration::ZERO,
+            dry_run: false,
+        },
+    )
+    .await
+    .unwrap();
+
+    let mut deleted = Vec::new();
+    for _ in 0..3 {
+        let event = recv(&mut subscription).await;
+        deleted.push((event.kind, event.id));
+    }
+    assert!(deleted.contains(&(EventKind::ArtistDeleted, artist.id.into())));
+    assert!(deleted.contains(&(EventKind::AlbumDeleted, album.id.into())));
+    assert!(deleted.contains(&(EventKind::TrackDeleted, track.id.into())));
+}
+
+#[tokio::test]
+async fn event_scrobble_import() {
+    let ctx = sonar::test::create_context_memory().await;
+    let user = sonar::test::create_user(&ctx, "user").await;
+    sonar::test::create_artist_album_track(&ctx, "artist", "album", "track").await;
+    let mut subscription = sonar::event_subscribe(&ctx, Default::default()).unwrap();
+
+    sonar::scrobble_import(
+        &ctx,
+        sonar::ScrobbleImport {
+            user: user.id,
+            scrobbler: None,
+            listen_device: "import".to_string(),
+            entries: vec![sonar::ScrobbleImportEntry {
+                artist: "artist".to_string(),
+                album: Some("album".to_string()),
+                title: "track".to_string(),
+                duration: None,
+                recording_mbid: None,
+                listen_at: sonar::Timestamp::from_seconds(1000),
+            }],
+        },
+    )
+    .await
+    .unwrap();
+
+    let event = recv(&mut subscription).await;
+    assert_eq!(event.kind, EventKind::ScrobbleCreated);
+    assert_eq!(event.user, Some(user.id));
+    let scrobbles = sonar::scrobble_list(&ctx, Default::default())
+        .await
+        .unwrap();
+    assert_eq!(event.id, sonar::SonarId::from(scrobbles[0].id));
+}
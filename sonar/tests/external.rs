@@ -124,6 +124,7 @@ async fn external_download_track() {
             track: None,
             playlist: None,
             media_type: None,
+            library: None,
         },
     )
     .await
@@ -0,0 +1,111 @@
+use std::sync::Mutex;
+
+use sonar::{FsckIssueKind, FsckParams, FsckRepair};
+
+#[tokio::test]
+async fn fsck_clean() {
+    let ctx = sonar::test::create_context_memory().await;
+    let _image = sonar::test::create_image(&ctx).await;
+
+    let report = sonar::blob_fsck(&ctx, Default::default(), &|_| {})
+        .await
+        .unwrap();
+    assert_eq!(report.checked, 1);
+    assert!(report.issues.is_empty());
+}
+
+#[tokio::test]
+async fn fsck_progress() {
+    let ctx = sonar::test::create_context_memory().await;
+    let _image = sonar::test::create_image(&ctx).await;
+    let _audio = sonar::test::create_audio(&ctx, sonar::test::SMALL_AUDIO_MP3).await;
+
+    let checked = Mutex::new(Vec::new());
+    sonar::blob_fsck(&ctx, Default::default(), &|progress| {
+        assert_eq!(progress.total, 2);
+        checked.lock().unwrap().push(progress.checked);
+    })
+    .await
+    .unwrap();
+    assert_eq!(checked.into_inner().unwrap(), vec![1, 2]);
+}
+
+#[tokio::test]
+async fn fsck_corrupted() {
+    let ctx = sonar::test::create_context_memory().await;
+    let _image = sonar::test::create_image(&ctx).await;
+    let key = sonar::test::storage_list(&ctx).await.remove(0);
+
+    sonar::test::storage_put(&ctx, &key, b"not an image").await;
+    let report = sonar::blob_fsck(&ctx, Default::default(), &|_| {})
+        .await
+        .unwrap();
+    assert_eq!(report.issues.len(), 1);
+    assert_eq!(report.issues[0].key, key);
+    assert_eq!(report.issues[0].kind, FsckIssueKind::Corrupted);
+    assert!(!report.issues[0].repaired);
+
+    sonar::test::storage_put(&ctx, &key, &sonar::test::SMALL_IMAGE_JPEG[..10]).await;
+    let report = sonar::blob_fsck(&ctx, Default::default(), &|_| {})
+        .await
+        .unwrap();
+    assert_eq!(report.issues.len(), 1);
+    assert_eq!(report.issues[0].kind, FsckIssueKind::Truncated);
+}
+
+#[tokio::test]
+async fn fsck_unreferenced_delete() {
+    let ctx = sonar::test::create_context_memory().await;
+    sonar::test::storage_put(&ctx, "image/unreferenced", b"data").await;
+
+    let params = FsckParams {
+        repair: FsckRepair::Delete,
+    };
+    let report = sonar::blob_fsck(&ctx, params, &|_| {}).await.unwrap();
+    assert_eq!(report.checked, 0);
+    assert_eq!(report.issues.len(), 1);
+    assert_eq!(report.issues[0].kind, FsckIssueKind::Unreferenced);
+    assert!(report.issues[0].repaired);
+    assert!(sonar::test::storage_list(&ctx).await.is_empty());
+}
+
+#[tokio::test]
+async fn fsck_unreferenced_quarantine() {
+    let ctx = sonar::test::create_context_memory().await;
+    sonar::test::storage_put(&ctx, "image/unreferenced", b"data").await;
+
+    let params = FsckParams {
+        repair: FsckRepair::Quarantine,
+    };
+    let report = sonar::blob_fsck(&ctx, params, &|_| {}).await.unwrap();
+    assert_eq!(report.issues.len(), 1);
+    assert!(report.issues[0].repaired);
+    assert_eq!(
+        sonar::test::storage_list(&ctx).await,
+        vec!["quarantine/image/unreferenced".to_string()]
+    );
+
+    // quarantined files are not reported again
+    let report = sonar::blob_fsck(&ctx, Default::default(), &|_| {})
+        .await
+        .unwrap();
+    assert!(report.issues.is_empty());
+}
+
+#[tokio::test]
+async fn fsck_unreferenced_in_flight() {
+    let ctx = sonar::test::create_context_memory().await;
+    // imports write the file before committing the blob that references it
+    let key = sonar::test::storage_random_key("audio");
+    sonar::test::storage_put(&ctx, &key, b"data").await;
+    // files written long ago are still reported
+    sonar::test::storage_put(&ctx, "audio/01ARZ3NDEKTSV4RRFFQ69G5FAV", b"data").await;
+
+    let params = FsckParams {
+        repair: FsckRepair::Delete,
+    };
+    let report = sonar::blob_fsck(&ctx, params, &|_| {}).await.unwrap();
+    assert_eq!(report.issues.len(), 1);
+    assert_eq!(report.issues[0].key, "audio/01ARZ3NDEKTSV4RRFFQ69G5FAV");
+    assert_eq!(sonar::test::storage_list(&ctx).await, vec![key]);
+}
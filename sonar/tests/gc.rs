@@ -0,0 +1,211 @@
+use std::time::Duration;
+
+use sonar::{GarbageCollectParams, Rating, ScrobbleCreate, SonarId, Timestamp};
+
+fn gc_params(dry_run: bool) -> GarbageCollectParams {
+    GarbageCollectParams {
+        min_age: Duration::ZERO,
+        dry_run,
+    }
+}
+
+#[tokio::test]
+async fn gc_deletes_candidates() {
+    let ctx = sonar::test::create_context_memory().await;
+    let audio = sonar::test::create_audio(&ctx, sonar::test::SMALL_AUDIO_MP3).await;
+    let artist = sonar::test::create_artist(&ctx, "artist").await;
+    let album = sonar::test::create_album(&ctx, artist.id, "album").await;
+    let track = sonar::test::create_track_with_audio(&ctx, album.id, "track", audio.id).await;
+
+    let report = sonar::garbage_collect(&ctx, gc_params(false))
+        .await
+        .unwrap();
+    assert_eq!(report.items.len(), 4);
+    assert!(report.items.contains(&SonarId::from(track.id)));
+    assert!(report.items.contains(&SonarId::from(audio.id)));
+
+    let err = sonar::track_get(&ctx, track.id).await.unwrap_err();
+    assert_eq!(err.kind(), sonar::ErrorKind::NotFound);
+    let err = sonar::album_get(&ctx, album.id).await.unwrap_err();
+    assert_eq!(err.kind(), sonar::ErrorKind::NotFound);
+    let err = sonar::artist_get(&ctx, artist.id).await.unwrap_err();
+    assert_eq!(err.kind(), sonar::ErrorKind::NotFound);
+    assert!(sonar::test::storage_list(&ctx).await.is_empty());
+}
+
+#[tokio::test]
+async fn gc_dry_run() {
+    let ctx = sonar::test::create_context_memory().await;
+    let (_, _, track) =
+        sonar::test::create_artist_album_track(&ctx, "artist", "album", "track").await;
+
+    let report = sonar::garbage_collect(&ctx, gc_params(true)).await.unwrap();
+    assert_eq!(report.items.len(), 3);
+    sonar::track_get(&ctx, track.id).await.unwrap();
+}
+
+#[tokio::test]
+async fn gc_min_age() {
+    let ctx = sonar::test::create_context_memory().await;
+    let (_, _, track) =
+        sonar::test::create_artist_album_track(&ctx, "artist", "album", "track").await;
+
+    let report = sonar::garbage_collect(&ctx, Default::default())
+        .await
+        .unwrap();
+    assert!(report.items.is_empty());
+    sonar::track_get(&ctx, track.id).await.unwrap();
+}
+
+#[tokio::test]
+async fn gc_keeps_favorites() {
+    let ctx = sonar::test::create_context_memory().await;
+    let user = sonar::test::create_user(&ctx, "user").await;
+    let (_, album, track1) =
+        sonar::test::create_artist_album_track(&ctx, "artist", "album", "track1").await;
+    let track2 = sonar::test::create_track(&ctx, album.id, "track2").await;
+
+    sonar::favorite_add(&ctx, user.id, track1.id.into())
+        .await
+        .unwrap();
+    let report = sonar::garbage_collect(&ctx, gc_params(false))
+        .await
+        .unwrap();
+    assert!(report.items.is_empty());
+    sonar::track_get(&ctx, track2.id).await.unwrap();
+}
+
+#[tokio::test]
+async fn gc_keeps_playlist_tracks() {
+    let ctx = sonar::test::create_context_memory().await;
+    let user = sonar::test::create_user(&ctx, "user").await;
+    let (_, _, track) =
+        sonar::test::create_artist_album_track(&ctx, "artist", "album", "track").await;
+    let playlist = sonar::test::create_playlist(&ctx, user.id, "playlist").await;
+
+    sonar::playlist_insert_tracks(&ctx, playlist.id, &[track.id])
+        .await
+        .unwrap();
+    let report = sonar::garbage_collect(&ctx, gc_params(false))
+        .await
+        .unwrap();
+    assert!(report.items.is_empty());
+}
+
+#[tokio::test]
+async fn gc_shared_cover_art() {
+    let ctx = sonar::test::create_context_memory().await;
+    let user = sonar::test::create_user(&ctx, "user").await;
+    let image = sonar::test::create_image(&ctx).await;
+    let (_, _, track) =
+        sonar::test::create_artist_album_track(&ctx, "artist", "album", "track").await;
+    let playlist = sonar::test::create_playlist(&ctx, user.id, "playlist").await;
+
+    sonar::track_update(
+        &ctx,
+        track.id,
+        sonar::TrackUpdate {
+            cover_art: sonar::ValueUpdate::set(image),
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap();
+    sonar::playlist_update(
+        &ctx,
+        playlist.id,
+        sonar::PlaylistUpdate {
+            cover_art: sonar::ValueUpdate::set(image),
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap();
+
+    let report = sonar::garbage_collect(&ctx, gc_params(false))
+        .await
+        .unwrap();
+    assert!(report.items.contains(&SonarId::from(track.id)));
+    assert!(!report.items.contains(&SonarId::from(image)));
+    sonar::image_download(&ctx, image).await.unwrap();
+}
+
+#[tokio::test]
+async fn gc_keeps_scrobbled_track() {
+    let ctx = sonar::test::create_context_memory().await;
+    let user = sonar::test::create_user(&ctx, "user").await;
+    let (_, _, track) =
+        sonar::test::create_artist_album_track(&ctx, "artist", "album", "track").await;
+
+    sonar::scrobble_create(
+        &ctx,
+        ScrobbleCreate {
+            user: user.id,
+            track: track.id,
+            listen_at: Timestamp::now(),
+            listen_duration: Duration::from_secs(60),
+            listen_device: "device".to_string(),
+            properties: Default::default(),
+        },
+    )
+    .await
+    .unwrap();
+
+    let report = sonar::garbage_collect(&ctx, gc_params(false))
+        .await
+        .unwrap();
+    assert!(report.items.is_empty());
+    let scrobbles = sonar::scrobble_list(&ctx, Default::default())
+        .await
+        .unwrap();
+    assert_eq!(scrobbles.len(), 1);
+}
+
+#[tokio::test]
+async fn gc_keeps_rated_album() {
+    let ctx = sonar::test::create_context_memory().await;
+    let user = sonar::test::create_user(&ctx, "user").await;
+    let (_, album, track) =
+        sonar::test::create_artist_album_track(&ctx, "artist", "album", "track").await;
+
+    sonar::rating_set(&ctx, user.id, album.id.into(), Rating::new(4).unwrap())
+        .await
+        .unwrap();
+    let report = sonar::garbage_collect(&ctx, gc_params(false))
+        .await
+        .unwrap();
+    assert!(report.items.is_empty());
+    sonar::track_get(&ctx, track.id).await.unwrap();
+}
+
+#[tokio::test]
+async fn gc_ignores_smart_playlist_tracks() {
+    let ctx = sonar::test::create_context_memory().await;
+    let user = sonar::test::create_user(&ctx, "user").await;
+    let (artist, _, track) =
+        sonar::test::create_artist_album_track(&ctx, "artist", "album", "track").await;
+    let playlist = sonar::smart_playlist_create(
+        &ctx,
+        sonar::SmartPlaylistCreate {
+            name: "smart".to_string(),
+            owner: user.id,
+            rules: sonar::SmartPlaylistRules {
+                rules: vec![sonar::SmartPlaylistRule::Artist(artist.id)],
+                ..Default::default()
+            },
+            properties: Default::default(),
+        },
+    )
+    .await
+    .unwrap();
+    assert_eq!(playlist.track_count, 1);
+
+    let report = sonar::garbage_collect(&ctx, gc_params(false))
+        .await
+        .unwrap();
+    assert!(report.items.contains(&SonarId::from(track.id)));
+    let tracks = sonar::playlist_list_tracks(&ctx, playlist.id, Default::default())
+        .await
+        .unwrap();
+    assert!(tracks.is_empty());
+}
@@ -32,3 +32,20 @@ async fn delete_image_one() {
     let reader = sonar::image_download(&ctx, image_id).await;
     assert!(reader.is_err());
 }
+
+#[tokio::test]
+async fn delete_image_shared_blob() {
+    let ctx = sonar::test::create_context_memory().await;
+    let image1 = sonar::test::create_image(&ctx).await;
+    let image2 = sonar::test::create_image(&ctx).await;
+    assert_ne!(image1, image2);
+
+    // both images share the same blob, deleting one must keep the other readable
+    sonar::image_delete(&ctx, image1).await.unwrap();
+    let reader = sonar::image_download(&ctx, image2).await.unwrap();
+    let data = sonar::bytestream::to_bytes(reader).await.unwrap();
+    assert_eq!(data, Bytes::from_static(sonar::test::SMALL_IMAGE_JPEG));
+
+    sonar::image_delete(&ctx, image2).await.unwrap();
+    assert!(sonar::image_download(&ctx, image2).await.is_err());
+}
@@ -23,6 +23,7 @@ async fn import_simple() {
         sonar::Import {
             artist: None,
             album: None,
+            library: None,
             filepath: Some("test.mp3".to_string()),
             stream: sonar::test::create_stream(sonar::test::SMALL_AUDIO_MP3),
         },
@@ -87,6 +88,7 @@ async fn import_merge_metadata() {
         sonar::Import {
             artist: None,
             album: None,
+            library: None,
             filepath: Some("test.mp3".to_string()),
             stream: sonar::test::create_stream(sonar::test::SMALL_AUDIO_MP3),
         },
@@ -127,6 +129,7 @@ async fn import_no_metadata_with_filepath() {
         sonar::Import {
             artist: None,
             album: None,
+            library: None,
             filepath: Some("artist/album/test.mp3".to_string()),
             stream: sonar::test::create_stream(sonar::test::SMALL_AUDIO_MP3),
         },
@@ -168,6 +171,7 @@ async fn import_no_metadata_without_filepath() {
         sonar::Import {
             artist: None,
             album: None,
+            library: None,
             filepath: None,
             stream: sonar::test::create_stream(sonar::test::SMALL_AUDIO_MP3),
         },
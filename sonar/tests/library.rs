@@ -0,0 +1,386 @@
+use sonar::{ErrorKind, LibraryFilter, LibraryId};
+
+async fn create_listener(ctx: &sonar::Context, username: &str) -> sonar::User {
+    sonar::user_create(
+        ctx,
+        sonar::UserCreate {
+            username: username.parse().unwrap(),
+            password: "password".to_string(),
+            avatar: None,
+            admin: false,
+            roles: sonar::UserRole::DEFAULT.to_vec(),
+        },
+    )
+    .await
+    .unwrap()
+}
+
+#[tokio::test]
+async fn library_crud() {
+    let ctx = sonar::test::create_context_memory().await;
+    let libraries = sonar::library_list(&ctx).await.unwrap();
+    assert_eq!(libraries.len(), 1);
+    assert_eq!(libraries[0].id, LibraryId::DEFAULT);
+
+    let library = sonar::test::create_library(&ctx, "audiobooks").await;
+    let err = sonar::library_create(
+        &ctx,
+        sonar::LibraryCreate {
+            name: "audiobooks".to_string(),
+        },
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::Invalid);
+
+    let library = sonar::library_update(
+        &ctx,
+        library.id,
+        sonar::LibraryUpdate {
+            name: sonar::ValueUpdate::Set("podcasts".to_string()),
+        },
+    )
+    .await
+    .unwrap();
+    assert_eq!(library.name, "podcasts");
+
+    let err = sonar::library_delete(&ctx, LibraryId::DEFAULT)
+        .await
+        .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::Invalid);
+
+    let artist = sonar::test::create_artist_in_library(&ctx, library.id, "artist").await;
+    let err = sonar::library_delete(&ctx, library.id).await.unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::Invalid);
+
+    sonar::artist_delete(&ctx, artist.id).await.unwrap();
+    sonar::library_delete(&ctx, library.id).await.unwrap();
+    let err = sonar::library_get(&ctx, library.id).await.unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::NotFound);
+}
+
+#[tokio::test]
+async fn library_media_inherits_artist_library() {
+    let ctx = sonar::test::create_context_memory().await;
+    let library = sonar::test::create_library(&ctx, "library").await;
+    let artist = sonar::test::create_artist_in_library(&ctx, library.id, "artist").await;
+    let album = sonar::test::create_album(&ctx, artist.id, "album").await;
+    let track = sonar::test::create_track(&ctx, album.id, "track").await;
+    assert_eq!(artist.library, library.id);
+    assert_eq!(album.library, library.id);
+    assert_eq!(track.library, library.id);
+
+    // moving a track to another album moves it to the album's library
+    let (_, other_album, _) =
+        sonar::test::create_artist_album_track(&ctx, "artist", "album", "track").await;
+    let track = sonar::track_update(
+        &ctx,
+        track.id,
+        sonar::TrackUpdate {
+            album: sonar::ValueUpdate::Set(other_album.id),
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap();
+    assert_eq!(track.library, LibraryId::DEFAULT);
+
+    // changing the artist of an album moves it and its tracks to the artist's library
+    let other_track = sonar::test::create_track(&ctx, album.id, "other").await;
+    let album = sonar::album_update(
+        &ctx,
+        album.id,
+        sonar::AlbumUpdate {
+            artist: sonar::ValueUpdate::Set(other_album.artist),
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap();
+    assert_eq!(album.library, LibraryId::DEFAULT);
+    let other_track = sonar::track_get(&ctx, other_track.id).await.unwrap();
+    assert_eq!(other_track.library, LibraryId::DEFAULT);
+}
+
+#[tokio::test]
+async fn library_access() {
+    let ctx = sonar::test::create_context_memory().await;
+    let admin = sonar::test::create_user(&ctx, "admin").await;
+    let user = create_listener(&ctx, "user").await;
+    let library = sonar::test::create_library(&ctx, "private").await;
+    let (public, _, _) =
+        sonar::test::create_artist_album_track(&ctx, "public", "album", "track").await;
+    let private = sonar::test::create_artist_in_library(&ctx, library.id, "private").await;
+    let album = sonar::test::create_album(&ctx, private.id, "album").await;
+    sonar::test::create_track(&ctx, album.id, "track").await;
+
+    // new users only have access to the default library
+    let libraries = sonar::library_list_by_user(&ctx, user.id).await.unwrap();
+    assert_eq!(libraries.len(), 1);
+    assert_eq!(libraries[0].id, LibraryId::DEFAULT);
+    let artists =
+        sonar::artist_list_filtered(&ctx, LibraryFilter::user(user.id), Default::default())
+            .await
+            .unwrap();
+    assert_eq!(artists.len(), 1);
+    assert_eq!(artists[0].id, public.id);
+    let tracks = sonar::track_list_random_filtered(
+        &ctx,
+        LibraryFilter::user(user.id),
+        sonar::TrackListRandom {
+            limit: None,
+            genre: None,
+        },
+    )
+    .await
+    .unwrap();
+    assert_eq!(tracks.len(), 1);
+    assert_eq!(tracks[0].library, LibraryId::DEFAULT);
+    let err = sonar::album_list_filtered(
+        &ctx,
+        LibraryFilter::user(user.id).with_library(Some(library.id)),
+        Default::default(),
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::Unauthorized);
+
+    // admins have access to every library
+    let albums =
+        sonar::album_list_filtered(&ctx, LibraryFilter::user(admin.id), Default::default())
+            .await
+            .unwrap();
+    assert_eq!(albums.len(), 2);
+
+    sonar::library_grant(&ctx, library.id, user.id)
+        .await
+        .unwrap();
+    assert_eq!(
+        sonar::library_user_list(&ctx, library.id).await.unwrap(),
+        vec![user.id]
+    );
+    let tracks = sonar::track_list_filtered(
+        &ctx,
+        LibraryFilter::user(user.id).with_library(Some(library.id)),
+        Default::default(),
+    )
+    .await
+    .unwrap();
+    assert_eq!(tracks.len(), 1);
+    assert_eq!(tracks[0].library, library.id);
+
+    sonar::library_revoke(&ctx, LibraryId::DEFAULT, user.id)
+        .await
+        .unwrap();
+    let artists =
+        sonar::artist_list_filtered(&ctx, LibraryFilter::user(user.id), Default::default())
+            .await
+            .unwrap();
+    assert_eq!(artists.len(), 1);
+    assert_eq!(artists[0].id, private.id);
+}
+
+#[tokio::test]
+async fn library_search() {
+    let ctx = sonar::test::create_context_memory().await;
+    let user = create_listener(&ctx, "user").await;
+    let library = sonar::test::create_library(&ctx, "private").await;
+    sonar::test::create_artist(&ctx, "artist one").await;
+    sonar::test::create_artist_in_library(&ctx, library.id, "artist two").await;
+
+    let query = sonar::SearchQuery {
+        query: "artist".to_string(),
+        limit: None,
+        flags: sonar::SearchQuery::FLAG_ARTIST,
+        library: None,
+    };
+    let results = sonar::search(&ctx, user.id, query.clone()).await.unwrap();
+    assert_eq!(results.results.len(), 1);
+
+    sonar::library_grant(&ctx, library.id, user.id)
+        .await
+        .unwrap();
+    let results = sonar::search(&ctx, user.id, query.clone()).await.unwrap();
+    assert_eq!(results.results.len(), 2);
+
+    let results = sonar::search(
+        &ctx,
+        user.id,
+        sonar::SearchQuery {
+            library: Some(library.id),
+            ..query
+        },
+    )
+    .await
+    .unwrap();
+    assert_eq!(results.results.len(), 1);
+}
+
+#[tokio::test]
+async fn library_search_limit() {
+    let ctx = sonar::test::create_context_memory().await;
+    let user = create_listener(&ctx, "user").await;
+    let library = sonar::test::create_library(&ctx, "private").await;
+    for _ in 0..5 {
+        sonar::test::create_artist_in_library(&ctx, library.id, "artist").await;
+    }
+    let artist = sonar::test::create_artist(&ctx, "artist").await;
+
+    // results from other libraries must not use up the limit
+    let query = sonar::SearchQuery {
+        query: "artist".to_string(),
+        limit: Some(1),
+        flags: sonar::SearchQuery::FLAG_ARTIST,
+        library: None,
+    };
+    let results = sonar::search(&ctx, user.id, query).await.unwrap();
+    let artists = results.into_artists().collect::<Vec<_>>();
+    assert_eq!(artists.len(), 1);
+    assert_eq!(artists[0].id, artist.id);
+}
+
+#[tokio::test]
+async fn library_recommendations() {
+    let ctx = sonar::test::create_context_memory().await;
+    let admin = sonar::test::create_user(&ctx, "admin").await;
+    let user = create_listener(&ctx, "user").await;
+    let library = sonar::test::create_library(&ctx, "private").await;
+    sonar::library_grant(&ctx, library.id, user.id)
+        .await
+        .unwrap();
+    sonar::library_revoke(&ctx, LibraryId::DEFAULT, user.id)
+        .await
+        .unwrap();
+
+    let public = sonar::test::create_artist(&ctx, "public").await;
+    let private = sonar::test::create_artist_in_library(&ctx, library.id, "private").await;
+    let mut tracks = Vec::new();
+    for artist in [&public, &private] {
+        let album = sonar::test::create_album(&ctx, artist.id, "album").await;
+        for name in ["track1", "track2", "track3"] {
+            tracks.push(sonar::test::create_track(&ctx, album.id, name).await);
+        }
+    }
+    // tracks of both libraries are listened in the same session so they are similar
+    for (index, track) in tracks.iter().enumerate() {
+        sonar::scrobble_create(
+            &ctx,
+            sonar::ScrobbleCreate {
+                user: admin.id,
+                track: track.id,
+                listen_at: sonar::Timestamp::from_seconds(1000 + index as u64 * 60),
+                listen_duration: std::time::Duration::from_secs(60),
+                listen_device: "test".to_string(),
+                properties: Default::default(),
+            },
+        )
+        .await
+        .unwrap();
+    }
+    let seed = tracks.last().unwrap().id;
+    let is_private = |track_id: &sonar::TrackId| tracks[3..].iter().any(|t| t.id == *track_id);
+
+    let filter = LibraryFilter::user(user.id);
+    let similar = sonar::recommend_similar_tracks_filtered(&ctx, filter, seed.into(), 10)
+        .await
+        .unwrap();
+    assert!(!similar.is_empty());
+    assert!(similar.iter().all(is_private));
+    let similar = sonar::recommend_similar_artists_filtered(&ctx, filter, private.id, 10)
+        .await
+        .unwrap();
+    assert!(similar.is_empty());
+    let similar = sonar::recommend_similar_tracks(&ctx, seed.into(), 10)
+        .await
+        .unwrap();
+    assert!(!similar.iter().all(is_private));
+
+    let radio = sonar::radio_create(
+        &ctx,
+        sonar::RadioCreate {
+            user: user.id,
+            seed: sonar::RadioSeed::Track(seed),
+        },
+    )
+    .await
+    .unwrap();
+    let batch = sonar::radio_next(&ctx, radio.id, 10).await.unwrap();
+    assert_eq!(batch.len(), 3);
+    assert!(batch.iter().all(is_private));
+
+    let playlist = sonar::smart_playlist_create(
+        &ctx,
+        sonar::SmartPlaylistCreate {
+            name: "smart".to_string(),
+            owner: user.id,
+            rules: Default::default(),
+            properties: Default::default(),
+        },
+    )
+    .await
+    .unwrap();
+    let playlist_tracks = sonar::playlist_list_tracks(&ctx, playlist.id, Default::default())
+        .await
+        .unwrap();
+    assert_eq!(playlist_tracks.len(), 3);
+    assert!(playlist_tracks.iter().all(|track| is_private(&track.track)));
+}
+
+#[tokio::test]
+async fn library_check_access() {
+    let ctx = sonar::test::create_context_memory().await;
+    let admin = sonar::test::create_user(&ctx, "admin").await;
+    let user = create_listener(&ctx, "user").await;
+    let library = sonar::test::create_library(&ctx, "private").await;
+    let public = sonar::test::create_artist(&ctx, "public").await;
+    let private = sonar::test::create_artist_in_library(&ctx, library.id, "private").await;
+    let album = sonar::test::create_album(&ctx, private.id, "album").await;
+    sonar::test::create_track(&ctx, album.id, "track").await;
+    // the public artist is also credited on the private album
+    sonar::album_update(
+        &ctx,
+        album.id,
+        sonar::AlbumUpdate {
+            artists: sonar::ValueUpdate::Set(vec![private.id, public.id]),
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap();
+
+    sonar::library_check_access(&ctx, user.id, LibraryId::DEFAULT)
+        .await
+        .unwrap();
+    sonar::library_check_access(&ctx, admin.id, library.id)
+        .await
+        .unwrap();
+    let err = sonar::library_check_access(&ctx, user.id, library.id)
+        .await
+        .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::Unauthorized);
+
+    let filter = LibraryFilter::user(user.id);
+    let albums = sonar::album_list_by_artist_filtered(&ctx, filter, public.id, Default::default())
+        .await
+        .unwrap();
+    assert!(albums.is_empty());
+    let tracks = sonar::track_list_by_album_filtered(&ctx, filter, album.id, Default::default())
+        .await
+        .unwrap();
+    assert!(tracks.is_empty());
+    let albums = sonar::album_list_by_artist(&ctx, public.id, Default::default())
+        .await
+        .unwrap();
+    assert_eq!(albums.len(), 1);
+
+    sonar::library_grant(&ctx, library.id, user.id)
+        .await
+        .unwrap();
+    sonar::library_check_access(&ctx, user.id, library.id)
+        .await
+        .unwrap();
+    let tracks = sonar::track_list_by_album_filtered(&ctx, filter, album.id, Default::default())
+        .await
+        .unwrap();
+    assert_eq!(tracks.len(), 1);
+}
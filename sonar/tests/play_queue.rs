@@ -0,0 +1,124 @@
+use std::time::Duration;
+
+use sonar::PlayQueueSave;
+
+fn queue_save(tracks: Vec<sonar::TrackId>, current: Option<u32>) -> PlayQueueSave {
+    PlayQueueSave {
+        tracks,
+        current,
+        position: Duration::from_millis(1500),
+        changed_by: "client".to_string(),
+    }
+}
+
+#[tokio::test]
+async fn play_queue_empty() {
+    let ctx = sonar::test::create_context_memory().await;
+    let user = sonar::test::create_user(&ctx, "user").await;
+    let queue = sonar::play_queue_get(&ctx, user.id).await.unwrap();
+    assert!(queue.is_none());
+}
+
+#[tokio::test]
+async fn play_queue_save_and_get() {
+    let ctx = sonar::test::create_context_memory().await;
+    let user = sonar::test::create_user(&ctx, "user").await;
+    let (_, album, track1) =
+        sonar::test::create_artist_album_track(&ctx, "artist", "album", "track1").await;
+    let track2 = sonar::test::create_track(&ctx, album.id, "track2").await;
+
+    let tracks = vec![track2.id, track1.id, track2.id];
+    sonar::play_queue_save(&ctx, user.id, queue_save(tracks.clone(), Some(1)))
+        .await
+        .unwrap();
+
+    let queue = sonar::play_queue_get(&ctx, user.id).await.unwrap().unwrap();
+    assert_eq!(queue.user, user.id);
+    assert_eq!(queue.tracks, tracks);
+    assert_eq!(queue.current, Some(1));
+    assert_eq!(queue.position, Duration::from_millis(1500));
+    assert_eq!(queue.changed_by, "client");
+}
+
+#[tokio::test]
+async fn play_queue_save_replaces() {
+    let ctx = sonar::test::create_context_memory().await;
+    let user = sonar::test::create_user(&ctx, "user").await;
+    let (_, album, track1) =
+        sonar::test::create_artist_album_track(&ctx, "artist", "album", "track1").await;
+    let track2 = sonar::test::create_track(&ctx, album.id, "track2").await;
+
+    sonar::play_queue_save(&ctx, user.id, queue_save(vec![track1.id, track2.id], None))
+        .await
+        .unwrap();
+    let queue = sonar::play_queue_save(&ctx, user.id, queue_save(vec![track2.id], Some(0)))
+        .await
+        .unwrap();
+    assert_eq!(queue.tracks, vec![track2.id]);
+    assert_eq!(queue.current, Some(0));
+}
+
+#[tokio::test]
+async fn play_queue_invalid_current() {
+    let ctx = sonar::test::create_context_memory().await;
+    let user = sonar::test::create_user(&ctx, "user").await;
+    let (_, _, track) =
+        sonar::test::create_artist_album_track(&ctx, "artist", "album", "track").await;
+
+    let err = sonar::play_queue_save(&ctx, user.id, queue_save(vec![track.id], Some(1)))
+        .await
+        .unwrap_err();
+    assert_eq!(err.kind(), sonar::ErrorKind::Invalid);
+}
+
+#[tokio::test]
+async fn play_queue_clear() {
+    let ctx = sonar::test::create_context_memory().await;
+    let user = sonar::test::create_user(&ctx, "user").await;
+    let (_, _, track) =
+        sonar::test::create_artist_album_track(&ctx, "artist", "album", "track").await;
+
+    sonar::play_queue_save(&ctx, user.id, queue_save(vec![track.id], Some(0)))
+        .await
+        .unwrap();
+    sonar::play_queue_clear(&ctx, user.id).await.unwrap();
+    let queue = sonar::play_queue_get(&ctx, user.id).await.unwrap();
+    assert!(queue.is_none());
+}
+
+#[tokio::test]
+async fn play_queue_track_deleted() {
+    let ctx = sonar::test::create_context_memory().await;
+    let user = sonar::test::create_user(&ctx, "user").await;
+    let (_, album, track1) =
+        sonar::test::create_artist_album_track(&ctx, "artist", "album", "track1").await;
+    let track2 = sonar::test::create_track(&ctx, album.id, "track2").await;
+
+    sonar::play_queue_save(
+        &ctx,
+        user.id,
+        queue_save(vec![track1.id, track2.id], Some(1)),
+    )
+    .await
+    .unwrap();
+    sonar::track_delete(&ctx, track1.id).await.unwrap();
+
+    let queue = sonar::play_queue_get(&ctx, user.id).await.unwrap().unwrap();
+    assert_eq!(queue.tracks, vec![track2.id]);
+    assert_eq!(queue.current, Some(0));
+}
+
+#[tokio::test]
+async fn play_queue_user_delete() {
+    let ctx = sonar::test::create_context_memory().await;
+    let user = sonar::test::create_user(&ctx, "user").await;
+    let (_, _, track) =
+        sonar::test::create_artist_album_track(&ctx, "artist", "album", "track").await;
+    sonar::play_queue_save(&ctx, user.id, queue_save(vec![track.id], Some(0)))
+        .await
+        .unwrap();
+
+    sonar::user_delete(&ctx, user.id).await.unwrap();
+    let queue = sonar::play_queue_get(&ctx, user.id).await.unwrap();
+    assert!(queue.is_none());
+}
@@ -158,3 +158,165 @@ async fn playlist_clear() {
         .unwrap();
     assert_eq!(tracks.len(), 0);
 }
+
+async fn playlist_track_ids(
+    ctx: &sonar::Context,
+    playlist_id: sonar::PlaylistId,
+) -> Vec<sonar::TrackId> {
+    let tracks = sonar::playlist_list_tracks(ctx, playlist_id, sonar::ListParams::default())
+        .await
+        .unwrap();
+    for (idx, track) in tracks.iter().enumerate() {
+        assert_eq!(track.position, idx as u32);
+    }
+    tracks.into_iter().map(|t| t.track).collect()
+}
+
+#[tokio::test]
+async fn playlist_duplicate_tracks() {
+    let ctx = sonar::test::create_context_memory().await;
+    let user = sonar::test::create_user(&ctx, "user").await;
+    let playlist = sonar::test::create_playlist(&ctx, user.id, "Playlist").await;
+    let (_artist, _album, track) = create_artist_album_track(&ctx).await;
+    sonar::playlist_insert_tracks(&ctx, playlist.id, &[track.id, track.id])
+        .await
+        .unwrap();
+    sonar::playlist_insert_tracks(&ctx, playlist.id, &[track.id])
+        .await
+        .unwrap();
+    let tracks = playlist_track_ids(&ctx, playlist.id).await;
+    assert_eq!(tracks, vec![track.id, track.id, track.id]);
+    let playlist = sonar::playlist_get(&ctx, playlist.id).await.unwrap();
+    assert_eq!(playlist.track_count, 3);
+}
+
+#[tokio::test]
+async fn playlist_insert_tracks_at() {
+    let ctx = sonar::test::create_context_memory().await;
+    let user = sonar::test::create_user(&ctx, "user").await;
+    let playlist = sonar::test::create_playlist(&ctx, user.id, "Playlist").await;
+    let (_artist, album, track1) = create_artist_album_track(&ctx).await;
+    let track2 = sonar::test::create_track(&ctx, album.id, "track2").await;
+    let track3 = sonar::test::create_track(&ctx, album.id, "track3").await;
+    sonar::playlist_insert_tracks(&ctx, playlist.id, &[track1.id, track3.id])
+        .await
+        .unwrap();
+    sonar::playlist_insert_tracks_at(&ctx, playlist.id, 1, &[track2.id])
+        .await
+        .unwrap();
+    sonar::playlist_insert_tracks_at(&ctx, playlist.id, 0, &[track3.id])
+        .await
+        .unwrap();
+    let tracks = playlist_track_ids(&ctx, playlist.id).await;
+    assert_eq!(tracks, vec![track3.id, track1.id, track2.id, track3.id]);
+
+    let result = sonar::playlist_insert_tracks_at(&ctx, playlist.id, 5, &[track1.id]).await;
+    assert!(result.is_err());
+}
+
+#[tokio::test]
+async fn playlist_move_tracks() {
+    let ctx = sonar::test::create_context_memory().await;
+    let user = sonar::test::create_user(&ctx, "user").await;
+    let playlist = sonar::test::create_playlist(&ctx, user.id, "Playlist").await;
+    let (_artist, album, track1) = create_artist_album_track(&ctx).await;
+    let track2 = sonar::test::create_track(&ctx, album.id, "track2").await;
+    let track3 = sonar::test::create_track(&ctx, album.id, "track3").await;
+    let track4 = sonar::test::create_track(&ctx, album.id, "track4").await;
+    sonar::playlist_insert_tracks(
+        &ctx,
+        playlist.id,
+        &[track1.id, track2.id, track3.id, track4.id],
+    )
+    .await
+    .unwrap();
+
+    sonar::playlist_move_tracks(&ctx, playlist.id, 0, 2, 2)
+        .await
+        .unwrap();
+    let tracks = playlist_track_ids(&ctx, playlist.id).await;
+    assert_eq!(tracks, vec![track3.id, track4.id, track1.id, track2.id]);
+
+    sonar::playlist_move_tracks(&ctx, playlist.id, 3, 1, 0)
+        .await
+        .unwrap();
+    let tracks = playlist_track_ids(&ctx, playlist.id).await;
+    assert_eq!(tracks, vec![track2.id, track3.id, track4.id, track1.id]);
+
+    let result = sonar::playlist_move_tracks(&ctx, playlist.id, 3, 2, 0).await;
+    assert!(result.is_err());
+}
+
+#[tokio::test]
+async fn playlist_remove_at() {
+    let ctx = sonar::test::create_context_memory().await;
+    let user = sonar::test::create_user(&ctx, "user").await;
+    let playlist = sonar::test::create_playlist(&ctx, user.id, "Playlist").await;
+    let (_artist, album, track1) = create_artist_album_track(&ctx).await;
+    let track2 = sonar::test::create_track(&ctx, album.id, "track2").await;
+    sonar::playlist_insert_tracks(
+        &ctx,
+        playlist.id,
+        &[track1.id, track2.id, track1.id, track2.id],
+    )
+    .await
+    .unwrap();
+
+    sonar::playlist_remove_at(&ctx, playlist.id, &[0, 3])
+        .await
+        .unwrap();
+    let tracks = playlist_track_ids(&ctx, playlist.id).await;
+    assert_eq!(tracks, vec![track2.id, track1.id]);
+
+    let result = sonar::playlist_remove_at(&ctx, playlist.id, &[2]).await;
+    assert!(result.is_err());
+}
+
+#[tokio::test]
+async fn playlist_remove_at_middle() {
+    let ctx = sonar::test::create_context_memory().await;
+    let user = sonar::test::create_user(&ctx, "user").await;
+    let playlist = sonar::test::create_playlist(&ctx, user.id, "Playlist").await;
+    let (_artist, album, track) = create_artist_album_track(&ctx).await;
+    let mut track_ids = vec![track.id];
+    for i in 1..10 {
+        let track = sonar::test::create_track(&ctx, album.id, &format!("track{i}")).await;
+        track_ids.push(track.id);
+    }
+    sonar::playlist_insert_tracks(&ctx, playlist.id, &track_ids)
+        .await
+        .unwrap();
+
+    sonar::playlist_remove_at(&ctx, playlist.id, &[4, 6])
+        .await
+        .unwrap();
+    track_ids.remove(6);
+    track_ids.remove(4);
+    let tracks = playlist_track_ids(&ctx, playlist.id).await;
+    assert_eq!(tracks, track_ids);
+
+    sonar::playlist_remove_tracks(&ctx, playlist.id, &[track_ids[2]])
+        .await
+        .unwrap();
+    track_ids.remove(2);
+    let tracks = playlist_track_ids(&ctx, playlist.id).await;
+    assert_eq!(tracks, track_ids);
+}
+
+#[tokio::test]
+async fn playlist_remove_tracks_all_occurrences() {
+    let ctx = sonar::test::create_context_memory().await;
+    let user = sonar::test::create_user(&ctx, "user").await;
+    let playlist = sonar::test::create_playlist(&ctx, user.id, "Playlist").await;
+    let (_artist, album, track1) = create_artist_album_track(&ctx).await;
+    let track2 = sonar::test::create_track(&ctx, album.id, "track2").await;
+    sonar::playlist_insert_tracks(&ctx, playlist.id, &[track1.id, track2.id, track1.id])
+        .await
+        .unwrap();
+
+    sonar::playlist_remove_tracks(&ctx, playlist.id, &[track1.id])
+        .await
+        .unwrap();
+    let tracks = playlist_track_ids(&ctx, playlist.id).await;
+    assert_eq!(tracks, vec![track2.id]);
+}
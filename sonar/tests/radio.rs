@@ -0,0 +1,130 @@
+use std::time::Duration;
+
+use sonar::{ErrorKind, Genre, RadioCreate, RadioSeed, ScrobbleCreate, Timestamp, TrackId, UserId};
+
+async fn scrobble(ctx: &sonar::Context, user: UserId, track: TrackId, listen_at: u64) {
+    sonar::scrobble_create(
+        ctx,
+        ScrobbleCreate {
+            user,
+            track,
+            listen_at: Timestamp::from_seconds(listen_at),
+            listen_duration: Duration::from_secs(60),
+            listen_device: "test".to_string(),
+            properties: Default::default(),
+        },
+    )
+    .await
+    .unwrap();
+}
+
+async fn create_radio(ctx: &sonar::Context, user: UserId, seed: RadioSeed) -> sonar::Radio {
+    sonar::radio_create(ctx, RadioCreate { user, seed })
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn radio_no_repeats() {
+    let ctx = sonar::test::create_context_memory().await;
+    let user = sonar::test::create_user(&ctx, "user").await;
+    let (_, album, track1) =
+        sonar::test::create_artist_album_track(&ctx, "artist", "album", "track1").await;
+    let mut tracks = vec![track1.id];
+    for i in 2..=6 {
+        let track = sonar::test::create_track(&ctx, album.id, &format!("track{i}")).await;
+        tracks.push(track.id);
+    }
+
+    let radio = create_radio(&ctx, user.id, RadioSeed::Track(track1.id)).await;
+    let batch1 = sonar::radio_next(&ctx, radio.id, 3).await.unwrap();
+    let batch2 = sonar::radio_next(&ctx, radio.id, 3).await.unwrap();
+    assert_eq!(batch1.len(), 3);
+    assert_eq!(batch2.len(), 3);
+    for track in tracks.iter() {
+        assert!(batch1.contains(track) || batch2.contains(track));
+    }
+
+    // the radio is endless, once every track was played it starts over
+    let batch3 = sonar::radio_next(&ctx, radio.id, 3).await.unwrap();
+    assert_eq!(batch3.len(), 3);
+
+    let radio = sonar::radio_get(&ctx, radio.id).await.unwrap();
+    assert_eq!(radio.track_count, 6);
+}
+
+#[tokio::test]
+async fn radio_skips_recent_scrobbles() {
+    let ctx = sonar::test::create_context_memory().await;
+    let user = sonar::test::create_user(&ctx, "user").await;
+    let (artist, album, track1) =
+        sonar::test::create_artist_album_track(&ctx, "artist", "album", "track1").await;
+    let track2 = sonar::test::create_track(&ctx, album.id, "track2").await;
+    let track3 = sonar::test::create_track(&ctx, album.id, "track3").await;
+    scrobble(&ctx, user.id, track2.id, Timestamp::now().seconds() - 60).await;
+
+    let radio = create_radio(&ctx, user.id, RadioSeed::Artist(artist.id)).await;
+    let batch = sonar::radio_next(&ctx, radio.id, 2).await.unwrap();
+    assert_eq!(batch.len(), 2);
+    assert!(batch.contains(&track1.id));
+    assert!(batch.contains(&track3.id));
+}
+
+#[tokio::test]
+async fn radio_genre() {
+    let ctx = sonar::test::create_context_memory().await;
+    let user = sonar::test::create_user(&ctx, "user").await;
+    let (_, album1, track1) =
+        sonar::test::create_artist_album_track(&ctx, "artist1", "album1", "track1").await;
+    sonar::test::create_artist_album_track(&ctx, "artist2", "album2", "track2").await;
+    sonar::album_update(
+        &ctx,
+        album1.id,
+        sonar::AlbumUpdate {
+            genres: vec![sonar::GenreUpdate {
+                action: sonar::GenreUpdateAction::Set,
+                genre: Genre::new_unchecked("rock"),
+            }],
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap();
+
+    let seed = "genre:rock".parse::<RadioSeed>().unwrap();
+    let radio = create_radio(&ctx, user.id, seed).await;
+    let batch = sonar::radio_next(&ctx, radio.id, 5).await.unwrap();
+    assert_eq!(batch, vec![track1.id]);
+}
+
+#[tokio::test]
+async fn radio_find_or_create() {
+    let ctx = sonar::test::create_context_memory().await;
+    let user = sonar::test::create_user(&ctx, "user").await;
+    let (artist, _, _) =
+        sonar::test::create_artist_album_track(&ctx, "artist", "album", "track").await;
+
+    let create = RadioCreate {
+        user: user.id,
+        seed: RadioSeed::Artist(artist.id),
+    };
+    let radio1 = sonar::radio_find_or_create(&ctx, create.clone())
+        .await
+        .unwrap();
+    let radio2 = sonar::radio_find_or_create(&ctx, create).await.unwrap();
+    assert_eq!(radio1.id, radio2.id);
+
+    sonar::radio_delete(&ctx, radio1.id).await.unwrap();
+    let err = sonar::radio_get(&ctx, radio1.id).await.unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::NotFound);
+}
+
+#[tokio::test]
+async fn radio_invalid_seed() {
+    let ctx = sonar::test::create_context_memory().await;
+    let user = sonar::test::create_user(&ctx, "user").await;
+    let playlist = sonar::test::create_playlist(&ctx, user.id, "playlist").await;
+
+    assert!(RadioSeed::try_from(sonar::SonarId::from(playlist.id)).is_err());
+    assert!("genre:Rock!".parse::<RadioSeed>().is_err());
+}
@@ -0,0 +1,141 @@
+use sonar::{Rating, SonarId};
+
+fn rating(value: u32) -> Rating {
+    Rating::new(value).unwrap()
+}
+
+#[tokio::test]
+async fn rating_empty() {
+    let ctx = sonar::test::create_context_memory().await;
+    let user = sonar::test::create_user(&ctx, "user").await;
+    let ratings = sonar::rating_list(&ctx, user.id).await.unwrap();
+    assert!(ratings.is_empty());
+}
+
+#[tokio::test]
+async fn rating_set_and_update() {
+    let ctx = sonar::test::create_context_memory().await;
+    let user = sonar::test::create_user(&ctx, "user").await;
+    let (artist, album, track) =
+        sonar::test::create_artist_album_track(&ctx, "artist", "album", "track").await;
+
+    sonar::rating_set(&ctx, user.id, album.id.into(), rating(3))
+        .await
+        .unwrap();
+    sonar::rating_set(&ctx, user.id, track.id.into(), rating(5))
+        .await
+        .unwrap();
+    sonar::rating_set(&ctx, user.id, album.id.into(), rating(4))
+        .await
+        .unwrap();
+
+    let ids = [
+        SonarId::from(artist.id),
+        SonarId::from(album.id),
+        SonarId::from(track.id),
+    ];
+    let mut ratings = sonar::rating_get_bulk(&ctx, user.id, &ids).await.unwrap();
+    ratings.sort_by_key(|r| r.rating);
+    assert_eq!(ratings.len(), 2);
+    assert_eq!(ratings[0].id, SonarId::from(album.id));
+    assert_eq!(ratings[0].rating, rating(4));
+    assert_eq!(ratings[1].id, SonarId::from(track.id));
+    assert_eq!(ratings[1].rating, rating(5));
+}
+
+#[tokio::test]
+async fn rating_remove() {
+    let ctx = sonar::test::create_context_memory().await;
+    let user = sonar::test::create_user(&ctx, "user").await;
+    let (artist, _, _) =
+        sonar::test::create_artist_album_track(&ctx, "artist", "album", "track").await;
+
+    sonar::rating_set(&ctx, user.id, artist.id.into(), rating(2))
+        .await
+        .unwrap();
+    sonar::rating_remove(&ctx, user.id, artist.id.into())
+        .await
+        .unwrap();
+    let ratings = sonar::rating_list(&ctx, user.id).await.unwrap();
+    assert!(ratings.is_empty());
+}
+
+#[tokio::test]
+async fn rating_invalid_item() {
+    let ctx = sonar::test::create_context_memory().await;
+    let user = sonar::test::create_user(&ctx, "user").await;
+    let playlist = sonar::test::create_playlist(&ctx, user.id, "playlist").await;
+    let err = sonar::rating_set(&ctx, user.id, playlist.id.into(), rating(1))
+        .await
+        .unwrap_err();
+    assert_eq!(err.kind(), sonar::ErrorKind::Invalid);
+}
+
+#[tokio::test]
+async fn rating_average() {
+    let ctx = sonar::test::create_context_memory().await;
+    let user1 = sonar::test::create_user(&ctx, "user1").await;
+    let user2 = sonar::test::create_user(&ctx, "user2").await;
+    let (_, album, track) =
+        sonar::test::create_artist_album_track(&ctx, "artist", "album", "track").await;
+
+    sonar::rating_set(&ctx, user1.id, album.id.into(), rating(2))
+        .await
+        .unwrap();
+    sonar::rating_set(&ctx, user2.id, album.id.into(), rating(5))
+        .await
+        .unwrap();
+
+    let averages = sonar::rating_average_get_bulk(&ctx, &[album.id.into(), track.id.into()])
+        .await
+        .unwrap();
+    assert_eq!(averages.len(), 1);
+    assert_eq!(averages[0].id, SonarId::from(album.id));
+    assert_eq!(averages[0].average, 3.5);
+    assert_eq!(averages[0].count, 2);
+}
+
+#[tokio::test]
+async fn album_list_highest_rated() {
+    let ctx = sonar::test::create_context_memory().await;
+    let user1 = sonar::test::create_user(&ctx, "user1").await;
+    let user2 = sonar::test::create_user(&ctx, "user2").await;
+    let artist = sonar::test::create_artist(&ctx, "artist").await;
+    let album1 = sonar::test::create_album(&ctx, artist.id, "album1").await;
+    let album2 = sonar::test::create_album(&ctx, artist.id, "album2").await;
+    let _album3 = sonar::test::create_album(&ctx, artist.id, "album3").await;
+
+    sonar::rating_set(&ctx, user1.id, album1.id.into(), rating(3))
+        .await
+        .unwrap();
+    sonar::rating_set(&ctx, user1.id, album2.id.into(), rating(4))
+        .await
+        .unwrap();
+    sonar::rating_set(&ctx, user2.id, album2.id.into(), rating(5))
+        .await
+        .unwrap();
+
+    let albums = sonar::album_list_highest_rated(&ctx, Default::default())
+        .await
+        .unwrap();
+    assert_eq!(albums.len(), 2);
+    assert_eq!(albums[0].id, album2.id);
+    assert_eq!(albums[1].id, album1.id);
+}
+
+#[tokio::test]
+async fn rating_user_delete() {
+    let ctx = sonar::test::create_context_memory().await;
+    let user = sonar::test::create_user(&ctx, "user").await;
+    let (_, _, track) =
+        sonar::test::create_artist_album_track(&ctx, "artist", "album", "track").await;
+    sonar::rating_set(&ctx, user.id, track.id.into(), rating(4))
+        .await
+        .unwrap();
+
+    sonar::user_delete(&ctx, user.id).await.unwrap();
+    let averages = sonar::rating_average_get_bulk(&ctx, &[SonarId::from(track.id)])
+        .await
+        .unwrap();
+    assert!(averages.is_empty());
+}
@@ -0,0 +1,167 @@
+use std::time::Duration;
+
+use sonar::{DailyMixParams, ErrorKind, ScrobbleCreate, Timestamp, TrackId, UserId};
+
+const DAY: u64 = 24 * 60 * 60;
+
+async fn scrobble(ctx: &sonar::Context, user: UserId, track: TrackId, listen_at: u64) {
+    sonar::scrobble_create(
+        ctx,
+        ScrobbleCreate {
+            user,
+            track,
+            listen_at: Timestamp::from_seconds(listen_at),
+            listen_duration: Duration::from_secs(60),
+            listen_device: "test".to_string(),
+            properties: Default::default(),
+        },
+    )
+    .await
+    .unwrap();
+}
+
+#[tokio::test]
+async fn recommend_similar_tracks_session() {
+    let ctx = sonar::test::create_context_memory().await;
+    let user = sonar::test::create_user(&ctx, "user").await;
+    let (_, _, track1) =
+        sonar::test::create_artist_album_track(&ctx, "artist1", "album1", "track1").await;
+    let (_, _, track2) =
+        sonar::test::create_artist_album_track(&ctx, "artist2", "album2", "track2").await;
+    let (_, _, track3) =
+        sonar::test::create_artist_album_track(&ctx, "artist3", "album3", "track3").await;
+
+    scrobble(&ctx, user.id, track1.id, 1000).await;
+    scrobble(&ctx, user.id, track2.id, 1200).await;
+    scrobble(&ctx, user.id, track3.id, 1000 + 10 * DAY).await;
+
+    let similar = sonar::recommend_similar_tracks(&ctx, track1.id.into(), 10)
+        .await
+        .unwrap();
+    assert_eq!(similar, vec![track2.id, track3.id]);
+
+    let similar = sonar::recommend_similar_tracks(&ctx, track1.id.into(), 1)
+        .await
+        .unwrap();
+    assert_eq!(similar, vec![track2.id]);
+}
+
+#[tokio::test]
+async fn recommend_similar_tracks_album() {
+    let ctx = sonar::test::create_context_memory().await;
+    let (artist, album, track1) =
+        sonar::test::create_artist_album_track(&ctx, "artist", "album1", "track1").await;
+    let track2 = sonar::test::create_track(&ctx, album.id, "track2").await;
+    let album2 = sonar::test::create_album(&ctx, artist.id, "album2").await;
+    let track3 = sonar::test::create_track(&ctx, album2.id, "track3").await;
+
+    // tracks of the album itself are not recommended
+    let similar = sonar::recommend_similar_tracks(&ctx, album.id.into(), 10)
+        .await
+        .unwrap();
+    assert_eq!(similar, vec![track3.id]);
+    assert!(!similar.contains(&track1.id));
+    assert!(!similar.contains(&track2.id));
+
+    let user = sonar::test::create_user(&ctx, "user").await;
+    let playlist = sonar::test::create_playlist(&ctx, user.id, "playlist").await;
+    let err = sonar::recommend_similar_tracks(&ctx, playlist.id.into(), 10)
+        .await
+        .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::Invalid);
+}
+
+#[tokio::test]
+async fn recommend_similar_artists_favorites() {
+    let ctx = sonar::test::create_context_memory().await;
+    let user = sonar::test::create_user(&ctx, "user").await;
+    let artist1 = sonar::test::create_artist(&ctx, "artist1").await;
+    let artist2 = sonar::test::create_artist(&ctx, "artist2").await;
+    sonar::test::create_artist(&ctx, "artist3").await;
+    sonar::favorite_add(&ctx, user.id, artist1.id.into())
+        .await
+        .unwrap();
+    sonar::favorite_add(&ctx, user.id, artist2.id.into())
+        .await
+        .unwrap();
+
+    let similar = sonar::recommend_similar_artists(&ctx, artist1.id, 10)
+        .await
+        .unwrap();
+    assert_eq!(similar, vec![artist2.id]);
+}
+
+#[tokio::test]
+async fn recommend_daily_mix() {
+    let ctx = sonar::test::create_context_memory().await;
+    let user = sonar::test::create_user(&ctx, "user").await;
+    let (artist, album, track1) =
+        sonar::test::create_artist_album_track(&ctx, "artist", "album", "track1").await;
+    let track2 = sonar::test::create_track(&ctx, album.id, "track2").await;
+    let now = Timestamp::now().seconds();
+
+    let mixes = sonar::recommend_daily_mixes(&ctx, DailyMixParams::new(user.id))
+        .await
+        .unwrap();
+    assert!(mixes.is_empty());
+
+    scrobble(&ctx, user.id, track1.id, now - 2 * DAY).await;
+    let mixes = sonar::recommend_daily_mixes(&ctx, DailyMixParams::new(user.id))
+        .await
+        .unwrap();
+    assert_eq!(mixes.len(), 1);
+    assert_eq!(mixes[0].artist, artist.id);
+    assert_eq!(mixes[0].tracks.len(), 2);
+    assert!(mixes[0].tracks.contains(&track1.id));
+    assert!(mixes[0].tracks.contains(&track2.id));
+
+    // tracks listened in the last day are left out
+    scrobble(&ctx, user.id, track2.id, now - 60).await;
+    let mixes = sonar::recommend_daily_mixes(&ctx, DailyMixParams::new(user.id))
+        .await
+        .unwrap();
+    assert_eq!(mixes[0].tracks, vec![track1.id]);
+}
+
+#[tokio::test]
+async fn recommend_daily_mix_refresh() {
+    let ctx = sonar::test::create_context_memory().await;
+    let user = sonar::test::create_user(&ctx, "user").await;
+    let (_, _, track) =
+        sonar::test::create_artist_album_track(&ctx, "artist", "album", "track").await;
+    scrobble(
+        &ctx,
+        user.id,
+        track.id,
+        Timestamp::now().seconds() - 2 * DAY,
+    )
+    .await;
+
+    let playlists = sonar::recommend_daily_mix_refresh(&ctx, DailyMixParams::new(user.id))
+        .await
+        .unwrap();
+    assert_eq!(playlists.len(), 1);
+    assert_eq!(playlists[0].name, "Daily Mix 1");
+    assert_eq!(playlists[0].owner, user.id);
+    assert_eq!(playlists[0].track_count, 1);
+    assert!(playlists[0].properties.contains_key(sonar::prop::DAILY_MIX));
+
+    // the existing playlist is reused
+    let refreshed = sonar::recommend_daily_mix_refresh(&ctx, DailyMixParams::new(user.id))
+        .await
+        .unwrap();
+    assert_eq!(refreshed.len(), 1);
+    assert_eq!(refreshed[0].id, playlists[0].id);
+
+    // mixes that are no longer generated are deleted
+    let mut params = DailyMixParams::new(user.id);
+    params.mixes = 0;
+    let refreshed = sonar::recommend_daily_mix_refresh(&ctx, params)
+        .await
+        .unwrap();
+    assert!(refreshed.is_empty());
+    let err = sonar::playlist_get(&ctx, playlists[0].id)
+        .await
+        .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::NotFound);
+}
@@ -0,0 +1,178 @@
+use sonar::{ScrobbleImport, ScrobbleImportEntry, ScrobbleImportFormat, Timestamp};
+
+fn entry(artist: &str, album: Option<&str>, title: &str, listen_at: u64) -> ScrobbleImportEntry {
+    ScrobbleImportEntry {
+        artist: artist.to_string(),
+        album: album.map(ToString::to_string),
+        title: title.to_string(),
+        duration: None,
+        recording_mbid: None,
+        listen_at: Timestamp::from_seconds(listen_at),
+    }
+}
+
+fn import(user: sonar::UserId, entries: Vec<ScrobbleImportEntry>) -> ScrobbleImport {
+    ScrobbleImport {
+        user,
+        scrobbler: Some("listenbrainz".to_string()),
+        listen_device: "import".to_string(),
+        entries,
+    }
+}
+
+#[tokio::test]
+async fn scrobble_import_match_by_name() {
+    let ctx = sonar::test::create_context_memory().await;
+    let user = sonar::test::create_user(&ctx, "user").await;
+    let (_, _, track) =
+        sonar::test::create_artist_album_track(&ctx, "Artist", "Album", "Track").await;
+
+    let report = sonar::scrobble_import(
+        &ctx,
+        import(
+            user.id,
+            vec![
+                entry("artist", Some("album"), "track", 1000),
+                entry("Artist", None, "Missing", 2000),
+            ],
+        ),
+    )
+    .await
+    .unwrap();
+    assert_eq!(report.imported, 1);
+    assert_eq!(report.duplicates, 0);
+    assert_eq!(report.unmatched.len(), 1);
+    assert_eq!(report.unmatched[0].title, "Missing");
+
+    let scrobbles = sonar::scrobble_list(&ctx, Default::default())
+        .await
+        .unwrap();
+    assert_eq!(scrobbles.len(), 1);
+    assert_eq!(scrobbles[0].track, track.id);
+    assert_eq!(scrobbles[0].user, user.id);
+    assert_eq!(scrobbles[0].listen_at, Timestamp::from_seconds(1000));
+}
+
+#[tokio::test]
+async fn scrobble_import_prefers_album() {
+    let ctx = sonar::test::create_context_memory().await;
+    let user = sonar::test::create_user(&ctx, "user").await;
+    let (artist, _, _) =
+        sonar::test::create_artist_album_track(&ctx, "Artist", "Album1", "Track").await;
+    let album2 = sonar::test::create_album(&ctx, artist.id, "Album2").await;
+    let track2 = sonar::test::create_track(&ctx, album2.id, "Track").await;
+
+    let report = sonar::scrobble_import(
+        &ctx,
+        import(
+            user.id,
+            vec![entry("Artist", Some("Album2"), "Track", 1000)],
+        ),
+    )
+    .await
+    .unwrap();
+    assert_eq!(report.imported, 1);
+    let scrobbles = sonar::scrobble_list(&ctx, Default::default())
+        .await
+        .unwrap();
+    assert_eq!(scrobbles[0].track, track2.id);
+}
+
+#[tokio::test]
+async fn scrobble_import_match_by_mbid() {
+    let ctx = sonar::test::create_context_memory().await;
+    let user = sonar::test::create_user(&ctx, "user").await;
+    let (_, _, track) =
+        sonar::test::create_artist_album_track(&ctx, "Artist", "Album", "Track").await;
+    let mbid = "0d3d6d2d-8bd2-4f4c-a5c7-e5c5d7c0b0a4";
+    sonar::track_update(
+        &ctx,
+        track.id,
+        sonar::TrackUpdate {
+            properties: vec![sonar::PropertyUpdate::set(
+                sonar::prop::EXTERNAL_MUSICBRAINZ_ID,
+                sonar::PropertyValue::new_uncheked(mbid),
+            )],
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap();
+
+    let mut renamed = entry("Someone", None, "Renamed", 1000);
+    renamed.recording_mbid = Some(mbid.to_string());
+    let report = sonar::scrobble_import(&ctx, import(user.id, vec![renamed]))
+        .await
+        .unwrap();
+    assert_eq!(report.imported, 1);
+    assert!(report.unmatched.is_empty());
+}
+
+#[tokio::test]
+async fn scrobble_import_duplicates() {
+    let ctx = sonar::test::create_context_memory().await;
+    let user = sonar::test::create_user(&ctx, "user").await;
+    sonar::test::create_artist_album_track(&ctx, "Artist", "Album", "Track").await;
+
+    let entries = vec![entry("Artist", None, "Track", 1000)];
+    sonar::scrobble_import(&ctx, import(user.id, entries.clone()))
+        .await
+        .unwrap();
+    let report = sonar::scrobble_import(&ctx, import(user.id, entries))
+        .await
+        .unwrap();
+    assert_eq!(report.imported, 0);
+    assert_eq!(report.duplicates, 1);
+}
+
+#[test]
+fn scrobble_import_parse_listenbrainz() {
+    let content = r#"{"listened_at": 1700000000, "track_metadata": {"artist_name": "Artist", "track_name": "Track", "release_name": "Album", "additional_info": {"duration_ms": 180000, "recording_mbid": "mbid"}}}
+{"listened_at": 1700000300, "track_metadata": {"artist_name": "Artist", "track_name": "Other"}}
+"#;
+    let entries = ScrobbleImportFormat::ListenBrainz.parse(content).unwrap();
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[0].album.as_deref(), Some("Album"));
+    assert_eq!(
+        entries[0].duration,
+        Some(std::time::Duration::from_secs(180))
+    );
+    assert_eq!(entries[0].recording_mbid.as_deref(), Some("mbid"));
+    assert_eq!(entries[1].listen_at, Timestamp::from_seconds(1700000300));
+    assert_eq!(entries[1].album, None);
+}
+
+#[test]
+fn scrobble_import_parse_lastfm_json() {
+    let content = r##"[{"recenttracks": {"track": [
+        {"artist": {"#text": "Artist"}, "album": {"#text": "Album"}, "name": "Now Playing", "mbid": "", "@attr": {"nowplaying": "true"}},
+        {"artist": {"#text": "Artist"}, "album": {"#text": ""}, "name": "Track", "mbid": "mbid", "date": {"uts": "1700000000", "#text": "14 Nov 2023, 22:13"}}
+    ]}}]"##;
+    let entries = ScrobbleImportFormat::LastFmJson.parse(content).unwrap();
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0].title, "Track");
+    assert_eq!(entries[0].album, None);
+    assert_eq!(entries[0].recording_mbid.as_deref(), Some("mbid"));
+    assert_eq!(entries[0].listen_at, Timestamp::from_seconds(1700000000));
+}
+
+#[test]
+fn scrobble_import_parse_lastfm_csv() {
+    let headerless = "Artist,\"Album, Deluxe\",Track,01 Jan 2020 00:01\n";
+    let entries = ScrobbleImportFormat::LastFmCsv.parse(headerless).unwrap();
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0].album.as_deref(), Some("Album, Deluxe"));
+    assert_eq!(entries[0].listen_at, Timestamp::from_seconds(1577836860));
+
+    let header = "uts,utc_time,artist,artist_mbid,album,album_mbid,track,track_mbid\n\
+        1577836860,\"01 Jan 2020, 00:01\",Artist,,Album,,Track,mbid\n";
+    let entries = ScrobbleImportFormat::LastFmCsv.parse(header).unwrap();
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0].title, "Track");
+    assert_eq!(entries[0].recording_mbid.as_deref(), Some("mbid"));
+    assert_eq!(entries[0].listen_at, Timestamp::from_seconds(1577836860));
+
+    assert!(ScrobbleImportFormat::LastFmCsv
+        .parse("Artist,Album,Track,yesterday\n")
+        .is_err());
+}
@@ -1,4 +1,4 @@
-//! NOTE: this tests assume the builtin search engine that just uses a simple 'name LIKE %?%' query.
+//! NOTE: this tests assume the builtin search engine, backed by an sqlite FTS5 index.
 
 #[tokio::test]
 async fn search_basic() {
@@ -14,6 +14,7 @@ async fn search_basic() {
             query: "".parse().unwrap(),
             limit: None,
             flags: sonar::SearchQuery::FLAG_ALL,
+            library: None,
         },
     )
     .await
@@ -47,6 +48,7 @@ async fn search_filter() {
             query: "artist".parse().unwrap(),
             limit: None,
             flags: sonar::SearchQuery::FLAG_ALL,
+            library: None,
         },
     )
     .await
@@ -63,3 +65,205 @@ async fn search_filter() {
 
     assert_eq!(artists[0].id, artist.id);
 }
+
+async fn search_query(
+    ctx: &sonar::Context,
+    user_id: sonar::UserId,
+    query: &str,
+    limit: Option<u32>,
+) -> sonar::SearchResults {
+    sonar::search(
+        ctx,
+        user_id,
+        sonar::SearchQuery {
+            query: query.to_string(),
+            limit,
+            flags: sonar::SearchQuery::FLAG_ALL,
+            library: None,
+        },
+    )
+    .await
+    .unwrap()
+}
+
+#[tokio::test]
+async fn search_ignores_diacritics() {
+    let ctx = sonar::test::create_context_memory().await;
+    let user = sonar::test::create_user(&ctx, "user").await;
+    let artist = sonar::test::create_artist(&ctx, "Björk").await;
+
+    let result = search_query(&ctx, user.id, "bjork", None).await;
+    let artists = result.artists().collect::<Vec<_>>();
+    assert_eq!(artists.len(), 1);
+    assert_eq!(artists[0].id, artist.id);
+}
+
+#[tokio::test]
+async fn search_ignores_word_order() {
+    let ctx = sonar::test::create_context_memory().await;
+    let user = sonar::test::create_user(&ctx, "user").await;
+    let (_artist, _album, track) =
+        sonar::test::create_artist_album_track(&ctx, "artist", "album", "Wish You Were Here").await;
+
+    let result = search_query(&ctx, user.id, "here wish", None).await;
+    let tracks = result.tracks().collect::<Vec<_>>();
+    assert_eq!(result.results.len(), 1);
+    assert_eq!(tracks[0].id, track.id);
+}
+
+#[tokio::test]
+async fn search_ranks_and_limits() {
+    let ctx = sonar::test::create_context_memory().await;
+    let user = sonar::test::create_user(&ctx, "user").await;
+    let artist = sonar::test::create_artist(&ctx, "Blue").await;
+    let album = sonar::test::create_album(&ctx, artist.id, "Blue Blue Blue").await;
+    sonar::test::create_track(&ctx, album.id, "Blue in Green, a very long track name").await;
+
+    let result = search_query(&ctx, user.id, "blue", None).await;
+    assert_eq!(result.results.len(), 3);
+    match &result.results[0] {
+        sonar::SearchResult::Album(a) => assert_eq!(a.id, album.id),
+        other => panic!("unexpected first result: {other:?}"),
+    }
+
+    let result = search_query(&ctx, user.id, "blue", Some(2)).await;
+    assert_eq!(result.results.len(), 2);
+}
+
+#[tokio::test]
+async fn search_index_follows_changes() {
+    let ctx = sonar::test::create_context_memory().await;
+    let user = sonar::test::create_user(&ctx, "user").await;
+    let artist = sonar::test::create_artist(&ctx, "artist").await;
+
+    sonar::artist_update(
+        &ctx,
+        artist.id,
+        sonar::ArtistUpdate {
+            name: sonar::ValueUpdate::set("renamed".to_string()),
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        search_query(&ctx, user.id, "artist", None)
+            .await
+            .results
+            .len(),
+        0
+    );
+    assert_eq!(
+        search_query(&ctx, user.id, "renamed", None)
+            .await
+            .results
+            .len(),
+        1
+    );
+
+    sonar::artist_delete(&ctx, artist.id).await.unwrap();
+    assert_eq!(
+        search_query(&ctx, user.id, "renamed", None)
+            .await
+            .results
+            .len(),
+        0
+    );
+}
+
+#[tokio::test]
+async fn search_playlists_only_from_owner() {
+    let ctx = sonar::test::create_context_memory().await;
+    let user1 = sonar::test::create_user(&ctx, "user1").await;
+    let user2 = sonar::test::create_user(&ctx, "user2").await;
+    let playlist = sonar::test::create_playlist(&ctx, user1.id, "road trip").await;
+
+    let result = search_query(&ctx, user1.id, "road", None).await;
+    let playlists = result.playlists().collect::<Vec<_>>();
+    assert_eq!(playlists.len(), 1);
+    assert_eq!(playlists[0].id, playlist.id);
+
+    let result = search_query(&ctx, user2.id, "road", None).await;
+    assert_eq!(result.results.len(), 0);
+}
+
+#[tokio::test]
+async fn search_query_field_filters() {
+    let ctx = sonar::test::create_context_memory().await;
+    let user = sonar::test::create_user(&ctx, "user").await;
+    let (_artist, album, track) =
+        sonar::test::create_artist_album_track(&ctx, "Pink Floyd", "Animals", "Dogs").await;
+    sonar::test::create_artist_album_track(&ctx, "Dogs Collective", "Other Animals", "Cats").await;
+
+    let result = search_query(&ctx, user.id, "artist:\"pink floyd\"", None).await;
+    assert_eq!(result.results.len(), 3);
+
+    let result = search_query(&ctx, user.id, "dogs album:animals", None).await;
+    let tracks = result.tracks().collect::<Vec<_>>();
+    assert_eq!(result.results.len(), 1);
+    assert_eq!(tracks[0].id, track.id);
+
+    let result = search_query(&ctx, user.id, "animals -other", None).await;
+    let albums = result.albums().collect::<Vec<_>>();
+    assert_eq!(result.results.len(), 1);
+    assert_eq!(albums[0].id, album.id);
+
+    let result = search_query(&ctx, user.id, "\"dogs collective\"", None).await;
+    assert_eq!(result.results.len(), 1);
+    assert!(result.artists().next().is_some());
+
+    sonar::favorite_add(&ctx, user.id, track.id.into())
+        .await
+        .unwrap();
+    let result = search_query(&ctx, user.id, "favorite:true", None).await;
+    let tracks = result.tracks().collect::<Vec<_>>();
+    assert_eq!(result.results.len(), 1);
+    assert_eq!(tracks[0].id, track.id);
+}
+
+#[tokio::test]
+async fn search_query_filters_page_and_limit() {
+    let ctx = sonar::test::create_context_memory().await;
+    let user = sonar::test::create_user(&ctx, "user").await;
+    let artist = sonar::test::create_artist(&ctx, "artist").await;
+    let album = sonar::test::create_album(&ctx, artist.id, "album").await;
+    let mut tracks = Vec::new();
+    for i in 0..250 {
+        tracks.push(sonar::test::create_track(&ctx, album.id, &format!("track {i}")).await);
+    }
+    // only the last tracks match, past the first batch of candidates
+    for track in &tracks[245..] {
+        sonar::favorite_add(&ctx, user.id, track.id.into())
+            .await
+            .unwrap();
+    }
+
+    let result = search_query(&ctx, user.id, "favorite:true", None).await;
+    assert_eq!(result.results.len(), 5);
+
+    let result = search_query(&ctx, user.id, "favorite:true", Some(2)).await;
+    let ids = result.tracks().map(|track| track.id).collect::<Vec<_>>();
+    assert_eq!(ids, vec![tracks[245].id, tracks[246].id]);
+
+    let result = search_query(&ctx, user.id, "favorite:false", Some(10)).await;
+    assert_eq!(result.results.len(), 10);
+}
+
+#[tokio::test]
+async fn search_query_invalid() {
+    let ctx = sonar::test::create_context_memory().await;
+    let user = sonar::test::create_user(&ctx, "user").await;
+
+    let result = sonar::search(
+        &ctx,
+        user.id,
+        sonar::SearchQuery {
+            query: "year:soon".to_string(),
+            limit: None,
+            flags: sonar::SearchQuery::FLAG_ALL,
+            library: None,
+        },
+    )
+    .await;
+    assert_eq!(result.unwrap_err().kind(), sonar::ErrorKind::Invalid);
+}
@@ -0,0 +1,111 @@
+async fn create_context(dir: &tempfile::TempDir) -> sonar::Context {
+    let config = sonar::Config::new(
+        ":memory:",
+        sonar::StorageBackend::Memory,
+        sonar::SearchBackend::Tantivy {
+            path: dir.path().join("search"),
+        },
+    );
+    sonar::test::create_context(config).await
+}
+
+async fn search_query(
+    ctx: &sonar::Context,
+    user_id: sonar::UserId,
+    query: &str,
+) -> sonar::SearchResults {
+    sonar::search(
+        ctx,
+        user_id,
+        sonar::SearchQuery {
+            query: query.to_string(),
+            limit: None,
+            flags: sonar::SearchQuery::FLAG_ALL,
+            library: None,
+        },
+    )
+    .await
+    .unwrap()
+}
+
+#[tokio::test]
+async fn search_tantivy_empty_query() {
+    let dir = tempfile::tempdir().unwrap();
+    let ctx = create_context(&dir).await;
+    let user = sonar::test::create_user(&ctx, "user").await;
+    sonar::test::create_artist_album_track(&ctx, "artist", "album", "track").await;
+
+    let result = search_query(&ctx, user.id, "").await;
+    assert_eq!(result.results.len(), 3);
+}
+
+#[tokio::test]
+async fn search_tantivy_prefix_and_typos() {
+    let dir = tempfile::tempdir().unwrap();
+    let ctx = create_context(&dir).await;
+    let user = sonar::test::create_user(&ctx, "user").await;
+    let artist = sonar::test::create_artist(&ctx, "Björk").await;
+    let album = sonar::test::create_album(&ctx, artist.id, "Homogenic").await;
+
+    let result = search_query(&ctx, user.id, "bjork").await;
+    let artists = result.artists().collect::<Vec<_>>();
+    assert_eq!(artists.len(), 1);
+    assert_eq!(artists[0].id, artist.id);
+
+    let result = search_query(&ctx, user.id, "homo").await;
+    let albums = result.albums().collect::<Vec<_>>();
+    assert_eq!(albums.len(), 1);
+    assert_eq!(albums[0].id, album.id);
+
+    let result = search_query(&ctx, user.id, "homgenic").await;
+    let albums = result.albums().collect::<Vec<_>>();
+    assert_eq!(albums.len(), 1);
+    assert_eq!(albums[0].id, album.id);
+}
+
+#[tokio::test]
+async fn search_tantivy_index_follows_changes() {
+    let dir = tempfile::tempdir().unwrap();
+    let ctx = create_context(&dir).await;
+    let user = sonar::test::create_user(&ctx, "user").await;
+    let artist = sonar::test::create_artist(&ctx, "artist").await;
+
+    sonar::artist_update(
+        &ctx,
+        artist.id,
+        sonar::ArtistUpdate {
+            name: sonar::ValueUpdate::set("renamed".to_string()),
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap();
+    assert_eq!(search_query(&ctx, user.id, "artist").await.results.len(), 0);
+    assert_eq!(
+        search_query(&ctx, user.id, "renamed").await.results.len(),
+        1
+    );
+
+    sonar::artist_delete(&ctx, artist.id).await.unwrap();
+    assert_eq!(
+        search_query(&ctx, user.id, "renamed").await.results.len(),
+        0
+    );
+}
+
+#[tokio::test]
+async fn search_tantivy_playlists_only_from_owner() {
+    let dir = tempfile::tempdir().unwrap();
+    let ctx = create_context(&dir).await;
+    let user1 = sonar::test::create_user(&ctx, "user1").await;
+    let user2 = sonar::test::create_user(&ctx, "user2").await;
+    let playlist = sonar::test::create_playlist(&ctx, user1.id, "road trip").await;
+
+    let result = search_query(&ctx, user1.id, "road").await;
+    let playlists = result.playlists().collect::<Vec<_>>();
+    assert_eq!(playlists.len(), 1);
+    assert_eq!(playlists[0].id, playlist.id);
+
+    let result = search_query(&ctx, user2.id, "road").await;
+    assert_eq!(result.results.len(), 0);
+}
@@ -0,0 +1,195 @@
+use sonar::{
+    ErrorKind, Genre, SmartPlaylistCreate, SmartPlaylistRule, SmartPlaylistRules,
+    SmartPlaylistSort, UserId,
+};
+
+async fn create_smart_playlist(
+    ctx: &sonar::Context,
+    owner: UserId,
+    rules: SmartPlaylistRules,
+) -> sonar::Playlist {
+    sonar::smart_playlist_create(
+        ctx,
+        SmartPlaylistCreate {
+            name: "smart".to_string(),
+            owner,
+            rules,
+            properties: Default::default(),
+        },
+    )
+    .await
+    .unwrap()
+}
+
+async fn playlist_track_ids(ctx: &sonar::Context, id: sonar::PlaylistId) -> Vec<sonar::TrackId> {
+    sonar::playlist_list_tracks(ctx, id, Default::default())
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|track| track.track)
+        .collect()
+}
+
+#[tokio::test]
+async fn smart_playlist_genre() {
+    let ctx = sonar::test::create_context_memory().await;
+    let user = sonar::test::create_user(&ctx, "user").await;
+    let (artist, album, track1) =
+        sonar::test::create_artist_album_track(&ctx, "artist", "album1", "track1").await;
+    let album2 = sonar::test::create_album(&ctx, artist.id, "album2").await;
+    sonar::test::create_track(&ctx, album2.id, "track2").await;
+    sonar::album_update(
+        &ctx,
+        album.id,
+        sonar::AlbumUpdate {
+            genres: vec![sonar::GenreUpdate {
+                action: sonar::GenreUpdateAction::Set,
+                genre: Genre::new_unchecked("rock"),
+            }],
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap();
+
+    let playlist = create_smart_playlist(
+        &ctx,
+        user.id,
+        SmartPlaylistRules {
+            rules: vec![SmartPlaylistRule::Genre(Genre::new_unchecked("rock"))],
+            ..Default::default()
+        },
+    )
+    .await;
+    assert!(playlist.smart);
+    assert_eq!(playlist.track_count, 1);
+    assert_eq!(playlist_track_ids(&ctx, playlist.id).await, vec![track1.id]);
+
+    let smart = sonar::smart_playlist_get(&ctx, playlist.id).await.unwrap();
+    assert_eq!(smart.rules.rules.len(), 1);
+    assert!(smart.refreshed_at.is_some());
+}
+
+#[tokio::test]
+async fn smart_playlist_favorite_refresh() {
+    let ctx = sonar::test::create_context_memory().await;
+    let user = sonar::test::create_user(&ctx, "user").await;
+    let (_, album, track1) =
+        sonar::test::create_artist_album_track(&ctx, "artist", "album", "track1").await;
+    let track2 = sonar::test::create_track(&ctx, album.id, "track2").await;
+    sonar::favorite_add(&ctx, user.id, track1.id.into())
+        .await
+        .unwrap();
+
+    let playlist = create_smart_playlist(
+        &ctx,
+        user.id,
+        SmartPlaylistRules {
+            rules: vec![SmartPlaylistRule::Favorite],
+            sort: SmartPlaylistSort::Name,
+            ..Default::default()
+        },
+    )
+    .await;
+    assert_eq!(playlist_track_ids(&ctx, playlist.id).await, vec![track1.id]);
+
+    sonar::favorite_add(&ctx, user.id, track2.id.into())
+        .await
+        .unwrap();
+    let playlist = sonar::smart_playlist_refresh(&ctx, playlist.id)
+        .await
+        .unwrap();
+    assert_eq!(playlist.track_count, 2);
+    assert_eq!(
+        playlist_track_ids(&ctx, playlist.id).await,
+        vec![track1.id, track2.id]
+    );
+}
+
+#[tokio::test]
+async fn smart_playlist_sort_limit() {
+    let ctx = sonar::test::create_context_memory().await;
+    let user = sonar::test::create_user(&ctx, "user").await;
+    let (_, album, track_a) =
+        sonar::test::create_artist_album_track(&ctx, "artist", "album", "a").await;
+    let track_b = sonar::test::create_track(&ctx, album.id, "b").await;
+    let track_c = sonar::test::create_track(&ctx, album.id, "c").await;
+
+    let playlist = create_smart_playlist(
+        &ctx,
+        user.id,
+        SmartPlaylistRules {
+            sort: SmartPlaylistSort::Name,
+            descending: true,
+            limit: Some(2),
+            ..Default::default()
+        },
+    )
+    .await;
+    assert_eq!(
+        playlist_track_ids(&ctx, playlist.id).await,
+        vec![track_c.id, track_b.id]
+    );
+
+    let playlist = sonar::smart_playlist_update(
+        &ctx,
+        playlist.id,
+        SmartPlaylistRules {
+            sort: SmartPlaylistSort::Name,
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        playlist_track_ids(&ctx, playlist.id).await,
+        vec![track_a.id, track_b.id, track_c.id]
+    );
+}
+
+#[tokio::test]
+async fn smart_playlist_not_editable() {
+    let ctx = sonar::test::create_context_memory().await;
+    let user = sonar::test::create_user(&ctx, "user").await;
+    let (_, _, track) =
+        sonar::test::create_artist_album_track(&ctx, "artist", "album", "track").await;
+
+    let playlist = create_smart_playlist(&ctx, user.id, Default::default()).await;
+    let err = sonar::playlist_insert_tracks(&ctx, playlist.id, &[track.id])
+        .await
+        .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::Invalid);
+    let err = sonar::playlist_clear_tracks(&ctx, playlist.id)
+        .await
+        .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::Invalid);
+
+    let regular = sonar::test::create_playlist(&ctx, user.id, "regular").await;
+    assert!(!regular.smart);
+    assert_eq!(
+        sonar::smart_playlist_get(&ctx, regular.id)
+            .await
+            .unwrap_err()
+            .kind(),
+        ErrorKind::NotFound
+    );
+
+    sonar::playlist_delete(&ctx, playlist.id).await.unwrap();
+}
+
+#[test]
+fn smart_playlist_rule_parse() {
+    assert_eq!(
+        "min-listens:5".parse::<SmartPlaylistRule>().unwrap(),
+        SmartPlaylistRule::MinListens(5)
+    );
+    assert_eq!(
+        "not-played-within:30d"
+            .parse::<SmartPlaylistRule>()
+            .unwrap()
+            .to_string(),
+        "not-played-within:30d"
+    );
+    assert!("loudness:10".parse::<SmartPlaylistRule>().is_err());
+    assert!("played-within:soon".parse::<SmartPlaylistRule>().is_err());
+}
@@ -0,0 +1,177 @@
+use std::time::Duration;
+
+use sonar::{Genre, ScrobbleCreate, StatsParams, StatsPeriod, Timestamp, TrackId, UserId};
+
+const DAY: u64 = 24 * 60 * 60;
+// 2024-01-01 00:00:00 UTC, a monday
+const MONDAY: u64 = 1704067200;
+
+async fn scrobble(ctx: &sonar::Context, user: UserId, track: TrackId, listen_at: u64) {
+    sonar::scrobble_create(
+        ctx,
+        ScrobbleCreate {
+            user,
+            track,
+            listen_at: Timestamp::from_seconds(listen_at),
+            listen_duration: Duration::from_secs(60),
+            listen_device: "test".to_string(),
+            properties: Default::default(),
+        },
+    )
+    .await
+    .unwrap();
+}
+
+#[tokio::test]
+async fn stats_empty() {
+    let ctx = sonar::test::create_context_memory().await;
+    let user = sonar::test::create_user(&ctx, "user").await;
+
+    let stats = sonar::stats_get(&ctx, StatsParams::new(user.id))
+        .await
+        .unwrap();
+    assert_eq!(stats, Default::default());
+}
+
+#[tokio::test]
+async fn stats_top() {
+    let ctx = sonar::test::create_context_memory().await;
+    let user = sonar::test::create_user(&ctx, "user").await;
+    let (artist, album, track1) =
+        sonar::test::create_artist_album_track(&ctx, "artist", "album", "track1").await;
+    let track2 = sonar::test::create_track(&ctx, album.id, "track2").await;
+    sonar::album_update(
+        &ctx,
+        album.id,
+        sonar::AlbumUpdate {
+            genres: vec![sonar::GenreUpdate {
+                action: sonar::GenreUpdateAction::Set,
+                genre: Genre::new_unchecked("rock"),
+            }],
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap();
+
+    scrobble(&ctx, user.id, track1.id, MONDAY).await;
+    scrobble(&ctx, user.id, track2.id, MONDAY + 60).await;
+    scrobble(&ctx, user.id, track2.id, MONDAY + 120).await;
+
+    let stats = sonar::stats_get(&ctx, StatsParams::new(user.id))
+        .await
+        .unwrap();
+    assert_eq!(stats.listen_count, 3);
+    assert_eq!(stats.listen_time, Duration::from_secs(180));
+    assert_eq!(stats.top_tracks.len(), 2);
+    assert_eq!(stats.top_tracks[0].item, track2.id);
+    assert_eq!(stats.top_tracks[0].listen_count, 2);
+    assert_eq!(stats.top_albums[0].item, album.id);
+    assert_eq!(stats.top_albums[0].listen_count, 3);
+    assert_eq!(stats.top_artists[0].item, artist.id);
+    assert_eq!(stats.top_genres[0].item, Genre::new_unchecked("rock"));
+    assert_eq!(stats.top_genres[0].listen_count, 3);
+}
+
+#[tokio::test]
+async fn stats_range() {
+    let ctx = sonar::test::create_context_memory().await;
+    let user = sonar::test::create_user(&ctx, "user").await;
+    let (_, _, track) =
+        sonar::test::create_artist_album_track(&ctx, "artist", "album", "track").await;
+
+    scrobble(&ctx, user.id, track.id, MONDAY).await;
+    scrobble(&ctx, user.id, track.id, MONDAY + 10 * DAY).await;
+
+    let mut params = StatsParams::new(user.id);
+    params.since = Some(Timestamp::from_seconds(MONDAY + DAY));
+    let stats = sonar::stats_get(&ctx, params).await.unwrap();
+    assert_eq!(stats.listen_count, 1);
+
+    let mut params = StatsParams::new(user.id);
+    params.until = Some(Timestamp::from_seconds(MONDAY + DAY));
+    let stats = sonar::stats_get(&ctx, params).await.unwrap();
+    assert_eq!(stats.listen_count, 1);
+}
+
+#[tokio::test]
+async fn stats_other_user() {
+    let ctx = sonar::test::create_context_memory().await;
+    let user1 = sonar::test::create_user(&ctx, "user1").await;
+    let user2 = sonar::test::create_user(&ctx, "user2").await;
+    let (_, _, track) =
+        sonar::test::create_artist_album_track(&ctx, "artist", "album", "track").await;
+
+    scrobble(&ctx, user1.id, track.id, MONDAY).await;
+
+    let stats = sonar::stats_get(&ctx, StatsParams::new(user2.id))
+        .await
+        .unwrap();
+    assert_eq!(stats.listen_count, 0);
+    assert!(stats.top_tracks.is_empty());
+}
+
+#[tokio::test]
+async fn stats_histograms_and_streaks() {
+    let ctx = sonar::test::create_context_memory().await;
+    let user = sonar::test::create_user(&ctx, "user").await;
+    let (_, _, track) =
+        sonar::test::create_artist_album_track(&ctx, "artist", "album", "track").await;
+
+    // monday, tuesday and wednesday at 10:00, then saturday at 22:00
+    for day in [0, 1, 2] {
+        scrobble(&ctx, user.id, track.id, MONDAY + day * DAY + 10 * 3600).await;
+    }
+    scrobble(&ctx, user.id, track.id, MONDAY + 5 * DAY + 22 * 3600).await;
+
+    let mut params = StatsParams::new(user.id);
+    params.until = Some(Timestamp::from_seconds(MONDAY + 6 * DAY));
+    let stats = sonar::stats_get(&ctx, params.clone()).await.unwrap();
+    assert_eq!(stats.hour_histogram[10], 3);
+    assert_eq!(stats.hour_histogram[22], 1);
+    assert_eq!(stats.weekday_histogram, [1, 1, 1, 0, 0, 1, 0]);
+    assert_eq!(stats.longest_streak, 3);
+    assert_eq!(stats.current_streak, 1);
+
+    // the saturday scrobble falls on sunday two hours ahead of utc
+    params.utc_offset = 2 * 3600;
+    let stats = sonar::stats_get(&ctx, params).await.unwrap();
+    assert_eq!(stats.hour_histogram[0], 1);
+    assert_eq!(stats.weekday_histogram, [1, 1, 1, 0, 0, 0, 1]);
+}
+
+#[tokio::test]
+async fn stats_utc_offset() {
+    let ctx = sonar::test::create_context_memory().await;
+    let user = sonar::test::create_user(&ctx, "user").await;
+    let (_, _, track) =
+        sonar::test::create_artist_album_track(&ctx, "artist", "album", "track").await;
+
+    // 00:30 utc on the first day of the epoch is 23:30 of the previous day an hour behind
+    scrobble(&ctx, user.id, track.id, 1800).await;
+    let mut params = StatsParams::new(user.id);
+    params.utc_offset = -3600;
+    let stats = sonar::stats_get(&ctx, params.clone()).await.unwrap();
+    assert_eq!(stats.hour_histogram[23], 1);
+    // 1969-12-31 was a wednesday
+    assert_eq!(stats.weekday_histogram, [0, 0, 1, 0, 0, 0, 0]);
+
+    params.utc_offset = StatsParams::MAX_UTC_OFFSET + 1;
+    let err = sonar::stats_get(&ctx, params.clone()).await.unwrap_err();
+    assert_eq!(err.kind(), sonar::ErrorKind::Invalid);
+    params.utc_offset = i32::MIN;
+    let err = sonar::stats_get(&ctx, params).await.unwrap_err();
+    assert_eq!(err.kind(), sonar::ErrorKind::Invalid);
+}
+
+#[test]
+fn stats_period() {
+    let now = Timestamp::from_seconds(MONDAY);
+    assert_eq!(
+        StatsPeriod::Week.since(now),
+        Some(Timestamp::from_seconds(MONDAY - 7 * DAY))
+    );
+    assert_eq!(StatsPeriod::AllTime.since(now), None);
+    assert_eq!("month".parse::<StatsPeriod>().unwrap(), StatsPeriod::Month);
+    assert!("decade".parse::<StatsPeriod>().is_err());
+}
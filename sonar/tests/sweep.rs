@@ -0,0 +1,126 @@
+use std::time::Duration;
+
+use sonar::SweepParams;
+
+fn sweep_params(dry_run: bool) -> SweepParams {
+    SweepParams {
+        min_age: Duration::ZERO,
+        dry_run,
+    }
+}
+
+#[tokio::test]
+async fn sweep_unreferenced_image() {
+    let ctx = sonar::test::create_context_memory().await;
+    let image = sonar::test::create_image(&ctx).await;
+
+    let report = sonar::orphan_sweep(&ctx, sweep_params(false))
+        .await
+        .unwrap();
+    assert_eq!(report.images, vec![image]);
+    assert_eq!(report.blobs, 1);
+    assert_eq!(
+        report.reclaimed_bytes,
+        sonar::test::SMALL_IMAGE_JPEG.len() as u64
+    );
+    assert!(sonar::image_download(&ctx, image).await.is_err());
+    assert!(sonar::test::storage_list(&ctx).await.is_empty());
+}
+
+#[tokio::test]
+async fn sweep_dry_run() {
+    let ctx = sonar::test::create_context_memory().await;
+    let image = sonar::test::create_image(&ctx).await;
+
+    let report = sonar::orphan_sweep(&ctx, sweep_params(true)).await.unwrap();
+    assert_eq!(report.images, vec![image]);
+    assert_eq!(report.blobs, 1);
+    sonar::image_download(&ctx, image).await.unwrap();
+}
+
+#[tokio::test]
+async fn sweep_min_age() {
+    let ctx = sonar::test::create_context_memory().await;
+    let image = sonar::test::create_image(&ctx).await;
+
+    let report = sonar::orphan_sweep(&ctx, Default::default()).await.unwrap();
+    assert!(report.images.is_empty());
+    sonar::image_download(&ctx, image).await.unwrap();
+}
+
+#[tokio::test]
+async fn sweep_keeps_referenced_image() {
+    let ctx = sonar::test::create_context_memory().await;
+    let image = sonar::test::create_image(&ctx).await;
+    sonar::artist_create(
+        &ctx,
+        sonar::ArtistCreate {
+            name: "artist".to_string(),
+            cover_art: Some(image),
+            genres: Default::default(),
+            properties: Default::default(),
+            library: None,
+        },
+    )
+    .await
+    .unwrap();
+
+    let report = sonar::orphan_sweep(&ctx, sweep_params(false))
+        .await
+        .unwrap();
+    assert!(report.images.is_empty());
+    assert_eq!(report.blobs, 0);
+    sonar::image_download(&ctx, image).await.unwrap();
+}
+
+#[tokio::test]
+async fn sweep_deleted_track_cover_art() {
+    let ctx = sonar::test::create_context_memory().await;
+    let image = sonar::test::create_image(&ctx).await;
+    let (_, _, track) =
+        sonar::test::create_artist_album_track(&ctx, "artist", "album", "track").await;
+    sonar::track_update(
+        &ctx,
+        track.id,
+        sonar::TrackUpdate {
+            cover_art: sonar::ValueUpdate::set(image),
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap();
+    sonar::track_delete(&ctx, track.id).await.unwrap();
+
+    let report = sonar::orphan_sweep(&ctx, sweep_params(false))
+        .await
+        .unwrap();
+    assert_eq!(report.images, vec![image]);
+    assert!(sonar::test::storage_list(&ctx).await.is_empty());
+}
+
+#[tokio::test]
+async fn sweep_shared_blob() {
+    let ctx = sonar::test::create_context_memory().await;
+    let image1 = sonar::test::create_image(&ctx).await;
+    let image2 = sonar::test::create_image(&ctx).await;
+    sonar::artist_create(
+        &ctx,
+        sonar::ArtistCreate {
+            name: "artist".to_string(),
+            cover_art: Some(image1),
+            genres: Default::default(),
+            properties: Default::default(),
+            library: None,
+        },
+    )
+    .await
+    .unwrap();
+
+    let report = sonar::orphan_sweep(&ctx, sweep_params(false))
+        .await
+        .unwrap();
+    assert_eq!(report.images, vec![image2]);
+    assert_eq!(report.blobs, 0);
+    assert_eq!(report.reclaimed_bytes, 0);
+    sonar::image_download(&ctx, image1).await.unwrap();
+}
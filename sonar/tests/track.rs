@@ -18,6 +18,7 @@ async fn track_create_one() {
     let create = sonar::TrackCreate {
         name: "Track".to_string(),
         album: album.id,
+        artists: Vec::new(),
         cover_art: None,
         audio: None,
         lyrics: None,
@@ -137,6 +138,7 @@ async fn track_with_lyrics() {
         sonar::TrackCreate {
             name: "Track".to_string(),
             album: album.id,
+            artists: Vec::new(),
             cover_art: None,
             audio: None,
             lyrics: Some(lyrics.clone()),
@@ -160,6 +162,7 @@ async fn track_list_properties() {
     let create = sonar::TrackCreate {
         name: "Track".to_string(),
         album: album.id,
+        artists: Vec::new(),
         cover_art: None,
         audio: None,
         lyrics: None,
@@ -171,3 +174,65 @@ async fn track_list_properties() {
     assert_eq!(tracks[0].name, "Track");
     assert_eq!(tracks[0].properties.len(), 2);
 }
+
+#[tokio::test]
+async fn track_artists_default_to_album_artists() {
+    let ctx = sonar::test::create_context_memory().await;
+    let (artist, _, track) =
+        sonar::test::create_artist_album_track(&ctx, "artist", "album", "track").await;
+    assert_eq!(track.artist, artist.id);
+    assert_eq!(track.artists, vec![sonar::ArtistCredit::primary(artist.id)]);
+}
+
+#[tokio::test]
+async fn track_update_artists() {
+    let ctx = sonar::test::create_context_memory().await;
+    let (artist, _, track) =
+        sonar::test::create_artist_album_track(&ctx, "artist", "album", "track").await;
+    let primary = sonar::test::create_artist(&ctx, "primary").await;
+    let remixer = sonar::test::create_artist(&ctx, "remixer").await;
+
+    let credits = vec![
+        sonar::ArtistCredit::primary(primary.id),
+        sonar::ArtistCredit::new(remixer.id, sonar::ArtistRole::Remixer),
+    ];
+    let update = sonar::TrackUpdate {
+        artists: sonar::ValueUpdate::Set(credits.clone()),
+        ..Default::default()
+    };
+    let track = sonar::track_update(&ctx, track.id, update).await.unwrap();
+    assert_eq!(track.artist, primary.id);
+    assert_eq!(track.artists, credits);
+
+    let update = sonar::TrackUpdate {
+        artists: sonar::ValueUpdate::Unset,
+        ..Default::default()
+    };
+    let track = sonar::track_update(&ctx, track.id, update).await.unwrap();
+    assert_eq!(track.artist, artist.id);
+    assert_eq!(track.artists, vec![sonar::ArtistCredit::primary(artist.id)]);
+}
+
+#[tokio::test]
+async fn track_list_top_from_featured_artist() {
+    let ctx = sonar::test::create_context_memory().await;
+    let (artist, _, track) =
+        sonar::test::create_artist_album_track(&ctx, "artist", "album", "track").await;
+    let featured = sonar::test::create_artist(&ctx, "featured").await;
+    let credit = sonar::ArtistCredit::new(featured.id, sonar::ArtistRole::Featured);
+    let update = sonar::TrackUpdate {
+        artists: sonar::ValueUpdate::Set(vec![credit]),
+        ..Default::default()
+    };
+    let track = sonar::track_update(&ctx, track.id, update).await.unwrap();
+    assert_eq!(
+        track.artists,
+        vec![sonar::ArtistCredit::primary(artist.id), credit]
+    );
+
+    let tracks = sonar::track_list_top_from_artist(&ctx, featured.id)
+        .await
+        .unwrap();
+    assert_eq!(tracks.len(), 1);
+    assert_eq!(tracks[0].id, track.id);
+}
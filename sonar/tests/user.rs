@@ -15,6 +15,7 @@ async fn create_user_one() {
         password: "password".to_string(),
         avatar: None,
         admin: false,
+        roles: sonar::UserRole::DEFAULT.to_vec(),
     };
     let user = sonar::user_create(&ctx, create).await.unwrap();
     assert_eq!(user.username.as_str(), "User");
@@ -29,6 +30,7 @@ async fn create_user_one_admin() {
         password: "password".to_string(),
         avatar: None,
         admin: true,
+        roles: sonar::UserRole::DEFAULT.to_vec(),
     };
     let user = sonar::user_create(&ctx, create).await.unwrap();
     assert_eq!(user.username.as_str(), "User");
@@ -84,6 +86,7 @@ async fn update_admin() {
         password: "password".to_string(),
         avatar: None,
         admin: false,
+        roles: sonar::UserRole::DEFAULT.to_vec(),
     };
     let user = sonar::user_create(&ctx, create).await.unwrap();
     assert_eq!(user.username.as_str(), "User");
@@ -112,6 +115,7 @@ async fn password_min_8() {
             password: "1234567".to_string(),
             avatar: None,
             admin: false,
+            roles: sonar::UserRole::DEFAULT.to_vec(),
         },
     )
     .await;
@@ -124,6 +128,7 @@ async fn password_min_8() {
             password: "12345678".to_string(),
             avatar: None,
             admin: false,
+            roles: sonar::UserRole::DEFAULT.to_vec(),
         },
     )
     .await;
@@ -135,10 +140,14 @@ async fn login() {
     let ctx = sonar::test::create_context_memory().await;
     let user = sonar::test::create_user_with_password(&ctx, "User", "admin1234").await;
 
-    let (user_id, token) =
-        sonar::user_login(&ctx, &sonar::Username::new("User").unwrap(), "admin1234")
-            .await
-            .unwrap();
+    let (user_id, token) = sonar::user_login(
+        &ctx,
+        &sonar::Username::new("User").unwrap(),
+        "admin1234",
+        Default::default(),
+    )
+    .await
+    .unwrap();
     assert_eq!(user.id, user_id);
 
     let user_id = sonar::user_validate_token(&ctx, &token).await.unwrap();
@@ -150,6 +159,274 @@ async fn login_failed() {
     let ctx = sonar::test::create_context_memory().await;
     let _user = sonar::test::create_user_with_password(&ctx, "User", "admin1234").await;
 
-    let result = sonar::user_login(&ctx, &sonar::Username::new("User").unwrap(), "wrong").await;
+    let result = sonar::user_login(
+        &ctx,
+        &sonar::Username::new("User").unwrap(),
+        "wrong",
+        Default::default(),
+    )
+    .await;
+    assert!(result.is_err());
+}
+
+#[tokio::test]
+async fn logout() {
+    let ctx = sonar::test::create_context_memory().await;
+    let _user = sonar::test::create_user_with_password(&ctx, "User", "admin1234").await;
+
+    let (_, token) = sonar::user_login(
+        &ctx,
+        &sonar::Username::new("User").unwrap(),
+        "admin1234",
+        Default::default(),
+    )
+    .await
+    .unwrap();
+    sonar::user_logout(&ctx, &token).await.unwrap();
+
+    let result = sonar::user_validate_token(&ctx, &token).await;
+    assert!(result.is_err());
+}
+
+#[tokio::test]
+async fn session_list() {
+    let ctx = sonar::test::create_context_memory().await;
+    let user = sonar::test::create_user_with_password(&ctx, "User", "admin1234").await;
+
+    let (_, _token) = sonar::user_login(
+        &ctx,
+        &sonar::Username::new("User").unwrap(),
+        "admin1234",
+        sonar::UserLoginParams {
+            device: Some("laptop".to_string()),
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap();
+
+    let sessions = sonar::user_session_list(&ctx, user.id).await.unwrap();
+    assert_eq!(sessions.len(), 1);
+    assert_eq!(sessions[0].user, user.id);
+    assert_eq!(sessions[0].device.as_deref(), Some("laptop"));
+    assert!(sessions[0].expires_at.is_none());
+}
+
+#[tokio::test]
+async fn session_revoke() {
+    let ctx = sonar::test::create_context_memory().await;
+    let user = sonar::test::create_user_with_password(&ctx, "User", "admin1234").await;
+
+    let (_, token) = sonar::user_login(
+        &ctx,
+        &sonar::Username::new("User").unwrap(),
+        "admin1234",
+        Default::default(),
+    )
+    .await
+    .unwrap();
+
+    let sessions = sonar::user_session_list(&ctx, user.id).await.unwrap();
+    sonar::user_session_revoke(&ctx, sessions[0].id)
+        .await
+        .unwrap();
+
+    let result = sonar::user_validate_token(&ctx, &token).await;
+    assert!(result.is_err());
+    let sessions = sonar::user_session_list(&ctx, user.id).await.unwrap();
+    assert!(sessions.is_empty());
+}
+
+#[tokio::test]
+async fn session_expired() {
+    let ctx = sonar::test::create_context_memory().await;
+    let _user = sonar::test::create_user_with_password(&ctx, "User", "admin1234").await;
+
+    let (_, token) = sonar::user_login(
+        &ctx,
+        &sonar::Username::new("User").unwrap(),
+        "admin1234",
+        sonar::UserLoginParams {
+            expires_in: Some(std::time::Duration::ZERO),
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap();
+
+    let result = sonar::user_validate_token(&ctx, &token).await;
+    assert!(result.is_err());
+}
+
+#[tokio::test]
+async fn api_key_create_and_validate() {
+    let ctx = sonar::test::create_context_memory().await;
+    let user = sonar::test::create_user(&ctx, "User").await;
+
+    let (api_key, key) = sonar::user_api_key_create(
+        &ctx,
+        sonar::ApiKeyCreate {
+            user: user.id,
+            name: "phone".to_string(),
+            scope: Some(sonar::ApiKeyScope::Streaming),
+        },
+    )
+    .await
+    .unwrap();
+    assert_eq!(api_key.user, user.id);
+    assert_eq!(api_key.name, "phone");
+    assert_eq!(api_key.scope, Some(sonar::ApiKeyScope::Streaming));
+    assert!(api_key.last_used_at.is_none());
+
+    let validated = sonar::user_api_key_validate(&ctx, &key).await.unwrap();
+    assert_eq!(validated.id, api_key.id);
+    assert!(validated.last_used_at.is_some());
+    assert!(validated.allows(sonar::ApiKeyAccess::Stream));
+    assert!(!validated.allows(sonar::ApiKeyAccess::Write));
+
+    let result = sonar::user_api_key_validate(&ctx, "sonar_invalid").await;
+    assert!(result.is_err());
+}
+
+#[tokio::test]
+async fn api_key_duplicate_name() {
+    let ctx = sonar::test::create_context_memory().await;
+    let user = sonar::test::create_user(&ctx, "User").await;
+
+    let create = sonar::ApiKeyCreate {
+        user: user.id,
+        name: "phone".to_string(),
+        scope: None,
+    };
+    sonar::user_api_key_create(&ctx, create.clone())
+        .await
+        .unwrap();
+    let result = sonar::user_api_key_create(&ctx, create).await;
+    assert!(result.is_err());
+}
+
+#[tokio::test]
+async fn api_key_revoke() {
+    let ctx = sonar::test::create_context_memory().await;
+    let user = sonar::test::create_user(&ctx, "User").await;
+
+    let (api_key, key) = sonar::user_api_key_create(
+        &ctx,
+        sonar::ApiKeyCreate {
+            user: user.id,
+            name: "phone".to_string(),
+            scope: None,
+        },
+    )
+    .await
+    .unwrap();
+    sonar::user_api_key_revoke(&ctx, api_key.id).await.unwrap();
+
+    let result = sonar::user_api_key_validate(&ctx, &key).await;
+    assert!(result.is_err());
+    let keys = sonar::user_api_key_list(&ctx, user.id).await.unwrap();
+    assert!(keys.is_empty());
+}
+
+#[tokio::test]
+async fn subsonic_password_disabled_by_default() {
+    let ctx = sonar::test::create_context_memory().await;
+    let user = sonar::test::create_user(&ctx, "User").await;
+
+    let enabled = sonar::user_subsonic_password_enabled(&ctx, user.id)
+        .await
+        .unwrap();
+    assert!(!enabled);
+    let result = sonar::user_subsonic_authenticate(&ctx, user.id, "token", "salt").await;
     assert!(result.is_err());
 }
+
+#[tokio::test]
+async fn subsonic_password_authenticate() {
+    let ctx = sonar::test::create_context_memory().await;
+    let user = sonar::test::create_user(&ctx, "User").await;
+
+    sonar::user_subsonic_password_set(&ctx, user.id, Some("sesame1234"))
+        .await
+        .unwrap();
+    assert!(sonar::user_subsonic_password_enabled(&ctx, user.id)
+        .await
+        .unwrap());
+
+    // md5("sesame1234" + "c19b2d")
+    let token = "d1c5306d33d6cdbdd32565b8f3f283a1";
+    sonar::user_subsonic_authenticate(&ctx, user.id, token, "c19b2d")
+        .await
+        .unwrap();
+    let result = sonar::user_subsonic_authenticate(&ctx, user.id, token, "other").await;
+    assert!(result.is_err());
+
+    sonar::user_subsonic_password_set(&ctx, user.id, None)
+        .await
+        .unwrap();
+    let result = sonar::user_subsonic_authenticate(&ctx, user.id, token, "c19b2d").await;
+    assert!(result.is_err());
+}
+
+#[tokio::test]
+async fn user_roles() {
+    let ctx = sonar::test::create_context_memory().await;
+    let create = sonar::UserCreate {
+        username: "User".parse().unwrap(),
+        password: "password".to_string(),
+        avatar: None,
+        admin: false,
+        roles: sonar::UserRole::DEFAULT.to_vec(),
+    };
+    let user = sonar::user_create(&ctx, create).await.unwrap();
+    assert!(user.has_role(sonar::UserRole::Stream));
+    assert!(user.has_role(sonar::UserRole::Download));
+    assert!(!user.has_role(sonar::UserRole::Upload));
+
+    let user = sonar::user_update(
+        &ctx,
+        user.id,
+        sonar::UserUpdate {
+            roles: sonar::ValueUpdate::Set(vec![
+                sonar::UserRole::Upload,
+                sonar::UserRole::EditMetadata,
+            ]),
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap();
+    assert!(user.has_role(sonar::UserRole::Upload));
+    assert!(user.has_role(sonar::UserRole::EditMetadata));
+    assert!(!user.has_role(sonar::UserRole::Stream));
+
+    let user = sonar::user_update(
+        &ctx,
+        user.id,
+        sonar::UserUpdate {
+            roles: sonar::ValueUpdate::Unset,
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap();
+    assert!(user.roles.is_empty());
+    let users = sonar::user_list(&ctx, Default::default()).await.unwrap();
+    assert!(users[0].roles.is_empty());
+}
+
+#[tokio::test]
+async fn admin_has_every_role() {
+    let ctx = sonar::test::create_context_memory().await;
+    let create = sonar::UserCreate {
+        username: "User".parse().unwrap(),
+        password: "password".to_string(),
+        avatar: None,
+        admin: true,
+        roles: Vec::new(),
+    };
+    let user = sonar::user_create(&ctx, create).await.unwrap();
+    for role in sonar::UserRole::ALL {
+        assert!(user.has_role(*role));
+    }
+}
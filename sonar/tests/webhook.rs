@@ -0,0 +1,300 @@
+use std::{collections::HashMap, sync::Arc, time::Duration};
+
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use sonar::{ErrorKind, EventKind, WebhookCreate, WebhookDeliveryStatus, WebhookUpdate};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader},
+    net::TcpListener,
+    sync::{mpsc, Mutex},
+};
+
+#[derive(Debug)]
+struct Request {
+    headers: HashMap<String, String>,
+    body: String,
+}
+
+/// Minimal http server that records every request it receives.
+/// The given status codes are returned in order, after which every request gets a 200.
+struct Receiver {
+    url: String,
+    requests: mpsc::UnboundedReceiver<Request>,
+}
+
+impl Receiver {
+    async fn new(statuses: Vec<u16>) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        let statuses = Arc::new(Mutex::new(statuses.into_iter()));
+        let (sender, requests) = mpsc::unbounded_channel();
+        tokio::spawn(async move {
+            loop {
+                let (stream, _) = listener.accept().await.unwrap();
+                let statuses = statuses.clone();
+                let sender = sender.clone();
+                tokio::spawn(async move {
+                    let mut stream = BufReader::new(stream);
+                    let mut headers = HashMap::new();
+                    let mut line = String::new();
+                    stream.read_line(&mut line).await.unwrap();
+                    loop {
+                        line.clear();
+                        stream.read_line(&mut line).await.unwrap();
+                        let Some((key, value)) = line.trim_end().split_once(':') else {
+                            break;
+                        };
+                        headers.insert(key.to_lowercase(), value.trim().to_string());
+                    }
+                    let length = headers["content-length"].parse::<usize>().unwrap();
+                    let mut body = vec![0u8; length];
+                    stream.read_exact(&mut body).await.unwrap();
+
+                    let status = statuses.lock().await.next().unwrap_or(200);
+                    let response = format!(
+                        "HTTP/1.1 {status} Status\r\ncontent-length: 0\r\nconnection: close\r\n\r\n"
+                    );
+                    stream.write_all(response.as_bytes()).await.unwrap();
+                    stream.flush().await.unwrap();
+                    let _ = sender.send(Request {
+                        headers,
+                        body: String::from_utf8(body).unwrap(),
+                    });
+                });
+            }
+        });
+        Self { url, requests }
+    }
+
+    async fn recv(&mut self) -> Request {
+        tokio::time::timeout(Duration::from_secs(10), self.requests.recv())
+            .await
+            .unwrap()
+            .unwrap()
+    }
+}
+
+async fn create_context() -> sonar::Context {
+    let mut config = sonar::test::create_config_memory();
+    config.set_webhook_retry_delay(Duration::from_millis(100));
+    sonar::test::create_context(config).await
+}
+
+async fn wait_delivery(
+    ctx: &sonar::Context,
+    webhook: sonar::WebhookId,
+    status: WebhookDeliveryStatus,
+) -> sonar::WebhookDelivery {
+    for _ in 0..100 {
+        let deliveries = sonar::webhook_delivery_list(ctx, webhook, Default::default())
+            .await
+            .unwrap();
+        if let Some(delivery) = deliveries.into_iter().find(|d| d.status == status) {
+            return delivery;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    panic!("webhook delivery did not reach status {status}");
+}
+
+#[tokio::test]
+async fn webhook_signed_delivery() {
+    let ctx = create_context().await;
+    let mut receiver = Receiver::new(vec![]).await;
+    let webhook = sonar::webhook_create(
+        &ctx,
+        WebhookCreate {
+            url: receiver.url.clone(),
+            kinds: vec![EventKind::ArtistCreated],
+            secret: Some("secret".to_string()),
+        },
+    )
+    .await
+    .unwrap();
+
+    let artist = sonar::test::create_artist(&ctx, "artist").await;
+    let request = receiver.recv().await;
+    assert_eq!(request.headers["x-sonar-event"], "artist-created");
+    assert_eq!(request.headers["content-type"], "application/json");
+
+    let mut mac = Hmac::<Sha256>::new_from_slice(b"secret").unwrap();
+    mac.update(request.body.as_bytes());
+    let signature = format!("sha256={}", hex::encode(mac.finalize().into_bytes()));
+    assert_eq!(request.headers["x-sonar-signature"], signature);
+
+    let payload = serde_json::from_str::<serde_json::Value>(&request.body).unwrap();
+    assert_eq!(payload["kind"], "artist-created");
+    assert_eq!(payload["id"], artist.id.to_string());
+
+    let delivery = wait_delivery(&ctx, webhook.id, WebhookDeliveryStatus::Delivered).await;
+    assert_eq!(delivery.event_kind, EventKind::ArtistCreated);
+    assert_eq!(delivery.attempts, 1);
+    assert_eq!(delivery.response_status, Some(200));
+    assert_eq!(request.headers["x-sonar-delivery"], delivery.id.to_string());
+}
+
+#[tokio::test]
+async fn webhook_retry() {
+    let ctx = create_context().await;
+    let mut receiver = Receiver::new(vec![500, 503]).await;
+    let webhook = sonar::webhook_create(
+        &ctx,
+        WebhookCreate {
+            url: receiver.url.clone(),
+            kinds: vec![],
+            secret: None,
+        },
+    )
+    .await
+    .unwrap();
+
+    sonar::test::create_artist(&ctx, "artist").await;
+    for _ in 0..3 {
+        let request = receiver.recv().await;
+        assert_eq!(request.headers["x-sonar-event"], "artist-created");
+    }
+
+    let delivery = wait_delivery(&ctx, webhook.id, WebhookDeliveryStatus::Delivered).await;
+    assert_eq!(delivery.attempts, 3);
+    assert_eq!(delivery.response_status, Some(200));
+    assert_eq!(delivery.error, None);
+}
+
+#[tokio::test]
+async fn webhook_filter() {
+    let ctx = create_context().await;
+    let user = sonar::test::create_user(&ctx, "user").await;
+    let mut receiver = Receiver::new(vec![]).await;
+    let webhook = sonar::webhook_create(
+        &ctx,
+        WebhookCreate {
+            url: receiver.url.clone(),
+            kinds: vec![EventKind::PlaylistCreated],
+            secret: None,
+        },
+    )
+    .await
+    .unwrap();
+    assert!(!webhook.secret.is_empty());
+
+    sonar::test::create_artist(&ctx, "artist").await;
+    sonar::test::create_playlist(&ctx, user.id, "playlist").await;
+    let request = receiver.recv().await;
+    assert_eq!(request.headers["x-sonar-event"], "playlist-created");
+
+    // disabled webhooks get no deliveries
+    sonar::webhook_update(
+        &ctx,
+        webhook.id,
+        WebhookUpdate {
+            enabled: sonar::ValueUpdate::Set(false),
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap();
+    sonar::test::create_playlist(&ctx, user.id, "playlist2").await;
+    tokio::time::sleep(Duration::from_millis(500)).await;
+    assert!(receiver.requests.try_recv().is_err());
+}
+
+#[tokio::test]
+async fn webhook_disabled_pending_delivery() {
+    let ctx = create_context().await;
+    let mut receiver = Receiver::new(vec![500]).await;
+    let webhook = sonar::webhook_create(
+        &ctx,
+        WebhookCreate {
+            url: receiver.url.clone(),
+            kinds: vec![],
+            secret: None,
+        },
+    )
+    .await
+    .unwrap();
+
+    sonar::test::create_artist(&ctx, "artist").await;
+    receiver.recv().await;
+    sonar::webhook_update(
+        &ctx,
+        webhook.id,
+        WebhookUpdate {
+            enabled: sonar::ValueUpdate::Set(false),
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap();
+
+    // the failed delivery is not retried while the webhook is disabled
+    tokio::time::sleep(Duration::from_millis(2500)).await;
+    assert!(receiver.requests.try_recv().is_err());
+    let delivery = wait_delivery(&ctx, webhook.id, WebhookDeliveryStatus::Pending).await;
+    assert_eq!(delivery.attempts, 1);
+}
+
+#[tokio::test]
+async fn webhook_slow_receiver() {
+    let ctx = create_context().await;
+    // accepts connections but never responds
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    sonar::webhook_create(
+        &ctx,
+        WebhookCreate {
+            url: format!("http://{}/hook", listener.local_addr().unwrap()),
+            kinds: vec![],
+            secret: None,
+        },
+    )
+    .await
+    .unwrap();
+    let mut receiver = Receiver::new(vec![]).await;
+    sonar::webhook_create(
+        &ctx,
+        WebhookCreate {
+            url: receiver.url.clone(),
+            kinds: vec![],
+            secret: None,
+        },
+    )
+    .await
+    .unwrap();
+
+    sonar::test::create_artist(&ctx, "artist").await;
+    let request = tokio::time::timeout(Duration::from_secs(5), receiver.requests.recv())
+        .await
+        .expect("delivery was held up by another webhook")
+        .unwrap();
+    assert_eq!(request.headers["x-sonar-event"], "artist-created");
+    drop(listener);
+}
+
+#[tokio::test]
+async fn webhook_invalid_url() {
+    let ctx = create_context().await;
+    let err = sonar::webhook_create(
+        &ctx,
+        WebhookCreate {
+            url: "ftp://localhost/hook".to_string(),
+            kinds: vec![],
+            secret: None,
+        },
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::Invalid);
+
+    let webhook = sonar::webhook_create(
+        &ctx,
+        WebhookCreate {
+            url: "http://localhost/hook".to_string(),
+            kinds: vec![],
+            secret: None,
+        },
+    )
+    .await
+    .unwrap();
+    sonar::webhook_delete(&ctx, webhook.id).await.unwrap();
+    let err = sonar::webhook_get(&ctx, webhook.id).await.unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::NotFound);
+}